@@ -6,5 +6,11 @@ mod xpath_benchmark;
 use crate::html_benchmark::*;
 use crate::xpath_benchmark::*;
 
-criterion_group!(benches, benchmark_html_parse, benchmark_xpath_parse);
+criterion_group!(
+    benches,
+    benchmark_html_parse,
+    benchmark_xpath_parse,
+    benchmark_xpath_apply,
+    benchmark_xpath_set_apply
+);
 criterion_main!(benches);
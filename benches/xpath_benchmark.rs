@@ -1,5 +1,17 @@
 use criterion::Criterion;
-use skyscraper::xpath;
+use skyscraper::{
+    html,
+    xpath::{self, xpath_set::XpathSet, Xpath, XpathItemTree},
+};
+
+static HTML: &str = include_str!("../tests/samples/James-LG_Skyscraper.html");
+
+/// Expressions typical of scraping, from index lookups to walks of the whole tree.
+static EXPRESSIONS: [&str; 3] = [
+    "//div[@class='BorderGrid-cell']/div[@class=' text-small']/a",
+    "/html/body//a[contains(@href, 'Skyscraper')]/@href",
+    "//span/text()",
+];
 
 pub fn benchmark_xpath_parse(c: &mut Criterion) {
     c.bench_function("xpath parse", |b| {
@@ -8,3 +20,52 @@ pub fn benchmark_xpath_parse(c: &mut Criterion) {
         })
     });
 }
+
+pub fn benchmark_xpath_apply(c: &mut Criterion) {
+    let document = html::parse(HTML).unwrap();
+    let tree = XpathItemTree::from(&document);
+
+    for text in EXPRESSIONS {
+        let xpath = xpath::parse(text).unwrap();
+        let compiled = xpath.compile();
+
+        // Run with `--features bench` to compare with evaluating the syntax tree.
+        #[cfg(feature = "bench")]
+        c.bench_function(&format!("xpath apply uncompiled {}", text), |b| {
+            b.iter(|| xpath.apply_uncompiled(&tree).unwrap())
+        });
+        c.bench_function(&format!("xpath apply {}", text), |b| {
+            b.iter(|| xpath.apply(&tree).unwrap())
+        });
+        c.bench_function(&format!("xpath apply compiled {}", text), |b| {
+            b.iter(|| compiled.apply(&tree).unwrap())
+        });
+    }
+}
+
+pub fn benchmark_xpath_set_apply(c: &mut Criterion) {
+    let document = html::parse(HTML).unwrap();
+    let tree = XpathItemTree::from(&document);
+
+    // Fields of the same repository card, which all start by finding the card.
+    let xpaths: Vec<Xpath> = [
+        "//div[@class='BorderGrid-cell']/h2",
+        "//div[@class='BorderGrid-cell']/div[@class=' text-small']/a",
+        "//div[@class='BorderGrid-cell']//a/@href",
+        "//div[@class='BorderGrid-cell']//span/text()",
+        "//div[@class='BorderGrid-cell']/p/text()",
+    ]
+    .iter()
+    .map(|text| xpath::parse(text).unwrap())
+    .collect();
+    let set = XpathSet::from_iter(xpaths.iter().cloned());
+
+    c.bench_function("xpath apply each of set", |b| {
+        b.iter(|| {
+            for xpath in xpaths.iter() {
+                xpath.apply(&tree).unwrap();
+            }
+        })
+    });
+    c.bench_function("xpath set apply", |b| b.iter(|| set.apply(&tree).unwrap()));
+}
@@ -0,0 +1,139 @@
+//! Compile-time checked XPath expressions for [Skyscraper](https://docs.rs/skyscraper).
+//!
+//! The [xpath!] macro parses an expression when the crate using it is compiled,
+//! so a typo in an expression is a compile error instead of a panic on first use.
+//!
+//! # Example
+//!
+//! ```rust
+//! use skyscraper::{html, xpath::XpathItemTree};
+//! use skyscraper_macros::xpath;
+//!
+//! let document = html::parse("<html><div class='x'>Hello</div></html>").unwrap();
+//! let tree = XpathItemTree::from(&document);
+//!
+//! let items = xpath!("//div[@class='x']").apply(&tree).unwrap();
+//!
+//! assert_eq!(items.len(), 1);
+//! ```
+//!
+//! An invalid expression does not compile:
+//!
+//! ```compile_fail
+//! let xpath = skyscraper_macros::xpath!("//div[@class='x'");
+//! ```
+
+#![warn(missing_docs)]
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::LitStr;
+
+/// Parse an XPath expression at compile time.
+///
+/// Expands to a `&'static Xpath` that is built the first time the expression is evaluated,
+/// and reused afterwards, like a `Lazy<Xpath>` in a static.
+///
+/// The expression is checked against the default
+/// [StaticContext](skyscraper::xpath::StaticContext), so it is rejected for the same reasons as
+/// [xpath::parse](skyscraper::xpath::parse). Expressions that call extension functions must be
+/// parsed at runtime with [xpath::parse_with_context](skyscraper::xpath::parse_with_context).
+///
+/// If the expression is invalid, compilation fails with the parse error,
+/// pointing at where in the expression it was found.
+#[proc_macro]
+pub fn xpath(input: TokenStream) -> TokenStream {
+    expand(input.into()).into()
+}
+
+fn expand(input: TokenStream2) -> TokenStream2 {
+    let literal = match syn::parse2::<LitStr>(input) {
+        Ok(literal) => literal,
+        Err(e) => {
+            return syn::Error::new(e.span(), "xpath! expects a string literal").to_compile_error()
+        }
+    };
+
+    if let Err(e) = skyscraper::xpath::parse(&literal.value()) {
+        let message = match e.position() {
+            Some(position) => format!(
+                "invalid XPath expression at line {}, column {}:\n{}",
+                position.line,
+                position.column,
+                e.diagnostic()
+            ),
+            None => format!("invalid XPath expression:\n{}", e.diagnostic()),
+        };
+
+        return syn::Error::new(literal.span(), message).to_compile_error();
+    }
+
+    quote! {
+        {
+            static XPATH: ::std::sync::OnceLock<::skyscraper::xpath::Xpath> =
+                ::std::sync::OnceLock::new();
+
+            XPATH.get_or_init(|| {
+                ::skyscraper::xpath::parse(#literal)
+                    .expect("expression was checked when it was compiled")
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_should_build_lazily() {
+        // arrange
+        let input = quote!("//div[@class='x']");
+
+        // act
+        let output = expand(input).to_string();
+
+        // assert
+        assert!(output.contains("OnceLock"), "{}", output);
+        assert!(!output.contains("compile_error"), "{}", output);
+    }
+
+    #[test]
+    fn expand_should_report_location_of_parse_error() {
+        // arrange
+        let input = quote!("//div[@class='x'");
+
+        // act
+        let output = expand(input).to_string();
+
+        // assert
+        assert!(output.contains("compile_error"), "{}", output);
+        assert!(output.contains("line 1, column 17"), "{}", output);
+    }
+
+    #[test]
+    fn expand_should_reject_unknown_functions() {
+        // arrange
+        let input = quote!("//div[foo()]");
+
+        // act
+        let output = expand(input).to_string();
+
+        // assert
+        assert!(output.contains("compile_error"), "{}", output);
+        assert!(output.contains("foo"), "{}", output);
+    }
+
+    #[test]
+    fn expand_should_reject_non_literals() {
+        // arrange
+        let input = quote!(some_variable);
+
+        // act
+        let output = expand(input).to_string();
+
+        // assert
+        assert!(output.contains("expects a string literal"), "{}", output);
+    }
+}
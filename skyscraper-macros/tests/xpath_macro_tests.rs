@@ -0,0 +1,60 @@
+use skyscraper::{html, xpath::XpathItemTree};
+use skyscraper_macros::xpath;
+
+static HTML: &str = r###"
+    <html>
+        <body>
+            <div class="x">Hello</div>
+            <div class="y">World</div>
+        </body>
+    </html>"###;
+
+fn find_x(tree: &XpathItemTree) -> usize {
+    xpath!("//div[@class='x']").apply(tree).unwrap().len()
+}
+
+#[test]
+fn xpath_macro_should_apply_expression() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+
+    // act
+    let items = xpath!("//div[@class='y']/text()")
+        .apply(&xpath_item_tree)
+        .unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].string_value(&xpath_item_tree), "World");
+}
+
+#[test]
+fn xpath_macro_should_reuse_expression() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+
+    // act
+    let first = find_x(&xpath_item_tree);
+    let second = find_x(&xpath_item_tree);
+
+    // assert
+    assert_eq!(first, 1);
+    assert_eq!(second, 1);
+}
+
+#[test]
+fn xpath_macro_should_be_static() {
+    // arrange
+    fn xpath() -> &'static skyscraper::xpath::Xpath {
+        xpath!("//div")
+    }
+
+    // act
+    let first = xpath() as *const _;
+    let second = xpath() as *const _;
+
+    // assert
+    assert_eq!(first, second);
+}
@@ -0,0 +1,267 @@
+//! Select nodes using CSS selectors.
+//!
+//! CSS selectors are parsed and compiled to an [Xpath] expression,
+//! so they are applied to an [XpathItemTree](crate::xpath::XpathItemTree)
+//! by the same evaluator as XPath expressions.
+//! The [Display](std::fmt::Display) implementation of the returned [Xpath]
+//! shows the generated expression.
+//!
+//! # Supported selectors
+//!
+//! - Type selectors `div` and the universal selector `*`.
+//!   Element names are matched as written in the document, like XPath name tests.
+//! - Class selectors `.product` and id selectors `#main`.
+//! - Attribute selectors `[href]`, `[lang=en]`, `[class~=a]`, `[lang|=en]`,
+//!   `[href^='https']`, `[href$='.pdf']` and `[title*=sale]`.
+//! - The combinators ` `, `>`, `+` and `~`, and selector lists separated by `,`.
+//! - The pseudo-classes `:root`, `:empty`, `:first-child`, `:last-child`, `:only-child`,
+//!   `:nth-child()`, `:nth-last-child()`, `:first-of-type`, `:last-of-type`, `:only-of-type`,
+//!   `:nth-of-type()` and `:nth-last-of-type()`.
+//!   The `*-of-type` pseudo-classes need a type selector, e.g. `li:first-of-type`.
+//! - `:not()`, `:is()` and `:where()` with a list of compound selectors,
+//!   and `:has()` with a list of relative selectors, e.g. `:has(> img, + p)`.
+//! - `:contains('text')`, matching elements whose text contains the given string.
+//!
+//! Pseudo-elements, namespaces, case-insensitive attribute matching and pseudo-classes
+//! that depend on user interaction such as `:hover` are reported as
+//! [SelectorParseError::Unsupported].
+//!
+//! # Example
+//!
+//! ```rust
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! use skyscraper::{css, html, xpath::XpathItemTree};
+//!
+//! let document = html::parse(r#"
+//!     <div class="product">
+//!         <a href="https://example.com">external</a>
+//!         <a href="/local">local</a>
+//!     </div>"#)?;
+//! let tree = XpathItemTree::from(&document);
+//!
+//! let xpath = css::parse(".product > a[href^='http']")?;
+//! assert_eq!(
+//!     xpath.to_string(),
+//!     "//*[contains(' ' || normalize-space(@class) || ' ', ' product ')]/a[starts-with(@href, 'http')]"
+//! );
+//!
+//! let items = xpath.apply(&tree)?;
+//! assert_eq!(items.len(), 1);
+//! assert_eq!(items[0].extract_as_node().text(&tree).unwrap(), "external");
+//! # Ok(())
+//! # }
+//! ```
+
+use thiserror::Error;
+
+use crate::xpath::{self, ExpressionParseError, Xpath};
+
+mod selector;
+mod translate;
+
+/// Error that occurs when parsing a CSS selector.
+#[derive(PartialEq, Debug, Error)]
+pub enum SelectorParseError {
+    /// The selector is not valid CSS.
+    #[error("Invalid selector at offset {offset}: {message}")]
+    Syntax {
+        /// The byte offset in the selector where the error was found.
+        offset: usize,
+        /// Description of what was expected.
+        message: String,
+    },
+
+    /// The selector is valid CSS, but cannot be expressed as a supported XPath expression.
+    #[error("Unsupported selector: {0}")]
+    Unsupported(String),
+
+    /// The XPath expression generated for the selector failed to parse.
+    ///
+    /// This is a bug in the translation of the selector.
+    #[error("Failed to parse generated xpath: {0}")]
+    Xpath(#[from] ExpressionParseError),
+}
+
+/// Parse a CSS selector into an [Xpath] expression selecting the matched elements.
+///
+/// # Example
+///
+/// ```rust
+/// use skyscraper::css;
+///
+/// let xpath = css::parse("ul > li:nth-child(odd)").expect("selector is invalid");
+/// assert_eq!(xpath.to_string(), "//ul/li[count(preceding-sibling::*) mod 2=0]");
+///
+/// assert!(css::parse("a:hover").is_err());
+/// ```
+pub fn parse(selector: &str) -> Result<Xpath, SelectorParseError> {
+    let list = selector::parse_selector_list(selector)?;
+    let expression = translate::selector_list(&list)?;
+
+    Ok(xpath::parse(&expression)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{html, xpath::XpathItemTree};
+
+    use super::*;
+
+    fn select_ids(text: &str, selector: &str) -> Vec<String> {
+        let document = html::parse(text).unwrap();
+        let tree = XpathItemTree::from(&document);
+        let xpath = parse(selector).unwrap();
+
+        xpath
+            .apply(&tree)
+            .unwrap()
+            .into_iter()
+            .map(|item| {
+                item.extract_as_node()
+                    .extract_as_element_node()
+                    .get_attribute(&tree, "id")
+                    .unwrap()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_should_translate_selectors_to_xpath() {
+        // arrange
+        let cases = [
+            ("div", "//div"),
+            ("*", "//*"),
+            ("#main", "//*[@id='main']"),
+            ("a[href]", "//a[@href]"),
+            ("a[title=\"it's\"]", "//a[@title='it''s']"),
+            ("[lang|=en]", "//*[@lang='en' or starts-with(@lang, 'en-')]"),
+            ("a[href$=''], b", "//a[false()], //b"),
+            ("div p > a", "//div//p/a"),
+            ("h2 + p", "//h2/following-sibling::*[1][self::p]"),
+            ("h2 ~ p", "//h2/following-sibling::p"),
+            ("li:nth-child(3)", "//li[count(preceding-sibling::*)=2]"),
+            ("li:nth-child(-n+3)", "//li[count(preceding-sibling::*)<=2]"),
+            (
+                "li:nth-last-child(3n+5)",
+                "//li[count(following-sibling::*)>=4 and (count(following-sibling::*) - 4) mod 3=0]",
+            ),
+            ("li:nth-child(n)", "//li"),
+            (
+                "li:nth-child(2147483647n+1)",
+                "//li[count(preceding-sibling::*) mod 2147483647=0]",
+            ),
+            ("li:first-of-type", "//li[not(preceding-sibling::li)]"),
+            ("p:not(.a, #b)", "//p[not(self::*[contains(' ' || normalize-space(@class) || ' ', ' a ')] or self::*[@id='b'])]"),
+            ("div:has(> img, + p)", "//div[img or following-sibling::*[1][self::p]]"),
+            ("div:has(span a)", "//div[.//span//a]"),
+            ("p:contains('sale')", "//p[contains(., 'sale')]"),
+        ];
+
+        for (selector, expected) in cases {
+            // act
+            let xpath = parse(selector).unwrap();
+
+            // assert
+            assert_eq!(xpath.to_string(), expected, "{}", selector);
+        }
+    }
+
+    #[test]
+    fn parse_should_select_elements_like_css() {
+        // arrange
+        let text = r#"
+            <ul id="list">
+                <li id="1" class="item first"></li>
+                <li id="2" class="item"><a id="link" href="http://example.com"></a></li>
+                <li id="3" class="item-x"></li>
+                <li id="4" lang="en-US">sale</li>
+                <p id="5"></p>
+                <li id="6"></li>
+            </ul>"#;
+
+        let cases = [
+            (".item", vec!["1", "2"]),
+            ("li:nth-child(odd)", vec!["1", "3"]),
+            ("li:nth-child(-2n+4)", vec!["2", "4"]),
+            ("li:nth-last-child(2)", vec![]),
+            ("li:last-of-type", vec!["6"]),
+            ("li:nth-of-type(5)", vec!["6"]),
+            ("li:first-child, li:last-child", vec!["1", "6"]),
+            ("li + li", vec!["2", "3", "4"]),
+            ("p ~ li", vec!["6"]),
+            ("ul > li:not(.item, [lang])", vec!["3", "6"]),
+            ("li:has(> a[href^='http'])", vec!["2"]),
+            ("li:has(+ p)", vec!["4"]),
+            ("[lang|=en]:contains(sale)", vec!["4"]),
+            ("li:empty", vec!["1", "3", "6"]),
+            (":root", vec!["list"]),
+        ];
+
+        for (selector, expected) in cases {
+            // act
+            let ids = select_ids(text, selector);
+
+            // assert
+            assert_eq!(ids, expected, "{}", selector);
+        }
+    }
+
+    #[test]
+    fn parse_should_return_error_for_invalid_or_unsupported_selectors() {
+        // arrange
+        let cases = [
+            (
+                "div >",
+                SelectorParseError::Syntax {
+                    offset: 5,
+                    message: String::from("expected a selector, found end of input"),
+                },
+            ),
+            (
+                "a:bogus",
+                SelectorParseError::Syntax {
+                    offset: 2,
+                    message: String::from("unknown pseudo-class `:bogus`"),
+                },
+            ),
+            (
+                "a:hover",
+                SelectorParseError::Unsupported(String::from("pseudo-class `:hover`")),
+            ),
+            (
+                "p::before",
+                SelectorParseError::Unsupported(String::from("pseudo-elements")),
+            ),
+            (
+                ".a:first-of-type",
+                SelectorParseError::Unsupported(String::from(
+                    ":first-of-type without a type selector",
+                )),
+            ),
+            (
+                "p:not(div > p)",
+                SelectorParseError::Unsupported(String::from(
+                    "complex selectors in :not() and :is()",
+                )),
+            ),
+            (
+                "li:nth-child(99999999999)",
+                SelectorParseError::Unsupported(String::from("an+b with a or b beyond 2147483647")),
+            ),
+            (
+                "li:nth-last-child(-9223372036854775808n)",
+                SelectorParseError::Unsupported(String::from("an+b with a or b beyond 2147483647")),
+            ),
+        ];
+
+        for (selector, expected) in cases {
+            // act
+            let err = parse(selector).unwrap_err();
+
+            // assert
+            assert_eq!(err, expected, "{}", selector);
+        }
+    }
+}
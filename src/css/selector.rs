@@ -0,0 +1,702 @@
+//! The CSS selector grammar and the parser building it.
+//!
+//! <https://www.w3.org/TR/selectors-4/#grammar>
+
+use super::SelectorParseError;
+
+/// A comma separated list of selectors, matching elements matched by any of them.
+#[derive(PartialEq, Debug, Clone)]
+pub(crate) struct SelectorList(pub Vec<ComplexSelector>);
+
+/// Compound selectors joined by combinators, e.g. `.product > a`.
+#[derive(PartialEq, Debug, Clone)]
+pub(crate) struct ComplexSelector {
+    pub first: CompoundSelector,
+    pub rest: Vec<(Combinator, CompoundSelector)>,
+}
+
+/// A complex selector relative to the element being tested, as used by `:has()`, e.g. `> img`.
+#[derive(PartialEq, Debug, Clone)]
+pub(crate) struct RelativeSelector {
+    pub combinator: Combinator,
+    pub selector: ComplexSelector,
+}
+
+/// The relationship between the elements matched by two compound selectors.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub(crate) enum Combinator {
+    /// Whitespace, e.g. `div p`.
+    Descendant,
+    /// `>`, e.g. `div > p`.
+    Child,
+    /// `+`, e.g. `h2 + p`.
+    NextSibling,
+    /// `~`, e.g. `h2 ~ p`.
+    SubsequentSibling,
+}
+
+/// Conditions that must all hold for a single element, e.g. `a.external[href]`.
+#[derive(PartialEq, Debug, Clone)]
+pub(crate) struct CompoundSelector {
+    /// The element name, or `None` for any element.
+    pub element: Option<String>,
+    pub conditions: Vec<Condition>,
+}
+
+/// A condition of a compound selector other than its type selector.
+#[derive(PartialEq, Debug, Clone)]
+pub(crate) enum Condition {
+    Id(String),
+    Class(String),
+    Attribute(AttributeSelector),
+    PseudoClass(PseudoClass),
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub(crate) struct AttributeSelector {
+    pub name: String,
+    pub matcher: Option<(AttributeOperator, String)>,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub(crate) enum AttributeOperator {
+    /// `=`
+    Equals,
+    /// `~=`
+    Includes,
+    /// `|=`
+    DashMatch,
+    /// `^=`
+    Prefix,
+    /// `$=`
+    Suffix,
+    /// `*=`
+    Substring,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub(crate) enum PseudoClass {
+    Root,
+    Empty,
+    FirstChild,
+    LastChild,
+    OnlyChild,
+    FirstOfType,
+    LastOfType,
+    OnlyOfType,
+    NthChild(Nth),
+    NthLastChild(Nth),
+    NthOfType(Nth),
+    NthLastOfType(Nth),
+    Not(SelectorList),
+    Is(SelectorList),
+    Has(Vec<RelativeSelector>),
+    Contains(String),
+}
+
+/// The `an+b` argument of the `:nth-*` pseudo-classes,
+/// matching elements at the positions `a*n + b` for every `n >= 0`.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub(crate) struct Nth {
+    pub a: i64,
+    pub b: i64,
+}
+
+/// Parse a selector list, requiring the whole input to be consumed.
+pub(crate) fn parse_selector_list(input: &str) -> Result<SelectorList, SelectorParseError> {
+    let mut parser = Parser { input, position: 0 };
+    parser.skip_whitespace();
+    let list = parser.selector_list()?;
+    parser.skip_whitespace();
+    if let Some(c) = parser.peek() {
+        return Err(parser.error(format!("unexpected `{}`", c)));
+    }
+
+    Ok(list)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn remaining(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.remaining().chars().next()
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.remaining().chars().nth(n)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.position += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), SelectorParseError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.expected(&format!("`{}`", c)))
+        }
+    }
+
+    /// Skip whitespace, returning whether there was any.
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.position;
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r' | '\x0C')) {
+            self.position += 1;
+        }
+        self.position != start
+    }
+
+    fn error(&self, message: String) -> SelectorParseError {
+        SelectorParseError::Syntax {
+            offset: self.position,
+            message,
+        }
+    }
+
+    fn expected(&self, what: &str) -> SelectorParseError {
+        match self.peek() {
+            Some(c) => self.error(format!("expected {}, found `{}`", what, c)),
+            None => self.error(format!("expected {}, found end of input", what)),
+        }
+    }
+
+    fn selector_list(&mut self) -> Result<SelectorList, SelectorParseError> {
+        let mut selectors = vec![self.complex_selector()?];
+        loop {
+            self.skip_whitespace();
+            if !self.eat(',') {
+                break;
+            }
+            self.skip_whitespace();
+            selectors.push(self.complex_selector()?);
+        }
+
+        Ok(SelectorList(selectors))
+    }
+
+    fn relative_selector_list(&mut self) -> Result<Vec<RelativeSelector>, SelectorParseError> {
+        let mut selectors = Vec::new();
+        loop {
+            let combinator = self.explicit_combinator().unwrap_or(Combinator::Descendant);
+            self.skip_whitespace();
+            let selector = self.complex_selector()?;
+            selectors.push(RelativeSelector {
+                combinator,
+                selector,
+            });
+
+            self.skip_whitespace();
+            if !self.eat(',') {
+                return Ok(selectors);
+            }
+            self.skip_whitespace();
+        }
+    }
+
+    fn complex_selector(&mut self) -> Result<ComplexSelector, SelectorParseError> {
+        let first = self.compound_selector()?;
+        let mut rest = Vec::new();
+        while let Some(combinator) = self.combinator() {
+            rest.push((combinator, self.compound_selector()?));
+        }
+
+        Ok(ComplexSelector { first, rest })
+    }
+
+    /// Parse a combinator and the whitespace around it.
+    ///
+    /// Whitespace followed by the end of the selector is not a descendant combinator
+    /// and is left for the caller, so lists and function arguments can end with whitespace.
+    fn combinator(&mut self) -> Option<Combinator> {
+        let start = self.position;
+        let had_whitespace = self.skip_whitespace();
+        let combinator = match self.explicit_combinator() {
+            Some(combinator) => combinator,
+            None if had_whitespace && !matches!(self.peek(), None | Some(',' | ')')) => {
+                return Some(Combinator::Descendant)
+            }
+            None => {
+                self.position = start;
+                return None;
+            }
+        };
+        self.skip_whitespace();
+
+        Some(combinator)
+    }
+
+    fn explicit_combinator(&mut self) -> Option<Combinator> {
+        let combinator = match self.peek()? {
+            '>' => Combinator::Child,
+            '+' => Combinator::NextSibling,
+            '~' => Combinator::SubsequentSibling,
+            _ => return None,
+        };
+        self.position += 1;
+
+        Some(combinator)
+    }
+
+    fn compound_selector(&mut self) -> Result<CompoundSelector, SelectorParseError> {
+        let (element, has_type_selector) = if self.eat('*') {
+            (None, true)
+        } else if self.starts_identifier() {
+            (Some(self.identifier()?), true)
+        } else {
+            (None, false)
+        };
+        if self.peek() == Some('|') && self.peek_nth(1) != Some('=') {
+            return Err(SelectorParseError::Unsupported(
+                "namespace prefixes".to_string(),
+            ));
+        }
+
+        let mut conditions = Vec::new();
+        loop {
+            let condition = match self.peek() {
+                Some('#') => {
+                    self.position += 1;
+                    Condition::Id(self.name()?)
+                }
+                Some('.') => {
+                    self.position += 1;
+                    Condition::Class(self.identifier()?)
+                }
+                Some('[') => {
+                    self.position += 1;
+                    Condition::Attribute(self.attribute_selector()?)
+                }
+                Some(':') => {
+                    self.position += 1;
+                    Condition::PseudoClass(self.pseudo_class()?)
+                }
+                _ => break,
+            };
+            conditions.push(condition);
+        }
+
+        if !has_type_selector && conditions.is_empty() {
+            return Err(self.expected("a selector"));
+        }
+
+        Ok(CompoundSelector {
+            element,
+            conditions,
+        })
+    }
+
+    fn attribute_selector(&mut self) -> Result<AttributeSelector, SelectorParseError> {
+        self.skip_whitespace();
+        let name = self.identifier()?;
+        if self.peek() == Some('|') && self.peek_nth(1) != Some('=') {
+            return Err(SelectorParseError::Unsupported(
+                "namespace prefixes".to_string(),
+            ));
+        }
+        self.skip_whitespace();
+        if self.eat(']') {
+            return Ok(AttributeSelector {
+                name,
+                matcher: None,
+            });
+        }
+
+        let operator = match self.bump() {
+            Some('=') => AttributeOperator::Equals,
+            Some(c @ ('~' | '|' | '^' | '$' | '*')) if self.eat('=') => match c {
+                '~' => AttributeOperator::Includes,
+                '|' => AttributeOperator::DashMatch,
+                '^' => AttributeOperator::Prefix,
+                '$' => AttributeOperator::Suffix,
+                _ => AttributeOperator::Substring,
+            },
+            _ => {
+                self.position -= 1;
+                return Err(self.expected("an attribute operator or `]`"));
+            }
+        };
+        self.skip_whitespace();
+        let value = match self.peek() {
+            Some('"' | '\'') => self.string()?,
+            _ => self.identifier()?,
+        };
+        self.skip_whitespace();
+        if self.starts_identifier() {
+            let modifier = self.identifier()?;
+            if !modifier.eq_ignore_ascii_case("s") {
+                return Err(SelectorParseError::Unsupported(format!(
+                    "attribute modifier `{}`",
+                    modifier
+                )));
+            }
+            self.skip_whitespace();
+        }
+        self.expect(']')?;
+
+        Ok(AttributeSelector {
+            name,
+            matcher: Some((operator, value)),
+        })
+    }
+
+    fn pseudo_class(&mut self) -> Result<PseudoClass, SelectorParseError> {
+        if self.peek() == Some(':') {
+            return Err(SelectorParseError::Unsupported(
+                "pseudo-elements".to_string(),
+            ));
+        }
+
+        let start = self.position;
+        let name = self.identifier()?.to_ascii_lowercase();
+        if !self.eat('(') {
+            return match name.as_str() {
+                "root" => Ok(PseudoClass::Root),
+                "empty" => Ok(PseudoClass::Empty),
+                "first-child" => Ok(PseudoClass::FirstChild),
+                "last-child" => Ok(PseudoClass::LastChild),
+                "only-child" => Ok(PseudoClass::OnlyChild),
+                "first-of-type" => Ok(PseudoClass::FirstOfType),
+                "last-of-type" => Ok(PseudoClass::LastOfType),
+                "only-of-type" => Ok(PseudoClass::OnlyOfType),
+                _ => Err(self.unknown_pseudo_class(start, &name)),
+            };
+        }
+
+        self.skip_whitespace();
+        let pseudo_class = match name.as_str() {
+            "nth-child" => PseudoClass::NthChild(self.nth()?),
+            "nth-last-child" => PseudoClass::NthLastChild(self.nth()?),
+            "nth-of-type" => PseudoClass::NthOfType(self.nth()?),
+            "nth-last-of-type" => PseudoClass::NthLastOfType(self.nth()?),
+            "not" => PseudoClass::Not(self.selector_list()?),
+            "is" | "where" | "matches" => PseudoClass::Is(self.selector_list()?),
+            "has" => PseudoClass::Has(self.relative_selector_list()?),
+            "contains" => PseudoClass::Contains(match self.peek() {
+                Some('"' | '\'') => self.string()?,
+                _ => self.identifier()?,
+            }),
+            _ => return Err(self.unknown_pseudo_class(start, &name)),
+        };
+        self.skip_whitespace();
+        self.expect(')')?;
+
+        Ok(pseudo_class)
+    }
+
+    fn unknown_pseudo_class(&self, start: usize, name: &str) -> SelectorParseError {
+        // Valid CSS that depends on user interaction or the browser cannot be evaluated
+        // against a static document, so it is reported as unsupported rather than invalid.
+        const KNOWN: &[&str] = &[
+            "active",
+            "any-link",
+            "checked",
+            "default",
+            "defined",
+            "disabled",
+            "enabled",
+            "focus",
+            "focus-visible",
+            "focus-within",
+            "hover",
+            "indeterminate",
+            "in-range",
+            "invalid",
+            "lang",
+            "link",
+            "optional",
+            "out-of-range",
+            "read-only",
+            "read-write",
+            "required",
+            "scope",
+            "target",
+            "valid",
+            "visited",
+        ];
+
+        if KNOWN.contains(&name) {
+            SelectorParseError::Unsupported(format!("pseudo-class `:{}`", name))
+        } else {
+            SelectorParseError::Syntax {
+                offset: start,
+                message: format!("unknown pseudo-class `:{}`", name),
+            }
+        }
+    }
+
+    /// Parse the `an+b` microsyntax, including `odd` and `even`.
+    ///
+    /// <https://www.w3.org/TR/css-syntax-3/#anb-microsyntax>
+    fn nth(&mut self) -> Result<Nth, SelectorParseError> {
+        let start = self.position;
+        let end = self
+            .remaining()
+            .find(')')
+            .map_or(self.input.len(), |i| self.position + i);
+        let argument = self.input[start..end].trim_end().to_ascii_lowercase();
+        if argument.contains(" of ") {
+            return Err(SelectorParseError::Unsupported(
+                "`of` selectors in `:nth-*` pseudo-classes".to_string(),
+            ));
+        }
+
+        let nth = match argument.as_str() {
+            "odd" => Some(Nth { a: 2, b: 1 }),
+            "even" => Some(Nth { a: 2, b: 0 }),
+            argument => parse_nth(argument),
+        };
+        match nth {
+            Some(nth) => {
+                self.position = start + self.input[start..end].trim_end().len();
+                Ok(nth)
+            }
+            None => Err(self.expected("an `an+b` expression")),
+        }
+    }
+
+    fn starts_identifier(&self) -> bool {
+        let mut chars = self.remaining().chars();
+        let first = match chars.next() {
+            Some('-') => match chars.next() {
+                Some('-') => return true,
+                Some(c) => c,
+                None => return false,
+            },
+            Some(c) => c,
+            None => return false,
+        };
+        first == '_' || first == '\\' || first.is_ascii_alphabetic() || !first.is_ascii()
+    }
+
+    fn identifier(&mut self) -> Result<String, SelectorParseError> {
+        if !self.starts_identifier() {
+            return Err(self.expected("an identifier"));
+        }
+
+        self.name()
+    }
+
+    /// Parse a sequence of name characters, which unlike an identifier may start with a digit.
+    fn name(&mut self) -> Result<String, SelectorParseError> {
+        let mut name = String::new();
+        while let Some(c) = self.peek() {
+            if c == '\\' {
+                name.push(self.escape()?);
+            } else if c == '_' || c == '-' || c.is_ascii_alphanumeric() || !c.is_ascii() {
+                name.push(c);
+                self.position += c.len_utf8();
+            } else {
+                break;
+            }
+        }
+
+        if name.is_empty() {
+            return Err(self.expected("a name"));
+        }
+
+        Ok(name)
+    }
+
+    /// Parse an escape sequence, e.g. `\:` or `\31 `.
+    fn escape(&mut self) -> Result<char, SelectorParseError> {
+        self.expect('\\')?;
+        let hex_len = self
+            .remaining()
+            .chars()
+            .take(6)
+            .take_while(char::is_ascii_hexdigit)
+            .count();
+        if hex_len == 0 {
+            return match self.bump() {
+                Some('\n' | '\r' | '\x0C') | None => Err(self.expected("an escaped character")),
+                Some(c) => Ok(c),
+            };
+        }
+
+        let hex = &self.remaining()[..hex_len];
+        let code = u32::from_str_radix(hex, 16).expect("hex digits should parse");
+        self.position += hex_len;
+        // A single whitespace character terminates the escape.
+        if self.eat('\r') {
+            self.eat('\n');
+        } else if matches!(self.peek(), Some(' ' | '\t' | '\n' | '\x0C')) {
+            self.position += 1;
+        }
+
+        Ok(match char::from_u32(code) {
+            Some(c) if code != 0 => c,
+            _ => char::REPLACEMENT_CHARACTER,
+        })
+    }
+
+    fn string(&mut self) -> Result<String, SelectorParseError> {
+        let quote = self.bump().expect("string should start with a quote");
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                Some(c) if c == quote => {
+                    self.position += 1;
+                    return Ok(value);
+                }
+                Some('\\') => match self.peek_nth(1) {
+                    // An escaped newline continues the string on the next line.
+                    Some('\n') => self.position += 2,
+                    _ => value.push(self.escape()?),
+                },
+                Some('\n') | None => return Err(self.expected(&format!("`{}`", quote))),
+                Some(c) => {
+                    value.push(c);
+                    self.position += c.len_utf8();
+                }
+            }
+        }
+    }
+}
+
+fn parse_nth(argument: &str) -> Option<Nth> {
+    fn integer(text: &str) -> Option<i64> {
+        let digits = text.strip_prefix('+').unwrap_or(text);
+        if digits.starts_with(['+', ' ']) {
+            return None;
+        }
+        digits.parse().ok()
+    }
+
+    let Some(n) = argument.find('n') else {
+        return Some(Nth {
+            a: 0,
+            b: integer(argument)?,
+        });
+    };
+
+    let a = match &argument[..n] {
+        "" | "+" => 1,
+        "-" => -1,
+        a => integer(a)?,
+    };
+    let rest = argument[n + 1..].trim_start();
+    let b = if rest.is_empty() {
+        0
+    } else {
+        let (sign, digits) = match rest.split_at(1) {
+            ("+", digits) => (1, digits.trim_start()),
+            ("-", digits) => (-1, digits.trim_start()),
+            _ => return None,
+        };
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        sign * digits.parse::<i64>().ok()?
+    };
+
+    Some(Nth { a, b })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_selector_list_should_parse_compound_selectors_and_combinators() {
+        // arrange
+        let input = "div.product > a[href^='http'], h2 + p";
+
+        // act
+        let list = parse_selector_list(input).unwrap();
+
+        // assert
+        assert_eq!(
+            list,
+            SelectorList(vec![
+                ComplexSelector {
+                    first: CompoundSelector {
+                        element: Some(String::from("div")),
+                        conditions: vec![Condition::Class(String::from("product"))],
+                    },
+                    rest: vec![(
+                        Combinator::Child,
+                        CompoundSelector {
+                            element: Some(String::from("a")),
+                            conditions: vec![Condition::Attribute(AttributeSelector {
+                                name: String::from("href"),
+                                matcher: Some((AttributeOperator::Prefix, String::from("http"))),
+                            })],
+                        }
+                    )],
+                },
+                ComplexSelector {
+                    first: CompoundSelector {
+                        element: Some(String::from("h2")),
+                        conditions: vec![],
+                    },
+                    rest: vec![(
+                        Combinator::NextSibling,
+                        CompoundSelector {
+                            element: Some(String::from("p")),
+                            conditions: vec![],
+                        }
+                    )],
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_nth_should_parse_anb_forms() {
+        // arrange
+        let cases = [
+            ("3", Nth { a: 0, b: 3 }),
+            ("n", Nth { a: 1, b: 0 }),
+            ("-n+3", Nth { a: -1, b: 3 }),
+            ("2n + 1", Nth { a: 2, b: 1 }),
+            ("+3n-2", Nth { a: 3, b: -2 }),
+        ];
+
+        for (argument, expected) in cases {
+            // act
+            let nth = parse_nth(argument);
+
+            // assert
+            assert_eq!(nth, Some(expected), "{}", argument);
+        }
+        assert_eq!(parse_nth("2n+"), None);
+        assert_eq!(parse_nth("n2"), None);
+    }
+
+    #[test]
+    fn parse_selector_list_should_report_offset_of_syntax_error() {
+        // arrange
+        let input = "div[href^=]";
+
+        // act
+        let err = parse_selector_list(input).unwrap_err();
+
+        // assert
+        assert_eq!(
+            err,
+            SelectorParseError::Syntax {
+                offset: 10,
+                message: String::from("expected an identifier, found `]`"),
+            }
+        );
+    }
+}
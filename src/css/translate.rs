@@ -0,0 +1,335 @@
+//! Translation of parsed CSS selectors into XPath expression text.
+//!
+//! The translation follows the approach of cssselect and lxml:
+//! every compound selector becomes a step selecting elements,
+//! and every condition becomes a predicate on that step.
+
+use super::{
+    selector::{
+        AttributeOperator, AttributeSelector, Combinator, ComplexSelector, CompoundSelector,
+        Condition, Nth, PseudoClass, RelativeSelector, SelectorList,
+    },
+    SelectorParseError,
+};
+
+/// Predicate that is never true, used for conditions that cannot match any element.
+const NEVER: &str = "false()";
+
+/// Largest `a` or `b` of `an+b` that is translated, far beyond any number of siblings.
+///
+/// Bounding them keeps the arithmetic on them from overflowing.
+const MAX_NTH: i64 = i32::MAX as i64;
+
+/// Translate a selector list into an expression selecting the matched elements from the document.
+///
+/// Each selector becomes a path starting with `//`,
+/// and a list of several selectors becomes a sequence of these paths.
+pub(crate) fn selector_list(list: &SelectorList) -> Result<String, SelectorParseError> {
+    let paths = list
+        .0
+        .iter()
+        .map(|selector| Ok(format!("//{}", path(selector)?)))
+        .collect::<Result<Vec<_>, SelectorParseError>>()?;
+
+    Ok(paths.join(", "))
+}
+
+/// Translate a complex selector into a relative path,
+/// without the separator leading to its first step.
+fn path(selector: &ComplexSelector) -> Result<String, SelectorParseError> {
+    let first = step(Combinator::Child, &selector.first)?;
+
+    Ok(format!("{}{}", first, steps(&selector.rest)?))
+}
+
+/// Translate the compound selectors following the first one into steps with their separators.
+fn steps(rest: &[(Combinator, CompoundSelector)]) -> Result<String, SelectorParseError> {
+    let mut steps = String::new();
+    for (combinator, compound) in rest.iter() {
+        let separator = match combinator {
+            Combinator::Descendant => "//",
+            _ => "/",
+        };
+        steps.push_str(separator);
+        steps.push_str(&step(*combinator, compound)?);
+    }
+
+    Ok(steps)
+}
+
+/// Translate a compound selector into a step from an element
+/// to the elements related to it by the combinator that match the compound selector.
+///
+/// Descendants are selected by the `//` separator, so they use the child axis here.
+fn step(combinator: Combinator, compound: &CompoundSelector) -> Result<String, SelectorParseError> {
+    let predicates = predicates(compound)?;
+    let step = match combinator {
+        Combinator::Descendant | Combinator::Child => {
+            format!("{}{}", name_test(compound)?, predicates)
+        }
+        Combinator::SubsequentSibling => {
+            format!("following-sibling::{}{}", name_test(compound)?, predicates)
+        }
+        Combinator::NextSibling => {
+            // The position is taken among all following siblings before testing the name.
+            let self_test = match &compound.element {
+                Some(name) => format!("[self::{}]", check_name(name)?),
+                None => String::new(),
+            };
+            format!("following-sibling::*[1]{}{}", self_test, predicates)
+        }
+    };
+
+    Ok(step)
+}
+
+fn name_test(compound: &CompoundSelector) -> Result<&str, SelectorParseError> {
+    match &compound.element {
+        Some(name) => check_name(name),
+        None => Ok("*"),
+    }
+}
+
+fn predicates(compound: &CompoundSelector) -> Result<String, SelectorParseError> {
+    let mut predicates = String::new();
+    for condition in compound.conditions.iter() {
+        if let Some(predicate) = condition_predicate(compound, condition)? {
+            predicates.push_str(&format!("[{}]", predicate));
+        }
+    }
+
+    Ok(predicates)
+}
+
+/// Translate a condition into a predicate expression,
+/// or `None` if every element matches it.
+fn condition_predicate(
+    compound: &CompoundSelector,
+    condition: &Condition,
+) -> Result<Option<String>, SelectorParseError> {
+    let predicate = match condition {
+        Condition::Id(id) => format!("@id={}", literal(id)),
+        Condition::Class(class) => contains_token("@class", class),
+        Condition::Attribute(attribute) => attribute_predicate(attribute)?,
+        Condition::PseudoClass(pseudo_class) => {
+            return pseudo_class_predicate(compound, pseudo_class)
+        }
+    };
+
+    Ok(Some(predicate))
+}
+
+fn attribute_predicate(attribute: &AttributeSelector) -> Result<String, SelectorParseError> {
+    let name = format!("@{}", check_name(&attribute.name)?);
+    let Some((operator, value)) = &attribute.matcher else {
+        return Ok(name);
+    };
+
+    let predicate = match operator {
+        AttributeOperator::Equals => format!("{}={}", name, literal(value)),
+        AttributeOperator::Includes => {
+            if value.is_empty() || value.contains(char::is_whitespace) {
+                NEVER.to_string()
+            } else {
+                contains_token(&name, value)
+            }
+        }
+        AttributeOperator::DashMatch => format!(
+            "{}={} or starts-with({}, {})",
+            name,
+            literal(value),
+            name,
+            literal(&format!("{}-", value))
+        ),
+        // Selectors 4 defines the substring operators with an empty value to match nothing.
+        _ if value.is_empty() => NEVER.to_string(),
+        AttributeOperator::Prefix => format!("starts-with({}, {})", name, literal(value)),
+        AttributeOperator::Suffix => format!("ends-with({}, {})", name, literal(value)),
+        AttributeOperator::Substring => format!("contains({}, {})", name, literal(value)),
+    };
+
+    Ok(predicate)
+}
+
+/// Test whether the whitespace separated list in `value` contains `token`, as `.class` does.
+fn contains_token(value: &str, token: &str) -> String {
+    format!(
+        "contains(' ' || normalize-space({}) || ' ', {})",
+        value,
+        literal(&format!(" {} ", token))
+    )
+}
+
+fn pseudo_class_predicate(
+    compound: &CompoundSelector,
+    pseudo_class: &PseudoClass,
+) -> Result<Option<String>, SelectorParseError> {
+    let predicate = match pseudo_class {
+        PseudoClass::Root => "not(parent::*)".to_string(),
+        PseudoClass::Empty => "not(*) and not(text())".to_string(),
+        PseudoClass::FirstChild => "not(preceding-sibling::*)".to_string(),
+        PseudoClass::LastChild => "not(following-sibling::*)".to_string(),
+        PseudoClass::OnlyChild => {
+            "not(preceding-sibling::*) and not(following-sibling::*)".to_string()
+        }
+        PseudoClass::FirstOfType => {
+            format!(
+                "not(preceding-sibling::{})",
+                of_type(compound, pseudo_class)?
+            )
+        }
+        PseudoClass::LastOfType => {
+            format!(
+                "not(following-sibling::{})",
+                of_type(compound, pseudo_class)?
+            )
+        }
+        PseudoClass::OnlyOfType => {
+            let name = of_type(compound, pseudo_class)?;
+            format!(
+                "not(preceding-sibling::{}) and not(following-sibling::{})",
+                name, name
+            )
+        }
+        PseudoClass::NthChild(nth) => return nth_predicate("preceding-sibling::*", nth),
+        PseudoClass::NthLastChild(nth) => return nth_predicate("following-sibling::*", nth),
+        PseudoClass::NthOfType(nth) => {
+            let siblings = format!("preceding-sibling::{}", of_type(compound, pseudo_class)?);
+            return nth_predicate(&siblings, nth);
+        }
+        PseudoClass::NthLastOfType(nth) => {
+            let siblings = format!("following-sibling::{}", of_type(compound, pseudo_class)?);
+            return nth_predicate(&siblings, nth);
+        }
+        PseudoClass::Not(list) => format!("not({})", self_tests(list)?),
+        PseudoClass::Is(list) => self_tests(list)?,
+        PseudoClass::Has(selectors) => selectors
+            .iter()
+            .map(relative_path)
+            .collect::<Result<Vec<_>, SelectorParseError>>()?
+            .join(" or "),
+        PseudoClass::Contains(text) => format!("contains(., {})", literal(text)),
+    };
+
+    Ok(Some(predicate))
+}
+
+/// Get the element name the `:*-of-type` pseudo-classes count siblings of.
+///
+/// A predicate on the siblings cannot refer back to the name of the context element,
+/// so the name must be given by a type selector.
+fn of_type<'a>(
+    compound: &'a CompoundSelector,
+    pseudo_class: &PseudoClass,
+) -> Result<&'a str, SelectorParseError> {
+    match &compound.element {
+        Some(name) => check_name(name),
+        None => Err(SelectorParseError::Unsupported(format!(
+            "{} without a type selector",
+            pseudo_class_name(pseudo_class)
+        ))),
+    }
+}
+
+fn pseudo_class_name(pseudo_class: &PseudoClass) -> &'static str {
+    match pseudo_class {
+        PseudoClass::FirstOfType => ":first-of-type",
+        PseudoClass::LastOfType => ":last-of-type",
+        PseudoClass::OnlyOfType => ":only-of-type",
+        PseudoClass::NthOfType(_) => ":nth-of-type()",
+        PseudoClass::NthLastOfType(_) => ":nth-last-of-type()",
+        _ => unreachable!("only the of-type pseudo-classes need a type selector"),
+    }
+}
+
+/// Translate `an+b` into a predicate on the number of siblings before (or after) an element.
+///
+/// An element at position `p` matches if `p = a*n + b` for some `n >= 0`.
+/// With `c = p - 1` siblings counted and `k = b - 1`, this is `c = a*n + k`.
+fn nth_predicate(siblings: &str, nth: &Nth) -> Result<Option<String>, SelectorParseError> {
+    let bounds = -MAX_NTH..=MAX_NTH;
+    if !bounds.contains(&nth.a) || !bounds.contains(&nth.b) {
+        return Err(SelectorParseError::Unsupported(format!(
+            "an+b with a or b beyond {}",
+            MAX_NTH
+        )));
+    }
+
+    let count = format!("count({})", siblings);
+    let k = nth.b - 1;
+    let predicate = match nth.a {
+        0 if k < 0 => NEVER.to_string(),
+        0 => format!("{}={}", count, k),
+        // Every count at or past `k` matches.
+        1 if k <= 0 => return Ok(None),
+        1 => format!("{}>={}", count, k),
+        -1 if k < 0 => NEVER.to_string(),
+        -1 => format!("{}<={}", count, k),
+        a if a > 0 && k <= 0 => match (-k).rem_euclid(a) {
+            0 => format!("{} mod {}=0", count, a),
+            r => format!("({} + {}) mod {}=0", count, r, a),
+        },
+        a if a > 0 => format!("{}>={} and ({} - {}) mod {}=0", count, k, count, k, a),
+        _ if k < 0 => NEVER.to_string(),
+        a => format!("{}<={} and ({} - {}) mod {}=0", count, k, k, count, a.abs()),
+    };
+
+    Ok(Some(predicate))
+}
+
+/// Translate the selectors of `:not()` and `:is()` into tests on the context element.
+fn self_tests(list: &SelectorList) -> Result<String, SelectorParseError> {
+    let tests = list
+        .0
+        .iter()
+        .map(|selector| {
+            if !selector.rest.is_empty() {
+                // Testing a complex selector would need the ancestor and preceding axes.
+                return Err(SelectorParseError::Unsupported(
+                    "complex selectors in :not() and :is()".to_string(),
+                ));
+            }
+
+            let compound = &selector.first;
+            Ok(format!(
+                "self::{}{}",
+                name_test(compound)?,
+                predicates(compound)?
+            ))
+        })
+        .collect::<Result<Vec<_>, SelectorParseError>>()?;
+
+    Ok(tests.join(" or "))
+}
+
+/// Translate a selector of `:has()` into a path from the context element.
+fn relative_path(selector: &RelativeSelector) -> Result<String, SelectorParseError> {
+    let first = step(selector.combinator, &selector.selector.first)?;
+    let first = match selector.combinator {
+        Combinator::Descendant => format!(".//{}", first),
+        _ => first,
+    };
+
+    Ok(format!("{}{}", first, steps(&selector.selector.rest)?))
+}
+
+/// Check that a CSS name is usable as an XPath name test.
+fn check_name(name: &str) -> Result<&str, SelectorParseError> {
+    let mut chars = name.chars();
+    let is_valid = chars.next().is_some_and(|c| c == '_' || c.is_alphabetic())
+        && chars.all(|c| c == '_' || c == '-' || c == '.' || c.is_alphanumeric());
+
+    if is_valid {
+        Ok(name)
+    } else {
+        Err(SelectorParseError::Unsupported(format!(
+            "name `{}` that is not a valid XPath name",
+            name
+        )))
+    }
+}
+
+/// Quote a string as an XPath string literal.
+fn literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
@@ -3,6 +3,7 @@
 //! created programatically or parsed from XPath string literals.
 //!
 //! This crate is split into two main modules: [html] and [xpath].
+//! The [css] module compiles CSS selectors to XPath expressions.
 //!
 //! For more information on HTML documents and nodes, including how to get text or attributes from nodes,
 //! see the [html] module documentation.
@@ -93,9 +94,29 @@
 //!     Ok(())
 //! }
 //! ```
+//!
+//! # Example: check static Xpath expressions at compile time
+//!
+//! The `skyscraper-macros` crate provides an `xpath!` macro that parses the expression
+//! when your crate is compiled, so a typo is a compile error rather than a panic on first use.
+//! The macro expands to a `&'static Xpath` that is built once, like the `Lazy` above.
+//!
+//! ```rust,ignore
+//! use skyscraper_macros::xpath;
+//!
+//! let result = xpath!("/div/span").apply(&xpath_item_tree)?;
+//! ```
+//!
+//! # Features
+//!
+//! - `serde` - Implement `Serialize` and `Deserialize` for [Xpath](xpath::Xpath) and the
+//!   expressions it is made of, so parsed expressions can be cached or inspected as JSON.
+//! - `bench` - Expose internals for the benchmarks, which compare compiled and uncompiled
+//!   expressions with `cargo bench --features bench`. Not meant for use by other crates.
 
 #![warn(missing_docs)]
 
+pub mod css;
 pub mod html;
 mod vecpointer;
 pub mod xpath;
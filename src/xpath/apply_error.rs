@@ -0,0 +1,468 @@
+//! Errors that occur when applying an [Xpath](crate::xpath::Xpath) expression.
+
+use std::{fmt::Display, ops::Range};
+
+use crate::xpath::{
+    context::ERR_NAMESPACE, functions::ExpandedName, limits::EvaluationLimit, span, Xpath,
+};
+
+/// The code identifying an XPath error.
+///
+/// <https://www.w3.org/TR/2017/REC-xpath-31-20170321/#id-errors>
+///
+/// Codes are displayed with the conventional `err` prefix, such as `err:XPTY0004`.
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub enum ErrorCode {
+    /// It is a static error if an expression is not a valid instance of the grammar.
+    XPST0003,
+
+    /// It is a static error if an expression refers to a variable name that is not in scope.
+    XPST0008,
+
+    /// It is a static error if a function call does not match the name and arity of a known function.
+    XPST0017,
+
+    /// It is a static error if a namespace prefix is not declared.
+    XPST0081,
+
+    /// It is a dynamic error if evaluation relies on a part of the dynamic context that is absent,
+    /// such as the context item.
+    XPDY0002,
+
+    /// It is a dynamic error if the value of a `treat` expression does not match the required type.
+    XPDY0050,
+
+    /// It is a type error if a value does not match a required type.
+    XPTY0004,
+
+    /// It is a type error if the context item of an axis step is not a node.
+    XPTY0020,
+
+    /// It is a dynamic error if a map constructor has two entries with the same key.
+    XQDY0137,
+
+    /// Division by zero.
+    FOAR0001,
+
+    /// Numeric operation overflow or underflow.
+    FOAR0002,
+
+    /// Array index out of bounds.
+    FOAY0001,
+
+    /// The collation is not supported.
+    FOCH0002,
+
+    /// Error retrieving resource.
+    FODC0002,
+
+    /// The decimal format name is not a known decimal format.
+    FODF1280,
+
+    /// The picture string of `fn:format-number` or `fn:format-integer` is invalid.
+    FODF1310,
+
+    /// JSON syntax error.
+    FOJS0001,
+
+    /// Invalid XML representation of JSON.
+    FOJS0006,
+
+    /// The base URI is not defined in the static context.
+    FONS0005,
+
+    /// Function items cannot be atomized.
+    FOTY0013,
+
+    /// Function items do not have a string value.
+    FOTY0014,
+
+    /// Unidentified error, raised by `fn:error` when no code is given.
+    FOER0000,
+
+    /// Invalid value for cast or constructor.
+    FORG0001,
+
+    /// Invalid argument to `fn:resolve-uri`.
+    FORG0002,
+
+    /// Invalid argument type.
+    FORG0006,
+
+    /// The resource read by `fn:unparsed-text` cannot be loaded.
+    FOUT1170,
+
+    /// The encoding given to `fn:unparsed-text` is not supported.
+    FOUT1190,
+
+    /// A serialization parameter has an invalid value.
+    SEPM0016,
+
+    /// A number cannot be serialized with the JSON output method, such as `NaN` or infinity.
+    SERE0020,
+
+    /// An item cannot be serialized with the JSON output method, such as an attribute node.
+    SERE0021,
+
+    /// A map has two keys with the same string value when serialized with the JSON output method.
+    SERE0022,
+
+    /// A sequence of more than one item cannot be serialized with the JSON output method.
+    SERE0023,
+
+    /// The expression uses a feature that is not supported by this implementation.
+    ///
+    /// This is not an XPath error code.
+    Unsupported,
+
+    /// The evaluation exceeded one of the [EvaluationLimits](crate::xpath::limits::EvaluationLimits)
+    /// set on the dynamic context.
+    ///
+    /// This is not an XPath error code.
+    LimitExceeded(EvaluationLimit),
+
+    /// Any other error code, such as a code passed to `fn:error`.
+    Other(ExpandedName),
+}
+
+impl ErrorCode {
+    /// Get the error code for a name.
+    ///
+    /// Names in the `err` namespace that match a known code return that code.
+    pub fn from_name(name: ExpandedName) -> Self {
+        if name.namespace == ERR_NAMESPACE {
+            let code = match name.local_name.as_str() {
+                "XPST0003" => Some(ErrorCode::XPST0003),
+                "XPST0008" => Some(ErrorCode::XPST0008),
+                "XPST0017" => Some(ErrorCode::XPST0017),
+                "XPST0081" => Some(ErrorCode::XPST0081),
+                "XPDY0002" => Some(ErrorCode::XPDY0002),
+                "XPDY0050" => Some(ErrorCode::XPDY0050),
+                "XPTY0004" => Some(ErrorCode::XPTY0004),
+                "XPTY0020" => Some(ErrorCode::XPTY0020),
+                "XQDY0137" => Some(ErrorCode::XQDY0137),
+                "FOAR0001" => Some(ErrorCode::FOAR0001),
+                "FOAR0002" => Some(ErrorCode::FOAR0002),
+                "FOAY0001" => Some(ErrorCode::FOAY0001),
+                "FOCH0002" => Some(ErrorCode::FOCH0002),
+                "FODC0002" => Some(ErrorCode::FODC0002),
+                "FODF1280" => Some(ErrorCode::FODF1280),
+                "FODF1310" => Some(ErrorCode::FODF1310),
+                "FOJS0001" => Some(ErrorCode::FOJS0001),
+                "FOJS0006" => Some(ErrorCode::FOJS0006),
+                "FONS0005" => Some(ErrorCode::FONS0005),
+                "FOTY0013" => Some(ErrorCode::FOTY0013),
+                "FOTY0014" => Some(ErrorCode::FOTY0014),
+                "FOER0000" => Some(ErrorCode::FOER0000),
+                "FORG0001" => Some(ErrorCode::FORG0001),
+                "FORG0002" => Some(ErrorCode::FORG0002),
+                "FORG0006" => Some(ErrorCode::FORG0006),
+                "FOUT1170" => Some(ErrorCode::FOUT1170),
+                "FOUT1190" => Some(ErrorCode::FOUT1190),
+                "SEPM0016" => Some(ErrorCode::SEPM0016),
+                "SERE0020" => Some(ErrorCode::SERE0020),
+                "SERE0021" => Some(ErrorCode::SERE0021),
+                "SERE0022" => Some(ErrorCode::SERE0022),
+                "SERE0023" => Some(ErrorCode::SERE0023),
+                _ => None,
+            };
+
+            if let Some(code) = code {
+                return code;
+            }
+        }
+
+        ErrorCode::Other(name)
+    }
+
+    /// The expanded name of the error code.
+    ///
+    /// [ErrorCode::Unsupported] and [ErrorCode::LimitExceeded] have no name, and return `None`.
+    pub fn name(&self) -> Option<ExpandedName> {
+        let local_name = match self {
+            ErrorCode::XPST0003 => "XPST0003",
+            ErrorCode::XPST0008 => "XPST0008",
+            ErrorCode::XPST0017 => "XPST0017",
+            ErrorCode::XPST0081 => "XPST0081",
+            ErrorCode::XPDY0002 => "XPDY0002",
+            ErrorCode::XPDY0050 => "XPDY0050",
+            ErrorCode::XPTY0004 => "XPTY0004",
+            ErrorCode::XPTY0020 => "XPTY0020",
+            ErrorCode::XQDY0137 => "XQDY0137",
+            ErrorCode::FOAR0001 => "FOAR0001",
+            ErrorCode::FOAR0002 => "FOAR0002",
+            ErrorCode::FOAY0001 => "FOAY0001",
+            ErrorCode::FOCH0002 => "FOCH0002",
+            ErrorCode::FODC0002 => "FODC0002",
+            ErrorCode::FODF1280 => "FODF1280",
+            ErrorCode::FODF1310 => "FODF1310",
+            ErrorCode::FOJS0001 => "FOJS0001",
+            ErrorCode::FOJS0006 => "FOJS0006",
+            ErrorCode::FONS0005 => "FONS0005",
+            ErrorCode::FOTY0013 => "FOTY0013",
+            ErrorCode::FOTY0014 => "FOTY0014",
+            ErrorCode::FOER0000 => "FOER0000",
+            ErrorCode::FORG0001 => "FORG0001",
+            ErrorCode::FORG0002 => "FORG0002",
+            ErrorCode::FORG0006 => "FORG0006",
+            ErrorCode::FOUT1170 => "FOUT1170",
+            ErrorCode::FOUT1190 => "FOUT1190",
+            ErrorCode::SEPM0016 => "SEPM0016",
+            ErrorCode::SERE0020 => "SERE0020",
+            ErrorCode::SERE0021 => "SERE0021",
+            ErrorCode::SERE0022 => "SERE0022",
+            ErrorCode::SERE0023 => "SERE0023",
+            ErrorCode::Unsupported | ErrorCode::LimitExceeded(_) => return None,
+            ErrorCode::Other(name) => return Some(name.clone()),
+        };
+
+        Some(ExpandedName::new(ERR_NAMESPACE, local_name))
+    }
+
+    /// Whether the error is a static error, found without looking at the document.
+    ///
+    /// Static errors are raised for every document, so retrying with another document will not help.
+    pub fn is_static_error(&self) -> bool {
+        self.has_prefix("XPST")
+    }
+
+    /// Whether the error is a type error, such as an operand with the wrong type or cardinality.
+    pub fn is_type_error(&self) -> bool {
+        self.has_prefix("XPTY")
+    }
+
+    /// Whether the error is a dynamic error, raised by the values found while evaluating.
+    ///
+    /// This includes errors raised by functions, such as `err:FORG0001`,
+    /// but not [ErrorCode::LimitExceeded].
+    pub fn is_dynamic_error(&self) -> bool {
+        !self.is_static_error()
+            && !self.is_type_error()
+            && !matches!(self, ErrorCode::Unsupported | ErrorCode::LimitExceeded(_))
+    }
+
+    fn has_prefix(&self, prefix: &str) -> bool {
+        match self.name() {
+            Some(name) => name.namespace == ERR_NAMESPACE && name.local_name.starts_with(prefix),
+            None => false,
+        }
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let ErrorCode::LimitExceeded(limit) = self {
+            return write!(f, "limit exceeded ({})", limit);
+        }
+
+        match self.name() {
+            Some(name) if name.namespace == ERR_NAMESPACE => write!(f, "err:{}", name.local_name),
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "unsupported"),
+        }
+    }
+}
+
+/// Error that occurs when applying an [Xpath](crate::xpath::Xpath) expression to an
+/// [XpathItemTree](crate::xpath::XpathItemTree).
+///
+/// The [ErrorCode] tells apart errors in the expression itself from errors caused by the document,
+/// and the failing sub-expression shows where in the expression the error was raised.
+///
+/// # Example
+///
+/// ```rust
+/// use skyscraper::html;
+/// use skyscraper::xpath::{self, ErrorCode, XpathItemTree};
+///
+/// let document = html::parse("<html><div>1</div><div>2</div></html>").unwrap();
+/// let tree = XpathItemTree::from(&document);
+///
+/// let xpath = xpath::parse("//div[ contains( //div , '1' ) ]").unwrap();
+/// let err = xpath.apply(&tree).unwrap_err();
+///
+/// assert_eq!(err.code(), &ErrorCode::XPTY0004);
+/// assert!(err.code().is_type_error());
+/// assert_eq!(err.expression(), Some("contains(//div, '1')"));
+/// assert_eq!(err.span(), Some(7..30));
+/// ```
+#[derive(PartialEq, Debug, Clone)]
+pub struct ExpressionApplyError {
+    code: ErrorCode,
+    msg: String,
+    expression: Option<String>,
+    location: Option<Location>,
+}
+
+/// Where [ExpressionApplyError::expression] is in the expression.
+#[derive(PartialEq, Debug, Clone)]
+enum Location {
+    /// The address of the sub-expression's syntax tree node,
+    /// which tells it apart from other sub-expressions with the same text until the error is located.
+    Node(usize),
+
+    /// The range of the sub-expression in the text of the expression.
+    Span(Range<usize>),
+}
+
+impl ExpressionApplyError {
+    /// Create a new [ExpressionApplyError] with the given message and the code `err:FOER0000`.
+    ///
+    /// Extension functions can use this to report errors.
+    pub fn new(msg: String) -> Self {
+        Self::with_code(ErrorCode::FOER0000, msg)
+    }
+
+    /// Create a new [ExpressionApplyError] with the given code and message.
+    pub fn with_code(code: ErrorCode, msg: String) -> Self {
+        Self {
+            code,
+            msg,
+            expression: None,
+            location: None,
+        }
+    }
+
+    /// Create an error for a construct that cannot be evaluated yet.
+    pub(crate) fn unsupported(construct: &str, expression: &impl Display) -> Self {
+        Self::with_code(
+            ErrorCode::Unsupported,
+            format!("{} is not supported", construct),
+        )
+        .in_expression(expression)
+    }
+
+    /// Record the sub-expression that raised the error.
+    ///
+    /// Errors keep the innermost sub-expression they were raised in,
+    /// so this does nothing if the error already has one.
+    pub(crate) fn in_expression<T: Display + ?Sized>(mut self, expression: &T) -> Self {
+        if self.expression.is_none() {
+            self.expression = Some(expression.to_string());
+            self.location = Some(Location::Node(expression as *const T as *const () as usize));
+        }
+        self
+    }
+
+    /// Locate the failing sub-expression in the given expression, which it was raised in.
+    pub(crate) fn locate_in(mut self, xpath: &Xpath) -> Self {
+        let node = match &self.location {
+            Some(Location::Span(_)) => return self,
+            Some(Location::Node(node)) => Some(*node),
+            None => None,
+        };
+
+        if let Some(expression) = &self.expression {
+            self.location = span::locate(xpath, node, expression).map(Location::Span);
+        }
+        self
+    }
+
+    /// The code identifying the error.
+    pub fn code(&self) -> &ErrorCode {
+        &self.code
+    }
+
+    /// The description of the error.
+    pub fn message(&self) -> &str {
+        &self.msg
+    }
+
+    /// The sub-expression that raised the error, if known.
+    pub fn expression(&self) -> Option<&str> {
+        self.expression.as_deref()
+    }
+
+    /// The byte range of [ExpressionApplyError::expression] in the text the expression was parsed from.
+    ///
+    /// For an expression that was built, rewritten or deserialized rather than parsed,
+    /// the range is in the expression as formatted by [Xpath]'s `Display`.
+    pub fn span(&self) -> Option<Range<usize>> {
+        match &self.location {
+            Some(Location::Span(span)) => Some(span.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl Display for ExpressionApplyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.code {
+            // The message of an unsupported feature already says so.
+            ErrorCode::Unsupported => write!(f, "Error applying expression {}", self.msg)?,
+            _ => write!(f, "Error applying expression {} {}", self.code, self.msg)?,
+        }
+
+        if let Some(expression) = &self.expression {
+            write!(f, " in `{}`", expression)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ExpressionApplyError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::xpath::grammar::expressions::xpath;
+
+    use super::*;
+
+    #[test]
+    fn error_code_should_round_trip_through_name() {
+        // arrange
+        let code = ErrorCode::FORG0001;
+
+        // act
+        let name = code.name().unwrap();
+
+        // assert
+        assert_eq!(ErrorCode::from_name(name), ErrorCode::FORG0001);
+    }
+
+    #[test]
+    fn error_code_should_keep_unknown_names() {
+        // arrange
+        let name = ExpandedName::new("http://example.com", "oops");
+
+        // act
+        let code = ErrorCode::from_name(name.clone());
+
+        // assert
+        assert_eq!(code, ErrorCode::Other(name));
+        assert_eq!(code.to_string(), "Q{http://example.com}oops");
+        assert!(code.is_dynamic_error());
+    }
+
+    #[test]
+    fn error_code_should_classify_errors() {
+        // assert
+        assert!(ErrorCode::XPST0017.is_static_error());
+        assert!(!ErrorCode::XPST0017.is_dynamic_error());
+        assert!(ErrorCode::XPTY0004.is_type_error());
+        assert!(ErrorCode::FOAR0001.is_dynamic_error());
+        assert!(!ErrorCode::Unsupported.is_dynamic_error());
+        assert!(!ErrorCode::LimitExceeded(EvaluationLimit::Deadline).is_dynamic_error());
+    }
+
+    #[test]
+    fn apply_error_should_keep_innermost_expression() {
+        // arrange
+        let err = ExpressionApplyError::with_code(ErrorCode::XPTY0004, String::from("bad"));
+
+        // act
+        let err = err
+            .in_expression(&"f(1)")
+            .in_expression(&"g(f(1))")
+            .locate_in(&xpath("//a[g(f(1))]").unwrap().1);
+
+        // assert
+        assert_eq!(err.expression(), Some("f(1)"));
+        assert_eq!(err.span(), Some(6..10));
+        assert_eq!(
+            err.to_string(),
+            "Error applying expression err:XPTY0004 bad in `f(1)`"
+        );
+    }
+}
@@ -0,0 +1,447 @@
+//! Build [Xpath] expressions from code instead of parsing them from strings.
+//!
+//! Start a path with [Xpath::root], [Xpath::child] or [Xpath::descendant],
+//! add steps and predicates, and finish it with [XpathBuilder::build].
+//! Names and values are passed as plain strings, so values with quotes in them
+//! do not need to be escaped.
+//!
+//! The built expression is the same as the one [parse](crate::xpath::parse) returns
+//! for its [Display](std::fmt::Display) string, and is checked against a
+//! [StaticContext] in the same way.
+//!
+//! # Example: find links in items
+//!
+//! ```rust
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! use skyscraper::html;
+//! use skyscraper::xpath::{Xpath, XpathItemTree};
+//!
+//! let document = html::parse(r#"
+//!     <html>
+//!         <div class="item"><a href="https://example.com">Example</a></div>
+//!         <div class="other"><a href="https://example.org">Other</a></div>
+//!     </html>"#)?;
+//! let tree = XpathItemTree::from(&document);
+//!
+//! let xpath = Xpath::descendant("div")
+//!     .attr_eq("class", "item")
+//!     .child("a")
+//!     .attr("href")
+//!     .build()?;
+//!
+//! assert_eq!(xpath.to_string(), "//div[@class='item']/a/@href");
+//!
+//! let items = xpath.apply(&tree)?;
+//! assert_eq!(items.len(), 1);
+//! assert_eq!(items[0].string_value(&tree), "https://example.com");
+//! # Ok(())
+//! # }
+//! ```
+
+use nom::combinator::all_consuming;
+
+use crate::xpath::{
+    context::DEFAULT_DYNAMIC_CONTEXT,
+    grammar::{
+        expressions::{
+            arithmetic_expressions::{AdditiveExpr, MultiplicativeExpr, UnaryExpr, ValueExpr},
+            arrow_operator::ArrowExpr,
+            common::{Argument, ArgumentList},
+            comparison_expressions::{
+                ComparisonExpr, ComparisonExprPair, ComparisonType, GeneralComp,
+            },
+            expressions_on_sequence_types::{
+                cast::CastExpr, castable::CastableExpr, instance_of::InstanceofExpr,
+                treat::TreatExpr,
+            },
+            logical_expressions::{AndExpr, OrExpr},
+            path_expressions::{
+                abbreviated_syntax::AbbrevForwardStep,
+                steps::{
+                    axis_step::{AxisStep, AxisStepType},
+                    forward_step::ForwardStep,
+                    node_tests::{name_test, NodeTest},
+                    reverse_step::ReverseStep,
+                    step_expr::StepExpr,
+                },
+                PathExpr, PathSeparator, RelativePathExpr, StepPair,
+            },
+            postfix_expressions::{PostfixExpr, Predicate},
+            primary_expressions::{
+                literals::{Literal, NumericLiteral},
+                static_function_calls::FunctionCall,
+                PrimaryExpr,
+            },
+            sequence_expressions::{
+                combining_node_sequences::{IntersectExceptExpr, UnionExpr},
+                constructing_sequences::RangeExpr,
+            },
+            simple_map_operator::SimpleMapExpr,
+            string_concat_expressions::StringConcatExpr,
+            Expr, ExprSingle,
+        },
+        terminal_symbols::StringLiteral,
+        types::{EQName, KindTest},
+        xml_names::QName,
+    },
+    static_analysis, ErrorCode, ExpressionParseError, StaticContext, Xpath,
+};
+
+impl Xpath {
+    /// Start a path at the root of the document, written `/`.
+    pub fn root() -> XpathBuilder {
+        XpathBuilder {
+            absolute: true,
+            steps: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Start a path with the children of the context item that have the given name,
+    /// written `name`.
+    ///
+    /// The name may be a `*` wildcard, a prefixed name or a `Q{uri}name`.
+    pub fn child(name: &str) -> XpathBuilder {
+        XpathBuilder {
+            absolute: false,
+            steps: Vec::new(),
+            error: None,
+        }
+        .child(name)
+    }
+
+    /// Start a path with the elements in the document that have the given name,
+    /// written `//name`.
+    pub fn descendant(name: &str) -> XpathBuilder {
+        Xpath::root().descendant(name)
+    }
+}
+
+/// A path expression under construction.
+///
+/// See the [module documentation](self).
+#[derive(PartialEq, Debug, Clone)]
+pub struct XpathBuilder {
+    /// Whether the path starts at the root of the document.
+    absolute: bool,
+
+    /// The steps of the path, each with the separator written before it.
+    steps: Vec<(PathSeparator, AxisStep)>,
+
+    /// The first invalid name or predicate, reported by [XpathBuilder::build].
+    error: Option<ExpressionParseError>,
+}
+
+impl XpathBuilder {
+    /// Add a step that selects the children with the given name, written `/name`.
+    pub fn child(self, name: &str) -> Self {
+        self.name_step(PathSeparator::Slash, false, name)
+    }
+
+    /// Add a step that selects the descendants with the given name, written `//name`.
+    pub fn descendant(self, name: &str) -> Self {
+        self.name_step(PathSeparator::DoubleSlash, false, name)
+    }
+
+    /// Add a step that selects the attribute with the given name, written `/@name`.
+    pub fn attr(self, name: &str) -> Self {
+        self.name_step(PathSeparator::Slash, true, name)
+    }
+
+    /// Add a step that selects the child text nodes, written `/text()`.
+    pub fn text(self) -> Self {
+        let step = abbreviated_step(false, NodeTest::KindTest(KindTest::TextTest));
+        self.step(PathSeparator::Slash, step)
+    }
+
+    /// Add a step that selects the parent, written `/..`.
+    pub fn parent(self) -> Self {
+        let step = AxisStep {
+            step_type: AxisStepType::ReverseStep(ReverseStep::Abbreviated),
+            predicates: Vec::new(),
+        };
+        self.step(PathSeparator::Slash, step)
+    }
+
+    /// Keep the items of the last step that have the attribute, written `[@name]`.
+    pub fn has_attr(self, name: &str) -> Self {
+        match attribute_path(name) {
+            Ok(attribute) => self.predicate(expr(comparison(attribute, None))),
+            Err(e) => self.fail(e),
+        }
+    }
+
+    /// Keep the items of the last step whose attribute equals the value,
+    /// written `[@name='value']`.
+    pub fn attr_eq(self, name: &str, value: &str) -> Self {
+        match attribute_path(name) {
+            Ok(attribute) => {
+                let value = ComparisonExprPair(
+                    ComparisonType::GeneralComp(GeneralComp::Equal),
+                    string_concat(string_literal(value)),
+                );
+                self.predicate(expr(comparison(attribute, Some(value))))
+            }
+            Err(e) => self.fail(e),
+        }
+    }
+
+    /// Keep the items of the last step whose attribute contains the value,
+    /// written `[contains(@name, 'value')]`.
+    pub fn attr_contains(self, name: &str, value: &str) -> Self {
+        match attribute_path(name) {
+            Ok(attribute) => self.predicate(contains(attribute, value)),
+            Err(e) => self.fail(e),
+        }
+    }
+
+    /// Keep the items of the last step whose text equals the value, written `[.='value']`.
+    pub fn text_eq(self, value: &str) -> Self {
+        let value = ComparisonExprPair(
+            ComparisonType::GeneralComp(GeneralComp::Equal),
+            string_concat(string_literal(value)),
+        );
+        self.predicate(expr(comparison(context_item(), Some(value))))
+    }
+
+    /// Keep the items of the last step whose text contains the value,
+    /// written `[contains(., 'value')]`.
+    pub fn text_contains(self, value: &str) -> Self {
+        self.predicate(contains(context_item(), value))
+    }
+
+    /// Keep the item of the last step at the 1-based position, written `[position]`.
+    pub fn nth(self, position: u32) -> Self {
+        let position = primary_path(PrimaryExpr::Literal(Literal::NumericLiteral(
+            NumericLiteral::Integer(position.into()),
+        )));
+        self.predicate(expr(comparison(position, None)))
+    }
+
+    /// Keep the items of the last step for which an expression is true, written `[expression]`.
+    ///
+    /// The expression is evaluated with each item as the context item,
+    /// so relative paths such as `Xpath::child("span")` start at the item.
+    pub fn filter(self, expression: Xpath) -> Self {
+        self.predicate(expression.0)
+    }
+
+    /// Finish the path, checking it against the default [StaticContext].
+    ///
+    /// Returns an error for invalid names, predicates with no step to apply to,
+    /// and features that [parse](crate::xpath::parse) would also reject.
+    pub fn build(self) -> Result<Xpath, ExpressionParseError> {
+        self.build_with_context(DEFAULT_DYNAMIC_CONTEXT.static_context())
+    }
+
+    /// Finish the path, checking it against the given [StaticContext].
+    ///
+    /// See [parse_with_context](crate::xpath::parse_with_context).
+    pub fn build_with_context(
+        self,
+        static_context: &StaticContext,
+    ) -> Result<Xpath, ExpressionParseError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        let mut steps = self
+            .steps
+            .into_iter()
+            .map(|(separator, step)| (separator, StepExpr::AxisStep(step)));
+
+        let path = match steps.next() {
+            Some((separator, first)) => {
+                let relative = RelativePathExpr {
+                    expr: first,
+                    items: steps
+                        .map(|(separator, step)| StepPair(separator, step))
+                        .collect(),
+                };
+
+                match (self.absolute, separator) {
+                    (true, PathSeparator::Slash) => PathExpr::LeadingSlash(Some(relative)),
+                    (true, PathSeparator::DoubleSlash) => PathExpr::LeadingDoubleSlash(relative),
+                    (false, _) => PathExpr::Plain(relative),
+                }
+            }
+            // Only Xpath::root creates a builder without steps.
+            None => PathExpr::LeadingSlash(None),
+        };
+
+        let xpath = Xpath(expr(comparison(path, None)), None);
+        static_analysis::analyze(&xpath, static_context)?;
+
+        Ok(xpath)
+    }
+
+    fn name_step(self, separator: PathSeparator, has_at: bool, name: &str) -> Self {
+        match parse_name_test(name) {
+            Ok(node_test) => self.step(separator, abbreviated_step(has_at, node_test)),
+            Err(e) => self.fail(e),
+        }
+    }
+
+    fn step(mut self, separator: PathSeparator, step: AxisStep) -> Self {
+        self.steps.push((separator, step));
+        self
+    }
+
+    fn predicate(mut self, predicate: Expr) -> Self {
+        match self.steps.last_mut() {
+            Some((_, step)) => step.predicates.push(Predicate(predicate)),
+            None => {
+                let error = ExpressionParseError::new(
+                    ErrorCode::XPST0003,
+                    format!("predicate [{}] has no step to apply to", predicate),
+                );
+                return self.fail(error);
+            }
+        }
+
+        self
+    }
+
+    fn fail(mut self, error: ExpressionParseError) -> Self {
+        self.error.get_or_insert(error);
+        self
+    }
+}
+
+fn parse_name_test(name: &str) -> Result<NodeTest, ExpressionParseError> {
+    match all_consuming(name_test)(name) {
+        Ok((_, name_test)) => Ok(NodeTest::NameTest(name_test)),
+        Err(_) => Err(ExpressionParseError::new(
+            ErrorCode::XPST0003,
+            format!("{:?} is not a valid name", name),
+        )),
+    }
+}
+
+fn abbreviated_step(has_at: bool, node_test: NodeTest) -> AxisStep {
+    AxisStep {
+        step_type: AxisStepType::ForwardStep(ForwardStep::Abbreviated(AbbrevForwardStep {
+            has_at,
+            node_test,
+        })),
+        predicates: Vec::new(),
+    }
+}
+
+/// The relative path `@name`.
+fn attribute_path(name: &str) -> Result<PathExpr, ExpressionParseError> {
+    let step = abbreviated_step(true, parse_name_test(name)?);
+    Ok(PathExpr::Plain(RelativePathExpr {
+        expr: StepExpr::AxisStep(step),
+        items: Vec::new(),
+    }))
+}
+
+/// The context item expression `.`.
+fn context_item() -> PathExpr {
+    primary_path(PrimaryExpr::ContextItemExpr)
+}
+
+fn string_literal(value: &str) -> PathExpr {
+    primary_path(PrimaryExpr::Literal(Literal::StringLiteral(
+        StringLiteral::new(value),
+    )))
+}
+
+/// The function call `contains(haystack, 'needle')`.
+fn contains(haystack: PathExpr, needle: &str) -> Expr {
+    let argument = |path| Argument::ExprSingle(expr_single(comparison(path, None)));
+    let call = FunctionCall {
+        name: EQName::QName(QName::UnprefixedName(String::from("contains"))),
+        argument_list: ArgumentList(vec![argument(haystack), argument(string_literal(needle))]),
+    };
+
+    expr(comparison(
+        primary_path(PrimaryExpr::FunctionCall(call)),
+        None,
+    ))
+}
+
+pub(crate) fn primary_path(primary: PrimaryExpr) -> PathExpr {
+    PathExpr::Plain(RelativePathExpr {
+        expr: StepExpr::PostfixExpr(PostfixExpr {
+            expr: primary,
+            items: Vec::new(),
+        }),
+        items: Vec::new(),
+    })
+}
+
+pub(crate) fn comparison(path: PathExpr, comparison: Option<ComparisonExprPair>) -> ComparisonExpr {
+    ComparisonExpr {
+        expr: string_concat(path),
+        comparison,
+    }
+}
+
+/// Wrap a path in the expressions of every precedence level between it and a comparison.
+pub(crate) fn string_concat(path: PathExpr) -> StringConcatExpr {
+    let unary = UnaryExpr {
+        leading_symbols: Vec::new(),
+        expr: ValueExpr(SimpleMapExpr {
+            expr: path,
+            items: Vec::new(),
+        }),
+    };
+    let cast = CastExpr {
+        expr: ArrowExpr {
+            expr: unary,
+            items: Vec::new(),
+        },
+        cast: None,
+    };
+    let instance_of = InstanceofExpr {
+        expr: TreatExpr {
+            expr: CastableExpr {
+                expr: cast,
+                cast_type: None,
+            },
+            treat_type: None,
+        },
+        instanceof_type: None,
+    };
+    let multiplicative = MultiplicativeExpr {
+        expr: UnionExpr {
+            expr: IntersectExceptExpr {
+                expr: instance_of,
+                items: Vec::new(),
+            },
+            items: Vec::new(),
+        },
+        items: Vec::new(),
+    };
+
+    StringConcatExpr {
+        expr: RangeExpr {
+            expr: Box::new(AdditiveExpr {
+                expr: multiplicative,
+                items: Vec::new(),
+            }),
+            to_expr: None,
+        },
+        items: Vec::new(),
+    }
+}
+
+pub(crate) fn expr_single(comparison: ComparisonExpr) -> ExprSingle {
+    ExprSingle::OrExpr(Box::new(OrExpr {
+        expr: AndExpr {
+            expr: comparison,
+            items: Vec::new(),
+        },
+        items: Vec::new(),
+    }))
+}
+
+pub(crate) fn expr(comparison: ComparisonExpr) -> Expr {
+    Expr {
+        expr: expr_single(comparison),
+        items: Vec::new(),
+    }
+}
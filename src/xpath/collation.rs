@@ -0,0 +1,133 @@
+//! Collations decide when two strings are equal, and how strings are ordered.
+//!
+//! <https://www.w3.org/TR/xpath-functions-31/#string-compare>
+//!
+//! Collations are used by general comparisons such as `=`, by `fn:contains`, `fn:starts-with`,
+//! `fn:ends-with` and `fn:compare`, and by `fn:distinct-values` and `fn:sort`.
+//! The functions take an optional collation URI; everything else uses the default collation
+//! of the [StaticContext](crate::xpath::StaticContext).
+//!
+//! Two collations are always available:
+//! the [CODEPOINT_COLLATION], which is the default,
+//! and the [HTML_ASCII_CASE_INSENSITIVE_COLLATION].
+//! Register other collations with
+//! [StaticContextBuilder::with_collation](crate::xpath::context::StaticContextBuilder::with_collation).
+//!
+//! # Example: match class names regardless of case
+//!
+//! ```rust
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! use skyscraper::html;
+//! use skyscraper::xpath::{
+//!     self,
+//!     collation::HTML_ASCII_CASE_INSENSITIVE_COLLATION,
+//!     context::{DynamicContext, StaticContextBuilder},
+//!     XpathItemTree,
+//! };
+//!
+//! let static_context = StaticContextBuilder::new()
+//!     .with_default_collation(HTML_ASCII_CASE_INSENSITIVE_COLLATION)
+//!     .build();
+//! let context = DynamicContext::new(static_context);
+//!
+//! let document = html::parse(r#"<html><div class="Item">A</div></html>"#)?;
+//! let tree = XpathItemTree::from(&document);
+//!
+//! let xpath = xpath::parse_with_context("//div[@class='item']", context.static_context())?;
+//! let items = xpath.apply_with_context(&tree, &context)?;
+//!
+//! assert_eq!(items.len(), 1);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{borrow::Cow, cmp::Ordering};
+
+/// The Unicode codepoint collation, which compares strings character by character.
+///
+/// <https://www.w3.org/TR/xpath-functions-31/#codepoint-collation>
+pub const CODEPOINT_COLLATION: &str = "http://www.w3.org/2005/xpath-functions/collation/codepoint";
+
+/// The HTML ASCII case-insensitive collation, which ignores the case of ASCII letters.
+///
+/// <https://www.w3.org/TR/xpath-functions-31/#html-ascii-case-insensitive-collation>
+pub const HTML_ASCII_CASE_INSENSITIVE_COLLATION: &str =
+    "http://www.w3.org/2005/xpath-functions/collation/html-ascii-case-insensitive";
+
+/// A way of comparing strings.
+///
+/// Only [Collation::key] must be implemented.
+pub trait Collation: Send + Sync {
+    /// Get the collation key of a string.
+    ///
+    /// Two strings are equal under the collation when their keys are equal,
+    /// and substrings are found by searching for the key of one string in the key of the other.
+    fn key<'a>(&self, s: &'a str) -> Cow<'a, str>;
+
+    /// Compare two strings.
+    ///
+    /// By default, the keys of the strings are compared character by character.
+    fn compare(&self, first: &str, second: &str) -> Ordering {
+        self.key(first).cmp(&self.key(second))
+    }
+}
+
+/// See [CODEPOINT_COLLATION].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CodepointCollation;
+
+impl Collation for CodepointCollation {
+    fn key<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(s)
+    }
+
+    fn compare(&self, first: &str, second: &str) -> Ordering {
+        first.cmp(second)
+    }
+}
+
+/// See [HTML_ASCII_CASE_INSENSITIVE_COLLATION].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HtmlAsciiCaseInsensitiveCollation;
+
+impl Collation for HtmlAsciiCaseInsensitiveCollation {
+    fn key<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        if s.bytes().any(|b| b.is_ascii_uppercase()) {
+            Cow::Owned(s.to_ascii_lowercase())
+        } else {
+            Cow::Borrowed(s)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_ascii_case_insensitive_collation_should_ignore_ascii_case() {
+        // arrange
+        let collation = HtmlAsciiCaseInsensitiveCollation;
+
+        // act
+        let ascii = collation.compare("Item", "iTEM");
+        let non_ascii = collation.compare("É", "é");
+
+        // assert
+        assert_eq!(ascii, Ordering::Equal);
+        assert_ne!(non_ascii, Ordering::Equal);
+    }
+
+    #[test]
+    fn html_ascii_case_insensitive_collation_should_order_by_lower_case() {
+        // arrange
+        let collation = HtmlAsciiCaseInsensitiveCollation;
+
+        // act
+        let ordering = collation.compare("b", "A");
+
+        // assert
+        assert_eq!(ordering, Ordering::Greater);
+    }
+}
@@ -0,0 +1,1451 @@
+//! Compile an [Xpath] expression to a plan that is faster to apply than its syntax tree.
+//!
+//! The syntax tree has a layer for every level of operator precedence in the grammar,
+//! so evaluating a simple path such as `//div[@id='main']/a` dispatches through more than a dozen
+//! layers that each pass their single operand through.
+//! [Xpath::compile] flattens the tree into a plan that only keeps the layers that do something:
+//! paths, steps, predicates, comparisons, function calls and literals.
+//! Function calls are resolved and literals are converted once, when the plan is compiled,
+//! and leading and double slashes are expanded without building new syntax trees.
+//! Axis steps with a name test, `*`, `node()` or `text()` select their nodes by walking the tree,
+//! and a `//` followed by a child step walks the descendants once for both steps
+//! unless the step's predicates depend on the position of the nodes, as in `//li[1]`.
+//!
+//! [Xpath::apply] compiles the expression each time it is called.
+//! Compile it once with [Xpath::compile] to apply it to many trees.
+//! Constructs the plan does not handle are evaluated from their syntax tree,
+//! so every expression can be compiled and gives the same result either way.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! use skyscraper::html;
+//! use skyscraper::xpath::{self, XpathItemTree};
+//!
+//! let pages = [
+//!     r#"<html><a class="next" href="/2">Next</a></html>"#,
+//!     r#"<html><a class="next" href="/3">Next</a></html>"#,
+//! ];
+//!
+//! let xpath = xpath::parse("//a[@class='next']/@href")?;
+//! let compiled = xpath.compile();
+//!
+//! for page in pages {
+//!     let tree = XpathItemTree::from(&html::parse(page)?);
+//!     let items = compiled.apply(&tree)?;
+//!     assert_eq!(items.len(), 1);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::cell::Cell;
+
+use crate::{
+    xpath::{
+        context::DEFAULT_DYNAMIC_CONTEXT,
+        explain::TraceId,
+        functions::{FunctionImplementation, FN_NAMESPACE},
+        grammar::{
+            data_model::{AnyAtomicType, XpathItem},
+            expressions::{
+                arithmetic_expressions::{AdditiveExpr, MultiplicativeExpr, UnaryExpr},
+                arrow_operator::ArrowExpr,
+                common::Argument,
+                comparison_expressions::{ComparisonExpr, ComparisonType},
+                expressions_on_sequence_types::{
+                    cast::CastExpr, castable::CastableExpr, instance_of::InstanceofExpr,
+                    treat::TreatExpr,
+                },
+                logical_expressions::{AndExpr, OrExpr},
+                maps_and_arrays::lookup_operator::postfix_lookup::Lookup,
+                path_expressions::{
+                    steps::{
+                        axes::forward_axis::ForwardAxis,
+                        axis_step::AxisStepType,
+                        forward_step::ForwardStep,
+                        node_tests::{NameTest, NodeTest, Wildcard},
+                        step_expr::StepExpr,
+                    },
+                    PathExpr, PathSeparator, RelativePathExpr, DESCENDANT_OR_SELF_STEP, ROOT_STEP,
+                },
+                postfix_expressions::{
+                    predicate_truth_value, PostfixExpr, PostfixExprItem, Predicate,
+                },
+                primary_expressions::{
+                    named_function_references::NamedFunctionRef,
+                    static_function_calls::FunctionCall, PrimaryExpr,
+                },
+                sequence_expressions::{
+                    combining_node_sequences::{IntersectExceptExpr, UnionExpr},
+                    constructing_sequences::RangeExpr,
+                },
+                simple_map_operator::SimpleMapExpr,
+                string_concat_expressions::StringConcatExpr,
+                Expr, ExprSingle,
+            },
+            types::{EQName, KindTest},
+            xml_names::QName,
+            XpathItemTreeNode,
+        },
+        limits::EvaluationBudget,
+        planner::IndexedPath,
+        visit::{self, Visitor},
+        DynamicContext, ErrorCode, ExpressionApplyError, StaticContext, Xpath,
+        XpathExpressionContext, XpathItemSet, XpathItemTree,
+    },
+    xpath_item_set,
+};
+
+impl Xpath {
+    /// Compile the expression to a plan that can be applied many times.
+    ///
+    /// Functions are resolved in the default [DynamicContext].
+    pub fn compile(&self) -> CompiledXpath<'_> {
+        self.compile_with_context(&DEFAULT_DYNAMIC_CONTEXT)
+    }
+
+    /// Compile the expression to a plan that is applied in the given [DynamicContext].
+    ///
+    /// Functions are resolved in the context's static context when the plan is compiled.
+    pub fn compile_with_context<'a>(
+        &'a self,
+        dynamic_context: &'a DynamicContext,
+    ) -> CompiledXpath<'a> {
+        self.compile_plan(dynamic_context, true)
+    }
+
+    /// Compile the expression to a plan that keeps every step,
+    /// so each one is recorded when explaining the expression.
+    pub(crate) fn compile_for_explain<'a>(
+        &'a self,
+        dynamic_context: &'a DynamicContext,
+    ) -> CompiledXpath<'a> {
+        self.compile_plan(dynamic_context, false)
+    }
+
+    fn compile_plan<'a>(
+        &'a self,
+        dynamic_context: &'a DynamicContext,
+        merges_steps: bool,
+    ) -> CompiledXpath<'a> {
+        let compiler = Compiler {
+            static_context: dynamic_context.static_context(),
+            trace_ids: Cell::new(0),
+            merges_steps,
+        };
+
+        CompiledXpath {
+            xpath: self,
+            dynamic_context,
+            operator: compiler.expr(&self.0),
+        }
+    }
+}
+
+/// An [Xpath] expression compiled to a plan of operators.
+///
+/// Created by [Xpath::compile].
+pub struct CompiledXpath<'a> {
+    xpath: &'a Xpath,
+    dynamic_context: &'a DynamicContext,
+    operator: Operator<'a>,
+}
+
+impl<'a> CompiledXpath<'a> {
+    /// The expression that was compiled.
+    pub fn xpath(&self) -> &'a Xpath {
+        self.xpath
+    }
+
+    /// Apply the compiled expression to the given item tree.
+    ///
+    /// The result is the same as [Xpath::apply_with_context] in the context the expression was compiled in.
+    pub fn apply<'tree>(
+        &self,
+        item_tree: &'tree XpathItemTree,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        let budget = EvaluationBudget::new(self.dynamic_context.limits());
+        let context = XpathExpressionContext::new_single(
+            item_tree,
+            self.dynamic_context,
+            &budget,
+            None,
+            XpathItem::Node(item_tree.root()),
+            true,
+        );
+        let mut item_set = self.eval(&context)?;
+        item_set.sort();
+        Ok(item_set)
+    }
+
+    /// Apply the compiled expression to the given item.
+    /// The expression will be evaluated relative to the given item.
+    ///
+    /// The result is the same as [Xpath::apply_to_item_with_context] in the context the expression was compiled in.
+    pub fn apply_to_item<'tree>(
+        &self,
+        item_tree: &'tree XpathItemTree,
+        item: XpathItem<'tree>,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        let budget = EvaluationBudget::new(self.dynamic_context.limits());
+        let context = XpathExpressionContext::new_single(
+            item_tree,
+            self.dynamic_context,
+            &budget,
+            None,
+            item,
+            false,
+        );
+        self.eval(&context)
+    }
+
+    pub(crate) fn eval<'tree>(
+        &self,
+        context: &XpathExpressionContext<'tree, '_>,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        self.operator
+            .eval(context)
+            .and_then(|items| {
+                context.budget.check_result_size(items.len())?;
+                Ok(items)
+            })
+            .map_err(|e| e.locate_in(self.xpath))
+    }
+}
+
+/// Builds the operators of a plan.
+struct Compiler<'a> {
+    static_context: &'a StaticContext,
+
+    /// The number of steps and predicates compiled so far,
+    /// which numbers them in the order they are written.
+    trace_ids: Cell<usize>,
+
+    /// Whether a `//` is merged into the child step after it.
+    merges_steps: bool,
+}
+
+impl<'a> Compiler<'a> {
+    fn trace_id(&self) -> TraceId {
+        let id = self.trace_ids.get();
+        self.trace_ids.set(id + 1);
+        TraceId::Plan(id)
+    }
+
+    fn expr(&self, expr: &'a Expr) -> Operator<'a> {
+        let mut operators = vec![self.expr_single(&expr.expr)];
+        operators.extend(expr.items.iter().map(|item| self.expr_single(item)));
+        Operator::Sequence(operators)
+    }
+
+    fn expr_single(&self, expr: &'a ExprSingle) -> Operator<'a> {
+        let expr = match expr {
+            ExprSingle::OrExpr(expr) => expr,
+            _ => return Operator::Tree(expr),
+        };
+
+        // Each layer below passes its operand through unless it has an operator.
+        if !expr.items.is_empty() {
+            return Operator::Tree(expr.as_ref());
+        }
+
+        let expr = &expr.expr;
+        if !expr.items.is_empty() {
+            return Operator::Tree(expr);
+        }
+
+        self.comparison(&expr.expr)
+    }
+
+    fn comparison(&self, expr: &'a ComparisonExpr) -> Operator<'a> {
+        match &expr.comparison {
+            Some(comparison) => Operator::Comparison {
+                expr,
+                comparison_type: &comparison.0,
+                left: Box::new(self.string_concat(&expr.expr)),
+                right: Box::new(self.string_concat(&comparison.1)),
+            },
+            None => self.string_concat(&expr.expr),
+        }
+    }
+
+    fn string_concat(&self, expr: &'a StringConcatExpr) -> Operator<'a> {
+        if !expr.items.is_empty() {
+            return Operator::Tree(expr);
+        }
+
+        let expr = &expr.expr;
+        if expr.to_expr.is_some() {
+            return Operator::Tree(expr);
+        }
+
+        let expr = expr.expr.as_ref();
+        if !expr.items.is_empty() {
+            return Operator::Tree(expr);
+        }
+
+        let expr = &expr.expr;
+        if !expr.items.is_empty() {
+            return Operator::Tree(expr);
+        }
+
+        let expr = &expr.expr;
+        if !expr.items.is_empty() {
+            return Operator::Tree(expr);
+        }
+
+        let expr = &expr.expr;
+        if !expr.items.is_empty() {
+            return Operator::Tree(expr);
+        }
+
+        let expr = &expr.expr;
+        if expr.instanceof_type.is_some() {
+            return Operator::Tree(expr);
+        }
+
+        let expr = &expr.expr;
+        if expr.treat_type.is_some() {
+            return Operator::Tree(expr);
+        }
+
+        let expr = &expr.expr;
+        if expr.cast_type.is_some() {
+            return Operator::Tree(expr);
+        }
+
+        let expr = &expr.expr;
+        if expr.cast.is_some() {
+            return Operator::Tree(expr);
+        }
+
+        let expr = &expr.expr;
+        if !expr.items.is_empty() {
+            return Operator::Tree(expr);
+        }
+
+        let expr = &expr.expr;
+        if !expr.leading_symbols.is_empty() {
+            return Operator::Tree(expr);
+        }
+
+        let expr = &expr.expr.0;
+        if !expr.items.is_empty() {
+            return Operator::Tree(expr);
+        }
+
+        self.path(&expr.expr)
+    }
+
+    fn path(&self, expr: &'a PathExpr) -> Operator<'a> {
+        let path = match expr {
+            PathExpr::LeadingSlash(expr) => Path {
+                start: PathStart::Root,
+                steps: expr
+                    .as_ref()
+                    .map(|expr| self.steps(expr))
+                    .unwrap_or_default(),
+            },
+            PathExpr::LeadingDoubleSlash(expr) => {
+                let mut steps = vec![self.step(&DESCENDANT_OR_SELF_STEP)];
+                steps.extend(self.steps(expr));
+                Path {
+                    start: PathStart::RootDescendants(expr),
+                    steps,
+                }
+            }
+            PathExpr::Plain(expr) => {
+                let mut steps = self.steps(expr);
+
+                // A path of one step is just that step, unless the step is traced.
+                if steps.len() == 1 && !steps[0].is_traced {
+                    return steps.pop().expect("path has one step").operator;
+                }
+
+                Path {
+                    start: PathStart::Context,
+                    steps,
+                }
+            }
+        };
+
+        Operator::Path(path)
+    }
+
+    /// Compile the steps of a relative path, expanding each `//` to a `descendant-or-self::node()` step.
+    ///
+    /// A `//` followed by a child step is merged into that step where it selects the same nodes,
+    /// so the descendants are walked once instead of selecting the children of each of them in turn.
+    fn steps(&self, expr: &'a RelativePathExpr) -> Vec<Step<'a>> {
+        let mut steps = vec![self.step(&expr.expr)];
+        for pair in expr.items.iter() {
+            if pair.0 == PathSeparator::DoubleSlash {
+                let descendants = self.step(&DESCENDANT_OR_SELF_STEP);
+                let mut step = self.step(&pair.1);
+                if !self.merges_steps || !self.merge_descendants(&mut step) {
+                    steps.push(descendants);
+                }
+
+                steps.push(step);
+                continue;
+            }
+
+            steps.push(self.step(&pair.1));
+        }
+
+        steps
+    }
+
+    /// Make a child step select the children of the context node and of each of its descendants,
+    /// which is what a `//` before it does.
+    ///
+    /// Returns `false` if the step cannot be merged.
+    /// Its predicates must not depend on the position of the nodes,
+    /// since the merged step gives positions among all the selected nodes
+    /// instead of among the children of each node, e.g. in `//li[1]`.
+    fn merge_descendants(&self, step: &mut Step<'a>) -> bool {
+        let Operator::Axis {
+            selector: Some(selector),
+            predicates,
+            ..
+        } = &mut step.operator
+        else {
+            return false;
+        };
+
+        let is_mergeable = selector.axis == SelectorAxis::Child
+            && predicates
+                .iter()
+                .all(|predicate| self.ignores_position(predicate));
+        if is_mergeable {
+            selector.axis = SelectorAxis::DescendantOrSelfChildren;
+            // A merged `//` selects the children of many nodes at once,
+            // so only the nodes that match the predicates are counted, as for separate steps.
+            selector.counts_selected = predicates.is_empty();
+        }
+
+        is_mergeable
+    }
+
+    /// Whether a predicate matches the same items whatever their position.
+    ///
+    /// This is only known if its value is a boolean or a sequence of nodes, never a number,
+    /// and it does not call `fn:position` or `fn:last`.
+    fn ignores_position(&self, predicate: &CompiledPredicate) -> bool {
+        let Operator::Sequence(operators) = &predicate.operator else {
+            return false;
+        };
+
+        let is_boolean_or_nodes = match operators.as_slice() {
+            [Operator::Comparison { .. }] | [Operator::Axis { .. }] => true,
+            [Operator::Path(path)] => path
+                .steps
+                .iter()
+                .all(|step| matches!(step.operator, Operator::Axis { .. })),
+            [Operator::FunctionCall { call, .. }] => self
+                .static_context
+                .resolve_function_name(&call.name)
+                .is_ok_and(|name| {
+                    self.static_context
+                        .functions()
+                        .is_boolean_built_in(&name, call.argument_list.0.len())
+                }),
+            _ => false,
+        };
+
+        is_boolean_or_nodes && !ContextPositionFinder::is_used_in(predicate.predicate, self)
+    }
+
+    fn step(&self, expr: &'a StepExpr) -> Step<'a> {
+        let trace_id = self.trace_id();
+        let operator = match expr {
+            StepExpr::AxisStep(step) => Operator::Axis {
+                step_type: &step.step_type,
+                selector: NodeSelector::new(&step.step_type),
+                predicates: step
+                    .predicates
+                    .iter()
+                    .map(|predicate| self.predicate(predicate))
+                    .collect(),
+            },
+            StepExpr::PostfixExpr(expr) => self.postfix(expr),
+        };
+
+        Step {
+            expr,
+            trace_id,
+            is_traced: expr.is_traced(),
+            operator,
+        }
+    }
+
+    fn predicate(&self, predicate: &'a Predicate) -> CompiledPredicate<'a> {
+        CompiledPredicate {
+            predicate,
+            trace_id: self.trace_id(),
+            operator: self.expr(&predicate.0),
+        }
+    }
+
+    fn postfix(&self, expr: &'a PostfixExpr) -> Operator<'a> {
+        if expr.items.is_empty() {
+            return self.primary(&expr.expr);
+        }
+
+        let mut items = Vec::new();
+        for item in expr.items.iter() {
+            match item {
+                PostfixExprItem::Predicate(predicate) => {
+                    items.push(FilterItem::Predicate(self.predicate(predicate)))
+                }
+                PostfixExprItem::Lookup(lookup) => items.push(FilterItem::Lookup(lookup)),
+                PostfixExprItem::ArgumentList(_) => return Operator::Tree(expr),
+            }
+        }
+
+        Operator::Filter {
+            base: Box::new(self.primary(&expr.expr)),
+            items,
+        }
+    }
+
+    fn primary(&self, expr: &'a PrimaryExpr) -> Operator<'a> {
+        match expr {
+            PrimaryExpr::Literal(literal) => Operator::Literal(literal.value()),
+            PrimaryExpr::ParenthesizedExpr(expr) => match &expr.0 {
+                Some(expr) => self.expr(expr),
+                None => Operator::Empty,
+            },
+            PrimaryExpr::ContextItemExpr => Operator::ContextItem,
+            PrimaryExpr::FunctionCall(call) => self.function_call(call),
+            _ => Operator::Tree(expr),
+        }
+    }
+
+    fn function_call(&self, call: &'a FunctionCall) -> Operator<'a> {
+        // Calls that fail to resolve are evaluated from the syntax tree, which reports the error.
+        let function = match self.static_context.resolve_function_name(&call.name) {
+            Ok(name) => self
+                .static_context
+                .functions()
+                .get(&name, call.argument_list.0.len()),
+            Err(_) => None,
+        };
+
+        let arguments: Option<Vec<Operator<'a>>> = call
+            .argument_list
+            .0
+            .iter()
+            .map(|argument| match argument {
+                Argument::ExprSingle(expr) => Some(self.expr_single(expr)),
+                Argument::ArgumentPlaceHolder => None,
+            })
+            .collect();
+
+        match (function, arguments) {
+            (Some(function), Some(arguments)) => Operator::FunctionCall {
+                call,
+                function,
+                arguments,
+            },
+            _ => Operator::Tree(call),
+        }
+    }
+}
+
+/// Finds the functions that depend on the context position and size, `fn:position` and `fn:last`.
+struct ContextPositionFinder<'a> {
+    static_context: &'a StaticContext,
+    is_found: bool,
+}
+
+impl<'a> ContextPositionFinder<'a> {
+    fn is_used_in(predicate: &Predicate, compiler: &Compiler<'a>) -> bool {
+        let mut finder = ContextPositionFinder {
+            static_context: compiler.static_context,
+            is_found: false,
+        };
+        finder.visit_predicate(predicate);
+        finder.is_found
+    }
+
+    fn check(&mut self, name: &EQName) {
+        // A name that does not resolve might be anything.
+        self.is_found |= self
+            .static_context
+            .resolve_function_name(name)
+            .map_or(true, |name| {
+                name.namespace == FN_NAMESPACE
+                    && matches!(name.local_name.as_str(), "position" | "last")
+            });
+    }
+}
+
+impl Visitor for ContextPositionFinder<'_> {
+    fn visit_function_call(&mut self, function_call: &FunctionCall) {
+        self.check(&function_call.name);
+        visit::walk_function_call(self, function_call);
+    }
+
+    fn visit_named_function_ref(&mut self, function_ref: &NamedFunctionRef) {
+        self.check(&function_ref.name);
+    }
+}
+
+/// An operation in a compiled plan.
+enum Operator<'a> {
+    /// The comma operator, which concatenates the results of its operands.
+    ///
+    /// Each operand is a nested sub-expression, counted towards the depth limit.
+    Sequence(Vec<Operator<'a>>),
+
+    /// A literal value.
+    Literal(AnyAtomicType),
+
+    /// The empty sequence, `()`.
+    Empty,
+
+    /// The context item, `.`.
+    ContextItem,
+
+    /// A call to a function that was resolved when compiling.
+    FunctionCall {
+        call: &'a FunctionCall,
+        function: &'a FunctionImplementation,
+        arguments: Vec<Operator<'a>>,
+    },
+
+    /// A comparison of two operands.
+    Comparison {
+        expr: &'a ComparisonExpr,
+        comparison_type: &'a ComparisonType,
+        left: Box<Operator<'a>>,
+        right: Box<Operator<'a>>,
+    },
+
+    /// A path of steps.
+    Path(Path<'a>),
+
+    /// An axis step and the predicates that filter the nodes it selects.
+    ///
+    /// The nodes are selected by the selector if the step has one,
+    /// otherwise by evaluating the step from its syntax tree.
+    Axis {
+        step_type: &'a AxisStepType,
+        selector: Option<NodeSelector<'a>>,
+        predicates: Vec<CompiledPredicate<'a>>,
+    },
+
+    /// Predicates and lookups applied in turn to the result of a primary expression.
+    Filter {
+        base: Box<Operator<'a>>,
+        items: Vec<FilterItem<'a>>,
+    },
+
+    /// An expression that is evaluated from its syntax tree.
+    Tree(&'a dyn Evaluate),
+}
+
+impl Operator<'_> {
+    fn eval<'tree>(
+        &self,
+        context: &XpathExpressionContext<'tree, '_>,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        match self {
+            Operator::Sequence(operators) => {
+                if let [operator] = operators.as_slice() {
+                    let _depth = context.budget.enter()?;
+                    return operator.eval(context);
+                }
+
+                let mut items = XpathItemSet::new();
+                for (i, operator) in operators.iter().enumerate() {
+                    let _depth = context.budget.enter()?;
+                    items.extend(operator.eval(context)?);
+                    if i > 0 {
+                        context.budget.check_result_size(items.len())?;
+                    }
+                }
+
+                Ok(items)
+            }
+            Operator::Literal(value) => {
+                Ok(xpath_item_set![XpathItem::AnyAtomicType(value.clone())])
+            }
+            Operator::Empty => Ok(XpathItemSet::new()),
+            Operator::ContextItem => Ok(xpath_item_set![context.item.clone()]),
+            Operator::FunctionCall {
+                call,
+                function,
+                arguments,
+            } => {
+                let mut values = Vec::with_capacity(arguments.len());
+                for argument in arguments.iter() {
+                    let _depth = context.budget.enter()?;
+                    values.push(argument.eval(context)?);
+                }
+
+                function
+                    .call(context, values)
+                    .map_err(|e| e.in_expression(*call))
+            }
+            Operator::Comparison {
+                expr,
+                comparison_type,
+                left,
+                right,
+            } => {
+                let left = left.eval(context)?;
+                let right = right.eval(context)?;
+                expr.compare(comparison_type, &left, &right, context)
+            }
+            Operator::Path(path) => path.eval(context),
+            Operator::Axis {
+                step_type,
+                selector,
+                predicates,
+            } => {
+                let items = match selector {
+                    Some(selector) => selector
+                        .select(context)
+                        .map_err(|e| e.in_expression(*step_type))?,
+                    None => {
+                        let nodes = step_type
+                            .eval(context)
+                            .map_err(|e| e.in_expression(*step_type))?;
+                        nodes.into_iter().map(XpathItem::Node).collect()
+                    }
+                };
+
+                // The selected nodes were counted towards the result size as they were selected.
+                if predicates.is_empty() {
+                    return Ok(items);
+                }
+
+                // All predicates must match for a node to be selected.
+                let mut filtered_items = XpathItemSet::new();
+                for (i, item) in items.iter().enumerate() {
+                    let predicate_context = context.with_position(&items, i + 1);
+
+                    let mut is_match = true;
+                    for predicate in predicates.iter() {
+                        if !predicate.is_match(&predicate_context)? {
+                            is_match = false;
+                        }
+                    }
+
+                    if is_match {
+                        filtered_items.insert(item.clone());
+                        context.budget.check_result_size(filtered_items.len())?;
+                    }
+                }
+
+                Ok(filtered_items)
+            }
+            Operator::Filter { base, items } => {
+                let mut res = base.eval(context)?;
+                for item in items.iter() {
+                    res = match item {
+                        FilterItem::Predicate(predicate) => {
+                            let mut filtered_items = XpathItemSet::new();
+                            for (i, item) in res.iter().enumerate() {
+                                let predicate_context = context.with_position(&res, i + 1);
+                                if predicate.is_match(&predicate_context)? {
+                                    filtered_items.insert(item.clone());
+                                }
+                            }
+
+                            filtered_items
+                        }
+                        FilterItem::Lookup(lookup) => lookup
+                            .0
+                            .lookup(&res, context)
+                            .map_err(|e| e.in_expression(*lookup))?,
+                    };
+                }
+
+                Ok(res)
+            }
+            Operator::Tree(expr) => expr.eval_tree(context),
+        }
+    }
+}
+
+/// Selects the nodes of an axis step whose node test was resolved when compiling,
+/// by walking the tree instead of testing each node of the axis in a context of its own.
+///
+/// The nodes are selected in the same order, and counted as visited the same way,
+/// as when the step is evaluated from its syntax tree.
+#[derive(Clone, Copy)]
+struct NodeSelector<'a> {
+    axis: SelectorAxis,
+    test: SelectorTest<'a>,
+
+    /// Whether the selected nodes count towards the maximum result size as they are selected.
+    counts_selected: bool,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum SelectorAxis {
+    Child,
+    Descendant,
+    DescendantOrSelf,
+    SelfAxis,
+    Attribute,
+
+    /// The children of the context node and of each of its descendants,
+    /// which a `//` followed by a child step selects.
+    DescendantOrSelfChildren,
+}
+
+#[derive(Clone, Copy)]
+enum SelectorTest<'a> {
+    /// A name without a namespace, which matches elements and attributes with that name.
+    Name(&'a str),
+
+    /// `*`, which matches the principal node kind of the axis.
+    Wildcard,
+
+    /// `node()`, which matches every node but attributes.
+    AnyNode,
+
+    /// `text()`.
+    Text,
+}
+
+impl<'a> NodeSelector<'a> {
+    /// Get the selector for an axis step, or `None` if its axis or node test has none.
+    fn new(step_type: &'a AxisStepType) -> Option<Self> {
+        let (axis, node_test) = match step_type {
+            AxisStepType::ForwardStep(ForwardStep::Full(axis, node_test)) => (*axis, node_test),
+            AxisStepType::ForwardStep(ForwardStep::Abbreviated(step)) if step.has_at => {
+                (ForwardAxis::Attribute, &step.node_test)
+            }
+            AxisStepType::ForwardStep(ForwardStep::Abbreviated(step)) => {
+                (ForwardAxis::Child, &step.node_test)
+            }
+            AxisStepType::ReverseStep(_) => return None,
+        };
+
+        let axis = match axis {
+            ForwardAxis::Child => SelectorAxis::Child,
+            ForwardAxis::Descendant => SelectorAxis::Descendant,
+            ForwardAxis::DescendantOrSelf => SelectorAxis::DescendantOrSelf,
+            ForwardAxis::SelfAxis => SelectorAxis::SelfAxis,
+            ForwardAxis::Attribute => SelectorAxis::Attribute,
+            _ => return None,
+        };
+
+        let test = match node_test {
+            NodeTest::NameTest(NameTest::Name(EQName::QName(QName::UnprefixedName(name)))) => {
+                SelectorTest::Name(name)
+            }
+            NodeTest::NameTest(NameTest::Name(EQName::UriQualifiedName(name)))
+                if name.uri.is_empty() =>
+            {
+                SelectorTest::Name(&name.name)
+            }
+            NodeTest::NameTest(NameTest::Wildcard(Wildcard::Simple)) => SelectorTest::Wildcard,
+            NodeTest::KindTest(KindTest::AnyKindTest) => SelectorTest::AnyNode,
+            NodeTest::KindTest(KindTest::TextTest) => SelectorTest::Text,
+            _ => return None,
+        };
+
+        Some(NodeSelector {
+            axis,
+            test,
+            counts_selected: true,
+        })
+    }
+
+    fn select<'tree>(
+        &self,
+        context: &XpathExpressionContext<'tree, '_>,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        let mut items = XpathItemSet::new();
+        let node = match &context.item {
+            XpathItem::Node(node) => *node,
+            // Only the descendant-or-self axis needs a node, the others select nothing.
+            _ => {
+                return match self.axis {
+                    SelectorAxis::DescendantOrSelf => Err(not_a_node()),
+                    SelectorAxis::DescendantOrSelfChildren => {
+                        Err(not_a_node().in_expression(&*DESCENDANT_OR_SELF_STEP))
+                    }
+                    _ => Ok(items),
+                }
+            }
+        };
+
+        match self.axis {
+            SelectorAxis::Child => {
+                for child in children(context.item_tree, node) {
+                    self.select_node(context, child, &mut items)?;
+                }
+            }
+            SelectorAxis::Descendant => self.select_descendants(context, node, &mut items)?,
+            SelectorAxis::DescendantOrSelf => {
+                self.select_node(context, node, &mut items)?;
+                self.select_descendants(context, node, &mut items)?;
+            }
+            SelectorAxis::SelfAxis => self.select_node(context, node, &mut items)?,
+            SelectorAxis::Attribute => {
+                // Only elements have attributes.
+                if let XpathItemTreeNode::ElementNode(_) = node {
+                    for child in children(context.item_tree, node) {
+                        if let XpathItemTreeNode::AttributeNode(_) = child {
+                            self.select_node(context, child, &mut items)?;
+                        }
+                    }
+                }
+            }
+            SelectorAxis::DescendantOrSelfChildren => {
+                self.select_descendant_children(context, node, &mut items)?
+            }
+        }
+
+        Ok(items)
+    }
+
+    fn select_node<'tree>(
+        &self,
+        context: &XpathExpressionContext<'tree, '_>,
+        node: &'tree XpathItemTreeNode,
+        items: &mut XpathItemSet<'tree>,
+    ) -> Result<(), ExpressionApplyError> {
+        context.budget.visit_node()?;
+        if self.is_match(node) {
+            items.insert(XpathItem::Node(node));
+            if self.counts_selected {
+                context.budget.check_result_size(items.len())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Select from the descendants of the node in document order.
+    fn select_descendants<'tree>(
+        &self,
+        context: &XpathExpressionContext<'tree, '_>,
+        node: &'tree XpathItemTreeNode,
+        items: &mut XpathItemSet<'tree>,
+    ) -> Result<(), ExpressionApplyError> {
+        for child in children(context.item_tree, node) {
+            self.select_node(context, child, items)?;
+            self.select_descendants(context, child, items)?;
+        }
+
+        Ok(())
+    }
+
+    /// Select from the children of the node and of each of its descendants,
+    /// in the order the children are selected by a `descendant-or-self::node()` step
+    /// followed by a child step.
+    fn select_descendant_children<'tree>(
+        &self,
+        context: &XpathExpressionContext<'tree, '_>,
+        node: &'tree XpathItemTreeNode,
+        items: &mut XpathItemSet<'tree>,
+    ) -> Result<(), ExpressionApplyError> {
+        // The descendant-or-self step visits every node,
+        // but only selects the nodes that are not attributes for the child step.
+        context.budget.visit_node()?;
+        if let XpathItemTreeNode::AttributeNode(_) = node {
+            return Ok(());
+        }
+
+        for child in children(context.item_tree, node) {
+            self.select_node(context, child, items)?;
+        }
+
+        for child in children(context.item_tree, node) {
+            self.select_descendant_children(context, child, items)?;
+        }
+
+        Ok(())
+    }
+
+    fn is_match(&self, node: &XpathItemTreeNode) -> bool {
+        match self.test {
+            SelectorTest::Name(name) => match node {
+                XpathItemTreeNode::ElementNode(element) => element.name == name,
+                XpathItemTreeNode::AttributeNode(attribute) => attribute.name == name,
+                _ => false,
+            },
+            SelectorTest::Wildcard => match self.axis {
+                SelectorAxis::Attribute => matches!(node, XpathItemTreeNode::AttributeNode(_)),
+                _ => matches!(node, XpathItemTreeNode::ElementNode(_)),
+            },
+            SelectorTest::AnyNode => !matches!(node, XpathItemTreeNode::AttributeNode(_)),
+            SelectorTest::Text => matches!(node, XpathItemTreeNode::TextNode(_)),
+        }
+    }
+}
+
+/// The children of a node, without collecting them as [XpathItemTreeNode::children] does.
+fn children<'tree>(
+    item_tree: &'tree XpathItemTree,
+    node: &XpathItemTreeNode,
+) -> impl Iterator<Item = &'tree XpathItemTreeNode> {
+    let id = match node {
+        XpathItemTreeNode::DocumentNode(document) => Some(document.id()),
+        XpathItemTreeNode::ElementNode(element) => Some(element.id()),
+        _ => None,
+    };
+
+    id.into_iter().flat_map(|id| item_tree.children_of(id))
+}
+
+fn not_a_node() -> ExpressionApplyError {
+    ExpressionApplyError::with_code(
+        ErrorCode::XPTY0020,
+        String::from("context item for axis step is not a node"),
+    )
+}
+
+/// A path expression, whose steps are evaluated from each item selected by the previous step.
+struct Path<'a> {
+    start: PathStart<'a>,
+    steps: Vec<Step<'a>>,
+}
+
+/// Where a path starts.
+enum PathStart<'a> {
+    /// The first step is evaluated from the context item.
+    Context,
+
+    /// A leading `/`, which starts at the root of the context item's tree.
+    Root,
+
+    /// A leading `//`, which starts at the root of the context item's tree
+    /// and is followed by a `descendant-or-self::node()` step.
+    RootDescendants(&'a RelativePathExpr),
+}
+
+impl Path<'_> {
+    fn eval<'tree>(
+        &self,
+        context: &XpathExpressionContext<'tree, '_>,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        // Leading slashes only start at the root of the tree in the outermost expression,
+        // otherwise they start at the context item.
+        // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#id-path-expressions
+        match self.start {
+            PathStart::Context => {
+                let first = self.steps[0].eval(context)?;
+                eval_from(context, first, &self.steps[1..])
+            }
+            PathStart::Root | PathStart::RootDescendants(_) if !context.is_root_level => {
+                eval_from(context, xpath_item_set![context.item.clone()], &self.steps)
+            }
+            PathStart::Root => {
+                let root = ROOT_STEP.eval(context)?;
+                eval_from(context, root, &self.steps)
+            }
+            PathStart::RootDescendants(expr) => match IndexedPath::plan(expr, context) {
+                // Look up the step after the `//` in the indexes of the tree
+                // instead of visiting every node.
+                Some(plan) => {
+                    let elements = plan.lookup(context, self.steps[1].trace_id)?;
+                    eval_from(context, elements, &self.steps[2..])
+                }
+                None => {
+                    let root = ROOT_STEP.eval(context)?;
+                    eval_from(context, root, &self.steps)
+                }
+            },
+        }
+    }
+}
+
+/// Evaluate the steps from each of the given items.
+fn eval_from<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    items: XpathItemSet<'tree>,
+    steps: &[Step],
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    if steps.is_empty() {
+        return Ok(items);
+    }
+
+    let mut result = XpathItemSet::new();
+    for i in 0..items.len() {
+        let item_context = context.with_position(&items, i + 1);
+        result.extend(eval_steps(&item_context, steps)?);
+        context.budget.check_result_size(result.len())?;
+    }
+
+    Ok(result)
+}
+
+/// Recursively evaluate a series of steps, feeding the result of each step into the next.
+fn eval_steps<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    steps: &[Step],
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    if steps.is_empty() {
+        return Ok(xpath_item_set![context.item.clone()]);
+    }
+
+    let this_result = steps[0].eval(context)?;
+    eval_from(context, this_result, &steps[1..])
+}
+
+/// A step of a path.
+struct Step<'a> {
+    /// The step, which describes it when explaining an expression.
+    expr: &'a StepExpr,
+
+    /// Identifies the step when explaining an expression.
+    trace_id: TraceId,
+
+    /// Whether the step is recorded when explaining an expression.
+    is_traced: bool,
+
+    operator: Operator<'a>,
+}
+
+impl Step<'_> {
+    fn eval<'tree>(
+        &self,
+        context: &XpathExpressionContext<'tree, '_>,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        let eval = || self.operator.eval(context);
+
+        match context.tracer {
+            Some(tracer) if self.is_traced => {
+                tracer.trace_step(self.trace_id, self.expr, context.item_tree, eval)
+            }
+            _ => eval(),
+        }
+    }
+}
+
+/// A predicate and its compiled expression.
+struct CompiledPredicate<'a> {
+    predicate: &'a Predicate,
+    trace_id: TraceId,
+    operator: Operator<'a>,
+}
+
+impl CompiledPredicate<'_> {
+    fn is_match(&self, context: &XpathExpressionContext) -> Result<bool, ExpressionApplyError> {
+        // Testing a predicate visits the context node, however cheap the predicate is.
+        context.budget.visit_node()?;
+
+        let is_match = || {
+            let res = self.operator.eval(context)?;
+            Ok(predicate_truth_value(&res, context.position))
+        };
+
+        match context.tracer {
+            Some(tracer) => tracer.trace_predicate(
+                self.trace_id,
+                self.predicate,
+                context.item_tree,
+                &context.item,
+                is_match,
+            ),
+            None => is_match(),
+        }
+    }
+}
+
+/// A predicate or lookup applied to the result of a primary expression.
+enum FilterItem<'a> {
+    Predicate(CompiledPredicate<'a>),
+    Lookup(&'a Lookup),
+}
+
+/// An expression that can be evaluated from its syntax tree.
+trait Evaluate {
+    fn eval_tree<'tree>(
+        &self,
+        context: &XpathExpressionContext<'tree, '_>,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError>;
+}
+
+macro_rules! impl_evaluate {
+    ($($expr:ty),*) => {
+        $(
+            impl Evaluate for $expr {
+                fn eval_tree<'tree>(
+                    &self,
+                    context: &XpathExpressionContext<'tree, '_>,
+                ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+                    self.eval(context)
+                }
+            }
+        )*
+    };
+}
+
+impl_evaluate!(
+    OrExpr,
+    AndExpr,
+    StringConcatExpr,
+    RangeExpr,
+    AdditiveExpr,
+    MultiplicativeExpr,
+    UnionExpr,
+    IntersectExceptExpr,
+    InstanceofExpr,
+    TreatExpr,
+    CastableExpr,
+    CastExpr,
+    ArrowExpr,
+    UnaryExpr,
+    SimpleMapExpr,
+    PostfixExpr,
+    PrimaryExpr,
+    FunctionCall
+);
+
+impl Evaluate for ExprSingle {
+    fn eval_tree<'tree>(
+        &self,
+        context: &XpathExpressionContext<'tree, '_>,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        // The caller has already entered the depth of the expression.
+        self.eval_entered(context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        html,
+        xpath::{
+            self,
+            context::{DynamicContextBuilder, StaticContextBuilder},
+            functions::ExpandedName,
+            limits::{EvaluationLimit, EvaluationLimits},
+            ErrorCode,
+        },
+    };
+
+    use super::*;
+
+    fn tree(text: &str) -> XpathItemTree {
+        XpathItemTree::from(&html::parse(text).unwrap())
+    }
+
+    #[test]
+    fn apply_should_match_syntax_tree_evaluation() {
+        // arrange
+        let tree = tree(
+            r#"
+            <html>
+                <body>
+                    <div id="main" class="a">
+                        <p id="1">one</p>
+                        <p id="2" class="b">two</p>
+                    </div>
+                    <div class="a b"><p>three</p></div>
+                </body>
+            </html>"#,
+        );
+        let expressions = [
+            "/",
+            "/html/body/div",
+            "//p",
+            "//div[@class='a']/p[2]",
+            "//div//p/text()",
+            "//p/@id",
+            "(//p)[1]",
+            "//div[p]",
+            "//p[. = 'two']",
+            "/html/*",
+            "//p[contains(@class, 'b')]",
+            "string(//p[2])",
+            "1 + 2",
+            "-(1)",
+            "//p[2], //div[1]",
+            "/html//p",
+            "/html//p[@class]",
+            "/html/body//p[contains(@class, 'b')]/@id",
+            "/html//div[@class = 'a b']",
+            "(/html//p)[3]",
+            "/html//div//p[1]",
+            "/html//p[2]",
+            "/html//p[not(@class)]",
+            "/html//node()",
+            "/html//*/text()",
+            "/html/body/descendant::p",
+            "/html/body/descendant-or-self::*",
+            "//div/self::div/@*",
+            "//p/parent::div",
+        ];
+
+        for text in expressions {
+            let xpath = xpath::parse(text).unwrap();
+
+            // act
+            let compiled = xpath.apply(&tree).unwrap();
+            let uncompiled = xpath.apply_uncompiled(&tree).unwrap();
+
+            // assert
+            assert_eq!(
+                format!("{:?}", compiled),
+                format!("{:?}", uncompiled),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn apply_should_reuse_plan_for_many_trees() {
+        // arrange
+        let xpath = xpath::parse("//a[@class='next']/@href").unwrap();
+        let compiled = xpath.compile();
+        let trees = [
+            tree(r#"<html><a class="next" href="/2">Next</a></html>"#),
+            tree(r#"<html><a href="/1">Back</a></html>"#),
+        ];
+
+        // act
+        let counts: Vec<usize> = trees
+            .iter()
+            .map(|tree| compiled.apply(tree).unwrap().len())
+            .collect();
+
+        // assert
+        assert_eq!(counts, vec![1, 0]);
+    }
+
+    #[test]
+    fn apply_to_item_should_start_leading_slash_at_item() {
+        // arrange
+        let tree = tree(r#"<html><div id="1"><p>one</p></div><div id="2"><p>two</p></div></html>"#);
+        let div = xpath::parse("//div[@id='2']")
+            .unwrap()
+            .apply(&tree)
+            .unwrap()[0]
+            .clone();
+        let xpath = xpath::parse("//p").unwrap();
+
+        // act
+        let items = xpath.compile().apply_to_item(&tree, div).unwrap();
+
+        // assert
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            items[0].extract_as_node().text_content(&tree),
+            String::from("two")
+        );
+    }
+
+    #[test]
+    fn compile_should_resolve_functions_in_context() {
+        // arrange
+        let tree = tree("<html><p>one</p><p>two</p></html>");
+        let static_context = StaticContextBuilder::new()
+            .with_namespace("ext", "urn:example")
+            .with_function(
+                ExpandedName::new("urn:example", "first"),
+                1,
+                |_tree, arguments| Ok(arguments[0].iter().take(1).cloned().collect()),
+            )
+            .build();
+        let xpath = xpath::parse_with_context("ext:first(//p)", &static_context).unwrap();
+        let context = DynamicContext::new(static_context);
+
+        // act
+        let items = xpath.compile_with_context(&context).apply(&tree).unwrap();
+
+        // assert
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].extract_as_node().text_content(&tree), "one");
+    }
+
+    #[test]
+    fn apply_should_select_by_position_when_boolean_built_in_is_replaced() {
+        // arrange
+        let tree = tree("<html><div><p>one</p><p>two</p></div><div><p>three</p></div></html>");
+        let static_context = StaticContextBuilder::new()
+            .with_function(ExpandedName::new(FN_NAMESPACE, "contains"), 2, |_, _| {
+                Ok(vec![XpathItem::AnyAtomicType(AnyAtomicType::Integer(1))]
+                    .into_iter()
+                    .collect())
+            })
+            .build();
+        let xpath =
+            xpath::parse_with_context("/html//p[contains(., 'o')]", &static_context).unwrap();
+        let context = DynamicContext::new(static_context);
+
+        // act
+        let items = xpath.compile_with_context(&context).apply(&tree).unwrap();
+
+        // assert
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].extract_as_node().text_content(&tree), "one");
+        assert_eq!(items[1].extract_as_node().text_content(&tree), "three");
+    }
+
+    #[test]
+    fn apply_should_report_error_in_expression() {
+        // arrange
+        let tree = tree("<html><div></div></html>");
+        let (_, xpath) = xpath::grammar::expressions::xpath("//div/ancestor::body").unwrap();
+
+        // act
+        let err = xpath.compile().apply(&tree).unwrap_err();
+
+        // assert
+        assert_eq!(err.code(), &ErrorCode::Unsupported);
+        assert_eq!(err.expression(), Some("ancestor::"));
+    }
+
+    #[test]
+    fn apply_should_respect_depth_limit() {
+        // arrange
+        let tree = tree("<html></html>");
+        let xpath = xpath::parse("((((1))))").unwrap();
+        let context = DynamicContextBuilder::new(StaticContext::new())
+            .with_limits(EvaluationLimits::new().with_max_depth(3))
+            .build();
+
+        // act
+        let err = xpath
+            .compile_with_context(&context)
+            .apply(&tree)
+            .unwrap_err();
+
+        // assert
+        assert_eq!(
+            err.code(),
+            &ErrorCode::LimitExceeded(EvaluationLimit::Depth)
+        );
+    }
+
+    #[test]
+    fn apply_should_count_node_visits_of_merged_steps_like_separate_steps() {
+        // arrange
+        let tree = tree(r#"<html><div id="1"><p>one</p><p class="a">two</p></div></html>"#);
+        let xpath = xpath::parse("/html//p[@class]").unwrap();
+        let is_ok = |max_node_visits: u64, merges_steps: bool| {
+            let context = DynamicContextBuilder::new(StaticContext::new())
+                .with_limits(EvaluationLimits::new().with_max_node_visits(max_node_visits))
+                .build();
+            xpath
+                .compile_plan(&context, merges_steps)
+                .apply(&tree)
+                .is_ok()
+        };
+
+        for max_node_visits in 0..40 {
+            // act
+            let merged = is_ok(max_node_visits, true);
+            let separate = is_ok(max_node_visits, false);
+
+            // assert
+            assert_eq!(merged, separate, "{}", max_node_visits);
+        }
+        assert!(is_ok(40, true));
+    }
+}
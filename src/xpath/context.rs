@@ -0,0 +1,631 @@
+//! The static and dynamic contexts that [Xpath](crate::xpath::Xpath) expressions are evaluated in.
+//!
+//! <https://www.w3.org/TR/2017/REC-xpath-31-20170321/#context>
+//!
+//! Most users will not need to touch these types; [Xpath::apply](crate::xpath::Xpath::apply) uses
+//! a default context that knows the built-in functions and the standard namespace prefixes.
+//! Build your own context to register extension functions or namespace prefixes,
+//! parse expressions with [parse_with_context](crate::xpath::parse_with_context),
+//! then apply them with [Xpath::apply_with_context](crate::xpath::Xpath::apply_with_context).
+//!
+//! # Example: register an extension function
+//!
+//! ```rust
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! use skyscraper::html;
+//! use skyscraper::xpath::{
+//!     self,
+//!     context::{DynamicContext, StaticContextBuilder},
+//!     functions::ExpandedName,
+//!     grammar::data_model::{AnyAtomicType, XpathItem},
+//!     xpath_item_set::XpathItemSet,
+//!     XpathItemTree,
+//! };
+//!
+//! let static_context = StaticContextBuilder::new()
+//!     .with_namespace("ext", "urn:example:ext")
+//!     .with_function(
+//!         ExpandedName::new("urn:example:ext", "shout"),
+//!         1,
+//!         |tree, arguments| {
+//!             let text: String = arguments[0]
+//!                 .iter()
+//!                 .map(|item| item.string_value(tree).to_uppercase())
+//!                 .collect();
+//!
+//!             Ok(XpathItemSet::from_iter([XpathItem::AnyAtomicType(
+//!                 AnyAtomicType::String(text),
+//!             )]))
+//!         },
+//!     )
+//!     .build();
+//! let context = DynamicContext::new(static_context);
+//!
+//! let document = html::parse("<html><div>hello</div></html>")?;
+//! let tree = XpathItemTree::from(&document);
+//!
+//! let xpath = xpath::parse_with_context("//div/ext:shout(.)", context.static_context())?;
+//! let items = xpath.apply_with_context(&tree, &context)?;
+//!
+//! assert_eq!(items[0].extract_as_any_atomic_type().to_string(), "HELLO");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::HashMap, sync::Arc};
+
+use once_cell::sync::Lazy;
+
+use crate::xpath::{
+    collation::{
+        CodepointCollation, Collation, HtmlAsciiCaseInsensitiveCollation, CODEPOINT_COLLATION,
+        HTML_ASCII_CASE_INSENSITIVE_COLLATION,
+    },
+    decimal_format::DecimalFormat,
+    functions::{ExpandedName, FunctionRegistry, FN_NAMESPACE},
+    grammar::{terminal_symbols::UriQualifiedName, types::EQName, xml_names::QName},
+    limits::EvaluationLimits,
+    resolver::ResourceResolver,
+    xpath_item_set::XpathItemSet,
+    ErrorCode, ExpressionApplyError, XpathItemTree,
+};
+
+/// The namespace bound to the `xs` prefix.
+pub const XS_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema";
+
+/// The namespace bound to the `math` prefix.
+pub const MATH_NAMESPACE: &str = "http://www.w3.org/2005/xpath-functions/math";
+
+/// The namespace bound to the `map` prefix.
+pub const MAP_NAMESPACE: &str = "http://www.w3.org/2005/xpath-functions/map";
+
+/// The namespace bound to the `array` prefix.
+pub const ARRAY_NAMESPACE: &str = "http://www.w3.org/2005/xpath-functions/array";
+
+/// The namespace bound to the `err` prefix.
+pub const ERR_NAMESPACE: &str = "http://www.w3.org/2005/xqt-errors";
+
+/// Context that is known before an expression is evaluated.
+///
+/// <https://www.w3.org/TR/2017/REC-xpath-31-20170321/#static_context>
+#[derive(Clone)]
+pub struct StaticContext {
+    /// Statically known namespaces, keyed by prefix.
+    namespaces: HashMap<String, String>,
+
+    /// The namespace used for unprefixed function names.
+    default_function_namespace: String,
+
+    /// Functions that can be called from an expression.
+    functions: FunctionRegistry,
+
+    /// The URI that relative URIs are resolved against when there is no other base.
+    base_uri: Option<String>,
+
+    /// Statically known collations, keyed by URI.
+    collations: HashMap<String, Arc<dyn Collation>>,
+
+    /// The URI of the collation used when an expression does not name one.
+    default_collation: String,
+
+    /// Statically known decimal formats, keyed by name.
+    decimal_formats: HashMap<ExpandedName, DecimalFormat>,
+
+    /// The decimal format used when an expression does not name one.
+    default_decimal_format: DecimalFormat,
+
+    /// Whether expressions are evaluated with the rules of XPath 1.0 where they differ.
+    xpath_1_0_compatibility: bool,
+}
+
+impl StaticContext {
+    /// Create a new [StaticContext] with the built-in functions and standard namespace prefixes.
+    pub fn new() -> Self {
+        let namespaces = [
+            ("fn", FN_NAMESPACE),
+            ("xs", XS_NAMESPACE),
+            ("math", MATH_NAMESPACE),
+            ("map", MAP_NAMESPACE),
+            ("array", ARRAY_NAMESPACE),
+            ("err", ERR_NAMESPACE),
+        ]
+        .into_iter()
+        .map(|(prefix, uri)| (prefix.to_string(), uri.to_string()))
+        .collect();
+
+        let collations: [(&str, Arc<dyn Collation>); 2] = [
+            (CODEPOINT_COLLATION, Arc::new(CodepointCollation)),
+            (
+                HTML_ASCII_CASE_INSENSITIVE_COLLATION,
+                Arc::new(HtmlAsciiCaseInsensitiveCollation),
+            ),
+        ];
+        let collations = collations
+            .into_iter()
+            .map(|(uri, collation)| (uri.to_string(), collation))
+            .collect();
+
+        Self {
+            namespaces,
+            default_function_namespace: FN_NAMESPACE.to_string(),
+            functions: FunctionRegistry::new(),
+            base_uri: None,
+            collations,
+            default_collation: CODEPOINT_COLLATION.to_string(),
+            decimal_formats: HashMap::new(),
+            default_decimal_format: DecimalFormat::new(),
+            xpath_1_0_compatibility: false,
+        }
+    }
+
+    /// Get the namespace URI bound to the given prefix.
+    pub fn namespace(&self, prefix: &str) -> Option<&str> {
+        self.namespaces.get(prefix).map(|uri| uri.as_str())
+    }
+
+    /// The functions that can be called from an expression.
+    pub fn functions(&self) -> &FunctionRegistry {
+        &self.functions
+    }
+
+    /// The functions that can be called from an expression.
+    pub fn functions_mut(&mut self) -> &mut FunctionRegistry {
+        &mut self.functions
+    }
+
+    /// The static base URI, which `fn:resolve-uri` falls back to
+    /// when the context item is not a node with a base URI.
+    pub fn base_uri(&self) -> Option<&str> {
+        self.base_uri.as_deref()
+    }
+
+    /// The URI of the collation used when an expression does not name one.
+    ///
+    /// This is the [CODEPOINT_COLLATION] unless set by the builder.
+    pub fn default_collation(&self) -> &str {
+        &self.default_collation
+    }
+
+    /// Get the collation with the given URI.
+    pub fn collation(&self, uri: &str) -> Option<&dyn Collation> {
+        self.collations.get(uri).map(|collation| collation.as_ref())
+    }
+
+    /// Get the collation with the given URI, or the default collation if no URI is given.
+    ///
+    /// Fails with [ErrorCode::FOCH0002] if the collation is not known.
+    pub(crate) fn resolve_collation(
+        &self,
+        uri: Option<&str>,
+    ) -> Result<&dyn Collation, ExpressionApplyError> {
+        let uri = uri.unwrap_or(&self.default_collation);
+        self.collation(uri).ok_or_else(|| {
+            ExpressionApplyError::with_code(
+                ErrorCode::FOCH0002,
+                format!("collation {} is not supported", uri),
+            )
+        })
+    }
+
+    /// Whether XPath 1.0 compatibility mode is on.
+    ///
+    /// <https://www.w3.org/TR/2017/REC-xpath-31-20170321/#dt-xpath-compat-mode>
+    ///
+    /// In compatibility mode, expressions written for XPath 1.0, such as those copied from
+    /// browser devtools or used with lxml, give the results they give there:
+    ///
+    /// - General comparisons such as `=` and `<` compare every pair of values, so comparisons
+    ///   with an empty sequence are false. `<`, `<=`, `>` and `>=` compare numbers,
+    ///   so `"2" > "10"` is false, and a boolean on either side compares effective boolean values.
+    /// - Arithmetic converts both operands to doubles with `fn:number`,
+    ///   so `() + 1` is `NaN` and `"1" + 1` is `2`.
+    /// - Functions that expect a single item or string take the first item of a longer sequence,
+    ///   so `string(//a)` is the string value of the first link.
+    ///
+    /// Off by default.
+    pub fn xpath_1_0_compatibility(&self) -> bool {
+        self.xpath_1_0_compatibility
+    }
+
+    /// The decimal format used by `fn:format-number` when an expression does not name one.
+    pub fn default_decimal_format(&self) -> &DecimalFormat {
+        &self.default_decimal_format
+    }
+
+    /// Get the decimal format with the given name.
+    pub fn decimal_format(&self, name: &ExpandedName) -> Option<&DecimalFormat> {
+        self.decimal_formats.get(name)
+    }
+
+    /// Get the decimal format named by a string such as `de`, `prefix:name` or `Q{uri}name`,
+    /// or the default decimal format if no name is given.
+    ///
+    /// Unprefixed names are in no namespace.
+    /// Fails with [ErrorCode::FODF1280] if the name is not valid or the format is not known.
+    pub(crate) fn resolve_decimal_format(
+        &self,
+        name: Option<&str>,
+    ) -> Result<&DecimalFormat, ExpressionApplyError> {
+        let name = match name {
+            Some(name) => name.trim(),
+            None => return Ok(&self.default_decimal_format),
+        };
+
+        let unknown = || {
+            ExpressionApplyError::with_code(
+                ErrorCode::FODF1280,
+                format!("decimal format {} is not known", name),
+            )
+        };
+
+        let expanded_name = if let Some(rest) = name.strip_prefix("Q{") {
+            let (uri, local_name) = rest.split_once('}').ok_or_else(unknown)?;
+            ExpandedName::new(uri, local_name)
+        } else if let Some((prefix, local_name)) = name.split_once(':') {
+            let uri = self.namespace(prefix).ok_or_else(unknown)?;
+            ExpandedName::new(uri, local_name)
+        } else {
+            ExpandedName::new("", name)
+        };
+
+        self.decimal_format(&expanded_name).ok_or_else(unknown)
+    }
+
+    /// Resolve the name of a function call to its expanded name.
+    ///
+    /// Unprefixed names are in the default function namespace.
+    pub(crate) fn resolve_function_name(
+        &self,
+        name: &EQName,
+    ) -> Result<ExpandedName, ExpressionApplyError> {
+        self.resolve_name(name, &self.default_function_namespace)
+    }
+
+    /// Resolve the name of a variable to its expanded name.
+    ///
+    /// Unprefixed names are in no namespace.
+    pub(crate) fn resolve_variable_name(
+        &self,
+        name: &EQName,
+    ) -> Result<ExpandedName, ExpressionApplyError> {
+        self.resolve_name(name, "")
+    }
+
+    fn resolve_name(
+        &self,
+        name: &EQName,
+        default_namespace: &str,
+    ) -> Result<ExpandedName, ExpressionApplyError> {
+        match name {
+            EQName::QName(QName::PrefixedName(prefixed_name)) => {
+                let namespace = self.namespace(&prefixed_name.prefix).ok_or_else(|| {
+                    ExpressionApplyError::with_code(
+                        ErrorCode::XPST0081,
+                        format!("namespace prefix {} is not declared", prefixed_name.prefix),
+                    )
+                })?;
+
+                Ok(ExpandedName::new(namespace, &prefixed_name.local_part))
+            }
+            EQName::QName(QName::UnprefixedName(local_name)) => {
+                Ok(ExpandedName::new(default_namespace, local_name))
+            }
+            EQName::UriQualifiedName(UriQualifiedName { uri, name }) => {
+                Ok(ExpandedName::new(uri, name))
+            }
+        }
+    }
+}
+
+impl Default for StaticContext {
+    fn default() -> Self {
+        StaticContext::new()
+    }
+}
+
+/// Builds a [StaticContext].
+///
+/// See [StaticContext::new] for the default values used if not set by the builder.
+pub struct StaticContextBuilder {
+    reducers: Vec<Box<dyn FnOnce(StaticContext) -> StaticContext>>,
+}
+
+impl StaticContextBuilder {
+    /// Creates a new [StaticContextBuilder].
+    pub fn new() -> Self {
+        Self {
+            reducers: Vec::new(),
+        }
+    }
+
+    /// Bind a namespace prefix to a namespace URI.
+    ///
+    /// Rebinding an existing prefix replaces the previous URI.
+    pub fn with_namespace(mut self, prefix: &str, uri: &str) -> Self {
+        let prefix = prefix.to_string();
+        let uri = uri.to_string();
+        let reducer = move |mut context: StaticContext| {
+            context.namespaces.insert(prefix, uri);
+            context
+        };
+        self.reducers.push(Box::new(reducer));
+        self
+    }
+
+    /// Set the static base URI.
+    ///
+    /// See [StaticContext::base_uri].
+    pub fn with_base_uri(mut self, uri: &str) -> Self {
+        let uri = uri.to_string();
+        let reducer = move |mut context: StaticContext| {
+            context.base_uri = Some(uri);
+            context
+        };
+        self.reducers.push(Box::new(reducer));
+        self
+    }
+
+    /// Register a collation, so that expressions can name it by its URI.
+    ///
+    /// Registering an existing URI replaces the previous collation.
+    pub fn with_collation(mut self, uri: &str, collation: impl Collation + 'static) -> Self {
+        let uri = uri.to_string();
+        let collation: Arc<dyn Collation> = Arc::new(collation);
+        let reducer = move |mut context: StaticContext| {
+            context.collations.insert(uri, collation);
+            context
+        };
+        self.reducers.push(Box::new(reducer));
+        self
+    }
+
+    /// Set the collation used when an expression does not name one.
+    ///
+    /// See [StaticContext::default_collation].
+    pub fn with_default_collation(mut self, uri: &str) -> Self {
+        let uri = uri.to_string();
+        let reducer = move |mut context: StaticContext| {
+            context.default_collation = uri;
+            context
+        };
+        self.reducers.push(Box::new(reducer));
+        self
+    }
+
+    /// Register a decimal format, so that `fn:format-number` can name it.
+    ///
+    /// Registering an existing name replaces the previous format.
+    pub fn with_decimal_format(mut self, name: ExpandedName, format: DecimalFormat) -> Self {
+        let reducer = move |mut context: StaticContext| {
+            context.decimal_formats.insert(name, format);
+            context
+        };
+        self.reducers.push(Box::new(reducer));
+        self
+    }
+
+    /// Set the decimal format used when an expression does not name one.
+    ///
+    /// See [StaticContext::default_decimal_format].
+    pub fn with_default_decimal_format(mut self, format: DecimalFormat) -> Self {
+        let reducer = move |mut context: StaticContext| {
+            context.default_decimal_format = format;
+            context
+        };
+        self.reducers.push(Box::new(reducer));
+        self
+    }
+
+    /// Turn XPath 1.0 compatibility mode on or off.
+    ///
+    /// See [StaticContext::xpath_1_0_compatibility].
+    pub fn with_xpath_1_0_compatibility(mut self, enabled: bool) -> Self {
+        let reducer = move |mut context: StaticContext| {
+            context.xpath_1_0_compatibility = enabled;
+            context
+        };
+        self.reducers.push(Box::new(reducer));
+        self
+    }
+
+    /// Register a function that can be called from an expression.
+    ///
+    /// See [FunctionRegistry::register].
+    pub fn with_function<F>(mut self, name: ExpandedName, arity: usize, function: F) -> Self
+    where
+        F: for<'tree> Fn(
+                &'tree XpathItemTree,
+                Vec<XpathItemSet<'tree>>,
+            ) -> Result<XpathItemSet<'tree>, ExpressionApplyError>
+            + Send
+            + Sync
+            + 'static,
+    {
+        let reducer = move |mut context: StaticContext| {
+            context.functions.register(name, arity, function);
+            context
+        };
+        self.reducers.push(Box::new(reducer));
+        self
+    }
+
+    /// Build the [StaticContext].
+    pub fn build(self) -> StaticContext {
+        self.reducers
+            .into_iter()
+            .fold(StaticContext::new(), |context, f| f(context))
+    }
+}
+
+impl Default for StaticContextBuilder {
+    fn default() -> Self {
+        StaticContextBuilder::new()
+    }
+}
+
+/// Context that is only known when an expression is evaluated.
+///
+/// <https://www.w3.org/TR/2017/REC-xpath-31-20170321/#eval_context>
+#[derive(Clone, Default)]
+pub struct DynamicContext {
+    /// The static context the expression was prepared in.
+    static_context: StaticContext,
+
+    /// Limits on the resources used by each evaluation.
+    limits: EvaluationLimits,
+
+    /// Loads resources named by URIs, if set.
+    resolver: Option<Arc<dyn ResourceResolver>>,
+}
+
+impl DynamicContext {
+    /// Create a new [DynamicContext] from a [StaticContext].
+    pub fn new(static_context: StaticContext) -> Self {
+        Self {
+            static_context,
+            limits: EvaluationLimits::new(),
+            resolver: None,
+        }
+    }
+
+    /// The static context the expression was prepared in.
+    pub fn static_context(&self) -> &StaticContext {
+        &self.static_context
+    }
+
+    /// Limits on the resources used by each evaluation.
+    pub fn limits(&self) -> &EvaluationLimits {
+        &self.limits
+    }
+
+    /// Loads resources named by URIs.
+    pub fn resolver(&self) -> Option<&dyn ResourceResolver> {
+        self.resolver.as_deref()
+    }
+
+    /// Get the resolver used to load the resource at the URI.
+    ///
+    /// Fails with [ErrorCode::FODC0002] if no resolver is set.
+    pub(crate) fn resolver_for(
+        &self,
+        uri: &str,
+    ) -> Result<&dyn ResourceResolver, ExpressionApplyError> {
+        self.resolver().ok_or_else(|| {
+            ExpressionApplyError::with_code(
+                ErrorCode::FODC0002,
+                format!("cannot load {}: no resource resolver is set", uri),
+            )
+        })
+    }
+}
+
+/// Builds a [DynamicContext].
+///
+/// See [DynamicContext::new] for the default values used if not set by the builder.
+pub struct DynamicContextBuilder {
+    static_context: StaticContext,
+    reducers: Vec<Box<dyn FnOnce(DynamicContext) -> DynamicContext>>,
+}
+
+impl DynamicContextBuilder {
+    /// Creates a new [DynamicContextBuilder] for expressions prepared in the given [StaticContext].
+    pub fn new(static_context: StaticContext) -> Self {
+        Self {
+            static_context,
+            reducers: Vec::new(),
+        }
+    }
+
+    /// Limit the resources used by each evaluation.
+    ///
+    /// See [EvaluationLimits].
+    pub fn with_limits(mut self, limits: EvaluationLimits) -> Self {
+        let reducer = move |mut context: DynamicContext| {
+            context.limits = limits;
+            context
+        };
+        self.reducers.push(Box::new(reducer));
+        self
+    }
+
+    /// Set the resolver used to load resources named by URIs.
+    ///
+    /// See [ResourceResolver].
+    pub fn with_resolver(mut self, resolver: impl ResourceResolver + 'static) -> Self {
+        let resolver: Arc<dyn ResourceResolver> = Arc::new(resolver);
+        let reducer = move |mut context: DynamicContext| {
+            context.resolver = Some(resolver);
+            context
+        };
+        self.reducers.push(Box::new(reducer));
+        self
+    }
+
+    /// Build the [DynamicContext].
+    pub fn build(self) -> DynamicContext {
+        self.reducers
+            .into_iter()
+            .fold(DynamicContext::new(self.static_context), |context, f| {
+                f(context)
+            })
+    }
+}
+
+/// The context used when no context is given to [Xpath::apply](crate::xpath::Xpath::apply).
+pub(crate) static DEFAULT_DYNAMIC_CONTEXT: Lazy<DynamicContext> =
+    Lazy::new(DynamicContext::default);
+
+#[cfg(test)]
+mod tests {
+    use crate::xpath::grammar::xml_names::PrefixedName;
+
+    use super::*;
+
+    #[test]
+    fn resolve_function_name_should_use_default_namespace_for_unprefixed_name() {
+        // arrange
+        let context = StaticContext::new();
+        let name = EQName::QName(QName::UnprefixedName(String::from("contains")));
+
+        // act
+        let expanded_name = context.resolve_function_name(&name).unwrap();
+
+        // assert
+        assert_eq!(expanded_name, ExpandedName::new(FN_NAMESPACE, "contains"));
+    }
+
+    #[test]
+    fn resolve_function_name_should_use_namespace_from_builder() {
+        // arrange
+        let context = StaticContextBuilder::new()
+            .with_namespace("ext", "urn:example")
+            .build();
+        let name = EQName::QName(QName::PrefixedName(PrefixedName {
+            prefix: String::from("ext"),
+            local_part: String::from("price"),
+        }));
+
+        // act
+        let expanded_name = context.resolve_function_name(&name).unwrap();
+
+        // assert
+        assert_eq!(expanded_name, ExpandedName::new("urn:example", "price"));
+    }
+
+    #[test]
+    fn resolve_function_name_should_fail_for_undeclared_prefix() {
+        // arrange
+        let context = StaticContext::new();
+        let name = EQName::QName(QName::PrefixedName(PrefixedName {
+            prefix: String::from("nope"),
+            local_part: String::from("price"),
+        }));
+
+        // act
+        let result = context.resolve_function_name(&name);
+
+        // assert
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,246 @@
+//! Decimal formats control the characters used by `fn:format-number`.
+//!
+//! <https://www.w3.org/TR/xpath-31/#dt-static-decimal-formats>
+//!
+//! Every [StaticContext](crate::xpath::StaticContext) has a default decimal format,
+//! which uses the characters of `1,234.5`. Register other formats with
+//! [StaticContextBuilder::with_decimal_format](crate::xpath::context::StaticContextBuilder::with_decimal_format),
+//! and name them in the third argument of `fn:format-number`.
+//!
+//! # Example: format prices the way they are written in Germany
+//!
+//! ```rust
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! use skyscraper::html;
+//! use skyscraper::xpath::{
+//!     self,
+//!     context::{DynamicContext, StaticContextBuilder},
+//!     decimal_format::DecimalFormat,
+//!     functions::ExpandedName,
+//!     XpathItemTree,
+//! };
+//!
+//! let german = DecimalFormat::new()
+//!     .with_decimal_separator(',')
+//!     .with_grouping_separator('.');
+//! let static_context = StaticContextBuilder::new()
+//!     .with_decimal_format(ExpandedName::new("", "de"), german)
+//!     .build();
+//! let context = DynamicContext::new(static_context);
+//!
+//! let document = html::parse("<html><span>1234.5</span></html>")?;
+//! let tree = XpathItemTree::from(&document);
+//!
+//! let xpath = xpath::parse_with_context(
+//!     "format-number(//span, '#.##0,00', 'de')",
+//!     context.static_context(),
+//! )?;
+//! let items = xpath.apply_with_context(&tree, &context)?;
+//!
+//! assert_eq!(items[0].string_value(&tree), "1.234,50");
+//! # Ok(())
+//! # }
+//! ```
+
+/// The characters and symbols used to format numbers.
+///
+/// <https://www.w3.org/TR/xpath-31/#id-decimal-format-properties>
+///
+/// The characters used in pictures, which are the decimal separator, exponent separator,
+/// grouping separator, digit sign, pattern separator and the ten digits,
+/// should all be different from each other.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct DecimalFormat {
+    decimal_separator: char,
+    exponent_separator: char,
+    grouping_separator: char,
+    percent: char,
+    per_mille: char,
+    zero_digit: char,
+    digit: char,
+    pattern_separator: char,
+    infinity: String,
+    nan: String,
+    minus_sign: char,
+}
+
+impl DecimalFormat {
+    /// Create a new [DecimalFormat] with the default characters and symbols.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the character that separates the integer part from the fractional part.
+    ///
+    /// Defaults to `.`.
+    pub fn with_decimal_separator(mut self, decimal_separator: char) -> Self {
+        self.decimal_separator = decimal_separator;
+        self
+    }
+
+    /// Set the character that separates the mantissa from the exponent.
+    ///
+    /// Defaults to `e`.
+    pub fn with_exponent_separator(mut self, exponent_separator: char) -> Self {
+        self.exponent_separator = exponent_separator;
+        self
+    }
+
+    /// Set the character that separates groups of digits, such as thousands.
+    ///
+    /// Defaults to `,`.
+    pub fn with_grouping_separator(mut self, grouping_separator: char) -> Self {
+        self.grouping_separator = grouping_separator;
+        self
+    }
+
+    /// Set the character that multiplies the number by 100 when it is in a picture.
+    ///
+    /// Defaults to `%`.
+    pub fn with_percent(mut self, percent: char) -> Self {
+        self.percent = percent;
+        self
+    }
+
+    /// Set the character that multiplies the number by 1000 when it is in a picture.
+    ///
+    /// Defaults to `‰`.
+    pub fn with_per_mille(mut self, per_mille: char) -> Self {
+        self.per_mille = per_mille;
+        self
+    }
+
+    /// Set the character used for the digit zero.
+    ///
+    /// The other digits are the nine characters that follow it, so this should be the zero
+    /// of a family of decimal digits, such as `٠` for Arabic-Indic digits. Defaults to `0`.
+    pub fn with_zero_digit(mut self, zero_digit: char) -> Self {
+        self.zero_digit = zero_digit;
+        self
+    }
+
+    /// Set the character that marks an optional digit in a picture.
+    ///
+    /// Defaults to `#`.
+    pub fn with_digit(mut self, digit: char) -> Self {
+        self.digit = digit;
+        self
+    }
+
+    /// Set the character that separates the pictures for positive and negative numbers.
+    ///
+    /// Defaults to `;`.
+    pub fn with_pattern_separator(mut self, pattern_separator: char) -> Self {
+        self.pattern_separator = pattern_separator;
+        self
+    }
+
+    /// Set the text used for positive and negative infinity.
+    ///
+    /// Defaults to `Infinity`.
+    pub fn with_infinity(mut self, infinity: &str) -> Self {
+        self.infinity = infinity.to_string();
+        self
+    }
+
+    /// Set the text used for `NaN`, which is also used for the empty sequence.
+    ///
+    /// Defaults to `NaN`.
+    pub fn with_nan(mut self, nan: &str) -> Self {
+        self.nan = nan.to_string();
+        self
+    }
+
+    /// Set the character that marks negative numbers when the picture does not say how.
+    ///
+    /// Defaults to `-`.
+    pub fn with_minus_sign(mut self, minus_sign: char) -> Self {
+        self.minus_sign = minus_sign;
+        self
+    }
+
+    /// The character that separates the integer part from the fractional part.
+    pub fn decimal_separator(&self) -> char {
+        self.decimal_separator
+    }
+
+    /// The character that separates the mantissa from the exponent.
+    pub fn exponent_separator(&self) -> char {
+        self.exponent_separator
+    }
+
+    /// The character that separates groups of digits.
+    pub fn grouping_separator(&self) -> char {
+        self.grouping_separator
+    }
+
+    /// The character that multiplies the number by 100.
+    pub fn percent(&self) -> char {
+        self.percent
+    }
+
+    /// The character that multiplies the number by 1000.
+    pub fn per_mille(&self) -> char {
+        self.per_mille
+    }
+
+    /// The character used for the digit zero.
+    pub fn zero_digit(&self) -> char {
+        self.zero_digit
+    }
+
+    /// The character that marks an optional digit.
+    pub fn digit(&self) -> char {
+        self.digit
+    }
+
+    /// The character that separates the pictures for positive and negative numbers.
+    pub fn pattern_separator(&self) -> char {
+        self.pattern_separator
+    }
+
+    /// The text used for infinity.
+    pub fn infinity(&self) -> &str {
+        &self.infinity
+    }
+
+    /// The text used for `NaN`.
+    pub fn nan(&self) -> &str {
+        &self.nan
+    }
+
+    /// The character that marks negative numbers.
+    pub fn minus_sign(&self) -> char {
+        self.minus_sign
+    }
+
+    /// Get the value of a digit in this format, if the character is one of its digits.
+    pub(crate) fn digit_value(&self, c: char) -> Option<u32> {
+        let value = (c as u32).checked_sub(self.zero_digit as u32)?;
+        (value < 10).then_some(value)
+    }
+
+    /// Get the character for a digit value from 0 to 9.
+    pub(crate) fn digit_char(&self, value: u32) -> char {
+        char::from_u32(self.zero_digit as u32 + value).unwrap_or('?')
+    }
+}
+
+impl Default for DecimalFormat {
+    fn default() -> Self {
+        Self {
+            decimal_separator: '.',
+            exponent_separator: 'e',
+            grouping_separator: ',',
+            percent: '%',
+            per_mille: '‰',
+            zero_digit: '0',
+            digit: '#',
+            pattern_separator: ';',
+            infinity: String::from("Infinity"),
+            nan: String::from("NaN"),
+            minus_sign: '-',
+        }
+    }
+}
@@ -0,0 +1,614 @@
+//! Trace how an [Xpath] expression is evaluated.
+//!
+//! [Xpath::explain] applies an expression like [Xpath::apply] does, and also records
+//! every step and predicate it evaluated: how many items went in, how many came out,
+//! a description of a few of the items that matched, and how long it took.
+//! This makes it easy to find the step that lost the node you expected,
+//! or the sub-expression that makes a query slow.
+//!
+//! Leading and double slashes are expanded before evaluation,
+//! so they show up in the trace as `fn:root` and `descendant-or-self::node()` steps.
+//! A step after a leading `//` that is looked up in the indexes of the tree,
+//! such as `//div[@id='main']`, shows up as a single step instead.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! use skyscraper::html;
+//! use skyscraper::xpath::{self, explain::TraceKind, XpathItemTree};
+//!
+//! let document = html::parse(r#"
+//!     <html>
+//!         <div data-kind="product"><a href="/1">One</a></div>
+//!         <div data-kind="banner"><a href="/ad">Ad</a></div>
+//!     </html>"#)?;
+//! let tree = XpathItemTree::from(&document);
+//!
+//! let xpath = xpath::parse("//div[@data-kind='product']/a")?;
+//! let explanation = xpath.explain(&tree)?;
+//!
+//! assert_eq!(explanation.result().len(), 1);
+//!
+//! let predicate = explanation
+//!     .entries()
+//!     .iter()
+//!     .find(|entry| entry.kind() == TraceKind::Predicate)
+//!     .unwrap();
+//! assert_eq!(predicate.expression(), "[@data-kind='product']");
+//! assert_eq!(predicate.input_count(), 2);
+//! assert_eq!(predicate.output_count(), 1);
+//!
+//! // Print a table of every step, or send it to the logger.
+//! println!("{}", explanation);
+//! explanation.log(log::Level::Debug);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    cell::RefCell,
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use indexmap::IndexMap;
+use log::log;
+
+use crate::xpath::{
+    context::DEFAULT_DYNAMIC_CONTEXT,
+    grammar::{data_model::XpathItem, XpathItemTreeNode},
+    limits::EvaluationBudget,
+    DynamicContext, ExpressionApplyError, Xpath, XpathExpressionContext, XpathItemSet,
+    XpathItemTree,
+};
+
+/// The most matched items kept as a sample for each trace entry.
+pub const SAMPLE_SIZE: usize = 5;
+
+/// The target of the records written by [Explanation::log].
+pub const LOG_TARGET: &str = "skyscraper::xpath::explain";
+
+impl Xpath {
+    /// Apply the expression to the given tree and trace each step of its evaluation.
+    ///
+    /// The result is the same as [Xpath::apply],
+    /// but evaluation is slower since every step and predicate is recorded.
+    pub fn explain<'tree>(
+        &self,
+        item_tree: &'tree XpathItemTree,
+    ) -> Result<Explanation<'tree>, ExpressionApplyError> {
+        self.explain_with_context(item_tree, &DEFAULT_DYNAMIC_CONTEXT)
+    }
+
+    /// Apply the expression to the given tree using the given [DynamicContext]
+    /// and trace each step of its evaluation.
+    ///
+    /// The result is the same as [Xpath::apply_with_context].
+    pub fn explain_with_context<'tree>(
+        &self,
+        item_tree: &'tree XpathItemTree,
+        dynamic_context: &DynamicContext,
+    ) -> Result<Explanation<'tree>, ExpressionApplyError> {
+        let budget = EvaluationBudget::new(dynamic_context.limits());
+        let tracer = Tracer::new();
+        let start = Instant::now();
+
+        let context = XpathExpressionContext::new_single(
+            item_tree,
+            dynamic_context,
+            &budget,
+            Some(&tracer),
+            XpathItem::Node(item_tree.root()),
+            true,
+        );
+        let mut result = self.compile_for_explain(dynamic_context).eval(&context)?;
+        result.sort();
+
+        Ok(Explanation {
+            expression: self.to_string(),
+            result,
+            entries: tracer.into_entries(),
+            elapsed: start.elapsed(),
+        })
+    }
+}
+
+/// The result of [Xpath::explain].
+#[derive(Debug)]
+pub struct Explanation<'tree> {
+    expression: String,
+    result: XpathItemSet<'tree>,
+    entries: Vec<TraceEntry>,
+    elapsed: Duration,
+}
+
+impl<'tree> Explanation<'tree> {
+    /// The expression that was explained.
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// The result of the expression, the same as [Xpath::apply] would return.
+    pub fn result(&self) -> &XpathItemSet<'tree> {
+        &self.result
+    }
+
+    /// Consume the explanation and return the result of the expression.
+    pub fn into_result(self) -> XpathItemSet<'tree> {
+        self.result
+    }
+
+    /// The steps and predicates that were evaluated, in the order they were first reached.
+    pub fn entries(&self) -> &[TraceEntry] {
+        &self.entries
+    }
+
+    /// The time taken to evaluate the whole expression.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Write one record for the expression and one for each entry to the [log] crate.
+    ///
+    /// Records use the [LOG_TARGET] target and `key=value` fields,
+    /// so they can be filtered and parsed by structured loggers.
+    pub fn log(&self, level: log::Level) {
+        log!(
+            target: LOG_TARGET,
+            level,
+            "expression={:?} results={} elapsed_us={}",
+            self.expression,
+            self.result.len(),
+            self.elapsed.as_micros()
+        );
+
+        for entry in self.entries.iter() {
+            log!(
+                target: LOG_TARGET,
+                level,
+                "kind={} expression={:?} calls={} input={} output={} elapsed_us={} sample={:?}",
+                entry.kind,
+                entry.expression,
+                entry.calls,
+                entry.input_count,
+                entry.output_count,
+                entry.elapsed.as_micros(),
+                entry.sample
+            );
+        }
+    }
+}
+
+impl Display for Explanation<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} ({} results in {:?})",
+            self.expression,
+            self.result.len(),
+            self.elapsed
+        )?;
+
+        for entry in self.entries.iter() {
+            write!(
+                f,
+                "  {:<9} {:>6} -> {:<6} {:>12?}  {}",
+                entry.kind, entry.input_count, entry.output_count, entry.elapsed, entry.expression
+            )?;
+
+            if !entry.sample.is_empty() {
+                write!(f, "  e.g. {}", entry.sample.join(", "))?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether a [TraceEntry] is for a step or a predicate.
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum TraceKind {
+    /// An axis step such as `child::div`, or a filter expression such as `(//a)[1]`.
+    Step,
+
+    /// A predicate such as `[@id='main']`.
+    Predicate,
+}
+
+impl Display for TraceKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TraceKind::Step => write!(f, "step"),
+            TraceKind::Predicate => write!(f, "predicate"),
+        }
+    }
+}
+
+/// What happened when a step or predicate was evaluated.
+///
+/// A step is evaluated once for each item in its context,
+/// and a predicate once for each item it filters.
+/// Each step and predicate in the expression has its own entry,
+/// even if another one has the same text, such as the two `li` steps of `//ul/li/ul/li`.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TraceEntry {
+    kind: TraceKind,
+    expression: String,
+    calls: usize,
+    input_count: usize,
+    output_count: usize,
+    sample: Vec<String>,
+    elapsed: Duration,
+}
+
+impl TraceEntry {
+    /// Whether this is a step or a predicate.
+    pub fn kind(&self) -> TraceKind {
+        self.kind
+    }
+
+    /// The text of the step or predicate, which labels the entry.
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// How many times the step or predicate was evaluated.
+    pub fn calls(&self) -> usize {
+        self.calls
+    }
+
+    /// How many items were given to the step or predicate.
+    ///
+    /// For a step this is the number of context items it was evaluated from,
+    /// and for a predicate it is the number of items it filtered.
+    pub fn input_count(&self) -> usize {
+        self.input_count
+    }
+
+    /// How many items the step returned, or how many items the predicate kept.
+    ///
+    /// Items returned from more than one context item are counted each time.
+    pub fn output_count(&self) -> usize {
+        self.output_count
+    }
+
+    /// Short descriptions of up to [SAMPLE_SIZE] items in the output, such as `<div id="main">`.
+    pub fn sample(&self) -> &[String] {
+        &self.sample
+    }
+
+    /// The total time spent evaluating the step or predicate.
+    ///
+    /// This includes the time spent in any nested steps and predicates.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+/// Identifies a step or predicate in a trace.
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub(crate) enum TraceId {
+    /// A step or predicate of a compiled plan, numbered in order when the plan is compiled.
+    Plan(usize),
+
+    /// A step or predicate evaluated from its syntax tree, identified by the address of its node.
+    Node(usize),
+}
+
+impl TraceId {
+    /// Identify a step or predicate that is evaluated from its syntax tree.
+    pub fn of<T>(node: &T) -> Self {
+        TraceId::Node(node as *const T as usize)
+    }
+}
+
+/// Records trace entries during an evaluation.
+pub(crate) struct Tracer {
+    entries: RefCell<IndexMap<(TraceKind, TraceId), TraceEntry>>,
+}
+
+impl Tracer {
+    pub fn new() -> Self {
+        Self {
+            entries: RefCell::new(IndexMap::new()),
+        }
+    }
+
+    /// Evaluate a step and record its output.
+    pub fn trace_step<'tree>(
+        &self,
+        id: TraceId,
+        step: &impl Display,
+        item_tree: &XpathItemTree,
+        eval: impl FnOnce() -> Result<XpathItemSet<'tree>, ExpressionApplyError>,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        let key = self.reserve(TraceKind::Step, id, step);
+        let start = Instant::now();
+        let result = eval()?;
+        self.record(
+            &key,
+            item_tree,
+            1,
+            result.len(),
+            result.iter(),
+            start.elapsed(),
+        );
+
+        Ok(result)
+    }
+
+    /// Evaluate a predicate against the context item and record whether it was kept.
+    pub fn trace_predicate<'tree>(
+        &self,
+        id: TraceId,
+        predicate: &impl Display,
+        item_tree: &XpathItemTree,
+        item: &XpathItem<'tree>,
+        eval: impl FnOnce() -> Result<bool, ExpressionApplyError>,
+    ) -> Result<bool, ExpressionApplyError> {
+        let key = self.reserve(TraceKind::Predicate, id, predicate);
+        let start = Instant::now();
+        let is_match = eval()?;
+        let kept: &[XpathItem<'tree>] = if is_match {
+            std::slice::from_ref(item)
+        } else {
+            &[]
+        };
+        self.record(&key, item_tree, 1, kept.len(), kept.iter(), start.elapsed());
+
+        Ok(is_match)
+    }
+
+    pub fn into_entries(self) -> Vec<TraceEntry> {
+        self.entries.into_inner().into_values().collect()
+    }
+
+    /// Add an empty entry if there is none yet, so outer steps come before the steps nested in them.
+    fn reserve(
+        &self,
+        kind: TraceKind,
+        id: TraceId,
+        expression: &impl Display,
+    ) -> (TraceKind, TraceId) {
+        let key = (kind, id);
+        self.entries
+            .borrow_mut()
+            .entry(key)
+            .or_insert_with(|| TraceEntry {
+                kind,
+                expression: expression.to_string(),
+                calls: 0,
+                input_count: 0,
+                output_count: 0,
+                sample: Vec::new(),
+                elapsed: Duration::ZERO,
+            });
+
+        key
+    }
+
+    fn record<'a, 'tree: 'a>(
+        &self,
+        key: &(TraceKind, TraceId),
+        item_tree: &XpathItemTree,
+        input_count: usize,
+        output_count: usize,
+        output: impl Iterator<Item = &'a XpathItem<'tree>>,
+        elapsed: Duration,
+    ) {
+        let mut entries = self.entries.borrow_mut();
+        let entry = entries.get_mut(key).expect("entry was reserved");
+        entry.calls += 1;
+        entry.input_count += input_count;
+        entry.output_count += output_count;
+        entry.elapsed += elapsed;
+
+        let remaining = SAMPLE_SIZE.saturating_sub(entry.sample.len());
+        entry.sample.extend(
+            output
+                .take(remaining)
+                .map(|item| describe_item(item, item_tree)),
+        );
+    }
+}
+
+/// A short description of an item for traces, such as `<div id="main">`.
+fn describe_item(item: &XpathItem<'_>, item_tree: &XpathItemTree) -> String {
+    match item {
+        XpathItem::Node(XpathItemTreeNode::ElementNode(element)) => {
+            let mut text = format!("<{}", element.name);
+            for attribute in element.attributes(item_tree) {
+                if attribute.name == "id" || attribute.name == "class" {
+                    text.push_str(&format!(" {}", attribute));
+                }
+            }
+            text.push('>');
+            text
+        }
+        XpathItem::Node(XpathItemTreeNode::AttributeNode(attribute)) => format!("@{}", attribute),
+        XpathItem::Node(XpathItemTreeNode::DocumentNode(_)) => String::from("/"),
+        XpathItem::Node(XpathItemTreeNode::TextNode(text)) => format!("{:?}", text.content.trim()),
+        XpathItem::Node(XpathItemTreeNode::CommentNode(comment)) => comment.to_string(),
+        XpathItem::Node(XpathItemTreeNode::PINode(pi)) => pi.to_string(),
+        XpathItem::Function(function) => function.to_string(),
+        XpathItem::AnyAtomicType(atomic) => atomic.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        html,
+        xpath::{
+            self,
+            context::DynamicContextBuilder,
+            limits::{EvaluationLimit, EvaluationLimits},
+            ErrorCode, StaticContext,
+        },
+    };
+
+    use super::*;
+
+    fn tree(text: &str) -> XpathItemTree {
+        XpathItemTree::from(&html::parse(text).unwrap())
+    }
+
+    fn entry<'a>(explanation: &'a Explanation<'_>, text: &str) -> &'a TraceEntry {
+        explanation
+            .entries()
+            .iter()
+            .find(|entry| entry.expression() == text)
+            .unwrap_or_else(|| panic!("no entry for {}", text))
+    }
+
+    #[test]
+    fn explain_should_return_same_result_as_apply() {
+        // arrange
+        let tree = tree("<html><div><p>1</p><p>2</p></div><p>3</p></html>");
+        let xpath = xpath::parse("//div/p").unwrap();
+
+        // act
+        let explanation = xpath.explain(&tree).unwrap();
+
+        // assert
+        assert_eq!(explanation.result(), &xpath.apply(&tree).unwrap());
+        assert_eq!(explanation.expression(), "//div/p");
+    }
+
+    #[test]
+    fn explain_should_count_step_input_and_output() {
+        // arrange
+        let tree = tree("<html><div><p>1</p><p>2</p></div><div><p>3</p></div></html>");
+        let xpath = xpath::parse("/html/div/p").unwrap();
+
+        // act
+        let explanation = xpath.explain(&tree).unwrap();
+
+        // assert
+        let div = entry(&explanation, "div");
+        assert_eq!(div.kind(), TraceKind::Step);
+        assert_eq!(div.input_count(), 1);
+        assert_eq!(div.output_count(), 2);
+
+        let p = entry(&explanation, "p");
+        assert_eq!(p.calls(), 2);
+        assert_eq!(p.input_count(), 2);
+        assert_eq!(p.output_count(), 3);
+        assert_eq!(p.sample().len(), 3);
+    }
+
+    #[test]
+    fn explain_should_count_predicate_input_and_output() {
+        // arrange
+        let tree = tree(r#"<html><a id="1"></a><a id="2"></a><a id="3"></a></html>"#);
+        let xpath = xpath::parse("/html/a[@id='2']").unwrap();
+
+        // act
+        let explanation = xpath.explain(&tree).unwrap();
+
+        // assert
+        let predicate = entry(&explanation, "[@id='2']");
+        assert_eq!(predicate.kind(), TraceKind::Predicate);
+        assert_eq!(predicate.input_count(), 3);
+        assert_eq!(predicate.output_count(), 1);
+        assert_eq!(predicate.sample(), [r#"<a id="2">"#]);
+    }
+
+    #[test]
+    fn explain_should_keep_steps_with_the_same_text_apart() {
+        // arrange
+        let tree = tree("<html><ul><li>1<ul><li>2</li><li>3</li></ul></li><li>4</li></ul></html>");
+        let xpath = xpath::parse("/html/ul/li/ul/li").unwrap();
+
+        // act
+        let explanation = xpath.explain(&tree).unwrap();
+
+        // assert
+        let li: Vec<&TraceEntry> = explanation
+            .entries()
+            .iter()
+            .filter(|entry| entry.expression() == "li")
+            .collect();
+        assert_eq!(li.len(), 2);
+        assert_eq!(li[0].calls(), 1);
+        assert_eq!(li[0].input_count(), 1);
+        assert_eq!(li[0].output_count(), 2);
+        assert_eq!(li[1].calls(), 1);
+        assert_eq!(li[1].input_count(), 1);
+        assert_eq!(li[1].output_count(), 2);
+    }
+
+    #[test]
+    fn explain_should_limit_sample_size() {
+        // arrange
+        let tree = tree("<html><p></p><p></p><p></p><p></p><p></p><p></p><p></p></html>");
+        let xpath = xpath::parse("/html/p").unwrap();
+
+        // act
+        let explanation = xpath.explain(&tree).unwrap();
+
+        // assert
+        let p = entry(&explanation, "p");
+        assert_eq!(p.output_count(), 7);
+        assert_eq!(p.sample().len(), SAMPLE_SIZE);
+    }
+
+    #[test]
+    fn explain_should_list_outer_steps_before_nested_steps() {
+        // arrange
+        let tree = tree("<html><div><a></a></div></html>");
+        let xpath = xpath::parse("//div[a]").unwrap();
+
+        // act
+        let explanation = xpath.explain(&tree).unwrap();
+
+        // assert
+        let expressions: Vec<&str> = explanation
+            .entries()
+            .iter()
+            .map(|entry| entry.expression())
+            .collect();
+        let step = expressions.iter().position(|x| *x == "div[a]").unwrap();
+        let predicate = expressions.iter().position(|x| *x == "[a]").unwrap();
+        let nested = expressions.iter().position(|x| *x == "a").unwrap();
+        assert!(step < predicate);
+        assert!(predicate < nested);
+    }
+
+    #[test]
+    fn explain_with_context_should_respect_limits() {
+        // arrange
+        let tree = tree("<html><p></p><p></p></html>");
+        let xpath = xpath::parse("//p").unwrap();
+        let context = DynamicContextBuilder::new(StaticContext::new())
+            .with_limits(EvaluationLimits::new().with_max_node_visits(1))
+            .build();
+
+        // act
+        let err = xpath.explain_with_context(&tree, &context).unwrap_err();
+
+        // assert
+        assert_eq!(
+            err.code(),
+            &ErrorCode::LimitExceeded(EvaluationLimit::NodeVisits)
+        );
+    }
+
+    #[test]
+    fn display_should_describe_each_entry() {
+        // arrange
+        let tree = tree(r#"<html><div id="main"></div></html>"#);
+        let xpath = xpath::parse("//div").unwrap();
+
+        // act
+        let text = xpath.explain(&tree).unwrap().to_string();
+
+        // assert
+        assert!(text.starts_with("//div (1 results in "));
+        assert!(text.contains(r#"e.g. <div id="main">"#));
+    }
+}
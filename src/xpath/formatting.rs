@@ -0,0 +1,652 @@
+//! Canonical and multi-line formatting of [Xpath] expressions.
+
+use crate::xpath::{
+    grammar::{
+        expressions::{
+            common::{Argument, ArgumentList},
+            comparison_expressions::ComparisonExpr,
+            logical_expressions::{AndExpr, OrExpr},
+            path_expressions::{
+                abbreviated_syntax::AbbrevForwardStep,
+                steps::{
+                    axes::{forward_axis::ForwardAxis, reverse_axis::ReverseAxis},
+                    axis_step::{AxisStep, AxisStepType},
+                    forward_step::ForwardStep,
+                    node_tests::NodeTest,
+                    reverse_step::ReverseStep,
+                    step_expr::StepExpr,
+                },
+                PathExpr, PathSeparator, RelativePathExpr, StepPair,
+            },
+            postfix_expressions::{PostfixExpr, PostfixExprItem, Predicate},
+            primary_expressions::{
+                literals::Literal, parenthesized_expressions::ParenthesizedExpr, PrimaryExpr,
+            },
+            Expr, ExprSingle,
+        },
+        terminal_symbols::StringLiteral,
+        types::KindTest,
+    },
+    visit::{self, VisitorMut},
+    Xpath,
+};
+
+impl Xpath {
+    /// Rewrite the expression into a canonical form.
+    ///
+    /// Expressions that are spelled differently but are equivalent by definition,
+    /// such as `//a` and `/descendant-or-self::node()/child::a`, or `'x'` and `"x"`,
+    /// normalize to the same expression, so their [Display](std::fmt::Display) strings
+    /// can be used to compare or deduplicate expressions.
+    ///
+    /// The canonical form uses the abbreviated syntax wherever the specification defines one:
+    /// `child::` is left out, `attribute::` is written `@`, `parent::node()` is written `..`
+    /// and `/descendant-or-self::node()/` is written `//`.
+    /// String literals are single quoted unless they contain single quotes but no double quotes.
+    /// Decimals are written with a point and doubles in the canonical form of `xs:double`,
+    /// such as `1.5E10`, so that numbers parse back to the same type.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skyscraper::xpath;
+    ///
+    /// let abbreviated = xpath::parse("//div[@class = 'item']/..").unwrap();
+    /// let full = xpath::parse(
+    ///     r#"/descendant-or-self::node()/child::div[attribute::class="item"]/parent::node()"#,
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(abbreviated.normalize(), full.normalize());
+    /// assert_eq!(full.normalize().to_string(), "//div[@class='item']/..");
+    /// ```
+    pub fn normalize(&self) -> Xpath {
+        let mut xpath = self.clone();
+        Normalizer.visit_xpath_mut(&mut xpath);
+        xpath
+    }
+
+    /// Format the expression over multiple lines, so that lines are at most `max_width` long
+    /// where possible.
+    ///
+    /// Sequences, `or` and `and` operands, path steps, predicates and function arguments
+    /// are put on their own lines when they do not fit, and nested parts are indented
+    /// by four spaces. Parts that fit are written as [Display](std::fmt::Display) writes them.
+    /// The result parses to the same expression.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use skyscraper::xpath;
+    ///
+    /// let xpath = xpath::parse(
+    ///     "//div[contains(@class, 'product')]/a[starts-with(@href, 'https://')]/@href",
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(
+    ///     xpath.to_pretty_string(40),
+    ///     "//div[contains(@class, 'product')]\n    /a[starts-with(@href, 'https://')]\n    /@href"
+    /// );
+    /// assert_eq!(xpath::parse(&xpath.to_pretty_string(40)).unwrap(), xpath);
+    /// ```
+    pub fn to_pretty_string(&self, max_width: usize) -> String {
+        PrettyPrinter { max_width }.expr(&self.0, 0)
+    }
+}
+
+/// Rewrites expressions into the canonical form described by [Xpath::normalize].
+struct Normalizer;
+
+impl Normalizer {
+    /// Whether a step is `descendant-or-self::node()`, which `//` abbreviates.
+    fn is_descendant_or_self_node(step: &StepExpr) -> bool {
+        matches!(
+            step,
+            StepExpr::AxisStep(AxisStep {
+                step_type: AxisStepType::ForwardStep(ForwardStep::Full(
+                    ForwardAxis::DescendantOrSelf,
+                    NodeTest::KindTest(KindTest::AnyKindTest),
+                )),
+                predicates,
+            }) if predicates.is_empty()
+        )
+    }
+
+    /// Whether a step on the child axis with this node test can leave out `child::`.
+    ///
+    /// An abbreviated step with an attribute or namespace node test is on a different axis.
+    fn can_abbreviate_child(node_test: &NodeTest) -> bool {
+        !matches!(
+            node_test,
+            NodeTest::KindTest(
+                KindTest::AttributeTest(_)
+                    | KindTest::SchemaAttributeTest(_)
+                    | KindTest::NamespaceNodeTest
+            )
+        )
+    }
+}
+
+impl VisitorMut for Normalizer {
+    fn visit_path_expr_mut(&mut self, expr: &mut PathExpr) {
+        visit::walk_path_expr_mut(self, expr);
+
+        // `/descendant-or-self::node()/a` is written `//a`.
+        if let PathExpr::LeadingSlash(Some(relative)) = expr {
+            let abbreviate = Self::is_descendant_or_self_node(&relative.expr)
+                && matches!(
+                    relative.items.first(),
+                    Some(StepPair(PathSeparator::Slash, _))
+                );
+
+            if abbreviate {
+                let StepPair(_, first) = relative.items.remove(0);
+                relative.expr = first;
+                *expr = PathExpr::LeadingDoubleSlash(relative.clone());
+            }
+        }
+    }
+
+    fn visit_relative_path_expr_mut(&mut self, expr: &mut RelativePathExpr) {
+        visit::walk_relative_path_expr_mut(self, expr);
+
+        // `a/descendant-or-self::node()/b` is written `a//b`.
+        let mut items: Vec<StepPair> = Vec::with_capacity(expr.items.len());
+        for item in expr.items.drain(..) {
+            match (items.last(), &item) {
+                (
+                    Some(StepPair(PathSeparator::Slash, previous)),
+                    StepPair(PathSeparator::Slash, _),
+                ) if Self::is_descendant_or_self_node(previous) => {
+                    items.pop();
+                    items.push(StepPair(PathSeparator::DoubleSlash, item.1));
+                }
+                _ => items.push(item),
+            }
+        }
+
+        expr.items = items;
+    }
+
+    fn visit_axis_step_mut(&mut self, step: &mut AxisStep) {
+        visit::walk_axis_step_mut(self, step);
+
+        let abbreviated = match &step.step_type {
+            AxisStepType::ForwardStep(ForwardStep::Full(ForwardAxis::Child, node_test))
+                if Self::can_abbreviate_child(node_test) =>
+            {
+                Some(AxisStepType::ForwardStep(ForwardStep::Abbreviated(
+                    AbbrevForwardStep {
+                        has_at: false,
+                        node_test: node_test.clone(),
+                    },
+                )))
+            }
+            AxisStepType::ForwardStep(ForwardStep::Full(ForwardAxis::Attribute, node_test)) => {
+                Some(AxisStepType::ForwardStep(ForwardStep::Abbreviated(
+                    AbbrevForwardStep {
+                        has_at: true,
+                        node_test: node_test.clone(),
+                    },
+                )))
+            }
+            AxisStepType::ReverseStep(ReverseStep::Full(
+                ReverseAxis::Parent,
+                NodeTest::KindTest(KindTest::AnyKindTest),
+            )) => Some(AxisStepType::ReverseStep(ReverseStep::Abbreviated)),
+            _ => None,
+        };
+
+        if let Some(step_type) = abbreviated {
+            step.step_type = step_type;
+        }
+    }
+
+    fn visit_literal_mut(&mut self, literal: &mut Literal) {
+        if let Literal::StringLiteral(x) = literal {
+            *x = StringLiteral::new(&x.value);
+        }
+    }
+}
+
+/// Writes expressions over multiple lines, as described by [Xpath::to_pretty_string].
+///
+/// Each method writes a node starting at the current position of the line,
+/// either returning its text or appending it to the text written so far,
+/// with every line after the first indented by `indent` levels.
+struct PrettyPrinter {
+    max_width: usize,
+}
+
+const INDENT: &str = "    ";
+
+impl PrettyPrinter {
+    fn fits(&self, indent: usize, text: &str) -> bool {
+        self.fits_after(indent, "", text)
+    }
+
+    /// Whether `text` fits on the last line of `written`.
+    fn fits_after(&self, indent: usize, written: &str, text: &str) -> bool {
+        let column = match written.rsplit_once('\n') {
+            Some((_, line)) => line.len(),
+            None => indent * INDENT.len() + written.len(),
+        };
+
+        column + text.len() <= self.max_width
+    }
+
+    fn new_line(indent: usize) -> String {
+        format!("\n{}", INDENT.repeat(indent))
+    }
+
+    /// Write each part on its own line, separated by `separator`.
+    fn lines(parts: impl Iterator<Item = String>, separator: &str, indent: usize) -> String {
+        parts
+            .collect::<Vec<_>>()
+            .join(&format!("{}{}", separator, Self::new_line(indent)))
+    }
+
+    /// Write `flat` if it fits after `text`, otherwise write `inner` on its own indented line
+    /// between `open` and `close`.
+    fn enclosed(
+        &self,
+        text: &mut String,
+        flat: String,
+        open: &str,
+        close: &str,
+        inner: impl FnOnce(usize) -> String,
+        indent: usize,
+    ) {
+        if self.fits_after(indent, text, &flat) {
+            text.push_str(&flat);
+            return;
+        }
+
+        text.push_str(open);
+        text.push_str(&Self::new_line(indent + 1));
+        text.push_str(&inner(indent + 1));
+        text.push_str(&Self::new_line(indent));
+        text.push_str(close);
+    }
+
+    fn expr(&self, expr: &Expr, indent: usize) -> String {
+        if expr.items.is_empty() {
+            return self.expr_single(&expr.expr, indent);
+        }
+
+        if self.fits(indent, &expr.to_string()) {
+            return expr.to_string();
+        }
+
+        let items = std::iter::once(&expr.expr).chain(expr.items.iter());
+        Self::lines(items.map(|x| self.expr_single(x, indent)), ",", indent)
+    }
+
+    fn expr_single(&self, expr: &ExprSingle, indent: usize) -> String {
+        match expr {
+            ExprSingle::OrExpr(x) => self.or_expr(x, indent),
+            _ => expr.to_string(),
+        }
+    }
+
+    fn or_expr(&self, expr: &OrExpr, indent: usize) -> String {
+        if expr.items.is_empty() {
+            return self.and_expr(&expr.expr, indent);
+        }
+
+        if self.fits(indent, &expr.to_string()) {
+            return expr.to_string();
+        }
+
+        let mut text = self.and_expr(&expr.expr, indent);
+        for item in expr.items.iter() {
+            text.push_str(&Self::new_line(indent));
+            text.push_str("or ");
+            text.push_str(&self.and_expr(item, indent));
+        }
+
+        text
+    }
+
+    fn and_expr(&self, expr: &AndExpr, indent: usize) -> String {
+        if expr.items.is_empty() {
+            return self.comparison_expr(&expr.expr, indent);
+        }
+
+        if self.fits(indent, &expr.to_string()) {
+            return expr.to_string();
+        }
+
+        let mut text = self.comparison_expr(&expr.expr, indent);
+        for item in expr.items.iter() {
+            text.push_str(&Self::new_line(indent));
+            text.push_str("and ");
+            text.push_str(&self.comparison_expr(item, indent));
+        }
+
+        text
+    }
+
+    fn comparison_expr(&self, expr: &ComparisonExpr, indent: usize) -> String {
+        // Only paths are broken over lines, so operands are written as they are displayed
+        // unless they are a path.
+        match (&expr.comparison, Self::path_expr_of(expr)) {
+            (None, Some(path)) => self.path_expr(path, indent),
+            _ => expr.to_string(),
+        }
+    }
+
+    /// The path expression that a comparison expression consists of, if it is only a path.
+    fn path_expr_of(expr: &ComparisonExpr) -> Option<&PathExpr> {
+        let range = &expr.expr;
+        if !range.items.is_empty() || range.expr.to_expr.is_some() {
+            return None;
+        }
+
+        let additive = &range.expr.expr;
+        let multiplicative = &additive.expr;
+        let union = &multiplicative.expr;
+        let intersect = &union.expr;
+        let instanceof = &intersect.expr;
+        let treat = &instanceof.expr;
+        let castable = &treat.expr;
+        let cast = &castable.expr;
+        let arrow = &cast.expr;
+        let unary = &arrow.expr;
+        let simple_map = &unary.expr.0;
+
+        let is_path_only = additive.items.is_empty()
+            && multiplicative.items.is_empty()
+            && union.items.is_empty()
+            && intersect.items.is_empty()
+            && instanceof.instanceof_type.is_none()
+            && treat.treat_type.is_none()
+            && castable.cast_type.is_none()
+            && cast.cast.is_none()
+            && arrow.items.is_empty()
+            && unary.leading_symbols.is_empty()
+            && simple_map.items.is_empty();
+
+        is_path_only.then_some(&simple_map.expr)
+    }
+
+    fn path_expr(&self, expr: &PathExpr, indent: usize) -> String {
+        let (leading, relative) = match expr {
+            PathExpr::LeadingSlash(None) => return expr.to_string(),
+            PathExpr::LeadingSlash(Some(x)) => ("/", x),
+            PathExpr::LeadingDoubleSlash(x) => ("//", x),
+            PathExpr::Plain(x) => ("", x),
+        };
+
+        let mut text = leading.to_string();
+        self.relative_path_expr(&mut text, relative, indent);
+        text
+    }
+
+    fn relative_path_expr(&self, text: &mut String, expr: &RelativePathExpr, indent: usize) {
+        let flat = expr.to_string();
+        if self.fits_after(indent, text, &flat) {
+            text.push_str(&flat);
+            return;
+        }
+
+        self.step_expr(text, &expr.expr, indent);
+        for item in expr.items.iter() {
+            text.push_str(&Self::new_line(indent + 1));
+            text.push_str(&item.0.to_string());
+            self.step_expr(text, &item.1, indent + 1);
+        }
+    }
+
+    fn step_expr(&self, text: &mut String, expr: &StepExpr, indent: usize) {
+        match expr {
+            StepExpr::PostfixExpr(x) => self.postfix_expr(text, x, indent),
+            StepExpr::AxisStep(x) => {
+                text.push_str(&x.step_type.to_string());
+                for predicate in x.predicates.iter() {
+                    self.predicate(text, predicate, indent);
+                }
+            }
+        }
+    }
+
+    fn predicate(&self, text: &mut String, predicate: &Predicate, indent: usize) {
+        self.enclosed(
+            text,
+            predicate.to_string(),
+            "[",
+            "]",
+            |indent| self.expr(&predicate.0, indent),
+            indent,
+        )
+    }
+
+    fn postfix_expr(&self, text: &mut String, expr: &PostfixExpr, indent: usize) {
+        self.primary_expr(text, &expr.expr, indent);
+        for item in expr.items.iter() {
+            match item {
+                PostfixExprItem::Predicate(x) => self.predicate(text, x, indent),
+                PostfixExprItem::ArgumentList(x) => self.argument_list(text, "", x, indent),
+                PostfixExprItem::Lookup(x) => text.push_str(&x.to_string()),
+            }
+        }
+    }
+
+    fn primary_expr(&self, text: &mut String, expr: &PrimaryExpr, indent: usize) {
+        match expr {
+            PrimaryExpr::FunctionCall(x) => {
+                self.argument_list(text, &x.name.to_string(), &x.argument_list, indent)
+            }
+            PrimaryExpr::ParenthesizedExpr(ParenthesizedExpr(Some(x))) => self.enclosed(
+                text,
+                expr.to_string(),
+                "(",
+                ")",
+                |indent| self.expr(x, indent),
+                indent,
+            ),
+            _ => text.push_str(&expr.to_string()),
+        }
+    }
+
+    /// Write an argument list after the name of the function it calls.
+    fn argument_list(
+        &self,
+        text: &mut String,
+        name: &str,
+        argument_list: &ArgumentList,
+        indent: usize,
+    ) {
+        self.enclosed(
+            text,
+            format!("{}{}", name, argument_list),
+            &format!("{}(", name),
+            ")",
+            |indent| {
+                let arguments = argument_list.0.iter().map(|argument| match argument {
+                    Argument::ExprSingle(x) => self.expr_single(x, indent),
+                    Argument::ArgumentPlaceHolder => argument.to_string(),
+                });
+                Self::lines(arguments, ",", indent)
+            },
+            indent,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use crate::xpath::{grammar::expressions::xpath, Xpath};
+
+    fn parse(text: &str) -> Result<Xpath, String> {
+        match xpath(text) {
+            Ok(("", xpath)) => Ok(xpath),
+            result => Err(format!("{:?}", result)),
+        }
+    }
+
+    #[test]
+    fn normalize_should_abbreviate_steps() {
+        // arrange
+        let xpath = parse(
+            "/descendant-or-self::node()/child::div/attribute::class/parent::node()/child::a/descendant-or-self::node()/child::span",
+        )
+        .unwrap();
+
+        // act
+        let normalized = xpath.normalize();
+
+        // assert
+        assert_eq!(normalized.to_string(), "//div/@class/../a//span");
+    }
+
+    #[test]
+    fn normalize_should_keep_steps_with_predicates() {
+        // arrange
+        let xpath = parse("/descendant-or-self::node()[1]/child::div").unwrap();
+
+        // act
+        let normalized = xpath.normalize();
+
+        // assert
+        assert_eq!(normalized.to_string(), "/descendant-or-self::node()[1]/div");
+    }
+
+    #[test]
+    fn normalize_should_keep_child_axis_for_attribute_test() {
+        // arrange
+        let xpath = parse("//div/child::attribute()").unwrap();
+
+        // act
+        let normalized = xpath.normalize();
+
+        // assert
+        assert_eq!(normalized.to_string(), "//div/child::attribute()");
+    }
+
+    #[test]
+    fn normalize_should_quote_literals() {
+        // arrange
+        let xpath = parse(r#"//div[@class = "a" or @id = "it's" or @name = 'say "hi"']"#).unwrap();
+
+        // act
+        let normalized = xpath.normalize();
+
+        // assert
+        assert_eq!(
+            normalized.to_string(),
+            r#"//div[@class='a' or @id="it's" or @name='say "hi"']"#
+        );
+    }
+
+    #[test]
+    fn normalize_should_write_literals_and_names_that_parse_back() {
+        // arrange
+        let texts = [
+            ("//p[. = 1.5e10]", "//p[.=1.5E10]"),
+            ("//p[. = 1e300]", "//p[.=1.0E300]"),
+            ("//p[. = 1.0]", "//p[.=1.0]"),
+            ("//p[. = .5]", "//p[.=0.5]"),
+            ("//p[4294967296]", "//p[4294967296]"),
+            ("//Q{}li", "//Q{}li"),
+            (
+                "//Q{http://www.w3.org/1999/xhtml}div",
+                "//Q{http://www.w3.org/1999/xhtml}div",
+            ),
+        ];
+
+        for (text, expected) in texts {
+            // act
+            let normalized = parse(text).unwrap().normalize();
+            let reparsed = parse(&normalized.to_string()).unwrap();
+
+            // assert
+            assert_eq!(normalized.to_string(), expected);
+            assert_eq!(reparsed.0, normalized.0, "{}", text);
+        }
+    }
+
+    #[test]
+    fn normalize_should_normalize_predicates() {
+        // arrange
+        let xpath = parse("//div[child::span/attribute::id]").unwrap();
+
+        // act
+        let normalized = xpath.normalize();
+
+        // assert
+        assert_eq!(normalized.to_string(), "//div[span/@id]");
+    }
+
+    #[test]
+    fn normalize_should_be_idempotent() {
+        // arrange
+        let xpath = parse("/descendant-or-self::node()/child::div[attribute::id = \"x\"]").unwrap();
+
+        // act
+        let normalized = xpath.normalize();
+
+        // assert
+        assert_eq!(normalized.normalize(), normalized);
+        assert_eq!(parse(&normalized.to_string()).unwrap(), normalized);
+    }
+
+    #[test]
+    fn to_pretty_string_should_keep_short_expressions_on_one_line() {
+        // arrange
+        let xpath = parse("//div[@class='item']/a").unwrap();
+
+        // act
+        let text = xpath.to_pretty_string(80);
+
+        // assert
+        assert_eq!(text, "//div[@class='item']/a");
+    }
+
+    #[test]
+    fn to_pretty_string_should_break_long_expressions() {
+        // arrange
+        let xpath = parse(
+            "//div[@class='item' or contains(@class, 'product-listing-entry')]/a[contains(@href, 'https://example.com/')]",
+        )
+        .unwrap();
+
+        // act
+        let text = xpath.to_pretty_string(40);
+
+        // assert
+        let expected = indoc! {"
+            //div[
+                @class='item'
+                or contains(
+                    @class,
+                    'product-listing-entry'
+                )
+            ]
+                /a[
+                    contains(
+                        @href,
+                        'https://example.com/'
+                    )
+                ]"};
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn to_pretty_string_should_parse_to_same_expression() {
+        // arrange
+        let xpath = parse(
+            "(//div[@class='item' and contains(@class, 'x')]/span, //p[@id='abc' or @id='def'])",
+        )
+        .unwrap();
+
+        // act
+        let text = xpath.to_pretty_string(20);
+
+        // assert
+        assert_eq!(parse(&text).unwrap(), xpath);
+    }
+}
@@ -0,0 +1,88 @@
+//! <https://www.w3.org/TR/xpath-functions-31/#boolean-functions>
+
+use crate::{
+    xpath::{
+        grammar::data_model::{AnyAtomicType, XpathItem},
+        xpath_item_set::XpathItemSet,
+        ExpressionApplyError, XpathExpressionContext,
+    },
+    xpath_item_set,
+};
+
+use super::FunctionRegistry;
+
+pub(super) fn register(registry: &mut FunctionRegistry) {
+    registry.register_built_in("true", 0, fn_true);
+    registry.register_built_in("false", 0, fn_false);
+    registry.register_built_in("not", 1, fn_not);
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-true>
+fn fn_true<'tree>(
+    _context: &XpathExpressionContext<'tree, '_>,
+    _arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    Ok(boolean(true))
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-false>
+fn fn_false<'tree>(
+    _context: &XpathExpressionContext<'tree, '_>,
+    _arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    Ok(boolean(false))
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-not>
+///
+/// Returns the negation of the effective boolean value of the argument.
+fn fn_not<'tree>(
+    _context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    Ok(boolean(!arguments[0].boolean()))
+}
+
+fn boolean<'tree>(value: bool) -> XpathItemSet<'tree> {
+    xpath_item_set![XpathItem::AnyAtomicType(AnyAtomicType::Boolean(value))]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        html,
+        xpath::{self, XpathItemTree},
+    };
+
+    #[test]
+    fn not_should_negate_effective_boolean_value() {
+        // arrange
+        let tree = XpathItemTree::from(&html::parse("<html><p id='1'></p><p></p></html>").unwrap());
+        let xpath = xpath::parse("//p[not(@id)]").unwrap();
+
+        // act
+        let items = xpath.apply(&tree).unwrap();
+
+        // assert
+        assert_eq!(items.len(), 1);
+        assert!(items[0]
+            .extract_as_node()
+            .extract_as_element_node()
+            .get_attribute(&tree, "id")
+            .is_none());
+    }
+
+    #[test]
+    fn true_and_false_should_return_booleans() {
+        // arrange
+        let tree = XpathItemTree::from(&html::parse("<html><p></p></html>").unwrap());
+
+        // act
+        let with_true = xpath::parse("//p[true()]").unwrap().apply(&tree).unwrap();
+        let with_false = xpath::parse("//p[false()]").unwrap().apply(&tree).unwrap();
+
+        // assert
+        assert_eq!(with_true.len(), 1);
+        assert!(with_false.is_empty());
+    }
+}
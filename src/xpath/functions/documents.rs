@@ -0,0 +1,200 @@
+//! Functions that load documents and text through the
+//! [ResourceResolver](crate::xpath::resolver::ResourceResolver) of the dynamic context.
+//!
+//! <https://www.w3.org/TR/xpath-functions-31/#func-doc>
+
+use crate::{
+    xpath::{
+        grammar::{
+            data_model::{AnyAtomicType, XpathItem},
+            XpathItemTreeNode,
+        },
+        xpath_item_set::XpathItemSet,
+        ErrorCode, ExpressionApplyError, XpathExpressionContext,
+    },
+    xpath_item_set,
+};
+
+use super::{strings::optional_string_argument, FunctionRegistry};
+
+pub(super) fn register(registry: &mut FunctionRegistry) {
+    registry.register_built_in("doc", 1, fn_doc);
+    registry.register_built_in("doc-available", 1, fn_doc_available);
+    registry.register_built_in("collection", 0, fn_collection);
+    registry.register_built_in("collection", 1, fn_collection);
+    registry.register_built_in("unparsed-text", 1, fn_unparsed_text);
+    registry.register_built_in("unparsed-text", 2, fn_unparsed_text);
+    registry.register_built_in("unparsed-text-lines", 1, fn_unparsed_text_lines);
+    registry.register_built_in("unparsed-text-lines", 2, fn_unparsed_text_lines);
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-doc>
+fn fn_doc<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    if arguments[0].is_empty() {
+        return Ok(XpathItemSet::new());
+    }
+
+    let uri = optional_string_argument("doc", &arguments[0], context, "first")?;
+    let document_node = load_document(context, &uri)?;
+
+    Ok(xpath_item_set![XpathItem::Node(document_node)])
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-doc-available>
+///
+/// A document that is available is loaded, so a later `fn:doc` does not load it again.
+fn fn_doc_available<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let is_available = if arguments[0].is_empty() {
+        false
+    } else {
+        let uri = optional_string_argument("doc-available", &arguments[0], context, "first")?;
+        load_document(context, &uri).is_ok()
+    };
+
+    Ok(xpath_item_set![XpathItem::AnyAtomicType(
+        AnyAtomicType::Boolean(is_available)
+    )])
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-collection>
+///
+/// Returns the document nodes of the documents in the collection.
+fn fn_collection<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let uri = match arguments.first() {
+        Some(argument) if !argument.is_empty() => Some(optional_string_argument(
+            "collection",
+            argument,
+            context,
+            "first",
+        )?),
+        _ => None,
+    };
+
+    let resolver = context
+        .dynamic_context
+        .resolver_for(uri.as_deref().unwrap_or("the default collection"))?;
+
+    let mut items = XpathItemSet::new();
+    for document_uri in resolver.resolve_collection(uri.as_deref())? {
+        items.insert(XpathItem::Node(load_document(context, &document_uri)?));
+    }
+
+    Ok(items)
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-unparsed-text>
+///
+/// Only the UTF-8 encoding is supported.
+fn fn_unparsed_text<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let text = match unparsed_text("unparsed-text", context, &arguments)? {
+        Some(text) => text,
+        None => return Ok(XpathItemSet::new()),
+    };
+
+    Ok(xpath_item_set![XpathItem::AnyAtomicType(
+        AnyAtomicType::String(text)
+    )])
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-unparsed-text-lines>
+///
+/// Lines are separated by `\n`, `\r` or `\r\n`.
+/// Like every sequence, repeated lines are only included once.
+fn fn_unparsed_text_lines<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let text = match unparsed_text("unparsed-text-lines", context, &arguments)? {
+        Some(text) => text,
+        None => return Ok(XpathItemSet::new()),
+    };
+
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+
+    // A newline at the end of the text does not start another line.
+    let text = text.strip_suffix('\n').unwrap_or(&text);
+    if text.is_empty() {
+        return Ok(XpathItemSet::new());
+    }
+
+    Ok(text
+        .split('\n')
+        .map(|line| XpathItem::AnyAtomicType(AnyAtomicType::String(line.to_string())))
+        .collect())
+}
+
+/// Load the document at the URI into the tree, or get it if the evaluation already loaded it.
+///
+/// The document URI is the URI it was loaded from, unless the resolver set another.
+fn load_document<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    uri: &str,
+) -> Result<&'tree XpathItemTreeNode, ExpressionApplyError> {
+    let resolver = context.dynamic_context.resolver_for(uri)?;
+
+    // The resolvers of the dynamic context are the same for the whole evaluation,
+    // so the URI identifies the document.
+    let document = context.budget.document(("doc", uri), || {
+        context.item_tree.add_document(|document| {
+            let mut tree = resolver.resolve_document(uri)?;
+            if tree.document_uri().is_none() {
+                tree.set_document_uri(uri);
+            }
+
+            Ok(tree.into_document(document))
+        })
+    })?;
+
+    Ok(context.item_tree.document_root_of(document))
+}
+
+/// Get the text for `fn:unparsed-text` and `fn:unparsed-text-lines`.
+///
+/// Returns `None` if the URI is the empty sequence.
+fn unparsed_text(
+    function_name: &str,
+    context: &XpathExpressionContext<'_, '_>,
+    arguments: &[XpathItemSet<'_>],
+) -> Result<Option<String>, ExpressionApplyError> {
+    if arguments[0].is_empty() {
+        return Ok(None);
+    }
+
+    let uri = optional_string_argument(function_name, &arguments[0], context, "first")?;
+
+    if let Some(encoding) = arguments.get(1) {
+        let encoding = optional_string_argument(function_name, encoding, context, "second")?;
+        if !encoding.eq_ignore_ascii_case("utf-8") && !encoding.eq_ignore_ascii_case("utf8") {
+            return Err(ExpressionApplyError::with_code(
+                ErrorCode::FOUT1190,
+                format!("{}: encoding {} is not supported", function_name, encoding),
+            ));
+        }
+    }
+
+    let text = context
+        .dynamic_context
+        .resolver_for(&uri)
+        .and_then(|resolver| resolver.resolve_text(&uri))
+        .map_err(|e| match e.code() {
+            // Text resources report their own code when they cannot be loaded.
+            ErrorCode::FODC0002 => {
+                ExpressionApplyError::with_code(ErrorCode::FOUT1170, e.message().to_string())
+            }
+            _ => e,
+        })?;
+
+    Ok(Some(text))
+}
@@ -0,0 +1,65 @@
+//! <https://www.w3.org/TR/xpath-functions-31/#errors-and-diagnostics>
+
+use nom::combinator::all_consuming;
+
+use crate::xpath::{
+    grammar::types::eq_name, xpath_item_set::XpathItemSet, ErrorCode, ExpressionApplyError,
+    XpathExpressionContext,
+};
+
+use super::{strings::optional_string_argument, FunctionRegistry};
+
+pub(super) fn register(registry: &mut FunctionRegistry) {
+    registry.register_built_in("error", 0, fn_error);
+    registry.register_built_in("error", 1, fn_error);
+    registry.register_built_in("error", 2, fn_error);
+    registry.register_built_in("error", 3, fn_error);
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-error>
+///
+/// There is no `xs:QName` type, so the code is given as a lexical QName string,
+/// such as `'err:FORG0001'` or `'Q{http://example.com}oops'`.
+/// The error object argument is accepted but not kept.
+fn fn_error<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let code = match arguments.first() {
+        Some(code) if !code.is_empty() => {
+            let code = optional_string_argument("error", code, context, "first")?;
+            error_code(&code, context)?
+        }
+        _ => ErrorCode::FOER0000,
+    };
+
+    let description = match arguments.get(1) {
+        Some(description) => optional_string_argument("error", description, context, "second")?,
+        None => String::from("error raised by fn:error"),
+    };
+
+    Err(ExpressionApplyError::with_code(code, description))
+}
+
+fn error_code(
+    code: &str,
+    context: &XpathExpressionContext<'_, '_>,
+) -> Result<ErrorCode, ExpressionApplyError> {
+    let name = match all_consuming(eq_name)(code.trim()) {
+        Ok((_, name)) => name,
+        Err(_) => {
+            return Err(ExpressionApplyError::with_code(
+                ErrorCode::FORG0001,
+                format!("error: '{}' is not a valid QName", code),
+            ))
+        }
+    };
+
+    // Unprefixed names are in no namespace, like variable names.
+    let name = context
+        .dynamic_context
+        .static_context()
+        .resolve_variable_name(&name)?;
+
+    Ok(ErrorCode::from_name(name))
+}
@@ -0,0 +1,1109 @@
+//! <https://www.w3.org/TR/xpath-functions-31/#formatting-integers>
+//! <https://www.w3.org/TR/xpath-functions-31/#formatting-numbers>
+
+use crate::{
+    xpath::{
+        decimal_format::DecimalFormat,
+        grammar::{
+            data_model::{AnyAtomicType, XpathItem},
+            expressions::primary_expressions::static_function_calls::{func_data, parse_double},
+        },
+        xpath_item_set::XpathItemSet,
+        ErrorCode, ExpressionApplyError, XpathExpressionContext,
+    },
+    xpath_item_set,
+};
+
+use super::{single_item_argument, strings::optional_string_argument, FunctionRegistry};
+
+pub(super) fn register(registry: &mut FunctionRegistry) {
+    registry.register_built_in("format-integer", 2, fn_format_integer);
+    registry.register_built_in("format-integer", 3, fn_format_integer);
+    registry.register_built_in("format-number", 2, fn_format_number);
+    registry.register_built_in("format-number", 3, fn_format_number);
+}
+
+/// The zero digits of the families of decimal digits that `fn:format-integer` pictures can use.
+const ZERO_DIGITS: [char; 20] = [
+    '0', '\u{660}', '\u{6F0}', '\u{7C0}', '\u{966}', '\u{9E6}', '\u{A66}', '\u{AE6}', '\u{B66}',
+    '\u{BE6}', '\u{C66}', '\u{CE6}', '\u{D66}', '\u{E50}', '\u{ED0}', '\u{F20}', '\u{1040}',
+    '\u{17E0}', '\u{1810}', '\u{FF10}',
+];
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-format-integer>
+///
+/// Pictures can be decimal digit patterns such as `001` or `#,##0`, `a` or `A` for letters,
+/// `i` or `I` for roman numerals, and `w`, `W` or `Ww` for words.
+/// A `;o` modifier gives ordinals, such as `1st` or `first`.
+///
+/// Words and ordinals are always in English, whatever the language argument,
+/// as the specification allows for languages that are not supported.
+fn fn_format_integer<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let value = integer_argument(context, &arguments[0])?;
+    let picture = optional_string_argument("format-integer", &arguments[1], context, "second")?;
+    let picture = IntegerPicture::parse(&picture)?;
+
+    let formatted = match value {
+        Some(value) => picture.format(value),
+        None => String::new(),
+    };
+
+    Ok(string(formatted))
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-format-number>
+///
+/// The third argument names a decimal format registered on the
+/// [StaticContext](crate::xpath::StaticContext); without it the default decimal format is used.
+///
+/// Numbers are rounded half to even on their shortest decimal representation,
+/// so `format-number(2.675, '0.00')` is `2.68`.
+/// The result always has at least one digit, so `format-number(0, '#')` is `0`.
+fn fn_format_number<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let value = number_argument(context, &arguments[0])?;
+    let picture = optional_string_argument("format-number", &arguments[1], context, "second")?;
+
+    let format_name = match arguments.get(2) {
+        Some(name) if !name.is_empty() => Some(optional_string_argument(
+            "format-number",
+            name,
+            context,
+            "third",
+        )?),
+        _ => None,
+    };
+    let format = context
+        .dynamic_context
+        .static_context()
+        .resolve_decimal_format(format_name.as_deref())?;
+
+    let picture = NumberPicture::parse(&picture, format)?;
+
+    Ok(string(picture.format(value)))
+}
+
+fn string<'tree>(value: String) -> XpathItemSet<'tree> {
+    xpath_item_set![XpathItem::AnyAtomicType(AnyAtomicType::String(value))]
+}
+
+fn invalid_picture(function_name: &str, picture: &str, reason: &str) -> ExpressionApplyError {
+    ExpressionApplyError::with_code(
+        ErrorCode::FODF1310,
+        format!(
+            "{}: invalid picture {:?}: {}",
+            function_name, picture, reason
+        ),
+    )
+}
+
+/// Get the value of an `xs:integer?` argument.
+///
+/// Untyped values, such as the text of nodes, are cast to integers.
+fn integer_argument<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    argument: &XpathItemSet<'tree>,
+) -> Result<Option<i64>, ExpressionApplyError> {
+    let argument = single_item_argument(context, argument);
+    let values = func_data(&argument, context.item_tree, context.budget)?;
+    match values.as_slice() {
+        [] => Ok(None),
+        [AnyAtomicType::Integer(n)] => Ok(Some(*n)),
+        [AnyAtomicType::String(s)] => s.trim().parse().map(Some).map_err(|_| {
+            ExpressionApplyError::with_code(
+                ErrorCode::FORG0001,
+                format!("format-integer: cannot cast {:?} to an integer", s),
+            )
+        }),
+        [value] => Err(ExpressionApplyError::with_code(
+            ErrorCode::XPTY0004,
+            format!("format-integer: expected an integer, found {}", value),
+        )),
+        _ => Err(ExpressionApplyError::with_code(
+            ErrorCode::XPTY0004,
+            String::from("format-integer: expected at most one integer"),
+        )),
+    }
+}
+
+/// Get the value of an `xs:numeric?` argument.
+///
+/// The empty sequence is `NaN`, and untyped values, such as the text of nodes,
+/// are cast to doubles.
+fn number_argument<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    argument: &XpathItemSet<'tree>,
+) -> Result<Number, ExpressionApplyError> {
+    let argument = single_item_argument(context, argument);
+    let values = func_data(&argument, context.item_tree, context.budget)?;
+    match values.as_slice() {
+        [] => Ok(Number::NaN),
+        [AnyAtomicType::Integer(n)] => Ok(Number::Finite {
+            negative: *n < 0,
+            magnitude: Decimal::from_integer(n.unsigned_abs()),
+        }),
+        [AnyAtomicType::Float(n)] => Ok(Number::from_float(n.0 as f64, format!("{:e}", n.0))),
+        [AnyAtomicType::Double(n)] => Ok(Number::from_float(n.0, format!("{:e}", n.0))),
+        [AnyAtomicType::String(s)] => match parse_double(s) {
+            Some(value) => Ok(Number::from_float(value, format!("{:e}", value))),
+            None => Err(ExpressionApplyError::with_code(
+                ErrorCode::FORG0001,
+                format!("format-number: cannot cast {:?} to a double", s),
+            )),
+        },
+        [value] => Err(ExpressionApplyError::with_code(
+            ErrorCode::XPTY0004,
+            format!("format-number: expected a number, found {}", value),
+        )),
+        _ => Err(ExpressionApplyError::with_code(
+            ErrorCode::XPTY0004,
+            String::from("format-number: expected at most one number"),
+        )),
+    }
+}
+
+/// A number to format.
+enum Number {
+    NaN,
+    Infinity { negative: bool },
+    Finite { negative: bool, magnitude: Decimal },
+}
+
+impl Number {
+    /// Create a number from a float and its shortest representation in scientific notation.
+    fn from_float(value: f64, scientific: String) -> Self {
+        if value.is_nan() {
+            Number::NaN
+        } else if value.is_infinite() {
+            Number::Infinity {
+                negative: value < 0.0,
+            }
+        } else {
+            Number::Finite {
+                negative: value.is_sign_negative(),
+                magnitude: Decimal::from_scientific(&scientific),
+            }
+        }
+    }
+}
+
+/// A non-negative decimal number `0.d₁d₂d₃… × 10^point`.
+///
+/// The digits never start or end with zero, and zero has no digits.
+#[derive(PartialEq, Debug, Clone)]
+struct Decimal {
+    digits: Vec<u8>,
+    point: i32,
+}
+
+impl Decimal {
+    fn new(mut digits: Vec<u8>, mut point: i32) -> Self {
+        let leading_zeros = digits.iter().take_while(|&&d| d == 0).count();
+        digits.drain(..leading_zeros);
+        point -= leading_zeros as i32;
+
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        if digits.is_empty() {
+            point = 0;
+        }
+
+        Self { digits, point }
+    }
+
+    fn from_integer(value: u64) -> Self {
+        let digits: Vec<u8> = value.to_string().bytes().map(|b| b - b'0').collect();
+        let point = digits.len() as i32;
+        Self::new(digits, point)
+    }
+
+    /// Parse the magnitude of a number written like `-1.25e3`.
+    fn from_scientific(text: &str) -> Self {
+        let (mantissa, exponent) = text.split_once(['e', 'E']).unwrap_or((text, "0"));
+        let exponent: i32 = exponent.parse().unwrap_or(0);
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let integer = integer.trim_start_matches(['-', '+']);
+
+        let digits = integer
+            .bytes()
+            .chain(fraction.bytes())
+            .map(|b| b - b'0')
+            .collect();
+        Self::new(digits, integer.len() as i32 + exponent)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    /// Multiply by a power of ten.
+    fn shift(&mut self, places: i32) {
+        if !self.is_zero() {
+            self.point += places;
+        }
+    }
+
+    /// Round half to even, keeping at most the given number of fractional digits.
+    fn round(&mut self, fraction_digits: usize) {
+        let keep = self.point + fraction_digits as i32;
+        if keep >= self.digits.len() as i32 {
+            return;
+        }
+        if keep < 0 {
+            *self = Self::new(Vec::new(), 0);
+            return;
+        }
+
+        let keep = keep as usize;
+        let first_dropped = self.digits[keep];
+        let rest_dropped = self.digits[keep + 1..].iter().any(|&d| d != 0);
+        let last_kept_odd = keep > 0 && self.digits[keep - 1] % 2 == 1;
+        let round_up = first_dropped > 5 || (first_dropped == 5 && (rest_dropped || last_kept_odd));
+
+        let mut digits = self.digits[..keep].to_vec();
+        let mut point = self.point;
+        if round_up {
+            let mut i = digits.len();
+            loop {
+                if i == 0 {
+                    digits.insert(0, 1);
+                    point += 1;
+                    break;
+                }
+                i -= 1;
+                if digits[i] == 9 {
+                    digits[i] = 0;
+                } else {
+                    digits[i] += 1;
+                    break;
+                }
+            }
+        }
+
+        *self = Self::new(digits, point);
+    }
+
+    /// The digits before the decimal point, without leading zeros.
+    fn integer_digits(&self) -> Vec<u8> {
+        (0..self.point.max(0) as usize)
+            .map(|i| self.digits.get(i).copied().unwrap_or(0))
+            .collect()
+    }
+
+    /// The digits after the decimal point, without trailing zeros.
+    fn fraction_digits(&self) -> Vec<u8> {
+        let mut digits = vec![0; (-self.point).max(0) as usize];
+        digits.extend(self.digits.iter().skip(self.point.max(0) as usize));
+        digits
+    }
+}
+
+/// Where grouping separators go in the integer part of a number.
+#[derive(Debug, Default)]
+struct IntegerGrouping {
+    /// Separators and the number of digits to their right.
+    separators: Vec<(usize, char)>,
+
+    /// The separator that is repeated every so many digits, if the grouping is regular.
+    every: Option<(usize, char)>,
+}
+
+impl IntegerGrouping {
+    /// Work out whether the separators are regular, which is when they are all the same
+    /// character, every position is a multiple of the smallest, and every such multiple
+    /// within the picture has a separator.
+    /// Regular separators repeat for numbers with more digits than the picture.
+    ///
+    /// # Arguments
+    ///
+    /// * `separators` - Separators and the number of digit positions to their right.
+    /// * `digit_positions` - The number of digit positions in the integer part of the picture.
+    fn new(separators: Vec<(usize, char)>, digit_positions: usize) -> Self {
+        let every = separators
+            .iter()
+            .map(|&(position, _)| position)
+            .min()
+            .filter(|&size| size > 0)
+            .and_then(|size| {
+                let separator = separators[0].1;
+                let is_regular = separators
+                    .iter()
+                    .all(|&(position, c)| c == separator && position.is_multiple_of(size))
+                    && (size..digit_positions)
+                        .step_by(size)
+                        .all(|position| separators.iter().any(|&(p, _)| p == position));
+                is_regular.then_some((size, separator))
+            });
+
+        Self { separators, every }
+    }
+
+    /// Get the separator that goes before the given number of digits from the right.
+    fn separator_at(&self, position: usize) -> Option<char> {
+        match self.every {
+            Some((size, separator)) => position.is_multiple_of(size).then_some(separator),
+            None => self
+                .separators
+                .iter()
+                .find(|&&(p, _)| p == position)
+                .map(|&(_, separator)| separator),
+        }
+    }
+
+    /// Write the digits with separators between groups.
+    fn apply(&self, digits: &[char]) -> String {
+        let mut grouped = Vec::with_capacity(digits.len() * 2);
+        for (i, &digit) in digits.iter().rev().enumerate() {
+            if i > 0 {
+                if let Some(separator) = self.separator_at(i) {
+                    grouped.push(separator);
+                }
+            }
+            grouped.push(digit);
+        }
+
+        grouped.into_iter().rev().collect()
+    }
+}
+
+/// A picture of `fn:format-number`, analysed as described in the specification.
+///
+/// <https://www.w3.org/TR/xpath-functions-31/#analysing-picture-string>
+#[derive(Debug)]
+struct NumberPicture<'a> {
+    format: &'a DecimalFormat,
+    positive: SubPicture,
+
+    /// The prefix and suffix of negative numbers.
+    negative: (String, String),
+}
+
+#[derive(Debug)]
+struct SubPicture {
+    prefix: String,
+    suffix: String,
+    integer_grouping: IntegerGrouping,
+    minimum_integer_size: usize,
+    scaling_factor: usize,
+
+    /// Positions of grouping separators, counted in digits from the decimal separator.
+    fraction_grouping: Vec<usize>,
+    minimum_fraction_size: usize,
+    maximum_fraction_size: usize,
+    exponent_size: Option<usize>,
+
+    /// The power of ten the number is multiplied by for a percent or per-mille sign.
+    scale: i32,
+}
+
+impl<'a> NumberPicture<'a> {
+    fn parse(picture: &str, format: &'a DecimalFormat) -> Result<Self, ExpressionApplyError> {
+        let sub_pictures: Vec<&str> = picture.split(format.pattern_separator()).collect();
+        let (positive, negative) = match sub_pictures.as_slice() {
+            [positive] => {
+                let positive = SubPicture::parse(positive, picture, format)?;
+                let negative = (
+                    format!("{}{}", format.minus_sign(), positive.prefix),
+                    positive.suffix.clone(),
+                );
+                (positive, negative)
+            }
+            [positive, negative] => {
+                let positive = SubPicture::parse(positive, picture, format)?;
+                let negative = SubPicture::parse(negative, picture, format)?;
+                (positive, (negative.prefix, negative.suffix))
+            }
+            _ => {
+                return Err(invalid_picture(
+                    "format-number",
+                    picture,
+                    "more than one pattern separator",
+                ))
+            }
+        };
+
+        Ok(Self {
+            format,
+            positive,
+            negative,
+        })
+    }
+
+    fn format(&self, number: Number) -> String {
+        let format = self.format;
+        let (negative, magnitude) = match number {
+            Number::NaN => return format.nan().to_string(),
+            Number::Infinity { negative } => (negative, None),
+            Number::Finite {
+                negative,
+                magnitude,
+            } => (negative, Some(magnitude)),
+        };
+
+        let picture = &self.positive;
+        let (prefix, suffix) = if negative {
+            (self.negative.0.as_str(), self.negative.1.as_str())
+        } else {
+            (picture.prefix.as_str(), picture.suffix.as_str())
+        };
+
+        let mut magnitude = match magnitude {
+            Some(magnitude) => magnitude,
+            None => return format!("{}{}{}", prefix, format.infinity(), suffix),
+        };
+        magnitude.shift(picture.scale);
+
+        // Scale the mantissa to have as many integer digits as the picture.
+        let mut exponent = 0;
+        if picture.exponent_size.is_some() && !magnitude.is_zero() {
+            let scaling_factor = picture.scaling_factor as i32;
+            exponent = magnitude.point - scaling_factor;
+            magnitude.point = scaling_factor;
+            magnitude.round(picture.maximum_fraction_size);
+            if magnitude.point > scaling_factor {
+                exponent += 1;
+                magnitude.point = scaling_factor;
+            }
+        } else {
+            magnitude.round(picture.maximum_fraction_size);
+        }
+
+        let to_chars = |digits: Vec<u8>| -> Vec<char> {
+            digits
+                .into_iter()
+                .map(|d| format.digit_char(d as u32))
+                .collect()
+        };
+
+        let mut integer = magnitude.integer_digits();
+        while integer.len() < picture.minimum_integer_size {
+            integer.insert(0, 0);
+        }
+        let mut fraction = magnitude.fraction_digits();
+        while fraction.len() < picture.minimum_fraction_size {
+            fraction.push(0);
+        }
+        if integer.is_empty() && fraction.is_empty() {
+            integer.push(0);
+        }
+
+        let mut formatted = String::from(prefix);
+        formatted.push_str(&picture.integer_grouping.apply(&to_chars(integer)));
+
+        if !fraction.is_empty() {
+            formatted.push(format.decimal_separator());
+            for (i, digit) in to_chars(fraction).into_iter().enumerate() {
+                if i > 0 && picture.fraction_grouping.contains(&i) {
+                    formatted.push(format.grouping_separator());
+                }
+                formatted.push(digit);
+            }
+        }
+
+        if let Some(exponent_size) = picture.exponent_size {
+            formatted.push(format.exponent_separator());
+            if exponent < 0 {
+                formatted.push(format.minus_sign());
+            }
+            let mut digits = Decimal::from_integer(exponent.unsigned_abs() as u64).integer_digits();
+            while digits.len() < exponent_size {
+                digits.insert(0, 0);
+            }
+            formatted.extend(to_chars(digits));
+        }
+
+        formatted.push_str(suffix);
+        formatted
+    }
+}
+
+impl SubPicture {
+    fn parse(
+        sub_picture: &str,
+        picture: &str,
+        format: &DecimalFormat,
+    ) -> Result<Self, ExpressionApplyError> {
+        let invalid = |reason: &str| invalid_picture("format-number", picture, reason);
+        let chars: Vec<char> = sub_picture.chars().collect();
+
+        let is_digit = |c: char| format.digit_value(c).is_some();
+        let is_mantissa_char = |c: char| {
+            c == format.decimal_separator()
+                || c == format.grouping_separator()
+                || c == format.digit()
+                || is_digit(c)
+        };
+
+        // An exponent separator is only active between the mantissa and the exponent digits.
+        let exponent_index = (1..chars.len().saturating_sub(1)).find(|&i| {
+            chars[i] == format.exponent_separator()
+                && is_mantissa_char(chars[i - 1])
+                && is_digit(chars[i + 1])
+        });
+        let is_active = |i: usize| is_mantissa_char(chars[i]) || Some(i) == exponent_index;
+
+        let first_active = (0..chars.len()).find(|&i| is_active(i));
+        let last_active = (0..chars.len()).rev().find(|&i| is_active(i));
+        let (first_active, last_active) = match (first_active, last_active) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Err(invalid("a sub-picture has no digits")),
+        };
+
+        if (first_active..=last_active).any(|i| !is_active(i)) {
+            return Err(invalid("passive characters between active characters"));
+        }
+
+        let percents = chars.iter().filter(|&&c| c == format.percent()).count();
+        let per_milles = chars.iter().filter(|&&c| c == format.per_mille()).count();
+        let scale = match (percents, per_milles) {
+            (0, 0) => 0,
+            (1, 0) => 2,
+            (0, 1) => 3,
+            _ => return Err(invalid("more than one percent or per-mille sign")),
+        };
+        if scale != 0 && exponent_index.is_some() {
+            return Err(invalid("a percent or per-mille sign with an exponent"));
+        }
+
+        let mantissa_end = exponent_index.unwrap_or(last_active + 1);
+        let mantissa = &chars[first_active..mantissa_end];
+        let exponent_size = match exponent_index {
+            Some(i) => {
+                let exponent = &chars[i + 1..=last_active];
+                if !exponent.iter().all(|&c| is_digit(c)) {
+                    return Err(invalid("an exponent that is not all digits"));
+                }
+                Some(exponent.len())
+            }
+            None => None,
+        };
+
+        if !mantissa.iter().any(|&c| c == format.digit() || is_digit(c)) {
+            return Err(invalid("a sub-picture has no digits"));
+        }
+
+        let decimal_separators = mantissa
+            .iter()
+            .filter(|&&c| c == format.decimal_separator())
+            .count();
+        if decimal_separators > 1 {
+            return Err(invalid("more than one decimal separator"));
+        }
+        let (integer, fraction) = match mantissa
+            .iter()
+            .position(|&c| c == format.decimal_separator())
+        {
+            Some(i) => (&mantissa[..i], &mantissa[i + 1..]),
+            None => (mantissa, &mantissa[mantissa.len()..]),
+        };
+
+        let is_grouping = |c: &char| *c == format.grouping_separator();
+        if mantissa
+            .windows(2)
+            .any(|w| is_grouping(&w[0]) && is_grouping(&w[1]))
+        {
+            return Err(invalid("adjacent grouping separators"));
+        }
+        if integer.last().is_some_and(is_grouping) || fraction.first().is_some_and(is_grouping) {
+            return Err(invalid("a grouping separator next to the decimal point"));
+        }
+        let integer_digits: Vec<char> = integer
+            .iter()
+            .copied()
+            .filter(|c| !is_grouping(c))
+            .collect();
+        let fraction_digits: Vec<char> = fraction
+            .iter()
+            .copied()
+            .filter(|c| !is_grouping(c))
+            .collect();
+        if integer_digits
+            .windows(2)
+            .any(|w| is_digit(w[0]) && w[1] == format.digit())
+        {
+            return Err(invalid("an optional digit after a mandatory digit"));
+        }
+        if fraction_digits
+            .windows(2)
+            .any(|w| w[0] == format.digit() && is_digit(w[1]))
+        {
+            return Err(invalid("a mandatory digit after an optional digit"));
+        }
+
+        let mut integer_separators = Vec::new();
+        for (i, c) in integer.iter().enumerate() {
+            if is_grouping(c) {
+                let position = integer[i + 1..].iter().filter(|c| !is_grouping(c)).count();
+                integer_separators.push((position, *c));
+            }
+        }
+        let mut fraction_grouping = Vec::new();
+        for (i, c) in fraction.iter().enumerate() {
+            if is_grouping(c) {
+                fraction_grouping.push(fraction[..i].iter().filter(|c| !is_grouping(c)).count());
+            }
+        }
+
+        let mandatory_integer = integer_digits.iter().filter(|&&c| is_digit(c)).count();
+        let mut minimum_integer_size = mandatory_integer;
+        let scaling_factor = mandatory_integer;
+        let mut minimum_fraction_size = fraction_digits.iter().filter(|&&c| is_digit(c)).count();
+        let mut maximum_fraction_size = fraction_digits.len();
+
+        if minimum_integer_size == 0 && decimal_separators == 0 {
+            minimum_integer_size = 1;
+        }
+        if minimum_integer_size == 0 && maximum_fraction_size == 0 {
+            if exponent_size.is_some() {
+                minimum_fraction_size = 1;
+                maximum_fraction_size = maximum_fraction_size.max(1);
+            } else {
+                minimum_integer_size = 1;
+            }
+        }
+        if exponent_size.is_some()
+            && minimum_integer_size == 0
+            && integer_digits.contains(&format.digit())
+        {
+            minimum_integer_size = 1;
+        }
+
+        Ok(Self {
+            prefix: chars[..first_active].iter().collect(),
+            suffix: chars[last_active + 1..].iter().collect(),
+            integer_grouping: IntegerGrouping::new(integer_separators, integer_digits.len()),
+            minimum_integer_size,
+            scaling_factor,
+            fraction_grouping,
+            minimum_fraction_size,
+            maximum_fraction_size,
+            exponent_size,
+            scale,
+        })
+    }
+}
+
+/// A picture of `fn:format-integer`.
+///
+/// <https://www.w3.org/TR/xpath-functions-31/#func-format-integer>
+#[derive(Debug)]
+struct IntegerPicture {
+    token: FormatToken,
+    ordinal: bool,
+}
+
+#[derive(Debug)]
+enum FormatToken {
+    Digits {
+        zero: char,
+        minimum_size: usize,
+        grouping: IntegerGrouping,
+    },
+    Letters {
+        upper: bool,
+    },
+    Roman {
+        upper: bool,
+    },
+    Words(WordCase),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum WordCase {
+    Lower,
+    Upper,
+    Title,
+}
+
+impl IntegerPicture {
+    fn parse(picture: &str) -> Result<Self, ExpressionApplyError> {
+        let invalid = |reason: &str| invalid_picture("format-integer", picture, reason);
+
+        let (primary, modifier) = match picture.rsplit_once(';') {
+            Some((primary, modifier)) => (primary, modifier),
+            None => (picture, ""),
+        };
+        if primary.is_empty() {
+            return Err(invalid("the primary format token is empty"));
+        }
+
+        // The modifier is `c` or `o` with an optional `(variant)`, then an optional `a` or `t`.
+        let rest = modifier.strip_prefix(['c', 'o']).unwrap_or(modifier);
+        let rest = match rest.strip_prefix('(') {
+            Some(variant) => match variant.split_once(')') {
+                Some((_, rest)) => rest,
+                None => return Err(invalid("an unclosed format modifier variant")),
+            },
+            None => rest,
+        };
+        if !matches!(rest, "" | "a" | "t") {
+            return Err(invalid("an unknown format modifier"));
+        }
+        let ordinal = modifier.starts_with('o');
+
+        let token = if primary.chars().any(|c| digit_family(c).is_some()) {
+            Self::parse_digits(primary, &invalid)?
+        } else {
+            match primary {
+                "a" => FormatToken::Letters { upper: false },
+                "A" => FormatToken::Letters { upper: true },
+                "i" => FormatToken::Roman { upper: false },
+                "I" => FormatToken::Roman { upper: true },
+                "w" => FormatToken::Words(WordCase::Lower),
+                "W" => FormatToken::Words(WordCase::Upper),
+                "Ww" => FormatToken::Words(WordCase::Title),
+                // Other numbering sequences are not supported, and fall back to `1`.
+                _ => FormatToken::Digits {
+                    zero: '0',
+                    minimum_size: 1,
+                    grouping: IntegerGrouping::default(),
+                },
+            }
+        };
+
+        Ok(Self { token, ordinal })
+    }
+
+    /// Parse a decimal digit pattern such as `001` or `#,##0`.
+    fn parse_digits(
+        primary: &str,
+        invalid: &dyn Fn(&str) -> ExpressionApplyError,
+    ) -> Result<FormatToken, ExpressionApplyError> {
+        let chars: Vec<char> = primary.chars().collect();
+        let is_separator = |c: char| c != '#' && !c.is_alphanumeric();
+
+        let mut zero = None;
+        for &c in chars.iter() {
+            if let Some((family, _)) = digit_family(c) {
+                if zero.is_some_and(|zero| zero != family) {
+                    return Err(invalid("digits from more than one family"));
+                }
+                zero = Some(family);
+            } else if c != '#' && !is_separator(c) {
+                return Err(invalid("a letter in a decimal digit pattern"));
+            }
+        }
+        let zero = zero.ok_or_else(|| invalid("no digits"))?;
+
+        if chars.first().is_some_and(|&c| is_separator(c))
+            || chars.last().is_some_and(|&c| is_separator(c))
+            || chars
+                .windows(2)
+                .any(|w| is_separator(w[0]) && is_separator(w[1]))
+        {
+            return Err(invalid(
+                "a grouping separator at the start, end or next to another",
+            ));
+        }
+
+        let digit_positions: Vec<char> = chars
+            .iter()
+            .copied()
+            .filter(|&c| !is_separator(c))
+            .collect();
+        if digit_positions
+            .windows(2)
+            .any(|w| w[0] != '#' && w[1] == '#')
+        {
+            return Err(invalid("an optional digit after a mandatory digit"));
+        }
+
+        let mut separators = Vec::new();
+        for (i, &c) in chars.iter().enumerate() {
+            if is_separator(c) {
+                let position = chars[i + 1..].iter().filter(|&&c| !is_separator(c)).count();
+                separators.push((position, c));
+            }
+        }
+
+        Ok(FormatToken::Digits {
+            zero,
+            minimum_size: digit_positions.iter().filter(|&&c| c != '#').count(),
+            grouping: IntegerGrouping::new(separators, digit_positions.len()),
+        })
+    }
+
+    fn format(&self, value: i64) -> String {
+        let magnitude = value.unsigned_abs();
+        let sign = if value < 0 { "-" } else { "" };
+
+        let decimal = |zero: char, minimum_size: usize, grouping: &IntegerGrouping| {
+            let mut digits: Vec<char> = magnitude
+                .to_string()
+                .bytes()
+                .map(|b| char::from_u32(zero as u32 + (b - b'0') as u32).unwrap_or('?'))
+                .collect();
+            while digits.len() < minimum_size {
+                digits.insert(0, zero);
+            }
+            let mut formatted = grouping.apply(&digits);
+            if self.ordinal {
+                formatted.push_str(ordinal_suffix(magnitude));
+            }
+            formatted
+        };
+        let fallback = || decimal('0', 1, &IntegerGrouping::default());
+
+        let formatted = match &self.token {
+            FormatToken::Digits {
+                zero,
+                minimum_size,
+                grouping,
+            } => decimal(*zero, *minimum_size, grouping),
+            FormatToken::Letters { .. } if magnitude == 0 => fallback(),
+            FormatToken::Letters { upper } => letters(magnitude, *upper),
+            FormatToken::Roman { .. } if magnitude == 0 || magnitude >= 4000 => fallback(),
+            FormatToken::Roman { upper } => roman(magnitude, *upper),
+            FormatToken::Words(case) => {
+                let words = if self.ordinal {
+                    ordinal_words(magnitude)
+                } else {
+                    cardinal_words(magnitude)
+                };
+                match case {
+                    WordCase::Lower => words,
+                    WordCase::Upper => words.to_uppercase(),
+                    WordCase::Title => title_case(&words),
+                }
+            }
+        };
+
+        format!("{}{}", sign, formatted)
+    }
+}
+
+/// Get the zero of the family of decimal digits the character is in, and its value.
+fn digit_family(c: char) -> Option<(char, u32)> {
+    ZERO_DIGITS.iter().find_map(|&zero| {
+        let value = (c as u32).checked_sub(zero as u32)?;
+        (value < 10).then_some((zero, value))
+    })
+}
+
+/// The English suffix of an ordinal number, such as `st` in `1st`.
+fn ordinal_suffix(n: u64) -> &'static str {
+    match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
+
+/// Number with letters: `a`, `b`, …, `z`, `aa`, `ab`, ….
+fn letters(mut n: u64, upper: bool) -> String {
+    let first = if upper { b'A' } else { b'a' };
+    let mut letters = Vec::new();
+    while n > 0 {
+        n -= 1;
+        letters.push((first + (n % 26) as u8) as char);
+        n /= 26;
+    }
+
+    letters.into_iter().rev().collect()
+}
+
+/// Roman numerals for numbers from 1 to 3999.
+fn roman(mut n: u64, upper: bool) -> String {
+    const NUMERALS: [(u64, &str); 13] = [
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+
+    let mut numerals = String::new();
+    for (value, numeral) in NUMERALS {
+        while n >= value {
+            numerals.push_str(numeral);
+            n -= value;
+        }
+    }
+
+    if upper {
+        numerals.to_uppercase()
+    } else {
+        numerals
+    }
+}
+
+const ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+const SCALES: [(u64, &str); 6] = [
+    (1_000_000_000_000_000_000, "quintillion"),
+    (1_000_000_000_000_000, "quadrillion"),
+    (1_000_000_000_000, "trillion"),
+    (1_000_000_000, "billion"),
+    (1_000_000, "million"),
+    (1_000, "thousand"),
+];
+
+/// English words for a number, such as `one hundred twenty-three`.
+fn cardinal_words(n: u64) -> String {
+    if n < 20 {
+        return ONES[n as usize].to_string();
+    }
+    if n < 100 {
+        return match n % 10 {
+            0 => TENS[(n / 10) as usize].to_string(),
+            ones => format!("{}-{}", TENS[(n / 10) as usize], ONES[ones as usize]),
+        };
+    }
+
+    let (scale, name) = SCALES
+        .iter()
+        .copied()
+        .find(|&(scale, _)| n >= scale)
+        .unwrap_or((100, "hundred"));
+
+    let words = format!("{} {}", cardinal_words(n / scale), name);
+    match n % scale {
+        0 => words,
+        rest => format!("{} {}", words, cardinal_words(rest)),
+    }
+}
+
+/// English words for an ordinal number, such as `twenty-first`.
+fn ordinal_words(n: u64) -> String {
+    let cardinal = cardinal_words(n);
+    let split = cardinal.rfind([' ', '-']).map_or(0, |i| i + 1);
+    let (start, last) = cardinal.split_at(split);
+
+    let last = match last {
+        "one" => String::from("first"),
+        "two" => String::from("second"),
+        "three" => String::from("third"),
+        "five" => String::from("fifth"),
+        "eight" => String::from("eighth"),
+        "nine" => String::from("ninth"),
+        "twelve" => String::from("twelfth"),
+        tens if tens.ends_with('y') => format!("{}ieth", &tens[..tens.len() - 1]),
+        other => format!("{}th", other),
+    };
+
+    format!("{}{}", start, last)
+}
+
+/// Capitalize the first letter of every word, including words after a hyphen.
+fn title_case(words: &str) -> String {
+    let mut title = String::with_capacity(words.len());
+    let mut start_of_word = true;
+    for c in words.chars() {
+        if start_of_word {
+            title.extend(c.to_uppercase());
+        } else {
+            title.push(c);
+        }
+        start_of_word = matches!(c, ' ' | '-');
+    }
+
+    title
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decimal_round_should_round_half_to_even() {
+        // arrange
+        let cases = [
+            ("2.675", 2, "2.68"),
+            ("0.125", 2, "0.12"),
+            ("0.135", 2, "0.14"),
+            ("9.995", 2, "10"),
+            ("0.5", 0, "0"),
+            ("0.6", 0, "1"),
+            ("0.0004", 2, "0"),
+        ];
+
+        for (value, fraction_digits, expected) in cases {
+            let mut decimal = Decimal::from_scientific(value);
+
+            // act
+            decimal.round(fraction_digits);
+
+            // assert
+            let integer: String = decimal
+                .integer_digits()
+                .iter()
+                .map(|d| d.to_string())
+                .collect();
+            let fraction: String = decimal
+                .fraction_digits()
+                .iter()
+                .map(|d| d.to_string())
+                .collect();
+            let rounded = match (integer.as_str(), fraction.as_str()) {
+                ("", "") => String::from("0"),
+                (integer, "") => integer.to_string(),
+                ("", fraction) => format!("0.{}", fraction),
+                (integer, fraction) => format!("{}.{}", integer, fraction),
+            };
+            assert_eq!(rounded, expected, "rounding {}", value);
+        }
+    }
+
+    #[test]
+    fn ordinal_words_should_change_last_word() {
+        // arrange
+        let numbers = [1, 12, 20, 21, 100, 1_000_003];
+
+        // act
+        let words: Vec<String> = numbers.into_iter().map(ordinal_words).collect();
+
+        // assert
+        assert_eq!(
+            words,
+            vec![
+                "first",
+                "twelfth",
+                "twentieth",
+                "twenty-first",
+                "one hundredth",
+                "one million third"
+            ]
+        );
+    }
+}
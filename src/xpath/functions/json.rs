@@ -0,0 +1,324 @@
+//! <https://www.w3.org/TR/xpath-functions-31/#json-functions>
+
+use indextree::NodeId;
+use serde_json::{Map, Number, Value};
+
+use crate::{
+    xpath::{
+        grammar::{
+            data_model::{AnyAtomicType, Function, XpathArray, XpathItem, XpathMap},
+            Document, DocumentBuilder, XpathItemTreeNode,
+        },
+        xpath_item_set::XpathItemSet,
+        ErrorCode, ExpressionApplyError, XpathExpressionContext, XpathItemTree,
+    },
+    xpath_item_set,
+};
+
+use super::{strings::optional_string_argument, FunctionRegistry};
+
+pub(super) fn register(registry: &mut FunctionRegistry) {
+    registry.register_built_in("parse-json", 1, fn_parse_json);
+    registry.register_built_in("json-doc", 1, fn_json_doc);
+    registry.register_built_in("json-to-xml", 1, fn_json_to_xml);
+    registry.register_built_in("xml-to-json", 1, fn_xml_to_json);
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-parse-json>
+///
+/// Objects become maps, arrays become arrays, numbers become `xs:double`,
+/// and `null` becomes the empty sequence.
+fn fn_parse_json<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    if arguments[0].is_empty() {
+        return Ok(XpathItemSet::new());
+    }
+
+    let text = optional_string_argument("parse-json", &arguments[0], context, "first")?;
+    let value = parse("parse-json", &text)?;
+
+    Ok(value_to_items(&value))
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-json-doc>
+///
+/// The text is loaded with the [ResourceResolver](crate::xpath::resolver::ResourceResolver)
+/// of the dynamic context, then parsed like `fn:parse-json`.
+fn fn_json_doc<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    if arguments[0].is_empty() {
+        return Ok(XpathItemSet::new());
+    }
+
+    let uri = optional_string_argument("json-doc", &arguments[0], context, "first")?;
+    let text = context
+        .dynamic_context
+        .resolver_for(&uri)?
+        .resolve_text(&uri)?;
+    let value = parse("json-doc", &text)?;
+
+    Ok(value_to_items(&value))
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-json-to-xml>
+///
+/// Returns a document node containing the XML representation of the JSON,
+/// with elements named `map`, `array`, `string`, `number`, `boolean` and `null`,
+/// and the `key` attribute on the entries of a map.
+/// The elements are not in a namespace, so they can be selected without a prefix.
+///
+/// Calls with the same text in one evaluation return the same document node.
+fn fn_json_to_xml<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    if arguments[0].is_empty() {
+        return Ok(XpathItemSet::new());
+    }
+
+    let text = optional_string_argument("json-to-xml", &arguments[0], context, "first")?;
+
+    // Reusing documents keeps calls in predicates from adding a document per context item.
+    let document = context.budget.document(("json-to-xml", &text), || {
+        context
+            .item_tree
+            .add_document(|document| json_to_document(&text, document))
+    })?;
+    let document_node = context.item_tree.document_root_of(document);
+
+    Ok(xpath_item_set![XpathItem::Node(document_node)])
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-xml-to-json>
+///
+/// Takes the XML representation of JSON made by `fn:json-to-xml`, and returns the JSON text.
+fn fn_xml_to_json<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let node = match arguments[0].len() {
+        0 => return Ok(XpathItemSet::new()),
+        1 => match &arguments[0][0] {
+            XpathItem::Node(node) => *node,
+            _ => {
+                return Err(ExpressionApplyError::with_code(
+                    ErrorCode::XPTY0004,
+                    String::from("xml-to-json: expected a node"),
+                ))
+            }
+        },
+        len => {
+            return Err(ExpressionApplyError::with_code(
+                ErrorCode::XPTY0004,
+                format!("xml-to-json: unexpected item set length {}", len),
+            ))
+        }
+    };
+
+    let element = match node {
+        XpathItemTreeNode::DocumentNode(_) => single_child_element(node, context.item_tree)?,
+        _ => node,
+    };
+    let value = element_to_value(element, context.item_tree)?;
+
+    Ok(xpath_item_set![XpathItem::AnyAtomicType(
+        AnyAtomicType::String(value.to_string())
+    )])
+}
+
+fn parse(function_name: &str, text: &str) -> Result<Value, ExpressionApplyError> {
+    serde_json::from_str(text).map_err(|e| {
+        ExpressionApplyError::with_code(
+            ErrorCode::FOJS0001,
+            format!("{}: invalid JSON: {}", function_name, e),
+        )
+    })
+}
+
+/// Convert a JSON value to the maps, arrays and atomic values made by `fn:parse-json`.
+pub(super) fn value_to_items<'tree>(value: &Value) -> XpathItemSet<'tree> {
+    let item = match value {
+        Value::Null => return XpathItemSet::new(),
+        Value::Bool(b) => XpathItem::AnyAtomicType(AnyAtomicType::Boolean(*b)),
+        // Numbers keep their text, so those too large for a double become infinite.
+        Value::Number(n) => XpathItem::AnyAtomicType(AnyAtomicType::Double(
+            n.to_string().parse::<f64>().unwrap_or(f64::NAN).into(),
+        )),
+        Value::String(s) => XpathItem::AnyAtomicType(AnyAtomicType::String(s.clone())),
+        Value::Array(values) => {
+            let array: XpathArray = values.iter().map(value_to_items).collect();
+            XpathItem::Function(Function::Array(array))
+        }
+        Value::Object(entries) => {
+            let map: XpathMap = entries
+                .iter()
+                .map(|(key, value)| (AnyAtomicType::String(key.clone()), value_to_items(value)))
+                .collect();
+            XpathItem::Function(Function::Map(map))
+        }
+    };
+
+    xpath_item_set![item]
+}
+
+fn json_to_document(text: &str, document: usize) -> Result<Document, ExpressionApplyError> {
+    fn append_value(
+        builder: &mut DocumentBuilder,
+        parent: NodeId,
+        key: Option<&str>,
+        value: &Value,
+    ) {
+        let name = match value {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "map",
+        };
+
+        let element = builder.append_element(parent, name.to_string());
+        if let Some(key) = key {
+            builder.append_attribute(element, String::from("key"), key.to_string());
+        }
+
+        match value {
+            Value::Null => {}
+            Value::Bool(b) => {
+                builder.append_text(element, b.to_string());
+            }
+            Value::Number(n) => {
+                builder.append_text(element, n.to_string());
+            }
+            Value::String(s) => {
+                if !s.is_empty() {
+                    builder.append_text(element, s.clone());
+                }
+            }
+            Value::Array(values) => {
+                for value in values {
+                    append_value(builder, element, None, value);
+                }
+            }
+            Value::Object(entries) => {
+                for (key, value) in entries {
+                    append_value(builder, element, Some(key), value);
+                }
+            }
+        }
+    }
+
+    let value = parse("json-to-xml", text)?;
+
+    let mut builder = DocumentBuilder::new(document);
+    let root = builder.root();
+    append_value(&mut builder, root, None, &value);
+
+    Ok(builder.build())
+}
+
+fn invalid_xml(msg: String) -> ExpressionApplyError {
+    ExpressionApplyError::with_code(ErrorCode::FOJS0006, format!("xml-to-json: {}", msg))
+}
+
+/// Get the only element child of a node, ignoring whitespace.
+fn single_child_element<'tree>(
+    node: &XpathItemTreeNode,
+    tree: &'tree XpathItemTree,
+) -> Result<&'tree XpathItemTreeNode, ExpressionApplyError> {
+    let elements = child_elements(node, tree)?;
+    match elements.as_slice() {
+        [element] => Ok(element),
+        _ => Err(invalid_xml(format!(
+            "expected one element, found {}",
+            elements.len()
+        ))),
+    }
+}
+
+/// Get the element children of a node, failing on text that is not whitespace.
+fn child_elements<'tree>(
+    node: &XpathItemTreeNode,
+    tree: &'tree XpathItemTree,
+) -> Result<Vec<&'tree XpathItemTreeNode>, ExpressionApplyError> {
+    let mut elements = Vec::new();
+    for child in node.children(tree) {
+        match child {
+            XpathItemTreeNode::ElementNode(_) => elements.push(child),
+            XpathItemTreeNode::TextNode(text) if text.content.trim().is_empty() => {}
+            XpathItemTreeNode::TextNode(text) => {
+                return Err(invalid_xml(format!(
+                    "unexpected text '{}'",
+                    text.content.trim()
+                )))
+            }
+            _ => {}
+        }
+    }
+
+    Ok(elements)
+}
+
+fn element_to_value(
+    node: &XpathItemTreeNode,
+    tree: &XpathItemTree,
+) -> Result<Value, ExpressionApplyError> {
+    let element = match node {
+        XpathItemTreeNode::ElementNode(element) => element,
+        _ => return Err(invalid_xml(String::from("expected an element"))),
+    };
+
+    let value = match element.name.as_str() {
+        "null" => Value::Null,
+        "boolean" => match node.text_content(tree).trim() {
+            "true" | "1" => Value::Bool(true),
+            "false" | "0" => Value::Bool(false),
+            text => return Err(invalid_xml(format!("'{}' is not a boolean", text))),
+        },
+        "number" => {
+            let text = node.text_content(tree);
+            let text = text.trim();
+            let number = match text.parse::<i64>() {
+                Ok(n) => Some(Number::from(n)),
+                Err(_) => text.parse::<f64>().ok().and_then(Number::from_f64),
+            };
+
+            match number {
+                Some(number) => Value::Number(number),
+                None => return Err(invalid_xml(format!("'{}' is not a number", text))),
+            }
+        }
+        "string" => Value::String(node.text_content(tree)),
+        "array" => Value::Array(
+            child_elements(node, tree)?
+                .into_iter()
+                .map(|child| element_to_value(child, tree))
+                .collect::<Result<_, _>>()?,
+        ),
+        "map" => {
+            let mut entries = Map::new();
+            for child in child_elements(node, tree)? {
+                let key = match child {
+                    XpathItemTreeNode::ElementNode(child) => child.get_attribute(tree, "key"),
+                    _ => None,
+                };
+                let key =
+                    key.ok_or_else(|| invalid_xml(String::from("map entry has no key attribute")))?;
+
+                if entries.contains_key(key) {
+                    return Err(invalid_xml(format!("duplicate key '{}'", key)));
+                }
+
+                entries.insert(key.to_string(), element_to_value(child, tree)?);
+            }
+            Value::Object(entries)
+        }
+        name => return Err(invalid_xml(format!("unexpected element '{}'", name))),
+    };
+
+    Ok(value)
+}
@@ -0,0 +1,336 @@
+//! Functions that can be called from [Xpath](crate::xpath::Xpath) expressions.
+//!
+//! <https://www.w3.org/TR/xpath-functions-31/>
+//!
+//! Functions are looked up in a [FunctionRegistry] by their [ExpandedName] and arity.
+//! The registry is part of the [StaticContext](crate::xpath::StaticContext),
+//! and can be extended with Rust closures to add custom functions.
+
+use std::{borrow::Cow, collections::HashMap, fmt::Display, sync::Arc};
+
+use crate::xpath::{
+    xpath_item_set::XpathItemSet, ErrorCode, ExpressionApplyError, XpathExpressionContext,
+    XpathItemTree,
+};
+
+mod booleans;
+mod documents;
+mod errors;
+mod formatting;
+mod json;
+mod nodes;
+mod numbers;
+mod sequences;
+pub(crate) mod serialization;
+mod strings;
+mod uris;
+
+/// The namespace of the built-in functions, bound to the `fn` prefix.
+pub const FN_NAMESPACE: &str = "http://www.w3.org/2005/xpath-functions";
+
+/// Registered built-in functions that return a boolean.
+const BOOLEAN_FUNCTIONS: [&str; 8] = [
+    "true",
+    "false",
+    "not",
+    "contains",
+    "starts-with",
+    "ends-with",
+    "lang",
+    "doc-available",
+];
+
+/// A name qualified by a namespace URI.
+///
+/// <https://www.w3.org/TR/xpath-datamodel-31/#dt-expanded-qname>
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct ExpandedName {
+    /// The namespace URI of the name.
+    pub namespace: String,
+
+    /// The local part of the name.
+    pub local_name: String,
+}
+
+impl ExpandedName {
+    /// Create a new expanded name.
+    ///
+    /// # Arguments
+    ///
+    /// * `namespace` - The namespace URI of the name.
+    /// * `local_name` - The local part of the name.
+    pub fn new(namespace: &str, local_name: &str) -> Self {
+        Self {
+            namespace: namespace.to_string(),
+            local_name: local_name.to_string(),
+        }
+    }
+}
+
+impl Display for ExpandedName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Q{{{}}}{}", self.namespace, self.local_name)
+    }
+}
+
+/// A function implemented by the user.
+///
+/// Receives the evaluated arguments of the function call and the tree the expression is applied to.
+pub type ExternalFunction = dyn for<'tree> Fn(
+        &'tree XpathItemTree,
+        Vec<XpathItemSet<'tree>>,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError>
+    + Send
+    + Sync;
+
+/// A function implemented by this crate.
+///
+/// Built-in functions have access to the full expression context,
+/// since some of them depend on the context item.
+pub(crate) type BuiltInFunction =
+    for<'tree, 'ctx> fn(
+        &XpathExpressionContext<'tree, 'ctx>,
+        Vec<XpathItemSet<'tree>>,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError>;
+
+#[derive(Clone)]
+pub(crate) enum FunctionImplementation {
+    BuiltIn(BuiltInFunction),
+    External(Arc<ExternalFunction>),
+}
+
+impl FunctionImplementation {
+    /// Call the function with the evaluated arguments.
+    pub(crate) fn call<'tree>(
+        &self,
+        context: &XpathExpressionContext<'tree, '_>,
+        arguments: Vec<XpathItemSet<'tree>>,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        match self {
+            FunctionImplementation::BuiltIn(function) => function(context, arguments),
+            FunctionImplementation::External(function) => function(context.item_tree, arguments),
+        }
+    }
+}
+
+/// Functions that can be called from an expression, keyed by name and arity.
+///
+/// <https://www.w3.org/TR/2017/REC-xpath-31-20170321/#dt-known-func-signatures>
+#[derive(Clone)]
+pub struct FunctionRegistry {
+    functions: HashMap<(ExpandedName, usize), FunctionImplementation>,
+}
+
+impl FunctionRegistry {
+    /// Create a new [FunctionRegistry] containing the built-in functions.
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+
+        booleans::register(&mut registry);
+        documents::register(&mut registry);
+        errors::register(&mut registry);
+        formatting::register(&mut registry);
+        json::register(&mut registry);
+        nodes::register(&mut registry);
+        numbers::register(&mut registry);
+        sequences::register(&mut registry);
+        serialization::register(&mut registry);
+        strings::register(&mut registry);
+        uris::register(&mut registry);
+
+        registry
+    }
+
+    /// Create a new [FunctionRegistry] without any functions.
+    pub fn empty() -> Self {
+        Self {
+            functions: HashMap::new(),
+        }
+    }
+
+    /// Register a function.
+    ///
+    /// Registering a function with the same name and arity as an existing function replaces it,
+    /// which can be used to override built-in functions.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The expanded name of the function.
+    /// * `arity` - The number of arguments the function accepts.
+    /// * `function` - The implementation of the function.
+    ///   It receives the tree and one [XpathItemSet] per argument.
+    pub fn register<F>(&mut self, name: ExpandedName, arity: usize, function: F)
+    where
+        F: for<'tree> Fn(
+                &'tree XpathItemTree,
+                Vec<XpathItemSet<'tree>>,
+            ) -> Result<XpathItemSet<'tree>, ExpressionApplyError>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.functions.insert(
+            (name, arity),
+            FunctionImplementation::External(Arc::new(function)),
+        );
+    }
+
+    /// Whether a function with the given name and arity is registered.
+    pub fn contains(&self, name: &ExpandedName, arity: usize) -> bool {
+        self.functions.contains_key(&(name.clone(), arity))
+    }
+
+    /// Whether the function with the given name and arity is built in,
+    /// and has not been replaced by an extension function.
+    pub(crate) fn is_built_in(&self, name: &ExpandedName, arity: usize) -> bool {
+        matches!(
+            self.functions.get(&(name.clone(), arity)),
+            Some(FunctionImplementation::BuiltIn(_))
+        )
+    }
+
+    /// Whether the function with the given name and arity is a built-in that returns a boolean,
+    /// and has not been replaced by an extension function.
+    pub(crate) fn is_boolean_built_in(&self, name: &ExpandedName, arity: usize) -> bool {
+        name.namespace == FN_NAMESPACE
+            && BOOLEAN_FUNCTIONS.contains(&name.local_name.as_str())
+            && self.is_built_in(name, arity)
+    }
+
+    pub(crate) fn register_built_in(
+        &mut self,
+        local_name: &str,
+        arity: usize,
+        function: BuiltInFunction,
+    ) {
+        self.functions.insert(
+            (ExpandedName::new(FN_NAMESPACE, local_name), arity),
+            FunctionImplementation::BuiltIn(function),
+        );
+    }
+
+    /// Call the function with the given name and arity.
+    pub(crate) fn call<'tree>(
+        &self,
+        name: &ExpandedName,
+        context: &XpathExpressionContext<'tree, '_>,
+        arguments: Vec<XpathItemSet<'tree>>,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        let function = self.get(name, arguments.len()).ok_or_else(|| {
+            ExpressionApplyError::with_code(
+                ErrorCode::XPST0017,
+                format!("Unknown function {}#{}", name, arguments.len()),
+            )
+        })?;
+
+        function.call(context, arguments)
+    }
+
+    /// Get the function with the given name and arity.
+    pub(crate) fn get(&self, name: &ExpandedName, arity: usize) -> Option<&FunctionImplementation> {
+        self.functions.get(&(name.clone(), arity))
+    }
+}
+
+impl Default for FunctionRegistry {
+    fn default() -> Self {
+        FunctionRegistry::new()
+    }
+}
+
+/// Convert an argument that expects at most one item.
+///
+/// <https://www.w3.org/TR/2017/REC-xpath-31-20170321/#id-function-conversion-rules>
+///
+/// In XPath 1.0 compatibility mode, only the first item of a longer sequence is kept,
+/// so `string(//a)` is the string value of the first link rather than a type error.
+pub(super) fn single_item_argument<'a, 'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    argument: &'a XpathItemSet<'tree>,
+) -> Cow<'a, XpathItemSet<'tree>> {
+    if argument.len() > 1
+        && context
+            .dynamic_context
+            .static_context()
+            .xpath_1_0_compatibility()
+    {
+        Cow::Owned(argument.iter().take(1).cloned().collect())
+    } else {
+        Cow::Borrowed(argument)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        html,
+        xpath::{
+            grammar::data_model::{AnyAtomicType, XpathItem},
+            limits::EvaluationBudget,
+            DynamicContext,
+        },
+        xpath_item_set,
+    };
+
+    use super::*;
+
+    #[test]
+    fn call_should_invoke_registered_function() {
+        // arrange
+        let mut registry = FunctionRegistry::empty();
+        let name = ExpandedName::new("urn:test", "answer");
+        registry.register(name.clone(), 0, |_tree, _arguments| {
+            Ok(xpath_item_set![XpathItem::AnyAtomicType(
+                AnyAtomicType::Integer(42)
+            )])
+        });
+
+        let document = html::parse("<html></html>").unwrap();
+        let tree = XpathItemTree::from(&document);
+        let dynamic_context = DynamicContext::default();
+        let budget = EvaluationBudget::new(dynamic_context.limits());
+        let context = XpathExpressionContext::new_single(
+            &tree,
+            &dynamic_context,
+            &budget,
+            None,
+            XpathItem::Node(tree.root()),
+            true,
+        );
+
+        // act
+        let result = registry.call(&name, &context, vec![]).unwrap();
+
+        // assert
+        assert_eq!(
+            result,
+            xpath_item_set![XpathItem::AnyAtomicType(AnyAtomicType::Integer(42))]
+        );
+    }
+
+    #[test]
+    fn call_should_fail_for_wrong_arity() {
+        // arrange
+        let registry = FunctionRegistry::new();
+        let name = ExpandedName::new(FN_NAMESPACE, "contains");
+
+        let document = html::parse("<html></html>").unwrap();
+        let tree = XpathItemTree::from(&document);
+        let dynamic_context = DynamicContext::default();
+        let budget = EvaluationBudget::new(dynamic_context.limits());
+        let context = XpathExpressionContext::new_single(
+            &tree,
+            &dynamic_context,
+            &budget,
+            None,
+            XpathItem::Node(tree.root()),
+            true,
+        );
+
+        // act
+        let result = registry.call(&name, &context, vec![XpathItemSet::new()]);
+
+        // assert
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,264 @@
+//! <https://www.w3.org/TR/xpath-functions-31/#node-functions>
+
+use crate::{
+    xpath::{
+        grammar::{
+            data_model::{AnyAtomicType, XpathItem},
+            expressions::primary_expressions::static_function_calls::func_string,
+            XpathItemTreeNode,
+        },
+        xpath_item_set::XpathItemSet,
+        ErrorCode, ExpressionApplyError, XpathExpressionContext, XpathItemTree,
+    },
+    xpath_item_set,
+};
+
+use super::{
+    single_item_argument, strings::optional_string_argument, uris::node_argument, FunctionRegistry,
+};
+
+pub(super) fn register(registry: &mut FunctionRegistry) {
+    registry.register_built_in("root", 0, fn_root);
+    registry.register_built_in("root", 1, fn_root);
+    registry.register_built_in("id", 1, fn_id);
+    registry.register_built_in("id", 2, fn_id);
+    registry.register_built_in("lang", 1, fn_lang);
+    registry.register_built_in("lang", 2, fn_lang);
+    registry.register_built_in("path", 0, fn_path);
+    registry.register_built_in("path", 1, fn_path);
+    registry.register_built_in("generate-id", 0, fn_generate_id);
+    registry.register_built_in("generate-id", 1, fn_generate_id);
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-root>
+///
+/// Returns the document node of the document containing the node,
+/// which is either the argument or the context item.
+fn fn_root<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let argument = arguments
+        .first()
+        .map(|argument| single_item_argument(context, argument));
+    let item = match argument.as_deref() {
+        Some(argument) => match argument.len() {
+            0 => return Ok(XpathItemSet::new()),
+            1 => argument[0].clone(),
+            _ => {
+                return Err(ExpressionApplyError::with_code(
+                    ErrorCode::XPTY0004,
+                    String::from("fn:root expects at most one node"),
+                ))
+            }
+        },
+        None => context.item.clone(),
+    };
+
+    let node = match item {
+        XpathItem::Node(node) => node,
+        _ => {
+            return Err(ExpressionApplyError::with_code(
+                ErrorCode::XPTY0004,
+                String::from("fn:root expects a node"),
+            ))
+        }
+    };
+
+    // Nodes that are not kept in the tree belong to the main document.
+    let root = match node.id() {
+        Some(id) => context.item_tree.document_root(id),
+        None => context.item_tree.root(),
+    };
+
+    Ok(xpath_item_set![XpathItem::Node(root)])
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-id>
+///
+/// Returns the elements whose `id` attribute is one of the whitespace separated IDs,
+/// in the document containing the node, which is either the second argument or the context item.
+/// IDs are looked up in an index of the document that is built on first use.
+fn fn_id<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let node = match node_argument("id", context, &arguments[1..])? {
+        Some(node) => node,
+        None => {
+            return Err(ExpressionApplyError::with_code(
+                ErrorCode::XPTY0004,
+                String::from("fn:id expects a node as the second argument"),
+            ))
+        }
+    };
+
+    let document = match node.id() {
+        Some(id) => context.item_tree.document_root(id),
+        None => context.item_tree.root(),
+    };
+    let document = document.id().expect("document node has an id");
+
+    let mut items = XpathItemSet::new();
+    for value in arguments[0].iter() {
+        let value = match value {
+            XpathItem::AnyAtomicType(AnyAtomicType::String(s)) => s.clone(),
+            item => func_string(item, context.item_tree, context.budget)?,
+        };
+
+        for id in value.split_ascii_whitespace() {
+            if let Some(element) = context.item_tree.element_with_id(document, id) {
+                items.insert(XpathItem::Node(element));
+            }
+        }
+    }
+
+    Ok(items)
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-lang>
+///
+/// Uses the `xml:lang` or `lang` attribute of the nearest element that has one,
+/// starting with the node itself, which is either the second argument or the context item.
+/// Languages are compared without regard to ASCII case, and `lang('en')` also matches `en-GB`.
+fn fn_lang<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let test_lang = optional_string_argument("lang", &arguments[0], context, "first")?;
+    let node = match node_argument("lang", context, &arguments[1..])? {
+        Some(node) => node,
+        None => {
+            return Err(ExpressionApplyError::with_code(
+                ErrorCode::XPTY0004,
+                String::from("fn:lang expects a node as the second argument"),
+            ))
+        }
+    };
+
+    let mut current = Some(node);
+    let mut lang = None;
+    while let Some(node) = current {
+        if let XpathItemTreeNode::ElementNode(element) = node {
+            lang = element
+                .get_attribute(context.item_tree, "xml:lang")
+                .or_else(|| element.get_attribute(context.item_tree, "lang"));
+            if lang.is_some() {
+                break;
+            }
+        }
+        current = node.parent(context.item_tree);
+    }
+
+    let is_match = match lang {
+        Some(lang) => {
+            let lang = lang.trim();
+            lang.eq_ignore_ascii_case(&test_lang)
+                || (lang.len() > test_lang.len()
+                    && lang.as_bytes()[test_lang.len()] == b'-'
+                    && lang[..test_lang.len()].eq_ignore_ascii_case(&test_lang))
+        }
+        None => false,
+    };
+
+    Ok(xpath_item_set![XpathItem::AnyAtomicType(
+        AnyAtomicType::Boolean(is_match)
+    )])
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-path>
+///
+/// Returns a path such as `/Q{}html[1]/Q{}body[1]/Q{}a[2]/@href` that locates the node,
+/// which is either the argument or the context item.
+/// HTML elements have no namespace, so their names are written with an empty `Q{}`.
+fn fn_path<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let node = match node_argument("path", context, &arguments)? {
+        Some(node) => node,
+        None => return Ok(XpathItemSet::new()),
+    };
+
+    let mut steps = Vec::new();
+    let mut current = node;
+    while let Some(parent) = current.parent(context.item_tree) {
+        steps.push(path_step(context.item_tree, parent, current));
+        current = parent;
+    }
+
+    let path = if steps.is_empty() {
+        String::from("/")
+    } else {
+        steps
+            .iter()
+            .rev()
+            .map(|step| format!("/{}", step))
+            .collect()
+    };
+
+    Ok(xpath_item_set![XpathItem::AnyAtomicType(
+        AnyAtomicType::String(path)
+    )])
+}
+
+/// Get the step of a path that selects the node from its parent.
+fn path_step(tree: &XpathItemTree, parent: &XpathItemTreeNode, node: &XpathItemTreeNode) -> String {
+    // Count the preceding siblings that the step would also select.
+    let position = |is_same_kind: &dyn Fn(&XpathItemTreeNode) -> bool| {
+        parent
+            .children(tree)
+            .into_iter()
+            .filter(|sibling| is_same_kind(sibling))
+            .position(|sibling| std::ptr::eq(sibling, node))
+            .map_or(1, |i| i + 1)
+    };
+
+    match node {
+        XpathItemTreeNode::ElementNode(element) => {
+            let position = position(
+                &|sibling| matches!(sibling, XpathItemTreeNode::ElementNode(e) if e.name == element.name),
+            );
+            format!("Q{{}}{}[{}]", element.name, position)
+        }
+        XpathItemTreeNode::AttributeNode(attribute) => format!("@{}", attribute.name),
+        XpathItemTreeNode::TextNode(_) => {
+            let position = position(&|sibling| matches!(sibling, XpathItemTreeNode::TextNode(_)));
+            format!("text()[{}]", position)
+        }
+        XpathItemTreeNode::CommentNode(_) => {
+            let position =
+                position(&|sibling| matches!(sibling, XpathItemTreeNode::CommentNode(_)));
+            format!("comment()[{}]", position)
+        }
+        XpathItemTreeNode::PINode(_) => {
+            let position = position(&|sibling| matches!(sibling, XpathItemTreeNode::PINode(_)));
+            format!("processing-instruction()[{}]", position)
+        }
+        XpathItemTreeNode::DocumentNode(_) => String::new(),
+    }
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-generate-id>
+///
+/// Returns a token that is different for every node in the tree,
+/// and the same every time it is generated for the node,
+/// which is either the argument or the context item.
+fn fn_generate_id<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let token = match node_argument("generate-id", context, &arguments)? {
+        Some(node) => match node.id() {
+            Some(id) => id.token(),
+            // Nodes that are not kept in the tree cannot be told apart by position,
+            // so they are identified by where they are in memory.
+            None => format!("p{:x}", node as *const XpathItemTreeNode as usize),
+        },
+        None => String::new(),
+    };
+
+    Ok(xpath_item_set![XpathItem::AnyAtomicType(
+        AnyAtomicType::String(token)
+    )])
+}
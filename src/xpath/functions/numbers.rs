@@ -0,0 +1,52 @@
+//! <https://www.w3.org/TR/xpath-functions-31/#numeric-functions>
+
+use ordered_float::OrderedFloat;
+
+use crate::{
+    xpath::{
+        grammar::{
+            data_model::{AnyAtomicType, XpathItem},
+            expressions::primary_expressions::static_function_calls::{func_data, func_number},
+        },
+        xpath_item_set::XpathItemSet,
+        ErrorCode, ExpressionApplyError, XpathExpressionContext,
+    },
+    xpath_item_set,
+};
+
+use super::{single_item_argument, FunctionRegistry};
+
+pub(super) fn register(registry: &mut FunctionRegistry) {
+    registry.register_built_in("number", 0, fn_number);
+    registry.register_built_in("number", 1, fn_number);
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-number>
+///
+/// Without an argument, converts the context item.
+/// The empty sequence, and values that cannot be cast to a double, are `NaN`.
+fn fn_number<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let argument = match arguments.first() {
+        Some(argument) => single_item_argument(context, argument).into_owned(),
+        None => xpath_item_set![context.item.clone()],
+    };
+
+    let values = func_data(&argument, context.item_tree, context.budget)?;
+    let number = match values.as_slice() {
+        [] => f64::NAN,
+        [value] => func_number(value),
+        _ => {
+            return Err(ExpressionApplyError::with_code(
+                ErrorCode::XPTY0004,
+                String::from("number: expected at most one item"),
+            ))
+        }
+    };
+
+    Ok(xpath_item_set![XpathItem::AnyAtomicType(
+        AnyAtomicType::Double(OrderedFloat(number))
+    )])
+}
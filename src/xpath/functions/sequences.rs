@@ -0,0 +1,182 @@
+//! <https://www.w3.org/TR/xpath-functions-31/#general-seq-funcs>
+
+use std::{cmp::Ordering, collections::HashSet};
+
+use ordered_float::OrderedFloat;
+
+use crate::{
+    xpath::{
+        collation::Collation,
+        grammar::{
+            data_model::{AnyAtomicType, XpathItem},
+            expressions::primary_expressions::static_function_calls::func_data,
+        },
+        xpath_item_set::XpathItemSet,
+        ErrorCode, ExpressionApplyError, XpathExpressionContext,
+    },
+    xpath_item_set,
+};
+
+use super::{strings::collation_argument, FunctionRegistry};
+
+pub(super) fn register(registry: &mut FunctionRegistry) {
+    registry.register_built_in("distinct-values", 1, fn_distinct_values);
+    registry.register_built_in("distinct-values", 2, fn_distinct_values);
+    registry.register_built_in("sort", 1, fn_sort);
+    registry.register_built_in("sort", 2, fn_sort);
+    registry.register_built_in("count", 1, fn_count);
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-distinct-values>
+///
+/// Strings are equal when they are equal under the collation,
+/// and the first of the equal values is kept.
+fn fn_distinct_values<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let collation = collation_argument("distinct-values", arguments.get(1), context)?;
+    let values = func_data(&arguments[0], context.item_tree, context.budget)?;
+
+    let mut seen = HashSet::new();
+    let mut items = XpathItemSet::new();
+    for value in values {
+        if seen.insert(DistinctKey::new(&value, collation)) {
+            items.insert(XpathItem::AnyAtomicType(value));
+        }
+    }
+
+    Ok(items)
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-sort>
+///
+/// Items are sorted by their atomized values, comparing strings with the collation.
+/// The sort is stable, so items with equal values keep their order.
+///
+/// The result of [Xpath::apply](crate::xpath::Xpath::apply) is always in document order,
+/// so the sorted order is only seen by the rest of the expression, such as `sort(//td)[1]`.
+fn fn_sort<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let collation = collation_argument("sort", arguments.get(1), context)?;
+
+    let mut keyed = Vec::with_capacity(arguments[0].len());
+    for item in arguments[0].iter() {
+        let key = func_data(
+            &xpath_item_set![item.clone()],
+            context.item_tree,
+            context.budget,
+        )?;
+        keyed.push((key, item.clone()));
+    }
+
+    // Sorting cannot stop early, so remember the first error and return it afterwards.
+    let mut error = None;
+    keyed.sort_by(|(first, _), (second, _)| {
+        compare_keys(first, second, collation).unwrap_or_else(|e| {
+            error.get_or_insert(e);
+            Ordering::Equal
+        })
+    });
+
+    match error {
+        Some(e) => Err(e),
+        None => Ok(keyed.into_iter().map(|(_, item)| item).collect()),
+    }
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-count>
+fn fn_count<'tree>(
+    _context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    Ok(xpath_item_set![XpathItem::AnyAtomicType(
+        AnyAtomicType::Integer(arguments[0].len() as i64)
+    )])
+}
+
+/// Compare two sort keys value by value; a shorter key that matches the start
+/// of a longer key sorts first.
+fn compare_keys(
+    first: &[AnyAtomicType],
+    second: &[AnyAtomicType],
+    collation: &dyn Collation,
+) -> Result<Ordering, ExpressionApplyError> {
+    for (first, second) in first.iter().zip(second) {
+        match compare_atomic(first, second, collation)? {
+            Ordering::Equal => continue,
+            ordering => return Ok(ordering),
+        }
+    }
+
+    Ok(first.len().cmp(&second.len()))
+}
+
+/// Compare two atomic values of comparable types.
+///
+/// `NaN` sorts before every other number.
+fn compare_atomic(
+    first: &AnyAtomicType,
+    second: &AnyAtomicType,
+    collation: &dyn Collation,
+) -> Result<Ordering, ExpressionApplyError> {
+    match (first, second) {
+        (AnyAtomicType::String(first), AnyAtomicType::String(second)) => {
+            Ok(collation.compare(first, second))
+        }
+        (AnyAtomicType::Boolean(first), AnyAtomicType::Boolean(second)) => Ok(first.cmp(second)),
+        (AnyAtomicType::Integer(first), AnyAtomicType::Integer(second)) => Ok(first.cmp(second)),
+        _ => match (as_double(first), as_double(second)) {
+            (Some(first), Some(second)) => Ok(match (first.is_nan(), second.is_nan()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Less,
+                (false, true) => Ordering::Greater,
+                (false, false) => first.total_cmp(&second),
+            }),
+            _ => Err(ExpressionApplyError::with_code(
+                ErrorCode::XPTY0004,
+                format!("cannot compare {} with {}", first, second),
+            )),
+        },
+    }
+}
+
+fn as_double(value: &AnyAtomicType) -> Option<f64> {
+    match value {
+        AnyAtomicType::Integer(n) => Some(*n as f64),
+        AnyAtomicType::Float(n) => Some(n.0 as f64),
+        AnyAtomicType::Double(n) => Some(n.0),
+        _ => None,
+    }
+}
+
+/// Identifies values that `fn:distinct-values` treats as equal.
+#[derive(PartialEq, Eq, Hash)]
+enum DistinctKey {
+    String(String),
+    Boolean(bool),
+    Integer(i64),
+    Double(OrderedFloat<f64>),
+}
+
+impl DistinctKey {
+    fn new(value: &AnyAtomicType, collation: &dyn Collation) -> Self {
+        match value {
+            AnyAtomicType::String(s) => DistinctKey::String(collation.key(s).into_owned()),
+            AnyAtomicType::Boolean(b) => DistinctKey::Boolean(*b),
+            AnyAtomicType::Integer(n) => DistinctKey::Integer(*n),
+            AnyAtomicType::Float(_) | AnyAtomicType::Double(_) => {
+                let n = as_double(value).unwrap_or(f64::NAN);
+
+                // Whole numbers are equal to the integers with the same value.
+                if n.fract() == 0.0 && n >= i64::MIN as f64 && n < i64::MAX as f64 {
+                    DistinctKey::Integer(n as i64)
+                } else {
+                    DistinctKey::Double(OrderedFloat(n))
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,429 @@
+//! <https://www.w3.org/TR/xpath-functions-31/#func-serialize>
+
+use serde_json::{Map, Number, Value};
+
+use crate::{
+    xpath::{
+        grammar::{
+            data_model::{AnyAtomicType, Function, XpathItem},
+            XpathItemTreeNode,
+        },
+        xpath_item_set::XpathItemSet,
+        ErrorCode, ExpressionApplyError, XpathExpressionContext, XpathItemTree,
+    },
+    xpath_item_set,
+};
+
+use super::FunctionRegistry;
+
+pub(super) fn register(registry: &mut FunctionRegistry) {
+    registry.register_built_in("serialize", 1, fn_serialize);
+    registry.register_built_in("serialize", 2, fn_serialize);
+}
+
+/// HTML elements that never have content, and are written without an end tag.
+pub(crate) const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// <https://www.w3.org/TR/xslt-xquery-serialization-31/#serparam>
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum Method {
+    Xml,
+    Html,
+    Json,
+}
+
+/// The serialization parameters that are supported.
+#[derive(Debug, Clone, Copy)]
+struct Parameters {
+    method: Method,
+    indent: bool,
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-serialize>
+///
+/// The parameters are given as a map, such as `map { 'method': 'json', 'indent': true() }`.
+/// The `method` parameter can be `xml` (the default), `html` or `json`.
+fn fn_serialize<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let parameters = match arguments.get(1) {
+        Some(parameters) => parse_parameters(parameters)?,
+        None => Parameters {
+            method: Method::Xml,
+            indent: false,
+        },
+    };
+
+    let text = match parameters.method {
+        Method::Json => {
+            let value = match arguments[0].len() {
+                0 => Value::Null,
+                1 => item_to_json(&arguments[0][0], context.item_tree, parameters)?,
+                len => {
+                    return Err(ExpressionApplyError::with_code(
+                        ErrorCode::SERE0023,
+                        format!(
+                            "serialize: cannot serialize {} items with the JSON method",
+                            len
+                        ),
+                    ))
+                }
+            };
+
+            if parameters.indent {
+                serde_json::to_string_pretty(&value)
+            } else {
+                serde_json::to_string(&value)
+            }
+            .expect("serde_json values always serialize")
+        }
+        Method::Xml | Method::Html => {
+            serialize_markup(&arguments[0], context.item_tree, parameters)?
+        }
+    };
+
+    Ok(xpath_item_set![XpathItem::AnyAtomicType(
+        AnyAtomicType::String(text)
+    )])
+}
+
+fn invalid_parameter(msg: String) -> ExpressionApplyError {
+    ExpressionApplyError::with_code(ErrorCode::SEPM0016, format!("serialize: {}", msg))
+}
+
+fn parse_parameters(parameters: &XpathItemSet) -> Result<Parameters, ExpressionApplyError> {
+    let mut result = Parameters {
+        method: Method::Xml,
+        indent: false,
+    };
+
+    let map = match parameters.len() {
+        0 => return Ok(result),
+        1 => match &parameters[0] {
+            XpathItem::Function(Function::Map(map)) => map,
+            _ => {
+                return Err(ExpressionApplyError::with_code(
+                    ErrorCode::XPTY0004,
+                    String::from("serialize: parameters must be a map"),
+                ))
+            }
+        },
+        len => {
+            return Err(ExpressionApplyError::with_code(
+                ErrorCode::XPTY0004,
+                format!(
+                    "serialize: unexpected item set length {} for parameters",
+                    len
+                ),
+            ))
+        }
+    };
+
+    for (key, value) in map.iter() {
+        let value = match value.len() {
+            0 => continue,
+            1 => &value[0],
+            _ => {
+                return Err(invalid_parameter(format!(
+                    "parameter '{}' must be a single value",
+                    key
+                )))
+            }
+        };
+
+        match key.to_string().as_str() {
+            "method" => {
+                result.method = match value {
+                    XpathItem::AnyAtomicType(AnyAtomicType::String(s)) => match s.as_str() {
+                        "xml" => Method::Xml,
+                        "html" => Method::Html,
+                        "json" => Method::Json,
+                        _ => {
+                            return Err(invalid_parameter(format!(
+                                "method '{}' is not supported",
+                                s
+                            )))
+                        }
+                    },
+                    _ => return Err(invalid_parameter(String::from("method must be a string"))),
+                }
+            }
+            "indent" => {
+                result.indent = match value {
+                    XpathItem::AnyAtomicType(AnyAtomicType::Boolean(b)) => *b,
+                    _ => return Err(invalid_parameter(String::from("indent must be a boolean"))),
+                }
+            }
+            // Parameters that do not apply to the supported methods are ignored.
+            _ => {}
+        }
+    }
+
+    Ok(result)
+}
+
+fn item_to_json(
+    item: &XpathItem,
+    tree: &XpathItemTree,
+    parameters: Parameters,
+) -> Result<Value, ExpressionApplyError> {
+    let value = match item {
+        // Attributes cannot be serialized on their own, and have no JSON representation.
+        XpathItem::Node(XpathItemTreeNode::AttributeNode(attribute)) => {
+            return Err(ExpressionApplyError::with_code(
+                ErrorCode::SERE0021,
+                format!(
+                    "serialize: cannot serialize attribute {} with the JSON method",
+                    attribute.name
+                ),
+            ))
+        }
+        // Other nodes are serialized with the XML method, and written as a string.
+        XpathItem::Node(node) => {
+            let xml = Parameters {
+                method: Method::Xml,
+                ..parameters
+            };
+            let mut text = String::new();
+            write_node(&mut text, node, tree, xml, 0);
+            Value::String(text)
+        }
+        XpathItem::AnyAtomicType(atomic) => match atomic {
+            AnyAtomicType::Boolean(b) => Value::Bool(*b),
+            AnyAtomicType::Integer(n) => Value::Number(Number::from(*n)),
+            AnyAtomicType::Float(n) => number_to_json(n.0 as f64)?,
+            AnyAtomicType::Double(n) => number_to_json(n.0)?,
+            AnyAtomicType::String(s) => Value::String(s.clone()),
+        },
+        XpathItem::Function(Function::Array(array)) => {
+            let mut values = Vec::new();
+            for member in array.iter() {
+                values.push(sequence_to_json(member, tree, parameters)?);
+            }
+            Value::Array(values)
+        }
+        XpathItem::Function(Function::Map(map)) => {
+            let mut entries = Map::new();
+            for (key, value) in map.iter() {
+                let key = key.to_string();
+                if entries.contains_key(&key) {
+                    return Err(ExpressionApplyError::with_code(
+                        ErrorCode::SERE0022,
+                        format!("serialize: map has more than one key '{}'", key),
+                    ));
+                }
+
+                let value = sequence_to_json(value, tree, parameters)?;
+                entries.insert(key, value);
+            }
+            Value::Object(entries)
+        }
+    };
+
+    Ok(value)
+}
+
+/// Members of arrays and values of maps must have at most one item.
+fn sequence_to_json(
+    sequence: &XpathItemSet,
+    tree: &XpathItemTree,
+    parameters: Parameters,
+) -> Result<Value, ExpressionApplyError> {
+    match sequence.len() {
+        0 => Ok(Value::Null),
+        1 => item_to_json(&sequence[0], tree, parameters),
+        len => Err(ExpressionApplyError::with_code(
+            ErrorCode::SERE0023,
+            format!(
+                "serialize: cannot serialize {} items with the JSON method",
+                len
+            ),
+        )),
+    }
+}
+
+fn number_to_json(n: f64) -> Result<Value, ExpressionApplyError> {
+    // Whole numbers are written without a fraction, as `xs:double` values are cast to strings.
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        return Ok(Value::Number(Number::from(n as i64)));
+    }
+
+    Number::from_f64(n).map(Value::Number).ok_or_else(|| {
+        ExpressionApplyError::with_code(
+            ErrorCode::SERE0020,
+            format!("serialize: {} cannot be written as JSON", n),
+        )
+    })
+}
+
+fn serialize_markup(
+    sequence: &XpathItemSet,
+    tree: &XpathItemTree,
+    parameters: Parameters,
+) -> Result<String, ExpressionApplyError> {
+    let mut text = String::new();
+    let mut previous_was_atomic = false;
+    for item in sequence.iter() {
+        match item {
+            XpathItem::Node(node) => {
+                write_node(&mut text, node, tree, parameters, 0);
+                previous_was_atomic = false;
+            }
+            XpathItem::AnyAtomicType(atomic) => {
+                // Adjacent atomic values are separated by a space.
+                if previous_was_atomic {
+                    text.push(' ');
+                }
+                text.push_str(&escape_text(&atomic.to_string()));
+                previous_was_atomic = true;
+            }
+            XpathItem::Function(_) => {
+                return Err(ExpressionApplyError::with_code(
+                    ErrorCode::FOTY0014,
+                    String::from("serialize: maps and arrays cannot be serialized as markup"),
+                ))
+            }
+        }
+    }
+
+    Ok(text)
+}
+
+/// Serialize a node as XML, or as HTML if the method is `html`.
+fn write_node(
+    text: &mut String,
+    node: &XpathItemTreeNode,
+    tree: &XpathItemTree,
+    parameters: Parameters,
+    depth: usize,
+) {
+    match node {
+        XpathItemTreeNode::DocumentNode(_) => {
+            for child in content(node, tree, parameters) {
+                write_node(text, child, tree, parameters, depth);
+            }
+        }
+        XpathItemTreeNode::ElementNode(element) => {
+            if parameters.indent && depth > 0 {
+                text.push('\n');
+                text.push_str(&"  ".repeat(depth));
+            }
+
+            text.push('<');
+            text.push_str(&element.name);
+            for attribute in element.attributes(tree) {
+                text.push_str(&format!(
+                    " {}=\"{}\"",
+                    attribute.name,
+                    escape_attribute(&attribute.value)
+                ));
+            }
+
+            let children = content(node, tree, parameters);
+            let is_void = parameters.method == Method::Html
+                && VOID_ELEMENTS.contains(&element.name.to_ascii_lowercase().as_str());
+            if is_void {
+                text.push('>');
+                return;
+            }
+            if children.is_empty() && parameters.method == Method::Xml {
+                text.push_str("/>");
+                return;
+            }
+            text.push('>');
+
+            let has_elements = children
+                .iter()
+                .any(|child| matches!(child, XpathItemTreeNode::ElementNode(_)));
+            for child in children {
+                write_node(text, child, tree, parameters, depth + 1);
+            }
+
+            if parameters.indent && has_elements {
+                text.push('\n');
+                text.push_str(&"  ".repeat(depth));
+            }
+            text.push_str(&format!("</{}>", element.name));
+        }
+        XpathItemTreeNode::TextNode(text_node) => {
+            if parameters.indent {
+                text.push_str(&escape_text(text_node.content.trim()));
+            } else {
+                text.push_str(&escape_text(&text_node.content));
+            }
+        }
+        XpathItemTreeNode::AttributeNode(attribute) => {
+            text.push_str(&format!(
+                "{}=\"{}\"",
+                attribute.name,
+                escape_attribute(&attribute.value)
+            ));
+        }
+        XpathItemTreeNode::CommentNode(comment) => text.push_str(&comment.to_string()),
+        XpathItemTreeNode::PINode(pi) => text.push_str(&pi.to_string()),
+    }
+}
+
+/// The children of a node that are written as its content.
+///
+/// Attributes are written in the start tag, and whitespace is left out when indenting.
+fn content<'tree>(
+    node: &XpathItemTreeNode,
+    tree: &'tree XpathItemTree,
+    parameters: Parameters,
+) -> Vec<&'tree XpathItemTreeNode> {
+    node.children(tree)
+        .into_iter()
+        .filter(|child| match child {
+            XpathItemTreeNode::AttributeNode(_) => false,
+            XpathItemTreeNode::TextNode(text) => {
+                !(parameters.indent && text.content.trim().is_empty())
+            }
+            _ => true,
+        })
+        .collect()
+}
+
+pub(crate) fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+pub(crate) fn escape_attribute(value: &str) -> String {
+    escape_text(value).replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_attribute_should_escape_quotes_and_markup() {
+        // arrange
+        let value = r#"a "b" & <c>"#;
+
+        // act
+        let escaped = escape_attribute(value);
+
+        // assert
+        assert_eq!(escaped, "a &quot;b&quot; &amp; &lt;c&gt;");
+    }
+
+    #[test]
+    fn number_to_json_should_write_whole_numbers_without_fraction() {
+        // act
+        let whole = number_to_json(12.0).unwrap();
+        let fraction = number_to_json(12.5).unwrap();
+        let err = number_to_json(f64::NAN).unwrap_err();
+
+        // assert
+        assert_eq!(whole.to_string(), "12");
+        assert_eq!(fraction.to_string(), "12.5");
+        assert_eq!(err.code(), &ErrorCode::SERE0020);
+    }
+}
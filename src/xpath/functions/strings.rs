@@ -0,0 +1,219 @@
+//! <https://www.w3.org/TR/xpath-functions-31/#string-functions>
+
+use std::cmp::Ordering;
+
+use crate::{
+    xpath::{
+        collation::Collation,
+        grammar::{
+            data_model::{AnyAtomicType, XpathItem},
+            expressions::primary_expressions::static_function_calls::func_string,
+        },
+        xpath_item_set::XpathItemSet,
+        ErrorCode, ExpressionApplyError, XpathExpressionContext,
+    },
+    xpath_item_set,
+};
+
+use super::{single_item_argument, FunctionRegistry};
+
+pub(super) fn register(registry: &mut FunctionRegistry) {
+    registry.register_built_in("string", 0, fn_string);
+    registry.register_built_in("string", 1, fn_string);
+    registry.register_built_in("contains", 2, fn_contains);
+    registry.register_built_in("contains", 3, fn_contains);
+    registry.register_built_in("starts-with", 2, fn_starts_with);
+    registry.register_built_in("starts-with", 3, fn_starts_with);
+    registry.register_built_in("ends-with", 2, fn_ends_with);
+    registry.register_built_in("ends-with", 3, fn_ends_with);
+    registry.register_built_in("compare", 2, fn_compare);
+    registry.register_built_in("compare", 3, fn_compare);
+    registry.register_built_in("default-collation", 0, fn_default_collation);
+    registry.register_built_in("normalize-space", 0, fn_normalize_space);
+    registry.register_built_in("normalize-space", 1, fn_normalize_space);
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-string>
+///
+/// Without an argument, returns the string value of the context item.
+fn fn_string<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let value = match arguments.first() {
+        Some(argument) => optional_string_argument("string", argument, context, "first")?,
+        None => func_string(&context.item, context.item_tree, context.budget)?,
+    };
+
+    Ok(xpath_item_set![XpathItem::AnyAtomicType(
+        AnyAtomicType::String(value)
+    )])
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-normalize-space>
+///
+/// Strips leading and trailing whitespace and replaces each run of whitespace with a single space.
+/// Without an argument, normalizes the string value of the context item.
+fn fn_normalize_space<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let value = match arguments.first() {
+        Some(argument) => optional_string_argument("normalize-space", argument, context, "first")?,
+        None => func_string(&context.item, context.item_tree, context.budget)?,
+    };
+
+    // XML whitespace is space, tab, carriage return and line feed.
+    let normalized = value
+        .split([' ', '\t', '\r', '\n'])
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Ok(xpath_item_set![XpathItem::AnyAtomicType(
+        AnyAtomicType::String(normalized)
+    )])
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-contains>
+fn fn_contains<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    substring_match("contains", context, &arguments, |haystack, needle| {
+        haystack.contains(needle)
+    })
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-starts-with>
+fn fn_starts_with<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    substring_match("starts-with", context, &arguments, |haystack, needle| {
+        haystack.starts_with(needle)
+    })
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-ends-with>
+fn fn_ends_with<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    substring_match("ends-with", context, &arguments, |haystack, needle| {
+        haystack.ends_with(needle)
+    })
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-compare>
+///
+/// Returns -1, 0 or 1 depending on whether the first string is less than, equal to
+/// or greater than the second.
+fn fn_compare<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let collation = collation_argument("compare", arguments.get(2), context)?;
+
+    if arguments[0].is_empty() || arguments[1].is_empty() {
+        return Ok(XpathItemSet::new());
+    }
+
+    let first = optional_string_argument("compare", &arguments[0], context, "first")?;
+    let second = optional_string_argument("compare", &arguments[1], context, "second")?;
+
+    let result = match collation.compare(&first, &second) {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+    };
+
+    Ok(xpath_item_set![XpathItem::AnyAtomicType(
+        AnyAtomicType::Integer(result)
+    )])
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-default-collation>
+fn fn_default_collation<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    _arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let uri = context.dynamic_context.static_context().default_collation();
+
+    Ok(xpath_item_set![XpathItem::AnyAtomicType(
+        AnyAtomicType::String(uri.to_string())
+    )])
+}
+
+/// Match the collation keys of the first two arguments,
+/// using the collation named by the third argument if there is one.
+fn substring_match<'tree>(
+    function_name: &str,
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: &[XpathItemSet<'tree>],
+    is_match: impl Fn(&str, &str) -> bool,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let haystack = optional_string_argument(function_name, &arguments[0], context, "first")?;
+    let needle = optional_string_argument(function_name, &arguments[1], context, "second")?;
+    let collation = collation_argument(function_name, arguments.get(2), context)?;
+
+    let is_match = is_match(&collation.key(&haystack), &collation.key(&needle));
+
+    Ok(xpath_item_set![XpathItem::AnyAtomicType(
+        AnyAtomicType::Boolean(is_match)
+    )])
+}
+
+/// Get the collation named by an optional `xs:string` argument,
+/// or the default collation if there is no argument.
+pub(super) fn collation_argument<'context>(
+    function_name: &str,
+    argument: Option<&XpathItemSet<'_>>,
+    context: &'context XpathExpressionContext<'_, '_>,
+) -> Result<&'context dyn Collation, ExpressionApplyError> {
+    let uri = match argument {
+        Some(argument) => Some(optional_string_argument(
+            function_name,
+            argument,
+            context,
+            "collation",
+        )?),
+        None => None,
+    };
+
+    context
+        .dynamic_context
+        .static_context()
+        .resolve_collation(uri.as_deref())
+}
+
+/// Get the string value of an `xs:string?` argument.
+///
+/// The empty sequence is treated as the zero-length string.
+pub(super) fn optional_string_argument<'tree>(
+    function_name: &str,
+    argument: &XpathItemSet<'tree>,
+    context: &XpathExpressionContext<'tree, '_>,
+    position: &str,
+) -> Result<String, ExpressionApplyError> {
+    let argument = single_item_argument(context, argument);
+    if argument.len() > 1 {
+        return Err(ExpressionApplyError::with_code(
+            ErrorCode::XPTY0004,
+            format!(
+                "{}: unexpected item set length {} for {} argument",
+                function_name,
+                argument.len(),
+                position
+            ),
+        ));
+    }
+
+    let value = if argument.is_empty() {
+        String::from("")
+    } else {
+        func_string(&argument[0], context.item_tree, context.budget)?
+    };
+
+    Ok(value)
+}
@@ -0,0 +1,227 @@
+//! Functions on URIs.
+//!
+//! <https://www.w3.org/TR/xpath-functions-31/#func-resolve-uri>
+//! <https://www.w3.org/TR/xpath-functions-31/#func-base-uri>
+
+use crate::{
+    xpath::{
+        grammar::{
+            data_model::{AnyAtomicType, XpathItem},
+            XpathItemTreeNode,
+        },
+        uri,
+        xpath_item_set::XpathItemSet,
+        ErrorCode, ExpressionApplyError, XpathExpressionContext,
+    },
+    xpath_item_set,
+};
+
+use super::{single_item_argument, strings::optional_string_argument, FunctionRegistry};
+
+pub(super) fn register(registry: &mut FunctionRegistry) {
+    registry.register_built_in("base-uri", 0, fn_base_uri);
+    registry.register_built_in("base-uri", 1, fn_base_uri);
+    registry.register_built_in("document-uri", 0, fn_document_uri);
+    registry.register_built_in("document-uri", 1, fn_document_uri);
+    registry.register_built_in("resolve-uri", 1, fn_resolve_uri);
+    registry.register_built_in("resolve-uri", 2, fn_resolve_uri);
+    registry.register_built_in("encode-for-uri", 1, fn_encode_for_uri);
+    registry.register_built_in("iri-to-uri", 1, fn_iri_to_uri);
+    registry.register_built_in("escape-html-uri", 1, fn_escape_html_uri);
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-base-uri>
+///
+/// Every node in an HTML document has the base URI of the document,
+/// which honors the first `<base href>` in the document.
+fn fn_base_uri<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let node = match node_argument("base-uri", context, &arguments)? {
+        Some(node) => node,
+        None => return Ok(XpathItemSet::new()),
+    };
+
+    let base_uri = node.id().and_then(|id| context.item_tree.base_uri_of(id));
+
+    Ok(optional_string(base_uri))
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-document-uri>
+///
+/// Returns the URI set with
+/// [XpathItemTree::set_document_uri](crate::xpath::XpathItemTree::set_document_uri),
+/// or the URI a document was loaded from by `fn:doc`.
+fn fn_document_uri<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let node = match node_argument("document-uri", context, &arguments)? {
+        Some(node) => node,
+        None => return Ok(XpathItemSet::new()),
+    };
+
+    let document_uri = match node {
+        XpathItemTreeNode::DocumentNode(_) => node
+            .id()
+            .and_then(|id| context.item_tree.document_uri_of(id)),
+        _ => None,
+    };
+
+    Ok(optional_string(document_uri))
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-resolve-uri>
+///
+/// Without a base argument, the URI is resolved against the base URI of the context item
+/// if it is a node, so `//a/resolve-uri(@href)` resolves links against the page they are on.
+/// Otherwise the static base URI is used.
+/// The base URI must be absolute.
+fn fn_resolve_uri<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    if arguments[0].is_empty() {
+        return Ok(XpathItemSet::new());
+    }
+
+    let relative = optional_string_argument("resolve-uri", &arguments[0], context, "first")?;
+
+    let base = match arguments.get(1) {
+        Some(base) => Some(optional_string_argument(
+            "resolve-uri",
+            base,
+            context,
+            "second",
+        )?),
+        None => {
+            let node_base_uri = match &context.item {
+                XpathItem::Node(node) => node.id().and_then(|id| context.item_tree.base_uri_of(id)),
+                _ => None,
+            };
+
+            node_base_uri
+                .or_else(|| context.dynamic_context.static_context().base_uri())
+                .map(str::to_string)
+        }
+    };
+
+    let resolved = match base {
+        Some(base) if !uri::is_absolute(base.trim()) => {
+            return Err(ExpressionApplyError::with_code(
+                ErrorCode::FORG0002,
+                format!("resolve-uri: base URI {} is not absolute", base),
+            ))
+        }
+        Some(base) => uri::resolve(&relative, &base),
+        None if uri::is_absolute(relative.trim()) => uri::resolve(&relative, ""),
+        None => {
+            return Err(ExpressionApplyError::with_code(
+                ErrorCode::FONS0005,
+                format!("resolve-uri: no base URI to resolve {} against", relative),
+            ))
+        }
+    }
+    .map_err(|e| {
+        ExpressionApplyError::with_code(ErrorCode::FORG0002, format!("resolve-uri: {}", e))
+    })?;
+
+    Ok(xpath_item_set![XpathItem::AnyAtomicType(
+        AnyAtomicType::String(resolved)
+    )])
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-encode-for-uri>
+fn fn_encode_for_uri<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    encode("encode-for-uri", context, &arguments, |c| {
+        c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '~')
+    })
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-iri-to-uri>
+fn fn_iri_to_uri<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    encode("iri-to-uri", context, &arguments, |c| {
+        matches!(c, '\x21'..='\x7E')
+            && !matches!(c, '<' | '>' | '"' | '{' | '}' | '|' | '\\' | '^' | '`')
+    })
+}
+
+/// <https://www.w3.org/TR/xpath-functions-31/#func-escape-html-uri>
+fn fn_escape_html_uri<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: Vec<XpathItemSet<'tree>>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    encode("escape-html-uri", context, &arguments, |c| {
+        matches!(c, '\x20'..='\x7E')
+    })
+}
+
+/// Get the node a function applies to, which is either the argument or the context item.
+///
+/// Returns `None` if the argument is the empty sequence.
+pub(super) fn node_argument<'tree>(
+    function_name: &str,
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: &[XpathItemSet<'tree>],
+) -> Result<Option<&'tree XpathItemTreeNode>, ExpressionApplyError> {
+    let argument = arguments
+        .first()
+        .map(|argument| single_item_argument(context, argument));
+    let item = match argument.as_deref() {
+        Some(argument) => match argument.len() {
+            0 => return Ok(None),
+            1 => argument[0].clone(),
+            _ => {
+                return Err(ExpressionApplyError::with_code(
+                    ErrorCode::XPTY0004,
+                    format!("fn:{} expects at most one node", function_name),
+                ))
+            }
+        },
+        None => context.item.clone(),
+    };
+
+    match item {
+        XpathItem::Node(node) => Ok(Some(node)),
+        _ => Err(ExpressionApplyError::with_code(
+            ErrorCode::XPTY0004,
+            format!("fn:{} expects a node", function_name),
+        )),
+    }
+}
+
+fn optional_string<'tree>(value: Option<&str>) -> XpathItemSet<'tree> {
+    match value {
+        Some(value) => xpath_item_set![XpathItem::AnyAtomicType(AnyAtomicType::String(
+            value.to_string()
+        ))],
+        None => XpathItemSet::new(),
+    }
+}
+
+/// Percent-encode the string argument, keeping the characters that match `keep`.
+///
+/// The empty sequence encodes to the empty string.
+fn encode<'tree>(
+    function_name: &str,
+    context: &XpathExpressionContext<'tree, '_>,
+    arguments: &[XpathItemSet<'tree>],
+    keep: impl Fn(char) -> bool,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let text = if arguments[0].is_empty() {
+        String::new()
+    } else {
+        optional_string_argument(function_name, &arguments[0], context, "first")?
+    };
+
+    Ok(xpath_item_set![XpathItem::AnyAtomicType(
+        AnyAtomicType::String(uri::percent_encode(&text, keep))
+    )])
+}
@@ -0,0 +1,263 @@
+//! Maps and arrays, the function items that hold values.
+//!
+//! <https://www.w3.org/TR/xpath-datamodel-31/#map-items>
+//! <https://www.w3.org/TR/xpath-datamodel-31/#array-items>
+
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    hash::{Hash, Hasher},
+};
+
+use indexmap::IndexMap;
+
+use crate::xpath::xpath_item_set::XpathItemSet;
+
+use super::{AnyAtomicType, XpathItem};
+
+/// A map from atomic keys to values.
+///
+/// Entries keep the order they were added in.
+///
+/// <https://www.w3.org/TR/xpath-datamodel-31/#map-items>
+#[derive(Debug, Clone, Default)]
+pub struct XpathMap<'tree> {
+    entries: IndexMap<AnyAtomicType, XpathItemSet<'tree>>,
+}
+
+impl<'tree> XpathMap<'tree> {
+    /// Create a new empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the value of the entry with the given key.
+    pub fn get(&self, key: &AnyAtomicType) -> Option<&XpathItemSet<'tree>> {
+        self.entries.get(key)
+    }
+
+    /// Whether the map has an entry with the given key.
+    pub fn contains_key(&self, key: &AnyAtomicType) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Add an entry to the map, replacing any entry with the same key.
+    ///
+    /// Returns the value of the replaced entry.
+    pub fn insert(
+        &mut self,
+        key: AnyAtomicType,
+        value: XpathItemSet<'tree>,
+    ) -> Option<XpathItemSet<'tree>> {
+        self.entries.insert(key, value)
+    }
+
+    /// The number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Return an iterator over the entries of the map.
+    pub fn iter(&self) -> indexmap::map::Iter<'_, AnyAtomicType, XpathItemSet<'tree>> {
+        self.entries.iter()
+    }
+}
+
+impl<'tree> FromIterator<(AnyAtomicType, XpathItemSet<'tree>)> for XpathMap<'tree> {
+    fn from_iter<T: IntoIterator<Item = (AnyAtomicType, XpathItemSet<'tree>)>>(iter: T) -> Self {
+        Self {
+            entries: IndexMap::from_iter(iter),
+        }
+    }
+}
+
+// Maps are compared entry by entry in order, so that equal maps also hash the same.
+impl PartialEq for XpathMap<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries.iter().eq(other.entries.iter())
+    }
+}
+
+impl Eq for XpathMap<'_> {}
+
+impl PartialOrd for XpathMap<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for XpathMap<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.entries.iter().cmp(other.entries.iter())
+    }
+}
+
+impl Hash for XpathMap<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.entries.len().hash(state);
+        for entry in self.entries.iter() {
+            entry.hash(state);
+        }
+    }
+}
+
+impl Display for XpathMap<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "map{{")?;
+        for (i, (key, value)) in self.entries.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write_atomic(f, key)?;
+            write!(f, ": ")?;
+            write_sequence(f, value)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+/// An array of members, each of which is a sequence.
+///
+/// <https://www.w3.org/TR/xpath-datamodel-31/#array-items>
+#[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Clone, Hash, Default)]
+pub struct XpathArray<'tree> {
+    members: Vec<XpathItemSet<'tree>>,
+}
+
+impl<'tree> XpathArray<'tree> {
+    /// Create a new empty array.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the member at the given position.
+    ///
+    /// Positions start at 1, as they do in XPath.
+    pub fn get(&self, position: usize) -> Option<&XpathItemSet<'tree>> {
+        position
+            .checked_sub(1)
+            .and_then(|index| self.members.get(index))
+    }
+
+    /// Add a member to the end of the array.
+    pub fn push(&mut self, member: XpathItemSet<'tree>) {
+        self.members.push(member);
+    }
+
+    /// The number of members in the array.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Whether the array has no members.
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Return an iterator over the members of the array.
+    pub fn iter(&self) -> std::slice::Iter<'_, XpathItemSet<'tree>> {
+        self.members.iter()
+    }
+}
+
+impl<'tree> FromIterator<XpathItemSet<'tree>> for XpathArray<'tree> {
+    fn from_iter<T: IntoIterator<Item = XpathItemSet<'tree>>>(iter: T) -> Self {
+        Self {
+            members: Vec::from_iter(iter),
+        }
+    }
+}
+
+impl Display for XpathArray<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[")?;
+        for (i, member) in self.members.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write_sequence(f, member)?;
+        }
+        write!(f, "]")
+    }
+}
+
+fn write_atomic(f: &mut std::fmt::Formatter<'_>, atomic: &AnyAtomicType) -> std::fmt::Result {
+    match atomic {
+        AnyAtomicType::String(s) => write!(f, "\"{}\"", s.replace('"', "\"\"")),
+        _ => write!(f, "{}", atomic),
+    }
+}
+
+fn write_sequence(f: &mut std::fmt::Formatter<'_>, sequence: &XpathItemSet) -> std::fmt::Result {
+    let write_item = |f: &mut std::fmt::Formatter<'_>, item: &XpathItem| match item {
+        XpathItem::Node(node) => write!(f, "{:?}", node),
+        XpathItem::Function(function) => write!(f, "{}", function),
+        XpathItem::AnyAtomicType(atomic) => write_atomic(f, atomic),
+    };
+
+    if sequence.len() == 1 {
+        return write_item(f, &sequence[0]);
+    }
+
+    write!(f, "(")?;
+    for (i, item) in sequence.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write_item(f, item)?;
+    }
+    write!(f, ")")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::xpath_item_set;
+
+    use super::*;
+
+    fn string(s: &str) -> AnyAtomicType {
+        AnyAtomicType::String(String::from(s))
+    }
+
+    #[test]
+    fn map_should_keep_insertion_order() {
+        // arrange
+        let mut map = XpathMap::new();
+
+        // act
+        map.insert(
+            string("b"),
+            xpath_item_set![XpathItem::AnyAtomicType(string("1"))],
+        );
+        map.insert(
+            string("a"),
+            xpath_item_set![XpathItem::AnyAtomicType(AnyAtomicType::Integer(2))],
+        );
+
+        // assert
+        assert_eq!(map.to_string(), r#"map{"b": "1", "a": 2}"#);
+    }
+
+    #[test]
+    fn array_get_should_start_at_one() {
+        // arrange
+        let array = XpathArray::from_iter([
+            xpath_item_set![XpathItem::AnyAtomicType(AnyAtomicType::Integer(1))],
+            XpathItemSet::new(),
+        ]);
+
+        // act
+        let first = array.get(1);
+        let zeroth = array.get(0);
+
+        // assert
+        assert_eq!(first.unwrap().len(), 1);
+        assert!(zeroth.is_none());
+        assert_eq!(array.to_string(), "[1, ()]");
+    }
+}
@@ -1,12 +1,20 @@
 //! <https://www.w3.org/TR/xpath-datamodel-31/#intro>
 
+mod maps_and_arrays;
+
 use std::fmt::Display;
 
 use enum_extract_macro::EnumExtract;
-use indextree::NodeId;
 use ordered_float::OrderedFloat;
 
-use super::{TextIter, XpathItemTree, XpathItemTreeNode};
+use crate::xpath::limits::{EvaluationBudget, EvaluationLimits};
+
+use super::{
+    expressions::primary_expressions::static_function_calls::func_string, TextIter, TreeNodeId,
+    XpathItemTree, XpathItemTreeNode,
+};
+
+pub use maps_and_arrays::{XpathArray, XpathMap};
 
 /// <https://www.w3.org/TR/xpath-datamodel-31/#dt-item>
 #[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Clone, Hash, EnumExtract)]
@@ -19,7 +27,7 @@ pub enum XpathItem<'tree> {
     /// A function item.
     ///
     /// <https://www.w3.org/TR/xpath-datamodel-31/#dt-function-item>
-    Function(Function),
+    Function(Function<'tree>),
 
     /// An atomic value.
     ///
@@ -27,6 +35,23 @@ pub enum XpathItem<'tree> {
     AnyAtomicType(AnyAtomicType),
 }
 
+impl<'tree> XpathItem<'tree> {
+    /// Get the string value of the item.
+    ///
+    /// <https://www.w3.org/TR/xpath-functions-31/#func-string>
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - The tree that this item is a part of.
+    ///
+    /// Function items do not have a string value, and return the empty string.
+    pub fn string_value(&self, tree: &XpathItemTree) -> String {
+        // Outside of an evaluation there are no limits to check.
+        let budget = EvaluationBudget::new(&EvaluationLimits::new());
+        func_string(self, tree, &budget).unwrap_or_default()
+    }
+}
+
 impl<'tree> From<&'tree XpathItemTreeNode> for XpathItem<'tree> {
     fn from(node: &'tree XpathItemTreeNode) -> Self {
         XpathItem::Node(node)
@@ -67,22 +92,51 @@ impl Display for AnyAtomicType {
 }
 
 /// <https://www.w3.org/TR/xpath-datamodel-31/#dt-function-item>
-#[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Clone, Hash)]
-pub struct Function {
-    // TODO
+///
+/// Only maps and arrays are supported as function items.
+#[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Clone, Hash, EnumExtract)]
+pub enum Function<'tree> {
+    /// A map item.
+    Map(XpathMap<'tree>),
+
+    /// An array item.
+    Array(XpathArray<'tree>),
 }
 
-impl Display for Function {
-    fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!("Function::fmt")
+impl Display for Function<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Function::Map(map) => write!(f, "{}", map),
+            Function::Array(array) => write!(f, "{}", array),
+        }
     }
 }
 
 /// <https://www.w3.org/TR/xpath-datamodel-31/#DocumentNode>
 #[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Hash, Clone)]
-pub struct XpathDocumentNode {}
+pub struct XpathDocumentNode {
+    /// ID of this node in the tree.
+    ///
+    /// Can be considered always Some in a valid tree.
+    id: Option<TreeNodeId>,
+}
 
 impl XpathDocumentNode {
+    /// Create a new document node.
+    pub(crate) fn new() -> Self {
+        Self { id: None }
+    }
+
+    /// Set the ID of this node.
+    pub(crate) fn set_id(&mut self, id: TreeNodeId) {
+        self.id = Some(id);
+    }
+
+    /// Get the ID of this node.
+    pub(crate) fn id(&self) -> TreeNodeId {
+        self.id.unwrap()
+    }
+
     /// Get all text contained in this element and its descendants.
     ///
     /// # Arguments
@@ -94,10 +148,7 @@ impl XpathDocumentNode {
     /// A string of all text contained in this document and its descendants.
     pub fn text_content<'tree>(&self, tree: &'tree XpathItemTree) -> String {
         let strings: Vec<String> = tree
-            .root_node
-            .children(&tree.arena)
-            .into_iter()
-            .map(|x| tree.get(x))
+            .children_of(self.id())
             .map(|x| x.text_content(tree))
             .collect();
 
@@ -118,10 +169,7 @@ impl XpathDocumentNode {
     /// A string of all text contained in this document.
     pub fn text<'tree>(&self, tree: &'tree XpathItemTree) -> Option<String> {
         let strings: Vec<String> = tree
-            .root_node
-            .children(&tree.arena)
-            .into_iter()
-            .map(|x| tree.get(x))
+            .children_of(self.id())
             .map(|x| x.text(tree))
             .filter_map(|x| x.map(|x| x.to_string()))
             .collect();
@@ -139,10 +187,7 @@ impl XpathDocumentNode {
     ///
     /// A vector of all children of the document.
     pub fn children<'tree>(&self, tree: &'tree XpathItemTree) -> Vec<&'tree XpathItemTreeNode> {
-        tree.root_node
-            .children(&tree.arena)
-            .map(|x| tree.get(x))
-            .collect()
+        tree.children_of(self.id()).collect()
     }
 }
 impl Display for XpathDocumentNode {
@@ -160,7 +205,7 @@ pub struct ElementNode {
     ///
     /// Optional to enable construction of the tree before assigning IDs.
     /// Can be considered always Some in a valid tree.
-    id: Option<NodeId>,
+    id: Option<TreeNodeId>,
 
     /// The name of the element.
     pub name: String,
@@ -173,12 +218,12 @@ impl ElementNode {
     }
 
     /// Set the ID of the element.
-    pub(crate) fn set_id(&mut self, id: NodeId) {
+    pub(crate) fn set_id(&mut self, id: TreeNodeId) {
         self.id = Some(id);
     }
 
     /// Get the ID of the element.
-    pub(crate) fn id(&self) -> NodeId {
+    pub(crate) fn id(&self) -> TreeNodeId {
         self.id.unwrap()
     }
 
@@ -233,7 +278,7 @@ impl ElementNode {
         &self,
         tree: &'tree XpathItemTree,
     ) -> impl Iterator<Item = &'tree XpathItemTreeNode> {
-        self.id().children(&tree.arena).map(|x| tree.get(x))
+        tree.children_of(self.id())
     }
 
     /// Get the parent of the element.
@@ -337,7 +382,7 @@ pub struct AttributeNode {
     ///
     /// Optional to enable construction of the tree before assigning IDs.
     /// Can be considered always Some in a valid tree.
-    id: Option<NodeId>,
+    id: Option<TreeNodeId>,
 
     /// The name of the attribute.
     pub name: String,
@@ -357,12 +402,12 @@ impl AttributeNode {
     }
 
     /// Set the ID of the attribute.
-    pub(crate) fn set_id(&mut self, id: NodeId) {
+    pub(crate) fn set_id(&mut self, id: TreeNodeId) {
         self.id = Some(id);
     }
 
     /// Get the ID of the attribute.
-    pub(crate) fn id(&self) -> NodeId {
+    pub(crate) fn id(&self) -> TreeNodeId {
         self.id.unwrap()
     }
 }
@@ -380,8 +425,9 @@ pub struct PINode {
 }
 
 impl Display for PINode {
-    fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!("PINode::fmt")
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Processing instructions do not carry a target or content yet.
+        write!(f, "<??>")
     }
 }
 
@@ -405,7 +451,7 @@ pub struct TextNode {
     ///
     /// Optional to enable construction of the tree before assigning IDs.
     /// Can be considered always Some in a valid tree.
-    id: Option<NodeId>,
+    id: Option<TreeNodeId>,
 
     /// The value of the text node.
     pub content: String,
@@ -425,12 +471,12 @@ impl TextNode {
     }
 
     /// Set the ID of the text node.
-    pub(crate) fn set_id(&mut self, id: NodeId) {
+    pub(crate) fn set_id(&mut self, id: TreeNodeId) {
         self.id = Some(id);
     }
 
     /// Get the ID of the text node.
-    pub(crate) fn id(&self) -> NodeId {
+    pub(crate) fn id(&self) -> TreeNodeId {
         self.id.unwrap()
     }
 }
@@ -0,0 +1,250 @@
+//! Indexes of the elements of a document, used to answer common lookups without walking the tree.
+
+use std::collections::HashMap;
+
+use indextree::NodeId;
+
+use crate::xpath::grammar::data_model::ElementNode;
+
+use super::XpathItemTree;
+
+/// A condition that an element must meet to be returned by [ElementIndex::lookup].
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub(crate) enum ElementCondition<'a> {
+    /// The element has the given name.
+    Name(&'a str),
+
+    /// The element has an `id` attribute with the given value.
+    Id(&'a str),
+
+    /// The element has a class token that contains the given text.
+    ///
+    /// The text must not contain whitespace, so that it can only be found within a single token.
+    ClassContains(&'a str),
+
+    /// The element has a `class` attribute with the given value.
+    Class(&'a str),
+}
+
+/// The elements of a document keyed by their name, `id` attribute, `class` attribute and class tokens.
+///
+/// Elements are stored as their position in document order,
+/// so the results of lookups can be merged and returned in document order.
+pub(crate) struct ElementIndex {
+    /// The elements of the document in document order.
+    elements: Vec<NodeId>,
+
+    names: HashMap<String, Vec<usize>>,
+    ids: HashMap<String, Vec<usize>>,
+    classes: HashMap<String, Vec<usize>>,
+    class_tokens: HashMap<String, Vec<usize>>,
+}
+
+impl ElementIndex {
+    /// Index the given elements, which must be in document order.
+    pub fn new<'a>(
+        elements: impl Iterator<Item = &'a ElementNode>,
+        item_tree: &XpathItemTree,
+    ) -> Self {
+        let mut index = Self {
+            elements: Vec::new(),
+            names: HashMap::new(),
+            ids: HashMap::new(),
+            classes: HashMap::new(),
+            class_tokens: HashMap::new(),
+        };
+
+        for (position, element) in elements.enumerate() {
+            index.elements.push(element.id().node);
+            insert(&mut index.names, &element.name, position);
+
+            for attribute in element.attributes(item_tree) {
+                match attribute.name.as_str() {
+                    "id" => insert(&mut index.ids, &attribute.value, position),
+                    "class" => {
+                        insert(&mut index.classes, &attribute.value, position);
+                        for token in attribute.value.split_ascii_whitespace() {
+                            insert(&mut index.class_tokens, token, position);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        index
+    }
+
+    /// Get the elements that meet every condition, in document order.
+    ///
+    /// Every element in the document is returned if there are no conditions.
+    pub fn lookup(&self, conditions: &[ElementCondition]) -> Vec<NodeId> {
+        let mut matches: Option<Vec<usize>> = None;
+        for condition in conditions.iter() {
+            let positions = self.positions(condition);
+            matches = Some(match matches {
+                Some(matches) => intersect(&matches, &positions),
+                None => positions,
+            });
+
+            if matches.as_ref().is_some_and(|matches| matches.is_empty()) {
+                break;
+            }
+        }
+
+        match matches {
+            Some(matches) => matches
+                .into_iter()
+                .map(|position| self.elements[position])
+                .collect(),
+            None => self.elements.clone(),
+        }
+    }
+
+    /// Get the sorted positions of the elements that meet the condition.
+    fn positions(&self, condition: &ElementCondition) -> Vec<usize> {
+        fn get(map: &HashMap<String, Vec<usize>>, key: &str) -> Vec<usize> {
+            map.get(key).cloned().unwrap_or_default()
+        }
+
+        match condition {
+            ElementCondition::Name(name) => get(&self.names, name),
+            ElementCondition::Id(id) => get(&self.ids, id),
+            ElementCondition::ClassContains(text) => {
+                let mut positions: Vec<usize> = self
+                    .class_tokens
+                    .iter()
+                    .filter(|(token, _)| token.contains(text))
+                    .flat_map(|(_, positions)| positions.iter().copied())
+                    .collect();
+                positions.sort_unstable();
+                positions.dedup();
+                positions
+            }
+            ElementCondition::Class(value) => get(&self.classes, value),
+        }
+    }
+}
+
+/// Add a position to the list for the key, unless it was just added.
+fn insert(map: &mut HashMap<String, Vec<usize>>, key: &str, position: usize) {
+    let positions = map.entry(key.to_string()).or_default();
+    if positions.last() != Some(&position) {
+        positions.push(position);
+    }
+}
+
+/// Get the positions in both of the sorted lists.
+fn intersect(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let mut result = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                result.push(a[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html;
+
+    use super::*;
+
+    fn lookup_names(text: &str, conditions: &[ElementCondition]) -> Vec<String> {
+        let tree = XpathItemTree::from(&html::parse(text).unwrap());
+        let root = tree.root().id().unwrap();
+
+        tree.find_elements(root, conditions)
+            .into_iter()
+            .map(|node| {
+                let element = node.as_element_node().unwrap();
+                match element.get_attribute(&tree, "id") {
+                    Some(id) => format!("{}#{}", element.name, id),
+                    None => element.name.clone(),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lookup_should_return_every_element_without_conditions() {
+        // arrange
+        let text = "<html><body><p></p></body></html>";
+
+        // act
+        let names = lookup_names(text, &[]);
+
+        // assert
+        assert_eq!(names, vec!["html", "body", "p"]);
+    }
+
+    #[test]
+    fn lookup_should_return_names_in_document_order() {
+        // arrange
+        let text = r#"<html><div id="1"><div id="2"></div></div><div id="3"></div></html>"#;
+
+        // act
+        let names = lookup_names(text, &[ElementCondition::Name("div")]);
+
+        // assert
+        assert_eq!(names, vec!["div#1", "div#2", "div#3"]);
+    }
+
+    #[test]
+    fn lookup_should_intersect_conditions() {
+        // arrange
+        let text = r#"<html><div id="a"></div><p id="a"></p><div id="b"></div></html>"#;
+
+        // act
+        let names = lookup_names(
+            text,
+            &[ElementCondition::Name("div"), ElementCondition::Id("a")],
+        );
+
+        // assert
+        assert_eq!(names, vec!["div#a"]);
+    }
+
+    #[test]
+    fn lookup_should_find_class_tokens_containing_text() {
+        // arrange
+        let text = r#"
+            <html>
+                <p id="1" class="product-card featured"></p>
+                <p id="2" class="product"></p>
+                <p id="3" class="banner"></p>
+            </html>"#;
+
+        // act
+        let names = lookup_names(text, &[ElementCondition::ClassContains("product")]);
+
+        // assert
+        assert_eq!(names, vec!["p#1", "p#2"]);
+    }
+
+    #[test]
+    fn lookup_should_match_whole_class_attribute() {
+        // arrange
+        let text = r#"
+            <html>
+                <p id="1" class="a b"></p>
+                <p id="2" class="b"></p>
+                <p id="3" class="b a"></p>
+            </html>"#;
+
+        // act
+        let names = lookup_names(text, &[ElementCondition::Class("a b")]);
+
+        // assert
+        assert_eq!(names, vec!["p#1"]);
+    }
+}
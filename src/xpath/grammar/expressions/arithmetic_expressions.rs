@@ -4,28 +4,38 @@ use std::fmt::Display;
 
 use nom::{
     branch::alt,
-    bytes::complete::tag,
     character::complete::{char, multispace0},
     error::context,
     multi::many0,
     sequence::tuple,
 };
 
-use crate::xpath::{
-    grammar::{
-        expressions::sequence_expressions::combining_node_sequences::union_expr, recipes::Res,
-        terminal_symbols::symbol_separator, whitespace_recipes::ws,
+use crate::xpath::grammar::recipes::tag;
+
+use ordered_float::OrderedFloat;
+
+use crate::{
+    xpath::{
+        grammar::{
+            data_model::{AnyAtomicType, XpathItem},
+            expressions::sequence_expressions::combining_node_sequences::union_expr,
+            recipes::Res,
+            terminal_symbols::symbol_separator,
+            whitespace_recipes::ws,
+        },
+        xpath_item_set::XpathItemSet,
+        ErrorCode, ExpressionApplyError, XpathExpressionContext,
     },
-    xpath_item_set::XpathItemSet,
-    ExpressionApplyError, XpathExpressionContext,
+    xpath_item_set,
 };
 
 use super::{
+    primary_expressions::static_function_calls::{func_data, func_number, parse_double},
     sequence_expressions::combining_node_sequences::UnionExpr,
     simple_map_operator::{simple_map_expr, SimpleMapExpr},
 };
 
-pub fn additive_expr(input: &str) -> Res<&str, AdditiveExpr> {
+pub(crate) fn additive_expr(input: &str) -> Res<&str, AdditiveExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-AdditiveExpr
 
     fn plus(input: &str) -> Res<&str, AdditiveExprOperator> {
@@ -54,6 +64,7 @@ pub fn additive_expr(input: &str) -> Res<&str, AdditiveExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AdditiveExpr {
     pub expr: MultiplicativeExpr,
     pub items: Vec<AdditiveExprPair>,
@@ -73,7 +84,7 @@ impl Display for AdditiveExpr {
 impl AdditiveExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         // Evaluate the first expression.
         let result = self.expr.eval(context)?;
@@ -83,12 +94,24 @@ impl AdditiveExpr {
             return Ok(result);
         }
 
-        // Otherwise, do the operation.
-        todo!("AdditiveExpr::eval additive operator")
+        // Otherwise, apply the operators from left to right.
+        let mut result = result;
+        for item in self.items.iter() {
+            let operator = match item.0 {
+                AdditiveExprOperator::Plus => ArithmeticOperator::Add,
+                AdditiveExprOperator::Minus => ArithmeticOperator::Subtract,
+            };
+            let second_result = item.1.eval(context)?;
+            result = arithmetic(operator, &result, &second_result, context)
+                .map_err(|e| e.in_expression(self))?;
+        }
+
+        Ok(result)
     }
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AdditiveExprPair(pub AdditiveExprOperator, pub MultiplicativeExpr);
 
 impl Display for AdditiveExprPair {
@@ -98,6 +121,7 @@ impl Display for AdditiveExprPair {
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AdditiveExprOperator {
     Plus,
     Minus,
@@ -153,6 +177,7 @@ fn multiplicative_expr(input: &str) -> Res<&str, MultiplicativeExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MultiplicativeExpr {
     pub expr: UnionExpr,
     pub items: Vec<MultiplicativeExprPair>,
@@ -172,7 +197,7 @@ impl Display for MultiplicativeExpr {
 impl MultiplicativeExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         // Evaluate the first expression.
         let result = self.expr.eval(context)?;
@@ -182,12 +207,26 @@ impl MultiplicativeExpr {
             return Ok(result);
         }
 
-        // Otherwise, do the operation.
-        todo!("MultiplicativeExpr::eval multiplicative operator")
+        // Otherwise, apply the operators from left to right.
+        let mut result = result;
+        for item in self.items.iter() {
+            let operator = match item.0 {
+                MultiplicativeExprOperator::Star => ArithmeticOperator::Multiply,
+                MultiplicativeExprOperator::Div => ArithmeticOperator::Divide,
+                MultiplicativeExprOperator::IntegerDiv => ArithmeticOperator::IntegerDivide,
+                MultiplicativeExprOperator::Modulus => ArithmeticOperator::Modulus,
+            };
+            let second_result = item.1.eval(context)?;
+            result = arithmetic(operator, &result, &second_result, context)
+                .map_err(|e| e.in_expression(self))?;
+        }
+
+        Ok(result)
     }
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MultiplicativeExprPair(pub MultiplicativeExprOperator, pub UnionExpr);
 
 impl Display for MultiplicativeExprPair {
@@ -197,6 +236,7 @@ impl Display for MultiplicativeExprPair {
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MultiplicativeExprOperator {
     Star,
     Div,
@@ -215,7 +255,7 @@ impl Display for MultiplicativeExprOperator {
     }
 }
 
-pub fn unary_expr(input: &str) -> Res<&str, UnaryExpr> {
+pub(crate) fn unary_expr(input: &str) -> Res<&str, UnaryExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-UnaryExpr
 
     fn plus(input: &str) -> Res<&str, UnarySymbol> {
@@ -240,6 +280,7 @@ pub fn unary_expr(input: &str) -> Res<&str, UnaryExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UnaryExpr {
     pub leading_symbols: Vec<UnarySymbol>,
     pub expr: ValueExpr,
@@ -258,7 +299,7 @@ impl Display for UnaryExpr {
 impl UnaryExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         // Evaluate the first expression.
         let result = self.expr.eval(context)?;
@@ -268,12 +309,34 @@ impl UnaryExpr {
             return Ok(result);
         }
 
-        // Otherwise, do the operation.
-        todo!("UnaryExpr::eval unary operator")
+        // Otherwise, negate the operand if there is an odd number of minus signs.
+        let operand = numeric_operand(&result, context).map_err(|e| e.in_expression(self))?;
+        let negate = self
+            .leading_symbols
+            .iter()
+            .filter(|symbol| **symbol == UnarySymbol::Minus)
+            .count()
+            % 2
+            == 1;
+
+        let value = match operand {
+            None => return Ok(XpathItemSet::new()),
+            Some(value) if !negate => value,
+            Some(AnyAtomicType::Integer(n)) => AnyAtomicType::Integer(
+                n.checked_neg()
+                    .ok_or_else(|| overflow().in_expression(self))?,
+            ),
+            Some(AnyAtomicType::Float(n)) => AnyAtomicType::Float(-n),
+            Some(AnyAtomicType::Double(n)) => AnyAtomicType::Double(-n),
+            Some(value) => value,
+        };
+
+        Ok(xpath_item_set![XpathItem::AnyAtomicType(value)])
     }
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum UnarySymbol {
     Plus,
     Minus,
@@ -288,6 +351,183 @@ impl Display for UnarySymbol {
     }
 }
 
+/// The operators of additive and multiplicative expressions.
+#[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum ArithmeticOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    IntegerDivide,
+    Modulus,
+}
+
+/// Apply an arithmetic operator to the results of two expressions.
+///
+/// <https://www.w3.org/TR/2017/REC-xpath-31-20170321/#id-arithmetic>
+///
+/// If either operand is empty, the result is empty. Two integers give an integer,
+/// except for `div`, which gives a double since there is no `xs:decimal` type.
+/// Otherwise the result is a double if either operand is a double, and a float if not.
+fn arithmetic<'tree>(
+    operator: ArithmeticOperator,
+    first: &XpathItemSet<'tree>,
+    second: &XpathItemSet<'tree>,
+    context: &XpathExpressionContext<'tree, '_>,
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    let (first, second) = match (
+        numeric_operand(first, context)?,
+        numeric_operand(second, context)?,
+    ) {
+        (Some(first), Some(second)) => (first, second),
+        _ => return Ok(XpathItemSet::new()),
+    };
+
+    let value = match (first, second) {
+        (AnyAtomicType::Integer(first), AnyAtomicType::Integer(second)) => {
+            integer_arithmetic(operator, first, second)?
+        }
+        (first, second) => {
+            let is_double = matches!(first, AnyAtomicType::Double(_))
+                || matches!(second, AnyAtomicType::Double(_));
+            let first = func_number(&first);
+            let second = func_number(&second);
+
+            match operator {
+                ArithmeticOperator::Add => float_value(first + second, is_double),
+                ArithmeticOperator::Subtract => float_value(first - second, is_double),
+                ArithmeticOperator::Multiply => float_value(first * second, is_double),
+                ArithmeticOperator::Divide => float_value(first / second, is_double),
+                ArithmeticOperator::Modulus => float_value(first % second, is_double),
+                ArithmeticOperator::IntegerDivide => {
+                    if second == 0.0 {
+                        return Err(division_by_zero());
+                    }
+
+                    let quotient = (first / second).trunc();
+                    if !quotient.is_finite()
+                        || quotient < i64::MIN as f64
+                        || quotient >= i64::MAX as f64
+                    {
+                        return Err(overflow());
+                    }
+
+                    AnyAtomicType::Integer(quotient as i64)
+                }
+            }
+        }
+    };
+
+    Ok(xpath_item_set![XpathItem::AnyAtomicType(value)])
+}
+
+fn integer_arithmetic(
+    operator: ArithmeticOperator,
+    first: i64,
+    second: i64,
+) -> Result<AnyAtomicType, ExpressionApplyError> {
+    let value = match operator {
+        ArithmeticOperator::Add => first.checked_add(second),
+        ArithmeticOperator::Subtract => first.checked_sub(second),
+        ArithmeticOperator::Multiply => first.checked_mul(second),
+        ArithmeticOperator::Divide => {
+            if second == 0 {
+                return Err(division_by_zero());
+            }
+
+            return Ok(AnyAtomicType::Double(OrderedFloat(
+                first as f64 / second as f64,
+            )));
+        }
+        ArithmeticOperator::IntegerDivide => {
+            if second == 0 {
+                return Err(division_by_zero());
+            }
+
+            first.checked_div(second)
+        }
+        ArithmeticOperator::Modulus => {
+            if second == 0 {
+                return Err(division_by_zero());
+            }
+
+            Some(first.wrapping_rem(second))
+        }
+    };
+
+    value.map(AnyAtomicType::Integer).ok_or_else(overflow)
+}
+
+fn float_value(value: f64, is_double: bool) -> AnyAtomicType {
+    if is_double {
+        AnyAtomicType::Double(OrderedFloat(value))
+    } else {
+        AnyAtomicType::Float(OrderedFloat(value as f32))
+    }
+}
+
+/// Get the value of an operand of an arithmetic expression.
+///
+/// Returns `None` for the empty sequence. Strings, such as the text of nodes,
+/// are treated as untyped values and cast to doubles.
+///
+/// In XPath 1.0 compatibility mode, the operand is converted with `fn:number` instead:
+/// only the first item is used, and the empty sequence and invalid strings are `NaN`.
+fn numeric_operand<'tree>(
+    operand: &XpathItemSet<'tree>,
+    context: &XpathExpressionContext<'tree, '_>,
+) -> Result<Option<AnyAtomicType>, ExpressionApplyError> {
+    if context
+        .dynamic_context
+        .static_context()
+        .xpath_1_0_compatibility()
+    {
+        let head = operand.iter().take(1).cloned().collect();
+        let number = match func_data(&head, context.item_tree, context.budget)?.first() {
+            Some(value) => func_number(value),
+            None => f64::NAN,
+        };
+
+        return Ok(Some(AnyAtomicType::Double(OrderedFloat(number))));
+    }
+
+    let values = func_data(operand, context.item_tree, context.budget)?;
+    let value = match values.as_slice() {
+        [] => return Ok(None),
+        [value] => value,
+        _ => return Err(ExpressionApplyError::with_code(
+            ErrorCode::XPTY0004,
+            String::from(
+                "An operand of an arithmetic expression is a sequence of length greater than one",
+            ),
+        )),
+    };
+
+    match value {
+        AnyAtomicType::String(s) => match parse_double(s) {
+            Some(n) => Ok(Some(AnyAtomicType::Double(OrderedFloat(n)))),
+            None => Err(ExpressionApplyError::with_code(
+                ErrorCode::FORG0001,
+                format!("cannot cast {:?} to a double", s),
+            )),
+        },
+        AnyAtomicType::Boolean(_) => Err(ExpressionApplyError::with_code(
+            ErrorCode::XPTY0004,
+            format!("{} is not a number", value),
+        )),
+        _ => Ok(Some(value.clone())),
+    }
+}
+
+fn division_by_zero() -> ExpressionApplyError {
+    ExpressionApplyError::with_code(ErrorCode::FOAR0001, String::from("division by zero"))
+}
+
+fn overflow() -> ExpressionApplyError {
+    ExpressionApplyError::with_code(ErrorCode::FOAR0002, String::from("numeric overflow"))
+}
+
 fn value_expr(input: &str) -> Res<&str, ValueExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-ValueExpr
 
@@ -296,6 +536,7 @@ fn value_expr(input: &str) -> Res<&str, ValueExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ValueExpr(pub SimpleMapExpr);
 
 impl Display for ValueExpr {
@@ -307,7 +548,7 @@ impl Display for ValueExpr {
 impl ValueExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         self.0.eval(context)
     }
@@ -2,7 +2,9 @@
 
 use std::fmt::Display;
 
-use nom::{branch::alt, bytes::complete::tag, error::context, multi::many0, sequence::tuple};
+use nom::{branch::alt, error::context, multi::many0, sequence::tuple};
+
+use crate::xpath::grammar::recipes::tag;
 
 use crate::xpath::{
     grammar::{
@@ -25,7 +27,7 @@ use super::{
     },
 };
 
-pub fn arrow_expr(input: &str) -> Res<&str, ArrowExpr> {
+pub(crate) fn arrow_expr(input: &str) -> Res<&str, ArrowExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-ArrowExpr
 
     context(
@@ -53,6 +55,7 @@ pub fn arrow_expr(input: &str) -> Res<&str, ArrowExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ArrowExpr {
     pub expr: UnaryExpr,
     pub items: Vec<ArrowExprItem>,
@@ -72,7 +75,7 @@ impl Display for ArrowExpr {
 impl ArrowExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         // Evaluate the first expression.
         let result = self.expr.eval(context)?;
@@ -83,11 +86,12 @@ impl ArrowExpr {
         }
 
         // Otherwise, do the operation.
-        todo!("ArrowExpr::eval operator")
+        Err(ExpressionApplyError::unsupported("arrow expression", self))
     }
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ArrowExprItem {
     pub function_specifier: ArrowFunctionSpecifier,
     pub arguments: ArgumentList,
@@ -122,6 +126,7 @@ fn arrow_function_specifier(input: &str) -> Res<&str, ArrowFunctionSpecifier> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ArrowFunctionSpecifier {
     Name(EQName),
     VarRef(VarRef),
@@ -10,7 +10,7 @@ use crate::xpath::{
 
 use super::ExprSingle;
 
-pub fn argument_list(input: &str) -> Res<&str, ArgumentList> {
+pub(crate) fn argument_list(input: &str) -> Res<&str, ArgumentList> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-ArgumentList
 
     context(
@@ -33,6 +33,7 @@ pub fn argument_list(input: &str) -> Res<&str, ArgumentList> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ArgumentList(pub Vec<Argument>);
 
 impl Display for ArgumentList {
@@ -50,7 +51,7 @@ impl Display for ArgumentList {
     }
 }
 
-pub fn argument(input: &str) -> Res<&str, Argument> {
+pub(crate) fn argument(input: &str) -> Res<&str, Argument> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-Argument
 
     fn argument_placeholder(input: &str) -> Res<&str, Argument> {
@@ -66,6 +67,7 @@ pub fn argument(input: &str) -> Res<&str, Argument> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Argument {
     ExprSingle(ExprSingle),
     ArgumentPlaceHolder,
@@ -83,11 +85,14 @@ impl Display for Argument {
 impl Argument {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         match &self {
             Argument::ExprSingle(expr_single) => expr_single.eval(context),
-            Argument::ArgumentPlaceHolder => todo!("Argument::ArgumentPlaceHolder eval"),
+            Argument::ArgumentPlaceHolder => Err(ExpressionApplyError::unsupported(
+                "partial function application",
+                self,
+            )),
         }
     }
 }
@@ -1,32 +1,35 @@
 //! <https://www.w3.org/TR/2017/REC-xpath-31-20170321/#id-comparisons>
 
-use std::fmt::Display;
+use std::{cmp::Ordering, fmt::Display};
 
 use nom::{
-    branch::alt, bytes::complete::tag, character::complete::multispace0, combinator::opt,
-    error::context, sequence::tuple,
+    branch::alt, character::complete::multispace0, combinator::opt, error::context, sequence::tuple,
 };
 
+use crate::xpath::grammar::recipes::tag;
+
 use crate::{
     xpath::{
+        collation::Collation,
         grammar::{
             data_model::{AnyAtomicType, XpathItem},
             expressions::string_concat_expressions::string_concat_expr,
             recipes::Res,
             terminal_symbols::symbol_separator,
         },
+        limits::EvaluationBudget,
         xpath_item_set::XpathItemSet,
-        ExpressionApplyError, XpathExpressionContext,
+        ErrorCode, ExpressionApplyError, XpathExpressionContext, XpathItemTree,
     },
     xpath_item_set,
 };
 
 use super::{
-    primary_expressions::static_function_calls::func_data,
+    primary_expressions::static_function_calls::{func_data, func_number},
     string_concat_expressions::StringConcatExpr,
 };
 
-pub fn comparison_expr(input: &str) -> Res<&str, ComparisonExpr> {
+pub(crate) fn comparison_expr(input: &str) -> Res<&str, ComparisonExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-ComparisonExpr
 
     fn value_comp_map(input: &str) -> Res<&str, ComparisonType> {
@@ -64,6 +67,7 @@ pub fn comparison_expr(input: &str) -> Res<&str, ComparisonExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ComparisonExpr {
     pub expr: StringConcatExpr,
     pub comparison: Option<ComparisonExprPair>,
@@ -84,7 +88,7 @@ impl Display for ComparisonExpr {
 impl ComparisonExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         // Evaluate the first expression.
         let result = self.expr.eval(context)?;
@@ -100,9 +104,41 @@ impl ComparisonExpr {
         // Get the second expression result.
         let second_result = comparison.1.eval(context)?;
 
+        self.compare(&comparison.0, &result, &second_result, context)
+    }
+
+    /// Compare the results of the two operands of this expression.
+    pub(crate) fn compare<'tree>(
+        &self,
+        comparison_type: &ComparisonType,
+        result: &XpathItemSet<'tree>,
+        second_result: &XpathItemSet<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        // In XPath 1.0 compatibility mode, general comparisons compare every pair of values.
+        let static_context = context.dynamic_context.static_context();
+        if let ComparisonType::GeneralComp(comp) = comparison_type {
+            if static_context.xpath_1_0_compatibility() {
+                let collation = static_context
+                    .resolve_collation(None)
+                    .map_err(|e| e.in_expression(self))?;
+                let bool_value = comp.is_match_1_0(
+                    result,
+                    second_result,
+                    context.item_tree,
+                    context.budget,
+                    collation,
+                )?;
+
+                return Ok(xpath_item_set![XpathItem::AnyAtomicType(
+                    AnyAtomicType::Boolean(bool_value),
+                )]);
+            }
+        }
+
         // Atomize both results.
-        let atomized1 = func_data(&result, &context.item_tree);
-        let atomized2 = func_data(&second_result, &context.item_tree);
+        let atomized1 = func_data(result, context.item_tree, context.budget)?;
+        let atomized2 = func_data(second_result, context.item_tree, context.budget)?;
 
         // Do some type checking first.
 
@@ -115,15 +151,29 @@ impl ComparisonExpr {
         // If the either atomized set is a sequence of length greater than one,
         // a type error is raised.
         if atomized1.len() > 1 || atomized2.len() > 1 {
-            return Err(ExpressionApplyError {
-                msg: String::from("err:XPTY0004 The first operand of a value comparison is a sequence of length greater than one")
-            });
+            return Err(ExpressionApplyError::with_code(
+                ErrorCode::XPTY0004,
+                String::from(
+                    "An operand of a value comparison is a sequence of length greater than one",
+                ),
+            )
+            .in_expression(self));
         }
 
-        let bool_value = match comparison.0 {
-            ComparisonType::ValueComp(_) => todo!("ComparisonType::ValueComp"),
-            ComparisonType::GeneralComp(comp) => comp.is_match(&atomized1[0], &atomized2[0]),
-            ComparisonType::NodeComp(_) => todo!("ComparisonType::NodeComp"),
+        let bool_value = match comparison_type {
+            ComparisonType::ValueComp(_) => {
+                return Err(ExpressionApplyError::unsupported("value comparison", self))
+            }
+            ComparisonType::GeneralComp(comp) => {
+                let collation = static_context
+                    .resolve_collation(None)
+                    .map_err(|e| e.in_expression(self))?;
+
+                comp.is_match(&atomized1[0], &atomized2[0], collation)
+            }
+            ComparisonType::NodeComp(_) => {
+                return Err(ExpressionApplyError::unsupported("node comparison", self))
+            }
         };
 
         Ok(xpath_item_set![XpathItem::AnyAtomicType(
@@ -133,6 +183,7 @@ impl ComparisonExpr {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ComparisonExprPair(pub ComparisonType, pub StringConcatExpr);
 
 impl Display for ComparisonExprPair {
@@ -142,6 +193,7 @@ impl Display for ComparisonExprPair {
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ComparisonType {
     ValueComp(ValueComp),
     GeneralComp(GeneralComp),
@@ -204,6 +256,7 @@ fn value_comp(input: &str) -> Res<&str, ValueComp> {
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ValueComp {
     Equal,
     NotEqual,
@@ -264,15 +317,17 @@ fn general_comp(input: &str) -> Res<&str, GeneralComp> {
         alt((
             equal,
             not_equal,
-            less_than,
+            // The two character operators must be tried before their prefixes.
             less_than_equal_to,
-            greater_than,
+            less_than,
             greater_than_equal_to,
+            greater_than,
         )),
     )(input)
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GeneralComp {
     Equal,
     NotEqual,
@@ -296,14 +351,115 @@ impl Display for GeneralComp {
 }
 
 impl GeneralComp {
-    pub(crate) fn is_match<'tree>(&self, first: &AnyAtomicType, second: &AnyAtomicType) -> bool {
+    /// Compare two atomic values.
+    ///
+    /// Strings are compared with the given collation.
+    pub(crate) fn is_match(
+        &self,
+        first: &AnyAtomicType,
+        second: &AnyAtomicType,
+        collation: &dyn Collation,
+    ) -> bool {
+        let ordering = match (first, second) {
+            (AnyAtomicType::String(first), AnyAtomicType::String(second)) => {
+                collation.compare(first, second)
+            }
+            _ => first.cmp(second),
+        };
+
+        self.holds(ordering)
+    }
+
+    /// Compare two sequences with the rules of XPath 1.0.
+    ///
+    /// <https://www.w3.org/TR/2017/REC-xpath-31-20170321/#id-general-comparisons>
+    ///
+    /// If either side is a single boolean, the other side is converted to its effective
+    /// boolean value. Otherwise the comparison is true if any pair of atomized values
+    /// satisfies it, so comparisons with an empty sequence are false.
+    /// `<`, `<=`, `>` and `>=` always compare numbers.
+    pub(crate) fn is_match_1_0<'tree>(
+        &self,
+        first: &XpathItemSet<'tree>,
+        second: &XpathItemSet<'tree>,
+        item_tree: &'tree XpathItemTree,
+        budget: &EvaluationBudget,
+        collation: &dyn Collation,
+    ) -> Result<bool, ExpressionApplyError> {
+        fn single_boolean(set: &XpathItemSet) -> bool {
+            set.len() == 1 && matches!(set[0], XpathItem::AnyAtomicType(AnyAtomicType::Boolean(_)))
+        }
+
+        if single_boolean(first) || single_boolean(second) {
+            return Ok(self.holds(first.boolean().cmp(&second.boolean())));
+        }
+
+        let first = func_data(first, item_tree, budget)?;
+        let second = func_data(second, item_tree, budget)?;
+
+        Ok(first.iter().any(|first| {
+            second
+                .iter()
+                .any(|second| self.is_match_1_0_values(first, second, collation))
+        }))
+    }
+
+    fn is_match_1_0_values(
+        &self,
+        first: &AnyAtomicType,
+        second: &AnyAtomicType,
+        collation: &dyn Collation,
+    ) -> bool {
+        let is_numeric = |value: &AnyAtomicType| {
+            matches!(
+                value,
+                AnyAtomicType::Integer(_) | AnyAtomicType::Float(_) | AnyAtomicType::Double(_)
+            )
+        };
+        let is_boolean = |value: &AnyAtomicType| matches!(value, AnyAtomicType::Boolean(_));
+
+        let is_relational = !matches!(self, GeneralComp::Equal | GeneralComp::NotEqual);
+        if is_relational || is_numeric(first) || is_numeric(second) {
+            let first = func_number(first);
+            let second = func_number(second);
+
+            // NaN is not equal to, less than or greater than anything.
+            return match first.partial_cmp(&second) {
+                Some(ordering) => self.holds(ordering),
+                None => *self == GeneralComp::NotEqual,
+            };
+        }
+
+        if is_boolean(first) || is_boolean(second) {
+            // Numbers were compared above, so only booleans and strings are left.
+            let ebv = |value: &AnyAtomicType| match value {
+                AnyAtomicType::Boolean(b) => *b,
+                AnyAtomicType::String(s) => !s.is_empty(),
+                _ => true,
+            };
+
+            return self.holds(ebv(first).cmp(&ebv(second)));
+        }
+
+        let ordering = match (first, second) {
+            (AnyAtomicType::String(first), AnyAtomicType::String(second)) => {
+                collation.compare(first, second)
+            }
+            _ => first.cmp(second),
+        };
+
+        self.holds(ordering)
+    }
+
+    /// Whether the ordering of two values satisfies the comparison.
+    fn holds(&self, ordering: Ordering) -> bool {
         match self {
-            GeneralComp::Equal => first == second,
-            GeneralComp::NotEqual => first != second,
-            GeneralComp::LessThan => first < second,
-            GeneralComp::LessThanEqualTo => first <= second,
-            GeneralComp::GreaterThan => first > second,
-            GeneralComp::GreaterThanEqualTo => first >= second,
+            GeneralComp::Equal => ordering == Ordering::Equal,
+            GeneralComp::NotEqual => ordering != Ordering::Equal,
+            GeneralComp::LessThan => ordering == Ordering::Less,
+            GeneralComp::LessThanEqualTo => ordering != Ordering::Greater,
+            GeneralComp::GreaterThan => ordering == Ordering::Greater,
+            GeneralComp::GreaterThanEqualTo => ordering != Ordering::Less,
         }
     }
 }
@@ -330,6 +486,7 @@ fn node_comp(input: &str) -> Res<&str, NodeComp> {
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NodeComp {
     Is,
     Precedes,
@@ -363,6 +520,19 @@ mod test {
         assert_eq!(res.to_string(), r#"$book1/author eq "Kennedy""#);
     }
 
+    #[test]
+    fn comparison_expr_should_parse_general_comp_less_than_equal_to() {
+        // arrange
+        let input = "count(preceding-sibling::*) <= 2";
+
+        // act
+        let (next_input, res) = comparison_expr(input).unwrap();
+
+        // assert
+        assert_eq!(next_input, "");
+        assert_eq!(res.to_string(), "count(preceding-sibling::*)<=2");
+    }
+
     #[test]
     fn comparison_expr_should_parse_node_comp_precedes() {
         // arrange
@@ -3,12 +3,13 @@
 use std::fmt::Display;
 
 use nom::{
-    bytes::complete::tag,
     character::complete::{char, multispace0},
     error::context,
     sequence::tuple,
 };
 
+use crate::xpath::grammar::recipes::tag;
+
 use crate::xpath::grammar::{
     recipes::Res,
     whitespace_recipes::{sep, ws},
@@ -16,7 +17,7 @@ use crate::xpath::grammar::{
 
 use super::{expr, expr_single, Expr, ExprSingle};
 
-pub fn if_expr(input: &str) -> Res<&str, IfExpr> {
+pub(crate) fn if_expr(input: &str) -> Res<&str, IfExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-IfExpr
 
     context(
@@ -40,6 +41,7 @@ pub fn if_expr(input: &str) -> Res<&str, IfExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IfExpr {
     pub condition: Expr,
     pub then: ExprSingle,
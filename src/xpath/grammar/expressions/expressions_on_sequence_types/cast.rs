@@ -2,10 +2,9 @@
 
 use std::fmt::Display;
 
-use nom::{
-    bytes::complete::tag, character::complete::char, combinator::opt, error::context,
-    sequence::tuple,
-};
+use nom::{character::complete::char, combinator::opt, error::context, sequence::tuple};
+
+use crate::xpath::grammar::recipes::tag;
 
 use crate::xpath::{
     grammar::{
@@ -18,7 +17,7 @@ use crate::xpath::{
     ExpressionApplyError, XpathExpressionContext,
 };
 
-pub fn cast_expr(input: &str) -> Res<&str, CastExpr> {
+pub(crate) fn cast_expr(input: &str) -> Res<&str, CastExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-CastExpr
 
     context(
@@ -32,6 +31,7 @@ pub fn cast_expr(input: &str) -> Res<&str, CastExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CastExpr {
     pub expr: ArrowExpr,
     pub cast: Option<SingleType>,
@@ -51,7 +51,7 @@ impl Display for CastExpr {
 impl CastExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         // Evaluate the first expression.
         let result = self.expr.eval(context)?;
@@ -62,11 +62,11 @@ impl CastExpr {
         }
 
         // Otherwise, do the operation.
-        todo!("CastExpr::eval operator")
+        Err(ExpressionApplyError::unsupported("cast expression", self))
     }
 }
 
-pub fn single_type(input: &str) -> Res<&str, SingleType> {
+pub(crate) fn single_type(input: &str) -> Res<&str, SingleType> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-SingleType
     context("single_type", tuple((simple_type_name, opt(char('?')))))(input).map(
         |(next_input, res)| {
@@ -82,6 +82,7 @@ pub fn single_type(input: &str) -> Res<&str, SingleType> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SingleType {
     pub type_name: SimpleTypeName,
     pub has_question_mark: bool,
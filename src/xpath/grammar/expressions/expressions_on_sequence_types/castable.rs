@@ -2,7 +2,9 @@
 
 use std::fmt::Display;
 
-use nom::{bytes::complete::tag, combinator::opt, error::context};
+use nom::{combinator::opt, error::context};
+
+use crate::xpath::grammar::recipes::tag;
 
 use crate::xpath::{
     grammar::{recipes::Res, whitespace_recipes::sep},
@@ -12,7 +14,7 @@ use crate::xpath::{
 
 use super::cast::{cast_expr, single_type, CastExpr, SingleType};
 
-pub fn castable_expr(input: &str) -> Res<&str, CastableExpr> {
+pub(crate) fn castable_expr(input: &str) -> Res<&str, CastableExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-CastableExpr
 
     context(
@@ -35,6 +37,7 @@ pub fn castable_expr(input: &str) -> Res<&str, CastableExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CastableExpr {
     pub expr: CastExpr,
     pub cast_type: Option<SingleType>,
@@ -54,7 +57,7 @@ impl Display for CastableExpr {
 impl CastableExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         // Evaluate the first expression.
         let result = self.expr.eval(context)?;
@@ -65,7 +68,10 @@ impl CastableExpr {
         }
 
         // Otherwise, do the operation.
-        todo!("CastableExpr::eval treat operator")
+        Err(ExpressionApplyError::unsupported(
+            "castable expression",
+            self,
+        ))
     }
 }
 
@@ -2,7 +2,9 @@
 
 use std::fmt::Display;
 
-use nom::{bytes::complete::tag, combinator::opt, error::context};
+use nom::{combinator::opt, error::context};
+
+use crate::xpath::grammar::recipes::tag;
 
 use crate::xpath::{
     grammar::{
@@ -16,7 +18,7 @@ use crate::xpath::{
 
 use super::treat::{treat_expr, TreatExpr};
 
-pub fn instanceof_expr(input: &str) -> Res<&str, InstanceofExpr> {
+pub(crate) fn instanceof_expr(input: &str) -> Res<&str, InstanceofExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-InstanceofExpr
 
     context(
@@ -39,6 +41,7 @@ pub fn instanceof_expr(input: &str) -> Res<&str, InstanceofExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InstanceofExpr {
     pub expr: TreatExpr,
     pub instanceof_type: Option<SequenceType>,
@@ -58,7 +61,7 @@ impl Display for InstanceofExpr {
 impl InstanceofExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         // Evaluate the first expression.
         let result = self.expr.eval(context)?;
@@ -69,7 +72,10 @@ impl InstanceofExpr {
         }
 
         // Otherwise, do the operation.
-        todo!("InstanceofExpr::eval instanceof operator")
+        Err(ExpressionApplyError::unsupported(
+            "instance of expression",
+            self,
+        ))
     }
 }
 
@@ -2,7 +2,9 @@
 
 use std::fmt::Display;
 
-use nom::{bytes::complete::tag, combinator::opt, error::context};
+use nom::{combinator::opt, error::context};
+
+use crate::xpath::grammar::recipes::tag;
 
 use crate::xpath::{
     grammar::{
@@ -11,12 +13,12 @@ use crate::xpath::{
         whitespace_recipes::sep,
     },
     xpath_item_set::XpathItemSet,
-    ExpressionApplyError, XpathExpressionContext,
+    ErrorCode, ExpressionApplyError, XpathExpressionContext,
 };
 
 use super::castable::{castable_expr, CastableExpr};
 
-pub fn treat_expr(input: &str) -> Res<&str, TreatExpr> {
+pub(crate) fn treat_expr(input: &str) -> Res<&str, TreatExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-TreatExpr
 
     context(
@@ -39,6 +41,7 @@ pub fn treat_expr(input: &str) -> Res<&str, TreatExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TreatExpr {
     pub expr: CastableExpr,
     pub treat_type: Option<SequenceType>,
@@ -58,7 +61,7 @@ impl Display for TreatExpr {
 impl TreatExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         // Evaluate the first expression.
         let result = self.expr.eval(context)?;
@@ -71,9 +74,11 @@ impl TreatExpr {
         };
 
         if !treat_type.is_match(&result)? {
-            return Err(ExpressionApplyError {
-                msg: format!("err:XPDY0050 Cannot treat as {}", treat_type.to_string()),
-            });
+            return Err(ExpressionApplyError::with_code(
+                ErrorCode::XPDY0050,
+                format!("Cannot treat as {}", treat_type),
+            )
+            .in_expression(self));
         }
 
         Ok(result)
@@ -3,13 +3,14 @@
 use std::fmt::Display;
 
 use nom::{
-    bytes::complete::tag,
     character::complete::{char, multispace0},
     error::context,
     multi::many0,
     sequence::tuple,
 };
 
+use crate::xpath::grammar::recipes::tag;
+
 use crate::xpath::grammar::{
     recipes::Res, terminal_symbols::symbol_separator, whitespace_recipes::sep,
 };
@@ -20,7 +21,7 @@ use super::{
     ExprSingle,
 };
 
-pub fn for_expr(input: &str) -> Res<&str, ForExpr> {
+pub(crate) fn for_expr(input: &str) -> Res<&str, ForExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-ForExpr
 
     context(
@@ -39,6 +40,7 @@ pub fn for_expr(input: &str) -> Res<&str, ForExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ForExpr {
     pub clause: SimpleForClause,
     pub expr: ExprSingle,
@@ -75,6 +77,7 @@ fn simple_for_clause(input: &str) -> Res<&str, SimpleForClause> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SimpleForClause {
     pub binding: SimpleForBinding,
     pub extras: Vec<SimpleForBinding>,
@@ -115,6 +118,7 @@ fn simple_for_binding(input: &str) -> Res<&str, SimpleForBinding> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SimpleForBinding {
     pub var: VarName,
     pub expr: ExprSingle,
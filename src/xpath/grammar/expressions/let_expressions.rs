@@ -3,13 +3,14 @@
 use std::fmt::Display;
 
 use nom::{
-    bytes::complete::tag,
     character::complete::{char, multispace0},
     error::context,
     multi::many0,
     sequence::tuple,
 };
 
+use crate::xpath::grammar::recipes::tag;
+
 use crate::xpath::grammar::{
     recipes::Res,
     terminal_symbols::symbol_separator,
@@ -22,7 +23,7 @@ use super::{
     ExprSingle,
 };
 
-pub fn let_expr(input: &str) -> Res<&str, LetExpr> {
+pub(crate) fn let_expr(input: &str) -> Res<&str, LetExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-LetExpr
 
     context(
@@ -41,6 +42,7 @@ pub fn let_expr(input: &str) -> Res<&str, LetExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LetExpr {
     pub clause: SimpleLetClause,
     pub expr: ExprSingle,
@@ -77,6 +79,7 @@ fn simple_let_clause(input: &str) -> Res<&str, SimpleLetClause> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SimpleLetClause {
     pub binding: SimpleLetBinding,
     pub extras: Vec<SimpleLetBinding>,
@@ -111,6 +114,7 @@ fn simple_let_binding(input: &str) -> Res<&str, SimpleLetBinding> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SimpleLetBinding {
     pub var: VarName,
     pub expr: ExprSingle,
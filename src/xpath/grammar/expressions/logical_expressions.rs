@@ -2,20 +2,26 @@
 
 use std::fmt::Display;
 
-use nom::{bytes::complete::tag, error::context};
+use nom::error::context;
 
-use crate::xpath::{
-    grammar::{
-        recipes::Res,
-        whitespace_recipes::{sep, sep_many0},
+use crate::xpath::grammar::recipes::tag;
+
+use crate::{
+    xpath::{
+        grammar::{
+            data_model::{AnyAtomicType, XpathItem},
+            recipes::Res,
+            whitespace_recipes::{sep, sep_many0},
+        },
+        xpath_item_set::XpathItemSet,
+        ExpressionApplyError, XpathExpressionContext,
     },
-    xpath_item_set::XpathItemSet,
-    ExpressionApplyError, XpathExpressionContext,
+    xpath_item_set,
 };
 
 use super::comparison_expressions::{comparison_expr, ComparisonExpr};
 
-pub fn or_expr(input: &str) -> Res<&str, OrExpr> {
+pub(crate) fn or_expr(input: &str) -> Res<&str, OrExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-OrExpr
 
     context("or_expr", sep_many0(and_expr, sep((tag("or"), and_expr))))(input).map(
@@ -27,6 +33,7 @@ pub fn or_expr(input: &str) -> Res<&str, OrExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OrExpr {
     pub expr: AndExpr,
     pub items: Vec<AndExpr>,
@@ -46,7 +53,7 @@ impl Display for OrExpr {
 impl OrExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         // Evaluate the first expression.
         let result = self.expr.eval(context)?;
@@ -56,8 +63,18 @@ impl OrExpr {
             return Ok(result);
         }
 
-        // Otherwise, do the boolean op.
-        todo!("OrExpr::eval or operator")
+        // Otherwise, the result is true if any operand's effective boolean value is true.
+        // Operands after the first true one are not evaluated.
+        let mut value = result.boolean();
+        for item in self.items.iter() {
+            if value {
+                break;
+            }
+
+            value = item.eval(context)?.boolean();
+        }
+
+        Ok(boolean_item_set(value))
     }
 }
 
@@ -75,6 +92,7 @@ fn and_expr(input: &str) -> Res<&str, AndExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AndExpr {
     pub expr: ComparisonExpr,
     pub items: Vec<ComparisonExpr>,
@@ -94,7 +112,7 @@ impl Display for AndExpr {
 impl AndExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         // Evaluate the first expression.
         let result = self.expr.eval(context)?;
@@ -104,13 +122,32 @@ impl AndExpr {
             return Ok(result);
         }
 
-        // Otherwise, do the boolean op.
-        todo!("AndExpr::eval and operator")
+        // Otherwise, the result is true if every operand's effective boolean value is true.
+        // Operands after the first false one are not evaluated.
+        let mut value = result.boolean();
+        for item in self.items.iter() {
+            if !value {
+                break;
+            }
+
+            value = item.eval(context)?.boolean();
+        }
+
+        Ok(boolean_item_set(value))
     }
 }
 
+fn boolean_item_set<'tree>(value: bool) -> XpathItemSet<'tree> {
+    xpath_item_set![XpathItem::AnyAtomicType(AnyAtomicType::Boolean(value))]
+}
+
 #[cfg(test)]
 mod test {
+    use crate::{
+        html,
+        xpath::{self, XpathItemTree},
+    };
+
     use super::*;
 
     #[test]
@@ -164,4 +201,59 @@ mod test {
         assert_eq!(next_input, " c");
         assert_eq!(res.to_string(), "a and band");
     }
+
+    #[test]
+    fn or_expr_should_be_true_if_any_operand_is_true() {
+        // arrange
+        let tree = tree("<html><p>1</p></html>");
+
+        // act
+        let results: Vec<bool> = ["//p or //div", "//div or //span", "0 or '' or 1"]
+            .iter()
+            .map(|text| apply_boolean(text, &tree))
+            .collect();
+
+        // assert
+        assert_eq!(results, vec![true, false, true]);
+    }
+
+    #[test]
+    fn and_expr_should_be_true_if_every_operand_is_true() {
+        // arrange
+        let tree = tree("<html><p>1</p></html>");
+
+        // act
+        let results: Vec<bool> = ["//p and //html", "//p and //div", "1 and 'a' and //p"]
+            .iter()
+            .map(|text| apply_boolean(text, &tree))
+            .collect();
+
+        // assert
+        assert_eq!(results, vec![true, false, true]);
+    }
+
+    #[test]
+    fn and_expr_should_not_evaluate_operands_after_false() {
+        // arrange
+        let tree = tree("<html><p>1</p></html>");
+        let xpath = xpath::parse("//p[@id and fn:error()]").unwrap();
+
+        // act
+        let items = xpath.apply(&tree).unwrap();
+
+        // assert
+        assert!(items.is_empty());
+    }
+
+    fn tree(text: &str) -> XpathItemTree {
+        XpathItemTree::from(&html::parse(text).unwrap())
+    }
+
+    fn apply_boolean(text: &str, tree: &XpathItemTree) -> bool {
+        let items = xpath::parse(text).unwrap().apply(tree).unwrap();
+        match &items[0] {
+            XpathItem::AnyAtomicType(AnyAtomicType::Boolean(value)) => *value,
+            item => panic!("expected a boolean, got {:?}", item),
+        }
+    }
 }
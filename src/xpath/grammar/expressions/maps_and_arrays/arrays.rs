@@ -2,22 +2,28 @@
 
 use std::fmt::Display;
 
-use nom::{
-    branch::alt, bytes::complete::tag, character::complete::char, combinator::opt, error::context,
-    multi::many0,
-};
-
-use crate::xpath::grammar::{
-    expressions::{
-        expr_single,
-        primary_expressions::enclosed_expressions::{enclosed_expr, EnclosedExpr},
-        ExprSingle,
+use nom::{branch::alt, character::complete::char, combinator::opt, error::context, multi::many0};
+
+use crate::xpath::grammar::recipes::tag;
+
+use crate::{
+    xpath::{
+        grammar::{
+            data_model::{Function, XpathArray, XpathItem},
+            expressions::{
+                expr_single,
+                primary_expressions::enclosed_expressions::{enclosed_expr, EnclosedExpr},
+                ExprSingle,
+            },
+            recipes::Res,
+            whitespace_recipes::ws,
+        },
+        ExpressionApplyError, XpathExpressionContext, XpathItemSet,
     },
-    recipes::Res,
-    whitespace_recipes::ws,
+    xpath_item_set,
 };
 
-pub fn array_constructor(input: &str) -> Res<&str, ArrayConstructor> {
+pub(crate) fn array_constructor(input: &str) -> Res<&str, ArrayConstructor> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-ArrayConstructor
 
     fn square_array_constructor_map(input: &str) -> Res<&str, ArrayConstructor> {
@@ -37,14 +43,44 @@ pub fn array_constructor(input: &str) -> Res<&str, ArrayConstructor> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ArrayConstructor {
     SquareArrayConstructor(SquareArrayConstructor),
     CurlyArrayConstructor(CurlyArrayConstructor),
 }
 
 impl Display for ArrayConstructor {
-    fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!("fmt ArrayConstructor")
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArrayConstructor::SquareArrayConstructor(x) => write!(f, "{}", x),
+            ArrayConstructor::CurlyArrayConstructor(x) => write!(f, "{}", x),
+        }
+    }
+}
+
+impl ArrayConstructor {
+    pub(crate) fn eval<'tree>(
+        &self,
+        context: &XpathExpressionContext<'tree, '_>,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        let array: XpathArray = match self {
+            // Each expression is a member.
+            ArrayConstructor::SquareArrayConstructor(x) => x
+                .entries
+                .iter()
+                .map(|entry| entry.eval(context))
+                .collect::<Result<_, _>>()?,
+
+            // Each item of the expression is a member.
+            ArrayConstructor::CurlyArrayConstructor(x) => {
+                x.0.eval(context)?
+                    .into_iter()
+                    .map(|item| xpath_item_set![item])
+                    .collect()
+            }
+        };
+
+        Ok(xpath_item_set![XpathItem::Function(Function::Array(array))])
     }
 }
 
@@ -70,6 +106,7 @@ fn square_array_constructor(input: &str) -> Res<&str, SquareArrayConstructor> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SquareArrayConstructor {
     pub entries: Vec<ExprSingle>,
 }
@@ -94,7 +131,8 @@ fn curly_array_constructor(input: &str) -> Res<&str, CurlyArrayConstructor> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
-pub struct CurlyArrayConstructor(EnclosedExpr);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CurlyArrayConstructor(pub EnclosedExpr);
 
 impl Display for CurlyArrayConstructor {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -8,13 +8,14 @@ use crate::xpath::grammar::recipes::Res;
 
 use super::unary_lookup::{key_specifier, KeySpecifier};
 
-pub fn lookup(input: &str) -> Res<&str, Lookup> {
+pub(crate) fn lookup(input: &str) -> Res<&str, Lookup> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-Lookup
     context("lookup", tuple((char('?'), key_specifier)))(input)
         .map(|(next_input, res)| (next_input, Lookup(res.1)))
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Lookup(pub KeySpecifier);
 
 impl Display for Lookup {
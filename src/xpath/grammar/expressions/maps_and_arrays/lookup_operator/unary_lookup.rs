@@ -4,22 +4,31 @@ use std::fmt::Display;
 
 use nom::{branch::alt, character::complete::char, error::context, sequence::tuple};
 
-use crate::xpath::grammar::{
-    expressions::primary_expressions::parenthesized_expressions::{
-        parenthesized_expr, ParenthesizedExpr,
+use crate::{
+    xpath::{
+        grammar::{
+            data_model::{AnyAtomicType, Function, XpathItem},
+            expressions::primary_expressions::{
+                parenthesized_expressions::{parenthesized_expr, ParenthesizedExpr},
+                static_function_calls::func_data,
+            },
+            recipes::Res,
+            terminal_symbols::integer_literal,
+            xml_names::nc_name,
+        },
+        ErrorCode, ExpressionApplyError, XpathExpressionContext, XpathItemSet,
     },
-    recipes::Res,
-    terminal_symbols::integer_literal,
-    xml_names::nc_name,
+    xpath_item_set,
 };
 
-pub fn unary_lookup(input: &str) -> Res<&str, UnaryLookup> {
+pub(crate) fn unary_lookup(input: &str) -> Res<&str, UnaryLookup> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-UnaryLookup
     context("unary_lookup", tuple((char('?'), key_specifier)))(input)
         .map(|(next_input, res)| (next_input, UnaryLookup(res.1)))
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UnaryLookup(pub KeySpecifier);
 
 impl Display for UnaryLookup {
@@ -28,7 +37,19 @@ impl Display for UnaryLookup {
     }
 }
 
-pub fn key_specifier(input: &str) -> Res<&str, KeySpecifier> {
+impl UnaryLookup {
+    pub(crate) fn eval<'tree>(
+        &self,
+        context: &XpathExpressionContext<'tree, '_>,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        // A unary lookup looks up the key in the context item.
+        self.0
+            .lookup(&xpath_item_set![context.item.clone()], context)
+            .map_err(|e| e.in_expression(self))
+    }
+}
+
+pub(crate) fn key_specifier(input: &str) -> Res<&str, KeySpecifier> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-KeySpecifier
 
     fn nc_name_map(input: &str) -> Res<&str, KeySpecifier> {
@@ -60,6 +81,7 @@ pub fn key_specifier(input: &str) -> Res<&str, KeySpecifier> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum KeySpecifier {
     Name(String),
     Integer(u32),
@@ -78,6 +100,98 @@ impl Display for KeySpecifier {
     }
 }
 
+impl KeySpecifier {
+    /// Look up the key in each of the maps and arrays.
+    ///
+    /// <https://www.w3.org/TR/2017/REC-xpath-31-20170321/#id-postfix-lookup>
+    ///
+    /// # Arguments
+    ///
+    /// * `items` - The maps and arrays to look up the key in.
+    /// * `context` - The context to evaluate a parenthesized key in.
+    pub(crate) fn lookup<'tree>(
+        &self,
+        items: &XpathItemSet<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        let keys = match self {
+            KeySpecifier::Name(name) => Some(vec![AnyAtomicType::String(name.clone())]),
+            KeySpecifier::Integer(n) => Some(vec![AnyAtomicType::Integer(*n as i64)]),
+            KeySpecifier::ParenthesizedExpr(expr) => Some(func_data(
+                &expr.eval(context)?,
+                context.item_tree,
+                context.budget,
+            )?),
+            KeySpecifier::Wildcard => None,
+        };
+
+        let mut result = XpathItemSet::new();
+        for item in items.iter() {
+            match item {
+                XpathItem::Function(Function::Map(map)) => match &keys {
+                    Some(keys) => {
+                        for key in keys {
+                            if let Some(value) = map.get(key) {
+                                result.extend(value.clone());
+                            }
+                        }
+                    }
+                    None => {
+                        for (_, value) in map.iter() {
+                            result.extend(value.clone());
+                        }
+                    }
+                },
+                XpathItem::Function(Function::Array(array)) => match &keys {
+                    Some(keys) => {
+                        for key in keys {
+                            let position = match key {
+                                AnyAtomicType::Integer(n) => *n,
+                                _ => {
+                                    return Err(ExpressionApplyError::with_code(
+                                        ErrorCode::XPTY0004,
+                                        format!("array lookup expects an integer, found {}", key),
+                                    ))
+                                }
+                            };
+
+                            let member = usize::try_from(position)
+                                .ok()
+                                .and_then(|position| array.get(position))
+                                .ok_or_else(|| {
+                                    ExpressionApplyError::with_code(
+                                        ErrorCode::FOAY0001,
+                                        format!(
+                                            "array index {} is out of bounds for an array of size {}",
+                                            position,
+                                            array.len()
+                                        ),
+                                    )
+                                })?;
+                            result.extend(member.clone());
+                        }
+                    }
+                    None => {
+                        for member in array.iter() {
+                            result.extend(member.clone());
+                        }
+                    }
+                },
+                _ => {
+                    return Err(ExpressionApplyError::with_code(
+                        ErrorCode::XPTY0004,
+                        String::from("lookup expects a map or an array"),
+                    ))
+                }
+            }
+
+            context.budget.check_result_size(result.len())?;
+        }
+
+        Ok(result)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
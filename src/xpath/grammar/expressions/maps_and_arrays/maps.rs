@@ -2,17 +2,26 @@
 
 use std::fmt::Display;
 
-use nom::{
-    bytes::complete::tag, character::complete::char, combinator::opt, error::context, multi::many0,
-};
+use nom::{character::complete::char, combinator::opt, error::context, multi::many0};
+
+use crate::xpath::grammar::recipes::tag;
 
-use crate::xpath::grammar::{
-    expressions::{expr_single, ExprSingle},
-    recipes::Res,
-    whitespace_recipes::ws,
+use crate::{
+    xpath::{
+        grammar::{
+            data_model::{Function, XpathItem, XpathMap},
+            expressions::{
+                expr_single, primary_expressions::static_function_calls::func_data, ExprSingle,
+            },
+            recipes::Res,
+            whitespace_recipes::ws,
+        },
+        ErrorCode, ExpressionApplyError, XpathExpressionContext, XpathItemSet,
+    },
+    xpath_item_set,
 };
 
-pub fn map_constructor(input: &str) -> Res<&str, MapConstructor> {
+pub(crate) fn map_constructor(input: &str) -> Res<&str, MapConstructor> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-MapConstructor
 
     context(
@@ -39,6 +48,7 @@ pub fn map_constructor(input: &str) -> Res<&str, MapConstructor> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MapConstructor {
     pub entries: Vec<MapConstructorEntry>,
 }
@@ -46,13 +56,52 @@ pub struct MapConstructor {
 impl Display for MapConstructor {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "map {{")?;
-        for entry in &self.entries {
+        for (i, entry) in self.entries.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
             write!(f, " {}", entry)?;
         }
         write!(f, " }}")
     }
 }
 
+impl MapConstructor {
+    pub(crate) fn eval<'tree>(
+        &self,
+        context: &XpathExpressionContext<'tree, '_>,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        let mut map = XpathMap::new();
+        for entry in self.entries.iter() {
+            let mut key = func_data(&entry.key.eval(context)?, context.item_tree, context.budget)?;
+            if key.len() != 1 {
+                return Err(ExpressionApplyError::with_code(
+                    ErrorCode::XPTY0004,
+                    format!(
+                        "map key must be a single atomic value, found {} values",
+                        key.len()
+                    ),
+                )
+                .in_expression(&entry.key));
+            }
+            let key = key.remove(0);
+
+            if map.contains_key(&key) {
+                return Err(ExpressionApplyError::with_code(
+                    ErrorCode::XQDY0137,
+                    format!("map has more than one entry with the key {}", key),
+                )
+                .in_expression(self));
+            }
+
+            let value = entry.value.eval(context)?;
+            map.insert(key, value);
+        }
+
+        Ok(xpath_item_set![XpathItem::Function(Function::Map(map))])
+    }
+}
+
 fn map_constructor_entry(input: &str) -> Res<&str, MapConstructorEntry> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-MapConstructorEntry
     context(
@@ -71,6 +120,7 @@ fn map_constructor_entry(input: &str) -> Res<&str, MapConstructorEntry> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MapConstructorEntry {
     pub key: ExprSingle,
     pub value: ExprSingle,
@@ -109,6 +159,19 @@ mod tests {
         assert_eq!(res.to_string(), input);
     }
 
+    #[test]
+    fn map_constructor_should_parse_multiple_entries() {
+        // arrange
+        let input = "map { a: b, c: d }";
+
+        // act
+        let (next_input, res) = map_constructor(input).unwrap();
+
+        // assert
+        assert_eq!(next_input, "");
+        assert_eq!(res.to_string(), input);
+    }
+
     #[test]
     fn map_constructor_should_parse_no_whitespace() {
         // arrange
@@ -5,6 +5,7 @@ use std::fmt::Display;
 use nom::{character::complete::char, error::context, multi::many0, sequence::tuple};
 
 use crate::xpath::{
+    context::DEFAULT_DYNAMIC_CONTEXT,
     grammar::{
         expressions::{
             conditional_expressions::if_expr, for_expressions::for_expr, let_expressions::let_expr,
@@ -12,9 +13,12 @@ use crate::xpath::{
         },
         recipes::max,
     },
-    ExpressionApplyError, XpathExpressionContext, XpathItemSet, XpathItemTree,
+    DynamicContext, ExpressionApplyError, XpathExpressionContext, XpathItemSet, XpathItemTree,
 };
 
+#[cfg(any(test, feature = "bench"))]
+use crate::xpath::limits::EvaluationBudget;
+
 use self::{
     conditional_expressions::IfExpr, for_expressions::ForExpr, let_expressions::LetExpr,
     logical_expressions::OrExpr, quantified_expressions::QuantifiedExpr,
@@ -46,12 +50,36 @@ pub mod string_concat_expressions;
 pub(crate) fn xpath(input: &str) -> Res<&str, Xpath> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-XPath
 
-    context("xpath", expr)(input).map(|(next_input, res)| (next_input, Xpath(res)))
+    context("xpath", expr)(input).map(|(next_input, res)| (next_input, Xpath(res, None)))
 }
 
 /// An XPath expression.
-#[derive(PartialEq, Debug)]
-pub struct Xpath(pub Expr);
+///
+/// With the `serde` feature, expressions can be serialized and deserialized,
+/// so parsed expressions can be cached or sent to other processes.
+/// A deserialized expression is not checked against a [StaticContext](crate::xpath::StaticContext),
+/// so it should come from a trusted source that parsed it with the same context.
+///
+/// An expression parsed from text keeps the text, so errors can point into it.
+/// Expressions are equal if their syntax trees are, whatever text they were parsed from.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct Xpath(
+    pub Expr,
+    /// The text the expression was parsed from.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) Option<String>,
+);
+
+impl PartialEq for Xpath {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
 
 impl Display for Xpath {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -60,11 +88,18 @@ impl Display for Xpath {
 }
 
 impl Xpath {
-    pub(crate) fn eval<'tree>(
+    #[cfg(any(test, feature = "bench"))]
+    fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
-        self.0.eval(context)
+        self.0
+            .eval(context)
+            .and_then(|items| {
+                context.budget.check_result_size(items.len())?;
+                Ok(items)
+            })
+            .map_err(|e| e.locate_in(self))
     }
 
     /// Apply the XPath expression to the given item tree.
@@ -118,8 +153,69 @@ impl Xpath {
         &self,
         item_tree: &'tree XpathItemTree,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
-        let context =
-            XpathExpressionContext::new_single(item_tree, XpathItem::Node(item_tree.root()), true);
+        self.apply_with_context(item_tree, &DEFAULT_DYNAMIC_CONTEXT)
+    }
+
+    /// Apply the XPath expression to the given item tree using the given [DynamicContext].
+    ///
+    /// Use this instead of [Xpath::apply] to call extension functions,
+    /// use namespace prefixes registered on the context,
+    /// or limit the resources used by the evaluation.
+    ///
+    /// # Arguments
+    ///
+    /// * `item_tree` - The item tree to apply the expression to.
+    /// * `dynamic_context` - The context to evaluate the expression in.
+    ///
+    /// # Returns
+    ///
+    /// The result of applying the expression to the item tree.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use skyscraper::html;
+    /// use skyscraper::xpath::{self, XpathItemTree, DynamicContext};
+    /// use std::error::Error;
+    ///
+    /// fn main() -> Result<(), Box<dyn Error>> {
+    ///     let document = html::parse("<html><div>Hello world</div></html>")?;
+    ///     let xpath_item_tree = XpathItemTree::from(&document);
+    ///     let xpath = xpath::parse("//div")?;
+    ///
+    ///     let context = DynamicContext::default();
+    ///     let items = xpath.apply_with_context(&xpath_item_tree, &context)?;
+    ///
+    ///     assert_eq!(items.len(), 1);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn apply_with_context<'tree>(
+        &self,
+        item_tree: &'tree XpathItemTree,
+        dynamic_context: &DynamicContext,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        self.compile_with_context(dynamic_context).apply(item_tree)
+    }
+
+    /// Apply the expression by evaluating its syntax tree instead of a compiled plan.
+    ///
+    /// The result is the same as [Xpath::apply].
+    /// This is only kept to compare the two in tests and benchmarks.
+    #[cfg(any(test, feature = "bench"))]
+    pub fn apply_uncompiled<'tree>(
+        &self,
+        item_tree: &'tree XpathItemTree,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        let budget = EvaluationBudget::new(DEFAULT_DYNAMIC_CONTEXT.limits());
+        let context = XpathExpressionContext::new_single(
+            item_tree,
+            &DEFAULT_DYNAMIC_CONTEXT,
+            &budget,
+            None,
+            XpathItem::Node(item_tree.root()),
+            true,
+        );
         let mut item_set = self.eval(&context)?;
         item_set.sort();
         Ok(item_set)
@@ -181,8 +277,29 @@ impl Xpath {
         item_tree: &'tree XpathItemTree,
         item: XpathItem<'tree>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
-        let context = XpathExpressionContext::new_single(item_tree, item, false);
-        self.eval(&context)
+        self.apply_to_item_with_context(item_tree, item, &DEFAULT_DYNAMIC_CONTEXT)
+    }
+
+    /// Apply the XPath expression to the given item using the given [DynamicContext].
+    /// The expression will be evaluated relative to the given item.
+    ///
+    /// # Arguments
+    ///
+    /// * `item_tree` - The item tree.
+    /// * `item` - The item to apply the expression to.
+    /// * `dynamic_context` - The context to evaluate the expression in.
+    ///
+    /// # Returns
+    ///
+    /// The result of applying the expression to the item.
+    pub fn apply_to_item_with_context<'tree>(
+        &self,
+        item_tree: &'tree XpathItemTree,
+        item: XpathItem<'tree>,
+        dynamic_context: &DynamicContext,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        self.compile_with_context(dynamic_context)
+            .apply_to_item(item_tree, item)
     }
 
     /// Apply the XPath expression to the given element.
@@ -242,12 +359,11 @@ impl Xpath {
         element: &'tree ElementNode,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         let item = element.to_item(item_tree);
-        let context = XpathExpressionContext::new_single(item_tree, item, false);
-        self.eval(&context)
+        self.apply_to_item(item_tree, item)
     }
 }
 
-pub fn expr(input: &str) -> Res<&str, Expr> {
+pub(crate) fn expr(input: &str) -> Res<&str, Expr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-Expr
 
     context(
@@ -261,6 +377,7 @@ pub fn expr(input: &str) -> Res<&str, Expr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Expr {
     pub expr: ExprSingle,
     pub items: Vec<ExprSingle>,
@@ -280,7 +397,7 @@ impl Display for Expr {
 impl Expr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         /// Add the result of an ExprSingle to the items vector.
         ///
@@ -290,7 +407,7 @@ impl Expr {
         /// * `items` - The vector to add the result to.
         /// * `expr_single` - The expression to evaluate.
         fn add_expr_single_item<'tree>(
-            context: &XpathExpressionContext<'tree>,
+            context: &XpathExpressionContext<'tree, '_>,
             items: &mut XpathItemSet<'tree>,
             expr_single: &ExprSingle,
         ) -> Result<(), ExpressionApplyError> {
@@ -318,13 +435,14 @@ impl Expr {
         // Get remaining items
         for item in self.items.iter() {
             add_expr_single_item(context, &mut items, item)?;
+            context.budget.check_result_size(items.len())?;
         }
 
         Ok(items)
     }
 }
 
-pub fn expr_single(input: &str) -> Res<&str, ExprSingle> {
+pub(crate) fn expr_single(input: &str) -> Res<&str, ExprSingle> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-ExprSingle
 
     fn for_expr_map(input: &str) -> Res<&str, ExprSingle> {
@@ -361,6 +479,7 @@ pub fn expr_single(input: &str) -> Res<&str, ExprSingle> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ExprSingle {
     ForExpr(Box<ForExpr>),
     LetExpr(Box<LetExpr>),
@@ -384,13 +503,26 @@ impl Display for ExprSingle {
 impl ExprSingle {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        // Every nested sub-expression is evaluated through here.
+        let _depth = context.budget.enter()?;
+        self.eval_entered(context)
+    }
+
+    /// Evaluate the expression after its depth has been entered in the budget.
+    pub(crate) fn eval_entered<'tree>(
+        &self,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         match self {
-            ExprSingle::ForExpr(_) => todo!("ExprSingle::ForExpr"),
-            ExprSingle::LetExpr(_) => todo!("ExprSingle::LetExpr"),
-            ExprSingle::QuantifiedExpr(_) => todo!("ExprSingle::QuantifiedExpr"),
-            ExprSingle::IfExpr(_) => todo!("ExprSingle::IfExpr"),
+            ExprSingle::ForExpr(e) => Err(ExpressionApplyError::unsupported("for expression", e)),
+            ExprSingle::LetExpr(e) => Err(ExpressionApplyError::unsupported("let expression", e)),
+            ExprSingle::QuantifiedExpr(e) => Err(ExpressionApplyError::unsupported(
+                "quantified expression",
+                e,
+            )),
+            ExprSingle::IfExpr(e) => Err(ExpressionApplyError::unsupported("if expression", e)),
             ExprSingle::OrExpr(e) => e.eval(context),
         }
     }
@@ -426,6 +558,72 @@ mod tests {
         assert_eq!(next_input, "");
     }
 
+    #[test]
+    fn apply_should_return_error_for_unsupported_axis() {
+        // arrange
+        let document = crate::html::parse("<html><body><div></div></body></html>").unwrap();
+        let xpath_item_tree = XpathItemTree::from(&document);
+        let (_, xpath) = xpath("//div/ancestor::body").unwrap();
+
+        // act
+        let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+        // assert
+        assert_eq!(err.code(), &crate::xpath::ErrorCode::Unsupported);
+        assert_eq!(err.message(), "axis is not supported");
+        assert_eq!(err.expression(), Some("ancestor::"));
+    }
+
+    #[test]
+    fn apply_should_return_error_for_unsupported_operator() {
+        // arrange
+        let document = crate::html::parse("<html><body><div></div></body></html>").unwrap();
+        let xpath_item_tree = XpathItemTree::from(&document);
+        let (_, xpath) = xpath("//div[1 to 2]").unwrap();
+
+        // act
+        let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+        // assert
+        assert_eq!(err.message(), "range expression is not supported");
+        assert_eq!(err.expression(), Some("1 to 2"));
+        assert_eq!(err.span(), Some(6..12));
+    }
+
+    #[test]
+    fn apply_should_locate_error_in_parsed_text() {
+        // arrange
+        let document = crate::html::parse("<html><div>1</div><div>2</div></html>").unwrap();
+        let xpath_item_tree = XpathItemTree::from(&document);
+        let text = "//div[ contains( //div , '1' ) ]";
+        let xpath = crate::xpath::parse(text).unwrap();
+
+        // act
+        let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+        // assert
+        let span = err.span().unwrap();
+        assert_eq!(err.expression(), Some("contains(//div, '1')"));
+        assert_eq!(&text[span], "contains( //div , '1' )");
+    }
+
+    #[test]
+    fn apply_should_locate_error_in_repeated_sub_expression() {
+        // arrange
+        let document =
+            crate::html::parse("<html><p><b>a</b></p><div><b>a</b><b>b</b></div></html>").unwrap();
+        let xpath_item_tree = XpathItemTree::from(&document);
+        let text = "//p[contains(*, 'a')], //div[contains(*, 'a')]";
+        let xpath = crate::xpath::parse(text).unwrap();
+
+        // act
+        let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+        // assert
+        assert_eq!(err.expression(), Some("contains(*, 'a')"));
+        assert_eq!(err.span(), Some(29..45));
+    }
+
     #[test]
     fn xpath_should_parse1() {
         // arrange
@@ -8,7 +8,7 @@ use crate::xpath::grammar::{recipes::Res, whitespace_recipes::ws};
 
 use super::steps::node_tests::{node_test, NodeTest};
 
-pub fn abbrev_forward_step(input: &str) -> Res<&str, AbbrevForwardStep> {
+pub(crate) fn abbrev_forward_step(input: &str) -> Res<&str, AbbrevForwardStep> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-AbbrevForwardStep
     context("abbrev_forward_step", ws((opt(char('@')), node_test)))(input).map(
         |(next_input, res)| {
@@ -24,6 +24,7 @@ pub fn abbrev_forward_step(input: &str) -> Res<&str, AbbrevForwardStep> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AbbrevForwardStep {
     pub has_at: bool,
     pub node_test: NodeTest,
@@ -3,14 +3,18 @@
 use std::fmt::Display;
 
 use nom::{
-    branch::alt, bytes::complete::tag, character::complete::char, combinator::opt, error::context,
-    multi::many0, sequence::tuple,
+    branch::alt, character::complete::char, combinator::opt, error::context, multi::many0,
+    sequence::tuple,
 };
+use once_cell::sync::Lazy;
+
+use crate::xpath::grammar::recipes::tag;
 
 use crate::xpath::grammar::whitespace_recipes::ws;
 use crate::xpath::xpath_item_set::XpathItemSet;
 use crate::xpath::{
     grammar::{expressions::path_expressions::steps::step_expr::step_expr, recipes::Res},
+    planner::IndexedPath,
     ExpressionApplyError, XpathExpressionContext,
 };
 
@@ -19,7 +23,21 @@ use self::steps::step_expr::StepExpr;
 pub mod abbreviated_syntax;
 pub mod steps;
 
-pub fn path_expr(input: &str) -> Res<&str, PathExpr> {
+/// The step a leading slash is expanded to, which selects the root of the context item's tree.
+pub(crate) static ROOT_STEP: Lazy<StepExpr> = Lazy::new(|| {
+    step_expr("(fn:root(self::node()) treat as document-node())")
+        .expect("root step failed to parse")
+        .1
+});
+
+/// The step a double slash is expanded to.
+pub(crate) static DESCENDANT_OR_SELF_STEP: Lazy<StepExpr> = Lazy::new(|| {
+    step_expr("descendant-or-self::node()")
+        .expect("descendant-or-self step failed to parse")
+        .1
+});
+
+pub(crate) fn path_expr(input: &str) -> Res<&str, PathExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-PathExpr
 
     fn leading_slash(input: &str) -> Res<&str, PathExpr> {
@@ -43,6 +61,7 @@ pub fn path_expr(input: &str) -> Res<&str, PathExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PathExpr {
     LeadingSlash(Option<RelativePathExpr>),
     LeadingDoubleSlash(RelativePathExpr),
@@ -69,7 +88,7 @@ impl Display for PathExpr {
 impl PathExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         // Leading slashes mean different things than slashes in the middle of a path expression
         // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#id-path-expressions
@@ -84,13 +103,15 @@ impl PathExpr {
                 expanded_expr.eval(context)
             }
             PathExpr::LeadingDoubleSlash(expr) => {
-                let expanded_expr = if context.is_root_level {
-                    initial_double_slash_expansion(expr)
-                } else {
-                    relative_double_slash_expansion(expr)
-                };
+                if !context.is_root_level {
+                    return relative_double_slash_expansion(expr).eval(context);
+                }
 
-                expanded_expr.eval(context)
+                // Look up the first step in the indexes of the tree instead of visiting every node.
+                match IndexedPath::plan(expr, context) {
+                    Some(plan) => plan.eval(context),
+                    None => initial_double_slash_expansion(expr).eval(context),
+                }
             }
             PathExpr::Plain(expr) => expr.eval(context),
         }
@@ -100,9 +121,7 @@ impl PathExpr {
 fn initial_slash_expansion(unexpanded_expr: &Option<RelativePathExpr>) -> RelativePathExpr {
     // A leading slash is expanded to `(fn:root(self::node()) treat as document-node())/<unexpanded_expr>`
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#id-path-expressions
-    let first_step = step_expr("(fn:root(self::node()) treat as document-node())")
-        .expect("initial slash expansion step 1 failed")
-        .1;
+    let first_step = ROOT_STEP.clone();
 
     let items = match unexpanded_expr {
         Some(x) => {
@@ -143,13 +162,9 @@ fn relative_slash_expansion(unexpanded_expr: &Option<RelativePathExpr>) -> Relat
 fn initial_double_slash_expansion(unexpanded_expr: &RelativePathExpr) -> RelativePathExpr {
     // A leading double slash is expanded to `(fn:root(self::node()) treat as document-node())/descendant-or-self::node()/<unexpanded_expr>`
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#id-path-expressions
-    let first_step = step_expr("(fn:root(self::node()) treat as document-node())")
-        .expect("double slash expansion step 1 failed")
-        .1;
+    let first_step = ROOT_STEP.clone();
 
-    let second_step = step_expr("descendant-or-self::node()")
-        .expect("double slash expansion step 2 failed")
-        .1;
+    let second_step = DESCENDANT_OR_SELF_STEP.clone();
 
     let mut items = vec![StepPair(PathSeparator::Slash, second_step)];
     items.push(StepPair(PathSeparator::Slash, unexpanded_expr.expr.clone()));
@@ -179,7 +194,7 @@ fn relative_double_slash_expansion(unexpanded_expr: &RelativePathExpr) -> Relati
     }
 }
 
-pub fn relative_path_expr(input: &str) -> Res<&str, RelativePathExpr> {
+pub(crate) fn relative_path_expr(input: &str) -> Res<&str, RelativePathExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-RelativePathExpr
 
     fn slash(input: &str) -> Res<&str, PathSeparator> {
@@ -209,6 +224,7 @@ pub fn relative_path_expr(input: &str) -> Res<&str, RelativePathExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RelativePathExpr {
     pub expr: StepExpr,
     pub items: Vec<StepPair>,
@@ -228,45 +244,8 @@ impl Display for RelativePathExpr {
 impl RelativePathExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
-        /// Recursively evaluate a series of steps.
-        fn eval_steps<'tree>(
-            context: &XpathExpressionContext<'tree>,
-            steps: &[StepPair],
-        ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
-            // If there are no steps, return the context item.
-            if steps.is_empty() {
-                let mut result = XpathItemSet::new();
-                result.insert(context.item.clone());
-                return Ok(result);
-            }
-
-            // Otherwise, evaluate the first step.
-            let mut items = XpathItemSet::new();
-            let this_result = steps[0].eval(context)?;
-
-            // For each item in the result of the first step, recursively evaluate the rest of the steps.
-            // The goal is to feed the result of the first steps into the following steps,
-            // so that the final result is only the result of the last step.
-            for (i, _item) in this_result.iter().enumerate() {
-                // Create a context for the inner steps using an item from the current result.
-                let inner_context = XpathExpressionContext::new(
-                    context.item_tree,
-                    &this_result,
-                    i + 1,
-                    context.is_root_level,
-                );
-
-                // Recursively evaluate the rest of the steps for this item.
-                let inner_result = eval_steps(&inner_context, &steps[1..])?;
-
-                // Add the result of the inner steps to the result.
-                items.extend(inner_result);
-            }
-
-            Ok(items)
-        }
         let e1_result = self.expr.eval(context)?;
 
         // If there are no items, return the result of the expression.
@@ -279,27 +258,71 @@ impl RelativePathExpr {
         for (i, _item) in e1_result.iter().enumerate() {
             let en_context = XpathExpressionContext::new(
                 context.item_tree,
+                context.dynamic_context,
+                context.budget,
+                context.tracer,
                 &e1_result,
                 i + 1,
                 context.is_root_level,
             );
             let result = eval_steps(&en_context, &self.items)?;
             items.extend(result);
+            context.budget.check_result_size(items.len())?;
         }
 
         Ok(items)
     }
 }
 
+/// Recursively evaluate a series of steps.
+pub(crate) fn eval_steps<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    steps: &[StepPair],
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    // If there are no steps, return the context item.
+    if steps.is_empty() {
+        let mut result = XpathItemSet::new();
+        result.insert(context.item.clone());
+        return Ok(result);
+    }
+
+    // Otherwise, evaluate the first step.
+    let mut items = XpathItemSet::new();
+    let this_result = steps[0].eval(context)?;
+
+    // For each item in the result of the first step, recursively evaluate the rest of the steps.
+    // The goal is to feed the result of the first steps into the following steps,
+    // so that the final result is only the result of the last step.
+    for (i, _item) in this_result.iter().enumerate() {
+        // Create a context for the inner steps using an item from the current result.
+        let inner_context = XpathExpressionContext::new(
+            context.item_tree,
+            context.dynamic_context,
+            context.budget,
+            context.tracer,
+            &this_result,
+            i + 1,
+            context.is_root_level,
+        );
+
+        // Recursively evaluate the rest of the steps for this item.
+        let inner_result = eval_steps(&inner_context, &steps[1..])?;
+
+        // Add the result of the inner steps to the result.
+        items.extend(inner_result);
+        context.budget.check_result_size(items.len())?;
+    }
+
+    Ok(items)
+}
+
 /// Double slash is expanded to `/descendant-or-self::node/`
 ///
 /// # Arguments
 ///
 /// `expr` - The step _after_ the double slash.
 fn double_slash_expansion(expr: &StepExpr) -> RelativePathExpr {
-    let expanded_double_slash = step_expr("descendant-or-self::node()")
-        .expect("double slash expansion step 1 failed")
-        .1;
+    let expanded_double_slash = DESCENDANT_OR_SELF_STEP.clone();
 
     let items = vec![StepPair(PathSeparator::Slash, expr.clone())];
 
@@ -310,6 +333,7 @@ fn double_slash_expansion(expr: &StepExpr) -> RelativePathExpr {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StepPair(pub PathSeparator, pub StepExpr);
 
 impl Display for StepPair {
@@ -322,7 +346,7 @@ impl Display for StepPair {
 impl StepPair {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         let result: XpathItemSet<'_> = match self.0 {
             PathSeparator::Slash => self.1.eval(context)?,
@@ -337,6 +361,7 @@ impl StepPair {
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PathSeparator {
     Slash,
     DoubleSlash,
@@ -4,12 +4,12 @@ use nom::branch::alt;
 
 use nom::error::context;
 
-use nom::bytes::complete::tag;
+use crate::xpath::grammar::recipes::tag;
 
 use crate::xpath::grammar::recipes::Res;
 use crate::xpath::grammar::whitespace_recipes::ws;
 
-pub fn forward_axis(input: &str) -> Res<&str, ForwardAxis> {
+pub(crate) fn forward_axis(input: &str) -> Res<&str, ForwardAxis> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-ForwardAxis
 
     fn child(input: &str) -> Res<&str, ForwardAxis> {
@@ -43,7 +43,7 @@ pub fn forward_axis(input: &str) -> Res<&str, ForwardAxis> {
     }
 
     fn following(input: &str) -> Res<&str, ForwardAxis> {
-        ws((tag("descendant-or-self"), tag("::")))(input)
+        ws((tag("following"), tag("::")))(input)
             .map(|(next_input, _res)| (next_input, ForwardAxis::Following))
     }
 
@@ -68,6 +68,7 @@ pub fn forward_axis(input: &str) -> Res<&str, ForwardAxis> {
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ForwardAxis {
     Child,
     Descendant,
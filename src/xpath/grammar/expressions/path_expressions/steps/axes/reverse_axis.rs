@@ -1,10 +1,12 @@
 use std::fmt::Display;
 
-use nom::{branch::alt, bytes::complete::tag, error::context, sequence::tuple};
+use nom::{branch::alt, error::context, sequence::tuple};
+
+use crate::xpath::grammar::recipes::tag;
 
 use crate::xpath::grammar::recipes::Res;
 
-pub fn reverse_axis(input: &str) -> Res<&str, ReverseAxis> {
+pub(crate) fn reverse_axis(input: &str) -> Res<&str, ReverseAxis> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-ReverseStep
 
     fn parent_map(input: &str) -> Res<&str, ReverseAxis> {
@@ -45,6 +47,7 @@ pub fn reverse_axis(input: &str) -> Res<&str, ReverseAxis> {
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ReverseAxis {
     Parent,
     Ancestor,
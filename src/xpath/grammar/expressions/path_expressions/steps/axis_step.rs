@@ -22,7 +22,7 @@ use crate::xpath::{
 
 use super::forward_step::ForwardStep;
 
-pub fn axis_step(input: &str) -> Res<&str, AxisStep> {
+pub(crate) fn axis_step(input: &str) -> Res<&str, AxisStep> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-AxisStep
 
     fn reverse_step_map(input: &str) -> Res<&str, AxisStepType> {
@@ -49,6 +49,7 @@ pub fn axis_step(input: &str) -> Res<&str, AxisStep> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AxisStep {
     pub step_type: AxisStepType,
     pub predicates: Vec<Predicate>,
@@ -68,11 +69,16 @@ impl Display for AxisStep {
 impl AxisStep {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
-        let nodes = self.step_type.eval(context)?;
+        let nodes = self
+            .step_type
+            .eval(context)
+            .map_err(|e| e.in_expression(&self.step_type))?;
         let items: XpathItemSet<'tree> = nodes.into_iter().map(XpathItem::Node).collect();
 
+        context.budget.check_result_size(items.len())?;
+
         // If there are no predicates, return expression result.
         if self.predicates.is_empty() {
             return Ok(items);
@@ -86,6 +92,9 @@ impl AxisStep {
 
             let predicate_context = XpathExpressionContext::new(
                 context.item_tree,
+                context.dynamic_context,
+                context.budget,
+                context.tracer,
                 &items,
                 i + 1,
                 context.is_root_level,
@@ -98,6 +107,7 @@ impl AxisStep {
 
             if is_match {
                 filtered_items.insert(item.clone());
+                context.budget.check_result_size(filtered_items.len())?;
             }
         }
 
@@ -106,6 +116,7 @@ impl AxisStep {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AxisStepType {
     ReverseStep(ReverseStep),
     ForwardStep(ForwardStep),
@@ -114,7 +125,7 @@ pub enum AxisStepType {
 impl AxisStepType {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<IndexSet<&'tree XpathItemTreeNode>, ExpressionApplyError> {
         match self {
             AxisStepType::ReverseStep(step) => step.eval(context),
@@ -12,10 +12,10 @@ use crate::xpath::{
         },
         recipes::Res,
         whitespace_recipes::ws,
-        XpathItemTreeNode,
+        XpathItemTree, XpathItemTreeNode,
     },
     xpath_item_set::XpathItemSet,
-    ExpressionApplyError, XpathExpressionContext,
+    ErrorCode, ExpressionApplyError, XpathExpressionContext,
 };
 
 use super::{
@@ -23,7 +23,7 @@ use super::{
     node_tests::{BiDirectionalAxis, NodeTest},
 };
 
-pub fn forward_step(input: &str) -> Res<&str, ForwardStep> {
+pub(crate) fn forward_step(input: &str) -> Res<&str, ForwardStep> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-ForwardStep
 
     fn full_forward_step(input: &str) -> Res<&str, ForwardStep> {
@@ -43,6 +43,7 @@ pub fn forward_step(input: &str) -> Res<&str, ForwardStep> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ForwardStep {
     Full(ForwardAxis, NodeTest),
     Abbreviated(AbbrevForwardStep),
@@ -60,7 +61,7 @@ impl Display for ForwardStep {
 impl ForwardStep {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<IndexSet<&'tree XpathItemTreeNode>, ExpressionApplyError> {
         match self {
             ForwardStep::Full(axis, node_test) => eval_forward_axis(context, *axis, node_test),
@@ -79,7 +80,7 @@ impl ForwardStep {
 }
 
 fn eval_forward_axis<'tree>(
-    context: &XpathExpressionContext<'tree>,
+    context: &XpathExpressionContext<'tree, '_>,
     axis: ForwardAxis,
     node_test: &NodeTest,
 ) -> Result<IndexSet<&'tree XpathItemTreeNode>, ExpressionApplyError> {
@@ -87,24 +88,35 @@ fn eval_forward_axis<'tree>(
         ForwardAxis::Child => eval_forward_axis_child(context),
         ForwardAxis::Descendant => eval_forward_axis_descendant(context),
         ForwardAxis::Attribute => eval_forward_axis_attribute(context),
-        ForwardAxis::SelfAxis => todo!("eval_forward_axis ForwardAxis::SelfAxis"),
+        ForwardAxis::SelfAxis => eval_forward_axis_self(context),
         ForwardAxis::DescendantOrSelf => eval_forward_axis_self_or_descendant(context),
-        ForwardAxis::FollowingSibling => todo!("eval_forward_axis ForwardAxis::FollowingSibling"),
-        ForwardAxis::Following => todo!("eval_forward_axis ForwardAxis::Following"),
-        ForwardAxis::Namespace => todo!("eval_forward_axis ForwardAxis::Namespace"),
+        ForwardAxis::FollowingSibling => eval_forward_axis_following_sibling(context),
+        ForwardAxis::Following | ForwardAxis::Namespace => {
+            Err(ExpressionApplyError::unsupported("axis", &axis))
+        }
     }?;
 
     let items: XpathItemSet<'tree> = axis_nodes.into_iter().map(XpathItem::Node).collect();
     let mut nodes = IndexSet::new();
 
     for (i, _item) in items.iter().enumerate() {
-        let node_test_context =
-            XpathExpressionContext::new(context.item_tree, &items, i + 1, context.is_root_level);
+        context.budget.visit_node()?;
+
+        let node_test_context = XpathExpressionContext::new(
+            context.item_tree,
+            context.dynamic_context,
+            context.budget,
+            context.tracer,
+            &items,
+            i + 1,
+            context.is_root_level,
+        );
 
         if let Some(result) =
             node_test.eval(BiDirectionalAxis::ForwardAxis(axis), &node_test_context)?
         {
             nodes.insert(result);
+            context.budget.check_result_size(nodes.len())?;
         }
     }
 
@@ -113,7 +125,7 @@ fn eval_forward_axis<'tree>(
 
 /// Direct children of the context nodes.
 fn eval_forward_axis_child<'tree>(
-    context: &XpathExpressionContext<'tree>,
+    context: &XpathExpressionContext<'tree, '_>,
 ) -> Result<IndexSet<&'tree XpathItemTreeNode>, ExpressionApplyError> {
     let mut nodes: IndexSet<&'tree XpathItemTreeNode> = IndexSet::new();
 
@@ -129,7 +141,7 @@ fn eval_forward_axis_child<'tree>(
 
 /// All descendants of the context nodes.
 fn eval_forward_axis_descendant<'tree>(
-    context: &XpathExpressionContext<'tree>,
+    context: &XpathExpressionContext<'tree, '_>,
 ) -> Result<IndexSet<&'tree XpathItemTreeNode>, ExpressionApplyError> {
     let mut nodes: IndexSet<&'tree XpathItemTreeNode> = IndexSet::new();
 
@@ -142,6 +154,9 @@ fn eval_forward_axis_descendant<'tree>(
             // Add the child's descendants.
             let child_eval_context = XpathExpressionContext::new_single(
                 context.item_tree,
+                context.dynamic_context,
+                context.budget,
+                context.tracer,
                 child.into(),
                 context.is_root_level,
             );
@@ -153,18 +168,73 @@ fn eval_forward_axis_descendant<'tree>(
     Ok(nodes)
 }
 
+/// The context node itself.
+fn eval_forward_axis_self<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+) -> Result<IndexSet<&'tree XpathItemTreeNode>, ExpressionApplyError> {
+    let mut nodes = IndexSet::new();
+
+    // Only tree nodes can be selected by an axis.
+    if let XpathItem::Node(node) = &context.item {
+        nodes.insert(*node);
+    }
+
+    Ok(nodes)
+}
+
+/// The siblings after the context node, in document order.
+fn eval_forward_axis_following_sibling<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+) -> Result<IndexSet<&'tree XpathItemTreeNode>, ExpressionApplyError> {
+    let mut nodes = IndexSet::new();
+
+    if let XpathItem::Node(node) = &context.item {
+        let siblings = sibling_nodes(node, context.item_tree);
+        let position = siblings
+            .iter()
+            .position(|sibling| sibling.id() == node.id());
+        if let Some(position) = position {
+            nodes.extend(siblings.into_iter().skip(position + 1));
+        }
+    }
+
+    Ok(nodes)
+}
+
+/// The children of the node's parent in document order, including the node itself.
+///
+/// Attributes are not siblings of anything, so they have none.
+pub(super) fn sibling_nodes<'tree>(
+    node: &XpathItemTreeNode,
+    item_tree: &'tree XpathItemTree,
+) -> Vec<&'tree XpathItemTreeNode> {
+    if let XpathItemTreeNode::AttributeNode(_) = node {
+        return Vec::new();
+    }
+
+    match node.parent(item_tree) {
+        Some(parent) => parent
+            .children(item_tree)
+            .into_iter()
+            .filter(|child| !matches!(child, XpathItemTreeNode::AttributeNode(_)))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
 /// All descendants of the context nodes including the context nodes.
 fn eval_forward_axis_self_or_descendant<'tree>(
-    context: &XpathExpressionContext<'tree>,
+    context: &XpathExpressionContext<'tree, '_>,
 ) -> Result<IndexSet<&'tree XpathItemTreeNode>, ExpressionApplyError> {
     let mut nodes = IndexSet::new();
 
     if let XpathItem::Node(node) = &context.item {
         nodes.insert(*node);
     } else {
-        return Err(ExpressionApplyError {
-            msg: String::from("err:XPTY0020 context item for axis step is not a node"),
-        });
+        return Err(ExpressionApplyError::with_code(
+            ErrorCode::XPTY0020,
+            String::from("context item for axis step is not a node"),
+        ));
     }
 
     nodes.extend(eval_forward_axis_descendant(context)?);
@@ -174,7 +244,7 @@ fn eval_forward_axis_self_or_descendant<'tree>(
 
 // All attributes of the context nodes.
 fn eval_forward_axis_attribute<'tree>(
-    context: &XpathExpressionContext<'tree>,
+    context: &XpathExpressionContext<'tree, '_>,
 ) -> Result<IndexSet<&'tree XpathItemTreeNode>, ExpressionApplyError> {
     let mut attributes: IndexSet<&'tree XpathItemTreeNode> = IndexSet::new();
 
@@ -2,9 +2,9 @@
 
 use std::fmt::Display;
 
-use nom::{
-    branch::alt, bytes::complete::tag, character::complete::char, error::context, sequence::tuple,
-};
+use nom::{branch::alt, character::complete::char, error::context, sequence::tuple};
+
+use crate::xpath::grammar::recipes::tag;
 
 use crate::{
     xpath::{
@@ -16,14 +16,14 @@ use crate::{
             xml_names::{nc_name, QName},
             XpathItemTreeNode,
         },
-        ExpressionApplyError, XpathExpressionContext,
+        ErrorCode, ExpressionApplyError, XpathExpressionContext,
     },
     xpath_item_set,
 };
 
 use super::axes::{forward_axis::ForwardAxis, reverse_axis::ReverseAxis};
 
-pub fn node_test(input: &str) -> Res<&str, NodeTest> {
+pub(crate) fn node_test(input: &str) -> Res<&str, NodeTest> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-NodeTest
 
     fn kind_test_map(input: &str) -> Res<&str, NodeTest> {
@@ -38,6 +38,7 @@ pub fn node_test(input: &str) -> Res<&str, NodeTest> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NodeTest {
     KindTest(KindTest),
     NameTest(NameTest),
@@ -56,7 +57,7 @@ impl NodeTest {
     pub(crate) fn eval<'tree>(
         &self,
         axis: BiDirectionalAxis,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<Option<&'tree XpathItemTreeNode>, ExpressionApplyError> {
         match self {
             NodeTest::KindTest(test) => {
@@ -74,7 +75,7 @@ impl NodeTest {
     }
 }
 
-fn name_test(input: &str) -> Res<&str, NameTest> {
+pub(crate) fn name_test(input: &str) -> Res<&str, NameTest> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-NameTest
 
     fn eq_name_map(input: &str) -> Res<&str, NameTest> {
@@ -89,6 +90,7 @@ fn name_test(input: &str) -> Res<&str, NameTest> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NameTest {
     Name(EQName),
     Wildcard(Wildcard),
@@ -112,33 +114,52 @@ impl NameTest {
     pub(crate) fn eval<'tree>(
         &self,
         axis: BiDirectionalAxis,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<Option<&'tree XpathItemTreeNode>, ExpressionApplyError> {
         let node = if let XpathItem::Node(node) = &context.item {
             node
         } else {
-            todo!("NameTest::eval non-node");
+            return Err(ExpressionApplyError::with_code(
+                ErrorCode::XPTY0020,
+                String::from("context item for name test is not a node"),
+            ));
         };
 
         let is_match = match self {
             NameTest::Name(expected_name) => {
                 // Get the name of the node, if available for the node type.
                 let node_name = match node {
-                    XpathItemTreeNode::DocumentNode(_) => todo!(),
                     XpathItemTreeNode::ElementNode(e) => Some(&e.name),
-                    XpathItemTreeNode::PINode(_) => todo!(),
-                    XpathItemTreeNode::CommentNode(_) => todo!(),
-                    XpathItemTreeNode::TextNode(_) => None, // Text nodes do not have a name.
                     XpathItemTreeNode::AttributeNode(a) => Some(&a.name),
+                    // Documents, comments and text nodes do not have a name,
+                    // and processing instructions are never the principal node kind.
+                    XpathItemTreeNode::DocumentNode(_)
+                    | XpathItemTreeNode::PINode(_)
+                    | XpathItemTreeNode::CommentNode(_)
+                    | XpathItemTreeNode::TextNode(_) => None,
                 };
 
                 match node_name {
                     Some(node_name) => match expected_name {
                         EQName::QName(qname) => match qname {
-                            QName::PrefixedName(_) => todo!("NameTest::is_match PrefixedName"),
+                            QName::PrefixedName(_) => {
+                                return Err(ExpressionApplyError::unsupported(
+                                    "name test with a prefix",
+                                    self,
+                                ))
+                            }
                             QName::UnprefixedName(unprefixed_name) => unprefixed_name == node_name,
                         },
-                        EQName::UriQualifiedName(_) => todo!("NameTest::is_match UriQualifiedName"),
+                        // HTML names have no namespace, which is written `Q{}name`.
+                        EQName::UriQualifiedName(name) if name.uri.is_empty() => {
+                            &name.name == node_name
+                        }
+                        EQName::UriQualifiedName(_) => {
+                            return Err(ExpressionApplyError::unsupported(
+                                "name test with a namespace",
+                                self,
+                            ))
+                        }
                     },
 
                     // Name tests need a name to match.
@@ -146,7 +167,7 @@ impl NameTest {
                     None => false,
                 }
             }
-            NameTest::Wildcard(wildcard) => wildcard.is_match(axis, node),
+            NameTest::Wildcard(wildcard) => wildcard.is_match(axis, node)?,
         };
 
         if is_match {
@@ -192,6 +213,7 @@ fn wildcard(input: &str) -> Res<&str, Wildcard> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Wildcard {
     Simple,
     PrefixedName(String),
@@ -215,7 +237,7 @@ impl Wildcard {
         &self,
         axis: BiDirectionalAxis,
         node: &'tree XpathItemTreeNode,
-    ) -> bool {
+    ) -> Result<bool, ExpressionApplyError> {
         // Wildcards only match context items that are the axis' principal node kind.
         // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#dt-principal-node-kind
         let is_principal_node_kind = match axis {
@@ -230,14 +252,14 @@ impl Wildcard {
         };
 
         if !is_principal_node_kind {
-            return false;
+            return Ok(false);
         }
 
         match self {
-            Wildcard::Simple => true,
-            Wildcard::PrefixedName(_) => todo!("Wildcard::is_match PrefixedName"),
-            Wildcard::SuffixedName(_) => todo!("Wildcard::is_match SuffixedName"),
-            Wildcard::BracedUri(_) => todo!("Wildcard::is_match BracedUri"),
+            Wildcard::Simple => Ok(true),
+            Wildcard::PrefixedName(_) | Wildcard::SuffixedName(_) | Wildcard::BracedUri(_) => {
+                Err(ExpressionApplyError::unsupported("wildcard", self))
+            }
         }
     }
 }
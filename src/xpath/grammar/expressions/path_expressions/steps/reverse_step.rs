@@ -1,7 +1,9 @@
 use std::fmt::Display;
 
 use indexmap::IndexSet;
-use nom::{branch::alt, bytes::complete::tag, error::context};
+use nom::{branch::alt, error::context};
+
+use crate::xpath::grammar::recipes::tag;
 
 use crate::xpath::{
     grammar::{
@@ -20,10 +22,11 @@ use crate::xpath::{
 
 use super::{
     axes::reverse_axis::ReverseAxis,
+    forward_step::sibling_nodes,
     node_tests::{BiDirectionalAxis, NodeTest},
 };
 
-pub fn reverse_step(input: &str) -> Res<&str, ReverseStep> {
+pub(crate) fn reverse_step(input: &str) -> Res<&str, ReverseStep> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-ReverseStep
     fn full_reverse_step(input: &str) -> Res<&str, ReverseStep> {
         ws((reverse_axis, node_test))(input)
@@ -42,6 +45,7 @@ pub fn reverse_step(input: &str) -> Res<&str, ReverseStep> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ReverseStep {
     Full(ReverseAxis, NodeTest),
     Abbreviated,
@@ -59,7 +63,7 @@ impl Display for ReverseStep {
 impl ReverseStep {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<IndexSet<&'tree XpathItemTreeNode>, ExpressionApplyError> {
         match self {
             ReverseStep::Full(axis, node_test) => eval_reverse_axis(context, *axis, node_test),
@@ -76,29 +80,39 @@ impl ReverseStep {
 }
 
 fn eval_reverse_axis<'tree>(
-    context: &XpathExpressionContext<'tree>,
+    context: &XpathExpressionContext<'tree, '_>,
     axis: ReverseAxis,
     node_test: &NodeTest,
 ) -> Result<IndexSet<&'tree XpathItemTreeNode>, ExpressionApplyError> {
     let axis_nodes: IndexSet<&'tree XpathItemTreeNode> = match axis {
         ReverseAxis::Parent => eval_reverse_axis_parent(context),
-        ReverseAxis::Ancestor => todo!("eval_reverse_axis ReverseAxis::Ancestor"),
-        ReverseAxis::PrecedingSibling => todo!("eval_reverse_axis ReverseAxis::PrecedingSibling"),
-        ReverseAxis::Preceding => todo!("eval_reverse_axis ReverseAxis::Preceding"),
-        ReverseAxis::AncestorOrSelf => todo!("eval_reverse_axis ReverseAxis::AncestorOrSelf"),
+        ReverseAxis::PrecedingSibling => eval_reverse_axis_preceding_sibling(context),
+        ReverseAxis::Ancestor | ReverseAxis::Preceding | ReverseAxis::AncestorOrSelf => {
+            Err(ExpressionApplyError::unsupported("axis", &axis))
+        }
     }?;
 
     let items: XpathItemSet<'tree> = axis_nodes.into_iter().map(XpathItem::Node).collect();
     let mut nodes = IndexSet::new();
 
     for (i, _node) in items.iter().enumerate() {
-        let node_test_context =
-            XpathExpressionContext::new(context.item_tree, &items, i + 1, context.is_root_level);
+        context.budget.visit_node()?;
+
+        let node_test_context = XpathExpressionContext::new(
+            context.item_tree,
+            context.dynamic_context,
+            context.budget,
+            context.tracer,
+            &items,
+            i + 1,
+            context.is_root_level,
+        );
 
         if let Some(result) =
             node_test.eval(BiDirectionalAxis::ReverseAxis(axis), &node_test_context)?
         {
             nodes.insert(result);
+            context.budget.check_result_size(nodes.len())?;
         }
     }
 
@@ -107,7 +121,7 @@ fn eval_reverse_axis<'tree>(
 
 /// Direct parent of the context node.
 fn eval_reverse_axis_parent<'tree>(
-    context: &XpathExpressionContext<'tree>,
+    context: &XpathExpressionContext<'tree, '_>,
 ) -> Result<IndexSet<&'tree XpathItemTreeNode>, ExpressionApplyError> {
     let mut nodes: IndexSet<&'tree XpathItemTreeNode> = IndexSet::new();
 
@@ -122,6 +136,28 @@ fn eval_reverse_axis_parent<'tree>(
     Ok(nodes)
 }
 
+/// The siblings before the context node, nearest first.
+///
+/// Positions on a reverse axis count back from the context node,
+/// so `preceding-sibling::*[1]` is the sibling right before it.
+fn eval_reverse_axis_preceding_sibling<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+) -> Result<IndexSet<&'tree XpathItemTreeNode>, ExpressionApplyError> {
+    let mut nodes = IndexSet::new();
+
+    if let XpathItem::Node(node) = &context.item {
+        let siblings = sibling_nodes(node, context.item_tree);
+        let position = siblings
+            .iter()
+            .position(|sibling| sibling.id() == node.id());
+        if let Some(position) = position {
+            nodes.extend(siblings.into_iter().take(position).rev());
+        }
+    }
+
+    Ok(nodes)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
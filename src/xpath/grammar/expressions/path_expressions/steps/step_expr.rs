@@ -3,6 +3,7 @@ use std::fmt::Display;
 use nom::error::context;
 
 use crate::xpath::{
+    explain::TraceId,
     grammar::{
         expressions::{
             path_expressions::steps::axis_step::axis_step,
@@ -16,7 +17,7 @@ use crate::xpath::{
 
 use super::axis_step::AxisStep;
 
-pub fn step_expr(input: &str) -> Res<&str, StepExpr> {
+pub(crate) fn step_expr(input: &str) -> Res<&str, StepExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-StepExpr
 
     fn postfix_expr_map(input: &str) -> Res<&str, StepExpr> {
@@ -31,6 +32,7 @@ pub fn step_expr(input: &str) -> Res<&str, StepExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StepExpr {
     PostfixExpr(PostfixExpr),
     AxisStep(AxisStep),
@@ -48,11 +50,29 @@ impl Display for StepExpr {
 impl StepExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
-        match self {
+        let eval = || match self {
             StepExpr::PostfixExpr(expr) => expr.eval(context),
             StepExpr::AxisStep(step) => step.eval(context),
+        };
+
+        match context.tracer {
+            Some(tracer) if self.is_traced() => {
+                tracer.trace_step(TraceId::of(self), self, context.item_tree, eval)
+            }
+            _ => eval(),
+        }
+    }
+
+    /// Whether this step is recorded when explaining an expression.
+    ///
+    /// Plain values such as literals and function calls are steps too,
+    /// but only axis steps and filter expressions are worth tracing.
+    pub(crate) fn is_traced(&self) -> bool {
+        match self {
+            StepExpr::PostfixExpr(expr) => !expr.items.is_empty(),
+            StepExpr::AxisStep(_) => true,
         }
     }
 }
@@ -5,6 +5,7 @@ use std::fmt::Display;
 use nom::{branch::alt, character::complete::char, error::context, multi::many0, sequence::tuple};
 
 use crate::xpath::{
+    explain::TraceId,
     grammar::{
         data_model::{AnyAtomicType, XpathItem},
         expressions::{
@@ -22,7 +23,7 @@ use super::{
     primary_expressions::PrimaryExpr, Expr,
 };
 
-pub fn postfix_expr(input: &str) -> Res<&str, PostfixExpr> {
+pub(crate) fn postfix_expr(input: &str) -> Res<&str, PostfixExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-PostfixExpr
 
     fn predicate_map(input: &str) -> Res<&str, PostfixExprItem> {
@@ -57,6 +58,7 @@ pub fn postfix_expr(input: &str) -> Res<&str, PostfixExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PostfixExpr {
     pub expr: PrimaryExpr,
     pub items: Vec<PostfixExprItem>,
@@ -76,12 +78,44 @@ impl Display for PostfixExpr {
 impl PostfixExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
-        let res = self.expr.eval(context)?;
-
-        if !self.items.is_empty() {
-            todo!("PostfixExpr eval items")
+        let mut res = self.expr.eval(context)?;
+
+        // Apply each predicate and lookup to the result of the previous one.
+        for item in self.items.iter() {
+            res = match item {
+                PostfixExprItem::Predicate(predicate) => {
+                    let mut filtered_items = XpathItemSet::new();
+                    for (i, item) in res.iter().enumerate() {
+                        let predicate_context = XpathExpressionContext::new(
+                            context.item_tree,
+                            context.dynamic_context,
+                            context.budget,
+                            context.tracer,
+                            &res,
+                            i + 1,
+                            context.is_root_level,
+                        );
+
+                        if predicate.is_match(&predicate_context)? {
+                            filtered_items.insert(item.clone());
+                        }
+                    }
+
+                    filtered_items
+                }
+                PostfixExprItem::Lookup(lookup) => lookup
+                    .0
+                    .lookup(&res, context)
+                    .map_err(|e| e.in_expression(lookup))?,
+                PostfixExprItem::ArgumentList(_) => {
+                    return Err(ExpressionApplyError::unsupported(
+                        "dynamic function call",
+                        self,
+                    ))
+                }
+            };
         }
 
         Ok(res)
@@ -89,6 +123,7 @@ impl PostfixExpr {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PostfixExprItem {
     Predicate(Predicate),
     ArgumentList(ArgumentList),
@@ -105,14 +140,15 @@ impl Display for PostfixExprItem {
     }
 }
 
-pub fn predicate(input: &str) -> Res<&str, Predicate> {
+pub(crate) fn predicate(input: &str) -> Res<&str, Predicate> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-Predicate
     context("predicate", ws((char('['), expr, char(']'))))(input)
         .map(|(next_input, res)| (next_input, Predicate(res.1)))
 }
 
 #[derive(PartialEq, Debug, Clone)]
-pub struct Predicate(Expr);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Predicate(pub Expr);
 
 impl Display for Predicate {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -123,31 +159,53 @@ impl Display for Predicate {
 impl Predicate {
     pub(crate) fn is_match<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
+    ) -> Result<bool, ExpressionApplyError> {
+        // Testing a predicate visits the context node, however cheap the predicate is.
+        context.budget.visit_node()?;
+
+        match context.tracer {
+            Some(tracer) => tracer.trace_predicate(
+                TraceId::of(self),
+                self,
+                context.item_tree,
+                &context.item,
+                || self.is_match_untraced(context),
+            ),
+            None => self.is_match_untraced(context),
+        }
+    }
+
+    fn is_match_untraced<'tree>(
+        &self,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<bool, ExpressionApplyError> {
         let res = self.0.eval(&context)?;
+        Ok(predicate_truth_value(&res, context.position))
+    }
+}
 
-        // The predicate truth value is derived by applying the following rules, in order:
-        // 1. If the value of the predicate expression is a singleton atomic value of a numeric type or derived from a numeric type,
-        //    the predicate truth value is true if the value of the predicate expression is equal (by the eq operator) to the context position,
-        //    and is false otherwise.
-        // 2. Otherwise, the predicate truth value is the effective boolean value of the predicate expression.
-
-        // Step 1. If the value is a number, check if it matches the context position.
-        if res.len() == 1 {
-            match &res[0] {
-                XpathItem::AnyAtomicType(atomic_type) => match atomic_type {
-                    AnyAtomicType::Integer(n) => return Ok(*n == context.position as i64),
-                    AnyAtomicType::Float(n) => return Ok(*n == context.position as f32),
-                    AnyAtomicType::Double(n) => return Ok(*n == context.position as f64),
-                    _ => {}
-                },
+/// Get the truth value of a predicate from the result of its expression.
+pub(crate) fn predicate_truth_value(res: &XpathItemSet, position: usize) -> bool {
+    // The predicate truth value is derived by applying the following rules, in order:
+    // 1. If the value of the predicate expression is a singleton atomic value of a numeric type or derived from a numeric type,
+    //    the predicate truth value is true if the value of the predicate expression is equal (by the eq operator) to the context position,
+    //    and is false otherwise.
+    // 2. Otherwise, the predicate truth value is the effective boolean value of the predicate expression.
+
+    // Step 1. If the value is a number, check if it matches the context position.
+    if res.len() == 1 {
+        if let XpathItem::AnyAtomicType(atomic_type) = &res[0] {
+            match atomic_type {
+                AnyAtomicType::Integer(n) => return *n == position as i64,
+                AnyAtomicType::Float(n) => return *n == position as f32,
+                AnyAtomicType::Double(n) => return *n == position as f64,
                 _ => {}
             }
         }
-
-        Ok(res.boolean())
     }
+
+    res.boolean()
 }
 
 #[cfg(test)]
@@ -2,20 +2,24 @@
 
 use nom::{character::complete::char, combinator::opt, error::context};
 
-use crate::xpath::grammar::{
-    expressions::{expr, Expr},
-    recipes::Res,
-    whitespace_recipes::ws,
+use crate::xpath::{
+    grammar::{
+        expressions::{expr, Expr},
+        recipes::Res,
+        whitespace_recipes::ws,
+    },
+    ExpressionApplyError, XpathExpressionContext, XpathItemSet,
 };
 
-pub fn enclosed_expr(input: &str) -> Res<&str, EnclosedExpr> {
+pub(crate) fn enclosed_expr(input: &str) -> Res<&str, EnclosedExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-EnclosedExpr
     context("enclosed_expr", ws((char('{'), opt(expr), char('}'))))(input)
         .map(|(next_input, res)| (next_input, EnclosedExpr(res.1)))
 }
 
 #[derive(PartialEq, Debug, Clone)]
-pub struct EnclosedExpr(Option<Expr>);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnclosedExpr(pub Option<Expr>);
 
 impl std::fmt::Display for EnclosedExpr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -27,6 +31,18 @@ impl std::fmt::Display for EnclosedExpr {
     }
 }
 
+impl EnclosedExpr {
+    pub(crate) fn eval<'tree>(
+        &self,
+        context: &XpathExpressionContext<'tree, '_>,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        match &self.0 {
+            Some(expr) => expr.eval(context),
+            None => Ok(XpathItemSet::new()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
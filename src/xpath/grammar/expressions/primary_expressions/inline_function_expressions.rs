@@ -2,9 +2,9 @@
 
 use std::fmt::Display;
 
-use nom::{
-    bytes::complete::tag, character::complete::char, combinator::opt, error::context, multi::many0,
-};
+use nom::{character::complete::char, combinator::opt, error::context, multi::many0};
+
+use crate::xpath::grammar::recipes::tag;
 
 use crate::xpath::grammar::{
     recipes::Res,
@@ -18,7 +18,7 @@ use crate::xpath::grammar::{
 
 use super::enclosed_expressions::{enclosed_expr, EnclosedExpr};
 
-pub fn inline_function_expr(input: &str) -> Res<&str, InlineFunctionExpr> {
+pub(crate) fn inline_function_expr(input: &str) -> Res<&str, InlineFunctionExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-InlineFunctionExpr
 
     context(
@@ -45,6 +45,7 @@ pub fn inline_function_expr(input: &str) -> Res<&str, InlineFunctionExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InlineFunctionExpr {
     pub param_list: Option<ParamList>,
     pub sequence_type: Option<SequenceType>,
@@ -67,7 +68,7 @@ impl Display for InlineFunctionExpr {
     }
 }
 
-pub fn param_list(input: &str) -> Res<&str, ParamList> {
+pub(crate) fn param_list(input: &str) -> Res<&str, ParamList> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-ParamList
     context("param_list", ws((param, many0(ws((char(','), param))))))(input).map(
         |(next_input, res)| {
@@ -80,7 +81,8 @@ pub fn param_list(input: &str) -> Res<&str, ParamList> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
-pub struct ParamList(Vec<Param>);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParamList(pub Vec<Param>);
 
 impl Display for ParamList {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -95,7 +97,7 @@ impl Display for ParamList {
     }
 }
 
-pub fn param(input: &str) -> Res<&str, Param> {
+pub(crate) fn param(input: &str) -> Res<&str, Param> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-Param
     context(
         "param",
@@ -113,6 +115,7 @@ pub fn param(input: &str) -> Res<&str, Param> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Param {
     pub name: EQName,
     pub type_declaration: Option<TypeDeclaration>,
@@ -129,13 +132,14 @@ impl Display for Param {
     }
 }
 
-pub fn type_declaration(input: &str) -> Res<&str, TypeDeclaration> {
+pub(crate) fn type_declaration(input: &str) -> Res<&str, TypeDeclaration> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-TypeDeclaration
     context("type_declaration", sep((tag("as"), sequence_type)))(input)
         .map(|(next_input, res)| (next_input, TypeDeclaration(res.1)))
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TypeDeclaration(pub SequenceType);
 
 impl Display for TypeDeclaration {
@@ -144,13 +148,14 @@ impl Display for TypeDeclaration {
     }
 }
 
-pub fn function_body(input: &str) -> Res<&str, FunctionBody> {
+pub(crate) fn function_body(input: &str) -> Res<&str, FunctionBody> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-FunctionBody
     context("function_body", enclosed_expr)(input)
         .map(|(next_input, res)| (next_input, FunctionBody(res)))
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FunctionBody(pub EnclosedExpr);
 
 impl Display for FunctionBody {
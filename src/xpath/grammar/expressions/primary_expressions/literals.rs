@@ -12,7 +12,7 @@ use crate::xpath::grammar::{
     },
 };
 
-pub fn literal(input: &str) -> Res<&str, Literal> {
+pub(crate) fn literal(input: &str) -> Res<&str, Literal> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-Literal
 
     fn numeric_literal_map(input: &str) -> Res<&str, Literal> {
@@ -27,6 +27,7 @@ pub fn literal(input: &str) -> Res<&str, Literal> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Literal {
     NumericLiteral(NumericLiteral),
     StringLiteral(StringLiteral),
@@ -50,7 +51,7 @@ impl Literal {
     }
 }
 
-pub fn numeric_literal(input: &str) -> Res<&str, NumericLiteral> {
+pub(crate) fn numeric_literal(input: &str) -> Res<&str, NumericLiteral> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-NumericLiteral
 
     fn integer_literal_map(input: &str) -> Res<&str, NumericLiteral> {
@@ -72,8 +73,9 @@ pub fn numeric_literal(input: &str) -> Res<&str, NumericLiteral> {
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NumericLiteral {
-    Integer(u32),
+    Integer(i64),
     Decimal(f32),
     Double(f64),
 }
@@ -82,7 +84,21 @@ impl Display for NumericLiteral {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             NumericLiteral::Integer(x) => write!(f, "{}", x),
+            // Decimals always have a point, so that they are not read back as integers.
+            NumericLiteral::Decimal(x) if x.is_finite() && x.fract() == 0.0 => {
+                write!(f, "{}.0", x)
+            }
             NumericLiteral::Decimal(x) => write!(f, "{}", x),
+            // Doubles are written in the canonical form of xs:double, such as `1.5E10`.
+            NumericLiteral::Double(x) if x.is_finite() => {
+                let text = format!("{:E}", x);
+                match text.split_once('E') {
+                    Some((mantissa, exponent)) if !mantissa.contains('.') => {
+                        write!(f, "{}.0E{}", mantissa, exponent)
+                    }
+                    _ => write!(f, "{}", text),
+                }
+            }
             NumericLiteral::Double(x) => write!(f, "{}", x),
         }
     }
@@ -91,7 +107,7 @@ impl Display for NumericLiteral {
 impl NumericLiteral {
     pub(crate) fn value(&self) -> AnyAtomicType {
         match self {
-            NumericLiteral::Integer(x) => AnyAtomicType::Integer((*x).into()),
+            NumericLiteral::Integer(x) => AnyAtomicType::Integer(*x),
             NumericLiteral::Decimal(x) => AnyAtomicType::Float(ordered_float::OrderedFloat(*x)),
             NumericLiteral::Double(x) => AnyAtomicType::Double(ordered_float::OrderedFloat(*x)),
         }
@@ -125,7 +141,20 @@ mod test {
 
         // assert
         assert_eq!(next_input, "");
-        assert_eq!(res.to_string(), "100");
+        assert_eq!(res.to_string(), "1.0E2");
+    }
+
+    #[test]
+    fn numeric_literal_should_match_integer_beyond_u32() {
+        // arrange
+        let input = "4294967296";
+
+        // act
+        let (next_input, res) = numeric_literal(input).unwrap();
+
+        // assert
+        assert_eq!(next_input, "");
+        assert_eq!(res.value(), AnyAtomicType::Integer(4294967296));
     }
 
     #[test]
@@ -39,14 +39,14 @@ use super::maps_and_arrays::{
 };
 
 pub mod enclosed_expressions;
-mod inline_function_expressions;
-mod literals;
-mod named_function_references;
+pub mod inline_function_expressions;
+pub mod literals;
+pub mod named_function_references;
 pub mod parenthesized_expressions;
 pub mod static_function_calls;
 pub mod variable_references;
 
-pub fn primary_expr(input: &str) -> Res<&str, PrimaryExpr> {
+pub(crate) fn primary_expr(input: &str) -> Res<&str, PrimaryExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-PrimaryExpr
 
     fn literal_map(input: &str) -> Res<&str, PrimaryExpr> {
@@ -107,6 +107,7 @@ pub fn primary_expr(input: &str) -> Res<&str, PrimaryExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PrimaryExpr {
     Literal(Literal),
     VarRef(VarRef),
@@ -138,23 +139,27 @@ impl Display for PrimaryExpr {
 impl PrimaryExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         match self {
             PrimaryExpr::Literal(literal) => {
                 Ok(xpath_item_set![XpathItem::AnyAtomicType(literal.value())])
             }
-            PrimaryExpr::VarRef(_) => todo!("PrimaryExpr::VarRef eval"),
+            PrimaryExpr::VarRef(e) => {
+                Err(ExpressionApplyError::unsupported("variable reference", e))
+            }
             PrimaryExpr::ParenthesizedExpr(expr) => expr.eval(context),
             PrimaryExpr::ContextItemExpr => {
                 // Context item expression is '.', which means select the current context item.
                 Ok(xpath_item_set![context.item.clone()])
             }
             PrimaryExpr::FunctionCall(expr) => expr.eval(context),
-            PrimaryExpr::FunctionItemExpr(_) => todo!("PrimaryExpr::FunctionItemExpr eval"),
-            PrimaryExpr::MapConstructor(_) => todo!("PrimaryExpr::MapConstructor eval"),
-            PrimaryExpr::ArrayConstructor(_) => todo!("PrimaryExpr::ArrayConstructor eval"),
-            PrimaryExpr::UnaryLookup(_) => todo!("PrimaryExpr::UnaryLookup eval"),
+            PrimaryExpr::FunctionItemExpr(e) => {
+                Err(ExpressionApplyError::unsupported("function item", e))
+            }
+            PrimaryExpr::MapConstructor(expr) => expr.eval(context),
+            PrimaryExpr::ArrayConstructor(expr) => expr.eval(context),
+            PrimaryExpr::UnaryLookup(expr) => expr.eval(context),
         }
     }
 }
@@ -179,13 +184,17 @@ fn function_item_expr(input: &str) -> Res<&str, FunctionItemExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FunctionItemExpr {
     NamedFunctionRef(NamedFunctionRef),
     InlineFunctionExpr(InlineFunctionExpr),
 }
 
 impl Display for FunctionItemExpr {
-    fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!("fmt FunctionItemExpr")
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FunctionItemExpr::NamedFunctionRef(x) => write!(f, "{}", x),
+            FunctionItemExpr::InlineFunctionExpr(x) => write!(f, "{}", x),
+        }
     }
 }
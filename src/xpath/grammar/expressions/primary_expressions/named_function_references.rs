@@ -11,7 +11,7 @@ use crate::xpath::grammar::{
     whitespace_recipes::ws,
 };
 
-pub fn named_function_ref(input: &str) -> Res<&str, NamedFunctionRef> {
+pub(crate) fn named_function_ref(input: &str) -> Res<&str, NamedFunctionRef> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-NamedFunctionRef
 
     context(
@@ -30,6 +30,7 @@ pub fn named_function_ref(input: &str) -> Res<&str, NamedFunctionRef> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NamedFunctionRef {
     pub name: EQName,
     pub number: u32,
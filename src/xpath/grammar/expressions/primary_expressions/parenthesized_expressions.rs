@@ -14,13 +14,14 @@ use crate::xpath::{
     ExpressionApplyError, XpathExpressionContext,
 };
 
-pub fn parenthesized_expr(input: &str) -> Res<&str, ParenthesizedExpr> {
+pub(crate) fn parenthesized_expr(input: &str) -> Res<&str, ParenthesizedExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-ParenthesizedExpr
     context("parenthesized_expr", ws((char('('), opt(expr), char(')'))))(input)
         .map(|(next_input, res)| (next_input, ParenthesizedExpr(res.1)))
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ParenthesizedExpr(pub Option<Expr>);
 
 impl Display for ParenthesizedExpr {
@@ -36,7 +37,7 @@ impl Display for ParenthesizedExpr {
 impl ParenthesizedExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         if let Some(expr) = &self.0 {
             expr.eval(context)
@@ -4,24 +4,21 @@ use std::fmt::Display;
 
 use nom::error::context;
 
-use crate::{
-    xpath::{
-        grammar::{
-            data_model::{AnyAtomicType, XpathItem},
-            expressions::common::{argument_list, ArgumentList},
-            recipes::Res,
-            types::{eq_name, EQName},
-            whitespace_recipes::ws,
-            xml_names::QName,
-            XpathItemTreeNode,
-        },
-        xpath_item_set::XpathItemSet,
-        ExpressionApplyError, XpathExpressionContext, XpathItemTree,
+use crate::xpath::{
+    grammar::{
+        data_model::{AnyAtomicType, Function, XpathItem},
+        expressions::common::{argument_list, Argument, ArgumentList},
+        recipes::Res,
+        types::{eq_name, EQName},
+        whitespace_recipes::ws,
+        XpathItemTreeNode,
     },
-    xpath_item_set,
+    limits::EvaluationBudget,
+    xpath_item_set::XpathItemSet,
+    ErrorCode, ExpressionApplyError, XpathExpressionContext, XpathItemTree,
 };
 
-pub fn function_call(input: &str) -> Res<&str, FunctionCall> {
+pub(crate) fn function_call(input: &str) -> Res<&str, FunctionCall> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-FunctionCall
 
     context("function_call", ws((eq_name, argument_list)))(input).map(|(next_input, res)| {
@@ -36,6 +33,7 @@ pub fn function_call(input: &str) -> Res<&str, FunctionCall> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FunctionCall {
     pub name: EQName,
     pub argument_list: ArgumentList,
@@ -50,123 +48,102 @@ impl Display for FunctionCall {
 impl FunctionCall {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
-        match &self.name {
-            EQName::QName(qname) => match qname {
-                QName::PrefixedName(prefixed_name) => {
-                    if prefixed_name.prefix == "fn" {
-                        // Root function selects the root node of the tree.
-                        if prefixed_name.local_part == "root" {
-                            return Ok(xpath_item_set![XpathItem::Node(context.item_tree.root())]);
-                        }
-                    }
-
-                    Err(ExpressionApplyError {
-                        msg: format!("Unknown function {}", self.name.to_string()),
-                    })
-                }
-                QName::UnprefixedName(unprefixed_name) => match unprefixed_name.as_str() {
-                    "contains" => func_contains(&self.argument_list, context),
-                    _ => Err(ExpressionApplyError {
-                        msg: format!("Unknown function {}", self.name.to_string()),
-                    }),
-                },
-            },
-            EQName::UriQualifiedName(_) => todo!("FunctionCall::eval UriQualifiedName"),
-        }
-    }
-}
-
-/// https://developer.mozilla.org/en-US/docs/Web/XPath/Functions/contains
-fn func_contains<'tree>(
-    argument_list: &ArgumentList,
-    context: &XpathExpressionContext<'tree>,
-) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
-    let arguments = &argument_list.0;
-    if arguments.len() != 2 {
-        return Err(ExpressionApplyError {
-            msg: format!(
-                "contains: function expects 2 arguments, got {}",
-                arguments.len()
-            ),
-        });
-    }
+        let static_context = context.dynamic_context.static_context();
+        let name = static_context.resolve_function_name(&self.name)?;
 
-    let arg1_set = arguments[0].eval(context)?;
-    if arg1_set.len() > 1 {
-        return Err(ExpressionApplyError {
-            msg: format!(
-                "contains: unexpected item set length {} for first argument",
-                arg1_set.len()
-            ),
-        });
-    }
+        // Evaluate the arguments before calling the function.
+        let mut arguments = Vec::new();
+        for argument in self.argument_list.0.iter() {
+            if let Argument::ArgumentPlaceHolder = argument {
+                return Err(ExpressionApplyError::with_code(
+                    ErrorCode::Unsupported,
+                    format!("Partial function application is not supported: {}", self),
+                ));
+            }
 
-    let haystack = if arg1_set.len() == 0 {
-        String::from("")
-    } else {
-        func_string(&arg1_set[0], &context.item_tree)
-    };
+            arguments.push(argument.eval(context)?);
+        }
 
-    let arg2_set = arguments[1].eval(context)?;
-    if arg2_set.len() > 1 {
-        return Err(ExpressionApplyError {
-            msg: format!(
-                "contains: unexpected item set length {} for second argument",
-                arg2_set.len()
-            ),
-        });
+        static_context
+            .functions()
+            .call(&name, context, arguments)
+            .map_err(|e| e.in_expression(self))
     }
-
-    let needle = if arg2_set.len() == 0 {
-        String::from("")
-    } else {
-        func_string(&arg2_set[0], &context.item_tree)
-    };
-
-    Ok(xpath_item_set![XpathItem::AnyAtomicType(
-        AnyAtomicType::Boolean(haystack.contains(&needle))
-    )])
 }
 
 /// https://www.w3.org/TR/2017/REC-xpath-31-20170321/#dt-atomization
 pub(crate) fn func_data<'tree>(
     set: &XpathItemSet<'tree>,
     item_tree: &'tree XpathItemTree,
-) -> Vec<AnyAtomicType> {
-    fn atomize<'tree>(item: &XpathItem, item_tree: &'tree XpathItemTree) -> AnyAtomicType {
-        match item {
+    budget: &EvaluationBudget,
+) -> Result<Vec<AnyAtomicType>, ExpressionApplyError> {
+    fn atomize(
+        item: &XpathItem,
+        item_tree: &XpathItemTree,
+        budget: &EvaluationBudget,
+        atomics: &mut Vec<AnyAtomicType>,
+    ) -> Result<(), ExpressionApplyError> {
+        let atomic = match item {
             XpathItem::Node(node) => match node {
-                XpathItemTreeNode::DocumentNode(_) => {
-                    AnyAtomicType::String(node.text_content(item_tree))
+                XpathItemTreeNode::DocumentNode(_) | XpathItemTreeNode::ElementNode(_) => {
+                    AnyAtomicType::String(descendant_text(node, item_tree, budget)?)
                 }
-                XpathItemTreeNode::ElementNode(_) => {
-                    AnyAtomicType::String(node.text_content(item_tree))
+                // Processing instructions do not carry their content yet.
+                XpathItemTreeNode::PINode(_) => AnyAtomicType::String(String::new()),
+                XpathItemTreeNode::CommentNode(comment) => {
+                    AnyAtomicType::String(comment.content.clone())
                 }
-                XpathItemTreeNode::PINode(_) => todo!("func_data PINode"),
-                XpathItemTreeNode::CommentNode(_) => todo!("func_data CommentNode"),
                 XpathItemTreeNode::TextNode(text) => AnyAtomicType::String(text.content.clone()),
                 &XpathItemTreeNode::AttributeNode(attribute) => {
                     AnyAtomicType::String(attribute.value.clone())
                 }
             },
-            XpathItem::Function(_) => todo!("func_data Function"),
+            // Arrays are atomized by atomizing their members.
+            XpathItem::Function(Function::Array(array)) => {
+                for member in array.iter() {
+                    for item in member.iter() {
+                        atomize(item, item_tree, budget, atomics)?;
+                    }
+                }
+                return Ok(());
+            }
+            XpathItem::Function(_) => {
+                return Err(ExpressionApplyError::with_code(
+                    ErrorCode::FOTY0013,
+                    String::from("function items cannot be atomized"),
+                ))
+            }
             XpathItem::AnyAtomicType(atomic) => atomic.clone(),
-        }
+        };
+
+        atomics.push(atomic);
+        Ok(())
     }
 
-    set.iter().map(|item| atomize(item, item_tree)).collect()
+    let mut atomics = Vec::new();
+    for item in set.iter() {
+        atomize(item, item_tree, budget, &mut atomics)?;
+    }
+
+    Ok(atomics)
 }
 
 /// https://www.w3.org/TR/xpath-functions-31/#func-string
-pub(crate) fn func_string<'tree>(item: &XpathItem, item_tree: &'tree XpathItemTree) -> String {
-    match item {
+pub(crate) fn func_string<'tree>(
+    item: &XpathItem,
+    item_tree: &'tree XpathItemTree,
+    budget: &EvaluationBudget,
+) -> Result<String, ExpressionApplyError> {
+    let string = match item {
         XpathItem::Node(node) => match node {
-            XpathItemTreeNode::DocumentNode(_) => node.text_content(item_tree),
-            XpathItemTreeNode::ElementNode(_) => node.text_content(item_tree),
-            XpathItemTreeNode::PINode(_) => todo!("func_string PINode"),
-            XpathItemTreeNode::CommentNode(_) => todo!("func_string CommentNode"),
+            XpathItemTreeNode::DocumentNode(_) | XpathItemTreeNode::ElementNode(_) => {
+                descendant_text(node, item_tree, budget)?
+            }
+            // Processing instructions do not carry their content yet.
+            XpathItemTreeNode::PINode(_) => String::new(),
+            XpathItemTreeNode::CommentNode(comment) => comment.content.clone(),
             XpathItemTreeNode::TextNode(text) => text.content.clone(),
             XpathItemTreeNode::AttributeNode(attribute) => attribute.value.clone(),
         },
@@ -177,7 +154,85 @@ pub(crate) fn func_string<'tree>(item: &XpathItem, item_tree: &'tree XpathItemTr
             AnyAtomicType::Double(n) => n.to_string(),
             AnyAtomicType::String(s) => s.clone(),
         },
-        XpathItem::Function(_) => todo!("func_string Function"),
+        XpathItem::Function(_) => {
+            return Err(ExpressionApplyError::with_code(
+                ErrorCode::FOTY0014,
+                String::from("function items do not have a string value"),
+            ))
+        }
+    };
+
+    Ok(string)
+}
+
+/// The text of the descendant text nodes of a document or element node, in document order.
+///
+/// Every descendant counts as a node visit, so string values of large subtrees
+/// are bounded by the evaluation's limits like axis steps are.
+fn descendant_text(
+    node: &XpathItemTreeNode,
+    item_tree: &XpathItemTree,
+    budget: &EvaluationBudget,
+) -> Result<String, ExpressionApplyError> {
+    fn push_text(
+        node: &XpathItemTreeNode,
+        item_tree: &XpathItemTree,
+        budget: &EvaluationBudget,
+        text: &mut String,
+    ) -> Result<(), ExpressionApplyError> {
+        for child in node.children(item_tree) {
+            budget.visit_node()?;
+            match child {
+                XpathItemTreeNode::TextNode(x) => text.push_str(&x.content),
+                XpathItemTreeNode::ElementNode(_) => push_text(child, item_tree, budget, text)?,
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    let mut text = String::new();
+    push_text(node, item_tree, budget, &mut text)?;
+    Ok(text)
+}
+
+/// Cast a string to a double, as `xs:double("...")` would.
+///
+/// <https://www.w3.org/TR/xpath-functions-31/#casting-to-double>
+///
+/// Surrounding whitespace is ignored. Returns `None` if the string is not a valid double.
+pub(crate) fn parse_double(s: &str) -> Option<f64> {
+    match s.trim() {
+        "INF" | "+INF" => Some(f64::INFINITY),
+        "-INF" => Some(f64::NEG_INFINITY),
+        "NaN" => Some(f64::NAN),
+        s if !s.is_empty()
+            && s.chars()
+                .all(|c| c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | 'e' | 'E')) =>
+        {
+            s.parse::<f64>().ok()
+        }
+        _ => None,
+    }
+}
+
+/// https://www.w3.org/TR/xpath-functions-31/#func-number
+///
+/// Values that cannot be cast to a double are `NaN`.
+pub(crate) fn func_number(value: &AnyAtomicType) -> f64 {
+    match value {
+        AnyAtomicType::Boolean(b) => {
+            if *b {
+                1.0
+            } else {
+                0.0
+            }
+        }
+        AnyAtomicType::Integer(n) => *n as f64,
+        AnyAtomicType::Float(n) => n.0 as f64,
+        AnyAtomicType::Double(n) => n.0,
+        AnyAtomicType::String(s) => parse_double(s).unwrap_or(f64::NAN),
     }
 }
 
@@ -10,14 +10,15 @@ use crate::xpath::grammar::{
     whitespace_recipes::ws,
 };
 
-pub fn var_ref(input: &str) -> Res<&str, VarRef> {
+pub(crate) fn var_ref(input: &str) -> Res<&str, VarRef> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-VarRef
     context("var_ref", ws((char('$'), var_name)))(input)
         .map(|(next_input, res)| (next_input, VarRef(res.1)))
 }
 
 #[derive(PartialEq, Debug, Clone)]
-pub struct VarRef(VarName);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VarRef(pub VarName);
 
 impl Display for VarRef {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -25,13 +26,14 @@ impl Display for VarRef {
     }
 }
 
-pub fn var_name(input: &str) -> Res<&str, VarName> {
+pub(crate) fn var_name(input: &str) -> Res<&str, VarName> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-VarName
     context("var_name", eq_name)(input).map(|(next_input, res)| (next_input, VarName(res)))
 }
 
 #[derive(PartialEq, Debug, Clone)]
-pub struct VarName(EQName);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VarName(pub EQName);
 
 impl Display for VarName {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
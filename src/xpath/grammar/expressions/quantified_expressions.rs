@@ -2,10 +2,9 @@
 
 use std::fmt::Display;
 
-use nom::{
-    branch::alt, bytes::complete::tag, character::complete::char, error::context, multi::many0,
-    sequence::tuple,
-};
+use nom::{branch::alt, character::complete::char, error::context, multi::many0, sequence::tuple};
+
+use crate::xpath::grammar::recipes::tag;
 
 use crate::xpath::grammar::{
     expressions::{expr_single, primary_expressions::variable_references::var_name},
@@ -15,7 +14,7 @@ use crate::xpath::grammar::{
 
 use super::{primary_expressions::variable_references::VarName, ExprSingle};
 
-pub fn quantified_expr(input: &str) -> Res<&str, QuantifiedExpr> {
+pub(crate) fn quantified_expr(input: &str) -> Res<&str, QuantifiedExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-QuantifiedExpr
 
     fn some_quantifier(input: &str) -> Res<&str, Quantifier> {
@@ -77,6 +76,7 @@ pub fn quantified_expr(input: &str) -> Res<&str, QuantifiedExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct QuantifiedExpr {
     pub quantifier: Quantifier,
     pub item: QuantifiedExprItem,
@@ -95,6 +95,7 @@ impl Display for QuantifiedExpr {
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Quantifier {
     Some,
     Every,
@@ -110,6 +111,7 @@ impl Display for Quantifier {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct QuantifiedExprItem {
     pub var: VarName,
     pub expr: ExprSingle,
@@ -3,10 +3,11 @@
 use std::fmt::Display;
 
 use nom::{
-    branch::alt, bytes::complete::tag, character::complete::multispace0, error::context,
-    multi::many0, sequence::tuple,
+    branch::alt, character::complete::multispace0, error::context, multi::many0, sequence::tuple,
 };
 
+use crate::xpath::grammar::recipes::tag;
+
 use crate::xpath::{
     grammar::{
         expressions::expressions_on_sequence_types::instance_of::{
@@ -19,7 +20,7 @@ use crate::xpath::{
     ExpressionApplyError, XpathExpressionContext,
 };
 
-pub fn union_expr(input: &str) -> Res<&str, UnionExpr> {
+pub(crate) fn union_expr(input: &str) -> Res<&str, UnionExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-UnionExpr
 
     fn union_operator_map(input: &str) -> Res<&str, UnionExprOperatorType> {
@@ -53,12 +54,14 @@ pub fn union_expr(input: &str) -> Res<&str, UnionExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UnionExpr {
     pub expr: IntersectExceptExpr,
     pub items: Vec<UnionExprPair>,
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UnionExprPair(UnionExprOperatorType, pub IntersectExceptExpr);
 
 impl Display for UnionExprPair {
@@ -70,7 +73,7 @@ impl Display for UnionExprPair {
 impl UnionExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         // Evaluate the first expression.
         let result = self.expr.eval(context)?;
@@ -81,11 +84,12 @@ impl UnionExpr {
         }
 
         // Otherwise, do the operation.
-        todo!("UnionExpr::eval union operator")
+        Err(ExpressionApplyError::unsupported("union expression", self))
     }
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum UnionExprOperatorType {
     Union,
     Bar,
@@ -142,6 +146,7 @@ fn intersect_except_expr(input: &str) -> Res<&str, IntersectExceptExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IntersectExceptExpr {
     pub expr: InstanceofExpr,
     pub items: Vec<IntersectExceptPair>,
@@ -161,7 +166,7 @@ impl Display for IntersectExceptExpr {
 impl IntersectExceptExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         // Evaluate the first expression.
         let result = self.expr.eval(context)?;
@@ -172,11 +177,15 @@ impl IntersectExceptExpr {
         }
 
         // Otherwise, do the operation.
-        todo!("IntersectExceptExpr::eval intersect or except operator")
+        Err(ExpressionApplyError::unsupported(
+            "intersect or except expression",
+            self,
+        ))
     }
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IntersectExceptPair(pub IntersectExceptType, pub InstanceofExpr);
 
 impl Display for IntersectExceptPair {
@@ -186,6 +195,7 @@ impl Display for IntersectExceptPair {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum IntersectExceptType {
     Intersect,
     Except,
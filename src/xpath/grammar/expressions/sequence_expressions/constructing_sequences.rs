@@ -2,7 +2,9 @@
 
 use std::fmt::Display;
 
-use nom::{bytes::complete::tag, combinator::opt, error::context, sequence::tuple};
+use nom::{combinator::opt, error::context, sequence::tuple};
+
+use crate::xpath::grammar::recipes::tag;
 
 use crate::xpath::{
     grammar::{
@@ -14,7 +16,7 @@ use crate::xpath::{
     ExpressionApplyError, XpathExpressionContext,
 };
 
-pub fn range_expr(input: &str) -> Res<&str, RangeExpr> {
+pub(crate) fn range_expr(input: &str) -> Res<&str, RangeExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-RangeExpr
 
     context(
@@ -33,6 +35,7 @@ pub fn range_expr(input: &str) -> Res<&str, RangeExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RangeExpr {
     pub expr: Box<AdditiveExpr>,
     pub to_expr: Option<Box<AdditiveExpr>>,
@@ -52,7 +55,7 @@ impl Display for RangeExpr {
 impl RangeExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         // Evaluate the first expression.
         let result = self.expr.eval(context)?;
@@ -63,7 +66,7 @@ impl RangeExpr {
         }
 
         // Otherwise, do the operation.
-        todo!("RangeExpr::eval range operator")
+        Err(ExpressionApplyError::unsupported("range expression", self))
     }
 }
 
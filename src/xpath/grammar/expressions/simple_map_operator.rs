@@ -12,7 +12,7 @@ use crate::xpath::{
 
 use super::path_expressions::{path_expr, PathExpr};
 
-pub fn simple_map_expr(input: &str) -> Res<&str, SimpleMapExpr> {
+pub(crate) fn simple_map_expr(input: &str) -> Res<&str, SimpleMapExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-SimpleMapExpr
 
     context(
@@ -27,6 +27,7 @@ pub fn simple_map_expr(input: &str) -> Res<&str, SimpleMapExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SimpleMapExpr {
     pub expr: PathExpr,
     pub items: Vec<PathExpr>,
@@ -46,7 +47,7 @@ impl Display for SimpleMapExpr {
 impl SimpleMapExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         // Evaluate the first expression.
         let result = self.expr.eval(context)?;
@@ -57,7 +58,10 @@ impl SimpleMapExpr {
         }
 
         // Otherwise, do the operation.
-        todo!("SimpleMapExpr::eval operator")
+        Err(ExpressionApplyError::unsupported(
+            "simple map expression",
+            self,
+        ))
     }
 }
 
@@ -2,17 +2,29 @@
 
 use std::fmt::Display;
 
-use nom::{bytes::complete::tag, error::context, multi::many0, sequence::tuple};
-
-use crate::xpath::{
-    grammar::{recipes::Res, whitespace_recipes::ws},
-    xpath_item_set::XpathItemSet,
-    ExpressionApplyError, XpathExpressionContext,
+use nom::{error::context, multi::many0, sequence::tuple};
+
+use crate::xpath::grammar::recipes::tag;
+
+use crate::{
+    xpath::{
+        grammar::{
+            data_model::{AnyAtomicType, XpathItem},
+            recipes::Res,
+            whitespace_recipes::ws,
+        },
+        xpath_item_set::XpathItemSet,
+        ErrorCode, ExpressionApplyError, XpathExpressionContext,
+    },
+    xpath_item_set,
 };
 
-use super::sequence_expressions::constructing_sequences::{range_expr, RangeExpr};
+use super::{
+    primary_expressions::static_function_calls::func_string,
+    sequence_expressions::constructing_sequences::{range_expr, RangeExpr},
+};
 
-pub fn string_concat_expr(input: &str) -> Res<&str, StringConcatExpr> {
+pub(crate) fn string_concat_expr(input: &str) -> Res<&str, StringConcatExpr> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-StringConcatExpr
 
     context(
@@ -26,6 +38,7 @@ pub fn string_concat_expr(input: &str) -> Res<&str, StringConcatExpr> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StringConcatExpr {
     pub expr: RangeExpr,
     pub items: Vec<RangeExpr>,
@@ -45,7 +58,7 @@ impl Display for StringConcatExpr {
 impl StringConcatExpr {
     pub(crate) fn eval<'tree>(
         &self,
-        context: &XpathExpressionContext<'tree>,
+        context: &XpathExpressionContext<'tree, '_>,
     ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
         // Evaluate the first expression.
         let result = self.expr.eval(context)?;
@@ -55,13 +68,44 @@ impl StringConcatExpr {
             return Ok(result);
         }
 
-        // Otherwise, do the operation.
-        todo!("StringConcatExpr::eval concat operator")
+        // Otherwise, join the string values of the operands.
+        let mut value = operand_string(&result, context).map_err(|e| e.in_expression(self))?;
+        for item in self.items.iter() {
+            let result = item.eval(context)?;
+            value.push_str(&operand_string(&result, context).map_err(|e| e.in_expression(self))?);
+        }
+
+        Ok(xpath_item_set![XpathItem::AnyAtomicType(
+            AnyAtomicType::String(value)
+        )])
+    }
+}
+
+/// Get the string value of an operand, which is the zero-length string for the empty sequence.
+fn operand_string<'tree>(
+    operand: &XpathItemSet<'tree>,
+    context: &XpathExpressionContext<'tree, '_>,
+) -> Result<String, ExpressionApplyError> {
+    match operand.len() {
+        0 => Ok(String::new()),
+        1 => func_string(&operand[0], context.item_tree, context.budget),
+        len => Err(ExpressionApplyError::with_code(
+            ErrorCode::XPTY0004,
+            format!(
+                "string concatenation expects at most one item per operand, got {}",
+                len
+            ),
+        )),
     }
 }
 
 #[cfg(test)]
 mod test {
+    use crate::{
+        html,
+        xpath::{self, XpathItemTree},
+    };
+
     use super::*;
 
     #[test]
@@ -89,4 +133,31 @@ mod test {
         assert_eq!(next_input, "");
         assert_eq!(res.to_string(), "a || b || c");
     }
+
+    #[test]
+    fn string_concat_expr_should_join_string_values() {
+        // arrange
+        let tree = XpathItemTree::from(&html::parse("<html><p>b</p></html>").unwrap());
+        let xpath = xpath::parse("'a' || //p || () || 1").unwrap();
+
+        // act
+        let items = xpath.apply(&tree).unwrap();
+
+        // assert
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].string_value(&tree), "ab1");
+    }
+
+    #[test]
+    fn string_concat_expr_should_fail_for_many_items() {
+        // arrange
+        let tree = XpathItemTree::from(&html::parse("<html><p>b</p><p>c</p></html>").unwrap());
+        let xpath = xpath::parse("'a' || //p").unwrap();
+
+        // act
+        let err = xpath.apply(&tree).unwrap_err();
+
+        // assert
+        assert_eq!(err.code(), &ErrorCode::XPTY0004);
+    }
 }
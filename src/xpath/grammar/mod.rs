@@ -5,25 +5,38 @@
 // https://github.com/rust-bakery/nom/blob/main/doc/making_a_new_parser_from_scratch.md
 
 pub mod data_model;
-mod expressions;
-mod recipes;
-mod terminal_symbols;
-mod types;
+mod element_index;
+// The syntax tree types follow the productions of the XPath grammar,
+// and each module links to the part of the specification it implements.
+#[allow(missing_docs)]
+pub mod expressions;
+pub(crate) mod recipes;
+#[allow(missing_docs)]
+pub mod terminal_symbols;
+#[allow(missing_docs)]
+pub mod types;
 mod whitespace_recipes;
-mod xml_names;
+#[allow(missing_docs)]
+pub mod xml_names;
 
-use std::iter;
+use std::{iter, sync::Mutex};
 
+pub(crate) use element_index::ElementCondition;
 use enum_extract_macro::EnumExtract;
 pub(crate) use expressions::xpath;
 pub use expressions::Xpath;
 
 use indextree::{Arena, NodeId};
+use once_cell::sync::OnceCell;
 
+use self::element_index::ElementIndex;
 use crate::{
     html::{DocumentNode, HtmlDocument, HtmlNode},
-    xpath::grammar::data_model::{
-        AttributeNode, CommentNode, ElementNode, PINode, TextNode, XpathDocumentNode,
+    xpath::{
+        grammar::data_model::{
+            AttributeNode, CommentNode, ElementNode, PINode, TextNode, XpathDocumentNode,
+        },
+        uri,
     },
 };
 
@@ -82,17 +95,33 @@ impl XpathItemTreeNode {
     ///
     /// The parent of the element if it exists, or `None` if it does not.
     pub fn parent<'tree>(&self, tree: &'tree XpathItemTree) -> Option<&'tree XpathItemTreeNode> {
-        let id = match self {
+        self.id().and_then(|id| tree.parent_of(id))
+    }
+
+    /// Get the ID of the node in its tree.
+    ///
+    /// Comments and processing instructions are not kept in the tree, and have no ID.
+    pub(crate) fn id(&self) -> Option<TreeNodeId> {
+        match self {
+            XpathItemTreeNode::DocumentNode(d) => Some(d.id()),
             XpathItemTreeNode::ElementNode(e) => Some(e.id()),
             XpathItemTreeNode::TextNode(t) => Some(t.id()),
             XpathItemTreeNode::AttributeNode(a) => Some(a.id()),
-            _ => None,
-        };
+            XpathItemTreeNode::PINode(_) | XpathItemTreeNode::CommentNode(_) => None,
+        }
+    }
 
-        id.and_then(|id| {
-            let parent_id = tree.arena.get(id).unwrap().parent()?;
-            Some(tree.get(parent_id))
-        })
+    /// Set the ID of the node in its tree.
+    ///
+    /// Does nothing for comments and processing instructions, which have no ID.
+    pub(crate) fn set_id(&mut self, id: TreeNodeId) {
+        match self {
+            XpathItemTreeNode::DocumentNode(d) => d.set_id(id),
+            XpathItemTreeNode::ElementNode(e) => e.set_id(id),
+            XpathItemTreeNode::TextNode(t) => t.set_id(id),
+            XpathItemTreeNode::AttributeNode(a) => a.set_id(id),
+            XpathItemTreeNode::PINode(_) | XpathItemTreeNode::CommentNode(_) => {}
+        }
     }
 
     /// Get an iterator over all text contained in this node and its descendants.
@@ -182,6 +211,168 @@ impl<'a> Iterator for TextIter<'a> {
     }
 }
 
+/// Identifies a node in an [`XpathItemTree`].
+///
+/// A tree can hold more than one document, such as documents loaded while evaluating an expression,
+/// so the ID includes the document the node belongs to.
+/// Document 0 is the document the tree was created from.
+#[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Hash, Clone, Copy)]
+pub(crate) struct TreeNodeId {
+    document: usize,
+    node: NodeId,
+}
+
+impl TreeNodeId {
+    /// A token that is unique among the nodes of the tree, and the same every time it is
+    /// generated for the node.
+    pub(crate) fn token(&self) -> String {
+        format!("d{}n{}", self.document, usize::from(self.node))
+    }
+}
+
+/// The nodes of a single document.
+pub(crate) struct Document {
+    /// The index tree that stores the nodes.
+    arena: Arena<XpathItemTreeNode>,
+
+    /// The document node.
+    root_node: NodeId,
+
+    /// The URI the document was loaded from.
+    uri: Option<String>,
+
+    /// The base URI of the document, found when first needed.
+    base_uri: OnceCell<Option<String>>,
+
+    /// The elements of the document keyed by their name and attributes, indexed when first needed.
+    index: OnceCell<ElementIndex>,
+}
+
+impl Document {
+    /// Get the elements of the document in document order.
+    fn elements(&self) -> impl Iterator<Item = &ElementNode> {
+        self.root_node.descendants(&self.arena).filter_map(|node| {
+            match self.arena.get(node).map(|node| node.get()) {
+                Some(XpathItemTreeNode::ElementNode(element)) => Some(element),
+                _ => None,
+            }
+        })
+    }
+}
+
+/// Builds a [`Document`] one node at a time.
+pub(crate) struct DocumentBuilder {
+    document: usize,
+    arena: Arena<XpathItemTreeNode>,
+    root_node: NodeId,
+}
+
+impl DocumentBuilder {
+    /// Create a builder for the document with the given index in its tree.
+    pub(crate) fn new(document: usize) -> Self {
+        let mut arena = Arena::new();
+        let root_node = arena.new_node(XpathItemTreeNode::DocumentNode(XpathDocumentNode::new()));
+
+        let mut builder = Self {
+            document,
+            arena,
+            root_node,
+        };
+        let id = builder.tree_node_id(root_node);
+        builder
+            .node_mut(root_node)
+            .as_document_node_mut()
+            .unwrap()
+            .set_id(id);
+
+        builder
+    }
+
+    /// The document node, which is the parent of the top level nodes.
+    pub(crate) fn root(&self) -> NodeId {
+        self.root_node
+    }
+
+    /// Append an element to the given parent.
+    pub(crate) fn append_element(&mut self, parent: NodeId, name: String) -> NodeId {
+        let node_id = self.append(
+            parent,
+            XpathItemTreeNode::ElementNode(ElementNode::new(name)),
+        );
+        let id = self.tree_node_id(node_id);
+        self.node_mut(node_id)
+            .as_element_node_mut()
+            .unwrap()
+            .set_id(id);
+
+        node_id
+    }
+
+    /// Append an attribute to the given element.
+    pub(crate) fn append_attribute(
+        &mut self,
+        parent: NodeId,
+        name: String,
+        value: String,
+    ) -> NodeId {
+        let node_id = self.append(
+            parent,
+            XpathItemTreeNode::AttributeNode(AttributeNode::new(name, value)),
+        );
+        let id = self.tree_node_id(node_id);
+        self.node_mut(node_id)
+            .as_attribute_node_mut()
+            .unwrap()
+            .set_id(id);
+
+        node_id
+    }
+
+    /// Append a text node to the given parent.
+    pub(crate) fn append_text(&mut self, parent: NodeId, content: String) -> NodeId {
+        let only_whitespace = content.trim().is_empty();
+        let node_id = self.append(
+            parent,
+            XpathItemTreeNode::TextNode(TextNode::new(content, only_whitespace)),
+        );
+        let id = self.tree_node_id(node_id);
+        self.node_mut(node_id)
+            .as_text_node_mut()
+            .unwrap()
+            .set_id(id);
+
+        node_id
+    }
+
+    /// Finish building the document.
+    pub(crate) fn build(self) -> Document {
+        Document {
+            arena: self.arena,
+            root_node: self.root_node,
+            uri: None,
+            base_uri: OnceCell::new(),
+            index: OnceCell::new(),
+        }
+    }
+
+    fn append(&mut self, parent: NodeId, node: XpathItemTreeNode) -> NodeId {
+        let node_id = self.arena.new_node(node);
+        parent.append(node_id, &mut self.arena);
+        node_id
+    }
+
+    fn node_mut(&mut self, node_id: NodeId) -> &mut XpathItemTreeNode {
+        self.arena.get_mut(node_id).unwrap().get_mut()
+    }
+
+    fn tree_node_id(&self, node: NodeId) -> TreeNodeId {
+        TreeNodeId {
+            document: self.document,
+            node,
+        }
+    }
+}
+
 /// A tree of [`XpathItemTreeNode`]s.
 ///
 /// This tree can be searched using an [`Xpath`] expression.
@@ -189,6 +380,9 @@ impl<'a> Iterator for TextIter<'a> {
 /// This tree is created from an [`HtmlDocument`],
 /// and bridges the gap between the [html](crate::html) and [xpath](crate::xpath) modules.
 ///
+/// Documents created while applying an expression, such as by `fn:json-to-xml`,
+/// are added to the tree so that their nodes live as long as the tree does.
+///
 /// # Example
 ///
 /// ```rust
@@ -201,125 +395,286 @@ impl<'a> Iterator for TextIter<'a> {
 /// let xpath_item_tree = XpathItemTree::from(&document);
 /// ```
 pub struct XpathItemTree {
-    /// The index tree that stores the nodes.
-    arena: Arena<XpathItemTreeNode>,
+    /// The document the tree was created from.
+    document: Document,
 
-    /// The root node of the document.
-    root_node: NodeId,
+    /// Documents added while applying expressions.
+    ///
+    /// Document `n` is stored at index `n - 1`.
+    /// Documents are only removed through a mutable reference,
+    /// so references to their nodes stay valid.
+    documents: boxcar::Vec<Document>,
+
+    /// Held while a document is built, so that the index it will have is known.
+    adding_document: Mutex<()>,
 }
 
 impl XpathItemTree {
-    fn get_index_node(&self, id: NodeId) -> &indextree::Node<XpathItemTreeNode> {
-        self.arena
-            .get(id)
+    fn get_document(&self, document: usize) -> &Document {
+        match document {
+            0 => &self.document,
+            n => self
+                .documents
+                .get(n - 1)
+                .expect("xpath item document missing from tree"),
+        }
+    }
+
+    fn get_index_node(&self, id: TreeNodeId) -> &indextree::Node<XpathItemTreeNode> {
+        self.get_document(id.document)
+            .arena
+            .get(id.node)
             .expect("xpath item node missing from tree")
     }
 
-    fn get(&self, id: NodeId) -> &XpathItemTreeNode {
+    fn get(&self, id: TreeNodeId) -> &XpathItemTreeNode {
         let indextree_node = self.get_index_node(id);
 
         indextree_node.get()
     }
 
-    fn root(&self) -> &XpathItemTreeNode {
-        self.get(self.root_node)
+    pub(crate) fn root(&self) -> &XpathItemTreeNode {
+        self.document_root_of(0)
+    }
+
+    /// Get the document node of the document containing the given node.
+    pub(crate) fn document_root(&self, id: TreeNodeId) -> &XpathItemTreeNode {
+        self.document_root_of(id.document)
+    }
+
+    /// Get the children of the node with the given ID.
+    pub(crate) fn children_of(&self, id: TreeNodeId) -> impl Iterator<Item = &XpathItemTreeNode> {
+        id.node
+            .children(&self.get_document(id.document).arena)
+            .map(move |node| {
+                self.get(TreeNodeId {
+                    document: id.document,
+                    node,
+                })
+            })
+    }
+
+    /// Get the parent of the node with the given ID.
+    pub(crate) fn parent_of(&self, id: TreeNodeId) -> Option<&XpathItemTreeNode> {
+        let parent = self.get_index_node(id).parent()?;
+        Some(self.get(TreeNodeId {
+            document: id.document,
+            node: parent,
+        }))
+    }
+
+    /// Add a document to the tree, and get its index.
+    ///
+    /// # Arguments
+    ///
+    /// * `build` - Builds the document given the index it will have in the tree.
+    pub(crate) fn add_document<E>(
+        &self,
+        build: impl FnOnce(usize) -> Result<Document, E>,
+    ) -> Result<usize, E> {
+        let _adding_document = self
+            .adding_document
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+
+        // Only this thread can push while the lock is held, so the next index is known.
+        let document = self.documents.count() + 1;
+        let index = self.documents.push(build(document)?);
+        debug_assert_eq!(index + 1, document);
+
+        Ok(document)
+    }
+
+    /// Drop the documents added while applying expressions,
+    /// such as those loaded by `fn:doc` or built by `fn:json-to-xml`.
+    ///
+    /// Every evaluation adds the documents it needs again,
+    /// so call this between evaluations to keep them from adding up.
+    pub fn clear_documents(&mut self) {
+        self.documents.clear();
+    }
+
+    /// Get the document node of the document with the given index.
+    pub(crate) fn document_root_of(&self, document: usize) -> &XpathItemTreeNode {
+        self.get(TreeNodeId {
+            document,
+            node: self.get_document(document).root_node,
+        })
+    }
+
+    /// Turn the tree into the document with the given index in another tree.
+    ///
+    /// Documents that were added to this tree are dropped.
+    pub(crate) fn into_document(self, document: usize) -> Document {
+        let mut main = self.document;
+        for node in main.arena.iter_mut().filter(|node| !node.is_removed()) {
+            let node = node.get_mut();
+            if let Some(id) = node.id() {
+                node.set_id(TreeNodeId {
+                    document,
+                    node: id.node,
+                });
+            }
+        }
+
+        main
+    }
+
+    /// The URI of the document the tree was created from, if it is known.
+    pub fn document_uri(&self) -> Option<&str> {
+        self.document.uri.as_deref()
+    }
+
+    /// Set the URI of the document the tree was created from, such as the URL of the page.
+    ///
+    /// Relative URIs in the document, such as the `href` of links,
+    /// are resolved against this URI and any `<base href>` in the document.
+    pub fn set_document_uri(&mut self, uri: &str) {
+        self.document.uri = Some(uri.to_string());
+        self.document.base_uri = OnceCell::new();
+    }
+
+    /// Get the URI of the document containing the node with the given ID.
+    pub(crate) fn document_uri_of(&self, id: TreeNodeId) -> Option<&str> {
+        self.get_document(id.document).uri.as_deref()
+    }
+
+    /// Get the base URI of the document containing the node with the given ID.
+    ///
+    /// This is the `href` of the first `<base>` element with one, resolved against the
+    /// document URI, or the document URI if there is no such element.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/urls-and-fetching.html#document-base-url>
+    pub(crate) fn base_uri_of(&self, id: TreeNodeId) -> Option<&str> {
+        let document = self.get_document(id.document);
+
+        document
+            .base_uri
+            .get_or_init(|| {
+                let href = document.elements().find_map(|element| {
+                    if element.name.eq_ignore_ascii_case("base") {
+                        element.get_attribute(self, "href")
+                    } else {
+                        None
+                    }
+                });
+
+                match (href, &document.uri) {
+                    (Some(href), Some(uri)) => uri::resolve(href, uri).ok().or(Some(uri.clone())),
+                    (Some(href), None) => Some(href.trim().to_string()),
+                    (None, uri) => uri.clone(),
+                }
+            })
+            .as_deref()
+    }
+
+    /// Get the element with the given `id` attribute in the document containing the node
+    /// with the given ID.
+    ///
+    /// If more than one element has the `id`, the first in document order is returned,
+    /// as it is by `getElementById` in the browser.
+    pub(crate) fn element_with_id(
+        &self,
+        id: TreeNodeId,
+        value: &str,
+    ) -> Option<&XpathItemTreeNode> {
+        self.find_elements(id, &[ElementCondition::Id(value)])
+            .into_iter()
+            .next()
+    }
+
+    /// Get the elements that meet every condition in the document containing the node
+    /// with the given ID, in document order.
+    ///
+    /// The elements of a document are indexed the first time they are looked up.
+    pub(crate) fn find_elements(
+        &self,
+        id: TreeNodeId,
+        conditions: &[ElementCondition],
+    ) -> Vec<&XpathItemTreeNode> {
+        let document = self.get_document(id.document);
+        let index = document
+            .index
+            .get_or_init(|| ElementIndex::new(document.elements(), self));
+
+        index
+            .lookup(conditions)
+            .into_iter()
+            .map(|node| {
+                self.get(TreeNodeId {
+                    document: id.document,
+                    node,
+                })
+            })
+            .collect()
     }
 
     /// Get an iterator over all nodes in the tree.
     pub fn iter(&self) -> impl Iterator<Item = &XpathItemTreeNode> {
-        self.arena.iter().map(|node| {
-            let id = self.arena.get_node_id(node).unwrap();
-            self.get(id)
-        })
+        self.document.arena.iter().map(|node| node.get())
+    }
+}
+
+impl From<Document> for XpathItemTree {
+    fn from(document: Document) -> Self {
+        XpathItemTree {
+            document,
+            documents: boxcar::Vec::new(),
+            adding_document: Mutex::new(()),
+        }
     }
 }
 
 impl From<&HtmlDocument> for XpathItemTree {
     fn from(html_document: &HtmlDocument) -> Self {
-        fn internal_from(
-            current_html_node: &DocumentNode,
-            html_document: &HtmlDocument,
-            item_arena: &mut Arena<XpathItemTreeNode>,
-        ) -> NodeId {
-            let html_node = html_document
-                .get_html_node(&current_html_node)
-                .expect("html document missing expected node");
-
-            let root_item_id = match html_node {
-                HtmlNode::Tag(tag) => {
-                    let node =
-                        XpathItemTreeNode::ElementNode(ElementNode::new(tag.name.to_string()));
-
-                    let item_id = item_arena.new_node(node);
-                    item_arena
-                        .get_mut(item_id)
-                        .unwrap()
-                        .get_mut()
-                        .as_element_node_mut()
-                        .unwrap()
-                        .set_id(item_id);
-
-                    let attributes: Vec<AttributeNode> = tag
-                        .attributes
-                        .iter()
-                        .map(|(name, value)| {
-                            AttributeNode::new(name.to_string(), value.to_string())
-                        })
-                        .collect();
-
-                    for attribute in attributes {
-                        let attribute_node = XpathItemTreeNode::AttributeNode(attribute);
-                        let attribute_id = item_arena.new_node(attribute_node);
-
-                        item_id.append(attribute_id, item_arena);
-
-                        item_arena
-                            .get_mut(attribute_id)
-                            .unwrap()
-                            .get_mut()
-                            .as_attribute_node_mut()
-                            .unwrap()
-                            .set_id(attribute_id);
-                    }
+        XpathItemTree::from(html_to_document(html_document, 0))
+    }
+}
 
-                    item_id
+/// Build the [`Document`] with the given index in its tree from an [`HtmlDocument`].
+pub(crate) fn html_to_document(html_document: &HtmlDocument, document: usize) -> Document {
+    fn internal_from(
+        current_html_node: &DocumentNode,
+        html_document: &HtmlDocument,
+        builder: &mut DocumentBuilder,
+        parent: NodeId,
+    ) {
+        let html_node = html_document
+            .get_html_node(current_html_node)
+            .expect("html document missing expected node");
+
+        let item_id = match html_node {
+            HtmlNode::Tag(tag) => {
+                let item_id = builder.append_element(parent, tag.name.to_string());
+
+                for (name, value) in tag.attributes.iter() {
+                    builder.append_attribute(item_id, name.to_string(), value.to_string());
                 }
-                HtmlNode::Text(text) => {
-                    let node = XpathItemTreeNode::TextNode(TextNode::new(
-                        text.value.to_string(),
-                        text.only_whitespace,
-                    ));
-
-                    let item_id = item_arena.new_node(node);
-                    item_arena
-                        .get_mut(item_id)
-                        .unwrap()
-                        .get_mut()
-                        .as_text_node_mut()
-                        .unwrap()
-                        .set_id(item_id);
-
-                    item_id
-                }
-            };
 
-            for child in current_html_node.children(&html_document) {
-                let child_node = internal_from(&child, html_document, item_arena);
-                root_item_id.append(child_node, item_arena);
+                item_id
             }
+            HtmlNode::Text(text) => {
+                let item_id = builder.append_text(parent, text.value.to_string());
 
-            root_item_id
-        }
+                // The HTML parser knows best which text is only whitespace.
+                builder
+                    .node_mut(item_id)
+                    .as_text_node_mut()
+                    .unwrap()
+                    .only_whitespace = text.only_whitespace;
 
-        let mut item_arena = Arena::<XpathItemTreeNode>::new();
-        let root_node_id =
-            item_arena.new_node(XpathItemTreeNode::DocumentNode(XpathDocumentNode {}));
-        let first_child = internal_from(&html_document.root_node, &html_document, &mut item_arena);
-        root_node_id.append(first_child, &mut item_arena);
+                item_id
+            }
+        };
 
-        XpathItemTree {
-            arena: item_arena,
-            root_node: root_node_id,
+        for child in current_html_node.children(html_document) {
+            internal_from(&child, html_document, builder, item_id);
         }
     }
+
+    let mut builder = DocumentBuilder::new(document);
+    let root = builder.root();
+    internal_from(&html_document.root_node, html_document, &mut builder, root);
+
+    builder.build()
 }
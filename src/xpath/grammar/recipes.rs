@@ -1,11 +1,192 @@
-use std::ops::RangeFrom;
+use std::{
+    cell::{Cell, RefCell},
+    cmp::Ordering,
+    ops::RangeFrom,
+};
 
 use nom::{
-    error::{ErrorKind, ParseError, VerboseError},
+    error::{ContextError, ErrorKind, FromExternalError, ParseError},
     AsChar, Err as NomErr, IResult, InputIter, Offset, Parser, Slice,
 };
 
-pub type Res<T, U> = IResult<T, U, VerboseError<T>>;
+pub type Res<T, U> = IResult<T, U, GrammarError<T>>;
+
+/// The error produced by the grammar parsers.
+///
+/// Unlike nom's `VerboseError`, this keeps the failure that got farthest into the input
+/// along with every token that was expected there,
+/// which is usually the most useful place to point a user at.
+#[derive(PartialEq, Debug, Clone)]
+pub struct GrammarError<I> {
+    /// The remaining input where parsing failed.
+    pub input: I,
+
+    /// The tokens that would have allowed parsing to continue.
+    pub expected: Vec<Expected>,
+
+    /// The labels given to [nom::error::context] by the parsers that failed, innermost first.
+    pub contexts: Vec<&'static str>,
+
+    /// The input where the outermost of `contexts` started parsing.
+    pub context_start: I,
+}
+
+/// A token a parser expected to find.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Expected {
+    Char(char),
+    Tag(&'static str),
+    Kind(ErrorKind),
+}
+
+impl<'a> GrammarError<&'a str> {
+    fn new(input: &'a str, expected: Expected) -> Self {
+        let error = Self {
+            input,
+            expected: vec![expected],
+            contexts: Vec::new(),
+            context_start: input,
+        };
+        record_failure(&error);
+        error
+    }
+}
+
+thread_local! {
+    static IS_TRACKING_FAILURES: Cell<bool> = const { Cell::new(false) };
+
+    /// The failures at the farthest position seen while tracking,
+    /// with their input stored as the length of the remaining input.
+    static FARTHEST_FAILURES: RefCell<Vec<GrammarError<usize>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Run `f`, keeping track of the farthest any parser got before failing.
+///
+/// Combinators like `opt` and `many0` discard the errors of the parsers they wrap,
+/// so the error a parser returns (or the input it leaves unparsed) is often not where the
+/// expression actually went wrong. The farthest failure usually is.
+///
+/// Returns the result of `f` and the farthest failure, with its inputs as lengths of the remaining input.
+/// Of the failures at that position, only the ones whose contexts consumed the most input are kept.
+/// Those are the parsers that committed to a construct and could not finish it;
+/// the others are optional parsers that never got started, and would only add noise.
+pub(crate) fn track_farthest_failure<T>(f: impl FnOnce() -> T) -> (T, Option<GrammarError<usize>>) {
+    FARTHEST_FAILURES.with(|failures| failures.borrow_mut().clear());
+    IS_TRACKING_FAILURES.with(|is_tracking| is_tracking.set(true));
+    let result = f();
+    IS_TRACKING_FAILURES.with(|is_tracking| is_tracking.set(false));
+
+    let failures = FARTHEST_FAILURES.with(|failures| failures.take());
+    let max_start = failures.iter().map(|failure| failure.context_start).max();
+    let farthest = failures
+        .into_iter()
+        .filter(|failure| Some(failure.context_start) == max_start)
+        .reduce(|mut farthest, failure| {
+            for expected in failure.expected {
+                if !farthest.expected.contains(&expected) {
+                    farthest.expected.push(expected);
+                }
+            }
+            farthest
+        });
+
+    (result, farthest)
+}
+
+fn record_failure(error: &GrammarError<&str>) {
+    if !IS_TRACKING_FAILURES.with(|is_tracking| is_tracking.get()) {
+        return;
+    }
+
+    FARTHEST_FAILURES.with(|failures| {
+        let mut failures = failures.borrow_mut();
+        let remaining = error.input.len();
+
+        match failures
+            .first()
+            .map(|failure| failure.input.cmp(&remaining))
+        {
+            Some(Ordering::Less) => return,
+            Some(Ordering::Greater) => failures.clear(),
+            Some(Ordering::Equal) | None => {}
+        }
+
+        // An error that was just given a context replaces its earlier record.
+        let parent_contexts = &error.contexts[..error.contexts.len().saturating_sub(1)];
+        failures.retain(|failure| {
+            failure.expected != error.expected || failure.contexts != parent_contexts
+        });
+
+        failures.push(GrammarError {
+            input: remaining,
+            expected: error.expected.clone(),
+            contexts: error.contexts.clone(),
+            context_start: error.context_start.len(),
+        });
+    });
+}
+
+impl<'a> ParseError<&'a str> for GrammarError<&'a str> {
+    fn from_error_kind(input: &'a str, kind: ErrorKind) -> Self {
+        Self::new(input, Expected::Kind(kind))
+    }
+
+    fn append(_input: &'a str, _kind: ErrorKind, other: Self) -> Self {
+        // The combinator that failed adds nothing a user can act on; keep the cause.
+        other
+    }
+
+    fn from_char(input: &'a str, c: char) -> Self {
+        Self::new(input, Expected::Char(c))
+    }
+
+    fn or(self, other: Self) -> Self {
+        // Less remaining input means the parser got farther.
+        match self.input.len().cmp(&other.input.len()) {
+            Ordering::Less => self,
+            Ordering::Greater => other,
+            Ordering::Equal => {
+                let mut merged = self;
+                for expected in other.expected {
+                    if !merged.expected.contains(&expected) {
+                        merged.expected.push(expected);
+                    }
+                }
+                if merged.contexts.is_empty() {
+                    merged.contexts = other.contexts;
+                    merged.context_start = other.context_start;
+                }
+
+                merged
+            }
+        }
+    }
+}
+
+impl<'a> ContextError<&'a str> for GrammarError<&'a str> {
+    fn add_context(input: &'a str, ctx: &'static str, mut other: Self) -> Self {
+        other.contexts.push(ctx);
+        other.context_start = input;
+        record_failure(&other);
+        other
+    }
+}
+
+impl<'a, E> FromExternalError<&'a str, E> for GrammarError<&'a str> {
+    fn from_external_error(input: &'a str, kind: ErrorKind, _e: E) -> Self {
+        Self::from_error_kind(input, kind)
+    }
+}
+
+/// Recognizes a literal string.
+///
+/// Works like nom's `tag`, but reports the literal as the expected token when it does not match.
+pub fn tag<'a>(tag: &'static str) -> impl Fn(&'a str) -> Res<&'a str, &'a str> {
+    move |input: &'a str| match input.strip_prefix(tag) {
+        Some(next_input) => Ok((next_input, &input[..tag.len()])),
+        None => Err(NomErr::Error(GrammarError::new(input, Expected::Tag(tag)))),
+    }
+}
 
 pub fn char_if<I, Error: ParseError<I>>(
     f: fn(char) -> bool,
@@ -98,6 +279,10 @@ fn find_max<Input: Clone + Offset, Output, Error: ParseError<Input>>(
     input: Input,
 ) -> Result<(Input, Output), NomErr<Error>> {
     match (res0, res1) {
+        // A failure means the input cannot be parsed at all, whatever the other parser matched.
+        (Err(NomErr::Failure(err)), _) | (_, Err(NomErr::Failure(err))) => {
+            Err(NomErr::Failure(err))
+        }
         (Ok(res0), Ok(res1)) => {
             let length0 = input.offset(&res0.0);
             let length1 = input.offset(&res1.0);
@@ -202,8 +387,6 @@ impl<
 
 #[cfg(test)]
 mod tests {
-    use nom::bytes::complete::tag;
-
     use super::*;
 
     #[test]
@@ -212,8 +395,7 @@ mod tests {
         let input = "hello world";
 
         // act
-        let result: Result<(&str, &str), NomErr<VerboseError<&str>>> =
-            max((tag("hello"), tag("hello world")))(input);
+        let result: Res<&str, &str> = max((tag("hello"), tag("hello world")))(input);
         let (next_input, res) = result.unwrap();
 
         // assert
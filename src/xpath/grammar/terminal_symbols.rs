@@ -1,23 +1,26 @@
 //! <https://www.w3.org/TR/2017/REC-xpath-31-20170321/#terminal-symbols>
 
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take_till1},
+    bytes::complete::take_till1,
     character::complete::{char, digit0, digit1},
     combinator::{map_res, opt, peek, recognize},
-    error::context,
+    error::{context, ErrorKind, ParseError},
     multi::{fold_many0, many0, many1},
     sequence::{terminated, tuple},
+    Err as NomErr,
 };
 
+use crate::xpath::grammar::recipes::tag;
+
 use super::{
-    recipes::{not_brace, not_quote, not_single_quote, Res},
+    recipes::{not_brace, not_quote, not_single_quote, GrammarError, Res},
     xml_names::nc_name,
 };
 
-pub fn symbol_separator(input: &str) -> Res<&str, ()> {
+pub(crate) fn symbol_separator(input: &str) -> Res<&str, ()> {
     //https://www.w3.org/TR/2017/REC-xpath-31-20170321/#id-terminal-delimitation
 
     fn comment_map(input: &str) -> Res<&str, char> {
@@ -33,7 +36,7 @@ pub fn symbol_separator(input: &str) -> Res<&str, ()> {
     .map(|(next_input, _res)| (next_input, ()))
 }
 
-pub fn comment(input: &str) -> Res<&str, Comment> {
+pub(crate) fn comment(input: &str) -> Res<&str, Comment> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-Comment
     fn comment_contents_map(input: &str) -> Res<&str, CommentItem> {
         comment_contents(input)
@@ -59,17 +62,19 @@ pub fn comment(input: &str) -> Res<&str, Comment> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Comment {
     pub items: Vec<CommentItem>,
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CommentItem {
     Comment(Box<Comment>),
     CommentContents(CommentContents),
 }
 
-pub fn comment_contents(input: &str) -> Res<&str, CommentContents> {
+pub(crate) fn comment_contents(input: &str) -> Res<&str, CommentContents> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-CommentContents
     context(
         "comment_contents",
@@ -82,14 +87,26 @@ pub fn comment_contents(input: &str) -> Res<&str, CommentContents> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CommentContents(String);
 
-pub fn integer_literal(input: &str) -> Res<&str, u32> {
+/// Parse an integer literal into a value of type `T`.
+///
+/// Digits whose value does not fit in `T` are a failure that stops parsing at the literal,
+/// since no other parser can accept them.
+pub(crate) fn integer_literal<T: FromStr>(input: &str) -> Res<&str, T> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-IntegerLiteral
-    map_res(digit1, str::parse)(input)
+    let (next_input, digits) = digit1(input)?;
+    match digits.parse() {
+        Ok(value) => Ok((next_input, value)),
+        Err(_) => Err(NomErr::Failure(GrammarError::from_error_kind(
+            input,
+            ErrorKind::TooLarge,
+        ))),
+    }
 }
 
-pub fn decimal_literal(input: &str) -> Res<&str, f32> {
+pub(crate) fn decimal_literal(input: &str) -> Res<&str, f32> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-DecimalLiteral
 
     map_res(
@@ -101,7 +118,7 @@ pub fn decimal_literal(input: &str) -> Res<&str, f32> {
     )(input)
 }
 
-pub fn double_literal(input: &str) -> Res<&str, f64> {
+pub(crate) fn double_literal(input: &str) -> Res<&str, f64> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-DoubleLiteral
 
     map_res(
@@ -118,7 +135,7 @@ pub fn double_literal(input: &str) -> Res<&str, f64> {
     )(input)
 }
 
-pub fn string_literal(input: &str) -> Res<&str, StringLiteral> {
+pub(crate) fn string_literal(input: &str) -> Res<&str, StringLiteral> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-StringLiteral
 
     fn double_quoted_map(input: &str) -> Res<&str, StringLiteral> {
@@ -176,27 +193,48 @@ pub fn string_literal(input: &str) -> Res<&str, StringLiteral> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StringLiteral {
     pub value: String,
     quotation_type: QuotationType,
 }
 
+impl StringLiteral {
+    /// Create a string literal for a value.
+    ///
+    /// The literal is single quoted, unless the value contains single quotes but no double quotes.
+    pub fn new(value: &str) -> Self {
+        let quotation_type = if value.contains('\'') && !value.contains('"') {
+            QuotationType::Double
+        } else {
+            QuotationType::Single
+        };
+
+        Self {
+            value: value.to_string(),
+            quotation_type,
+        }
+    }
+}
+
 impl Display for StringLiteral {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Quotes inside the literal are escaped by doubling them.
         match self.quotation_type {
-            QuotationType::Single => write!(f, "'{}'", self.value),
-            QuotationType::Double => write!(f, "\"{}\"", self.value),
+            QuotationType::Single => write!(f, "'{}'", self.value.replace('\'', "''")),
+            QuotationType::Double => write!(f, "\"{}\"", self.value.replace('"', "\"\"")),
         }
     }
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum QuotationType {
     Single,
     Double,
 }
 
-pub fn uri_qualified_name(input: &str) -> Res<&str, UriQualifiedName> {
+pub(crate) fn uri_qualified_name(input: &str) -> Res<&str, UriQualifiedName> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-URIQualifiedName
 
     tuple((braced_uri_literal, nc_name))(input).map(|(next_input, res)| {
@@ -211,6 +249,7 @@ pub fn uri_qualified_name(input: &str) -> Res<&str, UriQualifiedName> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UriQualifiedName {
     pub uri: String,
     pub name: String,
@@ -218,11 +257,11 @@ pub struct UriQualifiedName {
 
 impl Display for UriQualifiedName {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} {}", self.uri, self.name)
+        write!(f, "Q{{{}}}{}", self.uri, self.name)
     }
 }
 
-pub fn braced_uri_literal(input: &str) -> Res<&str, &str> {
+pub(crate) fn braced_uri_literal(input: &str) -> Res<&str, &str> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-BracedURILiteral
 
     tuple((
@@ -290,6 +329,34 @@ mod tests {
         )
     }
 
+    #[test]
+    fn string_literal_should_display_escaped_quotes() {
+        // arrange
+        let input = format!("'{}'", "He said, \"I don''t like it.\"");
+
+        // act
+        let (_, res) = string_literal(&input).unwrap();
+
+        // assert
+        assert_eq!(res.to_string(), input);
+    }
+
+    #[test]
+    fn string_literal_new_should_round_trip() {
+        // arrange
+        let values = ["plain", "don't", "say \"hi\"", "it's \"hers\""];
+
+        for value in values {
+            // act
+            let text = StringLiteral::new(value).to_string();
+            let (next_input, res) = string_literal(&text).unwrap();
+
+            // assert
+            assert_eq!(next_input, "");
+            assert_eq!(res.value, value);
+        }
+    }
+
     #[test]
     fn comment_should_match_comment() {
         // arrange
@@ -362,11 +429,23 @@ mod tests {
         assert_eq!(res, 0.01);
     }
 
+    #[test]
+    fn uri_qualified_name_should_display_as_written() {
+        for input in ["Q{urn:x}f", "Q{}li"] {
+            // act
+            let (next_input, res) = uri_qualified_name(input).unwrap();
+
+            // assert
+            assert_eq!(next_input, "");
+            assert_eq!(res.to_string(), input);
+        }
+    }
+
     proptest! {
         #[test]
         fn integer_literal_should_work_for_all_valid_u32(i in any::<u32>()) {
             let i_str = format!("{:?}", i);
-            let res = integer_literal(&i_str).unwrap();
+            let res = integer_literal::<u32>(&i_str).unwrap();
 
             prop_assert_eq!("", res.0, "next input not empty");
             prop_assert_eq!(i, res.1);
@@ -1,12 +1,14 @@
 use std::fmt::Display;
 
-use nom::{branch::alt, bytes::complete::tag, character::complete::char, error::context};
+use nom::{branch::alt, character::complete::char, error::context};
+
+use crate::xpath::grammar::recipes::tag;
 
 use crate::xpath::grammar::{recipes::Res, whitespace_recipes::ws};
 
 use super::sequence_type::{sequence_type, SequenceType};
 
-pub fn array_test(input: &str) -> Res<&str, ArrayTest> {
+pub(crate) fn array_test(input: &str) -> Res<&str, ArrayTest> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-ArrayTest
 
     fn any_array_test_(input: &str) -> Res<&str, ArrayTest> {
@@ -25,6 +27,7 @@ pub fn array_test(input: &str) -> Res<&str, ArrayTest> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ArrayTest {
     AnyArrayTest,
     TypedArrayTest(TypedArrayTest),
@@ -52,6 +55,7 @@ fn typed_array_test(input: &str) -> Res<&str, TypedArrayTest> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TypedArrayTest(pub SequenceType);
 
 impl Display for TypedArrayTest {
@@ -2,9 +2,9 @@
 
 use std::fmt::Display;
 
-use nom::{
-    branch::alt, bytes::complete::tag, character::complete::char, combinator::opt, error::context,
-};
+use nom::{branch::alt, character::complete::char, combinator::opt, error::context};
+
+use crate::xpath::grammar::recipes::tag;
 
 use crate::xpath::{
     grammar::{
@@ -15,7 +15,7 @@ use crate::xpath::{
 
 use super::common::{type_name, AttributeName, TypeName};
 
-pub fn attribute_test(input: &str) -> Res<&str, AttributeTest> {
+pub(crate) fn attribute_test(input: &str) -> Res<&str, AttributeTest> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-AttributeTest
 
     context(
@@ -43,6 +43,7 @@ pub fn attribute_test(input: &str) -> Res<&str, AttributeTest> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AttributeTest {
     pub pair: Option<AttributeTestPair>,
 }
@@ -77,6 +78,7 @@ impl AttributeTest {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AttributeTestPair {
     pub name_or_wildcard: AttribNameOrWildcard,
     pub type_name: Option<TypeName>,
@@ -99,15 +101,15 @@ impl AttributeTestPair {
     ) -> Result<bool, ExpressionApplyError> {
         let is_match = self.name_or_wildcard.is_match(node)?;
 
-        if let Some(_type_name) = &self.type_name {
-            todo!("AttributeTestPair::eval type_name")
+        if self.type_name.is_some() {
+            return Err(ExpressionApplyError::unsupported("kind test", self));
         }
 
         Ok(is_match)
     }
 }
 
-pub fn attrib_name_or_wildcard(input: &str) -> Res<&str, AttribNameOrWildcard> {
+pub(crate) fn attrib_name_or_wildcard(input: &str) -> Res<&str, AttribNameOrWildcard> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-AttribNameOrWildcard
 
     fn attribute_name_map(input: &str) -> Res<&str, AttribNameOrWildcard> {
@@ -126,6 +128,7 @@ pub fn attrib_name_or_wildcard(input: &str) -> Res<&str, AttribNameOrWildcard> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AttribNameOrWildcard {
     AttributeName(AttributeName),
     Wildcard,
@@ -147,7 +150,7 @@ impl AttribNameOrWildcard {
     ) -> Result<bool, ExpressionApplyError> {
         match self {
             AttribNameOrWildcard::AttributeName(_) => {
-                todo!("AttribNameOrWildcard::eval attribute_name")
+                Err(ExpressionApplyError::unsupported("kind test", self))
             }
             AttribNameOrWildcard::Wildcard => Ok(true),
         }
@@ -6,13 +6,14 @@ use crate::xpath::grammar::recipes::Res;
 
 use super::{eq_name, AtomicOrUnionType, EQName};
 
-pub fn element_name(input: &str) -> Res<&str, ElementName> {
+pub(crate) fn element_name(input: &str) -> Res<&str, ElementName> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-ElementName
 
     context("element_name", eq_name)(input).map(|(next_input, res)| (next_input, ElementName(res)))
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ElementName(pub EQName);
 
 impl Display for ElementName {
@@ -21,13 +22,14 @@ impl Display for ElementName {
     }
 }
 
-pub fn type_name(input: &str) -> Res<&str, TypeName> {
+pub(crate) fn type_name(input: &str) -> Res<&str, TypeName> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-TypeName
 
     context("type_name", eq_name)(input).map(|(next_input, res)| (next_input, TypeName(res)))
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TypeName(pub EQName);
 
 impl Display for TypeName {
@@ -36,7 +38,7 @@ impl Display for TypeName {
     }
 }
 
-pub fn attribute_name(input: &str) -> Res<&str, AttributeName> {
+pub(crate) fn attribute_name(input: &str) -> Res<&str, AttributeName> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-AttributeName
 
     context("attribute_name", eq_name)(input)
@@ -44,6 +46,7 @@ pub fn attribute_name(input: &str) -> Res<&str, AttributeName> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AttributeName(pub EQName);
 
 impl Display for AttributeName {
@@ -52,7 +55,7 @@ impl Display for AttributeName {
     }
 }
 
-pub fn atomic_or_union_type(input: &str) -> Res<&str, AtomicOrUnionType> {
+pub(crate) fn atomic_or_union_type(input: &str) -> Res<&str, AtomicOrUnionType> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-AtomicOrUnionType
     context("atomic_or_union_type", eq_name)(input)
         .map(|(next_input, res)| (next_input, AtomicOrUnionType(res)))
@@ -6,11 +6,11 @@ use crate::xpath::grammar::{recipes::Res, types::common::element_name, whitespac
 
 use super::common::{type_name, ElementName, TypeName};
 
-use nom::{
-    branch::alt, bytes::complete::tag, character::complete::char, combinator::opt, error::context,
-};
+use nom::{branch::alt, character::complete::char, combinator::opt, error::context};
 
-pub fn element_test(input: &str) -> Res<&str, ElementTest> {
+use crate::xpath::grammar::recipes::tag;
+
+pub(crate) fn element_test(input: &str) -> Res<&str, ElementTest> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-ElementTest
 
     context(
@@ -37,6 +37,7 @@ pub fn element_test(input: &str) -> Res<&str, ElementTest> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ElementTest {
     pub item: Option<ElementTestItem>,
 }
@@ -52,6 +53,7 @@ impl Display for ElementTest {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ElementTestItem {
     pub element_name_or_wildcard: ElementNameOrWildcard,
     pub type_name: Option<TypeName>,
@@ -67,7 +69,7 @@ impl Display for ElementTestItem {
     }
 }
 
-pub fn element_name_or_wildcard(input: &str) -> Res<&str, ElementNameOrWildcard> {
+pub(crate) fn element_name_or_wildcard(input: &str) -> Res<&str, ElementNameOrWildcard> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-ElementNameOrWildcard
 
     fn element_name_map(input: &str) -> Res<&str, ElementNameOrWildcard> {
@@ -86,6 +88,7 @@ pub fn element_name_or_wildcard(input: &str) -> Res<&str, ElementNameOrWildcard>
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ElementNameOrWildcard {
     ElementName(ElementName),
     Wildcard,
@@ -6,12 +6,11 @@ use crate::xpath::grammar::{recipes::Res, whitespace_recipes::ws};
 
 use super::sequence_type::{sequence_type, SequenceType};
 
-use nom::{
-    branch::alt, bytes::complete::tag, character::complete::char, combinator::opt, error::context,
-    multi::many0,
-};
+use nom::{branch::alt, character::complete::char, combinator::opt, error::context, multi::many0};
 
-pub fn function_test(input: &str) -> Res<&str, FunctionTest> {
+use crate::xpath::grammar::recipes::tag;
+
+pub(crate) fn function_test(input: &str) -> Res<&str, FunctionTest> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-FunctionTest
 
     fn any_function_test(input: &str) -> Res<&str, FunctionTest> {
@@ -33,6 +32,7 @@ pub fn function_test(input: &str) -> Res<&str, FunctionTest> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FunctionTest {
     AnyFunctionTest,
     TypedFunctionTest(TypedFunctionTest),
@@ -47,7 +47,7 @@ impl Display for FunctionTest {
     }
 }
 
-pub fn typed_function_test(input: &str) -> Res<&str, TypedFunctionTest> {
+pub(crate) fn typed_function_test(input: &str) -> Res<&str, TypedFunctionTest> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-TypedFunctionTest
 
     context(
@@ -80,6 +80,7 @@ pub fn typed_function_test(input: &str) -> Res<&str, TypedFunctionTest> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TypedFunctionTest {
     pub params: Vec<SequenceType>,
     pub ret_val: SequenceType,
@@ -2,7 +2,9 @@
 
 use std::fmt::Display;
 
-use nom::{branch::alt, bytes::complete::tag, character::complete::char, error::context};
+use nom::{branch::alt, character::complete::char, error::context};
+
+use crate::xpath::grammar::recipes::tag;
 
 use crate::xpath::grammar::{recipes::Res, whitespace_recipes::ws};
 
@@ -12,7 +14,7 @@ use super::{
     AtomicOrUnionType,
 };
 
-pub fn map_test(input: &str) -> Res<&str, MapTest> {
+pub(crate) fn map_test(input: &str) -> Res<&str, MapTest> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-MapTest
 
     fn any_map_test(input: &str) -> Res<&str, MapTest> {
@@ -30,6 +32,7 @@ pub fn map_test(input: &str) -> Res<&str, MapTest> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MapTest {
     AnyMapTest,
     TypedMapTest(TypedMapTest),
@@ -70,6 +73,7 @@ fn typed_map_test(input: &str) -> Res<&str, TypedMapTest> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TypedMapTest {
     pub atomic_or_union_type: AtomicOrUnionType,
     pub sequence_type: SequenceType,
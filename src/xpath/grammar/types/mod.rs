@@ -4,10 +4,11 @@ use std::fmt::Display;
 
 use indexmap::IndexSet;
 use nom::{
-    branch::alt, bytes::complete::tag, character::complete::char, combinator::opt, error::context,
-    sequence::tuple,
+    branch::alt, character::complete::char, combinator::opt, error::context, sequence::tuple,
 };
 
+use crate::xpath::grammar::recipes::tag;
+
 use crate::xpath::{
     grammar::{
         terminal_symbols::{string_literal, uri_qualified_name},
@@ -42,7 +43,7 @@ pub mod map_test;
 pub mod schema_element_test;
 pub mod sequence_type;
 
-pub fn kind_test(input: &str) -> Res<&str, KindTest> {
+pub(crate) fn kind_test(input: &str) -> Res<&str, KindTest> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-KindTest
 
     fn any_kind_test(input: &str) -> Res<&str, KindTest> {
@@ -119,6 +120,7 @@ pub fn kind_test(input: &str) -> Res<&str, KindTest> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum KindTest {
     AnyKindTest,
     TextTest,
@@ -170,10 +172,25 @@ impl KindTest {
 
                 Ok(filtered_nodes.collect())
             }
-            KindTest::CommentTest => todo!("KindTest::CommentTest::is_match"),
-            KindTest::NamespaceNodeTest => todo!("KindTest::NamespaceNodeTest::is_match"),
+            KindTest::CommentTest => {
+                // CommentTest is `comment()`.
+                // Select all comment nodes.
+                let filtered_nodes = item_set.iter().filter_map(|item| {
+                    if let XpathItem::Node(node) = item {
+                        if matches!(node, XpathItemTreeNode::CommentNode(_),) {
+                            return Some(*node);
+                        }
+                    }
+
+                    None
+                });
+
+                Ok(filtered_nodes.collect())
+            }
+            // HTML documents do not have namespace nodes.
+            KindTest::NamespaceNodeTest => Ok(IndexSet::new()),
             KindTest::DocumentTest(x) => x.filter(item_set),
-            KindTest::ElementTest(_) => todo!("KindTest::ElementTest::is_match"),
+            KindTest::ElementTest(_) => Err(ExpressionApplyError::unsupported("kind test", self)),
             KindTest::AttributeTest(x) => {
                 let mut filtered_nodes = IndexSet::new();
 
@@ -187,9 +204,9 @@ impl KindTest {
 
                 Ok(filtered_nodes)
             }
-            KindTest::SchemaElementTest(_) => todo!("KindTest::SchemaElementTest::is_match"),
-            KindTest::SchemaAttributeTest(_) => todo!("KindTest::SchemaAttributeTest::is_match"),
-            KindTest::PITest(_) => todo!("KindTest::PITest::is_match"),
+            KindTest::SchemaElementTest(_)
+            | KindTest::SchemaAttributeTest(_)
+            | KindTest::PITest(_) => Err(ExpressionApplyError::unsupported("kind test", self)),
         }
     }
 }
@@ -211,7 +228,7 @@ impl Display for KindTest {
     }
 }
 
-pub fn document_test(input: &str) -> Res<&str, DocumentTest> {
+pub(crate) fn document_test(input: &str) -> Res<&str, DocumentTest> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-DocumentTest
 
     fn element_test_map(input: &str) -> Res<&str, DocumentTestValue> {
@@ -237,6 +254,7 @@ pub fn document_test(input: &str) -> Res<&str, DocumentTest> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DocumentTest {
     pub value: Option<DocumentTestValue>,
 }
@@ -274,12 +292,13 @@ impl DocumentTest {
             // document-node( E ) matches any document node that contains exactly one element node,
             // optionally accompanied by one or more comment and processing instruction nodes,
             // if E is an ElementTest or SchemaElementTest that matches the element node.
-            Some(_) => todo!("DocumentTest::is_match value"),
+            Some(_) => Err(ExpressionApplyError::unsupported("kind test", self)),
         }
     }
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DocumentTestValue {
     ElementTest(ElementTest),
     SchemaElementTest(SchemaElementTest),
@@ -294,7 +313,7 @@ impl Display for DocumentTestValue {
     }
 }
 
-pub fn schema_attribute_test(input: &str) -> Res<&str, SchemaAttributeTest> {
+pub(crate) fn schema_attribute_test(input: &str) -> Res<&str, SchemaAttributeTest> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-SchemaAttributeTest
 
     context(
@@ -310,15 +329,16 @@ pub fn schema_attribute_test(input: &str) -> Res<&str, SchemaAttributeTest> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SchemaAttributeTest(pub AttributeDeclaration);
 
 impl Display for SchemaAttributeTest {
-    fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!("fmt SchemaAttributeTest")
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "schema-attribute({})", self.0)
     }
 }
 
-pub fn attribute_declaration(input: &str) -> Res<&str, AttributeDeclaration> {
+pub(crate) fn attribute_declaration(input: &str) -> Res<&str, AttributeDeclaration> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-AttributeDeclaration
 
     context("attribute_declaration", attribute_name)(input)
@@ -326,9 +346,16 @@ pub fn attribute_declaration(input: &str) -> Res<&str, AttributeDeclaration> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AttributeDeclaration(pub AttributeName);
 
-pub fn pi_test(input: &str) -> Res<&str, PITest> {
+impl Display for AttributeDeclaration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+pub(crate) fn pi_test(input: &str) -> Res<&str, PITest> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-PITest
 
     fn nc_name_map(input: &str) -> Res<&str, PITestValue> {
@@ -353,23 +380,39 @@ pub fn pi_test(input: &str) -> Res<&str, PITest> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PITest {
     pub val: Option<PITestValue>,
 }
 
 impl Display for PITest {
-    fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!("fmt PITest")
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "processing-instruction(")?;
+        if let Some(x) = &self.val {
+            write!(f, "{}", x)?;
+        }
+        write!(f, ")")
     }
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PITestValue {
     NCName(String),
     StringLiteral(String),
 }
 
+impl Display for PITestValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            // String literals keep their quotes.
+            PITestValue::NCName(x) | PITestValue::StringLiteral(x) => write!(f, "{}", x),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AtomicOrUnionType(EQName);
 
 impl Display for AtomicOrUnionType {
@@ -378,13 +421,14 @@ impl Display for AtomicOrUnionType {
     }
 }
 
-pub fn simple_type_name(input: &str) -> Res<&str, SimpleTypeName> {
+pub(crate) fn simple_type_name(input: &str) -> Res<&str, SimpleTypeName> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-SimpleTypeName
     context("simple_type_name", type_name)(input)
         .map(|(next_input, res)| (next_input, SimpleTypeName(res)))
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SimpleTypeName(TypeName);
 
 impl Display for SimpleTypeName {
@@ -393,7 +437,7 @@ impl Display for SimpleTypeName {
     }
 }
 
-pub fn eq_name(input: &str) -> Res<&str, EQName> {
+pub(crate) fn eq_name(input: &str) -> Res<&str, EQName> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-EQName
 
     fn qname_map(input: &str) -> Res<&str, EQName> {
@@ -409,6 +453,7 @@ pub fn eq_name(input: &str) -> Res<&str, EQName> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EQName {
     QName(QName),
     UriQualifiedName(UriQualifiedName),
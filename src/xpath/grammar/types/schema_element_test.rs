@@ -6,9 +6,11 @@ use crate::xpath::grammar::{recipes::Res, types::common::element_name, whitespac
 
 use super::common::ElementName;
 
-use nom::{bytes::complete::tag, character::complete::char, error::context};
+use nom::{character::complete::char, error::context};
 
-pub fn schema_element_test(input: &str) -> Res<&str, SchemaElementTest> {
+use crate::xpath::grammar::recipes::tag;
+
+pub(crate) fn schema_element_test(input: &str) -> Res<&str, SchemaElementTest> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#prod-xpath31-SchemaElementTest
 
     context(
@@ -24,6 +26,7 @@ pub fn schema_element_test(input: &str) -> Res<&str, SchemaElementTest> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SchemaElementTest(pub ElementDeclaration);
 
 impl Display for SchemaElementTest {
@@ -40,6 +43,7 @@ fn element_declaration(input: &str) -> Res<&str, ElementDeclaration> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ElementDeclaration(pub ElementName);
 
 impl Display for ElementDeclaration {
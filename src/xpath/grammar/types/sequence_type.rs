@@ -4,13 +4,14 @@ use std::fmt::Display;
 
 use nom::{
     branch::alt,
-    bytes::complete::tag,
     character::complete::char,
     combinator::{opt, recognize},
     error::context,
     sequence::tuple,
 };
 
+use crate::xpath::grammar::recipes::tag;
+
 use crate::xpath::{
     grammar::{
         recipes::Res,
@@ -29,7 +30,7 @@ use super::{
     KindTest,
 };
 
-pub fn sequence_type(input: &str) -> Res<&str, SequenceType> {
+pub(crate) fn sequence_type(input: &str) -> Res<&str, SequenceType> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-SequenceType
 
     fn empty_sequence_map(input: &str) -> Res<&str, SequenceType> {
@@ -56,6 +57,7 @@ pub fn sequence_type(input: &str) -> Res<&str, SequenceType> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SequenceType {
     EmptySequence,
     Sequence(SequenceTypeValue),
@@ -79,7 +81,10 @@ impl SequenceType {
             // The sequence type empty-sequence() matches a value that is the empty sequence.
             SequenceType::EmptySequence => Ok(item_set.is_empty()),
             SequenceType::Sequence(x) => match x.occurrence {
-                Some(_) => todo!("SequenceType::Sequence::is_match occurrence"),
+                Some(_) => Err(ExpressionApplyError::unsupported(
+                    "occurrence indicator",
+                    self,
+                )),
 
                 // An ItemType with no OccurrenceIndicator matches any value that contains exactly one item if the ItemType matches that item.
                 None => {
@@ -96,6 +101,7 @@ impl SequenceType {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SequenceTypeValue {
     pub item_type: ItemType,
     pub occurrence: Option<OccurrenceIndicator>,
@@ -112,7 +118,7 @@ impl Display for SequenceTypeValue {
     }
 }
 
-pub fn item_type(input: &str) -> Res<&str, ItemType> {
+pub(crate) fn item_type(input: &str) -> Res<&str, ItemType> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-ItemType
 
     fn item_map(input: &str) -> Res<&str, ItemType> {
@@ -157,6 +163,7 @@ pub fn item_type(input: &str) -> Res<&str, ItemType> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ItemType {
     Item,
     KindTest(KindTest),
@@ -191,15 +198,17 @@ impl ItemType {
                 let result = x.filter(item_set)?;
                 Ok(!result.is_empty())
             }
-            ItemType::FunctionTest(_x) => todo!("ItemType::FunctionTest::is_match"),
-            ItemType::MapTest(_x) => todo!("ItemType::MapTest::is_match"),
-            ItemType::ArrayTest(_x) => todo!("ItemType::ArrayTest::is_match"),
-            ItemType::AtomicOrUnionType(_x) => todo!("ItemType::AtomicOrUnionType::is_match"),
+            ItemType::FunctionTest(_)
+            | ItemType::MapTest(_)
+            | ItemType::ArrayTest(_)
+            | ItemType::AtomicOrUnionType(_) => {
+                Err(ExpressionApplyError::unsupported("item type", self))
+            }
         }
     }
 }
 
-pub fn parenthesized_item_type(input: &str) -> Res<&str, ItemType> {
+pub(crate) fn parenthesized_item_type(input: &str) -> Res<&str, ItemType> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-ParenthesizedItemType
     context(
         "parenthesized_item_type",
@@ -208,7 +217,7 @@ pub fn parenthesized_item_type(input: &str) -> Res<&str, ItemType> {
     .map(|(next_input, res)| (next_input, res.1))
 }
 
-pub fn occurrence_indicator(input: &str) -> Res<&str, OccurrenceIndicator> {
+pub(crate) fn occurrence_indicator(input: &str) -> Res<&str, OccurrenceIndicator> {
     // https://www.w3.org/TR/2017/REC-xpath-31-20170321/#doc-xpath31-OccurrenceIndicator
 
     fn zero_or_one_map(input: &str) -> Res<&str, OccurrenceIndicator> {
@@ -230,6 +239,7 @@ pub fn occurrence_indicator(input: &str) -> Res<&str, OccurrenceIndicator> {
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OccurrenceIndicator {
     ZeroOrOne,
     ZeroOrMore,
@@ -1,11 +1,9 @@
 use nom::{
-    character::complete::multispace0,
-    combinator::opt,
-    error::{ParseError, VerboseError},
-    Err as NomErr, IResult, Offset, Parser,
+    character::complete::multispace0, combinator::opt, error::ParseError, Err as NomErr, IResult,
+    Offset, Parser,
 };
 
-use super::terminal_symbols::symbol_separator;
+use super::{recipes::GrammarError, terminal_symbols::symbol_separator};
 
 /// Parses a list of whitespace-separated items.
 ///
@@ -21,19 +19,19 @@ pub trait Whitespace<I, O, E> {
     fn parse(&mut self, input: I) -> IResult<I, O, E>;
 }
 
-fn ws_parse<'a, Output, A: Parser<&'a str, Output, VerboseError<&'a str>>>(
+fn ws_parse<'a, Output, A: Parser<&'a str, Output, GrammarError<&'a str>>>(
     input: &'a str,
     parser: &mut A,
-) -> Result<(&'a str, Output), NomErr<VerboseError<&'a str>>> {
+) -> Result<(&'a str, Output), NomErr<GrammarError<&'a str>>> {
     let (next_input, _) = multispace0(input)?;
 
     parser.parse(next_input)
 }
 
-impl<'a, Output1, A: Parser<&'a str, Output1, VerboseError<&'a str>>>
-    Whitespace<&'a str, Output1, VerboseError<&'a str>> for (A,)
+impl<'a, Output1, A: Parser<&'a str, Output1, GrammarError<&'a str>>>
+    Whitespace<&'a str, Output1, GrammarError<&'a str>> for (A,)
 {
-    fn parse(&mut self, input: &'a str) -> IResult<&'a str, Output1, VerboseError<&'a str>> {
+    fn parse(&mut self, input: &'a str) -> IResult<&'a str, Output1, GrammarError<&'a str>> {
         ws_parse(input, &mut self.0)
     }
 }
@@ -42,14 +40,14 @@ impl<
         'a,
         Output1,
         Output2,
-        A: Parser<&'a str, Output1, VerboseError<&'a str>>,
-        B: Parser<&'a str, Output2, VerboseError<&'a str>>,
-    > Whitespace<&'a str, (Output1, Output2), VerboseError<&'a str>> for (A, B)
+        A: Parser<&'a str, Output1, GrammarError<&'a str>>,
+        B: Parser<&'a str, Output2, GrammarError<&'a str>>,
+    > Whitespace<&'a str, (Output1, Output2), GrammarError<&'a str>> for (A, B)
 {
     fn parse(
         &mut self,
         input: &'a str,
-    ) -> IResult<&'a str, (Output1, Output2), VerboseError<&'a str>> {
+    ) -> IResult<&'a str, (Output1, Output2), GrammarError<&'a str>> {
         let (input, res1) = ws_parse(input, &mut self.0)?;
         let (input, res2) = ws_parse(input, &mut self.1)?;
         Ok((input, (res1, res2)))
@@ -61,15 +59,15 @@ impl<
         Output1,
         Output2,
         Output3,
-        A: Parser<&'a str, Output1, VerboseError<&'a str>>,
-        B: Parser<&'a str, Output2, VerboseError<&'a str>>,
-        C: Parser<&'a str, Output3, VerboseError<&'a str>>,
-    > Whitespace<&'a str, (Output1, Output2, Output3), VerboseError<&'a str>> for (A, B, C)
+        A: Parser<&'a str, Output1, GrammarError<&'a str>>,
+        B: Parser<&'a str, Output2, GrammarError<&'a str>>,
+        C: Parser<&'a str, Output3, GrammarError<&'a str>>,
+    > Whitespace<&'a str, (Output1, Output2, Output3), GrammarError<&'a str>> for (A, B, C)
 {
     fn parse(
         &mut self,
         input: &'a str,
-    ) -> IResult<&'a str, (Output1, Output2, Output3), VerboseError<&'a str>> {
+    ) -> IResult<&'a str, (Output1, Output2, Output3), GrammarError<&'a str>> {
         let (input, res1) = ws_parse(input, &mut self.0)?;
         let (input, res2) = ws_parse(input, &mut self.1)?;
         let (input, res3) = ws_parse(input, &mut self.2)?;
@@ -83,17 +81,17 @@ impl<
         Output2,
         Output3,
         Output4,
-        A: Parser<&'a str, Output1, VerboseError<&'a str>>,
-        B: Parser<&'a str, Output2, VerboseError<&'a str>>,
-        C: Parser<&'a str, Output3, VerboseError<&'a str>>,
-        D: Parser<&'a str, Output4, VerboseError<&'a str>>,
-    > Whitespace<&'a str, (Output1, Output2, Output3, Output4), VerboseError<&'a str>>
+        A: Parser<&'a str, Output1, GrammarError<&'a str>>,
+        B: Parser<&'a str, Output2, GrammarError<&'a str>>,
+        C: Parser<&'a str, Output3, GrammarError<&'a str>>,
+        D: Parser<&'a str, Output4, GrammarError<&'a str>>,
+    > Whitespace<&'a str, (Output1, Output2, Output3, Output4), GrammarError<&'a str>>
     for (A, B, C, D)
 {
     fn parse(
         &mut self,
         input: &'a str,
-    ) -> IResult<&'a str, (Output1, Output2, Output3, Output4), VerboseError<&'a str>> {
+    ) -> IResult<&'a str, (Output1, Output2, Output3, Output4), GrammarError<&'a str>> {
         let (input, res1) = ws_parse(input, &mut self.0)?;
         let (input, res2) = ws_parse(input, &mut self.1)?;
         let (input, res3) = ws_parse(input, &mut self.2)?;
@@ -109,18 +107,18 @@ impl<
         Output3,
         Output4,
         Output5,
-        A: Parser<&'a str, Output1, VerboseError<&'a str>>,
-        B: Parser<&'a str, Output2, VerboseError<&'a str>>,
-        C: Parser<&'a str, Output3, VerboseError<&'a str>>,
-        D: Parser<&'a str, Output4, VerboseError<&'a str>>,
-        E: Parser<&'a str, Output5, VerboseError<&'a str>>,
-    > Whitespace<&'a str, (Output1, Output2, Output3, Output4, Output5), VerboseError<&'a str>>
+        A: Parser<&'a str, Output1, GrammarError<&'a str>>,
+        B: Parser<&'a str, Output2, GrammarError<&'a str>>,
+        C: Parser<&'a str, Output3, GrammarError<&'a str>>,
+        D: Parser<&'a str, Output4, GrammarError<&'a str>>,
+        E: Parser<&'a str, Output5, GrammarError<&'a str>>,
+    > Whitespace<&'a str, (Output1, Output2, Output3, Output4, Output5), GrammarError<&'a str>>
     for (A, B, C, D, E)
 {
     fn parse(
         &mut self,
         input: &'a str,
-    ) -> IResult<&'a str, (Output1, Output2, Output3, Output4, Output5), VerboseError<&'a str>>
+    ) -> IResult<&'a str, (Output1, Output2, Output3, Output4, Output5), GrammarError<&'a str>>
     {
         let (input, res1) = ws_parse(input, &mut self.0)?;
         let (input, res2) = ws_parse(input, &mut self.1)?;
@@ -139,17 +137,17 @@ impl<
         Output4,
         Output5,
         Output6,
-        A: Parser<&'a str, Output1, VerboseError<&'a str>>,
-        B: Parser<&'a str, Output2, VerboseError<&'a str>>,
-        C: Parser<&'a str, Output3, VerboseError<&'a str>>,
-        D: Parser<&'a str, Output4, VerboseError<&'a str>>,
-        E: Parser<&'a str, Output5, VerboseError<&'a str>>,
-        F: Parser<&'a str, Output6, VerboseError<&'a str>>,
+        A: Parser<&'a str, Output1, GrammarError<&'a str>>,
+        B: Parser<&'a str, Output2, GrammarError<&'a str>>,
+        C: Parser<&'a str, Output3, GrammarError<&'a str>>,
+        D: Parser<&'a str, Output4, GrammarError<&'a str>>,
+        E: Parser<&'a str, Output5, GrammarError<&'a str>>,
+        F: Parser<&'a str, Output6, GrammarError<&'a str>>,
     >
     Whitespace<
         &'a str,
         (Output1, Output2, Output3, Output4, Output5, Output6),
-        VerboseError<&'a str>,
+        GrammarError<&'a str>,
     > for (A, B, C, D, E, F)
 {
     fn parse(
@@ -158,7 +156,7 @@ impl<
     ) -> IResult<
         &'a str,
         (Output1, Output2, Output3, Output4, Output5, Output6),
-        VerboseError<&'a str>,
+        GrammarError<&'a str>,
     > {
         let (input, res1) = ws_parse(input, &mut self.0)?;
         let (input, res2) = ws_parse(input, &mut self.1)?;
@@ -174,12 +172,12 @@ pub fn sep_many0<
     'a,
     Output1,
     Output2,
-    A: Parser<&'a str, Output1, VerboseError<&'a str>>,
-    B: Parser<&'a str, Output2, VerboseError<&'a str>>,
+    A: Parser<&'a str, Output1, GrammarError<&'a str>>,
+    B: Parser<&'a str, Output2, GrammarError<&'a str>>,
 >(
     mut first_parser: A,
     mut many_parser: B,
-) -> impl FnMut(&'a str) -> IResult<&'a str, (Output1, Vec<Output2>), VerboseError<&'a str>> {
+) -> impl FnMut(&'a str) -> IResult<&'a str, (Output1, Vec<Output2>), GrammarError<&'a str>> {
     move |input: &'a str| {
         let (mut input, out1) = first_parser.parse(input)?;
 
@@ -216,10 +214,10 @@ pub trait Sep<I, O, E> {
     fn parse(&mut self, input: I) -> IResult<I, O, E>;
 }
 
-fn sep_parse<'a, Output, A: Parser<&'a str, Output, VerboseError<&'a str>>>(
+fn sep_parse<'a, Output, A: Parser<&'a str, Output, GrammarError<&'a str>>>(
     input: &'a str,
     parser: &mut A,
-) -> Result<(&'a str, Output), NomErr<VerboseError<&'a str>>> {
+) -> Result<(&'a str, Output), NomErr<GrammarError<&'a str>>> {
     let symbol_sep_res = symbol_separator(input);
 
     match symbol_sep_res {
@@ -238,10 +236,12 @@ fn sep_parse<'a, Output, A: Parser<&'a str, Output, VerboseError<&'a str>>>(
                         Ok((parser_next_input, parser_res))
                     }
                 }
-                Err(_) => Err(NomErr::Error(VerboseError::from_error_kind(
+                Err(NomErr::Error(e)) => Err(NomErr::Error(GrammarError::append(
                     input,
                     nom::error::ErrorKind::Many0,
+                    e,
                 ))),
+                Err(e) => Err(e),
             }
         }
         Err(e) => {
@@ -274,14 +274,14 @@ impl<
         'a,
         Output1,
         Output2,
-        A: Parser<&'a str, Output1, VerboseError<&'a str>>,
-        B: Parser<&'a str, Output2, VerboseError<&'a str>>,
-    > Sep<&'a str, (Output1, Output2), VerboseError<&'a str>> for (A, B)
+        A: Parser<&'a str, Output1, GrammarError<&'a str>>,
+        B: Parser<&'a str, Output2, GrammarError<&'a str>>,
+    > Sep<&'a str, (Output1, Output2), GrammarError<&'a str>> for (A, B)
 {
     fn parse(
         &mut self,
         input: &'a str,
-    ) -> IResult<&'a str, (Output1, Output2), VerboseError<&'a str>> {
+    ) -> IResult<&'a str, (Output1, Output2), GrammarError<&'a str>> {
         let (input, _) = opt(symbol_separator)(input)?;
         let (input, res1) = self.0.parse(input)?;
         let (input, res2) = sep_parse(input, &mut self.1)?;
@@ -294,15 +294,15 @@ impl<
         Output1,
         Output2,
         Output3,
-        A: Parser<&'a str, Output1, VerboseError<&'a str>>,
-        B: Parser<&'a str, Output2, VerboseError<&'a str>>,
-        C: Parser<&'a str, Output3, VerboseError<&'a str>>,
-    > Sep<&'a str, (Output1, Output2, Output3), VerboseError<&'a str>> for (A, B, C)
+        A: Parser<&'a str, Output1, GrammarError<&'a str>>,
+        B: Parser<&'a str, Output2, GrammarError<&'a str>>,
+        C: Parser<&'a str, Output3, GrammarError<&'a str>>,
+    > Sep<&'a str, (Output1, Output2, Output3), GrammarError<&'a str>> for (A, B, C)
 {
     fn parse(
         &mut self,
         input: &'a str,
-    ) -> IResult<&'a str, (Output1, Output2, Output3), VerboseError<&'a str>> {
+    ) -> IResult<&'a str, (Output1, Output2, Output3), GrammarError<&'a str>> {
         let (input, _) = opt(symbol_separator)(input)?;
         let (input, res1) = self.0.parse(input)?;
         let (input, res2) = sep_parse(input, &mut self.1)?;
@@ -317,16 +317,16 @@ impl<
         Output2,
         Output3,
         Output4,
-        A: Parser<&'a str, Output1, VerboseError<&'a str>>,
-        B: Parser<&'a str, Output2, VerboseError<&'a str>>,
-        C: Parser<&'a str, Output3, VerboseError<&'a str>>,
-        D: Parser<&'a str, Output4, VerboseError<&'a str>>,
-    > Sep<&'a str, (Output1, Output2, Output3, Output4), VerboseError<&'a str>> for (A, B, C, D)
+        A: Parser<&'a str, Output1, GrammarError<&'a str>>,
+        B: Parser<&'a str, Output2, GrammarError<&'a str>>,
+        C: Parser<&'a str, Output3, GrammarError<&'a str>>,
+        D: Parser<&'a str, Output4, GrammarError<&'a str>>,
+    > Sep<&'a str, (Output1, Output2, Output3, Output4), GrammarError<&'a str>> for (A, B, C, D)
 {
     fn parse(
         &mut self,
         input: &'a str,
-    ) -> IResult<&'a str, (Output1, Output2, Output3, Output4), VerboseError<&'a str>> {
+    ) -> IResult<&'a str, (Output1, Output2, Output3, Output4), GrammarError<&'a str>> {
         let (input, _) = opt(symbol_separator)(input)?;
         let (input, res1) = self.0.parse(input)?;
         let (input, res2) = sep_parse(input, &mut self.1)?;
@@ -343,18 +343,18 @@ impl<
         Output3,
         Output4,
         Output5,
-        A: Parser<&'a str, Output1, VerboseError<&'a str>>,
-        B: Parser<&'a str, Output2, VerboseError<&'a str>>,
-        C: Parser<&'a str, Output3, VerboseError<&'a str>>,
-        D: Parser<&'a str, Output4, VerboseError<&'a str>>,
-        E: Parser<&'a str, Output5, VerboseError<&'a str>>,
-    > Sep<&'a str, (Output1, Output2, Output3, Output4, Output5), VerboseError<&'a str>>
+        A: Parser<&'a str, Output1, GrammarError<&'a str>>,
+        B: Parser<&'a str, Output2, GrammarError<&'a str>>,
+        C: Parser<&'a str, Output3, GrammarError<&'a str>>,
+        D: Parser<&'a str, Output4, GrammarError<&'a str>>,
+        E: Parser<&'a str, Output5, GrammarError<&'a str>>,
+    > Sep<&'a str, (Output1, Output2, Output3, Output4, Output5), GrammarError<&'a str>>
     for (A, B, C, D, E)
 {
     fn parse(
         &mut self,
         input: &'a str,
-    ) -> IResult<&'a str, (Output1, Output2, Output3, Output4, Output5), VerboseError<&'a str>>
+    ) -> IResult<&'a str, (Output1, Output2, Output3, Output4, Output5), GrammarError<&'a str>>
     {
         let (input, _) = opt(symbol_separator)(input)?;
         let (input, res1) = self.0.parse(input)?;
@@ -369,7 +369,8 @@ impl<
 #[cfg(test)]
 mod test {
     use super::*;
-    use nom::{bytes::complete::tag, combinator::opt};
+    use crate::xpath::grammar::recipes::tag;
+    use nom::combinator::opt;
 
     #[test]
     fn ws_should_allow_whitespace_between() {
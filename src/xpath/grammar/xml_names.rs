@@ -10,7 +10,7 @@ use nom::{
 
 use super::recipes::{alphabetic, numeric, Res};
 
-pub fn nc_name(input: &str) -> Res<&str, &str> {
+pub(crate) fn nc_name(input: &str) -> Res<&str, &str> {
     // https://www.w3.org/TR/REC-xml-names/#NT-NCName
     fn name_start_char_no_colon(input: &str) -> Res<&str, char> {
         alt((alphabetic(), char('_')))(input)
@@ -56,6 +56,7 @@ fn prefixed_name(input: &str) -> Res<&str, PrefixedName> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PrefixedName {
     pub prefix: String,
     pub local_part: String,
@@ -67,7 +68,7 @@ impl Display for PrefixedName {
     }
 }
 
-pub fn qname(input: &str) -> Res<&str, QName> {
+pub(crate) fn qname(input: &str) -> Res<&str, QName> {
     // https://www.w3.org/TR/REC-xml-names/#NT-QName
     fn prefixedname_map(input: &str) -> Res<&str, QName> {
         prefixed_name(input).map(|(next_input, res)| (next_input, QName::PrefixedName(res)))
@@ -82,6 +83,7 @@ pub fn qname(input: &str) -> Res<&str, QName> {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum QName {
     PrefixedName(PrefixedName),
     UnprefixedName(String),
@@ -0,0 +1,469 @@
+//! Render a document with the nodes matched by an [Xpath](crate::xpath::Xpath) expression marked.
+//!
+//! A [MatchRenderer] takes an [XpathItemTree] and the [XpathItemSet] an expression returned,
+//! and renders the document in one of two forms:
+//!
+//! - [RenderFormat::Text] - an indented tree with `▶` in front of every matched node.
+//!   Matched attributes are listed under their element.
+//! - [RenderFormat::Html] - a copy of the document where matched elements have a
+//!   `data-skyscraper-match` attribute holding their position in the result,
+//!   and matched text is wrapped in a `<span>` with the same attribute.
+//!   Elements with matched attributes get a `data-skyscraper-match-attribute`
+//!   attribute listing their names.
+//!
+//! The matched nodes and their ancestors are always rendered.
+//! Other subtrees are rendered up to the [context depth](MatchRenderer::with_context_depth)
+//! below the nearest matched node or ancestor, and pruned beyond it.
+//! Pruned nodes are summarized as `… 3 nodes` in text, or as a comment in HTML.
+//!
+//! Atomic values in the item set, and nodes of documents other than the one the tree was
+//! created from, are not rendered.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! use skyscraper::html;
+//! use skyscraper::xpath::{self, highlight::MatchRenderer, XpathItemTree};
+//!
+//! let document = html::parse(r#"
+//!     <html>
+//!         <ul>
+//!             <li><a href="/1">One</a></li>
+//!             <li><a href="/2">Two</a></li>
+//!         </ul>
+//!         <footer><p>About</p></footer>
+//!     </html>"#)?;
+//! let tree = XpathItemTree::from(&document);
+//!
+//! let items = xpath::parse("//li[2]/a")?.apply(&tree)?;
+//! let text = MatchRenderer::new().render(&tree, &items);
+//!
+//! assert_eq!(text, concat!(
+//!     "  <html>\n",
+//!     "    <ul>\n",
+//!     "      <li>\n",
+//!     "        … 1 node\n",
+//!     "      <li>\n",
+//!     "▶       <a href=\"/2\">\n",
+//!     "          \"Two\"\n",
+//!     "    <footer>\n",
+//!     "      … 1 node\n",
+//! ));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use crate::xpath::{
+    functions::serialization::{escape_attribute, escape_text, VOID_ELEMENTS},
+    grammar::{
+        data_model::{ElementNode, TextNode, XpathItem},
+        TreeNodeId, XpathItemTreeNode,
+    },
+    xpath_item_set::XpathItemSet,
+    XpathItemTree,
+};
+
+/// Text longer than this is shortened in the text form.
+const MAX_TEXT_LEN: usize = 60;
+
+/// The form a [MatchRenderer] renders a document in.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum RenderFormat {
+    /// An indented tree with `▶` in front of every matched node.
+    #[default]
+    Text,
+
+    /// A copy of the document with `data-skyscraper-match` attributes on matched nodes.
+    Html,
+}
+
+/// Renders an [XpathItemTree] with the nodes of an [XpathItemSet] marked.
+///
+/// See the [module documentation](self) for the output of each [RenderFormat].
+#[derive(Debug, Clone)]
+pub struct MatchRenderer {
+    format: RenderFormat,
+    context_depth: usize,
+}
+
+impl Default for MatchRenderer {
+    fn default() -> Self {
+        Self {
+            format: RenderFormat::Text,
+            context_depth: 1,
+        }
+    }
+}
+
+impl MatchRenderer {
+    /// Create a new [MatchRenderer] rendering text, with a context depth of 1.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the form the document is rendered in.
+    pub fn with_format(mut self, format: RenderFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Set how many levels of nodes unrelated to the matches are rendered.
+    ///
+    /// A node is unrelated if it is neither a matched node nor an ancestor of one.
+    /// With a depth of 0 only the matched nodes and their ancestors are rendered.
+    /// With a depth of 1 their other children are rendered too, but not the children of those,
+    /// and so on. Use `usize::MAX` to render the whole document.
+    pub fn with_context_depth(mut self, context_depth: usize) -> Self {
+        self.context_depth = context_depth;
+        self
+    }
+
+    /// Render the document of the tree with the nodes of the item set marked.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - The tree the items were selected from.
+    /// * `items` - The items to mark, such as the result of [Xpath::apply](crate::xpath::Xpath::apply).
+    pub fn render(&self, tree: &XpathItemTree, items: &XpathItemSet) -> String {
+        let mut renderer = Renderer {
+            tree,
+            matches: Matches::new(tree, items),
+            format: self.format,
+            context_depth: self.context_depth,
+            output: String::new(),
+        };
+        renderer.children(tree.root(), 0, self.context_depth);
+
+        renderer.output
+    }
+}
+
+/// The matched nodes and their ancestors.
+struct Matches {
+    /// The position of each matched node in the item set, starting at 1.
+    positions: HashMap<TreeNodeId, usize>,
+
+    /// The matched nodes and all their ancestors.
+    related: HashSet<TreeNodeId>,
+}
+
+impl Matches {
+    fn new(tree: &XpathItemTree, items: &XpathItemSet) -> Self {
+        let mut positions = HashMap::new();
+        let mut related = HashSet::new();
+        for (index, item) in items.iter().enumerate() {
+            let XpathItem::Node(node) = item else {
+                continue;
+            };
+            let Some(id) = node.id() else {
+                continue;
+            };
+            positions.entry(id).or_insert(index + 1);
+
+            // Stop at the first node already seen, since its ancestors were added with it.
+            let mut current = Some(id);
+            while let Some(id) = current {
+                if !related.insert(id) {
+                    break;
+                }
+                current = tree.parent_of(id).and_then(|parent| parent.id());
+            }
+        }
+
+        Self { positions, related }
+    }
+
+    fn position(&self, node: &XpathItemTreeNode) -> Option<usize> {
+        node.id().and_then(|id| self.positions.get(&id).copied())
+    }
+
+    fn is_related(&self, node: &XpathItemTreeNode) -> bool {
+        node.id().is_some_and(|id| self.related.contains(&id))
+    }
+}
+
+struct Renderer<'a> {
+    tree: &'a XpathItemTree,
+    matches: Matches,
+    format: RenderFormat,
+    context_depth: usize,
+    output: String,
+}
+
+impl Renderer<'_> {
+    /// Render the children of a node.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node whose children are rendered.
+    /// * `depth` - The depth of the children in the tree, used for indenting text.
+    /// * `remaining` - How many more levels of unrelated nodes are rendered.
+    fn children(&mut self, node: &XpathItemTreeNode, depth: usize, remaining: usize) {
+        let mut pruned = 0;
+        for child in node.children(self.tree) {
+            if matches!(child, XpathItemTreeNode::AttributeNode(_)) {
+                continue;
+            }
+
+            let is_related = self.matches.is_related(child);
+            if !is_related && remaining == 0 {
+                if !is_whitespace(child) {
+                    pruned += 1;
+                }
+                continue;
+            }
+
+            self.pruned(pruned, depth);
+            pruned = 0;
+
+            let remaining = if is_related {
+                self.context_depth
+            } else {
+                remaining - 1
+            };
+            match child {
+                XpathItemTreeNode::ElementNode(element) => {
+                    self.element(child, element, depth, remaining)
+                }
+                XpathItemTreeNode::TextNode(text) => self.text(child, text, depth),
+                _ => {}
+            }
+        }
+        self.pruned(pruned, depth);
+    }
+
+    fn element(
+        &mut self,
+        node: &XpathItemTreeNode,
+        element: &ElementNode,
+        depth: usize,
+        remaining: usize,
+    ) {
+        let position = self.matches.position(node);
+        let attributes = element.attributes(self.tree);
+        let matched_attributes: Vec<_> = attributes
+            .iter()
+            .filter(|attribute| self.matches.positions.contains_key(&attribute.id()))
+            .collect();
+
+        match self.format {
+            RenderFormat::Text => {
+                let mut tag = format!("<{}", element.name);
+                for attribute in attributes.iter() {
+                    tag.push_str(&format!(" {}=\"{}\"", attribute.name, attribute.value));
+                }
+                tag.push('>');
+                self.line(position.is_some(), depth, &tag);
+
+                for attribute in matched_attributes {
+                    let text = format!("@{}=\"{}\"", attribute.name, attribute.value);
+                    self.line(true, depth + 1, &text);
+                }
+
+                self.children(node, depth + 1, remaining);
+            }
+            RenderFormat::Html => {
+                self.output.push('<');
+                self.output.push_str(&element.name);
+                for attribute in attributes.iter() {
+                    self.output.push_str(&format!(
+                        " {}=\"{}\"",
+                        attribute.name,
+                        escape_attribute(&attribute.value)
+                    ));
+                }
+                if let Some(position) = position {
+                    self.output
+                        .push_str(&format!(" data-skyscraper-match=\"{}\"", position));
+                }
+                if !matched_attributes.is_empty() {
+                    let names: Vec<_> = matched_attributes
+                        .iter()
+                        .map(|attribute| escape_attribute(&attribute.name))
+                        .collect();
+                    self.output.push_str(&format!(
+                        " data-skyscraper-match-attribute=\"{}\"",
+                        names.join(" ")
+                    ));
+                }
+                self.output.push('>');
+
+                if VOID_ELEMENTS.contains(&element.name.to_ascii_lowercase().as_str()) {
+                    return;
+                }
+
+                self.children(node, depth + 1, remaining);
+                self.output.push_str(&format!("</{}>", element.name));
+            }
+        }
+    }
+
+    fn text(&mut self, node: &XpathItemTreeNode, text: &TextNode, depth: usize) {
+        let position = self.matches.position(node);
+        match self.format {
+            RenderFormat::Text => {
+                // Whitespace between tags is left out unless it was matched.
+                if text.only_whitespace && position.is_none() {
+                    return;
+                }
+                let content = format!("\"{}\"", shorten(&text.content));
+                self.line(position.is_some(), depth, &content);
+            }
+            RenderFormat::Html => match position {
+                Some(position) => self.output.push_str(&format!(
+                    "<span data-skyscraper-match=\"{}\">{}</span>",
+                    position,
+                    escape_text(&text.content)
+                )),
+                None => self.output.push_str(&escape_text(&text.content)),
+            },
+        }
+    }
+
+    /// Summarize nodes that were pruned.
+    fn pruned(&mut self, count: usize, depth: usize) {
+        if count == 0 {
+            return;
+        }
+
+        let nodes = if count == 1 { "node" } else { "nodes" };
+        match self.format {
+            RenderFormat::Text => self.line(false, depth, &format!("… {} {}", count, nodes)),
+            RenderFormat::Html => self
+                .output
+                .push_str(&format!("<!-- {} {} pruned -->", count, nodes)),
+        }
+    }
+
+    /// Write a line of the text form.
+    fn line(&mut self, is_match: bool, depth: usize, content: &str) {
+        self.output.push_str(if is_match { "▶ " } else { "  " });
+        self.output.push_str(&"  ".repeat(depth));
+        self.output.push_str(content);
+        self.output.push('\n');
+    }
+}
+
+fn is_whitespace(node: &XpathItemTreeNode) -> bool {
+    matches!(node, XpathItemTreeNode::TextNode(text) if text.only_whitespace)
+}
+
+/// Collapse whitespace in text and shorten it to [MAX_TEXT_LEN] characters.
+fn shorten(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= MAX_TEXT_LEN {
+        return text;
+    }
+
+    let mut shortened: String = text.chars().take(MAX_TEXT_LEN - 1).collect();
+    shortened.push('…');
+    shortened
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{html, xpath};
+
+    use super::*;
+
+    const TEXT: &str = r#"
+        <html>
+            <div class="product">
+                <a href="https://example.com">external</a>
+                <a href="/local">local <b>link</b></a>
+            </div>
+            <div class="banner"><p>Ad</p><p>More</p></div>
+        </html>"#;
+
+    #[test]
+    fn render_should_mark_matches_and_prune_unrelated_subtrees() {
+        // arrange
+        let document = html::parse(TEXT).unwrap();
+        let tree = XpathItemTree::from(&document);
+        let items = xpath::parse("//a[1]").unwrap().apply(&tree).unwrap();
+
+        // act
+        let text = MatchRenderer::new().render(&tree, &items);
+
+        // assert
+        assert_eq!(
+            text,
+            concat!(
+                "  <html>\n",
+                "    <div class=\"product\">\n",
+                "▶     <a href=\"https://example.com\">\n",
+                "        \"external\"\n",
+                "      <a href=\"/local\">\n",
+                "        … 2 nodes\n",
+                "    <div class=\"banner\">\n",
+                "      … 2 nodes\n",
+            )
+        );
+    }
+
+    #[test]
+    fn render_should_show_only_ancestors_with_context_depth_zero() {
+        // arrange
+        let document = html::parse(TEXT).unwrap();
+        let tree = XpathItemTree::from(&document);
+        let items = xpath::parse("//a/@href, //b/text()")
+            .unwrap()
+            .apply(&tree)
+            .unwrap();
+
+        // act
+        let text = MatchRenderer::new()
+            .with_context_depth(0)
+            .render(&tree, &items);
+
+        // assert
+        assert_eq!(
+            text,
+            concat!(
+                "  <html>\n",
+                "    <div class=\"product\">\n",
+                "      <a href=\"https://example.com\">\n",
+                "▶       @href=\"https://example.com\"\n",
+                "        … 1 node\n",
+                "      <a href=\"/local\">\n",
+                "▶       @href=\"/local\"\n",
+                "        … 1 node\n",
+                "        <b>\n",
+                "▶         \"link\"\n",
+                "    … 1 node\n",
+            )
+        );
+    }
+
+    #[test]
+    fn render_should_add_match_attributes_to_html_copy() {
+        // arrange
+        let document =
+            html::parse(r#"<ul><li id="a">One</li><li>Two <br></li><li>x</li></ul>"#).unwrap();
+        let tree = XpathItemTree::from(&document);
+        let items = xpath::parse("//li[2], //li[2]/text(), //li[@id]/@id")
+            .unwrap()
+            .apply(&tree)
+            .unwrap();
+
+        // act
+        let html = MatchRenderer::new()
+            .with_format(RenderFormat::Html)
+            .with_context_depth(0)
+            .render(&tree, &items);
+
+        // assert
+        assert_eq!(
+            html,
+            concat!(
+                r#"<ul><li id="a" data-skyscraper-match-attribute="id"><!-- 1 node pruned --></li>"#,
+                r#"<li data-skyscraper-match="1"><span data-skyscraper-match="2">Two </span>"#,
+                r#"<!-- 1 node pruned --></li><!-- 1 node pruned --></ul>"#,
+            )
+        );
+    }
+}
@@ -0,0 +1,414 @@
+//! Limits on the resources used when applying an [Xpath](crate::xpath::Xpath) expression.
+//!
+//! Expressions such as `//*//*//*[//*//*]` take time that grows very quickly with the size
+//! of the document. Set [EvaluationLimits] on a [DynamicContext](crate::xpath::DynamicContext)
+//! before applying expressions from untrusted sources.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! use std::time::Duration;
+//!
+//! use skyscraper::html;
+//! use skyscraper::xpath::{
+//!     self,
+//!     context::DynamicContextBuilder,
+//!     limits::{EvaluationLimit, EvaluationLimits},
+//!     ErrorCode, StaticContext, XpathItemTree,
+//! };
+//!
+//! let limits = EvaluationLimits::new()
+//!     .with_max_node_visits(50)
+//!     .with_timeout(Duration::from_secs(1));
+//! let context = DynamicContextBuilder::new(StaticContext::new())
+//!     .with_limits(limits)
+//!     .build();
+//!
+//! let document = html::parse("<html><div><p>1</p><p>2</p></div></html>")?;
+//! let tree = XpathItemTree::from(&document);
+//!
+//! let xpath = xpath::parse("//p")?;
+//! assert_eq!(xpath.apply_with_context(&tree, &context)?.len(), 2);
+//!
+//! let xpath = xpath::parse("//*//*//*[//*//*]")?;
+//! let err = xpath.apply_with_context(&tree, &context).unwrap_err();
+//! assert_eq!(err.code(), &ErrorCode::LimitExceeded(EvaluationLimit::NodeVisits));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::{hash_map::RandomState, HashMap},
+    fmt::Display,
+    hash::{BuildHasher, Hash},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::xpath::{ErrorCode, ExpressionApplyError};
+
+/// How many node visits pass between checks of the clock.
+const CLOCK_CHECK_INTERVAL: u64 = 64;
+
+/// The limit that an evaluation exceeded.
+///
+/// See [ErrorCode::LimitExceeded].
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum EvaluationLimit {
+    /// Too many nodes were visited by axis steps, predicates or string values.
+    NodeVisits,
+
+    /// An intermediate or final result had too many items.
+    ResultSize,
+
+    /// Sub-expressions were nested too deeply.
+    Depth,
+
+    /// The deadline passed.
+    Deadline,
+
+    /// The evaluation was cancelled with a [CancellationToken].
+    Cancelled,
+}
+
+impl Display for EvaluationLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EvaluationLimit::NodeVisits => write!(f, "node visits"),
+            EvaluationLimit::ResultSize => write!(f, "result size"),
+            EvaluationLimit::Depth => write!(f, "depth"),
+            EvaluationLimit::Deadline => write!(f, "deadline"),
+            EvaluationLimit::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+/// A token used to cancel evaluations from another thread.
+///
+/// Clones share the same state, so cancelling any clone cancels them all.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Create a new token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel every evaluation using this token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether [CancellationToken::cancel] has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Limits on the resources a single evaluation may use.
+///
+/// No limits are set by default.
+/// An evaluation that exceeds a limit fails with [ErrorCode::LimitExceeded].
+#[derive(Clone, Debug, Default)]
+pub struct EvaluationLimits {
+    max_node_visits: Option<u64>,
+    max_result_size: Option<usize>,
+    max_depth: Option<usize>,
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+    cancellation_token: Option<CancellationToken>,
+}
+
+impl EvaluationLimits {
+    /// Create a new [EvaluationLimits] without any limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the number of nodes visited by axis steps, predicates and string values.
+    ///
+    /// Every node an axis step looks at counts, whether or not it matches the node test.
+    /// So does every node a predicate is tested on,
+    /// and every descendant walked to get the string value of a node.
+    pub fn with_max_node_visits(mut self, max_node_visits: u64) -> Self {
+        self.max_node_visits = Some(max_node_visits);
+        self
+    }
+
+    /// Limit the number of items in the result and in every path step along the way.
+    pub fn with_max_result_size(mut self, max_result_size: usize) -> Self {
+        self.max_result_size = Some(max_result_size);
+        self
+    }
+
+    /// Limit how deeply sub-expressions are nested while evaluating.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Limit the wall-clock time of each evaluation, measured from when it starts.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Stop evaluations that are still running at the given instant.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Stop evaluations when the token is cancelled.
+    pub fn with_cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    /// The maximum number of nodes visited by axis steps, predicates and string values.
+    pub fn max_node_visits(&self) -> Option<u64> {
+        self.max_node_visits
+    }
+
+    /// The maximum number of items in a result.
+    pub fn max_result_size(&self) -> Option<usize> {
+        self.max_result_size
+    }
+
+    /// The maximum nesting depth of sub-expressions.
+    pub fn max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    /// The maximum wall-clock time of each evaluation.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// The instant after which evaluations are stopped.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// The token used to cancel evaluations.
+    pub fn cancellation_token(&self) -> Option<&CancellationToken> {
+        self.cancellation_token.as_ref()
+    }
+}
+
+/// The resources used so far by a single evaluation.
+pub(crate) struct EvaluationBudget {
+    limits: EvaluationLimits,
+
+    /// The earlier of the deadline and the end of the timeout.
+    deadline: Option<Instant>,
+
+    node_visits: Cell<u64>,
+    depth: Cell<usize>,
+
+    /// The documents the evaluation added to the tree, keyed by a hash of where they came from.
+    documents: RefCell<HashMap<u64, usize>>,
+    document_hasher: RandomState,
+}
+
+impl EvaluationBudget {
+    /// Start tracking an evaluation that begins now.
+    pub fn new(limits: &EvaluationLimits) -> Self {
+        let timeout_deadline = limits.timeout.map(|timeout| Instant::now() + timeout);
+        let deadline = match (limits.deadline, timeout_deadline) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+
+        Self {
+            limits: limits.clone(),
+            deadline,
+            node_visits: Cell::new(0),
+            depth: Cell::new(0),
+            documents: RefCell::new(HashMap::new()),
+            document_hasher: RandomState::new(),
+        }
+    }
+
+    /// Record a node visited by an axis step, a predicate or a string value.
+    ///
+    /// This is also where the deadline and cancellation token are checked,
+    /// since node visits are where long evaluations spend their time.
+    pub fn visit_node(&self) -> Result<(), ExpressionApplyError> {
+        let node_visits = self.node_visits.get() + 1;
+        self.node_visits.set(node_visits);
+
+        if let Some(max) = self.limits.max_node_visits {
+            if node_visits > max {
+                return Err(limit_exceeded(
+                    EvaluationLimit::NodeVisits,
+                    format!("evaluation visited more than {} nodes", max),
+                ));
+            }
+        }
+
+        if let Some(token) = &self.limits.cancellation_token {
+            if token.is_cancelled() {
+                return Err(limit_exceeded(
+                    EvaluationLimit::Cancelled,
+                    String::from("evaluation was cancelled"),
+                ));
+            }
+        }
+
+        // Reading the clock is slower than the other checks, so only do it now and then.
+        if let Some(deadline) = self.deadline {
+            if node_visits % CLOCK_CHECK_INTERVAL == 1 && Instant::now() >= deadline {
+                return Err(limit_exceeded(
+                    EvaluationLimit::Deadline,
+                    String::from("evaluation did not finish before the deadline"),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check the number of items in a result.
+    pub fn check_result_size(&self, size: usize) -> Result<(), ExpressionApplyError> {
+        match self.limits.max_result_size {
+            Some(max) if size > max => Err(limit_exceeded(
+                EvaluationLimit::ResultSize,
+                format!("result has more than {} items", max),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Enter a nested sub-expression.
+    ///
+    /// The depth goes back down when the returned guard is dropped.
+    pub fn enter(&self) -> Result<DepthGuard<'_>, ExpressionApplyError> {
+        let depth = self.depth.get() + 1;
+        if let Some(max) = self.limits.max_depth {
+            if depth > max {
+                return Err(limit_exceeded(
+                    EvaluationLimit::Depth,
+                    format!("expressions are nested more than {} deep", max),
+                ));
+            }
+        }
+
+        self.depth.set(depth);
+        Ok(DepthGuard { budget: self })
+    }
+
+    /// Get the index of the document the evaluation added for the key,
+    /// or add it and remember it for the rest of the evaluation.
+    ///
+    /// Only a hash of the key is kept, and the hasher is seeded randomly
+    /// so that keys cannot be chosen to collide.
+    pub fn document<E>(
+        &self,
+        key: impl Hash,
+        add: impl FnOnce() -> Result<usize, E>,
+    ) -> Result<usize, E> {
+        let key = self.document_hasher.hash_one(key);
+        if let Some(&document) = self.documents.borrow().get(&key) {
+            return Ok(document);
+        }
+
+        let document = add()?;
+        self.documents.borrow_mut().insert(key, document);
+        Ok(document)
+    }
+}
+
+/// Leaves a nested sub-expression when dropped.
+pub(crate) struct DepthGuard<'a> {
+    budget: &'a EvaluationBudget,
+}
+
+impl Drop for DepthGuard<'_> {
+    fn drop(&mut self) {
+        self.budget.depth.set(self.budget.depth.get() - 1);
+    }
+}
+
+fn limit_exceeded(limit: EvaluationLimit, msg: String) -> ExpressionApplyError {
+    ExpressionApplyError::with_code(ErrorCode::LimitExceeded(limit), msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visit_node_should_fail_after_max_node_visits() {
+        // arrange
+        let budget = EvaluationBudget::new(&EvaluationLimits::new().with_max_node_visits(2));
+
+        // act
+        let results: Vec<bool> = (0..3).map(|_| budget.visit_node().is_ok()).collect();
+
+        // assert
+        assert_eq!(results, vec![true, true, false]);
+    }
+
+    #[test]
+    fn visit_node_should_fail_when_cancelled() {
+        // arrange
+        let token = CancellationToken::new();
+        let budget =
+            EvaluationBudget::new(&EvaluationLimits::new().with_cancellation_token(token.clone()));
+
+        // act
+        token.cancel();
+        let err = budget.visit_node().unwrap_err();
+
+        // assert
+        assert_eq!(
+            err.code(),
+            &ErrorCode::LimitExceeded(EvaluationLimit::Cancelled)
+        );
+    }
+
+    #[test]
+    fn visit_node_should_fail_after_deadline() {
+        // arrange
+        let budget = EvaluationBudget::new(&EvaluationLimits::new().with_deadline(Instant::now()));
+
+        // act
+        let err = budget.visit_node().unwrap_err();
+
+        // assert
+        assert_eq!(
+            err.code(),
+            &ErrorCode::LimitExceeded(EvaluationLimit::Deadline)
+        );
+    }
+
+    #[test]
+    fn enter_should_release_depth_when_guard_is_dropped() {
+        // arrange
+        let budget = EvaluationBudget::new(&EvaluationLimits::new().with_max_depth(1));
+
+        // act
+        let first = budget.enter().map(drop);
+        let guard = budget.enter().unwrap();
+        let nested = budget.enter().map(drop);
+        drop(guard);
+
+        // assert
+        assert!(first.is_ok());
+        assert_eq!(
+            nested.unwrap_err().code(),
+            &ErrorCode::LimitExceeded(EvaluationLimit::Depth)
+        );
+        assert_eq!(budget.depth.get(), 0);
+    }
+}
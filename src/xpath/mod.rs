@@ -5,6 +5,12 @@
 //! - [parse] - Parse a string into an [Xpath] expression.
 //! - [Xpath::apply] - Apply an [Xpath] expression to an [XpathItemTree].
 //! - [XpathItemTree] - A tree of [XpathItem]s that can be searched using an [Xpath] expression.
+//! - [Xpath::compile] - Compile an [Xpath] expression once to apply it to many trees.
+//! - [xpath_set::XpathSet] - Apply many [Xpath] expressions together, sharing the steps they have in common.
+//! - [Xpath::iter] - Stream the result of an [Xpath] expression in document order, stopping early when done.
+//! - [Xpath::explain] - Trace how each step of an [Xpath] expression narrows down the result.
+//! - [highlight::MatchRenderer] - Render a document with the nodes an [Xpath] expression matched marked.
+//! - [visit] - Walk and rewrite the syntax tree of a parsed [Xpath] expression.
 //!
 //! # Example: get links with the `/@href` xpath step
 //!
@@ -172,28 +178,58 @@
 //! # }
 //! ```
 
-use thiserror::Error;
+use nom::{error::ErrorKind, Err as NomErr};
 
 use self::{
-    grammar::{data_model::XpathItem, xpath},
+    context::DEFAULT_DYNAMIC_CONTEXT,
+    explain::Tracer,
+    grammar::{
+        data_model::XpathItem,
+        recipes::{track_farthest_failure, Expected},
+        xpath,
+    },
+    limits::EvaluationBudget,
     xpath_item_set::XpathItemSet,
 };
 
+mod apply_error;
+pub mod builder;
+pub mod collation;
+pub mod compile;
+pub mod context;
+pub mod decimal_format;
+pub mod explain;
+mod formatting;
+pub mod functions;
 pub mod grammar;
+pub mod highlight;
+pub mod limits;
+#[cfg(test)]
+mod no_panic_tests;
+mod parse_error;
+mod planner;
 pub mod query;
+pub mod resolver;
+mod span;
+mod static_analysis;
+pub mod stream;
+mod uri;
+pub mod visit;
 pub mod xpath_item_set;
+pub mod xpath_set;
 
+pub use self::apply_error::{ErrorCode, ExpressionApplyError};
+pub use self::context::{DynamicContext, StaticContext};
 pub use self::grammar::{Xpath, XpathItemTree};
-
-/// Error that occurs when parsing an [Xpath] expression.
-#[derive(PartialEq, Debug, Error)]
-#[error("Error parsing expression: {msg}")]
-pub struct ExpressionParseError {
-    msg: String,
-}
+pub use self::parse_error::{ExpressionParseError, ParsePosition};
 
 /// Parse a string into an [Xpath] expression.
 ///
+/// The expression is checked against the default [StaticContext],
+/// so calls to unknown functions and references to unbound variables are reported here
+/// rather than when the expression is applied.
+/// Use [parse_with_context] for expressions that call extension functions.
+///
 /// # Example
 ///
 /// ```rust
@@ -201,28 +237,78 @@ pub struct ExpressionParseError {
 ///
 /// let xpath = parse("//div[@class='yes']/parent::div/div[@class='duplicate']")
 ///    .expect("xpath is invalid");
+///
+/// assert!(parse("//div[foo()]").is_err());
 /// ```
 pub fn parse(input: &str) -> Result<Xpath, ExpressionParseError> {
-    xpath(input).map(|x| x.1).map_err(|e| ExpressionParseError {
-        msg: format!("{}", e),
-    })
+    parse_with_context(input, DEFAULT_DYNAMIC_CONTEXT.static_context())
 }
 
-/// Error that occurs when applying an [Xpath] expression to an [XpathItemTree].
-#[derive(PartialEq, Debug, Error)]
-#[error("Error applying expression {msg}")]
-pub struct ExpressionApplyError {
-    msg: String,
-}
+/// Parse a string into an [Xpath] expression and check it against the given [StaticContext].
+///
+/// Function names and namespace prefixes are resolved using the context,
+/// so this is where expressions calling extension functions are validated.
+/// Apply the result with a [DynamicContext] created from the same static context.
+///
+/// # Example
+///
+/// ```rust
+/// use skyscraper::xpath::{self, StaticContext};
+///
+/// let static_context = StaticContext::new();
+/// let xpath = xpath::parse_with_context("//div[contains(@class, 'item')]", &static_context)
+///    .expect("xpath is invalid");
+/// ```
+pub fn parse_with_context(
+    input: &str,
+    static_context: &StaticContext,
+) -> Result<Xpath, ExpressionParseError> {
+    let (result, farthest_failure) = track_farthest_failure(|| xpath(input));
+    let mut xpath = match result {
+        Ok((remaining, xpath)) => {
+            let remaining = remaining.trim_start();
+            if !remaining.is_empty() {
+                // A parser that got past the remaining input explains why it was not parsed.
+                return Err(match farthest_failure {
+                    Some(failure) if failure.input < remaining.len() => {
+                        ExpressionParseError::from_grammar_error(input, &failure)
+                    }
+                    _ => ExpressionParseError::from_remaining_input(input, remaining),
+                });
+            }
 
-impl ExpressionApplyError {
-    pub(crate) fn new(msg: String) -> Self {
-        Self { msg }
-    }
+            xpath
+        }
+        Err(NomErr::Failure(error)) if error.expected == [Expected::Kind(ErrorKind::TooLarge)] => {
+            return Err(ExpressionParseError::integer_overflow(input, error.input));
+        }
+        Err(_) => {
+            return Err(match farthest_failure {
+                Some(failure) => ExpressionParseError::from_grammar_error(input, &failure),
+                // Every failing parser records its failure, but point at the start if none did.
+                None => ExpressionParseError::from_remaining_input(input, input),
+            });
+        }
+    };
+
+    static_analysis::analyze(&xpath, static_context)?;
+    xpath.1 = Some(input.to_string());
+
+    Ok(xpath)
 }
 
-pub(crate) struct XpathExpressionContext<'tree> {
+pub(crate) struct XpathExpressionContext<'tree, 'ctx> {
     item_tree: &'tree XpathItemTree,
+
+    /// The dynamic context the expression is being evaluated in.
+    dynamic_context: &'ctx DynamicContext,
+
+    /// The resources used so far by the evaluation, checked against the dynamic context's limits.
+    budget: &'ctx EvaluationBudget,
+
+    /// Records each step and predicate when the expression is being explained.
+    tracer: Option<&'ctx Tracer>,
+
     item: XpathItem<'tree>,
     position: usize,
 
@@ -237,15 +323,21 @@ pub(crate) struct XpathExpressionContext<'tree> {
     is_root_level: bool,
 }
 
-impl<'tree> XpathExpressionContext<'tree> {
+impl<'tree, 'ctx> XpathExpressionContext<'tree, 'ctx> {
     pub fn new(
         item_tree: &'tree XpathItemTree,
+        dynamic_context: &'ctx DynamicContext,
+        budget: &'ctx EvaluationBudget,
+        tracer: Option<&'ctx Tracer>,
         items: &XpathItemSet<'tree>,
         position: usize,
         is_root_level: bool,
     ) -> Self {
         Self {
             item_tree,
+            dynamic_context,
+            budget,
+            tracer,
             item: items[position - 1].clone(), // Position is 1-based
             position: position,
             size: items.len(),
@@ -255,17 +347,37 @@ impl<'tree> XpathExpressionContext<'tree> {
 
     pub fn new_single(
         item_tree: &'tree XpathItemTree,
+        dynamic_context: &'ctx DynamicContext,
+        budget: &'ctx EvaluationBudget,
+        tracer: Option<&'ctx Tracer>,
         item: XpathItem<'tree>,
         is_root_level: bool,
     ) -> Self {
         Self {
             item_tree,
+            dynamic_context,
+            budget,
+            tracer,
             item,
             position: 1,
             size: 1,
             is_root_level,
         }
     }
+
+    /// Create a context for the item at the given position of a sequence,
+    /// in the same evaluation as this context.
+    pub fn with_position(&self, items: &XpathItemSet<'tree>, position: usize) -> Self {
+        Self::new(
+            self.item_tree,
+            self.dynamic_context,
+            self.budget,
+            self.tracer,
+            items,
+            position,
+            self.is_root_level,
+        )
+    }
 }
 
 #[cfg(test)]
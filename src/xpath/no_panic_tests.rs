@@ -0,0 +1,703 @@
+//! Property tests checking that evaluating and formatting expressions never panics.
+//!
+//! Expressions are generated as text from the grammar and parsed without static analysis,
+//! so the resulting ASTs include constructs that cannot be evaluated yet.
+//! Syntax trees are also built directly, to reach values the parser never produces,
+//! such as negative or non-finite literals and names with any characters in them.
+
+use proptest::prelude::*;
+
+use crate::{
+    html,
+    xpath::{
+        builder::{comparison, expr, expr_single, primary_path, string_concat},
+        grammar::{
+            data_model::XpathItem,
+            expressions::{
+                common::{Argument, ArgumentList},
+                comparison_expressions::{
+                    ComparisonExpr, ComparisonExprPair, ComparisonType, GeneralComp, NodeComp,
+                    ValueComp,
+                },
+                logical_expressions::{AndExpr, OrExpr},
+                maps_and_arrays::lookup_operator::{
+                    postfix_lookup::Lookup,
+                    unary_lookup::{KeySpecifier, UnaryLookup},
+                },
+                path_expressions::{
+                    abbreviated_syntax::AbbrevForwardStep,
+                    steps::{
+                        axes::{forward_axis::ForwardAxis, reverse_axis::ReverseAxis},
+                        axis_step::{AxisStep, AxisStepType},
+                        forward_step::ForwardStep,
+                        node_tests::{NameTest, NodeTest, Wildcard},
+                        reverse_step::ReverseStep,
+                        step_expr::StepExpr,
+                    },
+                    PathExpr, PathSeparator, RelativePathExpr, StepPair,
+                },
+                postfix_expressions::{PostfixExpr, PostfixExprItem, Predicate},
+                primary_expressions::{
+                    literals::{Literal, NumericLiteral},
+                    parenthesized_expressions::ParenthesizedExpr,
+                    static_function_calls::FunctionCall,
+                    variable_references::{VarName, VarRef},
+                    PrimaryExpr,
+                },
+                Expr, ExprSingle, Xpath,
+            },
+            terminal_symbols::{StringLiteral, UriQualifiedName},
+            types::{
+                attribute_test::AttributeTest, element_test::ElementTest, DocumentTest, EQName,
+                KindTest, PITest, PITestValue,
+            },
+            xml_names::{PrefixedName, QName},
+            xpath, XpathItemTreeNode,
+        },
+        XpathItemTree,
+    },
+};
+
+fn arb_leaf_expr() -> impl Strategy<Value = String> {
+    prop_oneof![
+        4 => arb_path(),
+        1 => any::<i32>().prop_map(|n| n.to_string()),
+        1 => (0u16..1000, 0u16..1000).prop_map(|(a, b)| format!("{}.{}", a, b)),
+        1 => "[a-z ]{0,5}".prop_map(|s| format!("'{}'", s)),
+        1 => Just(String::from(".")),
+        1 => Just(String::from("$x")),
+        1 => Just(String::from("root()")),
+        1 => Just(String::from("fn:root#0")),
+        1 => Just(String::from("?key")),
+        1 => Just(String::from("?*")),
+    ]
+}
+
+fn arb_path() -> impl Strategy<Value = String> {
+    // Supported axes and node tests are weighted up, so that most expressions evaluate.
+    let axis = prop_oneof![
+        8 => Just(""),
+        1 => Just("child::"),
+        1 => Just("descendant::"),
+        1 => Just("attribute::"),
+        1 => Just("self::"),
+        1 => Just("descendant-or-self::"),
+        2 => Just("parent::"),
+        4 => Just("@"),
+        1 => Just("following-sibling::"),
+        1 => Just("following::"),
+        1 => Just("namespace::"),
+        1 => Just("ancestor::"),
+        1 => Just("preceding-sibling::"),
+        1 => Just("preceding::"),
+        1 => Just("ancestor-or-self::"),
+    ];
+    let node_test = prop_oneof![
+        4 => Just("div"),
+        4 => Just("a"),
+        4 => Just("span"),
+        4 => Just("id"),
+        4 => Just("class"),
+        4 => Just("*"),
+        4 => Just("node()"),
+        4 => Just("text()"),
+        1 => Just("*:div"),
+        1 => Just("html:*"),
+        1 => Just("Q{http://www.w3.org/1999/xhtml}*"),
+        1 => Just("html:div"),
+        1 => Just("Q{http://www.w3.org/1999/xhtml}div"),
+        1 => Just("comment()"),
+        1 => Just("namespace-node()"),
+        1 => Just("document-node()"),
+        1 => Just("document-node(element(div))"),
+        1 => Just("element()"),
+        1 => Just("element(div)"),
+        1 => Just("attribute()"),
+        1 => Just("attribute(id)"),
+        1 => Just("attribute(*, xs:string)"),
+        1 => Just("schema-element(div)"),
+        1 => Just("schema-attribute(id)"),
+        1 => Just("processing-instruction()"),
+        1 => Just("processing-instruction(php)"),
+    ];
+    let leading = prop_oneof![Just(""), Just("/"), Just("//")];
+    let separator = prop_oneof![Just("/"), Just("//")];
+
+    (
+        leading,
+        prop::collection::vec((axis, node_test, separator), 1..4),
+    )
+        .prop_map(|(leading, steps)| {
+            let mut path = String::from(leading);
+            for (i, (axis, node_test, separator)) in steps.into_iter().enumerate() {
+                if i > 0 {
+                    path.push_str(separator);
+                }
+                path.push_str(axis);
+                path.push_str(node_test);
+            }
+            path
+        })
+}
+
+/// Paths using only the axes and node tests that can be evaluated.
+fn arb_supported_path() -> impl Strategy<Value = String> {
+    let axis = prop_oneof![
+        Just(""),
+        Just("child::"),
+        Just("descendant::"),
+        Just("self::"),
+        Just("descendant-or-self::"),
+        Just("parent::"),
+        Just("@"),
+    ];
+    let node_test = prop_oneof![
+        Just("div"),
+        Just("a"),
+        Just("span"),
+        Just("id"),
+        Just("class"),
+        Just("*"),
+        Just("node()"),
+        Just("text()"),
+        Just("comment()"),
+        Just("document-node()"),
+        Just("attribute()"),
+    ];
+    let leading = prop_oneof![Just(""), Just("/"), Just("//")];
+    let separator = prop_oneof![Just("/"), Just("//")];
+
+    (
+        leading,
+        prop::collection::vec((axis, node_test, separator), 1..4),
+    )
+        .prop_map(|(leading, steps)| {
+            let mut path = String::from(leading);
+            for (i, (axis, node_test, separator)) in steps.into_iter().enumerate() {
+                if i > 0 {
+                    path.push_str(separator);
+                }
+                path.push_str(axis);
+                path.push_str(node_test);
+            }
+            path
+        })
+}
+
+/// Expressions using only constructs that can be evaluated, so that evaluation goes deep.
+fn arb_supported_expr() -> impl Strategy<Value = String> {
+    let leaf = prop_oneof![
+        4 => arb_supported_path(),
+        1 => (1i64..5).prop_map(|n| n.to_string()),
+        1 => "[a-z ]{0,5}".prop_map(|s| format!("'{}'", s)),
+        1 => Just(String::from(".")),
+        1 => Just(String::from("root()")),
+    ];
+
+    leaf.prop_recursive(4, 32, 3, |inner| {
+        prop_oneof![
+            (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("({})[{}]", a, b)),
+            (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("({}) = ({})", a, b)),
+            (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("({}) != ({})", a, b)),
+            (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("({})/({})", a, b)),
+            (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("({})//({})", a, b)),
+            (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("({}, {})", a, b)),
+            (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("contains({}, {})", a, b)),
+            inner.clone().prop_map(|a| format!("root({})", a)),
+            inner
+                .clone()
+                .prop_map(|a| format!("({}) treat as node()", a)),
+        ]
+    })
+}
+
+fn arb_sequence_type() -> impl Strategy<Value = &'static str> {
+    prop_oneof![
+        Just("empty-sequence()"),
+        Just("item()"),
+        Just("node()"),
+        Just("node()?"),
+        Just("element()*"),
+        Just("text()+"),
+        Just("xs:string"),
+        Just("map(*)"),
+        Just("array(*)"),
+        Just("function(*)"),
+        Just("document-node()"),
+        Just("attribute(id)"),
+    ]
+}
+
+fn arb_expr() -> impl Strategy<Value = String> {
+    arb_leaf_expr().prop_recursive(4, 32, 3, |inner| {
+        let binary_operator = prop_oneof![
+            Just("or"),
+            Just("and"),
+            Just("="),
+            Just("!="),
+            Just("<"),
+            Just(">="),
+            Just("eq"),
+            Just("ne"),
+            Just("is"),
+            Just("<<"),
+            Just("||"),
+            Just("to"),
+            Just("+"),
+            Just("-"),
+            Just("*"),
+            Just("div"),
+            Just("idiv"),
+            Just("mod"),
+            Just("union"),
+            Just("|"),
+            Just("intersect"),
+            Just("except"),
+            Just("!"),
+            Just("/"),
+            Just("//"),
+        ];
+
+        prop_oneof![
+            8 => (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("({})[{}]", a, b)),
+            4 => (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("({}) = ({})", a, b)),
+            4 => (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("({})/({})", a, b)),
+            4 => (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("contains({}, {})", a, b)),
+            1 => (inner.clone(), binary_operator, inner.clone())
+                .prop_map(|(a, op, b)| format!("({}) {} ({})", a, op, b)),
+            1 => (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("({}, {})", a, b)),
+            1 => inner.clone().prop_map(|a| format!("-({})", a)),
+            1 => inner.clone().prop_map(|a| format!("({})?key", a)),
+            1 => inner.clone().prop_map(|a| format!("({})(1)", a)),
+            1 => (inner.clone(), inner.clone())
+                .prop_map(|(a, b)| format!("for $x in ({}) return ({})", a, b)),
+            1 => (inner.clone(), inner.clone())
+                .prop_map(|(a, b)| format!("let $x := ({}) return ({})", a, b)),
+            1 => (inner.clone(), inner.clone())
+                .prop_map(|(a, b)| format!("some $x in ({}) satisfies ({})", a, b)),
+            1 => (inner.clone(), inner.clone(), inner.clone())
+                .prop_map(|(a, b, c)| format!("if ({}) then ({}) else ({})", a, b, c)),
+            1 => (inner.clone(), arb_sequence_type())
+                .prop_map(|(a, t)| format!("({}) instance of {}", a, t)),
+            1 => (inner.clone(), arb_sequence_type())
+                .prop_map(|(a, t)| format!("({}) treat as {}", a, t)),
+            1 => inner
+                .clone()
+                .prop_map(|a| format!("({}) cast as xs:string?", a)),
+            1 => inner
+                .clone()
+                .prop_map(|a| format!("({}) castable as xs:integer", a)),
+            1 => inner
+                .clone()
+                .prop_map(|a| format!("({}) => string-length()", a)),
+            1 => inner.clone().prop_map(|a| format!("root({})", a)),
+            1 => (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("map {{ {} : {} }}", a, b)),
+            1 => (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("[{}, {}]", a, b)),
+            1 => inner.clone().prop_map(|a| format!("array {{ {} }}", a)),
+            1 => inner
+                .clone()
+                .prop_map(|a| format!("function($x) {{ {} }}", a)),
+            1 => inner.clone().prop_map(|a| format!("contains({}, ?)", a)),
+        ]
+    })
+}
+
+/// Names with any characters in them, as well as names that exist in the generated documents.
+fn arb_name() -> BoxedStrategy<String> {
+    prop_oneof![
+        4 => prop_oneof![Just("div"), Just("a"), Just("span"), Just("id"), Just("class")]
+            .prop_map(String::from),
+        1 => "\\PC{0,6}",
+    ]
+    .boxed()
+}
+
+fn arb_eq_name() -> BoxedStrategy<EQName> {
+    prop_oneof![
+        4 => arb_name().prop_map(|name| EQName::QName(QName::UnprefixedName(name))),
+        1 => (arb_name(), arb_name()).prop_map(|(prefix, local_part)| {
+            EQName::QName(QName::PrefixedName(PrefixedName { prefix, local_part }))
+        }),
+        1 => ("\\PC{0,6}", arb_name())
+            .prop_map(|(uri, name)| EQName::UriQualifiedName(UriQualifiedName { uri, name })),
+    ]
+    .boxed()
+}
+
+fn arb_node_test() -> BoxedStrategy<NodeTest> {
+    let wildcard = prop_oneof![
+        Just(Wildcard::Simple),
+        arb_name().prop_map(Wildcard::PrefixedName),
+        arb_name().prop_map(Wildcard::SuffixedName),
+        "\\PC{0,6}".prop_map(Wildcard::BracedUri),
+    ];
+    let kind_test = prop_oneof![
+        Just(KindTest::AnyKindTest),
+        Just(KindTest::TextTest),
+        Just(KindTest::CommentTest),
+        Just(KindTest::NamespaceNodeTest),
+        Just(KindTest::DocumentTest(DocumentTest { value: None })),
+        Just(KindTest::ElementTest(ElementTest { item: None })),
+        Just(KindTest::AttributeTest(AttributeTest { pair: None })),
+        prop::option::of(arb_name()).prop_map(|name| KindTest::PITest(PITest {
+            val: name.map(PITestValue::NCName),
+        })),
+    ];
+
+    prop_oneof![
+        4 => arb_eq_name().prop_map(|name| NodeTest::NameTest(NameTest::Name(name))),
+        1 => wildcard.prop_map(|wildcard| NodeTest::NameTest(NameTest::Wildcard(wildcard))),
+        2 => kind_test.prop_map(NodeTest::KindTest),
+    ]
+    .boxed()
+}
+
+fn arb_axis_step_type() -> BoxedStrategy<AxisStepType> {
+    let forward_axis = prop_oneof![
+        Just(ForwardAxis::Child),
+        Just(ForwardAxis::Descendant),
+        Just(ForwardAxis::Attribute),
+        Just(ForwardAxis::SelfAxis),
+        Just(ForwardAxis::DescendantOrSelf),
+        Just(ForwardAxis::FollowingSibling),
+        Just(ForwardAxis::Following),
+        Just(ForwardAxis::Namespace),
+    ];
+    let reverse_axis = prop_oneof![
+        Just(ReverseAxis::Parent),
+        Just(ReverseAxis::Ancestor),
+        Just(ReverseAxis::PrecedingSibling),
+        Just(ReverseAxis::Preceding),
+        Just(ReverseAxis::AncestorOrSelf),
+    ];
+
+    prop_oneof![
+        4 => (any::<bool>(), arb_node_test()).prop_map(|(has_at, node_test)| {
+            AxisStepType::ForwardStep(ForwardStep::Abbreviated(AbbrevForwardStep {
+                has_at,
+                node_test,
+            }))
+        }),
+        2 => (forward_axis, arb_node_test()).prop_map(|(axis, node_test)| {
+            AxisStepType::ForwardStep(ForwardStep::Full(axis, node_test))
+        }),
+        1 => (reverse_axis, arb_node_test()).prop_map(|(axis, node_test)| {
+            AxisStepType::ReverseStep(ReverseStep::Full(axis, node_test))
+        }),
+        1 => Just(AxisStepType::ReverseStep(ReverseStep::Abbreviated)),
+    ]
+    .boxed()
+}
+
+fn arb_literal() -> BoxedStrategy<Literal> {
+    prop_oneof![
+        any::<i64>().prop_map(|x| Literal::NumericLiteral(NumericLiteral::Integer(x))),
+        any::<f32>().prop_map(|x| Literal::NumericLiteral(NumericLiteral::Decimal(x))),
+        any::<f64>().prop_map(|x| Literal::NumericLiteral(NumericLiteral::Double(x))),
+        "\\PC{0,6}".prop_map(|x| Literal::StringLiteral(StringLiteral::new(&x))),
+    ]
+    .boxed()
+}
+
+fn arb_key_specifier() -> BoxedStrategy<KeySpecifier> {
+    prop_oneof![
+        arb_name().prop_map(KeySpecifier::Name),
+        any::<u32>().prop_map(KeySpecifier::Integer),
+        Just(KeySpecifier::Wildcard),
+    ]
+    .boxed()
+}
+
+fn arb_comparison_type() -> BoxedStrategy<ComparisonType> {
+    prop_oneof![
+        Just(ComparisonType::GeneralComp(GeneralComp::Equal)),
+        Just(ComparisonType::GeneralComp(GeneralComp::NotEqual)),
+        Just(ComparisonType::GeneralComp(GeneralComp::LessThan)),
+        Just(ComparisonType::GeneralComp(GeneralComp::GreaterThanEqualTo)),
+        Just(ComparisonType::ValueComp(ValueComp::Equal)),
+        Just(ComparisonType::ValueComp(ValueComp::GreaterThan)),
+        Just(ComparisonType::NodeComp(NodeComp::Is)),
+        Just(ComparisonType::NodeComp(NodeComp::Precedes)),
+    ]
+    .boxed()
+}
+
+fn arb_function_name() -> BoxedStrategy<EQName> {
+    prop_oneof![
+        4 => prop_oneof![
+            Just("root"),
+            Just("contains"),
+            Just("not"),
+            Just("string"),
+            Just("count"),
+            Just("text"),
+        ]
+        .prop_map(|name| EQName::QName(QName::UnprefixedName(String::from(name)))),
+        1 => arb_eq_name(),
+    ]
+    .boxed()
+}
+
+fn path_of(expr: Expr) -> PathExpr {
+    primary_path(PrimaryExpr::ParenthesizedExpr(ParenthesizedExpr(Some(
+        expr,
+    ))))
+}
+
+prop_compose! {
+    fn arb_predicate(inner: BoxedStrategy<Expr>)(expr in inner) -> Predicate {
+        Predicate(expr)
+    }
+}
+
+prop_compose! {
+    fn arb_axis_step(inner: BoxedStrategy<Expr>)(
+        step_type in arb_axis_step_type(),
+        predicates in prop::collection::vec(arb_predicate(inner), 0..3),
+    ) -> StepExpr {
+        StepExpr::AxisStep(AxisStep {
+            step_type,
+            predicates,
+        })
+    }
+}
+
+prop_compose! {
+    fn arb_postfix_step(inner: BoxedStrategy<Expr>)(
+        primary in prop_oneof![
+            4 => arb_literal().prop_map(PrimaryExpr::Literal),
+            2 => Just(PrimaryExpr::ContextItemExpr),
+            1 => arb_eq_name().prop_map(|name| PrimaryExpr::VarRef(VarRef(VarName(name)))),
+            1 => arb_key_specifier().prop_map(|key| PrimaryExpr::UnaryLookup(UnaryLookup(key))),
+            1 => Just(PrimaryExpr::ParenthesizedExpr(ParenthesizedExpr(None))),
+            2 => inner
+                .clone()
+                .prop_map(|expr| PrimaryExpr::ParenthesizedExpr(ParenthesizedExpr(Some(expr)))),
+            4 => (arb_function_name(), prop::collection::vec(inner.clone(), 0..3)).prop_map(
+                |(name, arguments)| {
+                    PrimaryExpr::FunctionCall(FunctionCall {
+                        name,
+                        argument_list: ArgumentList(
+                            arguments
+                                .into_iter()
+                                .map(|x| Argument::ExprSingle(x.expr))
+                                .collect(),
+                        ),
+                    })
+                }
+            ),
+        ],
+        items in prop::collection::vec(
+            prop_oneof![
+                arb_predicate(inner).prop_map(PostfixExprItem::Predicate),
+                arb_key_specifier().prop_map(|key| PostfixExprItem::Lookup(Lookup(key))),
+                Just(PostfixExprItem::ArgumentList(ArgumentList(vec![Argument::ArgumentPlaceHolder]))),
+            ],
+            0..2,
+        ),
+    ) -> StepExpr {
+        StepExpr::PostfixExpr(PostfixExpr {
+            expr: primary,
+            items,
+        })
+    }
+}
+
+fn arb_step(inner: BoxedStrategy<Expr>) -> BoxedStrategy<StepExpr> {
+    prop_oneof![
+        3 => arb_axis_step(inner.clone()),
+        1 => arb_postfix_step(inner),
+    ]
+    .boxed()
+}
+
+prop_compose! {
+    fn arb_path_expr(inner: BoxedStrategy<Expr>)(
+        leading in 0..4,
+        first in arb_step(inner.clone()),
+        items in prop::collection::vec(
+            (
+                prop_oneof![Just(PathSeparator::Slash), Just(PathSeparator::DoubleSlash)],
+                arb_step(inner),
+            )
+                .prop_map(|(separator, step)| StepPair(separator, step)),
+            0..3,
+        ),
+    ) -> PathExpr {
+        let relative = RelativePathExpr { expr: first, items };
+        match leading {
+            0 => PathExpr::LeadingSlash(None),
+            1 => PathExpr::LeadingSlash(Some(relative)),
+            2 => PathExpr::LeadingDoubleSlash(relative),
+            _ => PathExpr::Plain(relative),
+        }
+    }
+}
+
+prop_compose! {
+    fn arb_comparison_expr(inner: BoxedStrategy<Expr>)(
+        path in arb_path_expr(inner.clone()),
+        pair in prop::option::weighted(0.3, (arb_comparison_type(), inner)),
+    ) -> ComparisonExpr {
+        comparison(
+            path,
+            pair.map(|(comparison_type, other)| {
+                ComparisonExprPair(comparison_type, string_concat(path_of(other)))
+            }),
+        )
+    }
+}
+
+prop_compose! {
+    fn arb_and_expr(inner: BoxedStrategy<Expr>)(
+        expr in arb_comparison_expr(inner.clone()),
+        items in prop::collection::vec(arb_comparison_expr(inner), 0..2),
+    ) -> AndExpr {
+        AndExpr { expr, items }
+    }
+}
+
+prop_compose! {
+    fn arb_expr_single(inner: BoxedStrategy<Expr>)(
+        expr in arb_and_expr(inner.clone()),
+        items in prop::collection::vec(arb_and_expr(inner), 0..2),
+    ) -> ExprSingle {
+        ExprSingle::OrExpr(Box::new(OrExpr { expr, items }))
+    }
+}
+
+/// Syntax trees built directly rather than parsed.
+fn arb_expr_tree() -> impl Strategy<Value = Expr> {
+    let leaf = prop_oneof![
+        arb_axis_step_type().prop_map(|step_type| {
+            expr(comparison(
+                PathExpr::Plain(RelativePathExpr {
+                    expr: StepExpr::AxisStep(AxisStep {
+                        step_type,
+                        predicates: Vec::new(),
+                    }),
+                    items: Vec::new(),
+                }),
+                None,
+            ))
+        }),
+        arb_literal().prop_map(|literal| expr(comparison(
+            primary_path(PrimaryExpr::Literal(literal)),
+            None
+        ))),
+        Just(expr(comparison(PathExpr::LeadingSlash(None), None))),
+    ];
+
+    leaf.prop_recursive(3, 24, 3, |inner| {
+        let inner = inner.boxed();
+        (
+            arb_expr_single(inner.clone()),
+            prop::collection::vec(
+                inner.prop_map(|x| expr_single(comparison(path_of(x), None))),
+                0..2,
+            ),
+        )
+            .prop_map(|(expr, items)| Expr { expr, items })
+    })
+}
+
+fn arb_html() -> impl Strategy<Value = String> {
+    let leaf = prop_oneof![
+        "[a-z0-9 ]{0,8}",
+        "[a-z ]{0,8}".prop_map(|s| format!("<!--{}-->", s)),
+        Just(String::from("<br>")),
+    ];
+
+    let content = leaf.prop_recursive(4, 24, 4, |inner| {
+        let tag = prop_oneof![Just("div"), Just("span"), Just("a"), Just("p")];
+        let attributes = prop::collection::vec(
+            (
+                prop_oneof![Just("id"), Just("class"), Just("href")],
+                "[a-z ]{0,6}",
+            ),
+            0..3,
+        );
+
+        (tag, attributes, prop::collection::vec(inner, 0..4)).prop_map(
+            |(tag, attributes, children)| {
+                let attributes: String = attributes
+                    .into_iter()
+                    .map(|(name, value)| format!(r#" {}="{}""#, name, value))
+                    .collect();
+                format!("<{0}{1}>{2}</{0}>", tag, attributes, children.concat())
+            },
+        )
+    });
+
+    prop::collection::vec(content, 0..4)
+        .prop_map(|body| format!("<html><body>{}</body></html>", body.concat()))
+}
+
+/// Format an item every way it can be formatted.
+fn format_item(item: &XpathItem, tree: &XpathItemTree) -> String {
+    let shown = match item {
+        XpathItem::Node(node) => match node {
+            XpathItemTreeNode::DocumentNode(x) => x.to_string(),
+            XpathItemTreeNode::ElementNode(x) => x.name.clone(),
+            XpathItemTreeNode::PINode(x) => x.to_string(),
+            XpathItemTreeNode::CommentNode(x) => x.to_string(),
+            XpathItemTreeNode::TextNode(x) => x.to_string(),
+            XpathItemTreeNode::AttributeNode(x) => x.to_string(),
+        },
+        XpathItem::Function(x) => x.to_string(),
+        XpathItem::AnyAtomicType(x) => x.to_string(),
+    };
+
+    format!("{:?} {} {}", item, shown, item.string_value(tree))
+}
+
+/// Parse, format and apply the expression, checking only that nothing panics.
+fn check_no_panic(expr: &str, html_text: &str) -> Result<(), TestCaseError> {
+    let (remaining, xpath) = match xpath(expr) {
+        Ok(res) => res,
+        // Not every generated expression is valid, and parse errors are covered elsewhere.
+        Err(_) => return Ok(()),
+    };
+    prop_assume!(remaining.trim().is_empty());
+
+    check_xpath_no_panic(&xpath, html_text)
+}
+
+/// Format and apply a syntax tree, checking only that nothing panics.
+fn check_xpath_no_panic(xpath: &Xpath, html_text: &str) -> Result<(), TestCaseError> {
+    let document = html::parse(html_text).unwrap();
+    let tree = XpathItemTree::from(&document);
+
+    let _ = xpath.to_string();
+    let _ = format!("{:?}", xpath);
+
+    match xpath.apply(&tree) {
+        Ok(items) => {
+            for item in items.iter() {
+                format_item(item, &tree);
+            }
+        }
+        Err(err) => {
+            let _ = err.to_string();
+        }
+    }
+
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn apply_should_not_panic(expr in arb_expr(), html_text in arb_html()) {
+        check_no_panic(&expr, &html_text)?;
+    }
+
+    #[test]
+    fn apply_supported_should_not_panic(expr in arb_supported_expr(), html_text in arb_html()) {
+        check_no_panic(&expr, &html_text)?;
+    }
+
+    #[test]
+    fn apply_tree_should_not_panic(expr in arb_expr_tree(), html_text in arb_html()) {
+        check_xpath_no_panic(&Xpath(expr, None), &html_text)?;
+    }
+}
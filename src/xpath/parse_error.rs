@@ -0,0 +1,465 @@
+//! Errors that occur when parsing an [Xpath](crate::xpath::Xpath) expression.
+
+use std::fmt::Display;
+
+use nom::error::ErrorKind;
+
+use crate::xpath::{
+    grammar::recipes::{Expected, GrammarError},
+    ErrorCode,
+};
+
+/// Where in the expression text a parse error occurred.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct ParsePosition {
+    /// Byte offset into the expression text.
+    pub offset: usize,
+
+    /// 1-based line number.
+    pub line: usize,
+
+    /// 1-based column number, counted in characters.
+    pub column: usize,
+}
+
+impl ParsePosition {
+    fn new(input: &str, offset: usize) -> Self {
+        let before = &input[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let column = before[line_start..].chars().count() + 1;
+
+        Self {
+            offset,
+            line,
+            column,
+        }
+    }
+}
+
+/// Error that occurs when parsing an [Xpath](crate::xpath::Xpath) expression.
+///
+/// Syntax errors carry the [ParsePosition] they occurred at,
+/// the part of the grammar being parsed and the tokens that were expected there.
+/// Use the alternate format (`{:#}`) or [ExpressionParseError::diagnostic]
+/// to show the offending line with a caret under the error.
+///
+/// # Example
+///
+/// ```rust
+/// use skyscraper::xpath;
+///
+/// let err = xpath::parse("//div[@class='item'").unwrap_err();
+///
+/// assert_eq!(err.position().unwrap().column, 20);
+/// assert_eq!(err.expected(), ["']'"]);
+/// assert_eq!(
+///     err.diagnostic(),
+///     "//div[@class='item'\n                   ^ expected ']'"
+/// );
+/// ```
+#[derive(PartialEq, Debug, Clone)]
+pub struct ExpressionParseError {
+    code: ErrorCode,
+    pub(crate) msg: String,
+
+    /// Where a syntax error occurred. Boxed to keep the error small.
+    location: Option<Box<SyntaxErrorLocation>>,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+struct SyntaxErrorLocation {
+    position: ParsePosition,
+    context: Option<&'static str>,
+    expected: Vec<String>,
+
+    /// The line of the expression text the error is on.
+    source_line: String,
+}
+
+impl ExpressionParseError {
+    /// Create a new [ExpressionParseError] that is not tied to a position in the expression text,
+    /// such as an error found by static analysis.
+    pub(crate) fn new(code: ErrorCode, msg: String) -> Self {
+        Self {
+            code,
+            msg,
+            location: None,
+        }
+    }
+
+    /// Create an error from the farthest failure of the grammar parsers.
+    ///
+    /// The input of `error` is the length of the input remaining where it failed.
+    pub(crate) fn from_grammar_error(input: &str, error: &GrammarError<usize>) -> Self {
+        let expected = error
+            .expected
+            .iter()
+            .filter_map(|expected| match expected {
+                // Whitespace and comments are allowed almost everywhere, so they are not worth listing.
+                Expected::Char(' ' | '\t' | '\r' | '\n') | Expected::Tag("(:") => None,
+                Expected::Char(c) => Some(format!("'{}'", c)),
+                Expected::Tag(tag) => Some(format!("'{}'", tag)),
+                Expected::Kind(ErrorKind::Digit) => Some(String::from("digit")),
+                Expected::Kind(ErrorKind::Eof) => Some(String::from("end of expression")),
+                // Other kinds are described by the context instead.
+                Expected::Kind(_) => None,
+            })
+            .fold(Vec::new(), |mut expected, token| {
+                if !expected.contains(&token) {
+                    expected.push(token);
+                }
+                expected
+            });
+
+        let remaining = &input[input.len() - error.input..];
+        let context = error
+            .contexts
+            .iter()
+            .copied()
+            .find(|context| *context != "comment")
+            // Helper parsers are labelled `rule::helper`; report the rule.
+            .map(|context| context.split("::").next().unwrap_or(context))
+            // The context of a failure to start an expression is one of many alternatives tried, so it is misleading.
+            .filter(|_| expected.len() <= MAX_LISTED_EXPECTED);
+        Self::at(input, remaining, context, expected)
+    }
+
+    /// Create an error for input left over after a complete expression was parsed.
+    pub(crate) fn from_remaining_input(input: &str, remaining: &str) -> Self {
+        Self::at(
+            input,
+            remaining,
+            None,
+            vec![String::from("end of expression")],
+        )
+    }
+
+    /// Create an error for an integer literal whose value is too large to be represented.
+    ///
+    /// The remaining input starts at the literal.
+    pub(crate) fn integer_overflow(input: &str, remaining: &str) -> Self {
+        let digits: String = remaining
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        let msg = format!("integer literal {} is too large", digits);
+
+        Self::located(input, remaining, ErrorCode::FOAR0002, msg, None, Vec::new())
+    }
+
+    fn at(
+        input: &str,
+        remaining: &str,
+        context: Option<&'static str>,
+        expected: Vec<String>,
+    ) -> Self {
+        let mut msg = format!("unexpected {}", describe_token(remaining));
+        if let Some(context) = context {
+            msg.push_str(&format!(" in {}", context.replace('_', " ")));
+        }
+        if !expected.is_empty() {
+            msg.push_str(&format!(", {}", describe_expected(&expected)));
+        }
+
+        Self::located(
+            input,
+            remaining,
+            ErrorCode::XPST0003,
+            msg,
+            context,
+            expected,
+        )
+    }
+
+    fn located(
+        input: &str,
+        remaining: &str,
+        code: ErrorCode,
+        msg: String,
+        context: Option<&'static str>,
+        expected: Vec<String>,
+    ) -> Self {
+        let offset = input.len() - remaining.len();
+        let position = ParsePosition::new(input, offset);
+        let source_line = input.lines().nth(position.line - 1).unwrap_or("");
+
+        Self {
+            code,
+            msg,
+            location: Some(Box::new(SyntaxErrorLocation {
+                position,
+                context,
+                expected,
+                source_line: source_line.to_string(),
+            })),
+        }
+    }
+
+    /// The code identifying the error.
+    ///
+    /// Syntax errors have the code [ErrorCode::XPST0003],
+    /// and integer literals too large to be represented have the code [ErrorCode::FOAR0002].
+    pub fn code(&self) -> &ErrorCode {
+        &self.code
+    }
+
+    /// Where in the expression text the error occurred.
+    ///
+    /// Errors found after the expression was parsed, like calls to unknown functions, have no position.
+    pub fn position(&self) -> Option<ParsePosition> {
+        self.location.as_ref().map(|location| location.position)
+    }
+
+    /// The label of the grammar rule that was being parsed, such as `predicate`.
+    pub fn context(&self) -> Option<&str> {
+        self.location.as_ref().and_then(|location| location.context)
+    }
+
+    /// The tokens that would have allowed parsing to continue.
+    pub fn expected(&self) -> &[String] {
+        match &self.location {
+            Some(location) => &location.expected,
+            None => &[],
+        }
+    }
+
+    /// The message, prefixed with the code for errors found by static analysis.
+    ///
+    /// Syntax errors are described by their position instead.
+    fn description(&self) -> String {
+        match (&self.location, &self.code) {
+            (Some(_), _) | (None, ErrorCode::Unsupported) => self.msg.clone(),
+            (None, code) => format!("{} {}", code, self.msg),
+        }
+    }
+
+    /// Render the line of the expression with a caret under the error.
+    ///
+    /// Errors without a position render as just their message.
+    pub fn diagnostic(&self) -> String {
+        let SyntaxErrorLocation {
+            position,
+            expected,
+            source_line,
+            ..
+        } = match &self.location {
+            Some(location) => location.as_ref(),
+            None => return self.description(),
+        };
+
+        // Keep tabs so the caret lines up with the source line however tabs are displayed.
+        let padding: String = source_line
+            .chars()
+            .take(position.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        let label = if expected.is_empty() {
+            self.msg.clone()
+        } else {
+            describe_expected(expected)
+        };
+
+        format!("{}\n{}^ {}", source_line, padding, label)
+    }
+}
+
+impl Display for ExpressionParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.position() {
+            Some(position) => write!(
+                f,
+                "Error parsing expression at line {}, column {}: {}",
+                position.line, position.column, self.msg
+            )?,
+            None => write!(f, "Error parsing expression: {}", self.description())?,
+        }
+
+        if f.alternate() && self.location.is_some() {
+            write!(f, "\n{}", self.diagnostic())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ExpressionParseError {}
+
+/// Describe the token at the start of the remaining input.
+fn describe_token(remaining: &str) -> String {
+    let word: String = remaining
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+
+    if !word.is_empty() {
+        format!("'{}'", word)
+    } else {
+        match remaining.chars().next() {
+            Some(c) if c.is_whitespace() => String::from("whitespace"),
+            Some(c) => format!("'{}'", c),
+            None => String::from("end of expression"),
+        }
+    }
+}
+
+/// Beyond this many tokens the parser was at the start of an expression,
+/// and listing every token that can start one is not helpful.
+const MAX_LISTED_EXPECTED: usize = 8;
+
+fn describe_expected(expected: &[String]) -> String {
+    match expected {
+        [single] => format!("expected {}", single),
+        _ if expected.len() > MAX_LISTED_EXPECTED => String::from("expected an expression"),
+        _ => format!("expected one of {}", expected.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::xpath::parse;
+
+    use super::*;
+
+    #[test]
+    fn parse_error_should_point_at_missing_bracket() {
+        // arrange
+        let text = "//div[@class='item'";
+
+        // act
+        let err = parse(text).unwrap_err();
+
+        // assert
+        assert_eq!(
+            err.position(),
+            Some(ParsePosition {
+                offset: 19,
+                line: 1,
+                column: 20
+            })
+        );
+        assert_eq!(err.context(), Some("predicate"));
+        assert_eq!(err.expected(), ["']'"]);
+    }
+
+    #[test]
+    fn parse_error_should_report_line_and_column() {
+        // arrange
+        let text = "//div\n  /span[)]";
+
+        // act
+        let err = parse(text).unwrap_err();
+
+        // assert
+        let position = err.position().unwrap();
+        assert_eq!(position.line, 2);
+        assert_eq!(position.column, 9);
+        assert_eq!(position.offset, 14);
+    }
+
+    #[test]
+    fn parse_error_should_reject_remaining_input() {
+        // arrange
+        let text = "//div]";
+
+        // act
+        let err = parse(text).unwrap_err();
+
+        // assert
+        assert_eq!(err.position().unwrap().offset, 5);
+        assert_eq!(err.expected(), ["end of expression"]);
+        assert_eq!(
+            err.to_string(),
+            "Error parsing expression at line 1, column 6: unexpected ']', expected end of expression"
+        );
+    }
+
+    #[test]
+    fn parse_error_should_summarize_missing_expression() {
+        // arrange
+        let text = "//div[@class=]";
+
+        // act
+        let err = parse(text).unwrap_err();
+
+        // assert
+        assert_eq!(err.position().unwrap().column, 14);
+        assert_eq!(err.context(), None);
+        assert_eq!(
+            err.to_string(),
+            "Error parsing expression at line 1, column 14: unexpected ']', expected an expression"
+        );
+    }
+
+    #[test]
+    fn parse_error_should_report_integer_overflow_at_literal() {
+        // arrange
+        let text = "//li[99999999999999999999]";
+
+        // act
+        let err = parse(text).unwrap_err();
+
+        // assert
+        assert_eq!(err.code(), &ErrorCode::FOAR0002);
+        assert_eq!(err.position().unwrap().offset, 5);
+        assert_eq!(
+            err.to_string(),
+            "Error parsing expression at line 1, column 6: integer literal 99999999999999999999 is too large"
+        );
+    }
+
+    #[test]
+    fn parse_error_should_report_unsupported_axes_alike() {
+        // arrange
+        let cases = [
+            ("//li/following::li", "axis is not supported: following::li"),
+            ("//li/preceding::li", "axis is not supported: preceding::li"),
+        ];
+
+        for (text, expected) in cases {
+            // act
+            let err = parse(text).unwrap_err();
+
+            // assert
+            assert_eq!(err.code(), &ErrorCode::Unsupported, "{}", text);
+            assert_eq!(
+                err.to_string(),
+                format!("Error parsing expression: {}", expected)
+            );
+        }
+    }
+
+    #[test]
+    fn diagnostic_should_put_caret_under_error() {
+        // arrange
+        let text = "//div[@class='item'";
+
+        // act
+        let err = parse(text).unwrap_err();
+
+        // assert
+        assert_eq!(
+            format!("{:#}", err),
+            "Error parsing expression at line 1, column 20: unexpected end of expression in predicate, expected ']'\n\
+             //div[@class='item'\n\
+             \x20                  ^ expected ']'"
+        );
+    }
+
+    #[test]
+    fn diagnostic_should_be_message_for_static_errors() {
+        // arrange
+        let text = "//div[foo()]";
+
+        // act
+        let err = parse(text).unwrap_err();
+
+        // assert
+        assert_eq!(err.position(), None);
+        assert_eq!(
+            err.diagnostic(),
+            "err:XPST0017 Unknown function Q{http://www.w3.org/2005/xpath-functions}foo#0"
+        );
+    }
+}
@@ -0,0 +1,559 @@
+//! Plan the evaluation of path expressions that can be answered by the indexes of an [XpathItemTree].
+//!
+//! A path such as `//div[@id='main']` is defined as `/descendant-or-self::node()/child::div[@id='main']`,
+//! so evaluating it as written visits every node in the document.
+//! When the first step after a leading `//` selects elements by name, or `*`,
+//! and each of its predicates is one the indexes can answer, the step is looked up instead:
+//!
+//! - `[@id='value']`
+//! - `[@class='value']`
+//! - `[contains(@class, 'text')]`, where the text is not empty and has no whitespace.
+//!
+//! Lookups return elements in document order, so the rest of the path is evaluated as usual.
+//! Predicates are only answered from the indexes when they compare strings by codepoint,
+//! which is what the indexes store.
+
+use std::fmt::Display;
+
+use crate::xpath::{
+    collation::CODEPOINT_COLLATION,
+    explain::TraceId,
+    functions::{ExpandedName, FN_NAMESPACE},
+    grammar::{
+        data_model::XpathItem,
+        expressions::{
+            common::Argument,
+            comparison_expressions::{
+                ComparisonExpr, ComparisonExprPair, ComparisonType, GeneralComp,
+            },
+            path_expressions::{
+                abbreviated_syntax::AbbrevForwardStep,
+                eval_steps,
+                steps::{
+                    axes::forward_axis::ForwardAxis,
+                    axis_step::{AxisStep, AxisStepType},
+                    forward_step::ForwardStep,
+                    node_tests::{NameTest, NodeTest, Wildcard},
+                    step_expr::StepExpr,
+                },
+                PathExpr, RelativePathExpr, StepPair,
+            },
+            postfix_expressions::{PostfixExpr, Predicate},
+            primary_expressions::{
+                literals::Literal, static_function_calls::FunctionCall, PrimaryExpr,
+            },
+            string_concat_expressions::StringConcatExpr,
+            ExprSingle,
+        },
+        types::EQName,
+        xml_names::QName,
+        ElementCondition,
+    },
+    ExpressionApplyError, StaticContext, XpathExpressionContext, XpathItemSet,
+};
+
+/// A path with a leading `//` whose first step is looked up in the indexes of the tree.
+pub(crate) struct IndexedPath<'a> {
+    /// The step that is looked up.
+    step: &'a StepExpr,
+
+    /// The conditions equivalent to the step's node test and predicates.
+    conditions: Vec<ElementCondition<'a>>,
+
+    /// The steps evaluated from each element found by the lookup.
+    rest: &'a [StepPair],
+}
+
+impl<'a> IndexedPath<'a> {
+    /// Plan the evaluation of the path after a leading `//` in the given context.
+    ///
+    /// Returns `None` if the path has to be evaluated as written.
+    pub fn plan(expr: &'a RelativePathExpr, context: &XpathExpressionContext) -> Option<Self> {
+        // The path starts at the root of the context item's document.
+        match &context.item {
+            XpathItem::Node(node) if node.id().is_some() => {}
+            _ => return None,
+        }
+
+        let static_context = context.dynamic_context.static_context();
+        if static_context.default_collation() != CODEPOINT_COLLATION {
+            return None;
+        }
+
+        let step = match &expr.expr {
+            StepExpr::AxisStep(step) => step,
+            StepExpr::PostfixExpr(_) => return None,
+        };
+
+        let mut conditions = Vec::new();
+        if let Some(name) = child_element_test(step)? {
+            conditions.push(ElementCondition::Name(name));
+        }
+
+        for predicate in step.predicates.iter() {
+            conditions.push(predicate_condition(predicate, static_context)?);
+        }
+
+        Some(Self {
+            step: &expr.expr,
+            conditions,
+            rest: &expr.items,
+        })
+    }
+
+    pub fn eval<'tree>(
+        &self,
+        context: &XpathExpressionContext<'tree, '_>,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        let elements = self.lookup(context, TraceId::of(self.step))?;
+        if self.rest.is_empty() {
+            return Ok(elements);
+        }
+
+        // Evaluate the rest of the path from each element.
+        let mut items = XpathItemSet::new();
+        for (i, _item) in elements.iter().enumerate() {
+            let element_context = XpathExpressionContext::new(
+                context.item_tree,
+                context.dynamic_context,
+                context.budget,
+                context.tracer,
+                &elements,
+                i + 1,
+                context.is_root_level,
+            );
+            items.extend(eval_steps(&element_context, self.rest)?);
+            context.budget.check_result_size(items.len())?;
+        }
+
+        Ok(items)
+    }
+
+    /// Look up the elements selected by the first step, in document order.
+    ///
+    /// The lookup is traced as the given step.
+    pub fn lookup<'tree>(
+        &self,
+        context: &XpathExpressionContext<'tree, '_>,
+        id: TraceId,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        let lookup = || {
+            let id = match &context.item {
+                XpathItem::Node(node) => node.id().expect("planned for a node with an ID"),
+                _ => unreachable!("planned for a node"),
+            };
+
+            let mut items = XpathItemSet::new();
+            for element in context.item_tree.find_elements(id, &self.conditions) {
+                context.budget.visit_node()?;
+                items.insert(XpathItem::Node(element));
+                context.budget.check_result_size(items.len())?;
+            }
+
+            Ok(items)
+        };
+
+        match context.tracer {
+            Some(tracer) => tracer.trace_step(id, self, context.item_tree, lookup),
+            None => lookup(),
+        }
+    }
+}
+
+impl Display for IndexedPath<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "//{}", self.step)
+    }
+}
+
+/// Get the name tested by a step on the child axis that selects elements.
+///
+/// Returns `Some(None)` for a `*` wildcard, and `None` if the step selects anything else.
+fn child_element_test(step: &AxisStep) -> Option<Option<&str>> {
+    let node_test = match &step.step_type {
+        AxisStepType::ForwardStep(ForwardStep::Abbreviated(AbbrevForwardStep {
+            has_at: false,
+            node_test,
+        })) => node_test,
+        AxisStepType::ForwardStep(ForwardStep::Full(ForwardAxis::Child, node_test)) => node_test,
+        _ => return None,
+    };
+
+    match node_test {
+        NodeTest::NameTest(NameTest::Name(name)) => unprefixed_name(name).map(Some),
+        NodeTest::NameTest(NameTest::Wildcard(Wildcard::Simple)) => Some(None),
+        _ => None,
+    }
+}
+
+/// Get the condition an element must meet to match the predicate,
+/// if the predicate is one the indexes can answer.
+fn predicate_condition<'a>(
+    predicate: &'a Predicate,
+    static_context: &StaticContext,
+) -> Option<ElementCondition<'a>> {
+    if !predicate.0.items.is_empty() {
+        return None;
+    }
+
+    let comparison = comparison_of(&predicate.0.expr)?;
+    match &comparison.comparison {
+        // `@id='value'` or `'value'=@id`
+        Some(ComparisonExprPair(ComparisonType::GeneralComp(GeneralComp::Equal), other)) => {
+            let (attribute, value) = match (attribute_name(&comparison.expr), string_literal(other))
+            {
+                (Some(attribute), Some(value)) => (attribute, value),
+                _ => (attribute_name(other)?, string_literal(&comparison.expr)?),
+            };
+
+            match attribute {
+                "id" => Some(ElementCondition::Id(value)),
+                "class" => Some(ElementCondition::Class(value)),
+                _ => None,
+            }
+        }
+        Some(_) => None,
+
+        // `contains(@class, 'text')`
+        None => {
+            let function_call = function_call(&comparison.expr)?;
+            let name = static_context
+                .resolve_function_name(&function_call.name)
+                .ok()?;
+            if name != ExpandedName::new(FN_NAMESPACE, "contains")
+                || !static_context.functions().is_built_in(&name, 2)
+            {
+                return None;
+            }
+
+            let arguments = match function_call.argument_list.0.as_slice() {
+                [Argument::ExprSingle(first), Argument::ExprSingle(second)] => {
+                    (comparison_of(first)?, comparison_of(second)?)
+                }
+                _ => return None,
+            };
+            if arguments.0.comparison.is_some() || arguments.1.comparison.is_some() {
+                return None;
+            }
+
+            let text = string_literal(&arguments.1.expr)?;
+            let is_single_token = !text.is_empty() && !text.contains(char::is_whitespace);
+            if attribute_name(&arguments.0.expr)? != "class" || !is_single_token {
+                return None;
+            }
+
+            Some(ElementCondition::ClassContains(text))
+        }
+    }
+}
+
+/// Get the comparison an expression consists of.
+pub(crate) fn comparison_of(expr: &ExprSingle) -> Option<&ComparisonExpr> {
+    match expr {
+        ExprSingle::OrExpr(or) if or.items.is_empty() && or.expr.items.is_empty() => {
+            Some(&or.expr.expr)
+        }
+        _ => None,
+    }
+}
+
+/// Get the path expression an expression consists of.
+pub(crate) fn path_of(expr: &StringConcatExpr) -> Option<&PathExpr> {
+    let range = &expr.expr;
+    if !expr.items.is_empty() || range.to_expr.is_some() {
+        return None;
+    }
+
+    let additive = &range.expr;
+    let multiplicative = &additive.expr;
+    let union = &multiplicative.expr;
+    let intersect = &union.expr;
+    let instanceof = &intersect.expr;
+    let treat = &instanceof.expr;
+    let castable = &treat.expr;
+    let cast = &castable.expr;
+    let arrow = &cast.expr;
+    let unary = &arrow.expr;
+    let simple_map = &unary.expr.0;
+
+    let is_path_only = additive.items.is_empty()
+        && multiplicative.items.is_empty()
+        && union.items.is_empty()
+        && intersect.items.is_empty()
+        && instanceof.instanceof_type.is_none()
+        && treat.treat_type.is_none()
+        && castable.cast_type.is_none()
+        && cast.cast.is_none()
+        && arrow.items.is_empty()
+        && unary.leading_symbols.is_empty()
+        && simple_map.items.is_empty();
+
+    is_path_only.then_some(&simple_map.expr)
+}
+
+/// Get the single step an expression consists of.
+fn single_step(expr: &StringConcatExpr) -> Option<&StepExpr> {
+    match path_of(expr)? {
+        PathExpr::Plain(path) if path.items.is_empty() => Some(&path.expr),
+        _ => None,
+    }
+}
+
+/// Get the name of the attribute selected by an expression such as `@id`.
+fn attribute_name(expr: &StringConcatExpr) -> Option<&str> {
+    let step = match single_step(expr)? {
+        StepExpr::AxisStep(step) if step.predicates.is_empty() => step,
+        _ => return None,
+    };
+
+    let node_test = match &step.step_type {
+        AxisStepType::ForwardStep(ForwardStep::Abbreviated(AbbrevForwardStep {
+            has_at: true,
+            node_test,
+        })) => node_test,
+        AxisStepType::ForwardStep(ForwardStep::Full(ForwardAxis::Attribute, node_test)) => {
+            node_test
+        }
+        _ => return None,
+    };
+
+    match node_test {
+        NodeTest::NameTest(NameTest::Name(name)) => unprefixed_name(name),
+        _ => None,
+    }
+}
+
+/// Get the value of an expression that is a string literal.
+fn string_literal(expr: &StringConcatExpr) -> Option<&str> {
+    match primary_expr(expr)? {
+        PrimaryExpr::Literal(Literal::StringLiteral(literal)) => Some(&literal.value),
+        _ => None,
+    }
+}
+
+/// Get the function call an expression consists of.
+pub(crate) fn function_call(expr: &StringConcatExpr) -> Option<&FunctionCall> {
+    match primary_expr(expr)? {
+        PrimaryExpr::FunctionCall(function_call) => Some(function_call),
+        _ => None,
+    }
+}
+
+fn primary_expr(expr: &StringConcatExpr) -> Option<&PrimaryExpr> {
+    match single_step(expr)? {
+        StepExpr::PostfixExpr(PostfixExpr { expr, items }) if items.is_empty() => Some(expr),
+        _ => None,
+    }
+}
+
+/// Get a name that has no namespace, as the names of HTML elements and attributes do.
+fn unprefixed_name(name: &EQName) -> Option<&str> {
+    match name {
+        EQName::QName(QName::UnprefixedName(name)) => Some(name),
+        EQName::UriQualifiedName(name) if name.uri.is_empty() => Some(&name.name),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        html,
+        xpath::{
+            self,
+            collation::HTML_ASCII_CASE_INSENSITIVE_COLLATION,
+            context::{DynamicContextBuilder, StaticContextBuilder},
+            grammar::expressions::xpath as parse_unchecked,
+            XpathItemTree,
+        },
+    };
+
+    use super::*;
+
+    fn plan_conditions(text: &str) -> Option<Vec<String>> {
+        let xpath = parse_unchecked(text).unwrap().1;
+        let path = match comparison_of(&xpath.0.expr).and_then(|x| path_of(&x.expr)) {
+            Some(PathExpr::LeadingDoubleSlash(path)) => path.clone(),
+            _ => panic!("not a path with a leading double slash: {}", text),
+        };
+
+        let document = html::parse("<html></html>").unwrap();
+        let tree = XpathItemTree::from(&document);
+        let dynamic_context = xpath::DynamicContext::default();
+        let budget = crate::xpath::limits::EvaluationBudget::new(dynamic_context.limits());
+        let context = XpathExpressionContext::new_single(
+            &tree,
+            &dynamic_context,
+            &budget,
+            None,
+            XpathItem::Node(tree.root()),
+            true,
+        );
+
+        IndexedPath::plan(&path, &context).map(|plan| {
+            plan.conditions
+                .iter()
+                .map(|condition| format!("{:?}", condition))
+                .collect()
+        })
+    }
+
+    /// Apply the expression, and the same expression written so it cannot be planned.
+    fn apply_planned_and_unplanned(html_text: &str, text: &str) -> (Vec<String>, Vec<String>) {
+        let document = html::parse(html_text).unwrap();
+        let tree = XpathItemTree::from(&document);
+
+        let apply = |text: &str| -> Vec<String> {
+            xpath::parse(text)
+                .unwrap()
+                .apply(&tree)
+                .unwrap()
+                .iter()
+                .map(|item| {
+                    let element = item.extract_as_node().extract_as_element_node();
+                    let id = element.get_attribute(&tree, "id").unwrap_or_default();
+                    format!("{}#{}", element.name, id)
+                })
+                .collect()
+        };
+
+        let unplanned = format!("/descendant-or-self::node()/{}", &text[2..]);
+        (apply(text), apply(&unplanned))
+    }
+
+    #[test]
+    fn plan_should_look_up_names() {
+        // act
+        let conditions = plan_conditions("//div");
+
+        // assert
+        assert_eq!(conditions, Some(vec![String::from(r#"Name("div")"#)]));
+    }
+
+    #[test]
+    fn plan_should_look_up_id_and_class_predicates() {
+        // act
+        let conditions = plan_conditions(
+            "//*[@id='main'][\"a b\"=@class][contains(@class, 'product')]/child::a",
+        );
+
+        // assert
+        assert_eq!(
+            conditions,
+            Some(vec![
+                String::from(r#"Id("main")"#),
+                String::from(r#"Class("a b")"#),
+                String::from(r#"ClassContains("product")"#),
+            ])
+        );
+    }
+
+    #[test]
+    fn plan_should_not_look_up_other_steps() {
+        for text in [
+            "//@id",
+            "//text()",
+            "//descendant::div",
+            "//div[1]",
+            "//div[@title='x']",
+            "//div[@id!='x']",
+            "//div[contains(@class, 'a b')]",
+            "//div[contains(@class, '')]",
+            "//div[contains(@id, 'main')]",
+            "//div[starts-with(@class, 'product')]",
+            "//div[@id='main' or @id='other']",
+            "//x:div",
+        ] {
+            // act
+            let conditions = plan_conditions(text);
+
+            // assert
+            assert_eq!(conditions, None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn plan_should_not_look_up_with_other_collations() {
+        // arrange
+        let static_context = StaticContextBuilder::new()
+            .with_default_collation(HTML_ASCII_CASE_INSENSITIVE_COLLATION)
+            .build();
+        let dynamic_context = DynamicContextBuilder::new(static_context).build();
+        let document = html::parse(r#"<html><div id="MAIN"></div></html>"#).unwrap();
+        let tree = XpathItemTree::from(&document);
+        let xpath = xpath::parse("//div[@id='main']").unwrap();
+
+        // act
+        let items = xpath.apply_with_context(&tree, &dynamic_context).unwrap();
+
+        // assert
+        assert_eq!(items.len(), 1);
+    }
+
+    #[test]
+    fn planned_path_should_match_unplanned_path() {
+        // arrange
+        let html_text = r#"
+            <html>
+                <div id="a" class="product featured">
+                    <div id="b" class="product-card"><a id="b1"></a></div>
+                    <span id="c" class="product"></span>
+                </div>
+                <div id="d" class="banner"><a id="d1"></a></div>
+                <div id="a" class="product"></div>
+            </html>"#;
+
+        for text in [
+            "//div",
+            "//*",
+            "//div[@id='a']",
+            "//*[@class='product']",
+            "//div[contains(@class, 'product')]",
+            "//div[contains(@class, 'card')]/a",
+            "//div[contains(@class, 'product')][@id='a']//a",
+            "//div[@id='missing']",
+        ] {
+            // act
+            let (planned, unplanned) = apply_planned_and_unplanned(html_text, text);
+
+            // assert
+            assert_eq!(planned, unplanned, "{}", text);
+        }
+    }
+
+    #[test]
+    fn planned_path_should_return_document_order() {
+        // arrange
+        let document =
+            html::parse(r#"<html><div id="1"><div id="2"></div></div><div id="3"></div></html>"#)
+                .unwrap();
+        let tree = XpathItemTree::from(&document);
+        let xpath = xpath::parse("(//div)[2]/@id").unwrap();
+
+        // act
+        let items = xpath.apply(&tree).unwrap();
+
+        // assert
+        let id = items[0].extract_as_node().extract_as_attribute_node();
+        assert_eq!(id.value, "2");
+    }
+
+    #[test]
+    fn planned_path_should_be_explained_as_one_step() {
+        // arrange
+        let document = html::parse(r#"<html><div id="main"><p></p></div></html>"#).unwrap();
+        let tree = XpathItemTree::from(&document);
+        let xpath = xpath::parse("//div[@id='main']/p").unwrap();
+
+        // act
+        let explanation = xpath.explain(&tree).unwrap();
+
+        // assert
+        let expressions: Vec<&str> = explanation
+            .entries()
+            .iter()
+            .map(|entry| entry.expression())
+            .collect();
+        assert_eq!(expressions, vec!["//div[@id='main']", "p"]);
+    }
+}
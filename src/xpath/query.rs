@@ -5,7 +5,8 @@
 use thiserror::Error;
 
 use crate::xpath::{
-    self, xpath_item_set::XpathItemSet, ExpressionApplyError, ExpressionParseError, XpathItemTree,
+    self, xpath_item_set::XpathItemSet, ErrorCode, ExpressionApplyError, ExpressionParseError,
+    XpathItemTree,
 };
 
 use super::{
@@ -26,7 +27,7 @@ impl Xpath {
             let attribute = item
                 .as_node()
                 .and_then(|node| node.as_attribute_node())
-                .map_err(|e| ExpressionApplyError::new(e.to_string()))?;
+                .map_err(|e| ExpressionApplyError::with_code(ErrorCode::XPTY0004, e.to_string()))?;
 
             attributes.push(attribute);
         }
@@ -46,7 +47,7 @@ impl Xpath {
             let element = item
                 .as_node()
                 .and_then(|node| node.as_element_node())
-                .map_err(|e| ExpressionApplyError::new(e.to_string()))?;
+                .map_err(|e| ExpressionApplyError::with_code(ErrorCode::XPTY0004, e.to_string()))?;
 
             elements.push(element);
         }
@@ -68,7 +69,7 @@ impl Xpath {
             let element = item
                 .as_node()
                 .and_then(|node| node.as_element_node())
-                .map_err(|e| ExpressionApplyError::new(e.to_string()))?;
+                .map_err(|e| ExpressionApplyError::with_code(ErrorCode::XPTY0004, e.to_string()))?;
 
             elements.push(element);
         }
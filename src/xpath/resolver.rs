@@ -0,0 +1,271 @@
+//! Loading resources named by URIs, such as the documents read by `fn:doc` and `fn:json-doc`.
+//!
+//! Expressions cannot load resources unless a [ResourceResolver] is set on the
+//! [DynamicContext](crate::xpath::DynamicContext) with
+//! [DynamicContextBuilder::with_resolver](crate::xpath::context::DynamicContextBuilder::with_resolver).
+//! [FileSystemResolver] loads files from a local directory.
+//!
+//! Documents are loaded once per evaluation; an expression calling `doc('a.html')` twice
+//! gets the same document node, and only calls the resolver the first time.
+//! Loaded documents are added to the [XpathItemTree] the expression is applied to,
+//! until they are dropped with [XpathItemTree::clear_documents].
+//!
+//! # Example
+//!
+//! ```rust
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! use skyscraper::html;
+//! use skyscraper::xpath::{
+//!     self,
+//!     context::DynamicContextBuilder,
+//!     grammar::data_model::{AnyAtomicType, XpathItem},
+//!     resolver::ResourceResolver,
+//!     ErrorCode, ExpressionApplyError, StaticContext, XpathItemTree,
+//! };
+//!
+//! struct Offers;
+//!
+//! impl ResourceResolver for Offers {
+//!     fn resolve_text(&self, uri: &str) -> Result<String, ExpressionApplyError> {
+//!         match uri {
+//!             "offers.json" => Ok(String::from(r#"{"price": 12}"#)),
+//!             _ => Err(ExpressionApplyError::with_code(
+//!                 ErrorCode::FODC0002,
+//!                 format!("{} not found", uri),
+//!             )),
+//!         }
+//!     }
+//! }
+//!
+//! let context = DynamicContextBuilder::new(StaticContext::new())
+//!     .with_resolver(Offers)
+//!     .build();
+//!
+//! let document = html::parse("<html></html>")?;
+//! let tree = XpathItemTree::from(&document);
+//!
+//! let xpath = xpath::parse("json-doc('offers.json')?price")?;
+//! let items = xpath.apply_with_context(&tree, &context)?;
+//!
+//! assert_eq!(items[0], XpathItem::AnyAtomicType(AnyAtomicType::Double(12.0.into())));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    html,
+    xpath::{ErrorCode, ExpressionApplyError, XpathItemTree},
+};
+
+/// Loads the resources named by URIs in expressions.
+///
+/// Only [ResourceResolver::resolve_text] must be implemented.
+/// Implementations should fail with [ErrorCode::FODC0002] when a resource cannot be loaded.
+pub trait ResourceResolver: Send + Sync {
+    /// Get the text of the resource at the URI.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The URI as written in the expression.
+    fn resolve_text(&self, uri: &str) -> Result<String, ExpressionApplyError>;
+
+    /// Get the document at the URI.
+    ///
+    /// By default, the text from [ResourceResolver::resolve_text] is parsed as HTML.
+    /// Override this to return documents that were already parsed.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The URI as written in the expression.
+    fn resolve_document(&self, uri: &str) -> Result<XpathItemTree, ExpressionApplyError> {
+        let text = self.resolve_text(uri)?;
+        let document = html::parse(&text).map_err(|e| {
+            ExpressionApplyError::with_code(
+                ErrorCode::FODC0002,
+                format!("cannot parse {} as HTML: {}", uri, e),
+            )
+        })?;
+
+        Ok(XpathItemTree::from(&document))
+    }
+
+    /// Get the URIs of the documents in a collection.
+    ///
+    /// The URIs are loaded with [ResourceResolver::resolve_document].
+    /// By default there are no collections.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The URI of the collection, or `None` for the default collection.
+    fn resolve_collection(&self, uri: Option<&str>) -> Result<Vec<String>, ExpressionApplyError> {
+        Err(not_found(&format!(
+            "collection {} is not available",
+            uri.unwrap_or("()")
+        )))
+    }
+}
+
+/// Loads resources from files in a local directory.
+///
+/// URIs are paths relative to the base directory, or `file://` URIs.
+/// Files outside the base directory cannot be loaded, so expressions from untrusted sources
+/// can only read the files the base directory contains.
+///
+/// A collection is a directory, and contains the `.html` and `.htm` files directly inside it,
+/// in order of their names. The default collection is the base directory.
+///
+/// # Example
+///
+/// ```rust
+/// use skyscraper::xpath::{context::DynamicContextBuilder, resolver::FileSystemResolver, StaticContext};
+///
+/// let context = DynamicContextBuilder::new(StaticContext::new())
+///     .with_resolver(FileSystemResolver::new("pages"))
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileSystemResolver {
+    base_dir: PathBuf,
+}
+
+impl FileSystemResolver {
+    /// Create a resolver that loads files from the given directory.
+    pub fn new(base_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            base_dir: base_dir.into(),
+        }
+    }
+
+    /// The directory files are loaded from.
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
+
+    /// Get the path of the file named by the URI.
+    fn path(&self, uri: &str) -> Result<PathBuf, ExpressionApplyError> {
+        let relative = uri.strip_prefix("file://").unwrap_or(uri);
+        if relative.contains("://") {
+            return Err(not_found(&format!(
+                "cannot load {}: only files can be loaded",
+                uri
+            )));
+        }
+
+        let base_dir = fs::canonicalize(&self.base_dir).map_err(|e| {
+            not_found(&format!(
+                "cannot load {}: base directory {}: {}",
+                uri,
+                self.base_dir.display(),
+                e
+            ))
+        })?;
+        let path = fs::canonicalize(base_dir.join(relative))
+            .map_err(|e| not_found(&format!("cannot load {}: {}", uri, e)))?;
+
+        if !path.starts_with(&base_dir) {
+            return Err(not_found(&format!(
+                "cannot load {}: outside of the base directory",
+                uri
+            )));
+        }
+
+        Ok(path)
+    }
+}
+
+impl ResourceResolver for FileSystemResolver {
+    fn resolve_text(&self, uri: &str) -> Result<String, ExpressionApplyError> {
+        let path = self.path(uri)?;
+        fs::read_to_string(&path).map_err(|e| not_found(&format!("cannot load {}: {}", uri, e)))
+    }
+
+    fn resolve_collection(&self, uri: Option<&str>) -> Result<Vec<String>, ExpressionApplyError> {
+        let uri = uri.unwrap_or(".");
+        let dir = self.path(uri)?;
+        let entries =
+            fs::read_dir(&dir).map_err(|e| not_found(&format!("cannot list {}: {}", uri, e)))?;
+
+        let mut names = Vec::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|e| not_found(&format!("cannot list {}: {}", uri, e)))?
+                .path();
+
+            let is_html = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .map(|extension| {
+                    extension.eq_ignore_ascii_case("html") || extension.eq_ignore_ascii_case("htm")
+                })
+                .unwrap_or(false);
+
+            if is_html && path.is_file() {
+                if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort();
+
+        // Name the documents relative to the base directory, as the collection was named.
+        let prefix = uri.trim_end_matches('/');
+        Ok(names
+            .into_iter()
+            .map(|name| format!("{}/{}", prefix, name))
+            .collect())
+    }
+}
+
+fn not_found(msg: &str) -> ExpressionApplyError {
+    ExpressionApplyError::with_code(ErrorCode::FODC0002, msg.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_system_resolver_should_not_load_outside_base_dir() {
+        // arrange
+        let resolver = FileSystemResolver::new("tests/documents");
+
+        // act
+        let err = resolver.resolve_text("../json_tests.rs").unwrap_err();
+
+        // assert
+        assert_eq!(err.code(), &ErrorCode::FODC0002);
+        assert!(err.message().contains("outside of the base directory"));
+    }
+
+    #[test]
+    fn file_system_resolver_should_not_load_other_schemes() {
+        // arrange
+        let resolver = FileSystemResolver::new("tests/documents");
+
+        // act
+        let err = resolver
+            .resolve_text("https://example.com/index.html")
+            .unwrap_err();
+
+        // assert
+        assert_eq!(err.code(), &ErrorCode::FODC0002);
+    }
+
+    #[test]
+    fn file_system_resolver_should_list_html_files_in_order() {
+        // arrange
+        let resolver = FileSystemResolver::new("tests/documents");
+
+        // act
+        let uris = resolver.resolve_collection(Some("products")).unwrap();
+
+        // assert
+        assert_eq!(uris, vec!["products/lamp.html", "products/table.html"]);
+    }
+}
@@ -0,0 +1,288 @@
+//! Locate sub-expressions in the text an [Xpath] expression was parsed from.
+//!
+//! The syntax tree does not record where its nodes were written,
+//! so a node is located by walking the tree alongside the displayed expression:
+//! each node's text is found within its parent's text, after its preceding siblings,
+//! which tells apart nodes with the same text.
+//! The displayed expression differs from the parsed text only in whitespace,
+//! so a range in it is mapped to the parsed text by pairing up the characters that are not whitespace.
+
+use std::{fmt::Display, mem, ops::Range};
+
+use crate::xpath::{
+    grammar::{
+        expressions::{
+            arithmetic_expressions::{AdditiveExpr, MultiplicativeExpr, UnaryExpr},
+            arrow_operator::ArrowExpr,
+            common::ArgumentList,
+            comparison_expressions::ComparisonExpr,
+            conditional_expressions::IfExpr,
+            expressions_on_sequence_types::{
+                cast::CastExpr, castable::CastableExpr, instance_of::InstanceofExpr,
+                treat::TreatExpr,
+            },
+            for_expressions::ForExpr,
+            let_expressions::LetExpr,
+            logical_expressions::{AndExpr, OrExpr},
+            maps_and_arrays::{
+                arrays::ArrayConstructor, lookup_operator::unary_lookup::KeySpecifier,
+                maps::MapConstructor,
+            },
+            path_expressions::{
+                steps::{axis_step::AxisStep, node_tests::NodeTest, step_expr::StepExpr},
+                PathExpr, RelativePathExpr,
+            },
+            postfix_expressions::{PostfixExpr, Predicate},
+            primary_expressions::{
+                enclosed_expressions::EnclosedExpr,
+                inline_function_expressions::InlineFunctionExpr,
+                literals::Literal,
+                named_function_references::NamedFunctionRef,
+                parenthesized_expressions::ParenthesizedExpr,
+                static_function_calls::FunctionCall,
+                variable_references::{VarName, VarRef},
+                FunctionItemExpr, PrimaryExpr,
+            },
+            quantified_expressions::QuantifiedExpr,
+            sequence_expressions::{
+                combining_node_sequences::{IntersectExceptExpr, UnionExpr},
+                constructing_sequences::RangeExpr,
+            },
+            simple_map_operator::SimpleMapExpr,
+            string_concat_expressions::StringConcatExpr,
+            Expr, ExprSingle,
+        },
+        types::sequence_type::SequenceType,
+    },
+    visit::{self, Visitor},
+    Xpath,
+};
+
+/// Locate a sub-expression of the given expression.
+///
+/// `node` is the address of the sub-expression's syntax tree node, if it is known,
+/// and `expression` is its displayed text.
+/// The range is in the text the expression was parsed from,
+/// or in the displayed expression if it was not parsed.
+pub(crate) fn locate(xpath: &Xpath, node: Option<usize>, expression: &str) -> Option<Range<usize>> {
+    let display = xpath.to_string();
+    let range = node
+        .and_then(|node| NodeFinder::find(xpath, &display, node, expression))
+        .or_else(|| find_token(&display, 0..display.len(), expression));
+
+    match &xpath.1 {
+        None => range,
+        Some(source) => range
+            .and_then(|range| map_to_source(&display, source, range))
+            .or_else(|| find_token(source, 0..source.len(), expression)),
+    }
+}
+
+/// Find the range of a syntax tree node in the displayed expression.
+struct NodeFinder<'a> {
+    display: &'a str,
+    node: usize,
+    expression: &'a str,
+
+    /// The range of the node being visited,
+    /// and where in it the next child is written.
+    parent: Range<usize>,
+
+    /// The range of the node, once found.
+    found: Option<Range<usize>>,
+
+    /// The range of the innermost node whose memory contains the node.
+    /// Nodes of types that are not visited are searched for in it.
+    container: Option<Range<usize>>,
+}
+
+impl<'a> NodeFinder<'a> {
+    fn find(
+        xpath: &Xpath,
+        display: &'a str,
+        node: usize,
+        expression: &'a str,
+    ) -> Option<Range<usize>> {
+        let mut finder = NodeFinder {
+            display,
+            node,
+            expression,
+            parent: 0..display.len(),
+            found: None,
+            container: None,
+        };
+        finder.visit_expr(&xpath.0);
+
+        finder
+            .found
+            .or_else(|| find_token(display, finder.container?, expression))
+    }
+
+    /// Find a node after its preceding siblings, then walk its children within it.
+    fn enter<T: Display>(&mut self, node: &T, walk: impl FnOnce(&mut Self)) {
+        if self.found.is_some() {
+            return;
+        }
+
+        let text = node.to_string();
+        let Some(range) = find_token(self.display, self.parent.clone(), &text) else {
+            // Walk the children as if they were written in the parent.
+            walk(self);
+            return;
+        };
+
+        let address = node as *const T as usize;
+        if address == self.node && text == self.expression {
+            self.found = Some(range);
+            return;
+        }
+
+        if (address..address + mem::size_of::<T>()).contains(&self.node) {
+            self.container = Some(range.clone());
+        }
+
+        let parent = mem::replace(&mut self.parent, range.clone());
+        walk(self);
+        self.parent = parent.start.max(range.end)..parent.end;
+    }
+}
+
+macro_rules! enter_nodes {
+    ($($visit:ident($ty:ty) => $walk:path;)*) => {
+        $(
+            fn $visit(&mut self, node: &$ty) {
+                self.enter(node, |finder| $walk(finder, node));
+            }
+        )*
+    };
+}
+
+/// Leaf nodes have no children to walk.
+fn walk_leaf<T>(_: &mut NodeFinder, _: &T) {}
+
+impl Visitor for NodeFinder<'_> {
+    enter_nodes! {
+        visit_expr(Expr) => visit::walk_expr;
+        visit_expr_single(ExprSingle) => visit::walk_expr_single;
+        visit_for_expr(ForExpr) => visit::walk_for_expr;
+        visit_let_expr(LetExpr) => visit::walk_let_expr;
+        visit_quantified_expr(QuantifiedExpr) => visit::walk_quantified_expr;
+        visit_if_expr(IfExpr) => visit::walk_if_expr;
+        visit_or_expr(OrExpr) => visit::walk_or_expr;
+        visit_and_expr(AndExpr) => visit::walk_and_expr;
+        visit_comparison_expr(ComparisonExpr) => visit::walk_comparison_expr;
+        visit_string_concat_expr(StringConcatExpr) => visit::walk_string_concat_expr;
+        visit_range_expr(RangeExpr) => visit::walk_range_expr;
+        visit_additive_expr(AdditiveExpr) => visit::walk_additive_expr;
+        visit_multiplicative_expr(MultiplicativeExpr) => visit::walk_multiplicative_expr;
+        visit_union_expr(UnionExpr) => visit::walk_union_expr;
+        visit_intersect_except_expr(IntersectExceptExpr) => visit::walk_intersect_except_expr;
+        visit_instanceof_expr(InstanceofExpr) => visit::walk_instanceof_expr;
+        visit_treat_expr(TreatExpr) => visit::walk_treat_expr;
+        visit_castable_expr(CastableExpr) => visit::walk_castable_expr;
+        visit_cast_expr(CastExpr) => visit::walk_cast_expr;
+        visit_arrow_expr(ArrowExpr) => visit::walk_arrow_expr;
+        visit_unary_expr(UnaryExpr) => visit::walk_unary_expr;
+        visit_simple_map_expr(SimpleMapExpr) => visit::walk_simple_map_expr;
+        visit_path_expr(PathExpr) => visit::walk_path_expr;
+        visit_relative_path_expr(RelativePathExpr) => visit::walk_relative_path_expr;
+        visit_step_expr(StepExpr) => visit::walk_step_expr;
+        visit_axis_step(AxisStep) => visit::walk_axis_step;
+        visit_node_test(NodeTest) => walk_leaf;
+        visit_predicate(Predicate) => visit::walk_predicate;
+        visit_postfix_expr(PostfixExpr) => visit::walk_postfix_expr;
+        visit_primary_expr(PrimaryExpr) => visit::walk_primary_expr;
+        visit_literal(Literal) => walk_leaf;
+        visit_var_ref(VarRef) => visit::walk_var_ref;
+        visit_var_name(VarName) => walk_leaf;
+        visit_parenthesized_expr(ParenthesizedExpr) => visit::walk_parenthesized_expr;
+        visit_enclosed_expr(EnclosedExpr) => visit::walk_enclosed_expr;
+        visit_function_call(FunctionCall) => visit::walk_function_call;
+        visit_argument_list(ArgumentList) => visit::walk_argument_list;
+        visit_function_item_expr(FunctionItemExpr) => visit::walk_function_item_expr;
+        visit_named_function_ref(NamedFunctionRef) => walk_leaf;
+        visit_inline_function_expr(InlineFunctionExpr) => visit::walk_inline_function_expr;
+        visit_map_constructor(MapConstructor) => visit::walk_map_constructor;
+        visit_array_constructor(ArrayConstructor) => visit::walk_array_constructor;
+        visit_key_specifier(KeySpecifier) => visit::walk_key_specifier;
+        visit_sequence_type(SequenceType) => walk_leaf;
+    }
+}
+
+/// Find the first occurrence of `token` in the given range of `text`
+/// that does not start or end in the middle of a name or number.
+fn find_token(text: &str, range: Range<usize>, token: &str) -> Option<Range<usize>> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let haystack = text.get(range.clone())?;
+
+    haystack.match_indices(token).find_map(|(i, _)| {
+        let start = range.start + i;
+        let end = start + token.len();
+        let splits_before = token.starts_with(is_word) && text[..start].ends_with(is_word);
+        let splits_after = token.ends_with(is_word) && text[end..].starts_with(is_word);
+
+        (!splits_before && !splits_after).then_some(start..end)
+    })
+}
+
+/// Map a range in the displayed expression to the text it was parsed from.
+///
+/// Returns `None` if the texts differ in more than whitespace.
+fn map_to_source(display: &str, source: &str, range: Range<usize>) -> Option<Range<usize>> {
+    let mut display_chars = display.char_indices().filter(|(_, c)| !c.is_whitespace());
+    let mut source_chars = source.char_indices().filter(|(_, c)| !c.is_whitespace());
+
+    let mut start = None;
+    let mut end = None;
+    loop {
+        match (display_chars.next(), source_chars.next()) {
+            (None, None) => break,
+            (Some((i, c)), Some((j, d))) if c == d => {
+                if range.contains(&i) {
+                    start.get_or_insert(j);
+                    end = Some(j + d.len_utf8());
+                }
+            }
+            _ => return None,
+        }
+    }
+
+    Some(start?..end?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_to_source_should_skip_whitespace() {
+        // arrange
+        let display = "//a[f(1, 2)]";
+        let source = " //a [ f( 1 ,2 ) ] ";
+
+        // act
+        let range = map_to_source(display, source, 4..11);
+
+        // assert
+        assert_eq!(range, Some(7..16));
+        assert_eq!(&source[7..16], "f( 1 ,2 )");
+    }
+
+    #[test]
+    fn map_to_source_should_fail_for_different_text() {
+        // act
+        let range = map_to_source("//a[1]", "//b[1]", 4..5);
+
+        // assert
+        assert_eq!(range, None);
+    }
+
+    #[test]
+    fn find_token_should_not_split_names() {
+        // act
+        let range = find_token("ancestor::a", 0..11, "a");
+
+        // assert
+        assert_eq!(range, Some(10..11));
+    }
+}
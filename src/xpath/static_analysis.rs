@@ -0,0 +1,733 @@
+//! Static analysis of [Xpath] expressions.
+//!
+//! <https://www.w3.org/TR/2017/REC-xpath-31-20170321/#id-static-analysis>
+//!
+//! Runs after an expression is parsed and before it is applied to a document,
+//! so mistakes such as calling an unknown function or referencing an unbound variable
+//! are reported when the expression is parsed instead of when it is applied.
+
+use std::fmt::Display;
+
+use crate::xpath::{
+    functions::ExpandedName,
+    grammar::{
+        expressions::{
+            arithmetic_expressions::{AdditiveExpr, MultiplicativeExpr, UnaryExpr},
+            arrow_operator::{ArrowExpr, ArrowFunctionSpecifier},
+            common::{Argument, ArgumentList},
+            comparison_expressions::{ComparisonExpr, ComparisonType},
+            expressions_on_sequence_types::{
+                cast::CastExpr, castable::CastableExpr, instance_of::InstanceofExpr,
+                treat::TreatExpr,
+            },
+            logical_expressions::{AndExpr, OrExpr},
+            maps_and_arrays::{
+                arrays::ArrayConstructor,
+                lookup_operator::{postfix_lookup::Lookup, unary_lookup::KeySpecifier},
+            },
+            path_expressions::{
+                steps::{
+                    axes::{forward_axis::ForwardAxis, reverse_axis::ReverseAxis},
+                    axis_step::{AxisStep, AxisStepType},
+                    forward_step::ForwardStep,
+                    node_tests::{NameTest, NodeTest, Wildcard},
+                    reverse_step::ReverseStep,
+                    step_expr::StepExpr,
+                },
+                PathExpr, RelativePathExpr,
+            },
+            postfix_expressions::{PostfixExpr, PostfixExprItem},
+            primary_expressions::{
+                static_function_calls::FunctionCall,
+                variable_references::{VarName, VarRef},
+                PrimaryExpr,
+            },
+            sequence_expressions::{
+                combining_node_sequences::{IntersectExceptExpr, UnionExpr},
+                constructing_sequences::RangeExpr,
+            },
+            simple_map_operator::SimpleMapExpr,
+            string_concat_expressions::StringConcatExpr,
+            Expr, ExprSingle, Xpath,
+        },
+        types::{
+            attribute_test::AttribNameOrWildcard,
+            sequence_type::{ItemType, SequenceType},
+            EQName, KindTest,
+        },
+        xml_names::QName,
+    },
+    ErrorCode, ExpressionApplyError, ExpressionParseError, StaticContext,
+};
+
+/// Analyze an expression against a static context.
+///
+/// Returns the first static error found, such as an unknown function (`err:XPST0017`),
+/// a variable that is not in scope (`err:XPST0008`) or an undeclared namespace prefix (`err:XPST0081`).
+/// If there are no static errors, returns an error for the first construct
+/// that cannot be evaluated yet.
+pub(crate) fn analyze(
+    xpath: &Xpath,
+    static_context: &StaticContext,
+) -> Result<(), ExpressionParseError> {
+    let mut analyzer = StaticAnalyzer {
+        static_context,
+        in_scope_variables: Vec::new(),
+        unsupported: None,
+    };
+
+    analyzer.expr(&xpath.0)?;
+
+    match analyzer.unsupported {
+        Some(msg) => Err(ExpressionParseError::new(ErrorCode::Unsupported, msg)),
+        None => Ok(()),
+    }
+}
+
+impl From<ExpressionApplyError> for ExpressionParseError {
+    fn from(value: ExpressionApplyError) -> Self {
+        ExpressionParseError::new(value.code().clone(), value.message().to_string())
+    }
+}
+
+struct StaticAnalyzer<'ctx> {
+    static_context: &'ctx StaticContext,
+
+    /// Variables bound by the enclosing `for`, `let`, `some` and `every` expressions.
+    in_scope_variables: Vec<ExpandedName>,
+
+    /// The first construct found that cannot be evaluated yet.
+    unsupported: Option<String>,
+}
+
+type AnalysisResult = Result<(), ExpressionParseError>;
+
+impl StaticAnalyzer<'_> {
+    /// Record a construct that cannot be evaluated yet.
+    ///
+    /// Analysis continues so that static errors later in the expression take precedence.
+    fn unsupported(&mut self, construct: &str, expr: &impl Display) {
+        if self.unsupported.is_none() {
+            self.unsupported = Some(format!("{} is not supported: {}", construct, expr));
+        }
+    }
+
+    /// Analyze `expr` with `var` bound, as `for`, `let`, `some` and `every` do.
+    fn with_variable(
+        &mut self,
+        var: &VarName,
+        analyze: impl FnOnce(&mut Self) -> AnalysisResult,
+    ) -> AnalysisResult {
+        let name = self.static_context.resolve_variable_name(&var.0)?;
+        self.in_scope_variables.push(name);
+        let result = analyze(self);
+        self.in_scope_variables.pop();
+        result
+    }
+
+    /// Analyze bindings in order, each of which is in scope for the bindings after it,
+    /// followed by the expression they are all in scope for.
+    fn bindings<'a>(
+        &mut self,
+        mut bindings: impl Iterator<Item = (&'a VarName, &'a ExprSingle)>,
+        return_expr: &ExprSingle,
+    ) -> AnalysisResult {
+        match bindings.next() {
+            Some((var, expr)) => {
+                self.expr_single(expr)?;
+                self.with_variable(var, |analyzer| analyzer.bindings(bindings, return_expr))
+            }
+            None => self.expr_single(return_expr),
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) -> AnalysisResult {
+        self.expr_single(&expr.expr)?;
+        for item in expr.items.iter() {
+            self.expr_single(item)?;
+        }
+
+        Ok(())
+    }
+
+    fn expr_single(&mut self, expr: &ExprSingle) -> AnalysisResult {
+        match expr {
+            ExprSingle::ForExpr(x) => {
+                self.unsupported("for expression", x);
+                let bindings = std::iter::once(&x.clause.binding)
+                    .chain(x.clause.extras.iter())
+                    .map(|binding| (&binding.var, &binding.expr));
+                self.bindings(bindings, &x.expr)
+            }
+            ExprSingle::LetExpr(x) => {
+                self.unsupported("let expression", x);
+                let bindings = std::iter::once(&x.clause.binding)
+                    .chain(x.clause.extras.iter())
+                    .map(|binding| (&binding.var, &binding.expr));
+                self.bindings(bindings, &x.expr)
+            }
+            ExprSingle::QuantifiedExpr(x) => {
+                self.unsupported("quantified expression", x);
+                let bindings = std::iter::once(&x.item)
+                    .chain(x.extras.iter())
+                    .map(|item| (&item.var, &item.expr));
+                self.bindings(bindings, &x.satisfies)
+            }
+            ExprSingle::IfExpr(x) => {
+                self.unsupported("if expression", x);
+                self.expr(&x.condition)?;
+                self.expr_single(&x.then)?;
+                self.expr_single(&x.else_expr)
+            }
+            ExprSingle::OrExpr(x) => self.or_expr(x),
+        }
+    }
+
+    fn or_expr(&mut self, expr: &OrExpr) -> AnalysisResult {
+        self.and_expr(&expr.expr)?;
+        for item in expr.items.iter() {
+            self.and_expr(item)?;
+        }
+
+        Ok(())
+    }
+
+    fn and_expr(&mut self, expr: &AndExpr) -> AnalysisResult {
+        self.comparison_expr(&expr.expr)?;
+        for item in expr.items.iter() {
+            self.comparison_expr(item)?;
+        }
+
+        Ok(())
+    }
+
+    fn comparison_expr(&mut self, expr: &ComparisonExpr) -> AnalysisResult {
+        self.string_concat_expr(&expr.expr)?;
+
+        if let Some(comparison) = &expr.comparison {
+            match comparison.0 {
+                ComparisonType::ValueComp(_) => self.unsupported("value comparison", expr),
+                ComparisonType::NodeComp(_) => self.unsupported("node comparison", expr),
+                ComparisonType::GeneralComp(_) => {}
+            }
+
+            self.string_concat_expr(&comparison.1)?;
+        }
+
+        Ok(())
+    }
+
+    fn string_concat_expr(&mut self, expr: &StringConcatExpr) -> AnalysisResult {
+        self.range_expr(&expr.expr)?;
+        for item in expr.items.iter() {
+            self.range_expr(item)?;
+        }
+
+        Ok(())
+    }
+
+    fn range_expr(&mut self, expr: &RangeExpr) -> AnalysisResult {
+        self.additive_expr(&expr.expr)?;
+
+        if let Some(to_expr) = &expr.to_expr {
+            self.unsupported("range expression", expr);
+            self.additive_expr(to_expr)?;
+        }
+
+        Ok(())
+    }
+
+    fn additive_expr(&mut self, expr: &AdditiveExpr) -> AnalysisResult {
+        self.multiplicative_expr(&expr.expr)?;
+        for item in expr.items.iter() {
+            self.multiplicative_expr(&item.1)?;
+        }
+
+        Ok(())
+    }
+
+    fn multiplicative_expr(&mut self, expr: &MultiplicativeExpr) -> AnalysisResult {
+        self.union_expr(&expr.expr)?;
+        for item in expr.items.iter() {
+            self.union_expr(&item.1)?;
+        }
+
+        Ok(())
+    }
+
+    fn union_expr(&mut self, expr: &UnionExpr) -> AnalysisResult {
+        if !expr.items.is_empty() {
+            self.unsupported("union expression", expr);
+        }
+
+        self.intersect_except_expr(&expr.expr)?;
+        for item in expr.items.iter() {
+            self.intersect_except_expr(&item.1)?;
+        }
+
+        Ok(())
+    }
+
+    fn intersect_except_expr(&mut self, expr: &IntersectExceptExpr) -> AnalysisResult {
+        if !expr.items.is_empty() {
+            self.unsupported("intersect or except expression", expr);
+        }
+
+        self.instanceof_expr(&expr.expr)?;
+        for item in expr.items.iter() {
+            self.instanceof_expr(&item.1)?;
+        }
+
+        Ok(())
+    }
+
+    fn instanceof_expr(&mut self, expr: &InstanceofExpr) -> AnalysisResult {
+        if expr.instanceof_type.is_some() {
+            self.unsupported("instance of expression", expr);
+        }
+
+        self.treat_expr(&expr.expr)
+    }
+
+    fn treat_expr(&mut self, expr: &TreatExpr) -> AnalysisResult {
+        if let Some(treat_type) = &expr.treat_type {
+            self.sequence_type(treat_type);
+        }
+
+        self.castable_expr(&expr.expr)
+    }
+
+    fn castable_expr(&mut self, expr: &CastableExpr) -> AnalysisResult {
+        if expr.cast_type.is_some() {
+            self.unsupported("castable expression", expr);
+        }
+
+        self.cast_expr(&expr.expr)
+    }
+
+    fn cast_expr(&mut self, expr: &CastExpr) -> AnalysisResult {
+        if expr.cast.is_some() {
+            self.unsupported("cast expression", expr);
+        }
+
+        self.arrow_expr(&expr.expr)
+    }
+
+    fn arrow_expr(&mut self, expr: &ArrowExpr) -> AnalysisResult {
+        if !expr.items.is_empty() {
+            self.unsupported("arrow expression", expr);
+        }
+
+        self.unary_expr(&expr.expr)?;
+        for item in expr.items.iter() {
+            match &item.function_specifier {
+                // The left hand side of the arrow is the first argument of the function.
+                ArrowFunctionSpecifier::Name(name) => {
+                    self.function_name(name, item.arguments.0.len() + 1)?
+                }
+                ArrowFunctionSpecifier::VarRef(var_ref) => self.var_ref(var_ref)?,
+                ArrowFunctionSpecifier::ParenthesizedExpr(x) => {
+                    if let Some(x) = &x.0 {
+                        self.expr(x)?;
+                    }
+                }
+            }
+
+            self.argument_list(&item.arguments)?;
+        }
+
+        Ok(())
+    }
+
+    fn unary_expr(&mut self, expr: &UnaryExpr) -> AnalysisResult {
+        self.simple_map_expr(&expr.expr.0)
+    }
+
+    fn simple_map_expr(&mut self, expr: &SimpleMapExpr) -> AnalysisResult {
+        if !expr.items.is_empty() {
+            self.unsupported("simple map expression", expr);
+        }
+
+        self.path_expr(&expr.expr)?;
+        for item in expr.items.iter() {
+            self.path_expr(item)?;
+        }
+
+        Ok(())
+    }
+
+    fn path_expr(&mut self, expr: &PathExpr) -> AnalysisResult {
+        match expr {
+            PathExpr::LeadingSlash(None) => Ok(()),
+            PathExpr::LeadingSlash(Some(x))
+            | PathExpr::LeadingDoubleSlash(x)
+            | PathExpr::Plain(x) => self.relative_path_expr(x),
+        }
+    }
+
+    fn relative_path_expr(&mut self, expr: &RelativePathExpr) -> AnalysisResult {
+        self.step_expr(&expr.expr)?;
+        for item in expr.items.iter() {
+            self.step_expr(&item.1)?;
+        }
+
+        Ok(())
+    }
+
+    fn step_expr(&mut self, expr: &StepExpr) -> AnalysisResult {
+        match expr {
+            StepExpr::PostfixExpr(x) => self.postfix_expr(x),
+            StepExpr::AxisStep(x) => self.axis_step(x),
+        }
+    }
+
+    fn axis_step(&mut self, step: &AxisStep) -> AnalysisResult {
+        match &step.step_type {
+            AxisStepType::ReverseStep(ReverseStep::Full(axis, node_test)) => {
+                if !matches!(axis, ReverseAxis::Parent | ReverseAxis::PrecedingSibling) {
+                    self.unsupported("axis", &step.step_type);
+                }
+
+                self.node_test(node_test)?;
+            }
+            AxisStepType::ReverseStep(ReverseStep::Abbreviated) => {}
+            AxisStepType::ForwardStep(ForwardStep::Full(axis, node_test)) => {
+                if matches!(axis, ForwardAxis::Following | ForwardAxis::Namespace) {
+                    self.unsupported("axis", &step.step_type);
+                }
+
+                self.node_test(node_test)?;
+            }
+            AxisStepType::ForwardStep(ForwardStep::Abbreviated(x)) => {
+                self.node_test(&x.node_test)?;
+            }
+        }
+
+        for predicate in step.predicates.iter() {
+            self.expr(&predicate.0)?;
+        }
+
+        Ok(())
+    }
+
+    fn node_test(&mut self, node_test: &NodeTest) -> AnalysisResult {
+        match node_test {
+            NodeTest::KindTest(x) => self.kind_test(x),
+            NodeTest::NameTest(NameTest::Name(EQName::QName(QName::PrefixedName(x)))) => {
+                self.namespace_prefix(&x.prefix)?;
+                self.unsupported("name test with a prefix", node_test);
+            }
+            NodeTest::NameTest(NameTest::Name(EQName::QName(QName::UnprefixedName(_)))) => {}
+            NodeTest::NameTest(NameTest::Name(EQName::UriQualifiedName(x))) if x.uri.is_empty() => {
+            }
+            NodeTest::NameTest(NameTest::Name(EQName::UriQualifiedName(_))) => {
+                self.unsupported("name test with a namespace", node_test)
+            }
+            NodeTest::NameTest(NameTest::Wildcard(Wildcard::Simple)) => {}
+            NodeTest::NameTest(NameTest::Wildcard(Wildcard::SuffixedName(prefix))) => {
+                self.namespace_prefix(prefix)?;
+                self.unsupported("wildcard", node_test);
+            }
+            NodeTest::NameTest(NameTest::Wildcard(_)) => self.unsupported("wildcard", node_test),
+        }
+
+        Ok(())
+    }
+
+    fn kind_test(&mut self, kind_test: &KindTest) {
+        let is_supported = match kind_test {
+            KindTest::AnyKindTest
+            | KindTest::TextTest
+            | KindTest::CommentTest
+            | KindTest::NamespaceNodeTest => true,
+            KindTest::DocumentTest(x) => x.value.is_none(),
+            KindTest::AttributeTest(x) => match &x.pair {
+                Some(pair) => {
+                    pair.name_or_wildcard == AttribNameOrWildcard::Wildcard
+                        && pair.type_name.is_none()
+                }
+                None => true,
+            },
+            _ => false,
+        };
+
+        if !is_supported {
+            self.unsupported("kind test", kind_test);
+        }
+    }
+
+    fn sequence_type(&mut self, sequence_type: &SequenceType) {
+        let value = match sequence_type {
+            SequenceType::EmptySequence => return,
+            SequenceType::Sequence(x) => x,
+        };
+
+        if value.occurrence.is_some() {
+            self.unsupported("occurrence indicator", sequence_type);
+        }
+
+        match &value.item_type {
+            ItemType::Item => {}
+            ItemType::KindTest(x) => self.kind_test(x),
+            _ => self.unsupported("item type", &value.item_type),
+        }
+    }
+
+    fn postfix_expr(&mut self, expr: &PostfixExpr) -> AnalysisResult {
+        self.primary_expr(&expr.expr)?;
+        for item in expr.items.iter() {
+            match item {
+                PostfixExprItem::Predicate(x) => self.expr(&x.0)?,
+                PostfixExprItem::ArgumentList(x) => {
+                    self.unsupported("dynamic function call", expr);
+                    self.argument_list(x)?
+                }
+                PostfixExprItem::Lookup(Lookup(x)) => self.key_specifier(x)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn primary_expr(&mut self, expr: &PrimaryExpr) -> AnalysisResult {
+        match expr {
+            PrimaryExpr::Literal(_) | PrimaryExpr::ContextItemExpr => Ok(()),
+            PrimaryExpr::VarRef(x) => self.var_ref(x),
+            PrimaryExpr::ParenthesizedExpr(x) => match &x.0 {
+                Some(x) => self.expr(x),
+                None => Ok(()),
+            },
+            PrimaryExpr::FunctionCall(x) => self.function_call(x),
+            PrimaryExpr::FunctionItemExpr(_) => {
+                self.unsupported("function item", expr);
+                Ok(())
+            }
+            PrimaryExpr::MapConstructor(x) => {
+                for entry in x.entries.iter() {
+                    self.expr_single(&entry.key)?;
+                    self.expr_single(&entry.value)?;
+                }
+
+                Ok(())
+            }
+            PrimaryExpr::ArrayConstructor(ArrayConstructor::SquareArrayConstructor(x)) => {
+                for entry in x.entries.iter() {
+                    self.expr_single(entry)?;
+                }
+
+                Ok(())
+            }
+            PrimaryExpr::ArrayConstructor(ArrayConstructor::CurlyArrayConstructor(x)) => {
+                match &x.0 .0 {
+                    Some(x) => self.expr(x),
+                    None => Ok(()),
+                }
+            }
+            PrimaryExpr::UnaryLookup(x) => self.key_specifier(&x.0),
+        }
+    }
+
+    fn key_specifier(&mut self, key_specifier: &KeySpecifier) -> AnalysisResult {
+        match key_specifier {
+            KeySpecifier::ParenthesizedExpr(x) => match &x.0 {
+                Some(x) => self.expr(x),
+                None => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn var_ref(&mut self, var_ref: &VarRef) -> AnalysisResult {
+        let name = self.static_context.resolve_variable_name(&var_ref.0 .0)?;
+        if !self.in_scope_variables.contains(&name) {
+            return Err(ExpressionParseError::new(
+                ErrorCode::XPST0008,
+                format!("variable {} is not in scope", var_ref),
+            ));
+        }
+
+        self.unsupported("variable reference", var_ref);
+        Ok(())
+    }
+
+    fn function_call(&mut self, function_call: &FunctionCall) -> AnalysisResult {
+        self.function_name(&function_call.name, function_call.argument_list.0.len())?;
+        self.argument_list(&function_call.argument_list)
+    }
+
+    fn function_name(&mut self, name: &EQName, arity: usize) -> AnalysisResult {
+        let name = self.static_context.resolve_function_name(name)?;
+        if !self.static_context.functions().contains(&name, arity) {
+            return Err(ExpressionParseError::new(
+                ErrorCode::XPST0017,
+                format!("Unknown function {}#{}", name, arity),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn argument_list(&mut self, argument_list: &ArgumentList) -> AnalysisResult {
+        for argument in argument_list.0.iter() {
+            match argument {
+                Argument::ExprSingle(x) => self.expr_single(x)?,
+                Argument::ArgumentPlaceHolder => {
+                    self.unsupported("partial function application", argument_list)
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn namespace_prefix(&mut self, prefix: &str) -> AnalysisResult {
+        match self.static_context.namespace(prefix) {
+            Some(_) => Ok(()),
+            None => Err(ExpressionParseError::new(
+                ErrorCode::XPST0081,
+                format!("namespace prefix {} is not declared", prefix),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::xpath::{
+        context::StaticContextBuilder, grammar::expressions::xpath, parse_with_context,
+        xpath_item_set::XpathItemSet,
+    };
+
+    use super::*;
+
+    fn analyze_text(text: &str, static_context: &StaticContext) -> AnalysisResult {
+        let (_, xpath) = xpath(text).unwrap();
+        analyze(&xpath, static_context)
+    }
+
+    #[test]
+    fn analyze_should_accept_supported_expression() {
+        // arrange
+        let text = "//div[@class='item']/a[contains(@href, 'http')]/@href";
+
+        // act
+        let result = analyze_text(text, &StaticContext::new());
+
+        // assert
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn analyze_should_fail_for_unknown_function() {
+        // arrange
+        let text = "//div[foo()]";
+
+        // act
+        let result = analyze_text(text, &StaticContext::new());
+
+        // assert
+        let err = result.unwrap_err();
+        assert_eq!(err.code(), &ErrorCode::XPST0017, "{}", err);
+    }
+
+    #[test]
+    fn analyze_should_fail_for_wrong_arity() {
+        // arrange
+        let text = "//div[contains(@class)]";
+
+        // act
+        let result = analyze_text(text, &StaticContext::new());
+
+        // assert
+        let err = result.unwrap_err();
+        assert_eq!(err.code(), &ErrorCode::XPST0017, "{}", err);
+    }
+
+    #[test]
+    fn analyze_should_fail_for_undeclared_prefix() {
+        // arrange
+        let text = "//div/ext:price(.)";
+
+        // act
+        let result = analyze_text(text, &StaticContext::new());
+
+        // assert
+        let err = result.unwrap_err();
+        assert_eq!(err.code(), &ErrorCode::XPST0081, "{}", err);
+    }
+
+    #[test]
+    fn analyze_should_fail_for_variable_not_in_scope() {
+        // arrange
+        let text = "for $x in //div return $y";
+
+        // act
+        let result = analyze_text(text, &StaticContext::new());
+
+        // assert
+        let err = result.unwrap_err();
+        assert_eq!(err.code(), &ErrorCode::XPST0008, "{}", err);
+    }
+
+    #[test]
+    fn analyze_should_put_earlier_bindings_in_scope() {
+        // arrange
+        let text = "for $x in //div, $y in $x/span return $y";
+
+        // act
+        let result = analyze_text(text, &StaticContext::new());
+
+        // assert
+        let err = result.unwrap_err();
+        assert!(
+            err.msg.starts_with("for expression is not supported"),
+            "{}",
+            err.msg
+        );
+    }
+
+    #[test]
+    fn analyze_should_prefer_static_errors_over_unsupported_features() {
+        // arrange
+        let text = "//div/ancestor::body[foo()]";
+
+        // act
+        let result = analyze_text(text, &StaticContext::new());
+
+        // assert
+        let err = result.unwrap_err();
+        assert_eq!(err.code(), &ErrorCode::XPST0017, "{}", err);
+    }
+
+    #[test]
+    fn analyze_should_fail_for_unsupported_axis() {
+        // arrange
+        let text = "//div/ancestor::body";
+
+        // act
+        let result = analyze_text(text, &StaticContext::new());
+
+        // assert
+        assert_eq!(
+            result.unwrap_err().msg,
+            "axis is not supported: ancestor::body"
+        );
+    }
+
+    #[test]
+    fn parse_with_context_should_resolve_registered_functions() {
+        // arrange
+        let static_context = StaticContextBuilder::new()
+            .with_namespace("ext", "urn:example")
+            .with_function(ExpandedName::new("urn:example", "price"), 1, |_, _| {
+                Ok(XpathItemSet::new())
+            })
+            .build();
+
+        // act
+        let result = parse_with_context("//div/ext:price(.)", &static_context);
+
+        // assert
+        assert!(result.is_ok());
+    }
+}
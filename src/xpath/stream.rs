@@ -0,0 +1,634 @@
+//! Stream the results of an [Xpath] expression in document order.
+//!
+//! [Xpath::apply] evaluates an expression completely, then sorts the result.
+//! [Xpath::iter] walks the document instead, and yields each node as soon as it is found to match,
+//! so a caller that only needs the first few results, like [Xpath::find_first],
+//! stops the walk without looking at the rest of the document.
+//!
+//! A path is streamed when each node can be checked on its own:
+//! it starts at the root of the document, its steps only look down the tree
+//! on the `child`, `descendant`, `descendant-or-self`, `self` and `attribute` axes,
+//! and its predicates do not depend on the position of the node,
+//! such as `[@class='item']`, `[a]` or `[contains(@href, 'example')]`.
+//! Other expressions, such as `//li[1]`, are applied in full and their results yielded one by one.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! use skyscraper::html;
+//! use skyscraper::xpath::{self, XpathItemTree};
+//!
+//! let document = html::parse(r#"
+//!     <html>
+//!         <a href="/1">One</a>
+//!         <a href="/2">Two</a>
+//!     </html>"#)?;
+//! let tree = XpathItemTree::from(&document);
+//!
+//! let xpath = xpath::parse("//a[@href]")?;
+//!
+//! let first = xpath.find_first(&tree)?.unwrap();
+//! assert_eq!(first.extract_as_node().text_content(&tree), "One");
+//!
+//! for item in xpath.iter(&tree) {
+//!     let item = item?;
+//!     println!("{}", item.extract_as_node().text_content(&tree));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::HashMap, iter::FusedIterator};
+
+use crate::xpath::{
+    context::DEFAULT_DYNAMIC_CONTEXT,
+    grammar::{
+        data_model::XpathItem,
+        expressions::{
+            path_expressions::{
+                steps::{
+                    axes::forward_axis::ForwardAxis,
+                    axis_step::AxisStepType,
+                    forward_step::ForwardStep,
+                    node_tests::{BiDirectionalAxis, NodeTest},
+                    step_expr::StepExpr,
+                },
+                PathExpr, PathSeparator, RelativePathExpr, DESCENDANT_OR_SELF_STEP,
+            },
+            postfix_expressions::Predicate,
+        },
+        TreeNodeId, XpathItemTreeNode,
+    },
+    limits::EvaluationBudget,
+    planner::{comparison_of, function_call, path_of},
+    DynamicContext, ExpressionApplyError, StaticContext, Xpath, XpathExpressionContext,
+    XpathItemTree,
+};
+
+impl Xpath {
+    /// Iterate over the result of applying the expression to the given item tree.
+    ///
+    /// Items are yielded in document order.
+    /// See the [stream](crate::xpath::stream) module for the expressions that are evaluated
+    /// as the iterator advances, rather than all at once.
+    pub fn iter<'a, 'tree>(&'a self, item_tree: &'tree XpathItemTree) -> XpathIter<'a, 'tree> {
+        self.iter_with_context(item_tree, &DEFAULT_DYNAMIC_CONTEXT)
+    }
+
+    /// Iterate over the result of applying the expression to the given item tree
+    /// using the given [DynamicContext].
+    pub fn iter_with_context<'a, 'tree>(
+        &'a self,
+        item_tree: &'tree XpathItemTree,
+        dynamic_context: &'a DynamicContext,
+    ) -> XpathIter<'a, 'tree> {
+        let state = match stream_steps(self, dynamic_context.static_context()) {
+            Some(steps) => State::Streaming(Box::new(Stream {
+                item_tree,
+                dynamic_context,
+                budget: EvaluationBudget::new(dynamic_context.limits()),
+                steps,
+                nodes: Box::new(item_tree.iter()),
+                selected: HashMap::new(),
+                within: HashMap::new(),
+                count: 0,
+            })),
+            None => State::Pending,
+        };
+
+        XpathIter {
+            xpath: self,
+            item_tree,
+            dynamic_context,
+            state,
+        }
+    }
+
+    /// Find the first item, in document order, of the result of applying the expression
+    /// to the given item tree.
+    ///
+    /// Streamed expressions stop walking the document at the first match.
+    pub fn find_first<'tree>(
+        &self,
+        item_tree: &'tree XpathItemTree,
+    ) -> Result<Option<XpathItem<'tree>>, ExpressionApplyError> {
+        self.iter(item_tree).next().transpose()
+    }
+}
+
+/// An iterator over the result of an [Xpath] expression.
+///
+/// Created by [Xpath::iter].
+/// The iterator ends after yielding an error.
+pub struct XpathIter<'a, 'tree> {
+    xpath: &'a Xpath,
+    item_tree: &'tree XpathItemTree,
+    dynamic_context: &'a DynamicContext,
+    state: State<'a, 'tree>,
+}
+
+enum State<'a, 'tree> {
+    /// Walking the document, checking each node against the path.
+    Streaming(Box<Stream<'a, 'tree>>),
+
+    /// The expression will be applied in full when the first item is requested.
+    Pending,
+
+    /// Yielding the items of an expression that was applied in full.
+    Applied(indexmap::set::IntoIter<XpathItem<'tree>>),
+
+    Done,
+}
+
+impl<'a, 'tree> XpathIter<'a, 'tree> {
+    /// Whether items are found as the iterator advances,
+    /// rather than by applying the whole expression at once.
+    pub fn is_streaming(&self) -> bool {
+        matches!(self.state, State::Streaming(_))
+    }
+}
+
+impl<'a, 'tree> Iterator for XpathIter<'a, 'tree> {
+    type Item = Result<XpathItem<'tree>, ExpressionApplyError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = match &mut self.state {
+            State::Streaming(stream) => stream.next_match().transpose(),
+            State::Pending => match self
+                .xpath
+                .apply_with_context(self.item_tree, self.dynamic_context)
+            {
+                Ok(items) => {
+                    let mut items = items.into_iter();
+                    let item = items.next();
+                    self.state = State::Applied(items);
+                    return item.map(Ok);
+                }
+                Err(err) => Some(Err(err)),
+            },
+            State::Applied(items) => items.next().map(Ok),
+            State::Done => None,
+        };
+
+        match result {
+            Some(Ok(item)) => Some(Ok(item)),
+            Some(Err(err)) => {
+                self.state = State::Done;
+                Some(Err(err.locate_in(self.xpath)))
+            }
+            None => {
+                self.state = State::Done;
+                None
+            }
+        }
+    }
+}
+
+impl FusedIterator for XpathIter<'_, '_> {}
+
+/// A step of a streamed path.
+#[derive(Clone, Copy)]
+struct StreamStep<'a> {
+    axis: ForwardAxis,
+    node_test: &'a NodeTest,
+    predicates: &'a [Predicate],
+}
+
+/// Walks a document in order and checks whether each node is selected by a path.
+struct Stream<'a, 'tree> {
+    item_tree: &'tree XpathItemTree,
+    dynamic_context: &'a DynamicContext,
+    budget: EvaluationBudget,
+    steps: Vec<StreamStep<'a>>,
+
+    /// The nodes of the document that have not been checked yet.
+    nodes: Box<dyn Iterator<Item = &'tree XpathItemTreeNode> + 'tree>,
+
+    /// Whether a node is selected by the first `n` steps, keyed by the node and `n`.
+    selected: HashMap<(TreeNodeId, usize), bool>,
+
+    /// Whether a node or one of its ancestors is selected by the first `n` steps, keyed by the node and `n`.
+    within: HashMap<(TreeNodeId, usize), bool>,
+
+    /// The number of items yielded so far.
+    count: usize,
+}
+
+impl<'a, 'tree> Stream<'a, 'tree> {
+    fn next_match(&mut self) -> Result<Option<XpathItem<'tree>>, ExpressionApplyError> {
+        while let Some(node) = self.nodes.next() {
+            self.budget.visit_node()?;
+
+            if self.is_selected(node, self.steps.len())? {
+                self.count += 1;
+                self.budget.check_result_size(self.count)?;
+                return Ok(Some(XpathItem::Node(node)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Whether the node is selected by the first `n` steps of the path.
+    fn is_selected(
+        &mut self,
+        node: &'tree XpathItemTreeNode,
+        n: usize,
+    ) -> Result<bool, ExpressionApplyError> {
+        let id = node_id(node);
+
+        // The path starts at the root of the document.
+        if n == 0 {
+            return Ok(Some(id) == self.item_tree.root().id());
+        }
+
+        if let Some(is_selected) = self.selected.get(&(id, n)) {
+            return Ok(*is_selected);
+        }
+
+        // Check the cheap node test first, and only evaluate predicates
+        // for nodes the step reaches, as applying the path would.
+        let step = self.steps[n - 1];
+        let is_selected = self.is_match(node, step)?
+            && self.is_reached(node, step.axis, n - 1)?
+            && self.is_match_predicates(node, step)?;
+
+        self.selected.insert((id, n), is_selected);
+        Ok(is_selected)
+    }
+
+    /// Whether the axis reaches the node from a node selected by the first `n` steps.
+    fn is_reached(
+        &mut self,
+        node: &'tree XpathItemTreeNode,
+        axis: ForwardAxis,
+        n: usize,
+    ) -> Result<bool, ExpressionApplyError> {
+        let parent = self.item_tree.parent_of(node_id(node));
+
+        match axis {
+            ForwardAxis::Child => match parent {
+                Some(parent) => self.is_selected(parent, n),
+                None => Ok(false),
+            },
+            ForwardAxis::Attribute => match (node, parent) {
+                (
+                    XpathItemTreeNode::AttributeNode(_),
+                    Some(parent @ XpathItemTreeNode::ElementNode(_)),
+                ) => self.is_selected(parent, n),
+                _ => Ok(false),
+            },
+            ForwardAxis::SelfAxis => self.is_selected(node, n),
+            ForwardAxis::Descendant => match parent {
+                Some(parent) => self.is_within(parent, n),
+                None => Ok(false),
+            },
+            ForwardAxis::DescendantOrSelf => self.is_within(node, n),
+            ForwardAxis::FollowingSibling | ForwardAxis::Following | ForwardAxis::Namespace => {
+                unreachable!("only downward axes are streamed")
+            }
+        }
+    }
+
+    /// Whether the node or one of its ancestors is selected by the first `n` steps.
+    fn is_within(
+        &mut self,
+        node: &'tree XpathItemTreeNode,
+        n: usize,
+    ) -> Result<bool, ExpressionApplyError> {
+        let mut checked = Vec::new();
+        let mut current = Some(node);
+        let mut is_within = false;
+
+        while let Some(node) = current {
+            let id = node_id(node);
+            if let Some(within) = self.within.get(&(id, n)) {
+                is_within = *within;
+                break;
+            }
+
+            checked.push(id);
+            if self.is_selected(node, n)? {
+                is_within = true;
+                break;
+            }
+
+            current = self.item_tree.parent_of(id);
+        }
+
+        // Every node checked on the way up has the same answer.
+        for id in checked {
+            self.within.insert((id, n), is_within);
+        }
+
+        Ok(is_within)
+    }
+
+    fn is_match(
+        &self,
+        node: &'tree XpathItemTreeNode,
+        step: StreamStep,
+    ) -> Result<bool, ExpressionApplyError> {
+        let context = self.context(node);
+        let result = step
+            .node_test
+            .eval(BiDirectionalAxis::ForwardAxis(step.axis), &context)?;
+
+        Ok(result.is_some())
+    }
+
+    fn is_match_predicates(
+        &self,
+        node: &'tree XpathItemTreeNode,
+        step: StreamStep,
+    ) -> Result<bool, ExpressionApplyError> {
+        let context = self.context(node);
+
+        // All predicates must match for a node to be selected.
+        let mut is_match = true;
+        for predicate in step.predicates.iter() {
+            if !predicate.is_match(&context)? {
+                is_match = false;
+            }
+        }
+
+        Ok(is_match)
+    }
+
+    fn context(&self, node: &'tree XpathItemTreeNode) -> XpathExpressionContext<'tree, '_> {
+        XpathExpressionContext::new_single(
+            self.item_tree,
+            self.dynamic_context,
+            &self.budget,
+            None,
+            XpathItem::Node(node),
+            true,
+        )
+    }
+}
+
+fn node_id(node: &XpathItemTreeNode) -> TreeNodeId {
+    node.id().expect("nodes in a tree have an ID")
+}
+
+/// Get the steps of the path the expression consists of, if it can be streamed.
+fn stream_steps<'a>(
+    xpath: &'a Xpath,
+    static_context: &StaticContext,
+) -> Option<Vec<StreamStep<'a>>> {
+    if !xpath.0.items.is_empty() {
+        return None;
+    }
+
+    let comparison = comparison_of(&xpath.0.expr)?;
+    if comparison.comparison.is_some() {
+        return None;
+    }
+
+    // At the root level, a relative path starts at the root of the document like a leading slash does.
+    let (path, mut steps) = match path_of(&comparison.expr)? {
+        PathExpr::LeadingSlash(None) => return Some(Vec::new()),
+        PathExpr::LeadingSlash(Some(path)) | PathExpr::Plain(path) => (path, Vec::new()),
+        PathExpr::LeadingDoubleSlash(path) => (
+            path,
+            vec![stream_step(&DESCENDANT_OR_SELF_STEP, static_context)?],
+        ),
+    };
+
+    steps.push(stream_step(&path.expr, static_context)?);
+    for pair in path.items.iter() {
+        if pair.0 == PathSeparator::DoubleSlash {
+            steps.push(stream_step(&DESCENDANT_OR_SELF_STEP, static_context)?);
+        }
+
+        steps.push(stream_step(&pair.1, static_context)?);
+    }
+
+    Some(steps)
+}
+
+/// Get a step that looks down the tree and whose predicates do not depend on position.
+fn stream_step<'a>(step: &'a StepExpr, static_context: &StaticContext) -> Option<StreamStep<'a>> {
+    let step = match step {
+        StepExpr::AxisStep(step) => step,
+        StepExpr::PostfixExpr(_) => return None,
+    };
+
+    let (axis, node_test) = match &step.step_type {
+        AxisStepType::ForwardStep(ForwardStep::Full(axis, node_test)) => (*axis, node_test),
+        AxisStepType::ForwardStep(ForwardStep::Abbreviated(step)) => {
+            let axis = if step.has_at {
+                ForwardAxis::Attribute
+            } else {
+                ForwardAxis::Child
+            };
+
+            (axis, &step.node_test)
+        }
+        AxisStepType::ReverseStep(_) => return None,
+    };
+
+    match axis {
+        ForwardAxis::FollowingSibling | ForwardAxis::Following | ForwardAxis::Namespace => {
+            return None
+        }
+        _ => {}
+    }
+
+    let is_positionless = step
+        .predicates
+        .iter()
+        .all(|predicate| is_boolean_predicate(predicate, static_context));
+    if !is_positionless {
+        return None;
+    }
+
+    Some(StreamStep {
+        axis,
+        node_test,
+        predicates: &step.predicates,
+    })
+}
+
+/// Whether the predicate's expression is a comparison, a path of axis steps
+/// or a call to a function that returns a boolean.
+///
+/// The truth value of such predicates never depends on the position of the item.
+fn is_boolean_predicate(predicate: &Predicate, static_context: &StaticContext) -> bool {
+    if !predicate.0.items.is_empty() {
+        return false;
+    }
+
+    let comparison = match comparison_of(&predicate.0.expr) {
+        Some(comparison) => comparison,
+        None => return false,
+    };
+    if comparison.comparison.is_some() {
+        return true;
+    }
+
+    if let Some(function_call) = function_call(&comparison.expr) {
+        let arity = function_call.argument_list.0.len();
+        return match static_context.resolve_function_name(&function_call.name) {
+            // Built-in functions that return a boolean do not depend on position.
+            Ok(name) => static_context.functions().is_boolean_built_in(&name, arity),
+            Err(_) => false,
+        };
+    }
+
+    match path_of(&comparison.expr) {
+        Some(PathExpr::LeadingSlash(None)) => true,
+        Some(PathExpr::LeadingSlash(Some(path)))
+        | Some(PathExpr::LeadingDoubleSlash(path))
+        | Some(PathExpr::Plain(path)) => is_axis_path(path),
+        None => false,
+    }
+}
+
+/// Whether every step of the path is an axis step, so that it selects nodes.
+fn is_axis_path(path: &RelativePathExpr) -> bool {
+    std::iter::once(&path.expr)
+        .chain(path.items.iter().map(|pair| &pair.1))
+        .all(|step| matches!(step, StepExpr::AxisStep(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        html,
+        xpath::{
+            self,
+            context::DynamicContextBuilder,
+            limits::{EvaluationLimit, EvaluationLimits},
+            ErrorCode,
+        },
+    };
+
+    use super::*;
+
+    fn tree(text: &str) -> XpathItemTree {
+        XpathItemTree::from(&html::parse(text).unwrap())
+    }
+
+    #[test]
+    fn iter_should_yield_same_items_as_apply() {
+        // arrange
+        let tree = tree(
+            r#"
+            <html>
+                <body>
+                    <div id="main" class="a">
+                        <p id="1">one</p>
+                        <p id="2" class="b">two <a href="/x">x</a></p>
+                    </div>
+                    <div class="a b"><p>three</p><span><p>four</p></span></div>
+                </body>
+            </html>"#,
+        );
+        let expressions = [
+            "/",
+            "/html/body/div",
+            "//p",
+            "//div//p",
+            "//div/p/@id",
+            "//p[@class='b']/a",
+            "//div[p/a]/@class",
+            "//p[contains(., 'o')]",
+            "//div[@class='a']/descendant::p",
+            "/descendant-or-self::node()/self::span/p",
+            "html/body/div[starts-with(@class, 'a')]",
+        ];
+
+        for expression in expressions {
+            let xpath = xpath::parse(expression).unwrap();
+            let expected: Vec<XpathItem> = xpath.apply(&tree).unwrap().into_iter().collect();
+
+            // act
+            let iter = xpath.iter(&tree);
+            let is_streaming = iter.is_streaming();
+            let items: Vec<XpathItem> = iter.collect::<Result<_, _>>().unwrap();
+
+            // assert
+            assert!(is_streaming, "{} should be streamed", expression);
+            assert_eq!(items, expected, "{}", expression);
+        }
+    }
+
+    #[test]
+    fn iter_should_apply_expressions_that_cannot_be_streamed() {
+        // arrange
+        let tree = tree("<html><p>1</p><p>2</p><p>3</p></html>");
+        let xpath = xpath::parse("//p[2]").unwrap();
+
+        // act
+        let iter = xpath.iter(&tree);
+        let is_streaming = iter.is_streaming();
+        let items: Vec<String> = iter
+            .map(|item| item.unwrap().extract_as_node().text_content(&tree))
+            .collect();
+
+        // assert
+        assert!(!is_streaming);
+        assert_eq!(items, vec!["2"]);
+    }
+
+    #[test]
+    fn find_first_should_stop_walking_at_first_match() {
+        // arrange
+        let text = format!(
+            "<html><body><p>first</p>{}</body></html>",
+            "<div></div>".repeat(100)
+        );
+        let tree = tree(&text);
+        let xpath = xpath::parse("/html/body/p").unwrap();
+        let context = DynamicContextBuilder::new(StaticContext::new())
+            .with_limits(EvaluationLimits::new().with_max_node_visits(20))
+            .build();
+
+        // act
+        let first = xpath
+            .iter_with_context(&tree, &context)
+            .next()
+            .unwrap()
+            .unwrap();
+        let err = xpath.apply_with_context(&tree, &context).unwrap_err();
+
+        // assert
+        assert_eq!(first.extract_as_node().text_content(&tree), "first");
+        assert_eq!(
+            err.code(),
+            &ErrorCode::LimitExceeded(EvaluationLimit::NodeVisits)
+        );
+    }
+
+    #[test]
+    fn find_first_should_return_none_without_match() {
+        // arrange
+        let tree = tree("<html><p>1</p></html>");
+        let xpath = xpath::parse("//div").unwrap();
+
+        // act
+        let first = xpath.find_first(&tree).unwrap();
+
+        // assert
+        assert!(first.is_none());
+    }
+
+    #[test]
+    fn iter_should_end_after_error() {
+        // arrange
+        let tree = tree("<html><p>1</p></html>");
+        let xpath = xpath::parse("//p[fn:error()]").unwrap();
+
+        // act
+        let mut iter = xpath.iter(&tree);
+        let first = iter.next();
+        let second = iter.next();
+
+        // assert
+        assert!(matches!(first, Some(Err(_))));
+        assert!(second.is_none());
+    }
+}
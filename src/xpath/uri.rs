@@ -0,0 +1,274 @@
+//! Resolving and escaping URI references.
+//!
+//! <https://www.rfc-editor.org/rfc/rfc3986>
+
+use std::fmt::Display;
+
+/// A URI reference split into its components.
+///
+/// <https://www.rfc-editor.org/rfc/rfc3986#section-4.1>
+#[derive(PartialEq, Debug, Clone, Default)]
+struct UriReference<'a> {
+    scheme: Option<&'a str>,
+    authority: Option<&'a str>,
+    path: String,
+    query: Option<&'a str>,
+    fragment: Option<&'a str>,
+}
+
+impl<'a> UriReference<'a> {
+    /// Split a URI reference into its components.
+    ///
+    /// <https://www.rfc-editor.org/rfc/rfc3986#appendix-B>
+    fn parse(text: &'a str) -> Result<Self, String> {
+        // Every '%' must start an escape of two hex digits.
+        let is_escape = |s: &str| s.bytes().take(2).filter(u8::is_ascii_hexdigit).count() == 2;
+        if !text.split('%').skip(1).all(is_escape) {
+            return Err(format!("{} has an invalid percent-encoding", text));
+        }
+
+        let (rest, fragment) = match text.split_once('#') {
+            Some((rest, fragment)) => (rest, Some(fragment)),
+            None => (text, None),
+        };
+        let (rest, query) = match rest.split_once('?') {
+            Some((rest, query)) => (rest, Some(query)),
+            None => (rest, None),
+        };
+
+        let (scheme, rest) = match rest.split_once(':') {
+            Some((scheme, rest)) if is_scheme(scheme) => (Some(scheme), rest),
+            _ => (None, rest),
+        };
+
+        let (authority, path) = match rest.strip_prefix("//") {
+            Some(rest) => {
+                let end = rest.find('/').unwrap_or(rest.len());
+                (Some(&rest[..end]), &rest[end..])
+            }
+            None => (None, rest),
+        };
+
+        Ok(Self {
+            scheme,
+            authority,
+            path: path.to_string(),
+            query,
+            fragment,
+        })
+    }
+}
+
+impl Display for UriReference<'_> {
+    /// <https://www.rfc-editor.org/rfc/rfc3986#section-5.3>
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(scheme) = self.scheme {
+            write!(f, "{}:", scheme)?;
+        }
+        if let Some(authority) = self.authority {
+            write!(f, "//{}", authority)?;
+        }
+        write!(f, "{}", self.path)?;
+        if let Some(query) = self.query {
+            write!(f, "?{}", query)?;
+        }
+        if let Some(fragment) = self.fragment {
+            write!(f, "#{}", fragment)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn is_scheme(text: &str) -> bool {
+    let mut chars = text.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+/// Whether the URI reference has a scheme, and so does not need a base URI.
+pub(crate) fn is_absolute(uri: &str) -> bool {
+    matches!(uri.split_once(':'), Some((scheme, _)) if is_scheme(scheme))
+}
+
+/// Resolve a URI reference against a base URI.
+///
+/// Leading and trailing whitespace is ignored, as it is in HTML attributes.
+/// The base does not have to be absolute; resolving against a relative base
+/// gives a reference relative to whatever that base is relative to.
+///
+/// <https://www.rfc-editor.org/rfc/rfc3986#section-5.2>
+pub(crate) fn resolve(relative: &str, base: &str) -> Result<String, String> {
+    let relative = UriReference::parse(relative.trim())?;
+    let base = UriReference::parse(base.trim())?;
+
+    let target = if relative.scheme.is_some() {
+        UriReference {
+            path: remove_dot_segments(&relative.path),
+            ..relative
+        }
+    } else if relative.authority.is_some() {
+        UriReference {
+            scheme: base.scheme,
+            path: remove_dot_segments(&relative.path),
+            ..relative
+        }
+    } else if relative.path.is_empty() {
+        UriReference {
+            scheme: base.scheme,
+            authority: base.authority,
+            path: base.path,
+            query: relative.query.or(base.query),
+            fragment: relative.fragment,
+        }
+    } else {
+        let path = if relative.path.starts_with('/') {
+            relative.path
+        } else if base.authority.is_some() && base.path.is_empty() {
+            format!("/{}", relative.path)
+        } else {
+            let directory = base.path.rfind('/').map_or("", |i| &base.path[..=i]);
+            format!("{}{}", directory, relative.path)
+        };
+
+        UriReference {
+            scheme: base.scheme,
+            authority: base.authority,
+            path: remove_dot_segments(&path),
+            query: relative.query,
+            fragment: relative.fragment,
+        }
+    };
+
+    Ok(target.to_string())
+}
+
+/// <https://www.rfc-editor.org/rfc/rfc3986#section-5.2.4>
+fn remove_dot_segments(path: &str) -> String {
+    let mut input = path;
+    let mut output: Vec<&str> = Vec::new();
+
+    while !input.is_empty() {
+        if let Some(rest) = input.strip_prefix("../") {
+            input = rest;
+        } else if let Some(rest) = input.strip_prefix("./") {
+            input = rest;
+        } else if input.starts_with("/./") {
+            input = &input[2..];
+        } else if input == "/." {
+            input = "/";
+        } else if input.starts_with("/../") || input == "/.." {
+            input = if input == "/.." { "/" } else { &input[3..] };
+            output.pop();
+        } else if input == "." || input == ".." {
+            input = "";
+        } else {
+            // Move the first segment, with its leading slash, to the output.
+            let start = usize::from(input.starts_with('/'));
+            let end = input[start..].find('/').map_or(input.len(), |i| i + start);
+            output.push(&input[..end]);
+            input = &input[end..];
+        }
+    }
+
+    // A relative path stays relative when its first segment is removed.
+    let output = output.concat();
+    match output.strip_prefix('/') {
+        Some(relative) if !path.starts_with('/') => relative.to_string(),
+        _ => output,
+    }
+}
+
+/// Percent-encode the UTF-8 bytes of every character that should not be kept.
+pub(crate) fn percent_encode(text: &str, keep: impl Fn(char) -> bool) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for c in text.chars() {
+        if keep(c) {
+            encoded.push(c);
+        } else {
+            let mut bytes = [0; 4];
+            for byte in c.encode_utf8(&mut bytes).bytes() {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_should_match_rfc_examples() {
+        // arrange
+        let base = "http://a/b/c/d;p?q";
+        let examples = [
+            ("g:h", "g:h"),
+            ("g", "http://a/b/c/g"),
+            ("./g", "http://a/b/c/g"),
+            ("g/", "http://a/b/c/g/"),
+            ("/g", "http://a/g"),
+            ("//g", "http://g"),
+            ("?y", "http://a/b/c/d;p?y"),
+            ("g?y", "http://a/b/c/g?y"),
+            ("#s", "http://a/b/c/d;p?q#s"),
+            ("g#s", "http://a/b/c/g#s"),
+            ("", "http://a/b/c/d;p?q"),
+            (".", "http://a/b/c/"),
+            ("..", "http://a/b/"),
+            ("../g", "http://a/b/g"),
+            ("../../g", "http://a/g"),
+            ("../../../g", "http://a/g"),
+            ("/./g", "http://a/g"),
+            ("g.", "http://a/b/c/g."),
+            ("./../g", "http://a/b/g"),
+            ("g;x=1/../y", "http://a/b/c/y"),
+        ];
+
+        for (relative, expected) in examples {
+            // act
+            let resolved = resolve(relative, base).unwrap();
+
+            // assert
+            assert_eq!(resolved, expected, "resolving {}", relative);
+        }
+    }
+
+    #[test]
+    fn resolve_should_allow_relative_base() {
+        // arrange
+        let base = "pages/listing.html";
+
+        // act
+        let resolved = resolve("../products/lamp.html", base).unwrap();
+
+        // assert
+        assert_eq!(resolved, "products/lamp.html");
+    }
+
+    #[test]
+    fn resolve_should_fail_for_invalid_percent_encoding() {
+        // arrange
+        let base = "http://a/b";
+
+        // act
+        let err = resolve("c%zz", base);
+
+        // assert
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn percent_encode_should_encode_utf8_bytes() {
+        // arrange
+        let text = "a b/é";
+
+        // act
+        let encoded = percent_encode(text, |c| c.is_ascii_alphanumeric());
+
+        // assert
+        assert_eq!(encoded, "a%20b%2F%C3%A9");
+    }
+}
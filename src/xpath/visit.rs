@@ -0,0 +1,1510 @@
+//! Walk and rewrite the syntax tree of [Xpath] expressions.
+//!
+//! Implement [Visitor] to inspect an expression, for example to write lint rules,
+//! or [VisitorMut] to change it in place, for example to rename every name test.
+//! Each `visit_*` method is called when the walk reaches a node of that type,
+//! and by default continues into the node's children with the matching `walk_*` function.
+//! Override a method to act on a node, and call the `walk_*` function from it
+//! to keep walking into the node's children.
+//!
+//! Nodes are visited before their children, in the order they are written in the expression.
+//!
+//! # Example: reject leading `//`
+//!
+//! ```rust
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! use skyscraper::xpath::{
+//!     self,
+//!     grammar::expressions::path_expressions::PathExpr,
+//!     visit::{self, Visitor},
+//! };
+//!
+//! #[derive(Default)]
+//! struct LeadingDoubleSlashes(usize);
+//!
+//! impl Visitor for LeadingDoubleSlashes {
+//!     fn visit_path_expr(&mut self, path_expr: &PathExpr) {
+//!         if let PathExpr::LeadingDoubleSlash(_) = path_expr {
+//!             self.0 += 1;
+//!         }
+//!
+//!         visit::walk_path_expr(self, path_expr);
+//!     }
+//! }
+//!
+//! let xpath = xpath::parse("//div[//span]/a")?;
+//!
+//! let mut lint = LeadingDoubleSlashes::default();
+//! xpath.visit(&mut lint);
+//!
+//! assert_eq!(lint.0, 2);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Example: rename elements
+//!
+//! ```rust
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! use skyscraper::xpath::{
+//!     self,
+//!     grammar::{
+//!         expressions::path_expressions::steps::node_tests::{NameTest, NodeTest},
+//!         types::EQName,
+//!         xml_names::QName,
+//!     },
+//!     visit::VisitorMut,
+//! };
+//!
+//! struct Rename;
+//!
+//! impl VisitorMut for Rename {
+//!     fn visit_node_test_mut(&mut self, node_test: &mut NodeTest) {
+//!         if let NodeTest::NameTest(NameTest::Name(EQName::QName(QName::UnprefixedName(name)))) =
+//!             node_test
+//!         {
+//!             if name == "b" {
+//!                 *name = String::from("strong");
+//!             }
+//!         }
+//!     }
+//! }
+//!
+//! let xpath = xpath::parse("//p/b[@class='x']")?;
+//! let rewritten = xpath.rewrite(&mut Rename)?;
+//!
+//! assert_eq!(rewritten.to_string(), "//p/strong[@class='x']");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::xpath::{
+    context::DEFAULT_DYNAMIC_CONTEXT,
+    grammar::{
+        expressions::{
+            arithmetic_expressions::{AdditiveExpr, MultiplicativeExpr, UnaryExpr},
+            arrow_operator::{ArrowExpr, ArrowFunctionSpecifier},
+            common::{Argument, ArgumentList},
+            comparison_expressions::ComparisonExpr,
+            conditional_expressions::IfExpr,
+            expressions_on_sequence_types::{
+                cast::CastExpr, castable::CastableExpr, instance_of::InstanceofExpr,
+                treat::TreatExpr,
+            },
+            for_expressions::ForExpr,
+            let_expressions::LetExpr,
+            logical_expressions::{AndExpr, OrExpr},
+            maps_and_arrays::{
+                arrays::ArrayConstructor,
+                lookup_operator::{postfix_lookup::Lookup, unary_lookup::KeySpecifier},
+                maps::MapConstructor,
+            },
+            path_expressions::{
+                steps::{
+                    axis_step::{AxisStep, AxisStepType},
+                    forward_step::ForwardStep,
+                    node_tests::NodeTest,
+                    reverse_step::ReverseStep,
+                    step_expr::StepExpr,
+                },
+                PathExpr, RelativePathExpr,
+            },
+            postfix_expressions::{PostfixExpr, PostfixExprItem, Predicate},
+            primary_expressions::{
+                enclosed_expressions::EnclosedExpr,
+                inline_function_expressions::InlineFunctionExpr,
+                literals::Literal,
+                named_function_references::NamedFunctionRef,
+                parenthesized_expressions::ParenthesizedExpr,
+                static_function_calls::FunctionCall,
+                variable_references::{VarName, VarRef},
+                FunctionItemExpr, PrimaryExpr,
+            },
+            quantified_expressions::QuantifiedExpr,
+            sequence_expressions::{
+                combining_node_sequences::{IntersectExceptExpr, UnionExpr},
+                constructing_sequences::RangeExpr,
+            },
+            simple_map_operator::SimpleMapExpr,
+            string_concat_expressions::StringConcatExpr,
+            Expr, ExprSingle,
+        },
+        types::sequence_type::SequenceType,
+    },
+    static_analysis, ExpressionParseError, StaticContext, Xpath,
+};
+
+impl Xpath {
+    /// Walk the expression with the given [Visitor].
+    pub fn visit(&self, visitor: &mut impl Visitor) {
+        visitor.visit_xpath(self);
+    }
+
+    /// Copy the expression, change the copy with the given [VisitorMut],
+    /// and check the result against the default [StaticContext].
+    ///
+    /// Returns an error if the rewritten expression would not be accepted
+    /// by [parse](crate::xpath::parse), for example because it calls an unknown function.
+    /// To change an expression without checking it, call [walk_xpath_mut] on it directly.
+    pub fn rewrite(&self, visitor: &mut impl VisitorMut) -> Result<Xpath, ExpressionParseError> {
+        self.rewrite_with_context(visitor, DEFAULT_DYNAMIC_CONTEXT.static_context())
+    }
+
+    /// Copy the expression, change the copy with the given [VisitorMut],
+    /// and check the result against the given [StaticContext].
+    ///
+    /// See [parse_with_context](crate::xpath::parse_with_context).
+    pub fn rewrite_with_context(
+        &self,
+        visitor: &mut impl VisitorMut,
+        static_context: &StaticContext,
+    ) -> Result<Xpath, ExpressionParseError> {
+        let mut xpath = self.clone();
+        visitor.visit_xpath_mut(&mut xpath);
+
+        // The text no longer matches the rewritten syntax tree.
+        xpath.1 = None;
+        static_analysis::analyze(&xpath, static_context)?;
+
+        Ok(xpath)
+    }
+}
+
+/// Inspect the nodes of an [Xpath] expression.
+///
+/// See the [module documentation](self).
+pub trait Visitor {
+    /// Called for each [Xpath].
+    fn visit_xpath(&mut self, xpath: &Xpath) {
+        walk_xpath(self, xpath);
+    }
+
+    /// Called for each [Expr].
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr);
+    }
+
+    /// Called for each [ExprSingle].
+    fn visit_expr_single(&mut self, expr: &ExprSingle) {
+        walk_expr_single(self, expr);
+    }
+
+    /// Called for each [ForExpr].
+    fn visit_for_expr(&mut self, expr: &ForExpr) {
+        walk_for_expr(self, expr);
+    }
+
+    /// Called for each [LetExpr].
+    fn visit_let_expr(&mut self, expr: &LetExpr) {
+        walk_let_expr(self, expr);
+    }
+
+    /// Called for each [QuantifiedExpr].
+    fn visit_quantified_expr(&mut self, expr: &QuantifiedExpr) {
+        walk_quantified_expr(self, expr);
+    }
+
+    /// Called for each [IfExpr].
+    fn visit_if_expr(&mut self, expr: &IfExpr) {
+        walk_if_expr(self, expr);
+    }
+
+    /// Called for each [OrExpr].
+    fn visit_or_expr(&mut self, expr: &OrExpr) {
+        walk_or_expr(self, expr);
+    }
+
+    /// Called for each [AndExpr].
+    fn visit_and_expr(&mut self, expr: &AndExpr) {
+        walk_and_expr(self, expr);
+    }
+
+    /// Called for each [ComparisonExpr].
+    fn visit_comparison_expr(&mut self, expr: &ComparisonExpr) {
+        walk_comparison_expr(self, expr);
+    }
+
+    /// Called for each [StringConcatExpr].
+    fn visit_string_concat_expr(&mut self, expr: &StringConcatExpr) {
+        walk_string_concat_expr(self, expr);
+    }
+
+    /// Called for each [RangeExpr].
+    fn visit_range_expr(&mut self, expr: &RangeExpr) {
+        walk_range_expr(self, expr);
+    }
+
+    /// Called for each [AdditiveExpr].
+    fn visit_additive_expr(&mut self, expr: &AdditiveExpr) {
+        walk_additive_expr(self, expr);
+    }
+
+    /// Called for each [MultiplicativeExpr].
+    fn visit_multiplicative_expr(&mut self, expr: &MultiplicativeExpr) {
+        walk_multiplicative_expr(self, expr);
+    }
+
+    /// Called for each [UnionExpr].
+    fn visit_union_expr(&mut self, expr: &UnionExpr) {
+        walk_union_expr(self, expr);
+    }
+
+    /// Called for each [IntersectExceptExpr].
+    fn visit_intersect_except_expr(&mut self, expr: &IntersectExceptExpr) {
+        walk_intersect_except_expr(self, expr);
+    }
+
+    /// Called for each [InstanceofExpr].
+    fn visit_instanceof_expr(&mut self, expr: &InstanceofExpr) {
+        walk_instanceof_expr(self, expr);
+    }
+
+    /// Called for each [TreatExpr].
+    fn visit_treat_expr(&mut self, expr: &TreatExpr) {
+        walk_treat_expr(self, expr);
+    }
+
+    /// Called for each [CastableExpr].
+    fn visit_castable_expr(&mut self, expr: &CastableExpr) {
+        walk_castable_expr(self, expr);
+    }
+
+    /// Called for each [CastExpr].
+    fn visit_cast_expr(&mut self, expr: &CastExpr) {
+        walk_cast_expr(self, expr);
+    }
+
+    /// Called for each [ArrowExpr].
+    fn visit_arrow_expr(&mut self, expr: &ArrowExpr) {
+        walk_arrow_expr(self, expr);
+    }
+
+    /// Called for each [UnaryExpr].
+    fn visit_unary_expr(&mut self, expr: &UnaryExpr) {
+        walk_unary_expr(self, expr);
+    }
+
+    /// Called for each [SimpleMapExpr].
+    fn visit_simple_map_expr(&mut self, expr: &SimpleMapExpr) {
+        walk_simple_map_expr(self, expr);
+    }
+
+    /// Called for each [PathExpr].
+    fn visit_path_expr(&mut self, expr: &PathExpr) {
+        walk_path_expr(self, expr);
+    }
+
+    /// Called for each [RelativePathExpr].
+    fn visit_relative_path_expr(&mut self, expr: &RelativePathExpr) {
+        walk_relative_path_expr(self, expr);
+    }
+
+    /// Called for each [StepExpr].
+    fn visit_step_expr(&mut self, expr: &StepExpr) {
+        walk_step_expr(self, expr);
+    }
+
+    /// Called for each [AxisStep].
+    fn visit_axis_step(&mut self, step: &AxisStep) {
+        walk_axis_step(self, step);
+    }
+
+    /// The node test of an axis step, such as `div`, `*` or `text()`.
+    fn visit_node_test(&mut self, _node_test: &NodeTest) {}
+
+    /// Called for each [Predicate].
+    fn visit_predicate(&mut self, predicate: &Predicate) {
+        walk_predicate(self, predicate);
+    }
+
+    /// Called for each [PostfixExpr].
+    fn visit_postfix_expr(&mut self, expr: &PostfixExpr) {
+        walk_postfix_expr(self, expr);
+    }
+
+    /// Called for each [PrimaryExpr].
+    fn visit_primary_expr(&mut self, expr: &PrimaryExpr) {
+        walk_primary_expr(self, expr);
+    }
+
+    /// Called for each [Literal].
+    fn visit_literal(&mut self, _literal: &Literal) {}
+
+    /// Called for each [VarRef].
+    fn visit_var_ref(&mut self, var_ref: &VarRef) {
+        walk_var_ref(self, var_ref);
+    }
+
+    /// The name of a variable, either where it is bound by `for`, `let`, `some` or `every`
+    /// or where it is referenced.
+    fn visit_var_name(&mut self, _var_name: &VarName) {}
+
+    /// Called for each [ParenthesizedExpr].
+    fn visit_parenthesized_expr(&mut self, expr: &ParenthesizedExpr) {
+        walk_parenthesized_expr(self, expr);
+    }
+
+    /// Called for each [EnclosedExpr].
+    fn visit_enclosed_expr(&mut self, expr: &EnclosedExpr) {
+        walk_enclosed_expr(self, expr);
+    }
+
+    /// Called for each [FunctionCall].
+    fn visit_function_call(&mut self, function_call: &FunctionCall) {
+        walk_function_call(self, function_call);
+    }
+
+    /// Called for each [ArgumentList].
+    fn visit_argument_list(&mut self, argument_list: &ArgumentList) {
+        walk_argument_list(self, argument_list);
+    }
+
+    /// Called for each [FunctionItemExpr].
+    fn visit_function_item_expr(&mut self, expr: &FunctionItemExpr) {
+        walk_function_item_expr(self, expr);
+    }
+
+    /// Called for each [NamedFunctionRef].
+    fn visit_named_function_ref(&mut self, _function_ref: &NamedFunctionRef) {}
+
+    /// Called for each [InlineFunctionExpr].
+    fn visit_inline_function_expr(&mut self, expr: &InlineFunctionExpr) {
+        walk_inline_function_expr(self, expr);
+    }
+
+    /// Called for each [MapConstructor].
+    fn visit_map_constructor(&mut self, constructor: &MapConstructor) {
+        walk_map_constructor(self, constructor);
+    }
+
+    /// Called for each [ArrayConstructor].
+    fn visit_array_constructor(&mut self, constructor: &ArrayConstructor) {
+        walk_array_constructor(self, constructor);
+    }
+
+    /// The key of a lookup, such as `name` in `?name`.
+    fn visit_key_specifier(&mut self, key_specifier: &KeySpecifier) {
+        walk_key_specifier(self, key_specifier);
+    }
+
+    /// A sequence type, such as `xs:string*` in `treat as xs:string*`.
+    fn visit_sequence_type(&mut self, _sequence_type: &SequenceType) {}
+}
+
+/// Visit the children of the given [Xpath].
+pub fn walk_xpath<V: Visitor + ?Sized>(visitor: &mut V, xpath: &Xpath) {
+    visitor.visit_expr(&xpath.0);
+}
+
+/// Visit the children of the given [Expr].
+pub fn walk_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &Expr) {
+    visitor.visit_expr_single(&expr.expr);
+    for item in expr.items.iter() {
+        visitor.visit_expr_single(item);
+    }
+}
+
+/// Visit the children of the given [ExprSingle].
+pub fn walk_expr_single<V: Visitor + ?Sized>(visitor: &mut V, expr: &ExprSingle) {
+    match expr {
+        ExprSingle::ForExpr(x) => visitor.visit_for_expr(x),
+        ExprSingle::LetExpr(x) => visitor.visit_let_expr(x),
+        ExprSingle::QuantifiedExpr(x) => visitor.visit_quantified_expr(x),
+        ExprSingle::IfExpr(x) => visitor.visit_if_expr(x),
+        ExprSingle::OrExpr(x) => visitor.visit_or_expr(x),
+    }
+}
+
+/// Visit the children of the given [ForExpr].
+pub fn walk_for_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &ForExpr) {
+    for binding in std::iter::once(&expr.clause.binding).chain(expr.clause.extras.iter()) {
+        visitor.visit_var_name(&binding.var);
+        visitor.visit_expr_single(&binding.expr);
+    }
+
+    visitor.visit_expr_single(&expr.expr);
+}
+
+/// Visit the children of the given [LetExpr].
+pub fn walk_let_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &LetExpr) {
+    for binding in std::iter::once(&expr.clause.binding).chain(expr.clause.extras.iter()) {
+        visitor.visit_var_name(&binding.var);
+        visitor.visit_expr_single(&binding.expr);
+    }
+
+    visitor.visit_expr_single(&expr.expr);
+}
+
+/// Visit the children of the given [QuantifiedExpr].
+pub fn walk_quantified_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &QuantifiedExpr) {
+    for item in std::iter::once(&expr.item).chain(expr.extras.iter()) {
+        visitor.visit_var_name(&item.var);
+        visitor.visit_expr_single(&item.expr);
+    }
+
+    visitor.visit_expr_single(&expr.satisfies);
+}
+
+/// Visit the children of the given [IfExpr].
+pub fn walk_if_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &IfExpr) {
+    visitor.visit_expr(&expr.condition);
+    visitor.visit_expr_single(&expr.then);
+    visitor.visit_expr_single(&expr.else_expr);
+}
+
+/// Visit the children of the given [OrExpr].
+pub fn walk_or_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &OrExpr) {
+    visitor.visit_and_expr(&expr.expr);
+    for item in expr.items.iter() {
+        visitor.visit_and_expr(item);
+    }
+}
+
+/// Visit the children of the given [AndExpr].
+pub fn walk_and_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &AndExpr) {
+    visitor.visit_comparison_expr(&expr.expr);
+    for item in expr.items.iter() {
+        visitor.visit_comparison_expr(item);
+    }
+}
+
+/// Visit the children of the given [ComparisonExpr].
+pub fn walk_comparison_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &ComparisonExpr) {
+    visitor.visit_string_concat_expr(&expr.expr);
+    if let Some(comparison) = &expr.comparison {
+        visitor.visit_string_concat_expr(&comparison.1);
+    }
+}
+
+/// Visit the children of the given [StringConcatExpr].
+pub fn walk_string_concat_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &StringConcatExpr) {
+    visitor.visit_range_expr(&expr.expr);
+    for item in expr.items.iter() {
+        visitor.visit_range_expr(item);
+    }
+}
+
+/// Visit the children of the given [RangeExpr].
+pub fn walk_range_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &RangeExpr) {
+    visitor.visit_additive_expr(&expr.expr);
+    if let Some(to_expr) = &expr.to_expr {
+        visitor.visit_additive_expr(to_expr);
+    }
+}
+
+/// Visit the children of the given [AdditiveExpr].
+pub fn walk_additive_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &AdditiveExpr) {
+    visitor.visit_multiplicative_expr(&expr.expr);
+    for item in expr.items.iter() {
+        visitor.visit_multiplicative_expr(&item.1);
+    }
+}
+
+/// Visit the children of the given [MultiplicativeExpr].
+pub fn walk_multiplicative_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &MultiplicativeExpr) {
+    visitor.visit_union_expr(&expr.expr);
+    for item in expr.items.iter() {
+        visitor.visit_union_expr(&item.1);
+    }
+}
+
+/// Visit the children of the given [UnionExpr].
+pub fn walk_union_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &UnionExpr) {
+    visitor.visit_intersect_except_expr(&expr.expr);
+    for item in expr.items.iter() {
+        visitor.visit_intersect_except_expr(&item.1);
+    }
+}
+
+/// Visit the children of the given [IntersectExceptExpr].
+pub fn walk_intersect_except_expr<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    expr: &IntersectExceptExpr,
+) {
+    visitor.visit_instanceof_expr(&expr.expr);
+    for item in expr.items.iter() {
+        visitor.visit_instanceof_expr(&item.1);
+    }
+}
+
+/// Visit the children of the given [InstanceofExpr].
+pub fn walk_instanceof_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &InstanceofExpr) {
+    visitor.visit_treat_expr(&expr.expr);
+    if let Some(sequence_type) = &expr.instanceof_type {
+        visitor.visit_sequence_type(sequence_type);
+    }
+}
+
+/// Visit the children of the given [TreatExpr].
+pub fn walk_treat_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &TreatExpr) {
+    visitor.visit_castable_expr(&expr.expr);
+    if let Some(sequence_type) = &expr.treat_type {
+        visitor.visit_sequence_type(sequence_type);
+    }
+}
+
+/// Visit the children of the given [CastableExpr].
+pub fn walk_castable_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &CastableExpr) {
+    visitor.visit_cast_expr(&expr.expr);
+}
+
+/// Visit the children of the given [CastExpr].
+pub fn walk_cast_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &CastExpr) {
+    visitor.visit_arrow_expr(&expr.expr);
+}
+
+/// Visit the children of the given [ArrowExpr].
+pub fn walk_arrow_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &ArrowExpr) {
+    visitor.visit_unary_expr(&expr.expr);
+    for item in expr.items.iter() {
+        match &item.function_specifier {
+            ArrowFunctionSpecifier::Name(_) => {}
+            ArrowFunctionSpecifier::VarRef(x) => visitor.visit_var_ref(x),
+            ArrowFunctionSpecifier::ParenthesizedExpr(x) => visitor.visit_parenthesized_expr(x),
+        }
+
+        visitor.visit_argument_list(&item.arguments);
+    }
+}
+
+/// Visit the children of the given [UnaryExpr].
+pub fn walk_unary_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &UnaryExpr) {
+    visitor.visit_simple_map_expr(&expr.expr.0);
+}
+
+/// Visit the children of the given [SimpleMapExpr].
+pub fn walk_simple_map_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &SimpleMapExpr) {
+    visitor.visit_path_expr(&expr.expr);
+    for item in expr.items.iter() {
+        visitor.visit_path_expr(item);
+    }
+}
+
+/// Visit the children of the given [PathExpr].
+pub fn walk_path_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &PathExpr) {
+    match expr {
+        PathExpr::LeadingSlash(None) => {}
+        PathExpr::LeadingSlash(Some(x)) | PathExpr::LeadingDoubleSlash(x) | PathExpr::Plain(x) => {
+            visitor.visit_relative_path_expr(x)
+        }
+    }
+}
+
+/// Visit the children of the given [RelativePathExpr].
+pub fn walk_relative_path_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &RelativePathExpr) {
+    visitor.visit_step_expr(&expr.expr);
+    for item in expr.items.iter() {
+        visitor.visit_step_expr(&item.1);
+    }
+}
+
+/// Visit the children of the given [StepExpr].
+pub fn walk_step_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &StepExpr) {
+    match expr {
+        StepExpr::PostfixExpr(x) => visitor.visit_postfix_expr(x),
+        StepExpr::AxisStep(x) => visitor.visit_axis_step(x),
+    }
+}
+
+/// Visit the children of the given [AxisStep].
+pub fn walk_axis_step<V: Visitor + ?Sized>(visitor: &mut V, step: &AxisStep) {
+    match &step.step_type {
+        AxisStepType::ReverseStep(ReverseStep::Full(_, node_test))
+        | AxisStepType::ForwardStep(ForwardStep::Full(_, node_test)) => {
+            visitor.visit_node_test(node_test)
+        }
+        AxisStepType::ForwardStep(ForwardStep::Abbreviated(x)) => {
+            visitor.visit_node_test(&x.node_test)
+        }
+        AxisStepType::ReverseStep(ReverseStep::Abbreviated) => {}
+    }
+
+    for predicate in step.predicates.iter() {
+        visitor.visit_predicate(predicate);
+    }
+}
+
+/// Visit the children of the given [Predicate].
+pub fn walk_predicate<V: Visitor + ?Sized>(visitor: &mut V, predicate: &Predicate) {
+    visitor.visit_expr(&predicate.0);
+}
+
+/// Visit the children of the given [PostfixExpr].
+pub fn walk_postfix_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &PostfixExpr) {
+    visitor.visit_primary_expr(&expr.expr);
+    for item in expr.items.iter() {
+        match item {
+            PostfixExprItem::Predicate(x) => visitor.visit_predicate(x),
+            PostfixExprItem::ArgumentList(x) => visitor.visit_argument_list(x),
+            PostfixExprItem::Lookup(Lookup(x)) => visitor.visit_key_specifier(x),
+        }
+    }
+}
+
+/// Visit the children of the given [PrimaryExpr].
+pub fn walk_primary_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &PrimaryExpr) {
+    match expr {
+        PrimaryExpr::Literal(x) => visitor.visit_literal(x),
+        PrimaryExpr::VarRef(x) => visitor.visit_var_ref(x),
+        PrimaryExpr::ParenthesizedExpr(x) => visitor.visit_parenthesized_expr(x),
+        PrimaryExpr::ContextItemExpr => {}
+        PrimaryExpr::FunctionCall(x) => visitor.visit_function_call(x),
+        PrimaryExpr::FunctionItemExpr(x) => visitor.visit_function_item_expr(x),
+        PrimaryExpr::MapConstructor(x) => visitor.visit_map_constructor(x),
+        PrimaryExpr::ArrayConstructor(x) => visitor.visit_array_constructor(x),
+        PrimaryExpr::UnaryLookup(x) => visitor.visit_key_specifier(&x.0),
+    }
+}
+
+/// Visit the children of the given [VarRef].
+pub fn walk_var_ref<V: Visitor + ?Sized>(visitor: &mut V, var_ref: &VarRef) {
+    visitor.visit_var_name(&var_ref.0);
+}
+
+/// Visit the children of the given [ParenthesizedExpr].
+pub fn walk_parenthesized_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &ParenthesizedExpr) {
+    if let Some(x) = &expr.0 {
+        visitor.visit_expr(x);
+    }
+}
+
+/// Visit the children of the given [EnclosedExpr].
+pub fn walk_enclosed_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &EnclosedExpr) {
+    if let Some(x) = &expr.0 {
+        visitor.visit_expr(x);
+    }
+}
+
+/// Visit the children of the given [FunctionCall].
+pub fn walk_function_call<V: Visitor + ?Sized>(visitor: &mut V, function_call: &FunctionCall) {
+    visitor.visit_argument_list(&function_call.argument_list);
+}
+
+/// Visit the children of the given [ArgumentList].
+pub fn walk_argument_list<V: Visitor + ?Sized>(visitor: &mut V, argument_list: &ArgumentList) {
+    for argument in argument_list.0.iter() {
+        match argument {
+            Argument::ExprSingle(x) => visitor.visit_expr_single(x),
+            Argument::ArgumentPlaceHolder => {}
+        }
+    }
+}
+
+/// Visit the children of the given [FunctionItemExpr].
+pub fn walk_function_item_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &FunctionItemExpr) {
+    match expr {
+        FunctionItemExpr::NamedFunctionRef(x) => visitor.visit_named_function_ref(x),
+        FunctionItemExpr::InlineFunctionExpr(x) => visitor.visit_inline_function_expr(x),
+    }
+}
+
+/// Visit the children of the given [InlineFunctionExpr].
+pub fn walk_inline_function_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &InlineFunctionExpr) {
+    if let Some(param_list) = &expr.param_list {
+        for param in param_list.0.iter() {
+            if let Some(type_declaration) = &param.type_declaration {
+                visitor.visit_sequence_type(&type_declaration.0);
+            }
+        }
+    }
+
+    if let Some(sequence_type) = &expr.sequence_type {
+        visitor.visit_sequence_type(sequence_type);
+    }
+
+    visitor.visit_enclosed_expr(&expr.body.0);
+}
+
+/// Visit the children of the given [MapConstructor].
+pub fn walk_map_constructor<V: Visitor + ?Sized>(visitor: &mut V, constructor: &MapConstructor) {
+    for entry in constructor.entries.iter() {
+        visitor.visit_expr_single(&entry.key);
+        visitor.visit_expr_single(&entry.value);
+    }
+}
+
+/// Visit the children of the given [ArrayConstructor].
+pub fn walk_array_constructor<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    constructor: &ArrayConstructor,
+) {
+    match constructor {
+        ArrayConstructor::SquareArrayConstructor(x) => {
+            for entry in x.entries.iter() {
+                visitor.visit_expr_single(entry);
+            }
+        }
+        ArrayConstructor::CurlyArrayConstructor(x) => visitor.visit_enclosed_expr(&x.0),
+    }
+}
+
+/// Visit the children of the given [KeySpecifier].
+pub fn walk_key_specifier<V: Visitor + ?Sized>(visitor: &mut V, key_specifier: &KeySpecifier) {
+    if let KeySpecifier::ParenthesizedExpr(x) = key_specifier {
+        visitor.visit_parenthesized_expr(x);
+    }
+}
+
+/// Change the nodes of an [Xpath] expression in place.
+///
+/// See the [module documentation](self).
+pub trait VisitorMut {
+    /// Called for each [Xpath].
+    fn visit_xpath_mut(&mut self, xpath: &mut Xpath) {
+        walk_xpath_mut(self, xpath);
+    }
+
+    /// Called for each [Expr].
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        walk_expr_mut(self, expr);
+    }
+
+    /// Called for each [ExprSingle].
+    fn visit_expr_single_mut(&mut self, expr: &mut ExprSingle) {
+        walk_expr_single_mut(self, expr);
+    }
+
+    /// Called for each [ForExpr].
+    fn visit_for_expr_mut(&mut self, expr: &mut ForExpr) {
+        walk_for_expr_mut(self, expr);
+    }
+
+    /// Called for each [LetExpr].
+    fn visit_let_expr_mut(&mut self, expr: &mut LetExpr) {
+        walk_let_expr_mut(self, expr);
+    }
+
+    /// Called for each [QuantifiedExpr].
+    fn visit_quantified_expr_mut(&mut self, expr: &mut QuantifiedExpr) {
+        walk_quantified_expr_mut(self, expr);
+    }
+
+    /// Called for each [IfExpr].
+    fn visit_if_expr_mut(&mut self, expr: &mut IfExpr) {
+        walk_if_expr_mut(self, expr);
+    }
+
+    /// Called for each [OrExpr].
+    fn visit_or_expr_mut(&mut self, expr: &mut OrExpr) {
+        walk_or_expr_mut(self, expr);
+    }
+
+    /// Called for each [AndExpr].
+    fn visit_and_expr_mut(&mut self, expr: &mut AndExpr) {
+        walk_and_expr_mut(self, expr);
+    }
+
+    /// Called for each [ComparisonExpr].
+    fn visit_comparison_expr_mut(&mut self, expr: &mut ComparisonExpr) {
+        walk_comparison_expr_mut(self, expr);
+    }
+
+    /// Called for each [StringConcatExpr].
+    fn visit_string_concat_expr_mut(&mut self, expr: &mut StringConcatExpr) {
+        walk_string_concat_expr_mut(self, expr);
+    }
+
+    /// Called for each [RangeExpr].
+    fn visit_range_expr_mut(&mut self, expr: &mut RangeExpr) {
+        walk_range_expr_mut(self, expr);
+    }
+
+    /// Called for each [AdditiveExpr].
+    fn visit_additive_expr_mut(&mut self, expr: &mut AdditiveExpr) {
+        walk_additive_expr_mut(self, expr);
+    }
+
+    /// Called for each [MultiplicativeExpr].
+    fn visit_multiplicative_expr_mut(&mut self, expr: &mut MultiplicativeExpr) {
+        walk_multiplicative_expr_mut(self, expr);
+    }
+
+    /// Called for each [UnionExpr].
+    fn visit_union_expr_mut(&mut self, expr: &mut UnionExpr) {
+        walk_union_expr_mut(self, expr);
+    }
+
+    /// Called for each [IntersectExceptExpr].
+    fn visit_intersect_except_expr_mut(&mut self, expr: &mut IntersectExceptExpr) {
+        walk_intersect_except_expr_mut(self, expr);
+    }
+
+    /// Called for each [InstanceofExpr].
+    fn visit_instanceof_expr_mut(&mut self, expr: &mut InstanceofExpr) {
+        walk_instanceof_expr_mut(self, expr);
+    }
+
+    /// Called for each [TreatExpr].
+    fn visit_treat_expr_mut(&mut self, expr: &mut TreatExpr) {
+        walk_treat_expr_mut(self, expr);
+    }
+
+    /// Called for each [CastableExpr].
+    fn visit_castable_expr_mut(&mut self, expr: &mut CastableExpr) {
+        walk_castable_expr_mut(self, expr);
+    }
+
+    /// Called for each [CastExpr].
+    fn visit_cast_expr_mut(&mut self, expr: &mut CastExpr) {
+        walk_cast_expr_mut(self, expr);
+    }
+
+    /// Called for each [ArrowExpr].
+    fn visit_arrow_expr_mut(&mut self, expr: &mut ArrowExpr) {
+        walk_arrow_expr_mut(self, expr);
+    }
+
+    /// Called for each [UnaryExpr].
+    fn visit_unary_expr_mut(&mut self, expr: &mut UnaryExpr) {
+        walk_unary_expr_mut(self, expr);
+    }
+
+    /// Called for each [SimpleMapExpr].
+    fn visit_simple_map_expr_mut(&mut self, expr: &mut SimpleMapExpr) {
+        walk_simple_map_expr_mut(self, expr);
+    }
+
+    /// Called for each [PathExpr].
+    fn visit_path_expr_mut(&mut self, expr: &mut PathExpr) {
+        walk_path_expr_mut(self, expr);
+    }
+
+    /// Called for each [RelativePathExpr].
+    fn visit_relative_path_expr_mut(&mut self, expr: &mut RelativePathExpr) {
+        walk_relative_path_expr_mut(self, expr);
+    }
+
+    /// Called for each [StepExpr].
+    fn visit_step_expr_mut(&mut self, expr: &mut StepExpr) {
+        walk_step_expr_mut(self, expr);
+    }
+
+    /// Called for each [AxisStep].
+    fn visit_axis_step_mut(&mut self, step: &mut AxisStep) {
+        walk_axis_step_mut(self, step);
+    }
+
+    /// The node test of an axis step, such as `div`, `*` or `text()`.
+    fn visit_node_test_mut(&mut self, _node_test: &mut NodeTest) {}
+
+    /// Called for each [Predicate].
+    fn visit_predicate_mut(&mut self, predicate: &mut Predicate) {
+        walk_predicate_mut(self, predicate);
+    }
+
+    /// Called for each [PostfixExpr].
+    fn visit_postfix_expr_mut(&mut self, expr: &mut PostfixExpr) {
+        walk_postfix_expr_mut(self, expr);
+    }
+
+    /// Called for each [PrimaryExpr].
+    fn visit_primary_expr_mut(&mut self, expr: &mut PrimaryExpr) {
+        walk_primary_expr_mut(self, expr);
+    }
+
+    /// Called for each [Literal].
+    fn visit_literal_mut(&mut self, _literal: &mut Literal) {}
+
+    /// Called for each [VarRef].
+    fn visit_var_ref_mut(&mut self, var_ref: &mut VarRef) {
+        walk_var_ref_mut(self, var_ref);
+    }
+
+    /// The name of a variable, either where it is bound by `for`, `let`, `some` or `every`
+    /// or where it is referenced.
+    fn visit_var_name_mut(&mut self, _var_name: &mut VarName) {}
+
+    /// Called for each [ParenthesizedExpr].
+    fn visit_parenthesized_expr_mut(&mut self, expr: &mut ParenthesizedExpr) {
+        walk_parenthesized_expr_mut(self, expr);
+    }
+
+    /// Called for each [EnclosedExpr].
+    fn visit_enclosed_expr_mut(&mut self, expr: &mut EnclosedExpr) {
+        walk_enclosed_expr_mut(self, expr);
+    }
+
+    /// Called for each [FunctionCall].
+    fn visit_function_call_mut(&mut self, function_call: &mut FunctionCall) {
+        walk_function_call_mut(self, function_call);
+    }
+
+    /// Called for each [ArgumentList].
+    fn visit_argument_list_mut(&mut self, argument_list: &mut ArgumentList) {
+        walk_argument_list_mut(self, argument_list);
+    }
+
+    /// Called for each [FunctionItemExpr].
+    fn visit_function_item_expr_mut(&mut self, expr: &mut FunctionItemExpr) {
+        walk_function_item_expr_mut(self, expr);
+    }
+
+    /// Called for each [NamedFunctionRef].
+    fn visit_named_function_ref_mut(&mut self, _function_ref: &mut NamedFunctionRef) {}
+
+    /// Called for each [InlineFunctionExpr].
+    fn visit_inline_function_expr_mut(&mut self, expr: &mut InlineFunctionExpr) {
+        walk_inline_function_expr_mut(self, expr);
+    }
+
+    /// Called for each [MapConstructor].
+    fn visit_map_constructor_mut(&mut self, constructor: &mut MapConstructor) {
+        walk_map_constructor_mut(self, constructor);
+    }
+
+    /// Called for each [ArrayConstructor].
+    fn visit_array_constructor_mut(&mut self, constructor: &mut ArrayConstructor) {
+        walk_array_constructor_mut(self, constructor);
+    }
+
+    /// The key of a lookup, such as `name` in `?name`.
+    fn visit_key_specifier_mut(&mut self, key_specifier: &mut KeySpecifier) {
+        walk_key_specifier_mut(self, key_specifier);
+    }
+
+    /// A sequence type, such as `xs:string*` in `treat as xs:string*`.
+    fn visit_sequence_type_mut(&mut self, _sequence_type: &mut SequenceType) {}
+}
+
+/// Visit the children of the given [Xpath].
+pub fn walk_xpath_mut<V: VisitorMut + ?Sized>(visitor: &mut V, xpath: &mut Xpath) {
+    visitor.visit_expr_mut(&mut xpath.0);
+}
+
+/// Visit the children of the given [Expr].
+pub fn walk_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut Expr) {
+    visitor.visit_expr_single_mut(&mut expr.expr);
+    for item in expr.items.iter_mut() {
+        visitor.visit_expr_single_mut(item);
+    }
+}
+
+/// Visit the children of the given [ExprSingle].
+pub fn walk_expr_single_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut ExprSingle) {
+    match expr {
+        ExprSingle::ForExpr(x) => visitor.visit_for_expr_mut(x),
+        ExprSingle::LetExpr(x) => visitor.visit_let_expr_mut(x),
+        ExprSingle::QuantifiedExpr(x) => visitor.visit_quantified_expr_mut(x),
+        ExprSingle::IfExpr(x) => visitor.visit_if_expr_mut(x),
+        ExprSingle::OrExpr(x) => visitor.visit_or_expr_mut(x),
+    }
+}
+
+/// Visit the children of the given [ForExpr].
+pub fn walk_for_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut ForExpr) {
+    let clause = &mut expr.clause;
+    for binding in std::iter::once(&mut clause.binding).chain(clause.extras.iter_mut()) {
+        visitor.visit_var_name_mut(&mut binding.var);
+        visitor.visit_expr_single_mut(&mut binding.expr);
+    }
+
+    visitor.visit_expr_single_mut(&mut expr.expr);
+}
+
+/// Visit the children of the given [LetExpr].
+pub fn walk_let_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut LetExpr) {
+    let clause = &mut expr.clause;
+    for binding in std::iter::once(&mut clause.binding).chain(clause.extras.iter_mut()) {
+        visitor.visit_var_name_mut(&mut binding.var);
+        visitor.visit_expr_single_mut(&mut binding.expr);
+    }
+
+    visitor.visit_expr_single_mut(&mut expr.expr);
+}
+
+/// Visit the children of the given [QuantifiedExpr].
+pub fn walk_quantified_expr_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    expr: &mut QuantifiedExpr,
+) {
+    for item in std::iter::once(&mut expr.item).chain(expr.extras.iter_mut()) {
+        visitor.visit_var_name_mut(&mut item.var);
+        visitor.visit_expr_single_mut(&mut item.expr);
+    }
+
+    visitor.visit_expr_single_mut(&mut expr.satisfies);
+}
+
+/// Visit the children of the given [IfExpr].
+pub fn walk_if_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut IfExpr) {
+    visitor.visit_expr_mut(&mut expr.condition);
+    visitor.visit_expr_single_mut(&mut expr.then);
+    visitor.visit_expr_single_mut(&mut expr.else_expr);
+}
+
+/// Visit the children of the given [OrExpr].
+pub fn walk_or_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut OrExpr) {
+    visitor.visit_and_expr_mut(&mut expr.expr);
+    for item in expr.items.iter_mut() {
+        visitor.visit_and_expr_mut(item);
+    }
+}
+
+/// Visit the children of the given [AndExpr].
+pub fn walk_and_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut AndExpr) {
+    visitor.visit_comparison_expr_mut(&mut expr.expr);
+    for item in expr.items.iter_mut() {
+        visitor.visit_comparison_expr_mut(item);
+    }
+}
+
+/// Visit the children of the given [ComparisonExpr].
+pub fn walk_comparison_expr_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    expr: &mut ComparisonExpr,
+) {
+    visitor.visit_string_concat_expr_mut(&mut expr.expr);
+    if let Some(comparison) = &mut expr.comparison {
+        visitor.visit_string_concat_expr_mut(&mut comparison.1);
+    }
+}
+
+/// Visit the children of the given [StringConcatExpr].
+pub fn walk_string_concat_expr_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    expr: &mut StringConcatExpr,
+) {
+    visitor.visit_range_expr_mut(&mut expr.expr);
+    for item in expr.items.iter_mut() {
+        visitor.visit_range_expr_mut(item);
+    }
+}
+
+/// Visit the children of the given [RangeExpr].
+pub fn walk_range_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut RangeExpr) {
+    visitor.visit_additive_expr_mut(&mut expr.expr);
+    if let Some(to_expr) = &mut expr.to_expr {
+        visitor.visit_additive_expr_mut(to_expr);
+    }
+}
+
+/// Visit the children of the given [AdditiveExpr].
+pub fn walk_additive_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut AdditiveExpr) {
+    visitor.visit_multiplicative_expr_mut(&mut expr.expr);
+    for item in expr.items.iter_mut() {
+        visitor.visit_multiplicative_expr_mut(&mut item.1);
+    }
+}
+
+/// Visit the children of the given [MultiplicativeExpr].
+pub fn walk_multiplicative_expr_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    expr: &mut MultiplicativeExpr,
+) {
+    visitor.visit_union_expr_mut(&mut expr.expr);
+    for item in expr.items.iter_mut() {
+        visitor.visit_union_expr_mut(&mut item.1);
+    }
+}
+
+/// Visit the children of the given [UnionExpr].
+pub fn walk_union_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut UnionExpr) {
+    visitor.visit_intersect_except_expr_mut(&mut expr.expr);
+    for item in expr.items.iter_mut() {
+        visitor.visit_intersect_except_expr_mut(&mut item.1);
+    }
+}
+
+/// Visit the children of the given [IntersectExceptExpr].
+pub fn walk_intersect_except_expr_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    expr: &mut IntersectExceptExpr,
+) {
+    visitor.visit_instanceof_expr_mut(&mut expr.expr);
+    for item in expr.items.iter_mut() {
+        visitor.visit_instanceof_expr_mut(&mut item.1);
+    }
+}
+
+/// Visit the children of the given [InstanceofExpr].
+pub fn walk_instanceof_expr_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    expr: &mut InstanceofExpr,
+) {
+    visitor.visit_treat_expr_mut(&mut expr.expr);
+    if let Some(sequence_type) = &mut expr.instanceof_type {
+        visitor.visit_sequence_type_mut(sequence_type);
+    }
+}
+
+/// Visit the children of the given [TreatExpr].
+pub fn walk_treat_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut TreatExpr) {
+    visitor.visit_castable_expr_mut(&mut expr.expr);
+    if let Some(sequence_type) = &mut expr.treat_type {
+        visitor.visit_sequence_type_mut(sequence_type);
+    }
+}
+
+/// Visit the children of the given [CastableExpr].
+pub fn walk_castable_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut CastableExpr) {
+    visitor.visit_cast_expr_mut(&mut expr.expr);
+}
+
+/// Visit the children of the given [CastExpr].
+pub fn walk_cast_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut CastExpr) {
+    visitor.visit_arrow_expr_mut(&mut expr.expr);
+}
+
+/// Visit the children of the given [ArrowExpr].
+pub fn walk_arrow_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut ArrowExpr) {
+    visitor.visit_unary_expr_mut(&mut expr.expr);
+    for item in expr.items.iter_mut() {
+        match &mut item.function_specifier {
+            ArrowFunctionSpecifier::Name(_) => {}
+            ArrowFunctionSpecifier::VarRef(x) => visitor.visit_var_ref_mut(x),
+            ArrowFunctionSpecifier::ParenthesizedExpr(x) => visitor.visit_parenthesized_expr_mut(x),
+        }
+
+        visitor.visit_argument_list_mut(&mut item.arguments);
+    }
+}
+
+/// Visit the children of the given [UnaryExpr].
+pub fn walk_unary_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut UnaryExpr) {
+    visitor.visit_simple_map_expr_mut(&mut expr.expr.0);
+}
+
+/// Visit the children of the given [SimpleMapExpr].
+pub fn walk_simple_map_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut SimpleMapExpr) {
+    visitor.visit_path_expr_mut(&mut expr.expr);
+    for item in expr.items.iter_mut() {
+        visitor.visit_path_expr_mut(item);
+    }
+}
+
+/// Visit the children of the given [PathExpr].
+pub fn walk_path_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut PathExpr) {
+    match expr {
+        PathExpr::LeadingSlash(None) => {}
+        PathExpr::LeadingSlash(Some(x)) | PathExpr::LeadingDoubleSlash(x) | PathExpr::Plain(x) => {
+            visitor.visit_relative_path_expr_mut(x)
+        }
+    }
+}
+
+/// Visit the children of the given [RelativePathExpr].
+pub fn walk_relative_path_expr_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    expr: &mut RelativePathExpr,
+) {
+    visitor.visit_step_expr_mut(&mut expr.expr);
+    for item in expr.items.iter_mut() {
+        visitor.visit_step_expr_mut(&mut item.1);
+    }
+}
+
+/// Visit the children of the given [StepExpr].
+pub fn walk_step_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut StepExpr) {
+    match expr {
+        StepExpr::PostfixExpr(x) => visitor.visit_postfix_expr_mut(x),
+        StepExpr::AxisStep(x) => visitor.visit_axis_step_mut(x),
+    }
+}
+
+/// Visit the children of the given [AxisStep].
+pub fn walk_axis_step_mut<V: VisitorMut + ?Sized>(visitor: &mut V, step: &mut AxisStep) {
+    match &mut step.step_type {
+        AxisStepType::ReverseStep(ReverseStep::Full(_, node_test))
+        | AxisStepType::ForwardStep(ForwardStep::Full(_, node_test)) => {
+            visitor.visit_node_test_mut(node_test)
+        }
+        AxisStepType::ForwardStep(ForwardStep::Abbreviated(x)) => {
+            visitor.visit_node_test_mut(&mut x.node_test)
+        }
+        AxisStepType::ReverseStep(ReverseStep::Abbreviated) => {}
+    }
+
+    for predicate in step.predicates.iter_mut() {
+        visitor.visit_predicate_mut(predicate);
+    }
+}
+
+/// Visit the children of the given [Predicate].
+pub fn walk_predicate_mut<V: VisitorMut + ?Sized>(visitor: &mut V, predicate: &mut Predicate) {
+    visitor.visit_expr_mut(&mut predicate.0);
+}
+
+/// Visit the children of the given [PostfixExpr].
+pub fn walk_postfix_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut PostfixExpr) {
+    visitor.visit_primary_expr_mut(&mut expr.expr);
+    for item in expr.items.iter_mut() {
+        match item {
+            PostfixExprItem::Predicate(x) => visitor.visit_predicate_mut(x),
+            PostfixExprItem::ArgumentList(x) => visitor.visit_argument_list_mut(x),
+            PostfixExprItem::Lookup(Lookup(x)) => visitor.visit_key_specifier_mut(x),
+        }
+    }
+}
+
+/// Visit the children of the given [PrimaryExpr].
+pub fn walk_primary_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut PrimaryExpr) {
+    match expr {
+        PrimaryExpr::Literal(x) => visitor.visit_literal_mut(x),
+        PrimaryExpr::VarRef(x) => visitor.visit_var_ref_mut(x),
+        PrimaryExpr::ParenthesizedExpr(x) => visitor.visit_parenthesized_expr_mut(x),
+        PrimaryExpr::ContextItemExpr => {}
+        PrimaryExpr::FunctionCall(x) => visitor.visit_function_call_mut(x),
+        PrimaryExpr::FunctionItemExpr(x) => visitor.visit_function_item_expr_mut(x),
+        PrimaryExpr::MapConstructor(x) => visitor.visit_map_constructor_mut(x),
+        PrimaryExpr::ArrayConstructor(x) => visitor.visit_array_constructor_mut(x),
+        PrimaryExpr::UnaryLookup(x) => visitor.visit_key_specifier_mut(&mut x.0),
+    }
+}
+
+/// Visit the children of the given [VarRef].
+pub fn walk_var_ref_mut<V: VisitorMut + ?Sized>(visitor: &mut V, var_ref: &mut VarRef) {
+    visitor.visit_var_name_mut(&mut var_ref.0);
+}
+
+/// Visit the children of the given [ParenthesizedExpr].
+pub fn walk_parenthesized_expr_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    expr: &mut ParenthesizedExpr,
+) {
+    if let Some(x) = &mut expr.0 {
+        visitor.visit_expr_mut(x);
+    }
+}
+
+/// Visit the children of the given [EnclosedExpr].
+pub fn walk_enclosed_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut EnclosedExpr) {
+    if let Some(x) = &mut expr.0 {
+        visitor.visit_expr_mut(x);
+    }
+}
+
+/// Visit the children of the given [FunctionCall].
+pub fn walk_function_call_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    function_call: &mut FunctionCall,
+) {
+    visitor.visit_argument_list_mut(&mut function_call.argument_list);
+}
+
+/// Visit the children of the given [ArgumentList].
+pub fn walk_argument_list_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    argument_list: &mut ArgumentList,
+) {
+    for argument in argument_list.0.iter_mut() {
+        match argument {
+            Argument::ExprSingle(x) => visitor.visit_expr_single_mut(x),
+            Argument::ArgumentPlaceHolder => {}
+        }
+    }
+}
+
+/// Visit the children of the given [FunctionItemExpr].
+pub fn walk_function_item_expr_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    expr: &mut FunctionItemExpr,
+) {
+    match expr {
+        FunctionItemExpr::NamedFunctionRef(x) => visitor.visit_named_function_ref_mut(x),
+        FunctionItemExpr::InlineFunctionExpr(x) => visitor.visit_inline_function_expr_mut(x),
+    }
+}
+
+/// Visit the children of the given [InlineFunctionExpr].
+pub fn walk_inline_function_expr_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    expr: &mut InlineFunctionExpr,
+) {
+    if let Some(param_list) = &mut expr.param_list {
+        for param in param_list.0.iter_mut() {
+            if let Some(type_declaration) = &mut param.type_declaration {
+                visitor.visit_sequence_type_mut(&mut type_declaration.0);
+            }
+        }
+    }
+
+    if let Some(sequence_type) = &mut expr.sequence_type {
+        visitor.visit_sequence_type_mut(sequence_type);
+    }
+
+    visitor.visit_enclosed_expr_mut(&mut expr.body.0);
+}
+
+/// Visit the children of the given [MapConstructor].
+pub fn walk_map_constructor_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    constructor: &mut MapConstructor,
+) {
+    for entry in constructor.entries.iter_mut() {
+        visitor.visit_expr_single_mut(&mut entry.key);
+        visitor.visit_expr_single_mut(&mut entry.value);
+    }
+}
+
+/// Visit the children of the given [ArrayConstructor].
+pub fn walk_array_constructor_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    constructor: &mut ArrayConstructor,
+) {
+    match constructor {
+        ArrayConstructor::SquareArrayConstructor(x) => {
+            for entry in x.entries.iter_mut() {
+                visitor.visit_expr_single_mut(entry);
+            }
+        }
+        ArrayConstructor::CurlyArrayConstructor(x) => visitor.visit_enclosed_expr_mut(&mut x.0),
+    }
+}
+
+/// Visit the children of the given [KeySpecifier].
+pub fn walk_key_specifier_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    key_specifier: &mut KeySpecifier,
+) {
+    if let KeySpecifier::ParenthesizedExpr(x) = key_specifier {
+        visitor.visit_parenthesized_expr_mut(x);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::xpath::{
+        grammar::{
+            expressions::path_expressions::steps::node_tests::NameTest,
+            types::EQName,
+            xml_names::{PrefixedName, QName},
+        },
+        parse, ErrorCode,
+    };
+
+    use super::*;
+
+    #[derive(Default)]
+    struct FunctionNames(Vec<String>);
+
+    impl Visitor for FunctionNames {
+        fn visit_function_call(&mut self, function_call: &FunctionCall) {
+            self.0.push(function_call.name.to_string());
+            walk_function_call(self, function_call);
+        }
+    }
+
+    #[derive(Default)]
+    struct PositionalPredicates(Vec<String>);
+
+    impl Visitor for PositionalPredicates {
+        fn visit_predicate(&mut self, predicate: &Predicate) {
+            if predicate.0.to_string().parse::<u32>().is_ok() {
+                self.0.push(predicate.0.to_string());
+            }
+
+            walk_predicate(self, predicate);
+        }
+    }
+
+    /// Add a namespace prefix to every unprefixed name test.
+    struct AddPrefix(&'static str);
+
+    impl VisitorMut for AddPrefix {
+        fn visit_node_test_mut(&mut self, node_test: &mut NodeTest) {
+            if let NodeTest::NameTest(NameTest::Name(EQName::QName(QName::UnprefixedName(name)))) =
+                node_test
+            {
+                *node_test = NodeTest::NameTest(NameTest::Name(EQName::QName(
+                    QName::PrefixedName(PrefixedName {
+                        prefix: self.0.to_string(),
+                        local_part: name.clone(),
+                    }),
+                )));
+            }
+        }
+    }
+
+    /// Rename every function call to the given name.
+    struct RenameFunctions(&'static str);
+
+    impl VisitorMut for RenameFunctions {
+        fn visit_function_call_mut(&mut self, function_call: &mut FunctionCall) {
+            function_call.name = EQName::QName(QName::UnprefixedName(self.0.to_string()));
+            walk_function_call_mut(self, function_call);
+        }
+    }
+
+    #[test]
+    fn visit_should_reach_nested_function_calls() {
+        // arrange
+        let xpath =
+            parse("//div[contains(@class, string(number(.)))]/a[starts-with(@href, 'h')]").unwrap();
+        let mut visitor = FunctionNames::default();
+
+        // act
+        xpath.visit(&mut visitor);
+
+        // assert
+        assert_eq!(
+            visitor.0,
+            vec!["contains", "string", "number", "starts-with"]
+        );
+    }
+
+    #[test]
+    fn visit_should_find_positional_predicates() {
+        // arrange
+        let xpath = parse("//ul/li[2]/a[@href][1]").unwrap();
+        let mut visitor = PositionalPredicates::default();
+
+        // act
+        xpath.visit(&mut visitor);
+
+        // assert
+        assert_eq!(visitor.0, vec!["2", "1"]);
+    }
+
+    #[test]
+    fn walk_xpath_mut_should_add_prefix_to_name_tests() {
+        // arrange
+        let mut xpath = parse("//div[span]/a/@href").unwrap();
+
+        // act
+        walk_xpath_mut(&mut AddPrefix("h"), &mut xpath);
+
+        // assert
+        assert_eq!(xpath.to_string(), "//h:div[h:span]/h:a/@h:href");
+    }
+
+    #[test]
+    fn rewrite_should_not_change_original() {
+        // arrange
+        let xpath = parse("//div[contains(@class, 'a')]").unwrap();
+
+        // act
+        let rewritten = xpath.rewrite(&mut RenameFunctions("starts-with")).unwrap();
+
+        // assert
+        assert_eq!(xpath.to_string(), "//div[contains(@class, 'a')]");
+        assert_eq!(rewritten.to_string(), "//div[starts-with(@class, 'a')]");
+    }
+
+    #[test]
+    fn rewrite_should_fail_for_unknown_function() {
+        // arrange
+        let xpath = parse("//div[contains(@class, 'a')]").unwrap();
+
+        // act
+        let result = xpath.rewrite(&mut RenameFunctions("foo"));
+
+        // assert
+        let err = result.unwrap_err();
+        assert_eq!(err.code(), &ErrorCode::XPST0017, "{}", err);
+    }
+
+    #[test]
+    fn rewrite_should_fail_for_undeclared_prefix() {
+        // arrange
+        let xpath = parse("//div").unwrap();
+
+        // act
+        let result = xpath.rewrite(&mut AddPrefix("h"));
+
+        // assert
+        let err = result.unwrap_err();
+        assert_eq!(err.code(), &ErrorCode::XPST0081, "{}", err);
+    }
+}
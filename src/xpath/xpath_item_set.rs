@@ -1,13 +1,16 @@
 //! An ordered set of [`XpathItem`]s.
 
-use std::ops::Index;
+use std::{
+    hash::{Hash, Hasher},
+    ops::Index,
+};
 
 use indexmap::{self, IndexSet};
 
 use super::grammar::data_model::{AnyAtomicType, XpathItem};
 
 /// An ordered set of [`XpathItem`]s.
-#[derive(Debug)]
+#[derive(Debug, Clone, Default)]
 pub struct XpathItemSet<'tree> {
     index_set: IndexSet<XpathItem<'tree>>,
 }
@@ -18,9 +21,27 @@ impl PartialEq for XpathItemSet<'_> {
     }
 }
 
+impl Eq for XpathItemSet<'_> {}
+
 impl PartialOrd for XpathItemSet<'_> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.index_set.iter().partial_cmp(&other.index_set)
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for XpathItemSet<'_> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.index_set.iter().cmp(&other.index_set)
+    }
+}
+
+// Sets hold items in order, so they are hashed in order too.
+impl Hash for XpathItemSet<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index_set.len().hash(state);
+        for item in self.index_set.iter() {
+            item.hash(state);
+        }
     }
 }
 
@@ -0,0 +1,574 @@
+//! Apply many [Xpath] expressions to a document together, sharing the steps they have in common.
+//!
+//! Extracting data from a page usually takes many expressions that start the same way,
+//! such as `//div[@class='product']/h2` and `//div[@class='product']//span[@class='price']`.
+//! Applied one by one, each of them finds the products again.
+//! An [XpathSet] arranges the paths of its expressions in a tree of steps,
+//! so a step shared by several expressions is evaluated once for all of them.
+//!
+//! Paths from the root of the document share their leading axis steps, such as `child::div` or `@href`.
+//! Sharing stops before the last axis step that is followed by another kind of step, such as `string()`,
+//! because those steps can depend on the position of the items they are evaluated for.
+//! Other expressions are applied on their own.
+//! Either way, each expression gives the same result as [Xpath::apply].
+//!
+//! # Example
+//!
+//! ```rust
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! use skyscraper::html;
+//! use skyscraper::xpath::{self, xpath_set::XpathSet, XpathItemTree};
+//!
+//! let document = html::parse(r#"
+//!     <html>
+//!         <div class="product"><h2>Lamp</h2><span class="price">10</span></div>
+//!         <div class="product"><h2>Desk</h2><span class="price">90</span></div>
+//!     </html>"#)?;
+//! let tree = XpathItemTree::from(&document);
+//!
+//! let names = xpath::parse("//div[@class='product']/h2")?;
+//! let prices = xpath::parse("//div[@class='product']/span[@class='price']")?;
+//! let set = XpathSet::from_iter([names.clone(), prices.clone()]);
+//!
+//! let results = set.apply(&tree)?;
+//!
+//! assert_eq!(results.get(&names).unwrap().len(), 2);
+//! assert_eq!(results.get(&prices).unwrap().len(), 2);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    xpath::{
+        context::DEFAULT_DYNAMIC_CONTEXT,
+        explain::TraceId,
+        grammar::{
+            data_model::XpathItem,
+            expressions::path_expressions::{
+                eval_steps, steps::step_expr::StepExpr, PathExpr, PathSeparator, RelativePathExpr,
+                StepPair, DESCENDANT_OR_SELF_STEP,
+            },
+        },
+        limits::EvaluationBudget,
+        planner::{comparison_of, path_of, IndexedPath},
+        DynamicContext, ExpressionApplyError, Xpath, XpathExpressionContext, XpathItemSet,
+        XpathItemTree,
+    },
+    xpath_item_set,
+};
+
+/// A set of [Xpath] expressions that are applied together.
+///
+/// Expressions are kept in the order they were inserted, without duplicates.
+#[derive(Debug, Clone, Default)]
+pub struct XpathSet {
+    xpaths: Vec<Xpath>,
+
+    /// The steps shared by paths from the root of the document.
+    root: PrefixNode,
+
+    /// The positions of the expressions that are applied on their own.
+    unshared: Vec<usize>,
+}
+
+impl XpathSet {
+    /// Create an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an expression to the set.
+    ///
+    /// Returns `false` if the set already contains an equal expression.
+    pub fn insert(&mut self, xpath: Xpath) -> bool {
+        if self.contains(&xpath) {
+            return false;
+        }
+
+        let position = self.xpaths.len();
+        match shared_steps(&xpath) {
+            Some((steps, rest)) => self.root.insert(position, steps, rest),
+            None => self.unshared.push(position),
+        }
+
+        self.xpaths.push(xpath);
+        true
+    }
+
+    /// Whether the set contains an equal expression.
+    pub fn contains(&self, xpath: &Xpath) -> bool {
+        self.xpaths.contains(xpath)
+    }
+
+    /// The number of expressions in the set.
+    pub fn len(&self) -> usize {
+        self.xpaths.len()
+    }
+
+    /// Whether the set contains no expressions.
+    pub fn is_empty(&self) -> bool {
+        self.xpaths.is_empty()
+    }
+
+    /// Iterate over the expressions in the order they were inserted.
+    pub fn iter(&self) -> std::slice::Iter<'_, Xpath> {
+        self.xpaths.iter()
+    }
+
+    /// Apply every expression in the set to the given item tree.
+    pub fn apply<'a, 'tree>(
+        &'a self,
+        item_tree: &'tree XpathItemTree,
+    ) -> Result<XpathSetResult<'a, 'tree>, ExpressionApplyError> {
+        self.apply_with_context(item_tree, &DEFAULT_DYNAMIC_CONTEXT)
+    }
+
+    /// Apply every expression in the set to the given item tree using the given [DynamicContext].
+    ///
+    /// The limits of the context apply to applying the whole set, not to each expression.
+    /// The first expression to fail stops the evaluation.
+    pub fn apply_with_context<'a, 'tree>(
+        &'a self,
+        item_tree: &'tree XpathItemTree,
+        dynamic_context: &DynamicContext,
+    ) -> Result<XpathSetResult<'a, 'tree>, ExpressionApplyError> {
+        let budget = EvaluationBudget::new(dynamic_context.limits());
+        let context = XpathExpressionContext::new_single(
+            item_tree,
+            dynamic_context,
+            &budget,
+            None,
+            XpathItem::Node(item_tree.root()),
+            true,
+        );
+
+        let mut results: Vec<XpathItemSet<'tree>> =
+            self.xpaths.iter().map(|_| XpathItemSet::new()).collect();
+
+        // Shared paths start at the root of the document, which is the context item.
+        let root = xpath_item_set![context.item.clone()];
+        self.root
+            .eval_following(&context, &root, &self.xpaths, &mut results)?;
+
+        for &position in self.unshared.iter() {
+            results[position] = self.xpaths[position]
+                .compile_with_context(dynamic_context)
+                .eval(&context)?;
+        }
+
+        for result in results.iter_mut() {
+            result.sort();
+        }
+
+        Ok(XpathSetResult {
+            xpaths: &self.xpaths,
+            results,
+        })
+    }
+}
+
+impl FromIterator<Xpath> for XpathSet {
+    fn from_iter<T: IntoIterator<Item = Xpath>>(iter: T) -> Self {
+        let mut set = Self::new();
+        for xpath in iter {
+            set.insert(xpath);
+        }
+
+        set
+    }
+}
+
+impl<'a> IntoIterator for &'a XpathSet {
+    type Item = &'a Xpath;
+    type IntoIter = std::slice::Iter<'a, Xpath>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// The results of applying an [XpathSet], keyed by expression.
+///
+/// Created by [XpathSet::apply].
+#[derive(Debug)]
+pub struct XpathSetResult<'a, 'tree> {
+    xpaths: &'a [Xpath],
+    results: Vec<XpathItemSet<'tree>>,
+}
+
+impl<'a, 'tree> XpathSetResult<'a, 'tree> {
+    /// Get the result of the given expression, in document order.
+    ///
+    /// Returns `None` if the expression is not in the set that was applied.
+    pub fn get(&self, xpath: &Xpath) -> Option<&XpathItemSet<'tree>> {
+        let position = self.xpaths.iter().position(|x| x == xpath)?;
+        Some(&self.results[position])
+    }
+
+    /// The number of expressions with a result.
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Whether there are no results.
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Iterate over each expression and its result, in the order the expressions were inserted.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a Xpath, &XpathItemSet<'tree>)> {
+        self.xpaths.iter().zip(self.results.iter())
+    }
+}
+
+/// A step shared by the paths of one or more expressions.
+#[derive(Debug, Clone, Default)]
+struct PrefixNode {
+    /// The step evaluated from each item of the parent node,
+    /// or `None` for the root of the document.
+    step: Option<StepExpr>,
+
+    /// The path `//<step>` when the step follows a leading `//`,
+    /// so it can be looked up in the indexes of the tree.
+    indexed: Option<RelativePathExpr>,
+
+    /// The position of the first expression using the step, which errors in the step are located in.
+    xpath: usize,
+
+    children: Vec<PrefixNode>,
+
+    /// The expressions whose shared steps end here,
+    /// with the steps evaluated from each item of this node.
+    ends: Vec<(usize, Vec<StepPair>)>,
+}
+
+impl PrefixNode {
+    fn insert(&mut self, xpath: usize, steps: Vec<StepExpr>, rest: Vec<StepPair>) {
+        let mut node = self;
+        for (i, step) in steps.into_iter().enumerate() {
+            let position = node
+                .children
+                .iter()
+                .position(|child| child.step.as_ref() == Some(&step));
+
+            node = match position {
+                Some(position) => &mut node.children[position],
+                None => {
+                    let is_after_leading_double_slash =
+                        i == 1 && node.step.as_ref() == Some(&DESCENDANT_OR_SELF_STEP);
+                    let indexed = is_after_leading_double_slash.then(|| RelativePathExpr {
+                        expr: step.clone(),
+                        items: Vec::new(),
+                    });
+
+                    node.children.push(PrefixNode {
+                        step: Some(step),
+                        indexed,
+                        xpath,
+                        ..Default::default()
+                    });
+                    node.children.last_mut().expect("child was just added")
+                }
+            };
+        }
+
+        node.ends.push((xpath, rest));
+    }
+
+    /// Evaluate the step from the items of the parent node, then the steps that follow it.
+    fn eval<'tree>(
+        &self,
+        context: &XpathExpressionContext<'tree, '_>,
+        parent: &XpathItemSet<'tree>,
+        xpaths: &[Xpath],
+        results: &mut [XpathItemSet<'tree>],
+    ) -> Result<(), ExpressionApplyError> {
+        let items = match self.plan(context) {
+            Some(plan) => plan.lookup(context, TraceId::of(self)),
+            None if self.is_needed(context) => self.eval_step(context, parent),
+
+            // Every following step is looked up in the indexes instead.
+            None => Ok(XpathItemSet::new()),
+        }
+        .map_err(|e| e.locate_in(&xpaths[self.xpath]))?;
+
+        self.eval_following(context, &items, xpaths, results)
+    }
+
+    /// Evaluate the expressions and steps that follow this node from its items.
+    fn eval_following<'tree>(
+        &self,
+        context: &XpathExpressionContext<'tree, '_>,
+        items: &XpathItemSet<'tree>,
+        xpaths: &[Xpath],
+        results: &mut [XpathItemSet<'tree>],
+    ) -> Result<(), ExpressionApplyError> {
+        for (xpath, rest) in self.ends.iter() {
+            results[*xpath] =
+                eval_from(context, items, rest).map_err(|e| e.locate_in(&xpaths[*xpath]))?;
+        }
+
+        for child in self.children.iter() {
+            child.eval(context, items, xpaths, results)?;
+        }
+
+        Ok(())
+    }
+
+    fn eval_step<'tree>(
+        &self,
+        context: &XpathExpressionContext<'tree, '_>,
+        parent: &XpathItemSet<'tree>,
+    ) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+        let step = self.step.as_ref().expect("only the root has no step");
+
+        let mut items = XpathItemSet::new();
+        for i in 0..parent.len() {
+            let item_context = context.with_position(parent, i + 1);
+            items.extend(step.eval(&item_context)?);
+            context.budget.check_result_size(items.len())?;
+        }
+
+        Ok(items)
+    }
+
+    fn plan<'a>(&'a self, context: &XpathExpressionContext) -> Option<IndexedPath<'a>> {
+        IndexedPath::plan(self.indexed.as_ref()?, context)
+    }
+
+    /// Whether the items of this node are needed, or every following step can be looked up instead.
+    fn is_needed(&self, context: &XpathExpressionContext) -> bool {
+        !self.ends.is_empty()
+            || self
+                .children
+                .iter()
+                .any(|child| child.plan(context).is_none())
+    }
+}
+
+/// Evaluate the steps from each of the given items.
+fn eval_from<'tree>(
+    context: &XpathExpressionContext<'tree, '_>,
+    items: &XpathItemSet<'tree>,
+    steps: &[StepPair],
+) -> Result<XpathItemSet<'tree>, ExpressionApplyError> {
+    if steps.is_empty() {
+        return Ok(items.clone());
+    }
+
+    let mut result = XpathItemSet::new();
+    for i in 0..items.len() {
+        let item_context = context.with_position(items, i + 1);
+        result.extend(eval_steps(&item_context, steps)?);
+        context.budget.check_result_size(result.len())?;
+    }
+
+    Ok(result)
+}
+
+/// Split the path the expression consists of into the steps that can be shared with other expressions,
+/// and the steps that follow them.
+///
+/// Returns `None` if the expression is not a path from the root of the document.
+fn shared_steps(xpath: &Xpath) -> Option<(Vec<StepExpr>, Vec<StepPair>)> {
+    if !xpath.0.items.is_empty() {
+        return None;
+    }
+
+    let comparison = comparison_of(&xpath.0.expr)?;
+    if comparison.comparison.is_some() {
+        return None;
+    }
+
+    // At the root level, a relative path starts at the root of the document like a leading slash does.
+    let (path, mut steps) = match path_of(&comparison.expr)? {
+        PathExpr::LeadingSlash(None) => return Some((Vec::new(), Vec::new())),
+        PathExpr::LeadingSlash(Some(path)) | PathExpr::Plain(path) => (path, Vec::new()),
+        PathExpr::LeadingDoubleSlash(path) => (path, vec![DESCENDANT_OR_SELF_STEP.clone()]),
+    };
+
+    steps.push(path.expr.clone());
+    for pair in path.items.iter() {
+        if pair.0 == PathSeparator::DoubleSlash {
+            steps.push(DESCENDANT_OR_SELF_STEP.clone());
+        }
+
+        steps.push(pair.1.clone());
+    }
+
+    // Axis steps only depend on the context item, so they give the same result
+    // whether they are evaluated for the items of one expression or of many.
+    // The step before any other kind of step is evaluated for each expression,
+    // so that the other step sees the same context position and size as it would on its own.
+    let shared = match steps
+        .iter()
+        .position(|step| !matches!(step, StepExpr::AxisStep(_)))
+    {
+        Some(0) => return None,
+        Some(position) => position - 1,
+        None => steps.len(),
+    };
+
+    let rest = steps
+        .split_off(shared)
+        .into_iter()
+        .map(|step| StepPair(PathSeparator::Slash, step))
+        .collect();
+
+    Some((steps, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        html,
+        xpath::{
+            self,
+            context::DynamicContextBuilder,
+            limits::{EvaluationLimit, EvaluationLimits},
+            ErrorCode, StaticContext,
+        },
+    };
+
+    use super::*;
+
+    fn tree(text: &str) -> XpathItemTree {
+        XpathItemTree::from(&html::parse(text).unwrap())
+    }
+
+    #[test]
+    fn apply_should_match_applying_each_expression() {
+        // arrange
+        let tree = tree(
+            r#"
+            <html>
+                <body>
+                    <div class="product">
+                        <h2>Lamp</h2>
+                        <span class="price">10</span>
+                        <a href="/lamp">More</a>
+                    </div>
+                    <div class="product">
+                        <h2>Desk</h2>
+                        <p><span class="price">90</span></p>
+                    </div>
+                    <div class="banner"><h2>Sale</h2></div>
+                </body>
+            </html>"#,
+        );
+        let expressions = [
+            "/",
+            "//div[@class='product']",
+            "//div[@class='product']/h2",
+            "//div[@class='product']/h2/text()",
+            "//div[@class='product']//span[@class='price']",
+            "//div[@class='product']/a/@href",
+            "//div[@class='product']/h2[1]",
+            "//div[@class='product']/h2/string()",
+            "//div/h2",
+            "/html/body/div[2]/p/span",
+            "html/body/div",
+            "//h2[. = 'Sale']/..",
+            "string(/html/body/div[3]/h2)",
+            "1 + 2",
+        ];
+        let xpaths: Vec<Xpath> = expressions
+            .iter()
+            .map(|expression| xpath::parse(expression).unwrap())
+            .collect();
+        let set = XpathSet::from_iter(xpaths.iter().cloned());
+
+        // act
+        let results = set.apply(&tree).unwrap();
+
+        // assert
+        assert_eq!(results.len(), expressions.len());
+        for xpath in xpaths.iter() {
+            let expected = xpath.apply(&tree).unwrap();
+            assert_eq!(results.get(xpath).unwrap(), &expected, "{}", xpath);
+        }
+    }
+
+    #[test]
+    fn insert_should_share_common_prefixes() {
+        // arrange
+        let mut set = XpathSet::new();
+
+        // act
+        set.insert(xpath::parse("//div[@class='product']/h2").unwrap());
+        set.insert(xpath::parse("//div[@class='product']//span").unwrap());
+        set.insert(xpath::parse("//div[@class='product']/a/string(@href)").unwrap());
+        set.insert(xpath::parse("string(//div)").unwrap());
+
+        // assert
+        let descendants = &set.root.children;
+        assert_eq!(descendants.len(), 1);
+
+        let products = &descendants[0].children;
+        assert_eq!(products.len(), 1);
+        assert!(products[0].indexed.is_some());
+        assert_eq!(products[0].children.len(), 2);
+        assert_eq!(products[0].ends.len(), 1);
+        assert_eq!(set.unshared, vec![3]);
+    }
+
+    #[test]
+    fn insert_should_ignore_duplicates() {
+        // arrange
+        let mut set = XpathSet::new();
+
+        // act
+        let first = set.insert(xpath::parse("//div").unwrap());
+        let second = set.insert(xpath::parse("//div").unwrap());
+
+        // assert
+        assert!(first);
+        assert!(!second);
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn apply_should_fail_like_expression() {
+        // arrange
+        let tree = tree("<html><p>1</p></html>");
+        let set = XpathSet::from_iter([
+            xpath::parse("//p").unwrap(),
+            xpath::parse("//p/fn:error()").unwrap(),
+        ]);
+
+        let expected = xpath::parse("//p/fn:error()")
+            .unwrap()
+            .apply(&tree)
+            .unwrap_err();
+
+        // act
+        let err = set.apply(&tree).unwrap_err();
+
+        // assert
+        assert_eq!(err.code(), expected.code());
+        assert_eq!(err.expression(), expected.expression());
+    }
+
+    #[test]
+    fn apply_should_respect_limits() {
+        // arrange
+        let tree = tree("<html><p>1</p><p>2</p></html>");
+        let set = XpathSet::from_iter([
+            xpath::parse("/html/p").unwrap(),
+            xpath::parse("/html/p/text()").unwrap(),
+        ]);
+        let context = DynamicContextBuilder::new(StaticContext::new())
+            .with_limits(EvaluationLimits::new().with_max_node_visits(2))
+            .build();
+
+        // act
+        let err = set.apply_with_context(&tree, &context).unwrap_err();
+
+        // assert
+        assert_eq!(
+            err.code(),
+            &ErrorCode::LimitExceeded(EvaluationLimit::NodeVisits)
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use skyscraper::{
+    html,
+    xpath::{self, ErrorCode, Xpath, XpathItemTree},
+};
+
+static HTML: &str = r###"
+    <html>
+        <body>
+            <div class="item">
+                <a href="https://example.com/1">It's "one"</a>
+            </div>
+            <div class="item other">
+                <a href="https://example.com/2">Two</a>
+            </div>
+            <div>
+                <a>Three</a>
+            </div>
+        </body>
+    </html>"###;
+
+#[test]
+fn build_should_match_parsed_expression() {
+    // arrange
+    let builders = vec![
+        Xpath::descendant("div")
+            .attr_eq("class", "item")
+            .child("a")
+            .attr("href"),
+        Xpath::root()
+            .child("html")
+            .descendant("a")
+            .has_attr("href")
+            .text(),
+        Xpath::child("a").parent().attr_contains("class", "other"),
+        Xpath::descendant("a").text_eq("It's \"one\"").nth(1),
+        Xpath::descendant("div").filter(Xpath::child("a").has_attr("href").build().unwrap()),
+        Xpath::root(),
+    ];
+
+    for builder in builders {
+        // act
+        let xpath = builder.build().unwrap();
+        let parsed = xpath::parse(&xpath.to_string()).unwrap();
+
+        // assert
+        assert_eq!(xpath, parsed, "{}", xpath);
+    }
+}
+
+#[test]
+fn build_should_display_expression() {
+    // arrange
+    let builder = Xpath::descendant("div")
+        .attr_eq("class", "item")
+        .child("a")
+        .attr("href");
+
+    // act
+    let xpath = builder.build().unwrap();
+
+    // assert
+    assert_eq!(xpath.to_string(), "//div[@class='item']/a/@href");
+}
+
+#[test]
+fn build_should_escape_quotes_in_values() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = Xpath::descendant("a")
+        .text_contains("It's \"one")
+        .attr("href")
+        .build()
+        .unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(xpath.to_string(), r#"//a[contains(., 'It''s "one')]/@href"#);
+    assert_eq!(items.len(), 1);
+    assert_eq!(
+        items[0].string_value(&xpath_item_tree),
+        "https://example.com/1"
+    );
+}
+
+#[test]
+fn build_should_apply_filters() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = Xpath::descendant("div")
+        .filter(Xpath::child("a").has_attr("href").build().unwrap())
+        .attr("class")
+        .build()
+        .unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[1].string_value(&xpath_item_tree), "item other");
+}
+
+#[test]
+fn build_should_reject_invalid_names() {
+    // arrange
+    let builder = Xpath::descendant("div").child("a b").attr("href");
+
+    // act
+    let err = builder.build().unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::XPST0003);
+}
+
+#[test]
+fn build_should_reject_predicate_without_step() {
+    // arrange
+    let builder = Xpath::root().nth(1);
+
+    // act
+    let err = builder.build().unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::XPST0003);
+}
@@ -0,0 +1,229 @@
+use std::borrow::Cow;
+
+use skyscraper::{
+    html,
+    xpath::{
+        self,
+        collation::{Collation, HTML_ASCII_CASE_INSENSITIVE_COLLATION},
+        context::{DynamicContext, StaticContextBuilder},
+        grammar::data_model::{AnyAtomicType, XpathItem},
+        ErrorCode, XpathItemTree,
+    },
+};
+
+static HTML: &str = r###"
+    <html>
+        <body>
+            <div class="Item">Banana</div>
+            <div class="item">apple</div>
+            <div class="ITEM">Cherry</div>
+            <div class="other">APPLE</div>
+        </body>
+    </html>"###;
+
+fn string(s: &str) -> XpathItem<'static> {
+    XpathItem::AnyAtomicType(AnyAtomicType::String(s.to_string()))
+}
+
+fn boolean(b: bool) -> XpathItem<'static> {
+    XpathItem::AnyAtomicType(AnyAtomicType::Boolean(b))
+}
+
+fn case_insensitive_context() -> DynamicContext {
+    let static_context = StaticContextBuilder::new()
+        .with_default_collation(HTML_ASCII_CASE_INSENSITIVE_COLLATION)
+        .build();
+    DynamicContext::new(static_context)
+}
+
+#[test]
+fn general_comparison_should_use_default_collation() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("//div[@class='item']").unwrap();
+
+    // act
+    let codepoint_items = xpath.apply(&xpath_item_tree).unwrap();
+    let case_insensitive_items = xpath
+        .apply_with_context(&xpath_item_tree, &case_insensitive_context())
+        .unwrap();
+
+    // assert
+    assert_eq!(codepoint_items.len(), 1);
+    assert_eq!(case_insensitive_items.len(), 3);
+}
+
+#[test]
+fn contains_should_use_collation_argument() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse(
+        "//div[contains(., 'APP', 'http://www.w3.org/2005/xpath-functions/collation/html-ascii-case-insensitive')]",
+    )
+    .unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 2);
+}
+
+#[test]
+fn starts_with_and_ends_with_should_use_default_collation() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let starts_with = xpath::parse("starts-with('Banana', 'BAN')").unwrap();
+    let ends_with = xpath::parse("ends-with('Banana', 'NA')").unwrap();
+    let context = case_insensitive_context();
+
+    // act
+    let starts_with_items = starts_with
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap();
+    let ends_with_items = ends_with
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap();
+    let codepoint_items = starts_with.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(starts_with_items[0], boolean(true));
+    assert_eq!(ends_with_items[0], boolean(true));
+    assert_eq!(codepoint_items[0], boolean(false));
+}
+
+#[test]
+fn compare_should_order_strings() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let codepoint = xpath::parse("compare('apple', 'Banana')").unwrap();
+    let case_insensitive = xpath::parse(
+        "compare('Apple', 'apple', 'http://www.w3.org/2005/xpath-functions/collation/html-ascii-case-insensitive')",
+    )
+    .unwrap();
+
+    // act
+    let codepoint_items = codepoint.apply(&xpath_item_tree).unwrap();
+    let case_insensitive_items = case_insensitive.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(
+        codepoint_items[0],
+        XpathItem::AnyAtomicType(AnyAtomicType::Integer(1))
+    );
+    assert_eq!(
+        case_insensitive_items[0],
+        XpathItem::AnyAtomicType(AnyAtomicType::Integer(0))
+    );
+}
+
+#[test]
+fn compare_should_fail_for_unknown_collation() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("compare('a', 'b', 'urn:example:unknown')").unwrap();
+
+    // act
+    let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::FOCH0002);
+}
+
+#[test]
+fn distinct_values_should_keep_first_of_equal_values() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("distinct-values(//div/@class)").unwrap();
+
+    // act
+    let items = xpath
+        .apply_with_context(&xpath_item_tree, &case_insensitive_context())
+        .unwrap();
+
+    // assert
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0], string("Item"));
+    assert_eq!(items[1], string("other"));
+}
+
+#[test]
+fn sort_should_use_collation() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let codepoint = xpath::parse("sort(//div/text())[1]").unwrap();
+    let case_insensitive = xpath::parse(
+        "sort(//div/text(), 'http://www.w3.org/2005/xpath-functions/collation/html-ascii-case-insensitive')[1]",
+    )
+    .unwrap();
+
+    // act
+    let codepoint_items = codepoint.apply(&xpath_item_tree).unwrap();
+    let case_insensitive_items = case_insensitive.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(codepoint_items.len(), 1);
+    assert_eq!(codepoint_items[0].string_value(&xpath_item_tree), "APPLE");
+    assert_eq!(case_insensitive_items.len(), 1);
+    assert_eq!(
+        case_insensitive_items[0].string_value(&xpath_item_tree),
+        "apple"
+    );
+}
+
+struct ReverseCollation;
+
+impl Collation for ReverseCollation {
+    fn key<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(s)
+    }
+
+    fn compare(&self, first: &str, second: &str) -> std::cmp::Ordering {
+        second.cmp(first)
+    }
+}
+
+#[test]
+fn sort_should_use_registered_collation() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let static_context = StaticContextBuilder::new()
+        .with_collation("urn:example:reverse", ReverseCollation)
+        .build();
+    let context = DynamicContext::new(static_context);
+    let xpath = xpath::parse("sort(('b', 'c', 'a'), 'urn:example:reverse')[1]").unwrap();
+
+    // act
+    let items = xpath
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0], string("c"));
+}
+
+#[test]
+fn default_collation_should_return_uri() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("default-collation()").unwrap();
+
+    // act
+    let items = xpath
+        .apply_with_context(&xpath_item_tree, &case_insensitive_context())
+        .unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0], string(HTML_ASCII_CASE_INSENSITIVE_COLLATION));
+}
@@ -0,0 +1,221 @@
+use skyscraper::{
+    html,
+    xpath::{
+        self,
+        context::{DynamicContext, StaticContextBuilder},
+        grammar::data_model::{AnyAtomicType, XpathItem},
+        ErrorCode, XpathItemTree,
+    },
+};
+
+static HTML: &str = r###"
+    <html>
+        <body>
+            <ul>
+                <li>2</li>
+                <li>10</li>
+                <li>apple</li>
+            </ul>
+            <span class="price">7</span>
+        </body>
+    </html>"###;
+
+fn boolean(b: bool) -> XpathItem<'static> {
+    XpathItem::AnyAtomicType(AnyAtomicType::Boolean(b))
+}
+
+fn double(n: f64) -> XpathItem<'static> {
+    XpathItem::AnyAtomicType(AnyAtomicType::Double(n.into()))
+}
+
+fn compatibility_context() -> DynamicContext {
+    let static_context = StaticContextBuilder::new()
+        .with_xpath_1_0_compatibility(true)
+        .build();
+    DynamicContext::new(static_context)
+}
+
+#[test]
+fn relational_comparison_should_compare_numbers_in_compatibility_mode() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse(r#""2" > "10""#).unwrap();
+
+    // act
+    let default_items = xpath.apply(&xpath_item_tree).unwrap();
+    let compatible_items = xpath
+        .apply_with_context(&xpath_item_tree, &compatibility_context())
+        .unwrap();
+
+    // assert
+    assert_eq!(default_items[0], boolean(true));
+    assert_eq!(compatible_items[0], boolean(false));
+}
+
+#[test]
+fn general_comparison_should_compare_every_pair_in_compatibility_mode() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("//ul[li = 'apple']").unwrap();
+
+    // act
+    let default_err = xpath.apply(&xpath_item_tree).unwrap_err();
+    let compatible_items = xpath
+        .apply_with_context(&xpath_item_tree, &compatibility_context())
+        .unwrap();
+
+    // assert
+    assert_eq!(default_err.code(), &ErrorCode::XPTY0004);
+    assert_eq!(compatible_items.len(), 1);
+}
+
+#[test]
+fn comparison_with_empty_sequence_should_be_false_in_compatibility_mode() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let equal = xpath::parse("//missing = 'x'").unwrap();
+    let not_equal = xpath::parse("//missing != 'x'").unwrap();
+    let context = compatibility_context();
+
+    // act
+    let default_items = equal.apply(&xpath_item_tree).unwrap();
+    let equal_items = equal
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap();
+    let not_equal_items = not_equal
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap();
+
+    // assert
+    assert!(default_items.is_empty());
+    assert_eq!(equal_items[0], boolean(false));
+    assert_eq!(not_equal_items[0], boolean(false));
+}
+
+#[test]
+fn comparison_with_boolean_should_use_effective_boolean_value_in_compatibility_mode() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("//li = starts-with('yes', 'y')").unwrap();
+
+    // act
+    let items = xpath
+        .apply_with_context(&xpath_item_tree, &compatibility_context())
+        .unwrap();
+
+    // assert
+    assert_eq!(items[0], boolean(true));
+}
+
+#[test]
+fn string_should_take_first_node_in_compatibility_mode() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("string(//li)").unwrap();
+
+    // act
+    let default_err = xpath.apply(&xpath_item_tree).unwrap_err();
+    let compatible_items = xpath
+        .apply_with_context(&xpath_item_tree, &compatibility_context())
+        .unwrap();
+
+    // assert
+    assert_eq!(default_err.code(), &ErrorCode::XPTY0004);
+    assert_eq!(
+        compatible_items[0],
+        XpathItem::AnyAtomicType(AnyAtomicType::String(String::from("2")))
+    );
+}
+
+#[test]
+fn arithmetic_should_cast_node_text() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("//span * 3 - 1").unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0], double(20.0));
+}
+
+#[test]
+fn arithmetic_should_keep_integers() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("-(7 idiv 2) + 10 mod 4").unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(
+        items[0],
+        XpathItem::AnyAtomicType(AnyAtomicType::Integer(-1))
+    );
+}
+
+#[test]
+fn integer_division_by_zero_should_fail() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("1 div 0").unwrap();
+
+    // act
+    let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::FOAR0001);
+}
+
+#[test]
+fn arithmetic_should_use_number_in_compatibility_mode() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let first_item = xpath::parse("//li + 1").unwrap();
+    let empty = xpath::parse("//missing + 1").unwrap();
+    let context = compatibility_context();
+
+    // act
+    let default_err = first_item.apply(&xpath_item_tree).unwrap_err();
+    let first_item_items = first_item
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap();
+    let empty_items = empty
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap();
+
+    // assert
+    assert_eq!(default_err.code(), &ErrorCode::XPTY0004);
+    assert_eq!(first_item_items[0], double(3.0));
+    assert_eq!(empty_items.len(), 1);
+    assert_eq!(empty_items[0].string_value(&xpath_item_tree), "NaN");
+}
+
+#[test]
+fn number_should_convert_values() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let span = xpath::parse("number(//span)").unwrap();
+    let text = xpath::parse("//li[3]/number()").unwrap();
+
+    // act
+    let span_items = span.apply(&xpath_item_tree).unwrap();
+    let text_items = text.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(span_items[0], double(7.0));
+    assert_eq!(text_items[0].string_value(&xpath_item_tree), "NaN");
+}
@@ -0,0 +1,310 @@
+use skyscraper::{
+    html,
+    xpath::{
+        self,
+        context::{DynamicContext, DynamicContextBuilder},
+        grammar::{
+            data_model::{AnyAtomicType, XpathItem},
+            XpathItemTreeNode,
+        },
+        resolver::{FileSystemResolver, ResourceResolver},
+        ErrorCode, ExpressionApplyError, StaticContext, XpathItemTree,
+    },
+};
+
+static HTML: &str = r###"
+    <html>
+        <body>
+            <a href="products/lamp.html">Lamp</a>
+        </body>
+    </html>"###;
+
+fn context() -> DynamicContext {
+    DynamicContextBuilder::new(StaticContext::new())
+        .with_resolver(FileSystemResolver::new("tests/documents"))
+        .build()
+}
+
+/// Resolves every URI to the same text.
+struct TextResolver(&'static str);
+
+impl ResourceResolver for TextResolver {
+    fn resolve_text(&self, _uri: &str) -> Result<String, ExpressionApplyError> {
+        Ok(self.0.to_string())
+    }
+}
+
+fn string(s: &str) -> XpathItem<'static> {
+    XpathItem::AnyAtomicType(AnyAtomicType::String(s.to_string()))
+}
+
+#[test]
+fn doc_should_select_from_loaded_document() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("doc('listing.html')//a/@href").unwrap();
+
+    // act
+    let items = xpath
+        .apply_with_context(&xpath_item_tree, &context())
+        .unwrap();
+
+    // assert
+    assert_eq!(items.len(), 2);
+    assert_eq!(
+        items[0].string_value(&xpath_item_tree),
+        "products/lamp.html"
+    );
+    assert_eq!(
+        items[1].string_value(&xpath_item_tree),
+        "products/table.html"
+    );
+}
+
+#[test]
+fn doc_should_follow_links_from_the_context_document() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("doc(//a/@href)//span[@class='price']").unwrap();
+
+    // act
+    let items = xpath
+        .apply_with_context(&xpath_item_tree, &context())
+        .unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].string_value(&xpath_item_tree), "12.50");
+}
+
+#[test]
+fn doc_should_load_a_document_once() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("(doc('listing.html'), doc('listing.html'))").unwrap();
+
+    // act
+    let items = xpath
+        .apply_with_context(&xpath_item_tree, &context())
+        .unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+}
+
+#[test]
+fn doc_should_load_documents_from_each_context_resolver() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("doc('page.html')//p").unwrap();
+    let first = DynamicContextBuilder::new(StaticContext::new())
+        .with_resolver(TextResolver("<html><p>first</p></html>"))
+        .build();
+    let second = DynamicContextBuilder::new(StaticContext::new())
+        .with_resolver(TextResolver("<html><p>second</p></html>"))
+        .build();
+
+    // act
+    let first_items = xpath.apply_with_context(&xpath_item_tree, &first).unwrap();
+    let second_items = xpath.apply_with_context(&xpath_item_tree, &second).unwrap();
+
+    // assert
+    assert_eq!(first_items[0].string_value(&xpath_item_tree), "first");
+    assert_eq!(second_items[0].string_value(&xpath_item_tree), "second");
+}
+
+#[test]
+fn clear_documents_should_drop_loaded_documents() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let mut xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("doc('listing.html')//a").unwrap();
+    let count = xpath
+        .apply_with_context(&xpath_item_tree, &context())
+        .unwrap()
+        .len();
+
+    // act
+    xpath_item_tree.clear_documents();
+    let items = xpath
+        .apply_with_context(&xpath_item_tree, &context())
+        .unwrap();
+
+    // assert
+    assert_eq!(count, 2);
+    assert_eq!(items.len(), 2);
+    assert_eq!(
+        xpath::parse("//a")
+            .unwrap()
+            .apply(&xpath_item_tree)
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn doc_root_should_be_a_document_node() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("root(doc('products/lamp.html')//h1)").unwrap();
+
+    // act
+    let items = xpath
+        .apply_with_context(&xpath_item_tree, &context())
+        .unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    let root = items[0].extract_as_node();
+    assert!(matches!(root, XpathItemTreeNode::DocumentNode(_)));
+    assert!(root.text_content(&xpath_item_tree).contains("Lamp"));
+}
+
+#[test]
+fn doc_should_fail_for_missing_document() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("doc('missing.html')").unwrap();
+
+    // act
+    let err = xpath
+        .apply_with_context(&xpath_item_tree, &context())
+        .unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::FODC0002);
+}
+
+#[test]
+fn doc_should_fail_without_resolver() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("doc('listing.html')").unwrap();
+
+    // act
+    let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::FODC0002);
+}
+
+#[test]
+fn doc_available_should_check_document() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let available = xpath::parse("doc-available('listing.html')").unwrap();
+    let missing = xpath::parse("doc-available('missing.html')").unwrap();
+    let context = context();
+
+    // act
+    let available_items = available
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap();
+    let missing_items = missing
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap();
+
+    // assert
+    assert_eq!(
+        available_items[0],
+        XpathItem::AnyAtomicType(AnyAtomicType::Boolean(true))
+    );
+    assert_eq!(
+        missing_items[0],
+        XpathItem::AnyAtomicType(AnyAtomicType::Boolean(false))
+    );
+}
+
+#[test]
+fn collection_should_select_from_every_document() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("collection('products')//h1").unwrap();
+
+    // act
+    let items = xpath
+        .apply_with_context(&xpath_item_tree, &context())
+        .unwrap();
+
+    // assert
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].string_value(&xpath_item_tree), "Lamp");
+    assert_eq!(items[1].string_value(&xpath_item_tree), "Table");
+}
+
+#[test]
+fn unparsed_text_should_return_file_text() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("unparsed-text('products/prices.csv', 'UTF-8')").unwrap();
+
+    // act
+    let items = xpath
+        .apply_with_context(&xpath_item_tree, &context())
+        .unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0], string("lamp,12.50\ntable,80.00\n"));
+}
+
+#[test]
+fn unparsed_text_lines_should_split_lines() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("unparsed-text-lines('products/prices.csv')").unwrap();
+
+    // act
+    let items = xpath
+        .apply_with_context(&xpath_item_tree, &context())
+        .unwrap();
+
+    // assert
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0], string("lamp,12.50"));
+    assert_eq!(items[1], string("table,80.00"));
+}
+
+#[test]
+fn unparsed_text_should_fail_for_missing_file() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("unparsed-text('missing.txt')").unwrap();
+
+    // act
+    let err = xpath
+        .apply_with_context(&xpath_item_tree, &context())
+        .unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::FOUT1170);
+}
+
+#[test]
+fn unparsed_text_should_fail_for_unsupported_encoding() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("unparsed-text('products/prices.csv', 'latin1')").unwrap();
+
+    // act
+    let err = xpath
+        .apply_with_context(&xpath_item_tree, &context())
+        .unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::FOUT1190);
+}
@@ -0,0 +1,137 @@
+use skyscraper::{
+    html,
+    xpath::{
+        self, context::StaticContextBuilder, functions::ExpandedName, xpath_item_set::XpathItemSet,
+        ErrorCode, XpathItemTree,
+    },
+};
+
+static HTML: &str = r###"
+    <html>
+        <body>
+            <div class="price">12</div>
+            <div class="price">n/a</div>
+        </body>
+    </html>"###;
+
+#[test]
+fn error_without_arguments_should_raise_foer0000() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("//div[fn:error()]").unwrap();
+
+    // act
+    let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::FOER0000);
+    assert_eq!(err.expression(), Some("fn:error()"));
+    assert_eq!(err.span(), Some(6..16));
+}
+
+#[test]
+fn error_with_code_and_description_should_raise_code() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("error('err:FORG0001', 'not a price')").unwrap();
+
+    // act
+    let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::FORG0001);
+    assert!(err.code().is_dynamic_error());
+    assert_eq!(err.message(), "not a price");
+    assert_eq!(
+        err.to_string(),
+        "Error applying expression err:FORG0001 not a price in `error('err:FORG0001', 'not a price')`"
+    );
+}
+
+#[test]
+fn error_with_custom_code_should_raise_other_code() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("error('Q{http://example.com/errors}missing-price')").unwrap();
+
+    // act
+    let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+    // assert
+    assert_eq!(
+        err.code(),
+        &ErrorCode::Other(ExpandedName::new(
+            "http://example.com/errors",
+            "missing-price"
+        ))
+    );
+}
+
+#[test]
+fn type_error_should_report_failing_function_call() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("//body[contains(div, '12')]").unwrap();
+
+    // act
+    let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::XPTY0004);
+    assert!(err.code().is_type_error());
+    assert_eq!(err.expression(), Some("contains(div, '12')"));
+}
+
+#[test]
+fn unknown_function_should_be_static_error() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+
+    let static_context = StaticContextBuilder::new()
+        .with_function(
+            ExpandedName::new("http://example.com", "price"),
+            0,
+            |_, _| Ok(XpathItemSet::new()),
+        )
+        .build();
+    let xpath = xpath::parse_with_context("Q{http://example.com}price()", &static_context).unwrap();
+
+    // act
+    // Applying without the extension function registered.
+    let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::XPST0017);
+    assert!(err.code().is_static_error());
+}
+
+#[test]
+fn parse_error_should_have_code() {
+    // act
+    let syntax_err = xpath::parse("//div[").unwrap_err();
+    let static_err = xpath::parse("//div[foo()]").unwrap_err();
+
+    // assert
+    assert_eq!(syntax_err.code(), &ErrorCode::XPST0003);
+    assert_eq!(static_err.code(), &ErrorCode::XPST0017);
+}
+
+#[test]
+fn comment_test_should_evaluate() {
+    // arrange
+    // Comments are not kept when parsing HTML, so there is nothing to select.
+    let document = html::parse("<html><body><!-- note --><div></div></body></html>").unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("//body/comment()").unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 0);
+}
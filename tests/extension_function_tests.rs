@@ -0,0 +1,175 @@
+use skyscraper::{
+    html,
+    xpath::{
+        self,
+        context::{DynamicContext, StaticContextBuilder},
+        functions::ExpandedName,
+        grammar::data_model::{AnyAtomicType, XpathItem},
+        xpath_item_set::XpathItemSet,
+        ExpressionApplyError, XpathItemTree,
+    },
+};
+
+fn price_context() -> DynamicContext {
+    let static_context = StaticContextBuilder::new()
+        .with_namespace("ext", "urn:example:ext")
+        .with_function(
+            ExpandedName::new("urn:example:ext", "price"),
+            1,
+            |tree, arguments| {
+                let mut prices = XpathItemSet::new();
+                for item in arguments[0].iter() {
+                    let text = item.string_value(tree);
+                    let digits: String = text
+                        .chars()
+                        .filter(|c| c.is_ascii_digit() || *c == '.')
+                        .collect();
+                    let price: f64 = digits
+                        .parse()
+                        .map_err(|_| ExpressionApplyError::new(format!("not a price: {}", text)))?;
+                    prices.insert(XpathItem::AnyAtomicType(AnyAtomicType::Double(
+                        price.into(),
+                    )));
+                }
+
+                Ok(prices)
+            },
+        )
+        .build();
+
+    DynamicContext::new(static_context)
+}
+
+#[test]
+fn extension_function_should_be_called_with_evaluated_arguments() {
+    // arrange
+    let text = r###"
+        <html>
+            <span class="price">$1,299.50</span>
+        </html>"###;
+
+    let document = html::parse(text).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let context = price_context();
+    let xpath = xpath::parse_with_context(
+        "//span[@class='price']/ext:price(text())",
+        context.static_context(),
+    )
+    .unwrap();
+
+    // act
+    let items = xpath
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(
+        items[0],
+        XpathItem::AnyAtomicType(AnyAtomicType::Double(1299.5.into()))
+    );
+}
+
+#[test]
+fn extension_function_should_be_usable_in_predicates() {
+    // arrange
+    let text = r###"
+        <html>
+            <span>$10</span>
+            <span>$200</span>
+        </html>"###;
+
+    let document = html::parse(text).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let context = price_context();
+    let xpath = xpath::parse_with_context("//span[ext:price(.) > 100e0]", context.static_context())
+        .unwrap();
+
+    // act
+    let items = xpath
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    let element = items[0].extract_as_node().extract_as_element_node();
+    assert_eq!(element.text(&xpath_item_tree).unwrap(), "$200");
+}
+
+#[test]
+fn extension_function_errors_should_be_returned() {
+    // arrange
+    let text = r###"
+        <html>
+            <span>free</span>
+        </html>"###;
+
+    let document = html::parse(text).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let context = price_context();
+    let xpath = xpath::parse_with_context("//span/ext:price(.)", context.static_context()).unwrap();
+
+    // act
+    let result = xpath.apply_with_context(&xpath_item_tree, &context);
+
+    // assert
+    assert!(result.is_err());
+}
+
+#[test]
+fn extension_function_should_not_be_available_in_default_context() {
+    // arrange
+    let text = r###"
+        <html>
+            <span>$10</span>
+        </html>"###;
+
+    let document = html::parse(text).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath =
+        xpath::parse_with_context("//span/ext:price(.)", price_context().static_context()).unwrap();
+
+    // act
+    let result = xpath.apply(&xpath_item_tree);
+
+    // assert
+    assert!(result.is_err());
+}
+
+#[test]
+fn builtin_functions_should_be_resolvable_with_fn_prefix() {
+    // arrange
+    let text = r###"
+        <html>
+            <span>hello</span>
+            <span>world</span>
+        </html>"###;
+
+    let document = html::parse(text).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("//span[fn:contains(text(), 'wor')]").unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+}
+
+#[test]
+fn parse_should_fail_for_unknown_function() {
+    // act
+    let result = xpath::parse("//div[foo()]");
+
+    // assert
+    assert!(result.is_err());
+}
+
+#[test]
+fn parse_should_fail_for_extension_function_without_context() {
+    // act
+    let result = xpath::parse("//span/ext:price(.)");
+
+    // assert
+    assert!(result.is_err());
+}
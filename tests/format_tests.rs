@@ -0,0 +1,257 @@
+use skyscraper::{
+    html,
+    xpath::{
+        self,
+        context::{DynamicContext, StaticContextBuilder},
+        decimal_format::DecimalFormat,
+        functions::ExpandedName,
+        grammar::data_model::{AnyAtomicType, XpathItem},
+        ErrorCode, XpathItemTree,
+    },
+};
+
+static HTML: &str = r###"
+    <html>
+        <body>
+            <span class="price">1234.5</span>
+            <span class="page">14</span>
+            <span class="loss">-3.5</span>
+            <span class="change">-42</span>
+            <span class="best">INF</span>
+            <span class="worst">-INF</span>
+        </body>
+    </html>"###;
+
+fn string(s: &str) -> XpathItem<'static> {
+    XpathItem::AnyAtomicType(AnyAtomicType::String(s.to_string()))
+}
+
+fn apply(expression: &str) -> String {
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse(expression).unwrap();
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+    assert_eq!(items.len(), 1, "applying {}", expression);
+    items[0].string_value(&xpath_item_tree)
+}
+
+#[test]
+fn format_number_should_group_and_round() {
+    // arrange
+    let cases = [
+        (
+            "format-number(//span[@class='price'], '#,##0.00')",
+            "1,234.50",
+        ),
+        ("format-number(1234567.891e0, '#,##0.##')", "1,234,567.89"),
+        ("format-number(2.675, '0.00')", "2.68"),
+        ("format-number(0.125, '0.00')", "0.12"),
+        ("format-number(7, '000')", "007"),
+        ("format-number(0, '#')", "0"),
+        ("format-number(12345678, '#,##,##0')", "123,45,678"),
+    ];
+
+    for (expression, expected) in cases {
+        // act
+        let formatted = apply(expression);
+
+        // assert
+        assert_eq!(formatted, expected, "applying {}", expression);
+    }
+}
+
+#[test]
+fn format_number_should_format_signs_and_specials() {
+    // arrange
+    let cases = [
+        ("format-number(//span[@class='loss'], '#,##0.0')", "-3.5"),
+        (
+            "format-number(//span[@class='loss'], '#,##0.0;(#,##0.0)')",
+            "(3.5)",
+        ),
+        ("format-number(0.256, '#0.0%')", "25.6%"),
+        ("format-number(0.0042, '0‰')", "4‰"),
+        ("format-number(//span[@class='best'], '#')", "Infinity"),
+        ("format-number((), '#')", "NaN"),
+        ("format-number(//span[@class='worst'], '$#')", "-$Infinity"),
+    ];
+
+    for (expression, expected) in cases {
+        // act
+        let formatted = apply(expression);
+
+        // assert
+        assert_eq!(formatted, expected, "applying {}", expression);
+    }
+}
+
+#[test]
+fn format_number_should_format_exponents() {
+    // arrange
+    let cases = [
+        ("format-number(1234.5, '0.00e0')", "1.23e3"),
+        ("format-number(0.000123, '00.0e00')", "12.3e-05"),
+        ("format-number(99999, '0.0e0')", "1.0e5"),
+    ];
+
+    for (expression, expected) in cases {
+        // act
+        let formatted = apply(expression);
+
+        // assert
+        assert_eq!(formatted, expected, "applying {}", expression);
+    }
+}
+
+#[test]
+fn format_number_should_use_named_decimal_format() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let german = DecimalFormat::new()
+        .with_decimal_separator(',')
+        .with_grouping_separator('.')
+        .with_nan("keine Zahl");
+    let static_context = StaticContextBuilder::new()
+        .with_namespace("ex", "urn:example")
+        .with_decimal_format(ExpandedName::new("urn:example", "de"), german)
+        .build();
+    let context = DynamicContext::new(static_context);
+    let formatted = xpath::parse("format-number(1234.5, '#.##0,00', 'ex:de')").unwrap();
+    let nan = xpath::parse("format-number((), '#', 'Q{urn:example}de')").unwrap();
+
+    // act
+    let formatted_items = formatted
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap();
+    let nan_items = nan.apply_with_context(&xpath_item_tree, &context).unwrap();
+
+    // assert
+    assert_eq!(formatted_items[0], string("1.234,50"));
+    assert_eq!(nan_items[0], string("keine Zahl"));
+}
+
+#[test]
+fn format_number_should_use_default_decimal_format() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let static_context = StaticContextBuilder::new()
+        .with_default_decimal_format(
+            DecimalFormat::new()
+                .with_grouping_separator(' ')
+                .with_infinity("∞"),
+        )
+        .build();
+    let context = DynamicContext::new(static_context);
+    let grouped = xpath::parse("format-number(1234567, '# ##0')").unwrap();
+    let infinity = xpath::parse("format-number(//span[@class='best'], '#')").unwrap();
+
+    // act
+    let grouped_items = grouped
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap();
+    let infinity_items = infinity
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap();
+
+    // assert
+    assert_eq!(grouped_items[0], string("1 234 567"));
+    assert_eq!(infinity_items[0], string("∞"));
+}
+
+#[test]
+fn format_number_should_fail_for_unknown_decimal_format() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("format-number(1, '#', 'missing')").unwrap();
+
+    // act
+    let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::FODF1280);
+}
+
+#[test]
+fn format_number_should_fail_for_invalid_picture() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let pictures = ["#.#.#", "#;#;#", "0#", "#,,##0", "#,.0", "abc", "%#%"];
+
+    for picture in pictures {
+        let xpath = xpath::parse(&format!("format-number(1, '{}')", picture)).unwrap();
+
+        // act
+        let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+        // assert
+        assert_eq!(err.code(), &ErrorCode::FODF1310, "picture {}", picture);
+    }
+}
+
+#[test]
+fn format_integer_should_format_digits() {
+    // arrange
+    let cases = [
+        ("format-integer(7, '001')", "007"),
+        ("format-integer(1234567, '#,##0')", "1,234,567"),
+        ("format-integer(//span[@class='change'], '0')", "-42"),
+        ("format-integer(//span[@class='page'], '1;o')", "14th"),
+        ("format-integer(22, '1;o')", "22nd"),
+        ("format-integer(12, '٠')", "١٢"),
+        ("format-integer((), '1')", ""),
+    ];
+
+    for (expression, expected) in cases {
+        // act
+        let formatted = apply(expression);
+
+        // assert
+        assert_eq!(formatted, expected, "applying {}", expression);
+    }
+}
+
+#[test]
+fn format_integer_should_format_letters_roman_numerals_and_words() {
+    // arrange
+    let cases = [
+        ("format-integer(28, 'a')", "ab"),
+        ("format-integer(3, 'A')", "C"),
+        ("format-integer(1994, 'I')", "MCMXCIV"),
+        ("format-integer(14, 'i')", "xiv"),
+        ("format-integer(0, 'i')", "0"),
+        ("format-integer(123, 'w')", "one hundred twenty-three"),
+        ("format-integer(21, 'Ww;o')", "Twenty-First"),
+        ("format-integer(3, 'W;o', 'de')", "THIRD"),
+        ("format-integer(5, 'x')", "5"),
+    ];
+
+    for (expression, expected) in cases {
+        // act
+        let formatted = apply(expression);
+
+        // assert
+        assert_eq!(formatted, expected, "applying {}", expression);
+    }
+}
+
+#[test]
+fn format_integer_should_fail_for_invalid_picture() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let pictures = [";o", "1;x", ",001", "0#", "1٣", "1a"];
+
+    for picture in pictures {
+        let xpath = xpath::parse(&format!("format-integer(1, '{}')", picture)).unwrap();
+
+        // act
+        let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+        // assert
+        assert_eq!(err.code(), &ErrorCode::FODF1310, "picture {}", picture);
+    }
+}
@@ -34,3 +34,60 @@ fn abbrev_attribute_step_should_match_given_attribute() {
         assert_eq!(attribute.value, "bar")
     }
 }
+
+#[test]
+fn following_sibling_axis_should_select_later_siblings() {
+    // arrange
+    let text = r###"
+        <html>
+            <body>
+                <p id="1"></p>
+                <div id="2"></div>
+                <p id="3"></p>
+                <p id="4"></p>
+            </body>
+        </html>"###;
+
+    let document = html::parse(&text).unwrap();
+    let xpath_item_tree = xpath::XpathItemTree::from(&document);
+    let xpath = xpath::parse("//div/following-sibling::p").unwrap();
+
+    // act
+    let nodes = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    let ids: Vec<&str> = nodes
+        .iter()
+        .map(|item| {
+            item.extract_as_node()
+                .extract_as_element_node()
+                .get_attribute(&xpath_item_tree, "id")
+                .unwrap()
+        })
+        .collect();
+    assert_eq!(ids, vec!["3", "4"]);
+}
+
+#[test]
+fn following_sibling_axis_should_count_positions_from_context_node() {
+    // arrange
+    let text = r###"
+        <html>
+            <body>
+                <p id="1"></p>
+                <p id="2"></p>
+                <p id="3"></p>
+            </body>
+        </html>"###;
+
+    let document = html::parse(&text).unwrap();
+    let xpath_item_tree = xpath::XpathItemTree::from(&document);
+    let xpath = xpath::parse("//p[@id='1']/following-sibling::*[1]/@id").unwrap();
+
+    // act
+    let nodes = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(nodes.len(), 1);
+    assert_eq!(nodes[0].string_value(&xpath_item_tree), "2");
+}
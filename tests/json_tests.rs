@@ -0,0 +1,321 @@
+use skyscraper::{
+    html,
+    xpath::{
+        self,
+        context::DynamicContextBuilder,
+        grammar::{
+            data_model::{AnyAtomicType, XpathItem},
+            XpathItemTreeNode,
+        },
+        resolver::ResourceResolver,
+        ErrorCode, ExpressionApplyError, StaticContext, XpathItemTree,
+    },
+};
+
+static HTML: &str = r###"
+    <html>
+        <head>
+            <script type="application/ld+json">
+                {"@type": "Product", "name": "Lamp", "offers": {"price": 12.5, "currency": "EUR"}}
+            </script>
+        </head>
+        <body>
+            <div class="item">A</div>
+        </body>
+    </html>"###;
+
+fn string(s: &str) -> XpathItem<'static> {
+    XpathItem::AnyAtomicType(AnyAtomicType::String(s.to_string()))
+}
+
+fn double(d: f64) -> XpathItem<'static> {
+    XpathItem::AnyAtomicType(AnyAtomicType::Double(d.into()))
+}
+
+#[test]
+fn parse_json_should_look_up_embedded_json_ld() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath =
+        xpath::parse("parse-json(//script[@type='application/ld+json'])?offers?price").unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0], double(12.5));
+}
+
+#[test]
+fn parse_json_should_make_arrays() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse(r#"parse-json('{"a": [1, "two", null]}')?a?2"#).unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0], string("two"));
+}
+
+#[test]
+fn parse_json_wildcard_lookup_should_return_all_values() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("parse-json('[1, 2, 3]')?*").unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[2], double(3.0));
+}
+
+#[test]
+fn parse_json_should_make_infinity_of_numbers_too_large_for_a_double() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("parse-json('1e400')").unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0], double(f64::INFINITY));
+}
+
+#[test]
+fn parse_json_should_raise_fojs0001_for_invalid_json() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("parse-json('{oops')").unwrap();
+
+    // act
+    let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::FOJS0001);
+}
+
+#[test]
+fn array_lookup_out_of_bounds_should_raise_foay0001() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("[1, 2]?3").unwrap();
+
+    // act
+    let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::FOAY0001);
+}
+
+#[test]
+fn map_constructor_should_raise_xqdy0137_for_duplicate_keys() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("map { 'a': 1, 'a': 2 }").unwrap();
+
+    // act
+    let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::XQDY0137);
+}
+
+#[test]
+fn json_doc_should_load_through_resolver() {
+    // arrange
+    struct Resolver;
+
+    impl ResourceResolver for Resolver {
+        fn resolve_text(&self, uri: &str) -> Result<String, ExpressionApplyError> {
+            assert_eq!(uri, "state.json");
+            Ok(String::from(r#"{"user": {"name": "Ada"}}"#))
+        }
+    }
+
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let context = DynamicContextBuilder::new(StaticContext::new())
+        .with_resolver(Resolver)
+        .build();
+    let xpath = xpath::parse("json-doc('state.json')?user?name").unwrap();
+
+    // act
+    let items = xpath
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0], string("Ada"));
+}
+
+#[test]
+fn json_doc_without_resolver_should_raise_fodc0002() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("json-doc('state.json')").unwrap();
+
+    // act
+    let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::FODC0002);
+}
+
+#[test]
+fn json_to_xml_should_make_queryable_nodes() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse(
+        "json-to-xml(//script[@type='application/ld+json'])/map/map[@key='offers']/number[@key='price']",
+    )
+    .unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].string_value(&xpath_item_tree), "12.5");
+}
+
+#[test]
+fn json_to_xml_root_should_be_its_own_document() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("root(json-to-xml('[true]')/array/boolean)").unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    let root = items[0].extract_as_node();
+    assert!(matches!(root, XpathItemTreeNode::DocumentNode(_)));
+    assert_eq!(root.text_content(&xpath_item_tree), "true");
+}
+
+#[test]
+fn xml_to_json_should_round_trip_json_to_xml() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath =
+        xpath::parse(r#"xml-to-json(json-to-xml('{"a": [1, "x", null, false], "b": {}}'))"#)
+            .unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0], string(r#"{"a":[1,"x",null,false],"b":{}}"#));
+}
+
+#[test]
+fn xml_to_json_should_raise_fojs0006_for_other_elements() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("xml-to-json(//div)").unwrap();
+
+    // act
+    let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::FOJS0006);
+}
+
+#[test]
+fn serialize_should_write_json() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse(
+        "serialize(map { 'name': //div/text(), 'tags': ['a', 'b'], 'price': 12 }, map { 'method': 'json' })",
+    )
+    .unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(
+        items[0],
+        string(r#"{"name":"A","tags":["a","b"],"price":12}"#)
+    );
+}
+
+#[test]
+fn serialize_should_write_xml_by_default() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("serialize(//div)").unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items[0], string(r#"<div class="item">A</div>"#));
+}
+
+#[test]
+fn serialize_json_should_raise_sere0023_for_sequences() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("serialize((1, 2), map { 'method': 'json' })").unwrap();
+
+    // act
+    let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::SERE0023);
+}
+
+#[test]
+fn serialize_json_should_raise_sere0021_for_attributes() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("serialize(//div/@class, map { 'method': 'json' })").unwrap();
+
+    // act
+    let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::SERE0021);
+}
+
+#[test]
+fn serialize_should_raise_sepm0016_for_unknown_method() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("serialize(1, map { 'method': 'yaml' })").unwrap();
+
+    // act
+    let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::SEPM0016);
+}
@@ -0,0 +1,190 @@
+use std::time::{Duration, Instant};
+
+use skyscraper::{
+    html,
+    xpath::{
+        self,
+        context::DynamicContextBuilder,
+        limits::{CancellationToken, EvaluationLimit, EvaluationLimits},
+        DynamicContext, ErrorCode, StaticContext, XpathItemTree,
+    },
+};
+
+static HTML: &str = r###"
+    <html>
+        <body>
+            <div><p>1</p><p>2</p><p>3</p></div>
+            <div><p>4</p><p>5</p><p>6</p></div>
+        </body>
+    </html>"###;
+
+fn context_with_limits(limits: EvaluationLimits) -> DynamicContext {
+    DynamicContextBuilder::new(StaticContext::new())
+        .with_limits(limits)
+        .build()
+}
+
+#[test]
+fn apply_should_succeed_within_limits() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("//div/p").unwrap();
+    let context = context_with_limits(
+        EvaluationLimits::new()
+            .with_max_node_visits(1_000)
+            .with_max_result_size(100)
+            .with_max_depth(50)
+            .with_timeout(Duration::from_secs(60)),
+    );
+
+    // act
+    let items = xpath
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap();
+
+    // assert
+    assert_eq!(items.len(), 6);
+}
+
+#[test]
+fn apply_should_fail_after_max_node_visits() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("//*//*//*[//*//*]").unwrap();
+    let context = context_with_limits(EvaluationLimits::new().with_max_node_visits(100));
+
+    // act
+    let err = xpath
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap_err();
+
+    // assert
+    assert_eq!(
+        err.code(),
+        &ErrorCode::LimitExceeded(EvaluationLimit::NodeVisits)
+    );
+    assert_eq!(err.message(), "evaluation visited more than 100 nodes");
+}
+
+#[test]
+fn apply_should_count_string_values_as_node_visits() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("//*[string(/) = 'x']").unwrap();
+    let context = context_with_limits(EvaluationLimits::new().with_max_node_visits(100));
+
+    // act
+    let err = xpath
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap_err();
+
+    // assert
+    assert_eq!(
+        err.code(),
+        &ErrorCode::LimitExceeded(EvaluationLimit::NodeVisits)
+    );
+}
+
+#[test]
+fn apply_should_fail_when_result_is_too_large() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("//div/p").unwrap();
+    let context = context_with_limits(EvaluationLimits::new().with_max_result_size(5));
+
+    // act
+    let err = xpath
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap_err();
+
+    // assert
+    assert_eq!(
+        err.code(),
+        &ErrorCode::LimitExceeded(EvaluationLimit::ResultSize)
+    );
+}
+
+#[test]
+fn apply_should_fail_when_expressions_are_nested_too_deeply() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("//div[((((p))))]").unwrap();
+    let context = context_with_limits(EvaluationLimits::new().with_max_depth(3));
+
+    // act
+    let err = xpath
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap_err();
+
+    // assert
+    assert_eq!(
+        err.code(),
+        &ErrorCode::LimitExceeded(EvaluationLimit::Depth)
+    );
+}
+
+#[test]
+fn apply_should_fail_after_deadline() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("//div/p").unwrap();
+    let context = context_with_limits(EvaluationLimits::new().with_deadline(Instant::now()));
+
+    // act
+    let err = xpath
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap_err();
+
+    // assert
+    assert_eq!(
+        err.code(),
+        &ErrorCode::LimitExceeded(EvaluationLimit::Deadline)
+    );
+    assert!(!err.code().is_dynamic_error());
+}
+
+#[test]
+fn apply_should_fail_when_cancelled() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("//div/p").unwrap();
+    let token = CancellationToken::new();
+    let context =
+        context_with_limits(EvaluationLimits::new().with_cancellation_token(token.clone()));
+
+    // act
+    token.cancel();
+    let err = xpath
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap_err();
+
+    // assert
+    assert_eq!(
+        err.code(),
+        &ErrorCode::LimitExceeded(EvaluationLimit::Cancelled)
+    );
+}
+
+#[test]
+fn limits_should_apply_to_each_evaluation_separately() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("//div/p").unwrap();
+    let context = context_with_limits(EvaluationLimits::new().with_max_node_visits(100));
+
+    // act
+    let first = xpath.apply_with_context(&xpath_item_tree, &context);
+    let second = xpath.apply_with_context(&xpath_item_tree, &context);
+
+    // assert
+    assert_eq!(first.unwrap().len(), 6);
+    assert_eq!(second.unwrap().len(), 6);
+}
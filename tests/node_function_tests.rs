@@ -0,0 +1,182 @@
+use skyscraper::{
+    html,
+    xpath::{
+        self,
+        grammar::data_model::{AnyAtomicType, XpathItem},
+        ErrorCode, XpathItemTree,
+    },
+};
+
+static HTML: &str = r###"
+    <html lang="en-GB">
+        <body>
+            <div id="main">
+                <a href="/first">First</a>
+                <a href="/second" id="second">Second</a>
+                <p lang="fr">Bonjour <b>monde</b></p>
+            </div>
+            <div id="main">Duplicate</div>
+            <div id="footer">Footer</div>
+        </body>
+    </html>"###;
+
+fn string(s: &str) -> XpathItem<'static> {
+    XpathItem::AnyAtomicType(AnyAtomicType::String(s.to_string()))
+}
+
+fn boolean(b: bool) -> XpathItem<'static> {
+    XpathItem::AnyAtomicType(AnyAtomicType::Boolean(b))
+}
+
+#[test]
+fn id_should_return_first_element_with_id() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("id('main')/a[1]").unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].string_value(&xpath_item_tree), "First");
+}
+
+#[test]
+fn id_should_split_ids_on_whitespace() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("id((' footer second ', 'missing'))").unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].string_value(&xpath_item_tree), "Second");
+    assert_eq!(items[1].string_value(&xpath_item_tree), "Footer");
+}
+
+#[test]
+fn id_should_fail_without_node() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("id('main', 'not a node')").unwrap();
+
+    // act
+    let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::XPTY0004);
+}
+
+#[test]
+fn lang_should_use_nearest_ancestor_lang() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let english = xpath::parse("//a[lang('EN')]").unwrap();
+    let french = xpath::parse("//b[lang('fr')]").unwrap();
+    let not_english = xpath::parse("//b[lang('en')]").unwrap();
+
+    // act
+    let english_items = english.apply(&xpath_item_tree).unwrap();
+    let french_items = french.apply(&xpath_item_tree).unwrap();
+    let not_english_items = not_english.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(english_items.len(), 2);
+    assert_eq!(french_items.len(), 1);
+    assert_eq!(not_english_items.len(), 0);
+}
+
+#[test]
+fn lang_should_not_match_partial_subtag() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("lang('en-G', //a[1])").unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0], boolean(false));
+}
+
+#[test]
+fn path_should_locate_nodes() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let element = xpath::parse("path(//a[2])").unwrap();
+    let attribute = xpath::parse("path(//a[2]/@href)").unwrap();
+    let text = xpath::parse("path(//p/text())").unwrap();
+    let root = xpath::parse("path(/)").unwrap();
+
+    // act
+    let element_items = element.apply(&xpath_item_tree).unwrap();
+    let attribute_items = attribute.apply(&xpath_item_tree).unwrap();
+    let text_items = text.apply(&xpath_item_tree).unwrap();
+    let root_items = root.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(
+        element_items[0],
+        string("/Q{}html[1]/Q{}body[1]/Q{}div[1]/Q{}a[2]")
+    );
+    assert_eq!(
+        attribute_items[0],
+        string("/Q{}html[1]/Q{}body[1]/Q{}div[1]/Q{}a[2]/@href")
+    );
+    assert_eq!(
+        text_items[0],
+        string("/Q{}html[1]/Q{}body[1]/Q{}div[1]/Q{}p[1]/text()[1]")
+    );
+    assert_eq!(root_items[0], string("/"));
+}
+
+#[test]
+fn path_should_select_the_node() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let path = xpath::parse("path(//div[3])").unwrap();
+    let path = path.apply(&xpath_item_tree).unwrap()[0].string_value(&xpath_item_tree);
+
+    // act
+    let items = xpath::parse(&path)
+        .unwrap()
+        .apply(&xpath_item_tree)
+        .unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].string_value(&xpath_item_tree), "Footer");
+}
+
+#[test]
+fn generate_id_should_be_unique_and_stable() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let all = xpath::parse("distinct-values(//node()/generate-id())").unwrap();
+    let nodes = xpath::parse("//node()").unwrap();
+    let same = xpath::parse("generate-id(//a[1]) = generate-id(id('main')/a[1])").unwrap();
+    let empty = xpath::parse("generate-id(())").unwrap();
+
+    // act
+    let all_items = all.apply(&xpath_item_tree).unwrap();
+    let node_items = nodes.apply(&xpath_item_tree).unwrap();
+    let same_items = same.apply(&xpath_item_tree).unwrap();
+    let empty_items = empty.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(all_items.len(), node_items.len());
+    assert_eq!(same_items[0], boolean(true));
+    assert_eq!(empty_items[0], string(""));
+}
@@ -129,3 +129,34 @@ fn parent_axis_should_respect_node_test() {
         assert_eq!(element.get_attribute(&xpath_item_tree, "id"), Some("2"));
     }
 }
+
+#[test]
+fn preceding_sibling_axis_should_count_positions_back_from_context_node() {
+    // arrange
+    let text = r###"
+        <html>
+            <body>
+                <p id="1"></p>
+                <p id="2"></p>
+                <p id="3"></p>
+            </body>
+        </html>"###;
+
+    let document = html::parse(&text).unwrap();
+    let xpath_item_tree = xpath::XpathItemTree::from(&document);
+    let all = xpath::parse("//p[@id='3']/preceding-sibling::p/@id").unwrap();
+    let nearest = xpath::parse("//p[@id='3']/preceding-sibling::p[1]/@id").unwrap();
+
+    // act
+    let all = all.apply(&xpath_item_tree).unwrap();
+    let nearest = nearest.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    let all: Vec<String> = all
+        .iter()
+        .map(|item| item.string_value(&xpath_item_tree))
+        .collect();
+    assert_eq!(all, vec!["1", "2"]);
+    assert_eq!(nearest.len(), 1);
+    assert_eq!(nearest[0].string_value(&xpath_item_tree), "2");
+}
@@ -0,0 +1,50 @@
+#![cfg(feature = "serde")]
+
+use skyscraper::{
+    html,
+    xpath::{self, Xpath, XpathItemTree},
+};
+
+static HTML: &str = r###"
+    <html>
+        <body>
+            <div class="item"><a href="https://example.com">It's "here"</a></div>
+            <div class="other"><a href="https://example.org">Other</a></div>
+        </body>
+    </html>"###;
+
+#[test]
+fn xpath_should_round_trip_through_json() {
+    // arrange
+    let xpath =
+        xpath::parse(r#"//div[@class='item']/a[contains(., 'It''s')][1]/@href[. != -2.5e0 * 4]"#)
+            .unwrap();
+
+    // act
+    let json = serde_json::to_string(&xpath).unwrap();
+    let deserialized: Xpath = serde_json::from_str(&json).unwrap();
+
+    // assert
+    assert_eq!(deserialized, xpath);
+    assert_eq!(deserialized.to_string(), xpath.to_string());
+}
+
+#[test]
+fn deserialized_xpath_should_apply() {
+    // arrange
+    let document = html::parse(HTML).unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("//div[@class='item']/a/@href").unwrap();
+    let json = serde_json::to_value(&xpath).unwrap();
+
+    // act
+    let deserialized: Xpath = serde_json::from_value(json).unwrap();
+    let items = deserialized.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(
+        items[0].string_value(&xpath_item_tree),
+        "https://example.com"
+    );
+}
@@ -27,3 +27,41 @@ fn root_should_select_root_node() {
         tree_node.extract_as_document_node();
     }
 }
+
+#[test]
+fn count_should_return_number_of_items() {
+    // arrange
+    let text = r###"
+        <html>
+            <ul id="short"><li>1</li></ul>
+            <ul id="long"><li>1</li><li>2</li><li>3</li></ul>
+        </html>"###;
+
+    let document = html::parse(&text).unwrap();
+    let xpath_item_tree = xpath::XpathItemTree::from(&document);
+    let xpath = xpath::parse("//ul[count(li) > 2]/@id").unwrap();
+
+    // act
+    let nodes = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(nodes.len(), 1);
+    assert_eq!(nodes[0].string_value(&xpath_item_tree), "long");
+}
+
+#[test]
+fn normalize_space_should_collapse_whitespace() {
+    // arrange
+    let text = "<html><p class=\"  a\n\tb   c \">text</p></html>";
+
+    let document = html::parse(&text).unwrap();
+    let xpath_item_tree = xpath::XpathItemTree::from(&document);
+    let xpath = xpath::parse("//p/normalize-space(@class)").unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].string_value(&xpath_item_tree), "a b c");
+}
@@ -37,8 +37,9 @@ fn treat_incorrect_type_should_fail() {
     let err = xpath.apply(&xpath_item_tree).unwrap_err();
 
     // assert
+    assert_eq!(err.code(), &xpath::ErrorCode::XPDY0050);
     assert_eq!(
         err.to_string(),
-        "Error applying expression err:XPDY0050 Cannot treat as document-node()"
+        "Error applying expression err:XPDY0050 Cannot treat as document-node() in `/html treat as document-node()`"
     );
 }
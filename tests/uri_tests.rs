@@ -0,0 +1,234 @@
+use skyscraper::{
+    html,
+    xpath::{
+        self,
+        context::{DynamicContext, DynamicContextBuilder, StaticContextBuilder},
+        grammar::data_model::{AnyAtomicType, XpathItem},
+        resolver::FileSystemResolver,
+        ErrorCode, StaticContext, XpathItemTree,
+    },
+};
+
+static HTML: &str = r###"
+    <html>
+        <head>
+            <base href="/shop/">
+        </head>
+        <body>
+            <a href="lamp.html">Lamp</a>
+            <a href="../about.html#team">About</a>
+            <a href=" https://other.example/table.html ">Table</a>
+        </body>
+    </html>"###;
+
+fn string(s: &str) -> XpathItem<'static> {
+    XpathItem::AnyAtomicType(AnyAtomicType::String(s.to_string()))
+}
+
+fn tree(html: &str, uri: &str) -> XpathItemTree {
+    let document = html::parse(html).unwrap();
+    let mut xpath_item_tree = XpathItemTree::from(&document);
+    xpath_item_tree.set_document_uri(uri);
+    xpath_item_tree
+}
+
+#[test]
+fn resolve_uri_should_resolve_links_against_base_href() {
+    // arrange
+    let xpath_item_tree = tree(HTML, "https://example.com/index.html");
+    let xpath = xpath::parse("//a/resolve-uri(@href)").unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[0], string("https://example.com/about.html#team"));
+    assert_eq!(items[1], string("https://example.com/shop/lamp.html"));
+    assert_eq!(items[2], string("https://other.example/table.html"));
+}
+
+#[test]
+fn resolve_uri_should_use_document_uri_without_base_href() {
+    // arrange
+    let html = r#"<html><body><a href="lamp.html">Lamp</a></body></html>"#;
+    let xpath_item_tree = tree(html, "https://example.com/shop/index.html");
+    let xpath = xpath::parse("//a/resolve-uri(@href)").unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0], string("https://example.com/shop/lamp.html"));
+}
+
+#[test]
+fn resolve_uri_should_use_base_argument() {
+    // arrange
+    let xpath_item_tree = tree(HTML, "https://example.com/index.html");
+    let xpath = xpath::parse("resolve-uri('../b/./c?q', 'http://a/x/y/z')").unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0], string("http://a/x/b/c?q"));
+}
+
+#[test]
+fn resolve_uri_should_use_static_base_uri() {
+    // arrange
+    let document = html::parse("<html></html>").unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let static_context = StaticContextBuilder::new()
+        .with_base_uri("https://example.com/docs/")
+        .build();
+    let context = DynamicContext::new(static_context);
+    let xpath = xpath::parse("resolve-uri('guide.html')").unwrap();
+
+    // act
+    let items = xpath
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0], string("https://example.com/docs/guide.html"));
+}
+
+#[test]
+fn resolve_uri_should_fail_without_base_uri() {
+    // arrange
+    let document = html::parse("<html></html>").unwrap();
+    let xpath_item_tree = XpathItemTree::from(&document);
+    let xpath = xpath::parse("resolve-uri('guide.html')").unwrap();
+
+    // act
+    let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::FONS0005);
+}
+
+#[test]
+fn resolve_uri_should_fail_for_invalid_uri() {
+    // arrange
+    let xpath_item_tree = tree(HTML, "https://example.com/index.html");
+    let xpath = xpath::parse("resolve-uri('100%.html')").unwrap();
+
+    // act
+    let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::FORG0002);
+}
+
+#[test]
+fn resolve_uri_should_fail_for_relative_base() {
+    // arrange
+    let xpath_item_tree = tree(HTML, "https://example.com/index.html");
+    let xpath = xpath::parse("resolve-uri('guide.html', 'docs/')").unwrap();
+
+    // act
+    let err = xpath.apply(&xpath_item_tree).unwrap_err();
+
+    // assert
+    assert_eq!(err.code(), &ErrorCode::FORG0002);
+}
+
+#[test]
+fn base_uri_should_honor_base_href() {
+    // arrange
+    let xpath_item_tree = tree(HTML, "https://example.com/index.html");
+    let xpath = xpath::parse("base-uri(//a[1])").unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0], string("https://example.com/shop/"));
+}
+
+#[test]
+fn document_uri_should_return_loaded_document_uri() {
+    // arrange
+    let xpath_item_tree = tree(HTML, "https://example.com/index.html");
+    let context = DynamicContextBuilder::new(StaticContext::new())
+        .with_resolver(FileSystemResolver::new("tests/documents"))
+        .build();
+    let xpath = xpath::parse("document-uri(doc('listing.html'))").unwrap();
+    let main_xpath = xpath::parse("document-uri(/)").unwrap();
+
+    // act
+    let items = xpath
+        .apply_with_context(&xpath_item_tree, &context)
+        .unwrap();
+    let main_items = main_xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0], string("listing.html"));
+    assert_eq!(main_items.len(), 1);
+    assert_eq!(main_items[0], string("https://example.com/index.html"));
+}
+
+#[test]
+fn document_uri_should_be_empty_for_elements() {
+    // arrange
+    let xpath_item_tree = tree(HTML, "https://example.com/index.html");
+    let xpath = xpath::parse("document-uri(//a[1])").unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 0);
+}
+
+#[test]
+fn encode_for_uri_should_encode_reserved_characters() {
+    // arrange
+    let xpath_item_tree = tree(HTML, "https://example.com/index.html");
+    let xpath = xpath::parse("encode-for-uri('100% ~ café/a?b')").unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0], string("100%25%20~%20caf%C3%A9%2Fa%3Fb"));
+}
+
+#[test]
+fn iri_to_uri_should_keep_reserved_characters() {
+    // arrange
+    let xpath_item_tree = tree(HTML, "https://example.com/index.html");
+    let xpath = xpath::parse("iri-to-uri('http://example.com/café?a=<b>#c d')").unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(
+        items[0],
+        string("http://example.com/caf%C3%A9?a=%3Cb%3E#c%20d")
+    );
+}
+
+#[test]
+fn escape_html_uri_should_only_encode_non_ascii() {
+    // arrange
+    let xpath_item_tree = tree(HTML, "https://example.com/index.html");
+    let xpath = xpath::parse("escape-html-uri('http://example.com/café?a=<b> c')").unwrap();
+
+    // act
+    let items = xpath.apply(&xpath_item_tree).unwrap();
+
+    // assert
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0], string("http://example.com/caf%C3%A9?a=<b> c"));
+}